@@ -0,0 +1,318 @@
+//! Amplicon-constrained degenerate primer search
+//!
+//! Merges the top variants of each screened window into a single degenerate
+//! oligo (within a degeneracy budget) and pairs forward/reverse oligos whose
+//! amplicon length falls within a user-defined range.
+
+use serde::{Deserialize, Serialize};
+
+use super::analyzer::create_consensus_from_seqs;
+use super::filter::ExclusivityFilter;
+use super::iupac::{degeneracy, iupac_to_mask, reverse_complement};
+use super::types::{ScreeningResults, Variant};
+
+/// Parameters for the amplicon-constrained primer pair search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmpliconSearchParams {
+    pub min_amplicon_length: usize,
+    pub max_amplicon_length: usize,
+    /// Maximum number of concrete sequences each degenerate primer may expand to
+    pub max_degeneracy: u64,
+    /// Minimum coverage (% of all references) each degenerate primer must reach
+    pub min_coverage: f64,
+    pub exclude_n: bool,
+    pub max_results: usize,
+    /// Optional exclusivity filter; windows failing it are not used as primers
+    #[serde(default)]
+    pub exclusivity_filter: Option<ExclusivityFilter>,
+}
+
+impl Default for AmpliconSearchParams {
+    fn default() -> Self {
+        Self {
+            min_amplicon_length: 70,
+            max_amplicon_length: 250,
+            max_degeneracy: 8,
+            min_coverage: 90.0,
+            exclude_n: true,
+            max_results: 200,
+            exclusivity_filter: None,
+        }
+    }
+}
+
+/// A degenerate oligo built by merging the top variants of one window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegenerateOligo {
+    pub position: usize,
+    pub length: u32,
+    /// Oligo sequence in binding orientation (reverse complemented for reverse primers)
+    pub sequence: String,
+    pub degeneracy: u64,
+    /// Percentage of all references covered by the degenerate oligo
+    pub coverage: f64,
+    pub variants_merged: usize,
+}
+
+/// A forward/reverse degenerate primer pair satisfying the amplicon constraints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmpliconCandidate {
+    pub forward: DegenerateOligo,
+    pub reverse: DegenerateOligo,
+    pub amplicon_length: usize,
+    /// Upper bound on the percentage of references amplified by both primers
+    pub coverage: f64,
+    pub total_degeneracy: u64,
+}
+
+/// Merge variants (in rank order) into one degenerate consensus while its
+/// degeneracy stays within `max_degeneracy`. Variants that would exceed the
+/// budget are skipped. Returns (consensus, degeneracy, coverage %, merged count).
+pub fn merge_variants_within_budget(
+    variants: &[Variant],
+    max_degeneracy: u64,
+    exclude_n: bool,
+) -> Option<(String, u64, f64, usize)> {
+    let mut merged: Vec<&str> = Vec::new();
+    let mut consensus = String::new();
+
+    for variant in variants {
+        merged.push(&variant.sequence);
+        let (trial, _, is_valid) = create_consensus_from_seqs(&merged, exclude_n);
+        if is_valid && degeneracy(trial.as_bytes()) <= max_degeneracy {
+            consensus = trial;
+        } else {
+            merged.pop();
+        }
+    }
+
+    if consensus.is_empty() {
+        return None;
+    }
+
+    // A merged consensus may also cover variants that were never merged explicitly
+    let cons_bytes = consensus.as_bytes();
+    let coverage: f64 = variants
+        .iter()
+        .filter(|v| is_subset_of(v.sequence.as_bytes(), cons_bytes))
+        .map(|v| v.percentage)
+        .sum();
+
+    let degen = degeneracy(cons_bytes);
+    Some((consensus, degen, coverage, merged.len()))
+}
+
+/// True if every base set in `seq` is contained in the corresponding base set of `consensus`.
+fn is_subset_of(seq: &[u8], consensus: &[u8]) -> bool {
+    seq.len() == consensus.len()
+        && seq
+            .iter()
+            .zip(consensus)
+            .all(|(&s, &c)| iupac_to_mask(s) & !iupac_to_mask(c) == 0)
+}
+
+/// Build the best degenerate oligo per template position across all lengths.
+/// Preference: highest coverage, then lowest degeneracy, then shortest length.
+pub fn best_degenerate_oligos(
+    results: &ScreeningResults,
+    params: &AmpliconSearchParams,
+) -> Vec<DegenerateOligo> {
+    let mut best: std::collections::BTreeMap<usize, DegenerateOligo> =
+        std::collections::BTreeMap::new();
+
+    let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+    lengths.sort();
+
+    for length in lengths {
+        let length_result = &results.results_by_length[&length];
+        for pr in &length_result.positions {
+            if pr.analysis.skipped
+                || params.exclusivity_filter.is_some_and(|f| !f.passes(pr))
+            {
+                continue;
+            }
+            let Some((sequence, degen, coverage, merged)) = merge_variants_within_budget(
+                &pr.analysis.variants,
+                params.max_degeneracy,
+                params.exclude_n,
+            ) else {
+                continue;
+            };
+            if coverage < params.min_coverage {
+                continue;
+            }
+
+            let candidate = DegenerateOligo {
+                position: pr.position,
+                length,
+                sequence,
+                degeneracy: degen,
+                coverage,
+                variants_merged: merged,
+            };
+
+            let replace = match best.get(&pr.position) {
+                None => true,
+                Some(current) => {
+                    candidate.coverage > current.coverage
+                        || (candidate.coverage == current.coverage
+                            && candidate.degeneracy < current.degeneracy)
+                }
+            };
+            if replace {
+                best.insert(pr.position, candidate);
+            }
+        }
+    }
+
+    best.into_values().collect()
+}
+
+/// Search for forward/reverse degenerate primer pairs whose amplicon length is
+/// within range. Results are ranked by pair coverage (descending), then total
+/// degeneracy and amplicon length (ascending), and truncated to `max_results`.
+pub fn find_amplicon_candidates(
+    results: &ScreeningResults,
+    params: &AmpliconSearchParams,
+) -> Vec<AmpliconCandidate> {
+    let oligos = best_degenerate_oligos(results, params);
+    let max_results = params.max_results.max(1);
+
+    // Compact (forward_idx, reverse_idx, amplicon_length, coverage, degeneracy) tuples,
+    // periodically pruned so dense templates don't accumulate millions of pairs.
+    let mut pairs: Vec<(usize, usize, usize, f64, u64)> = Vec::new();
+
+    for (fi, fwd) in oligos.iter().enumerate() {
+        let fwd_end = fwd.position + fwd.length as usize;
+        let start = oligos.partition_point(|o| o.position < fwd_end);
+
+        for (ri, rev) in oligos.iter().enumerate().skip(start) {
+            let amplicon_length = rev.position + rev.length as usize - fwd.position;
+            if rev.position > fwd.position + params.max_amplicon_length {
+                break;
+            }
+            if amplicon_length < params.min_amplicon_length
+                || amplicon_length > params.max_amplicon_length
+            {
+                continue;
+            }
+            pairs.push((
+                fi,
+                ri,
+                amplicon_length,
+                fwd.coverage.min(rev.coverage),
+                fwd.degeneracy.saturating_mul(rev.degeneracy),
+            ));
+        }
+
+        if pairs.len() > max_results * 4 {
+            rank_pairs(&mut pairs);
+            pairs.truncate(max_results);
+        }
+    }
+
+    rank_pairs(&mut pairs);
+    pairs.truncate(max_results);
+
+    pairs
+        .into_iter()
+        .map(|(fi, ri, amplicon_length, coverage, total_degeneracy)| {
+            let rev = &oligos[ri];
+            AmpliconCandidate {
+                forward: oligos[fi].clone(),
+                reverse: DegenerateOligo {
+                    sequence: reverse_complement(&rev.sequence),
+                    ..rev.clone()
+                },
+                amplicon_length,
+                coverage,
+                total_degeneracy,
+            }
+        })
+        .collect()
+}
+
+fn rank_pairs(pairs: &mut [(usize, usize, usize, f64, u64)]) {
+    pairs.sort_by(|a, b| {
+        b.3.partial_cmp(&a.3)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.4.cmp(&b.4))
+            .then(a.2.cmp(&b.2))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::{AnalysisMethod, AnalysisParams};
+
+    fn variant(seq: &str, count: usize, pct: f64) -> Variant {
+        Variant {
+            sequence: seq.to_string(),
+            count,
+            percentage: pct,
+        }
+    }
+
+    #[test]
+    fn test_merge_within_budget() {
+        let variants = vec![
+            variant("ACGT", 6, 60.0),
+            variant("ACGA", 3, 30.0),
+            variant("TCGC", 1, 10.0),
+        ];
+        // Budget of 2 allows ACGW but not the third variant (two extra ambiguities)
+        let (seq, degen, cov, merged) =
+            merge_variants_within_budget(&variants, 2, true).unwrap();
+        assert_eq!(seq, "ACGW");
+        assert_eq!(degen, 2);
+        assert_eq!(merged, 2);
+        assert!((cov - 90.0).abs() < 1e-9);
+
+        let (seq, _, cov, _) = merge_variants_within_budget(&variants, 1, true).unwrap();
+        assert_eq!(seq, "ACGT");
+        assert!((cov - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_amplicon_candidates() {
+        let template = "ATGCGTACGTTAGCCATGACTGGATCCGTAACGTTGCAAGCTTGGCACTGGCCGTCGTTTTAC";
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string(), "R2".to_string()],
+                sequences: vec![template.to_string(), template.to_string()],
+            },
+            &AnalysisParams {
+                method: AnalysisMethod::NoAmbiguities,
+                min_oligo_length: 10,
+                max_oligo_length: 10,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let params = AmpliconSearchParams {
+            min_amplicon_length: 40,
+            max_amplicon_length: 50,
+            max_results: 10,
+            ..Default::default()
+        };
+        let candidates = find_amplicon_candidates(&results, &params);
+        assert_eq!(candidates.len(), 10);
+        for c in &candidates {
+            assert!(c.amplicon_length >= 40 && c.amplicon_length <= 50);
+            assert!(c.reverse.position >= c.forward.position + 10);
+            let rev_site = &template[c.reverse.position..c.reverse.position + 10];
+            assert_eq!(c.reverse.sequence, reverse_complement(rev_site));
+            assert_eq!(c.total_degeneracy, 1);
+        }
+    }
+}
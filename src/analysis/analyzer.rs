@@ -1,15 +1,46 @@
 //! Core analysis algorithms for oligo variant detection
 
+use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
-use super::iupac::{base_to_bit, sequence_matches_consensus_bytes, IUPAC_FROM_MASK};
-use super::types::{AnalysisMethod, Variant, WindowAnalysisResult};
+use std::time::{Duration, Instant};
+use super::iupac::{base_to_bit, indel_count, sequence_matches_consensus_bytes, IUPAC_FROM_MASK};
+use super::types::{
+    AmbiguityCosts, AnalysisMethod, MergeStrategy, Variant, WindowAnalysisResult,
+};
+
+/// Compute deadline of a single window; remembers once it has been reached.
+struct WindowDeadline {
+    at: Option<Instant>,
+    reached: Cell<bool>,
+}
+
+impl WindowDeadline {
+    fn new(at: Option<Instant>) -> Self {
+        Self {
+            at,
+            reached: Cell::new(false),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        if !self.reached.get() && self.at.is_some_and(|at| Instant::now() >= at) {
+            self.reached.set(true);
+        }
+        self.reached.get()
+    }
+}
 
-/// Analyze sequences using the specified method
+/// Analyze sequences using the specified method. Merging stops at `deadline`,
+/// leaving the sequences not yet merged as exact variants, and the result is
+/// flagged as truncated.
 pub fn analyze_sequences(
     sequences: &[&str],
     method: &AnalysisMethod,
     exclude_n: bool,
+    costs: &AmbiguityCosts,
+    merge_strategy: MergeStrategy,
     coverage_threshold: f64,
+    deadline: Option<Instant>,
 ) -> WindowAnalysisResult {
     if sequences.is_empty() {
         return WindowAnalysisResult {
@@ -20,22 +51,62 @@ pub fn analyze_sequences(
     }
 
     let total = sequences.len();
+    let deadline = WindowDeadline::new(deadline);
 
-    let variants = match method {
+    // Sequences with indels don't line up base for base with the others, so
+    // they are kept as exact variants and left out of merging
+    let (gapped, ungapped): (Vec<&str>, Vec<&str>) = sequences
+        .iter()
+        .copied()
+        .partition(|seq| indel_count(seq) > 0);
+    let sequences = ungapped.as_slice();
+
+    let mut variants = match method {
         AnalysisMethod::NoAmbiguities => find_variants_no_ambiguities(sequences),
         AnalysisMethod::FixedAmbiguities(max_amb) => {
-            find_minimum_variants_greedy(sequences, *max_amb as usize, exclude_n)
+            let max_cost = *max_amb as usize;
+            let greedy =
+                find_minimum_variants_greedy(sequences, max_cost, exclude_n, costs, &deadline);
+            match merge_strategy {
+                MergeStrategy::Optimal { time_budget_ms } if !deadline.expired() => {
+                    find_minimum_variants_optimal(
+                        sequences,
+                        greedy,
+                        max_cost,
+                        exclude_n,
+                        costs,
+                        Duration::from_millis(time_budget_ms),
+                        &deadline,
+                    )
+                }
+                _ => greedy,
+            }
         }
         AnalysisMethod::Incremental(target_pct, max_amb) => {
             find_incremental_variants(
                 sequences,
                 *target_pct as f64,
                 exclude_n,
+                costs,
                 max_amb.map(|n| n as usize),
+                &deadline,
             )
         }
     };
 
+    if !gapped.is_empty() {
+        for variant in find_variants_no_ambiguities(&gapped) {
+            let at = variants
+                .iter()
+                .position(|v| v.count < variant.count)
+                .unwrap_or(variants.len());
+            variants.insert(at, variant);
+        }
+        for variant in &mut variants {
+            variant.percentage = (variant.count as f64 / total as f64) * 100.0;
+        }
+    }
+
     // Calculate variants needed for coverage threshold
     let (variants_needed, coverage_at_threshold) =
         calculate_variants_for_threshold(&variants, total, coverage_threshold);
@@ -49,9 +120,24 @@ pub fn analyze_sequences(
         coverage_at_threshold,
         skipped: false,
         skip_reason: None,
+        score_stats: None,
+        identity_stats: None,
+        mismatch_stats: None,
+        truncated: deadline.reached.get(),
+        indel_matches: gapped.len(),
     }
 }
 
+/// Index of the variant a matched window belongs to: the variant equal to it,
+/// or else the first whose consensus covers it.
+pub fn variant_for_sequence(variants: &[Variant], seq: &str) -> Option<usize> {
+    variants.iter().position(|v| v.sequence == seq).or_else(|| {
+        variants
+            .iter()
+            .position(|v| sequence_matches_consensus_bytes(seq.as_bytes(), v.sequence.as_bytes()))
+    })
+}
+
 /// Find all unique variants without ambiguity codes
 fn find_variants_no_ambiguities(sequences: &[&str]) -> Vec<Variant> {
     let mut counts: HashMap<&str, usize> = HashMap::new();
@@ -70,16 +156,41 @@ fn find_variants_no_ambiguities(sequences: &[&str]) -> Vec<Variant> {
         })
         .collect();
 
-    // Sort by count descending
-    variants.sort_by(|a, b| b.count.cmp(&a.count));
+    // Sort by count descending; ties by sequence so the order is stable
+    variants.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.sequence.cmp(&b.sequence))
+    });
     variants
 }
 
-/// Find minimum variants using greedy set cover with ambiguity codes
+/// Unmerged sequences as exact variants, most frequent first, once a
+/// window's deadline cuts merging short.
+fn exact_variants<'a>(
+    remaining: impl Iterator<Item = (&'a str, usize)>,
+    total: f64,
+) -> Vec<Variant> {
+    let mut remaining: Vec<(&str, usize)> = remaining.collect();
+    remaining.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    remaining
+        .into_iter()
+        .map(|(seq, count)| Variant {
+            sequence: seq.to_string(),
+            count,
+            percentage: (count as f64 / total) * 100.0,
+        })
+        .collect()
+}
+
+/// Find minimum variants using greedy set cover with ambiguity codes, each
+/// variant's ambiguity cost within `max_cost`
 fn find_minimum_variants_greedy(
     sequences: &[&str],
-    max_ambiguities: usize,
+    max_cost: usize,
     exclude_n: bool,
+    costs: &AmbiguityCosts,
+    deadline: &WindowDeadline,
 ) -> Vec<Variant> {
     if sequences.is_empty() {
         return Vec::new();
@@ -96,11 +207,21 @@ fn find_minimum_variants_greedy(
     let mut variants = Vec::new();
 
     while !uncovered.is_empty() {
+        if deadline.expired() {
+            variants.extend(exact_variants(
+                uncovered.iter().map(|&s| (s, seq_counts[s])),
+                total,
+            ));
+            break;
+        }
+
         let (best_consensus, best_coverage) = find_best_consensus(
             &uncovered,
             &seq_counts,
-            max_ambiguities,
+            max_cost,
             exclude_n,
+            costs,
+            deadline,
         );
 
         if best_coverage.is_empty() {
@@ -138,17 +259,185 @@ fn find_minimum_variants_greedy(
     variants
 }
 
-/// Find the best consensus that covers the most sequences within ambiguity limit.
+/// Search state of the optimal merging: unique sequences as base masks with
+/// their counts, and the groups (merged masks) built so far.
+struct MergeSearch<'a> {
+    masks: Vec<Vec<u8>>,
+    max_cost: usize,
+    exclude_n: bool,
+    costs: &'a AmbiguityCosts,
+    groups: Vec<Vec<u8>>,
+    best: Option<Vec<Vec<u8>>>,
+    best_len: usize,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl MergeSearch<'_> {
+    /// Merged mask of `group` and `seq`, if it stays a valid variant
+    fn merge(&self, group: &[u8], seq: &[u8]) -> Option<Vec<u8>> {
+        let mut cost = 0usize;
+        let mut merged = Vec::with_capacity(group.len());
+        for (&g, &b) in group.iter().zip(seq) {
+            let m = g | b;
+            if m.count_ones() > 1 {
+                if self.exclude_n && m == 0b1111 {
+                    return None;
+                }
+                cost += self.costs.mask_cost(m);
+                if cost > self.max_cost {
+                    return None;
+                }
+            }
+            merged.push(m);
+        }
+        Some(merged)
+    }
+
+    /// Assign sequence `i` (and all after it) to an existing or new group,
+    /// keeping any complete assignment with fewer groups than the best so far.
+    fn assign(&mut self, i: usize) {
+        if self.timed_out || self.groups.len() >= self.best_len {
+            return;
+        }
+        if Instant::now() >= self.deadline {
+            self.timed_out = true;
+            return;
+        }
+        if i == self.masks.len() {
+            self.best_len = self.groups.len();
+            self.best = Some(self.groups.clone());
+            return;
+        }
+
+        let merges: Vec<(usize, Vec<u8>)> = self
+            .groups
+            .iter()
+            .enumerate()
+            .filter_map(|(g, group)| self.merge(group, &self.masks[i]).map(|m| (g, m)))
+            .collect();
+        for (g, merged) in merges {
+            let previous = std::mem::replace(&mut self.groups[g], merged);
+            self.assign(i + 1);
+            self.groups[g] = previous;
+        }
+        if self.groups.len() + 1 < self.best_len {
+            self.groups.push(self.masks[i].clone());
+            self.assign(i + 1);
+            self.groups.pop();
+        }
+    }
+}
+
+/// Find the fewest variants covering every sequence by branch and bound over
+/// assignments of unique sequences to variants, improving on the `greedy`
+/// solution. The search stops at `budget` (or the window deadline) and keeps
+/// the best found, so the result is never worse than greedy.
+fn find_minimum_variants_optimal(
+    sequences: &[&str],
+    greedy: Vec<Variant>,
+    max_cost: usize,
+    exclude_n: bool,
+    costs: &AmbiguityCosts,
+    budget: Duration,
+    deadline: &WindowDeadline,
+) -> Vec<Variant> {
+    let mut seq_counts: HashMap<&str, usize> = HashMap::new();
+    for &seq in sequences {
+        *seq_counts.entry(seq).or_insert(0) += 1;
+    }
+    if greedy.len() <= 1 || seq_counts.len() < 2 {
+        return greedy;
+    }
+
+    // Frequent sequences first so large groups form early; ties by sequence
+    // for deterministic results
+    let mut uniques: Vec<(&str, usize)> = seq_counts.iter().map(|(&s, &c)| (s, c)).collect();
+    uniques.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut search = MergeSearch {
+        masks: uniques
+            .iter()
+            .map(|(s, _)| s.bytes().map(base_to_bit).collect())
+            .collect(),
+        max_cost,
+        exclude_n,
+        costs,
+        groups: Vec::new(),
+        best: None,
+        best_len: greedy.len(),
+        deadline: deadline
+            .at
+            .map_or(Instant::now() + budget, |at| at.min(Instant::now() + budget)),
+        timed_out: false,
+    };
+    search.assign(0);
+    // Flags the window when the search was cut short by its deadline
+    deadline.expired();
+
+    let Some(groups) = search.best else {
+        return greedy;
+    };
+    let consensuses: Vec<String> = groups
+        .iter()
+        .map(|mask| consensus_from_mask(mask, exclude_n, costs).0)
+        .collect();
+    order_variants_by_coverage(consensuses, &uniques, sequences.len())
+}
+
+/// Order consensus sequences as greedy set cover would take them (most
+/// remaining sequences first), each claiming the sequences it covers.
+fn order_variants_by_coverage(
+    mut consensuses: Vec<String>,
+    uniques: &[(&str, usize)],
+    total: usize,
+) -> Vec<Variant> {
+    let covered = |consensus: &str, uncovered: &[(&str, usize)]| -> usize {
+        uncovered
+            .iter()
+            .filter(|(s, _)| sequence_matches_consensus_bytes(s.as_bytes(), consensus.as_bytes()))
+            .map(|(_, c)| c)
+            .sum()
+    };
+    let mut uncovered: Vec<(&str, usize)> = uniques.to_vec();
+    let mut variants = Vec::with_capacity(consensuses.len());
+    while !consensuses.is_empty() {
+        let (best, count) = consensuses
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, covered(c, &uncovered)))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .unwrap();
+        let consensus = consensuses.remove(best);
+        uncovered.retain(|(s, _)| {
+            !sequence_matches_consensus_bytes(s.as_bytes(), consensus.as_bytes())
+        });
+        if count > 0 {
+            variants.push(Variant {
+                sequence: consensus,
+                count,
+                percentage: (count as f64 / total as f64) * 100.0,
+            });
+        }
+    }
+    variants
+}
+
+/// Find the best consensus that covers the most sequences within the ambiguity
+/// cost limit, preferring the cheapest among equal coverage.
 /// Uses bitmask tracking for zero-allocation inner loop.
 fn find_best_consensus<'a>(
     uncovered: &HashSet<&'a str>,
     seq_counts: &HashMap<&'a str, usize>,
-    max_ambiguities: usize,
+    max_cost: usize,
     exclude_n: bool,
+    costs: &AmbiguityCosts,
+    deadline: &WindowDeadline,
 ) -> (String, HashSet<&'a str>) {
     let mut best_consensus = String::new();
     let mut best_coverage: HashSet<&str> = HashSet::new();
     let mut best_score = 0usize;
+    let mut best_cost = usize::MAX;
 
     let mut uncovered_sorted: Vec<_> = uncovered.iter().copied().collect();
     uncovered_sorted.sort_by_key(|&s| std::cmp::Reverse(seq_counts.get(s).unwrap_or(&0)));
@@ -161,6 +450,10 @@ fn find_best_consensus<'a>(
     let mut group_mask: Vec<u8> = vec![0u8; seq_len];
 
     for &seed_seq in uncovered_sorted.iter().take(50) {
+        if deadline.expired() {
+            break;
+        }
+
         // Initialize group_mask from seed
         let seed_bytes = seed_seq.as_bytes();
         for pos in 0..seq_len {
@@ -174,14 +467,14 @@ fn find_best_consensus<'a>(
             }
 
             let other_bytes = other_seq.as_bytes();
-            let mut trial_amb_count = 0usize;
+            let mut trial_cost = 0usize;
             let mut trial_valid = true;
 
             for pos in 0..seq_len {
                 let m = group_mask[pos] | base_to_bit(other_bytes[pos]);
                 if m.count_ones() > 1 {
-                    trial_amb_count += 1;
-                    if (exclude_n && m == 0b1111) || trial_amb_count > max_ambiguities {
+                    trial_cost += costs.mask_cost(m);
+                    if (exclude_n && m == 0b1111) || trial_cost > max_cost {
                         trial_valid = false;
                         break;
                     }
@@ -196,8 +489,9 @@ fn find_best_consensus<'a>(
             }
         }
 
-        let (consensus, _, is_valid) = consensus_from_mask(&group_mask, exclude_n);
-        if !is_valid {
+        // A degenerate seed (a reference window with ambiguity codes) can exceed the budget
+        let (consensus, cost, is_valid) = consensus_from_mask(&group_mask, exclude_n, costs);
+        if !is_valid || cost > max_cost {
             continue;
         }
 
@@ -214,8 +508,9 @@ fn find_best_consensus<'a>(
             .map(|&s| seq_counts.get(s).unwrap_or(&0))
             .sum();
 
-        if score > best_score {
+        if score > best_score || (score == best_score && score > 0 && cost < best_cost) {
             best_score = score;
+            best_cost = cost;
             best_consensus = consensus;
             best_coverage = coverage;
         }
@@ -229,7 +524,9 @@ fn find_incremental_variants(
     sequences: &[&str],
     target_percentage: f64,
     exclude_n: bool,
-    max_ambiguities: Option<usize>,
+    costs: &AmbiguityCosts,
+    max_cost: Option<usize>,
+    deadline: &WindowDeadline,
 ) -> Vec<Variant> {
     if sequences.is_empty() {
         return Vec::new();
@@ -248,6 +545,11 @@ fn find_incremental_variants(
             *remaining_counts.entry(seq).or_insert(0) += 1;
         }
 
+        if deadline.expired() {
+            variants.extend(exact_variants(remaining_counts.into_iter(), total_original));
+            break;
+        }
+
         let unique_remaining: Vec<&str> = remaining_counts.keys().copied().collect();
 
         let (best_consensus, best_coverage_count) = find_incremental_consensus(
@@ -255,7 +557,9 @@ fn find_incremental_variants(
             &remaining_counts,
             target_count,
             exclude_n,
-            max_ambiguities,
+            costs,
+            max_cost,
+            deadline,
         );
 
         let percentage = (best_coverage_count as f64 / total_original) * 100.0;
@@ -273,13 +577,17 @@ fn find_incremental_variants(
     variants
 }
 
-/// Find consensus for incremental method using bitmask tracking.
+/// Find consensus for incremental method using bitmask tracking. Cost
+/// budgets are tried in increasing order, so the first consensus reaching the
+/// target is the cheapest found.
 fn find_incremental_consensus(
     unique_remaining: &[&str],
     remaining_counts: &HashMap<&str, usize>,
     target_count: usize,
     exclude_n: bool,
-    max_ambiguities: Option<usize>,
+    costs: &AmbiguityCosts,
+    max_cost: Option<usize>,
+    deadline: &WindowDeadline,
 ) -> (String, usize) {
     if unique_remaining.is_empty() {
         return (String::new(), 0);
@@ -290,20 +598,25 @@ fn find_incremental_consensus(
     let mut best_coverage_count = 0usize;
     let mut found_target = false;
 
-    let max_amb_level = max_ambiguities.unwrap_or(seq_len);
+    let max_cost_level = max_cost.unwrap_or(seq_len * costs.max_cost());
 
-    // Allocated once per call, reused across all seeds and amb_levels
+    // Allocated once per call, reused across all seeds and cost levels
     let mut group_mask: Vec<u8> = vec![0u8; seq_len];
 
-    for amb_level in 0..=max_amb_level {
-        if found_target {
-            break;
-        }
+    let mut cost_level = 0usize;
+    while !found_target && cost_level <= max_cost_level {
+        // Smallest trial cost rejected at this level. Levels below it make the
+        // same decisions, so the search jumps straight to it.
+        let mut next_level: Option<usize> = None;
 
         let mut sorted_remaining: Vec<_> = unique_remaining.to_vec();
         sorted_remaining.sort_by_key(|&s| std::cmp::Reverse(remaining_counts.get(s).unwrap_or(&0)));
 
         for &seed_seq in sorted_remaining.iter().take(50) {
+            if deadline.expired() {
+                break;
+            }
+
             // Initialize group_mask from seed
             let seed_bytes = seed_seq.as_bytes();
             for pos in 0..seq_len {
@@ -317,14 +630,20 @@ fn find_incremental_consensus(
                 }
 
                 let other_bytes = other_seq.as_bytes();
-                let mut trial_amb_count = 0usize;
+                let mut trial_cost = 0usize;
                 let mut trial_valid = true;
 
                 for pos in 0..seq_len {
                     let m = group_mask[pos] | base_to_bit(other_bytes[pos]);
                     if m.count_ones() > 1 {
-                        trial_amb_count += 1;
-                        if (exclude_n && m == 0b1111) || trial_amb_count > amb_level {
+                        if exclude_n && m == 0b1111 {
+                            trial_valid = false;
+                            break;
+                        }
+                        trial_cost += costs.mask_cost(m);
+                        if trial_cost > cost_level {
+                            next_level =
+                                Some(next_level.map_or(trial_cost, |n| n.min(trial_cost)));
                             trial_valid = false;
                             break;
                         }
@@ -338,9 +657,9 @@ fn find_incremental_consensus(
                 }
             }
 
-            let (consensus, amb_count, is_valid) = consensus_from_mask(&group_mask, exclude_n);
+            let (consensus, cost, is_valid) = consensus_from_mask(&group_mask, exclude_n, costs);
 
-            if !is_valid || amb_count > amb_level {
+            if !is_valid || cost > cost_level {
                 continue;
             }
 
@@ -364,6 +683,11 @@ fn find_incremental_consensus(
                 best_coverage_count = coverage_count;
             }
         }
+
+        match next_level {
+            Some(level) if !deadline.expired() => cost_level = level,
+            _ => break,
+        }
     }
 
     // Fallback
@@ -381,25 +705,30 @@ fn find_incremental_consensus(
 }
 
 /// Build a consensus String from a bitmask array.
-/// Returns (consensus, ambiguity_count, is_valid).
-fn consensus_from_mask(mask: &[u8], exclude_n: bool) -> (String, usize, bool) {
+/// Returns (consensus, ambiguity_cost, is_valid).
+fn consensus_from_mask(
+    mask: &[u8],
+    exclude_n: bool,
+    costs: &AmbiguityCosts,
+) -> (String, usize, bool) {
     let mut consensus = String::with_capacity(mask.len());
-    let mut amb_count = 0;
+    let mut cost = 0;
     for &m in mask {
         let code = IUPAC_FROM_MASK[m as usize];
         if m.count_ones() > 1 {
-            amb_count += 1;
+            cost += costs.mask_cost(m);
             if exclude_n && code == b'N' {
-                return (consensus, amb_count, false);
+                return (consensus, cost, false);
             }
         }
         consensus.push(code as char);
     }
-    (consensus, amb_count, true)
+    (consensus, cost, true)
 }
 
 /// Create consensus from sequences using bitmask arithmetic.
-fn create_consensus_from_seqs(sequences: &[&str], exclude_n: bool) -> (String, usize, bool) {
+/// Returns (consensus, ambiguity_count, is_valid).
+pub fn create_consensus_from_seqs(sequences: &[&str], exclude_n: bool) -> (String, usize, bool) {
     if sequences.is_empty() {
         return (String::new(), 0, true);
     }
@@ -454,6 +783,11 @@ fn calculate_variants_for_threshold(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::iupac::iupac_to_mask;
+
+    fn no_deadline() -> WindowDeadline {
+        WindowDeadline::new(None)
+    }
 
     #[test]
     fn test_no_ambiguities() {
@@ -479,7 +813,9 @@ mod tests {
     #[test]
     fn test_incremental_variants() {
         let seqs = vec!["ACGT", "ACGT", "ACGA", "ACGA", "ACGA", "TCGT", "TCGT"];
-        let variants = find_incremental_variants(&seqs, 50.0, false, Some(1));
+        let costs = AmbiguityCosts::default();
+        let variants =
+            find_incremental_variants(&seqs, 50.0, false, &costs, Some(1), &no_deadline());
         assert!(!variants.is_empty());
         let total_count: usize = variants.iter().map(|v| v.count).sum();
         assert_eq!(total_count, 7);
@@ -488,8 +824,124 @@ mod tests {
     #[test]
     fn test_fixed_ambiguities() {
         let seqs = vec!["ACGT", "ACGA"];
-        let variants = find_minimum_variants_greedy(&seqs, 1, false);
+        let costs = AmbiguityCosts::default();
+        let variants = find_minimum_variants_greedy(&seqs, 1, false, &costs, &no_deadline());
         assert_eq!(variants.len(), 1);
         assert_eq!(variants[0].count, 2);
+        // ACGW expands to ACGA and ACGT
+        assert_eq!(variants[0].expansion_count(), 2);
+    }
+
+    #[test]
+    fn test_degenerate_references() {
+        // A reference read as RCGT is only covered by a variant that includes both A and G
+        let seqs = vec!["ACGT", "ACGT", "RCGT"];
+        let costs = AmbiguityCosts::default();
+        let tally = |max_cost| -> Vec<(String, usize)> {
+            find_minimum_variants_greedy(&seqs, max_cost, false, &costs, &no_deadline())
+                .into_iter()
+                .map(|v| (v.sequence, v.count))
+                .collect()
+        };
+        assert_eq!(
+            tally(0),
+            vec![("ACGT".to_string(), 2), ("RCGT".to_string(), 1)]
+        );
+        assert_eq!(tally(1), vec![("RCGT".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_optimal_merging() {
+        // One ambiguity per variant: two variants (AAAM + CAAM, or MAAA +
+        // MAAC) cover all four, improving on a four-singleton starting point
+        let seqs = vec!["AAAA", "AAAC", "CAAA", "CAAC", "CAAC"];
+        let costs = AmbiguityCosts::default();
+        let singletons = find_variants_no_ambiguities(&seqs);
+        assert_eq!(singletons.len(), 4);
+        let optimal = find_minimum_variants_optimal(
+            &seqs,
+            singletons,
+            1,
+            false,
+            &costs,
+            Duration::from_secs(5),
+            &no_deadline(),
+        );
+        assert_eq!(optimal.len(), 2);
+        assert_eq!(optimal[0].count, 3);
+        assert_eq!(optimal[1].count, 2);
+        for variant in &optimal {
+            assert_eq!(variant.sequence.chars().filter(|&c| c == 'M').count(), 1);
+        }
+
+        // Without any ambiguity budget nothing can merge
+        let exact = find_variants_no_ambiguities(&seqs);
+        let budget = Duration::from_secs(5);
+        let unchanged =
+            find_minimum_variants_optimal(&seqs, exact, 0, false, &costs, budget, &no_deadline());
+        assert_eq!(unchanged.len(), 4);
+    }
+
+    #[test]
+    fn test_expired_deadline_truncates() {
+        let seqs = vec!["ACGT", "ACGA", "ACGA", "TTTT"];
+        let method = AnalysisMethod::FixedAmbiguities(1);
+        let costs = AmbiguityCosts::default();
+        let past = Some(Instant::now());
+        let result =
+            analyze_sequences(&seqs, &method, false, &costs, MergeStrategy::Greedy, 90.0, past);
+        assert!(result.truncated);
+        // Nothing was merged: every unique sequence is its own exact variant
+        let sequences: Vec<&str> = result.variants.iter().map(|v| v.sequence.as_str()).collect();
+        assert_eq!(sequences, vec!["ACGA", "ACGT", "TTTT"]);
+
+        let result =
+            analyze_sequences(&seqs, &method, false, &costs, MergeStrategy::Greedy, 90.0, None);
+        assert!(!result.truncated);
+        assert_eq!(result.variants[0].sequence, "ACGW");
+    }
+
+    #[test]
+    fn test_indel_variants_kept_exact() {
+        let seqs = vec!["ACGT", "ACGA", "AC-T", "ACGA", "AC-T", "ACgGT"];
+        let method = AnalysisMethod::FixedAmbiguities(1);
+        let costs = AmbiguityCosts::default();
+        let result =
+            analyze_sequences(&seqs, &method, false, &costs, MergeStrategy::Greedy, 90.0, None);
+        let tally: Vec<(&str, usize)> = result
+            .variants
+            .iter()
+            .map(|v| (v.sequence.as_str(), v.count))
+            .collect();
+        assert_eq!(tally, vec![("ACGW", 3), ("AC-T", 2), ("ACgGT", 1)]);
+        assert!((result.variants[1].percentage - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(result.variants[2].indel_count(), 1);
+        assert_eq!(result.indel_matches, 3);
+    }
+
+    #[test]
+    fn test_weighted_ambiguity_costs() {
+        // Merging all three needs a 3-fold code (ACGH); two of them merge with
+        // a 2-fold code
+        let seqs = vec!["ACGA", "ACGA", "ACGC", "ACGT"];
+        let unit_costs = AmbiguityCosts::default();
+        let unit = find_minimum_variants_greedy(&seqs, 1, false, &unit_costs, &no_deadline());
+        assert_eq!(unit[0].sequence, "ACGH");
+
+        let costs = AmbiguityCosts {
+            two_fold: 1,
+            three_fold: 3,
+            four_fold: 5,
+        };
+        // With 3-fold codes too expensive, only a 2-fold code fits the budget
+        let weighted = find_minimum_variants_greedy(&seqs, 2, false, &costs, &no_deadline());
+        assert_eq!(weighted[0].count, 3);
+        assert_eq!(costs.mask_cost(iupac_to_mask(weighted[0].sequence.as_bytes()[3])), 1);
+
+        // Incremental: the cheapest consensus covering the target is chosen
+        let incremental =
+            find_incremental_variants(&seqs, 75.0, false, &costs, None, &no_deadline());
+        assert_eq!(incremental[0].count, 3);
+        assert!(costs.mask_cost(iupac_to_mask(incremental[0].sequence.as_bytes()[3])) <= 1);
     }
 }
@@ -0,0 +1,172 @@
+//! Template anchors reused across windows
+//!
+//! Adjacent windows overlap almost entirely, yet each aligns its oligo
+//! against every reference from scratch. Mapping each reference to the
+//! template once, as the stretches of the template it contains verbatim,
+//! answers "does this window occur exactly here?" for every window without
+//! aligning; only windows outside those stretches are aligned.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+/// k-mer size used to find shared stretches
+const ANCHOR_K: usize = 16;
+
+/// Template stretch `start..end` found verbatim in a reference at
+/// `start + offset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ExactRun {
+    start: usize,
+    end: usize,
+    offset: i64,
+}
+
+/// Exact runs of one reference, sorted by start, with the index of the run
+/// reaching furthest among the runs up to each one
+#[derive(Debug, Clone, Default)]
+struct ReferenceRuns {
+    runs: Vec<ExactRun>,
+    furthest: Vec<usize>,
+}
+
+/// Stretches of the template each reference contains verbatim
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceAnchors {
+    references: Vec<ReferenceRuns>,
+}
+
+impl ReferenceAnchors {
+    /// Map every reference to `template`: k-mers shared on the same diagonal
+    /// at consecutive template positions join into one exact run.
+    pub fn build(template: &[u8], references: &[Vec<u8>]) -> Self {
+        let mut template_kmers: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        for (pos, kmer) in template.windows(ANCHOR_K).enumerate() {
+            template_kmers.entry(kmer).or_default().push(pos);
+        }
+        let references = references
+            .par_iter()
+            .map(|reference| exact_runs(&template_kmers, reference))
+            .collect();
+        Self { references }
+    }
+
+    /// Whether the template window of `length` bases at `position` occurs
+    /// verbatim in the reference at `reference_index`.
+    pub fn contains_window(&self, reference_index: usize, position: usize, length: usize) -> bool {
+        let Some(runs) = self.references.get(reference_index) else {
+            return false;
+        };
+        let before = runs.runs.partition_point(|run| run.start <= position);
+        before > 0 && runs.runs[runs.furthest[before - 1]].end >= position + length
+    }
+
+    /// Number of exact runs over all references
+    pub fn run_count(&self) -> usize {
+        self.references.iter().map(|r| r.runs.len()).sum()
+    }
+}
+
+fn exact_runs(template_kmers: &HashMap<&[u8], Vec<usize>>, reference: &[u8]) -> ReferenceRuns {
+    // (offset, template position) of every shared k-mer
+    let mut hits: Vec<(i64, usize)> = Vec::new();
+    for (ref_pos, kmer) in reference.windows(ANCHOR_K).enumerate() {
+        if let Some(template_positions) = template_kmers.get(kmer) {
+            hits.extend(
+                template_positions
+                    .iter()
+                    .map(|&t| (ref_pos as i64 - t as i64, t)),
+            );
+        }
+    }
+    hits.sort_unstable();
+
+    let mut runs: Vec<ExactRun> = Vec::new();
+    for (offset, t) in hits {
+        match runs.last_mut() {
+            Some(run) if run.offset == offset && run.end == t + ANCHOR_K - 1 => {
+                run.end = t + ANCHOR_K;
+            }
+            _ => runs.push(ExactRun {
+                start: t,
+                end: t + ANCHOR_K,
+                offset,
+            }),
+        }
+    }
+    runs.sort_unstable_by_key(|run| run.start);
+
+    let mut furthest = Vec::<usize>::with_capacity(runs.len());
+    for (i, run) in runs.iter().enumerate() {
+        let best = match furthest.last() {
+            Some(&best) if runs[best].end >= run.end => best,
+            _ => i,
+        };
+        furthest.push(best);
+    }
+    ReferenceRuns { runs, furthest }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pairwise::{
+        PairwiseMatch, collect_matches_with_aligner, collect_matches_with_anchors, create_aligner,
+    };
+    use crate::analysis::types::PairwiseParams;
+
+    const TEMPLATE: &[u8] = b"CAGGACTTCAGCTTAGCCGATCCTGAACGTAGGCTTACATATGGTACGTCATGTTCTAGAAATGG";
+
+    #[test]
+    fn test_contains_window() {
+        let mut shifted = b"TTTTT".to_vec();
+        shifted.extend_from_slice(TEMPLATE);
+        // One substitution at template position 30
+        let mut mutated = TEMPLATE.to_vec();
+        mutated[30] = b'T';
+        let references = vec![shifted, mutated, b"ACGT".to_vec()];
+        let anchors = ReferenceAnchors::build(TEMPLATE, &references);
+
+        let last = TEMPLATE.len() - 20;
+        assert!((0..=last).all(|p| anchors.contains_window(0, p, 20)));
+        for p in 0..=last {
+            let exact = !(p..p + 20).contains(&30);
+            assert_eq!(anchors.contains_window(1, p, 20), exact, "position {}", p);
+        }
+        assert!(!anchors.contains_window(2, 0, 20));
+        assert!(!anchors.contains_window(3, 0, 20));
+        assert_eq!(anchors.run_count(), 1 + 2);
+    }
+
+    #[test]
+    fn test_anchored_matches_equal_alignment() {
+        let mut mutated = TEMPLATE.to_vec();
+        mutated[30] = b'T';
+        let mut indel = TEMPLATE.to_vec();
+        indel.remove(40);
+        let references = vec![TEMPLATE.to_vec(), mutated, indel];
+        let anchors = ReferenceAnchors::build(TEMPLATE, &references);
+        let params = PairwiseParams {
+            max_indels: 1,
+            ..Default::default()
+        };
+        let mut aligner = create_aligner(20, TEMPLATE.len(), &params);
+
+        for p in 0..=TEMPLATE.len() - 20 {
+            let oligo = &TEMPLATE[p..p + 20];
+            let (aligned, aligned_no_match) =
+                collect_matches_with_aligner(&mut aligner, oligo, &references, &params);
+            let (anchored, anchored_no_match) =
+                collect_matches_with_anchors(&mut aligner, oligo, &references, &params, |i| {
+                    anchors.contains_window(i, p, 20)
+                });
+            assert_eq!(aligned_no_match, anchored_no_match);
+            let summary = |m: &[PairwiseMatch]| {
+                m.iter()
+                    .map(|m| (m.reference_index, m.matched_sequence.clone(), m.score))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(summary(&aligned), summary(&anchored), "position {}", p);
+        }
+    }
+}
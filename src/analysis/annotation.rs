@@ -0,0 +1,224 @@
+//! Feature annotations (GFF3 or BED) for the template
+//!
+//! Annotations are only drawn over the heatmap, so parsing keeps what the
+//! track shows: where a feature lies, what it is called, and its strand.
+
+/// One annotated feature, in 0-based half-open template coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    /// Sequence the feature is on (GFF seqid / BED chrom)
+    pub seqid: String,
+    /// Feature type (gene, CDS, ...); "feature" for BED records
+    pub kind: String,
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub reverse: bool,
+}
+
+/// GFF3 feature types that describe the sequence itself rather than a
+/// feature on it, and would cover the whole track
+const GFF_SKIPPED_TYPES: [&str; 2] = ["region", "source"];
+
+/// Parse a GFF3 or BED annotation file. The format is detected from a
+/// `##gff-version` header or the first record's columns. GFF coordinates are
+/// 1-based inclusive and BED 0-based half-open; both come out 0-based
+/// half-open. GFF region/source records and an embedded `##FASTA` section are
+/// skipped.
+pub fn parse_annotations(text: &str) -> Result<Vec<Feature>, String> {
+    let records: Vec<(usize, Vec<&str>)> = text
+        .lines()
+        .enumerate()
+        .take_while(|(_, line)| !line.starts_with("##FASTA"))
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("track")
+                && !line.starts_with("browser")
+        })
+        .map(|(i, line)| (i + 1, line.trim_end().split('\t').collect()))
+        .collect();
+
+    let gff = text.lines().any(|l| l.starts_with("##gff-version"))
+        || records.first().is_some_and(|(_, fields)| {
+            fields.len() >= 9
+                && fields[1].parse::<usize>().is_err()
+                && fields[3].parse::<usize>().is_ok()
+        });
+
+    let mut features = Vec::new();
+    for (line_no, fields) in records {
+        let feature = if gff {
+            parse_gff_record(&fields)
+        } else {
+            parse_bed_record(&fields)
+        };
+        match feature {
+            Ok(Some(feature)) => features.push(feature),
+            Ok(None) => {}
+            Err(e) => return Err(format!("Line {}: {}", line_no, e)),
+        }
+    }
+
+    if features.is_empty() {
+        return Err("No features found in annotation file".to_string());
+    }
+    Ok(features)
+}
+
+fn parse_gff_record(fields: &[&str]) -> Result<Option<Feature>, String> {
+    if fields.len() < 9 {
+        return Err(format!("expected 9 GFF columns, found {}", fields.len()));
+    }
+    let kind = fields[2];
+    if GFF_SKIPPED_TYPES.contains(&kind) {
+        return Ok(None);
+    }
+    let start = parse_coordinate(fields[3])?;
+    let end = parse_coordinate(fields[4])?;
+    if start == 0 || end < start {
+        return Err(format!("invalid GFF coordinates {}..{}", start, end));
+    }
+    // Name, then the common identifying attributes, then the type
+    let attribute = |key: &str| {
+        fields[8].split(';').find_map(|pair| {
+            let (k, v) = pair.trim().split_once('=')?;
+            (k == key && !v.is_empty()).then(|| v.to_string())
+        })
+    };
+    let name = attribute("Name")
+        .or_else(|| attribute("gene"))
+        .or_else(|| attribute("ID"))
+        .unwrap_or_else(|| kind.to_string());
+    Ok(Some(Feature {
+        seqid: fields[0].to_string(),
+        kind: kind.to_string(),
+        name,
+        start: start - 1,
+        end,
+        reverse: fields[6] == "-",
+    }))
+}
+
+fn parse_bed_record(fields: &[&str]) -> Result<Option<Feature>, String> {
+    if fields.len() < 3 {
+        return Err(format!(
+            "expected at least 3 BED columns, found {}",
+            fields.len()
+        ));
+    }
+    let start = parse_coordinate(fields[1])?;
+    let end = parse_coordinate(fields[2])?;
+    if end <= start {
+        return Err(format!("invalid BED coordinates {}..{}", start, end));
+    }
+    let name = fields
+        .get(3)
+        .filter(|n| !n.is_empty() && **n != ".")
+        .map_or_else(
+            || format!("{}:{}-{}", fields[0], start + 1, end),
+            |n| n.to_string(),
+        );
+    Ok(Some(Feature {
+        seqid: fields[0].to_string(),
+        kind: "feature".to_string(),
+        name,
+        start,
+        end,
+        reverse: fields.get(5) == Some(&"-"),
+    }))
+}
+
+fn parse_coordinate(field: &str) -> Result<usize, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid coordinate '{}'", field))
+}
+
+/// The features on the template: those whose seqid is the first word of
+/// `template_name`, or all of them when none is (single-sequence files often
+/// name the sequence differently from the FASTA header).
+pub fn features_for_template<'a>(features: &'a [Feature], template_name: &str) -> Vec<&'a Feature> {
+    let id = template_name.split_whitespace().next().unwrap_or("");
+    let on_template: Vec<&Feature> = features.iter().filter(|f| f.seqid == id).collect();
+    if on_template.is_empty() {
+        features.iter().collect()
+    } else {
+        on_template
+    }
+}
+
+/// Stack overlapping features into lanes for drawing: the lane of each
+/// feature (in input order) and the number of lanes. Features are placed in
+/// start order into the first lane that is free by then.
+pub fn feature_lanes(features: &[&Feature]) -> (Vec<usize>, usize) {
+    let mut order: Vec<usize> = (0..features.len()).collect();
+    order.sort_by_key(|&i| (features[i].start, std::cmp::Reverse(features[i].end)));
+
+    let mut lane_ends: Vec<usize> = Vec::new();
+    let mut lanes = vec![0; features.len()];
+    for i in order {
+        let feature = features[i];
+        let lane = match lane_ends.iter().position(|&end| end <= feature.start) {
+            Some(lane) => lane,
+            None => {
+                lane_ends.push(0);
+                lane_ends.len() - 1
+            }
+        };
+        lane_ends[lane] = feature.end;
+        lanes[i] = lane;
+    }
+    (lanes, lane_ends.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gff() {
+        let text = "##gff-version 3\n\
+                    NC_045512.2\tRefSeq\tregion\t1\t29903\t.\t+\t.\tID=NC_045512.2:1..29903\n\
+                    NC_045512.2\tRefSeq\tgene\t266\t21555\t.\t+\t.\tID=gene-ORF1ab;Name=ORF1ab\n\
+                    NC_045512.2\tRefSeq\tCDS\t21563\t25384\t.\t-\t0\tID=cds-S;gene=S\n\
+                    ##FASTA\n>NC_045512.2\nACGT\n";
+        let features = parse_annotations(text).unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].name, "ORF1ab");
+        assert_eq!((features[0].start, features[0].end), (265, 21555));
+        assert_eq!(features[1].name, "S");
+        assert_eq!(features[1].kind, "CDS");
+        assert!(features[1].reverse);
+    }
+
+    #[test]
+    fn test_parse_bed() {
+        let text = "track name=genes\nchr1\t0\t100\tgeneA\t0\t-\nchr1\t50\t80\n";
+        let features = parse_annotations(text).unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!((features[0].start, features[0].end), (0, 100));
+        assert!(features[0].reverse);
+        assert_eq!(features[1].name, "chr1:51-80");
+
+        let err = parse_annotations("chr1\t10\tx\n").unwrap_err();
+        assert!(err.starts_with("Line 1:"), "{}", err);
+        assert!(parse_annotations("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_features_for_template_and_lanes() {
+        let text = "chrA\t0\t100\ta\nchrA\t50\t150\tb\nchrA\t100\t200\tc\nchrB\t0\t10\td\n";
+        let features = parse_annotations(text).unwrap();
+
+        let on_a = features_for_template(&features, "chrA some description");
+        assert_eq!(on_a.len(), 3);
+        assert_eq!(features_for_template(&features, "other").len(), 4);
+
+        let (lanes, count) = feature_lanes(&on_a);
+        assert_eq!(lanes, vec![0, 1, 0]);
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,304 @@
+//! Per-reference assignment of a window
+//!
+//! Lists, for one window, which variant each reference's matched window went
+//! to and how closely the reference aligned. Results keep only the variant
+//! sequences and counts, so the assignment comes from realigning the template
+//! oligo to the reference sequences, prepared and aligned as the screen did.
+
+use std::borrow::Cow;
+
+use super::analyzer::variant_for_sequence;
+use super::export::csv_field;
+use super::fasta::ReferenceData;
+use super::pairwise::create_aligner;
+use super::screener::screened_window_matches;
+use super::trim::trim_references;
+use super::types::{AnalysisParams, Variant};
+
+/// One reference's outcome in a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceAssignment {
+    /// Index into `ScreeningResults::reference_names`
+    pub reference: usize,
+    pub name: String,
+    /// 0-based index of the variant carrying the reference; None for no match
+    pub variant: Option<usize>,
+    /// Mismatches of the accepted alignment; None for no match
+    pub mismatches: Option<u32>,
+    /// The reference's matched window; None for no match
+    pub matched_sequence: Option<String>,
+}
+
+/// Column of the reference assignment table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentColumn {
+    Reference,
+    Variant,
+    Mismatches,
+}
+
+/// The references as the screen aligned windows against them: trimmed to the
+/// template region when the results were screened with trimming. Prepared
+/// once for all the windows passed to `reference_assignments`.
+pub fn assignment_references<'a>(
+    template: &str,
+    references: &'a ReferenceData,
+    params: &AnalysisParams,
+) -> Cow<'a, ReferenceData> {
+    match params.reference_trim_flank {
+        Some(flank) => Cow::Owned(trim_references(template, references, flank).0),
+        None => Cow::Borrowed(references),
+    }
+}
+
+/// Assign every reference of a window by realigning it to `oligo`, the
+/// template window (expanded over ambiguity codes as in the screen).
+/// `references` come from `assignment_references` for the sequences the
+/// results were screened against, in `reference_names` order; a reference
+/// whose matched window is in no variant counts as no match.
+pub fn reference_assignments(
+    oligo: &[u8],
+    variants: &[Variant],
+    references: &ReferenceData,
+    params: &AnalysisParams,
+) -> Vec<ReferenceAssignment> {
+    let mut assignments: Vec<ReferenceAssignment> = references
+        .names
+        .iter()
+        .enumerate()
+        .map(|(reference, name)| ReferenceAssignment {
+            reference,
+            name: name.clone(),
+            variant: None,
+            mismatches: None,
+            matched_sequence: None,
+        })
+        .collect();
+
+    let ref_bytes: Vec<Vec<u8>> = references
+        .sequences
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect();
+    let max_ref_len = ref_bytes.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut aligner = create_aligner(oligo.len(), max_ref_len, &params.pairwise);
+    let matches =
+        screened_window_matches(&mut aligner, oligo, &ref_bytes, params).unwrap_or_default();
+    for m in matches {
+        let a = &mut assignments[m.reference_index];
+        a.variant = variant_for_sequence(variants, &m.matched_sequence);
+        if a.variant.is_none() {
+            continue;
+        }
+        a.mismatches = Some(m.mismatches as u32);
+        a.matched_sequence = Some(m.matched_sequence);
+    }
+    assignments
+}
+
+/// Sort by one column, ascending unless `descending`, breaking ties by
+/// reference order. No match sorts after every variant and mismatch count.
+pub fn sort_assignments(
+    assignments: &mut [ReferenceAssignment],
+    column: AssignmentColumn,
+    descending: bool,
+) {
+    assignments.sort_by(|a, b| {
+        let order = match column {
+            AssignmentColumn::Reference => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            AssignmentColumn::Variant => {
+                (a.variant.is_none(), a.variant).cmp(&(b.variant.is_none(), b.variant))
+            }
+            AssignmentColumn::Mismatches => {
+                let key = |x: &ReferenceAssignment| (x.variant.is_none(), x.mismatches);
+                key(a).cmp(&key(b))
+            }
+        };
+        let order = if descending { order.reverse() } else { order };
+        order.then(a.reference.cmp(&b.reference))
+    });
+}
+
+/// The assignments as CSV, one row per reference: 1-based variant (or "no
+/// match"), mismatches and matched window (empty when unknown).
+pub fn assignments_csv(assignments: &[ReferenceAssignment], variants: &[Variant]) -> String {
+    let mut csv = String::from("reference,variant,variant_sequence,mismatches,matched_window\n");
+    for a in assignments {
+        let (variant, sequence) = match a.variant.and_then(|i| variants.get(i).map(|v| (i, v))) {
+            Some((i, v)) => ((i + 1).to_string(), v.sequence.as_str()),
+            None => ("no match".to_string(), ""),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&a.name),
+            variant,
+            sequence,
+            a.mismatches.map_or(String::new(), |m| m.to_string()),
+            a.matched_sequence.as_deref().unwrap_or("")
+        ));
+    }
+    csv
+}
+
+/// The assignments carrying each of `variant_count` variants, in reference
+/// order
+pub fn variant_members(
+    assignments: &[ReferenceAssignment],
+    variant_count: usize,
+) -> Vec<Vec<&ReferenceAssignment>> {
+    let mut members = vec![Vec::new(); variant_count];
+    for a in assignments {
+        if let Some(variant) = a.variant.and_then(|i| members.get_mut(i)) {
+            variant.push(a);
+        }
+    }
+    for variant in &mut members {
+        variant.sort_by_key(|a| a.reference);
+    }
+    members
+}
+
+/// The references carrying each variant as CSV: one row per (variant rank,
+/// variant sequence, reference name), in reference order within a variant.
+pub fn variant_members_csv(assignments: &[ReferenceAssignment], variants: &[Variant]) -> String {
+    let mut csv = String::from("variant,sequence,reference\n");
+    let members = variant_members(assignments, variants.len());
+    for (i, (variant, members)) in variants.iter().zip(members).enumerate() {
+        for a in members {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                i + 1,
+                variant.sequence,
+                csv_field(&a.name)
+            ));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::TemplateData;
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::{AnalysisMethod, TemplateAmbiguityPolicy};
+
+    fn variant(sequence: &str, count: usize) -> Variant {
+        Variant {
+            sequence: sequence.to_string(),
+            count,
+            percentage: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_reference_assignments() {
+        let oligo = b"TATGGTACGT";
+        let references = ReferenceData {
+            names: vec!["Exact".into(), "One off".into(), "Unrelated".into()],
+            sequences: vec![
+                "CCTATGGTACGTCC".into(),
+                "CCTATGGTCCGTCC".into(),
+                "AAAAAAAAAAAAAA".into(),
+            ],
+        };
+        let variants = vec![variant("TATGGTACGT", 1), variant("TATGGTCCGT", 1)];
+        let params = AnalysisParams::default();
+
+        let mut assignments = reference_assignments(oligo, &variants, &references, &params);
+        assert_eq!(
+            assignments.iter().map(|a| a.variant).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), None]
+        );
+        assert_eq!(assignments[0].mismatches, Some(0));
+        assert_eq!(assignments[1].mismatches, Some(1));
+        assert_eq!(
+            assignments[1].matched_sequence.as_deref(),
+            Some("TATGGTCCGT")
+        );
+        assert_eq!(assignments[2].variant, None);
+
+        sort_assignments(&mut assignments, AssignmentColumn::Mismatches, true);
+        let names: Vec<&str> = assignments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Unrelated", "One off", "Exact"]);
+
+        let csv = assignments_csv(&assignments, &variants);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "Unrelated,no match,,,");
+        assert_eq!(lines[2], "One off,2,TATGGTCCGT,1,TATGGTCCGT");
+    }
+
+    #[test]
+    fn test_assignments_agree_with_trimmed_expanded_screen() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGRAATGGGCTGTACCGATTCAG".to_string(),
+            rna: false,
+        };
+        let background = "CAGGACTTCAGCTTAGCCGATCCTGAACGTAGGCTTACA".repeat(5);
+        // Ref2's window has two mismatches in its template region, but an
+        // exact copy of Ref1's window lies outside the trimmed region
+        let references = ReferenceData {
+            names: vec!["Ref1".into(), "Ref2".into()],
+            sequences: vec![
+                format!(
+                    "{}TATGGTACGTCATGTTCTAGAAATGGGCTGTACCGATTCAG{}",
+                    background, background
+                ),
+                format!(
+                    "{}TATGGTACGTCATGTTCAAGGAATCGGCTGTACCGATTCAG{}TCTAGAAATGGG{}",
+                    background, background, background
+                ),
+            ],
+        };
+        let params = AnalysisParams {
+            method: AnalysisMethod::NoAmbiguities,
+            min_oligo_length: 12,
+            max_oligo_length: 12,
+            resolution: 1,
+            template_ambiguity: TemplateAmbiguityPolicy::Expand,
+            reference_trim_flank: Some(10),
+            ..Default::default()
+        };
+        let results = run_screening(&template, &references, &params, &[], None);
+        let pr = &results.results_by_length[&12].positions[15];
+        assert_eq!(pr.position, 15);
+        let variants = &pr.analysis.variants;
+        assert_eq!(variants.len(), 2);
+
+        let screened = assignment_references(&template.sequence, &references, &params);
+        let oligo = &template.sequence.as_bytes()[15..27];
+        let assignments = reference_assignments(oligo, variants, &screened, &params);
+        let members = variant_members(&assignments, variants.len());
+        for (variant, members) in variants.iter().zip(&members) {
+            assert_eq!(members.len(), variant.count);
+        }
+        assert_eq!(
+            assignments[1].matched_sequence.as_deref(),
+            Some("TCAAGGAATCGG")
+        );
+    }
+
+    #[test]
+    fn test_variant_members_csv() {
+        let variants = vec![variant("ACGT", 2)];
+        let assignment =
+            |reference: usize, name: &str, variant: Option<usize>| ReferenceAssignment {
+                reference,
+                name: name.to_string(),
+                variant,
+                mismatches: variant.map(|_| 0),
+                matched_sequence: variant.map(|_| "ACGT".to_string()),
+            };
+        let assignments = vec![
+            assignment(1, "strain 2, isolate B", Some(0)),
+            assignment(2, "Unrelated", None),
+            assignment(0, "R1", Some(0)),
+        ];
+        let csv = variant_members_csv(&assignments, &variants);
+        assert_eq!(
+            csv,
+            "variant,sequence,reference\n1,ACGT,R1\n1,ACGT,\"strain 2, isolate B\"\n"
+        );
+    }
+}
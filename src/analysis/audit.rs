@@ -0,0 +1,144 @@
+//! Timestamped audit trail of parameter changes and job submissions
+//!
+//! The trail is kept for the session and copied into each job's results, so a
+//! saved results file records what was tried (and when) before it was run.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::AnalysisParams;
+
+/// A single audit trail event with its time (seconds since the Unix epoch, UTC)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub event: AuditEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AuditEvent {
+    /// Analysis parameters were edited; one `field: old -> new` line per change
+    ParamsChanged { changes: Vec<String> },
+    /// A job was added to the worklist with these (resolved) parameters
+    JobSubmitted {
+        job_id: u64,
+        template_file: String,
+        reference_file: String,
+        params: Box<AnalysisParams>,
+    },
+}
+
+impl AuditEntry {
+    /// Entry stamped with the current time.
+    pub fn now(event: AuditEvent) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self { timestamp, event }
+    }
+
+    /// One-line description for display.
+    pub fn summary(&self) -> String {
+        let what = match &self.event {
+            AuditEvent::ParamsChanged { changes } => {
+                format!("Parameters changed: {}", changes.join("; "))
+            }
+            AuditEvent::JobSubmitted {
+                job_id,
+                template_file,
+                reference_file,
+                ..
+            } => format!(
+                "Job #{} submitted ({} vs {})",
+                job_id, template_file, reference_file
+            ),
+        };
+        format!("{}  {}", format_timestamp(self.timestamp), what)
+    }
+}
+
+/// Field-level differences between two parameter sets, as `path: old -> new`.
+/// Nested fields use dotted paths (e.g. `pairwise.max_mismatches`).
+pub fn param_changes(old: &AnalysisParams, new: &AnalysisParams) -> Vec<String> {
+    let mut changes = Vec::new();
+    if let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) {
+        diff_values("", &old, &new, &mut changes);
+    }
+    changes
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) if a.len() == b.len() && a.keys().eq(b.keys()) => {
+            for (key, old_value) in a {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(&child, old_value, &b[key], changes);
+            }
+        }
+        _ if old != new => changes.push(format!("{}: {} -> {}", path, old, new)),
+        _ => {}
+    }
+}
+
+/// Format Unix seconds as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::AnalysisMethod;
+
+    #[test]
+    fn test_param_changes() {
+        let old = AnalysisParams::default();
+        let mut new = old.clone();
+        assert!(param_changes(&old, &new).is_empty());
+
+        new.pairwise.max_mismatches = 3;
+        new.method = AnalysisMethod::FixedAmbiguities(2);
+        let changes = param_changes(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.contains(&"pairwise.max_mismatches: 8 -> 3".to_string()));
+        assert!(
+            changes.contains(&"method: \"NoAmbiguities\" -> {\"FixedAmbiguities\":2}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_790_000_000), "2026-09-21 14:13:20 UTC");
+    }
+}
@@ -0,0 +1,399 @@
+//! Compact results files
+//!
+//! Variant sequences make up most of a results file. They are written once
+//! to a top-level `sequence_table` and each variant refers to its sequence by
+//! index, so a sequence found in several windows or lengths is stored once.
+//! A window whose analysis is identical to the one before it (runs of
+//! skipped windows, conserved stretches, exclusivity-only results) is written
+//! as a reference to the first window of the run instead. Files without these
+//! (written by earlier versions) load unchanged.
+//!
+//! The same layout can also be written as zstd-compressed MessagePack, which
+//! is several times smaller than the JSON and much faster to load for
+//! whole-genome results. Loading tells the two apart by the first bytes.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::types::ScreeningResults;
+
+const SEQUENCE_TABLE: &str = "sequence_table";
+/// Key of an analysis stored as the index (within its length's positions)
+/// of an earlier, identical one
+const SAME_AS: &str = "same_as";
+
+/// Extension of compressed binary results files
+pub const BINARY_RESULTS_EXTENSION: &str = "osrz";
+
+/// Magic number opening every zstd frame, and so every binary results file
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// zstd's default level: most of the size gain at a fraction of the time of
+/// the higher levels
+const ZSTD_LEVEL: i32 = 3;
+
+/// Serialize results as pretty JSON with interned variant sequences and
+/// repeated window analyses collapsed.
+pub fn results_to_json(results: &ScreeningResults) -> Result<String, String> {
+    let value = compact_value(results)?;
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+/// Write results in the same format as `results_to_json`, but without
+/// indentation and through a buffer, for large results where building and
+/// pretty-printing one string is slow. `progress` is called with the bytes
+/// written so far each time the buffer is flushed.
+pub fn write_results_json(
+    results: &ScreeningResults,
+    writer: impl Write,
+    progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let value = compact_value(results)?;
+    let counting = CountingWriter {
+        inner: writer,
+        written: 0,
+        progress,
+    };
+    let mut out = BufWriter::with_capacity(1 << 20, counting);
+    serde_json::to_writer(&mut out, &value).map_err(|e| e.to_string())?;
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// Write results in the layout of `write_results_json`, encoded as
+/// MessagePack and zstd-compressed. `progress` is called with the compressed
+/// bytes written so far.
+pub fn write_results_binary(
+    results: &ScreeningResults,
+    writer: impl Write,
+    progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let value = compact_value(results)?;
+    let counting = CountingWriter {
+        inner: writer,
+        written: 0,
+        progress,
+    };
+    let mut encoder = zstd::Encoder::new(counting, ZSTD_LEVEL).map_err(|e| e.to_string())?;
+    rmp_serde::encode::write(&mut encoder, &value).map_err(|e| e.to_string())?;
+    let mut counting = encoder.finish().map_err(|e| e.to_string())?;
+    counting.flush().map_err(|e| e.to_string())
+}
+
+/// Whether results saved to `path` are written in the binary format, going by
+/// the extension.
+pub fn is_binary_results_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(BINARY_RESULTS_EXTENSION))
+}
+
+/// Writer reporting the running byte count of each write
+struct CountingWriter<W, F> {
+    inner: W,
+    written: u64,
+    progress: F,
+}
+
+impl<W: Write, F: FnMut(u64)> Write for CountingWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        (self.progress)(self.written);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Serialized results with sequences interned and repeated analyses collapsed.
+fn compact_value(results: &ScreeningResults) -> Result<Value, String> {
+    let mut value = serde_json::to_value(results).map_err(|e| e.to_string())?;
+
+    let mut table: Vec<String> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for_each_variant_sequence(&mut value, |slot| {
+        if let Value::String(seq) = slot {
+            let seq = std::mem::take(seq);
+            let i = match index.get(&seq) {
+                Some(&i) => i,
+                None => {
+                    index.insert(seq.clone(), table.len());
+                    table.push(seq);
+                    table.len() - 1
+                }
+            };
+            *slot = Value::from(i);
+        }
+    });
+    if let Value::Object(map) = &mut value {
+        map.insert(SEQUENCE_TABLE.to_string(), Value::from(table));
+    }
+
+    for positions in positions_by_length(&mut value) {
+        // Start of the current run of identical analyses
+        let mut first = 0;
+        for i in 1..positions.len() {
+            let (head, tail) = positions.split_at_mut(i);
+            match (head[first].get("analysis"), tail[0].get_mut("analysis")) {
+                (Some(canonical), Some(analysis)) if canonical == analysis => {
+                    *analysis = serde_json::json!({ SAME_AS: first });
+                }
+                _ => first = i,
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+/// Parse results JSON, expanding collapsed window analyses and resolving
+/// interned variant sequences.
+pub fn results_from_json(json: &str) -> Result<ScreeningResults, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    results_from_value(value)
+}
+
+/// Parse a results file in either format: binary when it starts like a zstd
+/// frame, JSON otherwise.
+pub fn results_from_bytes(bytes: &[u8]) -> Result<ScreeningResults, String> {
+    if !bytes.starts_with(&ZSTD_MAGIC) {
+        let json = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+        return results_from_json(json);
+    }
+    let decoder = zstd::Decoder::new(bytes).map_err(|e| e.to_string())?;
+    let value: Value = rmp_serde::from_read(decoder).map_err(|e| e.to_string())?;
+    results_from_value(value)
+}
+
+/// Results from their serialized compact layout.
+fn results_from_value(mut value: Value) -> Result<ScreeningResults, String> {
+    for positions in positions_by_length(&mut value) {
+        for i in 0..positions.len() {
+            let Some(first) = positions[i]
+                .pointer(&format!("/analysis/{}", SAME_AS))
+                .and_then(Value::as_u64)
+            else {
+                continue;
+            };
+            let first = first as usize;
+            if first >= i {
+                return Err(format!(
+                    "window {} refers to a later window ({}) for its analysis",
+                    i, first
+                ));
+            }
+            positions[i]["analysis"] = positions[first]["analysis"].clone();
+        }
+    }
+
+    let table = value
+        .as_object_mut()
+        .and_then(|map| map.remove(SEQUENCE_TABLE));
+    if let Some(Value::Array(table)) = table {
+        let mut error = None;
+        for_each_variant_sequence(&mut value, |slot| {
+            let Some(i) = slot.as_u64() else {
+                return;
+            };
+            match table.get(i as usize) {
+                Some(seq) => *slot = seq.clone(),
+                None => {
+                    error = Some(format!(
+                        "variant sequence {} is not in the sequence table",
+                        i
+                    ))
+                }
+            }
+        });
+        if let Some(e) = error {
+            return Err(e);
+        }
+    }
+
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// The serialized position list of each length.
+fn positions_by_length(results: &mut Value) -> impl Iterator<Item = &mut Vec<Value>> {
+    results
+        .get_mut("results_by_length")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flat_map(|lengths| lengths.values_mut())
+        .filter_map(|length_result| {
+            length_result
+                .get_mut("positions")
+                .and_then(Value::as_array_mut)
+        })
+}
+
+/// Visit the `sequence` value of every variant in serialized results.
+fn for_each_variant_sequence(results: &mut Value, mut f: impl FnMut(&mut Value)) {
+    for positions in positions_by_length(results) {
+        for position in positions {
+            let Some(variants) = position
+                .pointer_mut("/analysis/variants")
+                .and_then(Value::as_array_mut)
+            else {
+                continue;
+            };
+            for variant in variants {
+                if let Some(seq) = variant.get_mut("sequence") {
+                    f(seq);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    #[test]
+    fn test_results_json_round_trip() {
+        // A repeated template: windows 4 bases apart have the same sequence
+        let template = "ACGTACGTACGTACGTACGT";
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string(), "R2".to_string()],
+                sequences: vec![template.to_string(), template.to_string()],
+            },
+            &AnalysisParams {
+                min_oligo_length: 8,
+                max_oligo_length: 8,
+                resolution: 4,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let json = results_to_json(&results).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[SEQUENCE_TABLE], serde_json::json!(["ACGTACGT"]));
+
+        // All four windows match both references identically
+        let positions = value["results_by_length"]["8"]["positions"]
+            .as_array()
+            .unwrap();
+        assert!(positions[0]["analysis"]["variants"].is_array());
+        for position in &positions[1..] {
+            assert_eq!(position["analysis"], serde_json::json!({ "same_as": 0 }));
+        }
+
+        let loaded = results_from_json(&json).unwrap();
+        let sequences = |r: &ScreeningResults| -> Vec<String> {
+            r.results_by_length[&8]
+                .positions
+                .iter()
+                .flat_map(|p| p.analysis.variants.iter().map(|v| v.sequence.clone()))
+                .collect()
+        };
+        assert_eq!(sequences(&loaded), sequences(&results));
+        assert_eq!(sequences(&loaded).len(), 4);
+
+        // Files without a table load as before
+        let plain = serde_json::to_string(&results).unwrap();
+        assert_eq!(
+            sequences(&results_from_json(&plain).unwrap()),
+            sequences(&results)
+        );
+
+        // Indices outside the table are an error, not a silent empty sequence
+        let mut broken = value.clone();
+        broken[SEQUENCE_TABLE] = serde_json::json!([]);
+        assert!(results_from_json(&broken.to_string()).is_err());
+        let mut broken = value;
+        broken["results_by_length"]["8"]["positions"][0]["analysis"] =
+            serde_json::json!({ "same_as": 2 });
+        assert!(results_from_json(&broken.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_write_results_json() {
+        let template = "TATGGTACGTCATGTTCTAG";
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string()],
+                sequences: vec![template.to_string()],
+            },
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 10,
+                resolution: 5,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let mut written = Vec::new();
+        let mut reported = 0;
+        write_results_json(&results, &mut written, |bytes| reported = bytes).unwrap();
+        assert_eq!(reported, written.len() as u64);
+        assert!(!written.contains(&b'\n'));
+
+        // Same content as the pretty form
+        let compact: Value = serde_json::from_slice(&written).unwrap();
+        let pretty: Value = serde_json::from_str(&results_to_json(&results).unwrap()).unwrap();
+        assert_eq!(compact, pretty);
+    }
+
+    #[test]
+    fn test_binary_results_round_trip() {
+        let template = "ACGTACGTACGTACGTACGT";
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string(), "R2".to_string()],
+                sequences: vec![template.to_string(), "ACGTACGAACGTACGTACGT".to_string()],
+            },
+            &AnalysisParams {
+                min_oligo_length: 8,
+                max_oligo_length: 9,
+                resolution: 2,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let mut binary = Vec::new();
+        let mut reported = 0;
+        write_results_binary(&results, &mut binary, |bytes| reported = bytes).unwrap();
+        assert_eq!(reported, binary.len() as u64);
+        assert!(binary.starts_with(&ZSTD_MAGIC));
+
+        // Both formats load to the same results
+        let json = results_to_json(&results).unwrap();
+        let from_binary = serde_json::to_value(results_from_bytes(&binary).unwrap()).unwrap();
+        let from_json = serde_json::to_value(results_from_bytes(json.as_bytes()).unwrap()).unwrap();
+        assert_eq!(from_binary, from_json);
+
+        assert!(results_from_bytes(&binary[..binary.len() / 2]).is_err());
+        assert!(is_binary_results_path(Path::new("run.OSRZ")));
+        assert!(!is_binary_results_path(Path::new("run.json")));
+    }
+}
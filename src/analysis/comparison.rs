@@ -0,0 +1,144 @@
+//! Side-by-side comparison of two jobs
+//!
+//! Lines up the windows of two jobs by oligo length and template position,
+//! so the effect of different parameters or reference sets on the same
+//! template can be read window by window.
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::types::ScreeningResults;
+
+/// Variants needed by one window in each job; None where that job has no
+/// result for the window or skipped it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowPair {
+    pub a: Option<usize>,
+    pub b: Option<usize>,
+}
+
+impl WindowPair {
+    /// Variants needed in A minus those in B, when both jobs analyzed the
+    /// window
+    pub fn delta(&self) -> Option<i64> {
+        Some(self.a? as i64 - self.b? as i64)
+    }
+}
+
+/// Windows of two jobs over the union of their lengths and positions
+#[derive(Debug, Clone)]
+pub struct JobComparison {
+    pub lengths: Vec<u32>,
+    pub positions: Vec<usize>,
+    /// The jobs screened the same template sequence; otherwise positions
+    /// are only compared by index
+    pub same_template: bool,
+    windows: HashMap<(u32, usize), WindowPair>,
+}
+
+impl JobComparison {
+    pub fn new(a: &ScreeningResults, b: &ScreeningResults) -> Self {
+        let mut windows: HashMap<(u32, usize), WindowPair> = HashMap::new();
+        let mut lengths = BTreeSet::new();
+        let mut positions = BTreeSet::new();
+        for (results, is_a) in [(a, true), (b, false)] {
+            for (&length, length_result) in &results.results_by_length {
+                lengths.insert(length);
+                for pr in &length_result.positions {
+                    positions.insert(pr.position);
+                    let needed = (!pr.analysis.skipped).then_some(pr.variants_needed);
+                    let pair = windows.entry((length, pr.position)).or_default();
+                    if is_a {
+                        pair.a = needed;
+                    } else {
+                        pair.b = needed;
+                    }
+                }
+            }
+        }
+        Self {
+            lengths: lengths.into_iter().collect(),
+            positions: positions.into_iter().collect(),
+            same_template: a.template_sequence == b.template_sequence,
+            windows,
+        }
+    }
+
+    pub fn get(&self, length: u32, position: usize) -> WindowPair {
+        self.windows
+            .get(&(length, position))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Windows analyzed by both jobs where A needs fewer, the same, and more
+    /// variants than B
+    pub fn tally(&self) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+        for delta in self.windows.values().filter_map(WindowPair::delta) {
+            match delta.signum() {
+                -1 => counts.0 += 1,
+                0 => counts.1 += 1,
+                _ => counts.2 += 1,
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    const TEMPLATE: &str = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+
+    fn screen(references: &[&str], max_oligo_length: u32) -> ScreeningResults {
+        run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: TEMPLATE.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: (0..references.len()).map(|i| format!("R{}", i)).collect(),
+                sequences: references.iter().map(|s| s.to_string()).collect(),
+            },
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length,
+                resolution: 10,
+                ..Default::default()
+            },
+            &[],
+            None,
+        )
+    }
+
+    #[test]
+    fn test_job_comparison() {
+        // B's second reference differs from the template at base 6 only
+        let mut mutated = TEMPLATE.as_bytes().to_vec();
+        mutated[5] = b'A';
+        let mutated = String::from_utf8(mutated).unwrap();
+        let a = screen(&[TEMPLATE, TEMPLATE], 10);
+        let b = screen(&[TEMPLATE, &mutated], 11);
+
+        let comparison = JobComparison::new(&a, &b);
+        assert!(comparison.same_template);
+        assert_eq!(comparison.lengths, vec![10, 11]);
+        assert_eq!(comparison.positions, vec![0, 10, 20]);
+
+        let first = comparison.get(10, 0);
+        assert_eq!((first.a, first.b), (Some(1), Some(2)));
+        assert_eq!(first.delta(), Some(-1));
+        assert_eq!(comparison.get(10, 10).delta(), Some(0));
+
+        // Length 11 only in B
+        assert_eq!(comparison.get(11, 0).a, None);
+        assert_eq!(comparison.get(11, 0).delta(), None);
+
+        assert_eq!(comparison.tally(), (1, 2, 0));
+    }
+}
@@ -0,0 +1,340 @@
+//! Default-parameter configuration files
+//!
+//! An optional site-level and user-level JSON file override the built-in
+//! analysis and viewer defaults at startup. Files may be partial: only the
+//! fields they contain replace the defaults, and the user file is applied on
+//! top of the site file.
+//!
+//! Example (`defaults.json`):
+//! ```json
+//! {
+//!   "analysis": { "min_oligo_length": 20, "pairwise": { "max_mismatches": 4 } },
+//!   "viewer": { "color_red_at": 6 }
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::export::{MetricRange, TargetBand};
+use super::types::AnalysisParams;
+
+/// Environment variable naming the site-level defaults file
+pub const SITE_CONFIG_ENV: &str = "OLIGOSCREEN_SITE_CONFIG";
+
+/// Environment variable naming the user-level defaults file
+pub const USER_CONFIG_ENV: &str = "OLIGOSCREEN_CONFIG";
+
+/// Environment variable naming the saved worklist file
+pub const WORKLIST_ENV: &str = "OLIGOSCREEN_WORKLIST";
+
+/// Environment variable naming the directory of exclusivity panel seed indexes
+pub const PANEL_CACHE_ENV: &str = "OLIGOSCREEN_PANEL_CACHE";
+
+/// Results viewer settings applied when the app starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewerDefaults {
+    pub coverage_threshold: f64,
+    pub color_green_at: usize,
+    pub color_red_at: usize,
+    pub nomatch_ok_percent: f64,
+    pub nomatch_bad_percent: f64,
+    pub identity_green_at: f64,
+    pub identity_red_at: f64,
+    pub mismatch_green_at: f64,
+    pub mismatch_red_at: f64,
+    pub coverage_range: MetricRange,
+    pub entropy_range: MetricRange,
+    pub composite_range: MetricRange,
+    pub gc_band: TargetBand,
+    pub tm_band: TargetBand,
+    pub diff_green_at: u32,
+    pub diff_red_at: u32,
+    pub diff_ignore_count: usize,
+    pub excl_filter_threshold: u32,
+    pub zoom_level: f32,
+}
+
+impl Default for ViewerDefaults {
+    fn default() -> Self {
+        Self {
+            coverage_threshold: 95.0,
+            color_green_at: 1,
+            color_red_at: 10,
+            nomatch_ok_percent: 5.0,
+            nomatch_bad_percent: 50.0,
+            identity_green_at: 100.0,
+            identity_red_at: 90.0,
+            mismatch_green_at: 0.5,
+            mismatch_red_at: 3.0,
+            coverage_range: MetricRange {
+                green_at: 100.0,
+                red_at: 80.0,
+            },
+            entropy_range: MetricRange {
+                green_at: 0.0,
+                red_at: 2.0,
+            },
+            composite_range: MetricRange {
+                green_at: 80.0,
+                red_at: 40.0,
+            },
+            gc_band: TargetBand {
+                min: 40.0,
+                max: 60.0,
+                tolerance: 15.0,
+            },
+            tm_band: TargetBand {
+                min: 55.0,
+                max: 65.0,
+                tolerance: 10.0,
+            },
+            diff_green_at: 5,
+            diff_red_at: 0,
+            diff_ignore_count: 0,
+            excl_filter_threshold: 2,
+            zoom_level: 1.0,
+        }
+    }
+}
+
+/// Startup defaults for the analysis parameters and the results viewer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefaultsConfig {
+    pub analysis: AnalysisParams,
+    pub viewer: ViewerDefaults,
+}
+
+/// Defaults after applying the config files, with the files that were used
+/// and any that failed to load.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedDefaults {
+    pub config: DefaultsConfig,
+    pub sources: Vec<PathBuf>,
+    pub errors: Vec<String>,
+}
+
+/// Candidate config file paths, site level first.
+///
+/// Site: `$OLIGOSCREEN_SITE_CONFIG`, else `/etc/oligoscreen/defaults.json`
+/// (`%PROGRAMDATA%\oligoscreen\defaults.json` on Windows).
+/// User: `$OLIGOSCREEN_CONFIG`, else `defaults.json` under
+/// `$XDG_CONFIG_HOME/oligoscreen`, `~/.config/oligoscreen`, or
+/// `%APPDATA%\oligoscreen`.
+pub fn default_config_paths() -> Vec<PathBuf> {
+    let env_path = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let mut paths = Vec::new();
+
+    let site = env_path(SITE_CONFIG_ENV).or_else(|| {
+        if cfg!(windows) {
+            env_path("PROGRAMDATA").map(|p| p.join("oligoscreen").join("defaults.json"))
+        } else {
+            Some(PathBuf::from("/etc/oligoscreen/defaults.json"))
+        }
+    });
+    paths.extend(site);
+
+    let user = env_path(USER_CONFIG_ENV)
+        .or_else(|| user_config_dir().map(|dir| dir.join("defaults.json")));
+    paths.extend(user);
+
+    paths
+}
+
+/// Per-user `oligoscreen` directory under `$XDG_CONFIG_HOME`,
+/// `~/.config`, or `%APPDATA%`.
+fn user_config_dir() -> Option<PathBuf> {
+    let env_path = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    env_path("XDG_CONFIG_HOME")
+        .or_else(|| env_path("HOME").map(|h| h.join(".config")))
+        .or_else(|| env_path("APPDATA"))
+        .map(|dir| dir.join("oligoscreen"))
+}
+
+/// File holding the queued worklist between sessions:
+/// `$OLIGOSCREEN_WORKLIST`, else `worklist.json` in the user config directory.
+pub fn worklist_state_path() -> Option<PathBuf> {
+    std::env::var_os(WORKLIST_ENV)
+        .map(PathBuf::from)
+        .or_else(|| user_config_dir().map(|dir| dir.join("worklist.json")))
+}
+
+/// Per-user `oligoscreen` cache directory under `$XDG_CACHE_HOME`,
+/// `~/.cache`, or `%LOCALAPPDATA%`.
+fn user_cache_dir() -> Option<PathBuf> {
+    let env_path = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    env_path("XDG_CACHE_HOME")
+        .or_else(|| env_path("HOME").map(|h| h.join(".cache")))
+        .or_else(|| env_path("LOCALAPPDATA"))
+        .map(|dir| dir.join("oligoscreen"))
+}
+
+/// Directory of exclusivity panel seed indexes shared by jobs and sessions:
+/// `$OLIGOSCREEN_PANEL_CACHE`, else `panels` in the user cache directory.
+pub fn panel_cache_dir() -> Option<PathBuf> {
+    std::env::var_os(PANEL_CACHE_ENV)
+        .map(PathBuf::from)
+        .or_else(|| user_cache_dir().map(|dir| dir.join("panels")))
+}
+
+/// Load defaults from the given files in order; missing files are skipped.
+pub fn load_defaults(paths: &[PathBuf]) -> LoadedDefaults {
+    let mut loaded = LoadedDefaults::default();
+    let mut merged = match serde_json::to_value(DefaultsConfig::default()) {
+        Ok(v) => v,
+        Err(_) => return loaded,
+    };
+
+    for path in paths {
+        if !path.is_file() {
+            continue;
+        }
+        match read_json(path) {
+            Ok(overrides) => {
+                let mut candidate = merged.clone();
+                merge_json(&mut candidate, overrides);
+                // Validate each file on its own so one bad file doesn't discard the others
+                match serde_json::from_value::<DefaultsConfig>(candidate.clone()) {
+                    Ok(_) => {
+                        merged = candidate;
+                        loaded.sources.push(path.clone());
+                    }
+                    Err(e) => loaded.errors.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+            Err(e) => loaded.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    if let Ok(config) = serde_json::from_value(merged) {
+        loaded.config = config;
+    }
+    loaded
+}
+
+/// `params` with the fields of a partial `AnalysisParams` JSON object
+/// replaced, as in the `analysis` section of a defaults file.
+pub fn params_with_overrides(
+    params: &AnalysisParams,
+    json: &str,
+) -> Result<AnalysisParams, String> {
+    let overrides: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut merged = serde_json::to_value(params).map_err(|e| e.to_string())?;
+    merge_json(&mut merged, overrides);
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+/// Recursively overlay `overrides` onto `base`. A nested object merges key
+/// by key when every key it sets already exists in the base object; any
+/// other value, such as an enum switched to another variant (`{"Millis": 500}`
+/// over `{"Positions": 10}`), replaces the base value.
+fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) if extends_fields(existing, &value) => {
+                        merge_json(existing, value)
+                    }
+                    _ => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Both values are objects and `overrides` only sets keys `base` already has
+fn extends_fields(base: &Value, overrides: &Value) -> bool {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            overrides.keys().all(|key| base.contains_key(key))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::{AnalysisMethod, ProgressInterval};
+
+    #[test]
+    fn test_load_defaults_layers_files() {
+        let dir = std::env::temp_dir().join(format!("oligoscreen_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let site = dir.join("site.json");
+        let user = dir.join("user.json");
+        let broken = dir.join("broken.json");
+        std::fs::write(
+            &site,
+            r#"{"analysis": {"min_oligo_length": 20, "pairwise": {"max_mismatches": 4}},
+                "viewer": {"color_red_at": 6}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &user,
+            r#"{"analysis": {"min_oligo_length": 22, "method": {"FixedAmbiguities": 2}}}"#,
+        )
+        .unwrap();
+        std::fs::write(&broken, r#"{"analysis": {"resolution": "fine"}}"#).unwrap();
+
+        let loaded = load_defaults(&[site.clone(), dir.join("missing.json"), broken, user]);
+        let params = &loaded.config.analysis;
+        assert_eq!(params.min_oligo_length, 22);
+        assert_eq!(params.max_oligo_length, 25);
+        assert_eq!(params.pairwise.max_mismatches, 4);
+        assert_eq!(params.pairwise.match_score, 2);
+        assert_eq!(params.method, AnalysisMethod::FixedAmbiguities(2));
+        assert_eq!(params.resolution, 1);
+        assert_eq!(loaded.config.viewer.color_red_at, 6);
+        assert_eq!(loaded.sources.len(), 2);
+        assert_eq!(loaded.errors.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_params_with_overrides() {
+        let base = AnalysisParams::default();
+        let params = params_with_overrides(
+            &base,
+            r#"{"resolution": 5, "pairwise": {"max_mismatches": 3}}"#,
+        )
+        .unwrap();
+        assert_eq!(params.resolution, 5);
+        assert_eq!(params.pairwise.max_mismatches, 3);
+        assert_eq!(params.pairwise.match_score, base.pairwise.match_score);
+        assert_eq!(params.min_oligo_length, base.min_oligo_length);
+        assert!(params_with_overrides(&base, r#"{"resolution": "fine"}"#).is_err());
+    }
+
+    #[test]
+    fn test_overrides_switch_enum_variant() {
+        let base = AnalysisParams {
+            method: AnalysisMethod::FixedAmbiguities(2),
+            ..Default::default()
+        };
+        let params = params_with_overrides(
+            &base,
+            r#"{"progress_interval": {"Millis": 500}, "method": {"Incremental": [90, null]}}"#,
+        )
+        .unwrap();
+        assert_eq!(params.progress_interval, ProgressInterval::Millis(500));
+        assert_eq!(params.method, AnalysisMethod::Incremental(90, None));
+
+        // The same variant still takes its new value
+        let params =
+            params_with_overrides(&base, r#"{"method": {"FixedAmbiguities": 1}}"#).unwrap();
+        assert_eq!(params.method, AnalysisMethod::FixedAmbiguities(1));
+    }
+}
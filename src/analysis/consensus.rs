@@ -0,0 +1,365 @@
+//! Consensus of the reference set on the template
+//!
+//! When no single strain represents the panel, a consensus of the references
+//! makes a better template. Each reference is anchored on the template by
+//! shared k-mers (as for trimming), its template region aligned against the
+//! template with a banded aligner, and its aligned bases tallied per template
+//! position. Bases a reference inserts relative to the template are not
+//! counted, so the consensus stays in template coordinates apart from the
+//! positions most references delete. The same tallies give the per-base
+//! conservation recorded with each screen.
+
+use bio::alignment::pairwise::banded;
+use bio::alignment::{Alignment, AlignmentOperation};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::fasta::ReferenceData;
+use super::iupac::{IUPAC_FROM_MASK, iupac_to_mask};
+use super::pairwise::IupacMatch;
+use super::trim::{map_to_template, template_kmers};
+
+/// k-mer length and diagonal width of the banded alignment
+const BAND_K: usize = 8;
+const BAND_W: usize = 20;
+
+/// Reference bases kept beyond the anchored region, so indels near the
+/// template ends still align
+const REGION_PAD: usize = 100;
+
+/// How each position's bases become a consensus base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConsensusMode {
+    /// The most frequent base
+    #[default]
+    Majority,
+    /// The IUPAC code of every base reaching the minimum share
+    Iupac,
+}
+
+impl ConsensusMode {
+    pub const ALL: [Self; 2] = [Self::Majority, Self::Iupac];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Majority => "Majority",
+            Self::Iupac => "IUPAC",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsensusParams {
+    pub mode: ConsensusMode,
+    /// IUPAC mode: include every base found in at least this fraction of the
+    /// references covering a position
+    pub min_fraction: f64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self {
+            mode: ConsensusMode::Majority,
+            min_fraction: 0.1,
+        }
+    }
+}
+
+/// Consensus sequence and how it was reached
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsensusResult {
+    pub sequence: String,
+    /// References aligned to the template
+    pub aligned: usize,
+    /// References sharing no anchor k-mer with the template, left out
+    pub unmapped: usize,
+    /// Positions whose consensus base differs from the template's
+    pub changed: usize,
+    /// Positions given an ambiguity code
+    pub ambiguous: usize,
+    /// Template positions most covering references delete, left out
+    pub deleted: usize,
+    /// Template positions no reference covers, kept from the template
+    pub uncovered: usize,
+}
+
+/// Conservation of the references at one template position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BaseConservation {
+    /// References covering the position with a base or a deletion
+    pub coverage: u32,
+    /// Share of the covering references with a base the template's includes
+    pub identity: f32,
+    /// Shannon entropy in bits of A, C, G, T and deletion
+    pub entropy: f32,
+}
+
+/// Largest `BaseConservation::entropy`: all five states equally frequent
+pub const MAX_BASE_ENTROPY: f32 = 2.321_928;
+
+/// Tallies of one template position: A, C, G, T, then deletions
+type Column = [u32; 5];
+
+/// Consensus of `references` in the coordinates of `template`. Positions no
+/// reference covers keep the template's base; ties go to the template's
+/// base, then to the first in ACGT order. Ambiguous reference bases are not
+/// counted.
+pub fn reference_consensus(
+    template: &str,
+    references: &ReferenceData,
+    params: &ConsensusParams,
+) -> ConsensusResult {
+    let template = template.as_bytes();
+    let weights = vec![1; references.len()];
+    let (columns, aligned) = tally_references(template, &references.sequences, &weights);
+    let aligned = aligned as usize;
+
+    let mut result = ConsensusResult {
+        aligned,
+        unmapped: references.len() - aligned,
+        ..Default::default()
+    };
+    for (&t, column) in template.iter().zip(&columns) {
+        let bases = &column[..4];
+        let covered: u32 = bases.iter().sum();
+        if covered + column[4] == 0 {
+            result.uncovered += 1;
+            result.sequence.push(t as char);
+            continue;
+        }
+        if column[4] > covered {
+            result.deleted += 1;
+            continue;
+        }
+
+        let key = |i: usize| (bases[i], b"ACGT"[i] == t);
+        let best = (1..4).fold(0, |best, i| if key(i) > key(best) { i } else { best });
+        // Bit i is the i-th base of ACGT, as in the IUPAC masks
+        let mut mask = 1u8 << best;
+        if params.mode == ConsensusMode::Iupac {
+            for (i, &count) in bases.iter().enumerate() {
+                if count > 0 && count as f64 >= params.min_fraction * covered as f64 {
+                    mask |= 1 << i;
+                }
+            }
+        }
+        let code = IUPAC_FROM_MASK[mask as usize];
+        if mask.count_ones() > 1 {
+            result.ambiguous += 1;
+        }
+        if code != t {
+            result.changed += 1;
+        }
+        result.sequence.push(code as char);
+    }
+    result
+}
+
+/// Conservation of each template position across `references`, each
+/// counted `weights[i]` times (the copies of a distinct sequence). Positions
+/// no reference covers have zero coverage.
+pub fn template_conservation(
+    template: &str,
+    references: &[Vec<u8>],
+    weights: &[u32],
+) -> Vec<BaseConservation> {
+    let template = template.as_bytes();
+    let (columns, _) = tally_references(template, references, weights);
+    template
+        .iter()
+        .zip(&columns)
+        .map(|(&t, column)| {
+            let coverage: u32 = column.iter().sum();
+            if coverage == 0 {
+                return BaseConservation::default();
+            }
+            let mask = iupac_to_mask(t);
+            let identical: u32 = (0..4)
+                .filter(|&i| mask & (1 << i) != 0)
+                .map(|i| column[i])
+                .sum();
+            let entropy = column
+                .iter()
+                .filter(|&&n| n > 0)
+                .map(|&n| {
+                    let p = n as f32 / coverage as f32;
+                    -p * p.log2()
+                })
+                .sum();
+            BaseConservation {
+                coverage,
+                identity: identical as f32 / coverage as f32,
+                entropy,
+            }
+        })
+        .collect()
+}
+
+/// Weighted tallies of every template position over the references that
+/// anchor on the template, and the total weight of those references
+fn tally_references<R: AsRef<[u8]> + Sync>(
+    template: &[u8],
+    references: &[R],
+    weights: &[u32],
+) -> (Vec<Column>, u32) {
+    let kmers = template_kmers(template);
+    let scoring = IupacMatch {
+        match_score: 1,
+        mismatch_score: -1,
+        compatible: true,
+    };
+    let empty = || (vec![[0u32; 5]; template.len()], 0);
+    references
+        .par_iter()
+        .zip(weights)
+        .fold(empty, |(mut columns, aligned), (seq, &weight)| {
+            let seq = seq.as_ref();
+            let Some((start, end)) = map_to_template(&kmers, template.len(), seq) else {
+                return (columns, aligned);
+            };
+            let region = &seq[start.saturating_sub(REGION_PAD)..(end + REGION_PAD).min(seq.len())];
+            let mut aligner = banded::Aligner::new(-5, -1, scoring, BAND_K, BAND_W);
+            let alignment = aligner.semiglobal(template, region);
+            tally(&mut columns, region, &alignment, weight);
+            (columns, aligned + weight)
+        })
+        .reduce(empty, |(mut a, a_aligned), (b, b_aligned)| {
+            for (x, y) in a.iter_mut().zip(&b) {
+                for (x, y) in x.iter_mut().zip(y) {
+                    *x += y;
+                }
+            }
+            (a, a_aligned + b_aligned)
+        })
+}
+
+/// Add one reference's aligned bases to the tallies, `weight` times.
+/// Template positions before its first and after its last aligned base are
+/// not covered by it, so partial references do not count as deletions at
+/// the ends.
+fn tally(columns: &mut [Column], region: &[u8], alignment: &Alignment, weight: u32) {
+    let mut aligned: Vec<(usize, Option<u8>)> = Vec::new();
+    let (mut x, mut y) = (alignment.xstart, alignment.ystart);
+    for op in &alignment.operations {
+        match op {
+            AlignmentOperation::Match | AlignmentOperation::Subst => {
+                aligned.push((x, Some(region[y])));
+                x += 1;
+                y += 1;
+            }
+            AlignmentOperation::Ins => {
+                aligned.push((x, None));
+                x += 1;
+            }
+            AlignmentOperation::Del => y += 1,
+            AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => {}
+        }
+    }
+    let first = aligned.iter().position(|(_, base)| base.is_some());
+    let last = aligned.iter().rposition(|(_, base)| base.is_some());
+    let (Some(first), Some(last)) = (first, last) else {
+        return;
+    };
+    for &(pos, base) in &aligned[first..=last] {
+        match base {
+            None => columns[pos][4] += weight,
+            Some(base) => {
+                if let Some(i) = b"ACGT".iter().position(|&b| b == base) {
+                    columns[pos][i] += weight;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::random_seq;
+
+    fn substitute(seq: &str, pos: usize, base: char) -> String {
+        let mut seq = seq.to_string();
+        seq.replace_range(pos..pos + 1, &base.to_string());
+        seq
+    }
+
+    #[test]
+    fn test_reference_consensus() {
+        let template = random_seq(400, 1);
+        let other = if &template[100..101] == "A" { 'C' } else { 'A' };
+        let variant = substitute(&template, 100, other);
+        // Four of six aligned references carry the variant; two delete bases 300..303
+        let mut deleted = variant.clone();
+        deleted.replace_range(300..303, "");
+        let references = ReferenceData {
+            names: (0..7).map(|i| format!("ref{}", i)).collect(),
+            sequences: vec![
+                format!("{}{}{}", random_seq(500, 2), variant, random_seq(500, 3)),
+                deleted.clone(),
+                deleted,
+                template.clone(),
+                template.clone(),
+                // Covers only the first half, so it votes on nothing after it
+                variant[..200].to_string(),
+                random_seq(400, 4),
+            ],
+        };
+
+        let majority = reference_consensus(&template, &references, &ConsensusParams::default());
+        assert_eq!(majority.aligned, 6);
+        assert_eq!(majority.unmapped, 1);
+        assert_eq!(majority.deleted, 0);
+        assert_eq!(majority.uncovered, 0);
+        assert_eq!(majority.changed, 1);
+        assert_eq!(majority.sequence, variant);
+
+        // 2-fold code where the variant and the template base both reach the share
+        let params = ConsensusParams {
+            mode: ConsensusMode::Iupac,
+            min_fraction: 0.3,
+        };
+        let iupac = reference_consensus(&template, &references, &params);
+        assert_eq!(iupac.ambiguous, 1);
+        let code = iupac.sequence.as_bytes()[100];
+        assert!(!b"ACGT".contains(&code));
+        assert_eq!(iupac.sequence.len(), template.len());
+
+        // A deletion most references share leaves the consensus
+        let mut short = template.clone();
+        short.replace_range(300..303, "");
+        let references = ReferenceData {
+            names: vec!["a".to_string(), "b".to_string()],
+            sequences: vec![short.clone(), short.clone()],
+        };
+        let result = reference_consensus(&template, &references, &ConsensusParams::default());
+        assert_eq!(result.deleted, 3);
+        assert_eq!(result.sequence, short);
+    }
+
+    #[test]
+    fn test_template_conservation() {
+        let template = random_seq(300, 5);
+        let other = if &template[100..101] == "A" { 'C' } else { 'A' };
+        // Two copies of the template and one variant, plus an unrelated sequence
+        let references = vec![
+            template.as_bytes().to_vec(),
+            substitute(&template, 100, other).into_bytes(),
+            random_seq(300, 6).into_bytes(),
+        ];
+        let conservation = template_conservation(&template, &references, &[2, 1, 1]);
+        assert_eq!(conservation.len(), template.len());
+        assert_eq!(conservation[50].coverage, 3);
+        assert_eq!(conservation[50].identity, 1.0);
+        assert_eq!(conservation[50].entropy, 0.0);
+
+        let varied = conservation[100];
+        assert_eq!(varied.coverage, 3);
+        assert!((varied.identity - 2.0 / 3.0).abs() < 1e-6);
+        // Two states at 2/3 and 1/3
+        assert!((varied.entropy - 0.918_296).abs() < 1e-4);
+        assert!(varied.entropy < MAX_BASE_ENTROPY);
+
+        let uncovered = template_conservation(&template, &[], &[]);
+        assert!(uncovered.iter().all(|c| c.coverage == 0));
+    }
+}
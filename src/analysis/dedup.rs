@@ -0,0 +1,126 @@
+//! Collapsing identical reference sequences
+//!
+//! Large downloads hold many copies of the same genome. Each distinct
+//! sequence is aligned once per window and its match handed to every copy,
+//! so duplicates cost no alignment time yet still count toward coverage
+//! under their own names.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::fasta::ReferenceData;
+use super::pairwise::PairwiseMatch;
+
+/// Distinct sequences of a reference set and the references carrying each
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReferenceGroups {
+    /// Index of the first reference with each distinct sequence, in order
+    pub representatives: Vec<usize>,
+    /// Indices of the references with each distinct sequence, starting with
+    /// its representative
+    pub members: Vec<Vec<usize>>,
+}
+
+/// Outcome of collapsing a reference set, for display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupSummary {
+    pub references: usize,
+    /// Distinct sequences, each aligned once
+    pub distinct: usize,
+    /// Copies of the most repeated sequence
+    pub largest_group: usize,
+}
+
+impl DedupSummary {
+    /// References whose alignments are shared with an identical one
+    pub fn duplicates(&self) -> usize {
+        self.references - self.distinct
+    }
+}
+
+impl ReferenceGroups {
+    /// Group references with identical sequences.
+    pub fn new(references: &ReferenceData) -> Self {
+        let mut groups = Self::default();
+        let mut group_of: HashMap<&str, usize> = HashMap::new();
+        for (i, seq) in references.sequences.iter().enumerate() {
+            match group_of.get(seq.as_str()) {
+                Some(&group) => groups.members[group].push(i),
+                None => {
+                    group_of.insert(seq, groups.representatives.len());
+                    groups.representatives.push(i);
+                    groups.members.push(vec![i]);
+                }
+            }
+        }
+        groups
+    }
+
+    /// Number of references, copies included
+    pub fn reference_count(&self) -> usize {
+        self.members.iter().map(Vec::len).sum()
+    }
+
+    pub fn summary(&self) -> DedupSummary {
+        DedupSummary {
+            references: self.reference_count(),
+            distinct: self.representatives.len(),
+            largest_group: self.members.iter().map(Vec::len).max().unwrap_or(0),
+        }
+    }
+
+    /// Matches of the distinct sequences (indexed by group) as matches of
+    /// every reference carrying them, in reference order.
+    pub fn expand_matches(&self, matches: Vec<PairwiseMatch>) -> Vec<PairwiseMatch> {
+        let mut expanded: Vec<PairwiseMatch> = Vec::with_capacity(matches.len());
+        for m in matches {
+            for &reference_index in &self.members[m.reference_index] {
+                expanded.push(PairwiseMatch {
+                    reference_index,
+                    ..m.clone()
+                });
+            }
+        }
+        expanded.sort_by_key(|m| m.reference_index);
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pairwise::exact_match;
+    use crate::analysis::types::PairwiseParams;
+
+    #[test]
+    fn test_reference_groups() {
+        let references = ReferenceData {
+            names: ["a", "b", "c", "d", "e"].map(String::from).to_vec(),
+            sequences: ["ACGT", "TTTT", "ACGT", "GGGG", "ACGT"]
+                .map(String::from)
+                .to_vec(),
+        };
+        let groups = ReferenceGroups::new(&references);
+        assert_eq!(groups.representatives, vec![0, 1, 3]);
+        assert_eq!(groups.members, vec![vec![0, 2, 4], vec![1], vec![3]]);
+        let summary = groups.summary();
+        assert_eq!((summary.references, summary.distinct), (5, 3));
+        assert_eq!((summary.largest_group, summary.duplicates()), (3, 2));
+
+        // Matches of groups 0 and 2 go to their members, in reference order
+        let params = PairwiseParams::default();
+        let matches = [0, 2]
+            .map(|group| PairwiseMatch {
+                reference_index: group,
+                ..exact_match(b"ACGT", &params)
+            })
+            .to_vec();
+        let indices: Vec<usize> = groups
+            .expand_matches(matches)
+            .iter()
+            .map(|m| m.reference_index)
+            .collect();
+        assert_eq!(indices, vec![0, 2, 3, 4]);
+    }
+}
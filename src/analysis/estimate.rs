@@ -0,0 +1,414 @@
+//! Pre-run runtime estimation
+//!
+//! Alignment dominates screening time and scales with the size of the
+//! Smith-Waterman DP matrix (oligo length × sequence length). The estimate
+//! counts the DP cells a job will fill and multiplies by a per-cell cost
+//! measured with a short calibration run on this machine.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::fasta::ReferenceData;
+use super::pairwise::{collect_matches_with_aligner, create_aligner};
+use super::types::{AnalysisParams, PairwiseParams, PositionResult, ProgressUpdate, QuickScan};
+
+/// Time budget for the calibration alignments
+const CALIBRATION_BUDGET: Duration = Duration::from_millis(20);
+
+/// Calibration sequences are truncated so one alignment stays well under the budget
+const MAX_CALIBRATION_LEN: usize = 10_000;
+
+/// Estimated workload of one screening job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RuntimeEstimate {
+    /// Number of (position, length) windows
+    pub windows: usize,
+    /// Pairwise alignments (windows × (references + exclusivity sequences))
+    pub alignments: u64,
+    /// Single-threaded runtime estimate in seconds
+    pub cpu_seconds: f64,
+}
+
+impl RuntimeEstimate {
+    /// Wall-clock estimate when the windows are spread over `threads` threads.
+    pub fn wall_seconds(&self, threads: usize) -> f64 {
+        self.cpu_seconds / threads.max(1) as f64
+    }
+}
+
+/// Number of positions screened for one oligo length.
+pub fn positions_for_length(template_len: usize, length: usize, resolution: usize) -> usize {
+    if length == 0 || length > template_len {
+        return 0;
+    }
+    (template_len - length) / resolution.max(1) + 1
+}
+
+/// Smallest step size that keeps the number of positions for `oligo_length`
+/// at or below `target_positions`.
+pub fn auto_resolution(template_len: usize, oligo_length: u32, target_positions: usize) -> u32 {
+    let positions = positions_for_length(template_len, oligo_length as usize, 1);
+    positions.div_ceil(target_positions.max(1)).max(1) as u32
+}
+
+impl AnalysisParams {
+    /// The screened stretch of a template of the given length, as 0-based
+    /// start and exclusive end: the region bounds clamped to the template.
+    pub fn screened_region(&self, template_len: usize) -> (usize, usize) {
+        let end = self
+            .region_end
+            .map_or(template_len, |e| e.min(template_len));
+        let start = self.region_start.unwrap_or(0).min(end);
+        (start, end)
+    }
+
+    /// Start positions of the windows screened for one oligo length: every
+    /// `resolution`-th position whose window lies inside the screened region.
+    pub fn window_positions(&self, template_len: usize, length: usize) -> Vec<usize> {
+        let (start, end) = self.screened_region(template_len);
+        if length == 0 || start + length > end {
+            return Vec::new();
+        }
+        (start..=end - length)
+            .step_by(self.resolution.max(1) as usize)
+            .collect()
+    }
+
+    /// Positions of the second, step-1 pass of a refined screen: those in the
+    /// screened region within one step of a coarse window needing at most
+    /// `refine_max_variants` variants, and not screened already. Empty unless
+    /// refinement is on and the coarse step is above 1; exclusivity-only
+    /// screens count no variants, so they are never refined.
+    pub fn refinement_positions(
+        &self,
+        template_len: usize,
+        length: usize,
+        coarse: &[PositionResult],
+    ) -> Vec<usize> {
+        let step = self.resolution as usize;
+        let Some(max_variants) = self.refine_max_variants else {
+            return Vec::new();
+        };
+        if step <= 1 || self.exclusivity_only {
+            return Vec::new();
+        }
+        let (start, end) = self.screened_region(template_len);
+        if length == 0 || start + length > end {
+            return Vec::new();
+        }
+
+        let last = end - length;
+        let mut positions = Vec::new();
+        for result in coarse
+            .iter()
+            .filter(|r| !r.analysis.skipped && r.variants_needed <= max_variants)
+        {
+            let from = result.position.saturating_sub(step - 1).max(start);
+            let to = (result.position + step - 1).min(last);
+            positions.extend(from..=to);
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        let screened: HashSet<usize> = coarse.iter().map(|r| r.position).collect();
+        positions.retain(|p| !screened.contains(p));
+        positions
+    }
+
+    /// Apply `auto_resolution_target` (if set) for a template of the given
+    /// length, counting the screened region only.
+    pub fn resolve_auto_resolution(&mut self, template_len: usize) {
+        if let Some(target) = self.auto_resolution_target {
+            let (start, end) = self.screened_region(template_len);
+            self.resolution = auto_resolution(end - start, self.min_oligo_length, target);
+        }
+    }
+}
+
+impl QuickScan {
+    /// Turn `params` into a quick scan of a template of the given length: the
+    /// middle oligo length of the range only, at a resolution giving about
+    /// `target_positions` windows.
+    pub fn apply(&self, params: &mut AnalysisParams, template_len: usize) {
+        let span = params
+            .max_oligo_length
+            .saturating_sub(params.min_oligo_length);
+        let length = params.min_oligo_length + span / 2;
+        params.min_oligo_length = length;
+        params.max_oligo_length = length;
+        params.auto_resolution_target = None;
+        let (start, end) = params.screened_region(template_len);
+        params.resolution = auto_resolution(end - start, length, self.target_positions);
+        params.quick_scan = Some(*self);
+    }
+}
+
+/// At most `max` records spread evenly through `data` (every k-th record,
+/// in order), so a sample still spans a panel sorted by clade or date.
+pub fn subsample_evenly(data: &ReferenceData, max: usize) -> ReferenceData {
+    let total = data.len();
+    if total <= max {
+        return data.clone();
+    }
+    let mut sample = ReferenceData::new();
+    for i in 0..max {
+        let index = i * total / max;
+        sample.names.push(data.names[index].clone());
+        sample.sequences.push(data.sequences[index].clone());
+    }
+    sample
+}
+
+/// Measure the alignment cost (nanoseconds per DP cell) by repeatedly aligning
+/// the first oligo of `template` against `sample` for a short time budget.
+pub fn calibrate_ns_per_cell(
+    template: &[u8],
+    sample: &[u8],
+    oligo_length: usize,
+    params: &PairwiseParams,
+) -> f64 {
+    let oligo_length = oligo_length.min(template.len());
+    let sample = &sample[..sample.len().min(MAX_CALIBRATION_LEN)];
+    if oligo_length == 0 || sample.is_empty() {
+        return 0.0;
+    }
+    let oligo = &template[..oligo_length];
+    let references = [sample.to_vec()];
+    let mut aligner = create_aligner(oligo_length, sample.len(), params);
+
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while iterations == 0 || start.elapsed() < CALIBRATION_BUDGET {
+        let _ = collect_matches_with_aligner(&mut aligner, oligo, &references, params);
+        iterations += 1;
+    }
+
+    let cells = iterations as f64 * oligo_length as f64 * sample.len() as f64;
+    start.elapsed().as_nanos() as f64 / cells
+}
+
+/// Estimate the workload of a job from its inputs and a calibrated per-cell cost.
+pub fn estimate_runtime(
+    template_len: usize,
+    sequence_lengths: &[usize],
+    params: &AnalysisParams,
+    ns_per_cell: f64,
+) -> RuntimeEstimate {
+    let total_seq_len: usize = sequence_lengths.iter().sum();
+    let (start, end) = params.screened_region(template_len);
+    let mut windows = 0usize;
+    let mut cells = 0.0f64;
+
+    for length in params.min_oligo_length..=params.max_oligo_length {
+        let length = length as usize;
+        let positions = positions_for_length(end - start, length, params.resolution as usize);
+        windows += positions;
+        cells += positions as f64 * length as f64 * total_seq_len as f64;
+    }
+
+    RuntimeEstimate {
+        windows,
+        alignments: windows as u64 * sequence_lengths.len() as u64,
+        cpu_seconds: cells * ns_per_cell / 1e9,
+    }
+}
+
+impl ProgressUpdate {
+    /// Fraction of the job's windows completed
+    pub fn fraction(&self) -> f32 {
+        if self.total_windows == 0 {
+            return 0.0;
+        }
+        (self.windows_completed as f32 / self.total_windows as f32).min(1.0)
+    }
+
+    /// Seconds left at the rate observed since the job started `elapsed`
+    /// seconds ago; None until a window has completed.
+    pub fn eta_seconds(&self, elapsed: f64) -> Option<f64> {
+        if self.windows_completed == 0 {
+            return None;
+        }
+        let remaining = self.total_windows.saturating_sub(self.windows_completed);
+        Some(elapsed / self.windows_completed as f64 * remaining as f64)
+    }
+}
+
+/// Human-readable duration ("45 s", "12 min", "3.5 h").
+pub fn format_duration(seconds: f64) -> String {
+    if seconds < 1.0 {
+        "< 1 s".to_string()
+    } else if seconds < 90.0 {
+        format!("{:.0} s", seconds)
+    } else if seconds < 90.0 * 60.0 {
+        format!("{:.0} min", seconds / 60.0)
+    } else if seconds < 48.0 * 3600.0 {
+        format!("{:.1} h", seconds / 3600.0)
+    } else {
+        format!("{:.1} days", seconds / 86400.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::WindowAnalysisResult;
+
+    #[test]
+    fn test_estimate_counts() {
+        assert_eq!(positions_for_length(100, 20, 1), 81);
+        assert_eq!(positions_for_length(100, 20, 10), 9);
+        assert_eq!(positions_for_length(10, 20, 1), 0);
+
+        let params = AnalysisParams {
+            min_oligo_length: 20,
+            max_oligo_length: 21,
+            resolution: 1,
+            ..Default::default()
+        };
+        let estimate = estimate_runtime(100, &[100, 200], &params, 1.0);
+        assert_eq!(estimate.windows, 81 + 80);
+        assert_eq!(estimate.alignments, 161 * 2);
+        let cells = (81.0 * 20.0 + 80.0 * 21.0) * 300.0;
+        assert!((estimate.cpu_seconds - cells / 1e9).abs() < 1e-12);
+        assert!((estimate.wall_seconds(4) - estimate.cpu_seconds / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_auto_resolution() {
+        assert_eq!(auto_resolution(1_000, 20, 2_000), 1);
+        // 1,000,000 - 20 + 1 positions at step 1 -> step 500 gives <= 2000
+        assert_eq!(auto_resolution(1_000_000, 20, 2_000), 500);
+
+        let mut params = AnalysisParams {
+            auto_resolution_target: Some(100),
+            ..Default::default()
+        };
+        params.resolve_auto_resolution(10_017);
+        assert_eq!(params.resolution, 100);
+        assert_eq!(positions_for_length(10_017, 18, 100), 100);
+    }
+
+    #[test]
+    fn test_screened_region() {
+        let mut params = AnalysisParams {
+            min_oligo_length: 20,
+            max_oligo_length: 20,
+            resolution: 10,
+            region_start: Some(100),
+            region_end: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(params.screened_region(1_000), (100, 150));
+        assert_eq!(params.window_positions(1_000, 20), vec![100, 110, 120, 130]);
+        assert_eq!(params.window_positions(120, 20), vec![100]);
+        assert!(params.window_positions(110, 20).is_empty());
+
+        // Runtime and auto resolution count the region only
+        params.resolution = 1;
+        let estimate = estimate_runtime(1_000, &[100], &params, 1.0);
+        assert_eq!(estimate.windows, 31);
+        params.auto_resolution_target = Some(10);
+        params.resolve_auto_resolution(1_000);
+        assert_eq!(params.resolution, 4);
+
+        params.region_start = None;
+        params.region_end = None;
+        params.resolution = 1;
+        assert_eq!(params.window_positions(30, 20).len(), 11);
+    }
+
+    #[test]
+    fn test_refinement_positions() {
+        let mut params = AnalysisParams {
+            resolution: 10,
+            region_start: Some(5),
+            refine_max_variants: Some(2),
+            ..Default::default()
+        };
+        let coarse: Vec<PositionResult> = [(5, 1), (15, 5), (25, 2), (35, 0)]
+            .into_iter()
+            .map(|(position, variants_needed)| PositionResult {
+                position,
+                variants_needed,
+                analysis: WindowAnalysisResult::default(),
+                exclusivity: None,
+                group_exclusivity: Vec::new(),
+                tm: None,
+                gc_percent: None,
+            })
+            .collect();
+        // Around 5 (clamped to the region start), 25, and 35 (clamped to the
+        // last window start, 60 - 20), without the coarse positions themselves
+        let fine = params.refinement_positions(60, 20, &coarse);
+        let expected: Vec<usize> = (6..=14)
+            .chain(16..=24)
+            .chain(26..=34)
+            .chain(36..=40)
+            .collect();
+        assert_eq!(fine, expected);
+
+        params.resolution = 1;
+        assert!(params.refinement_positions(60, 20, &coarse).is_empty());
+        params.resolution = 10;
+        params.refine_max_variants = None;
+        assert!(params.refinement_positions(60, 20, &coarse).is_empty());
+    }
+
+    #[test]
+    fn test_progress_eta() {
+        let mut progress = ProgressUpdate {
+            current_length: 20,
+            current_position: 0,
+            total_positions: 100,
+            lengths_completed: 0,
+            total_lengths: 2,
+            windows_completed: 0,
+            total_windows: 200,
+            message: String::new(),
+        };
+        assert_eq!(progress.fraction(), 0.0);
+        assert_eq!(progress.eta_seconds(5.0), None);
+
+        // 50 windows in 10 s leaves 150 windows, 30 s
+        progress.windows_completed = 50;
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(progress.eta_seconds(10.0), Some(30.0));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.2), "< 1 s");
+        assert_eq!(format_duration(45.0), "45 s");
+        assert_eq!(format_duration(600.0), "10 min");
+        assert_eq!(format_duration(3.0 * 3600.0), "3.0 h");
+    }
+
+    #[test]
+    fn test_quick_scan() {
+        let mut params = AnalysisParams {
+            min_oligo_length: 18,
+            max_oligo_length: 25,
+            auto_resolution_target: Some(5_000),
+            ..Default::default()
+        };
+        let quick = QuickScan {
+            target_positions: 100,
+            max_references: 3,
+        };
+        quick.apply(&mut params, 10_020);
+        assert_eq!((params.min_oligo_length, params.max_oligo_length), (21, 21));
+        assert_eq!(params.auto_resolution_target, None);
+        assert_eq!(params.resolution, 100);
+        assert_eq!(params.quick_scan, Some(quick));
+
+        let data = ReferenceData {
+            names: (0..10).map(|i| format!("r{}", i)).collect(),
+            sequences: (0..10).map(|i| "A".repeat(i + 1)).collect(),
+        };
+        let sample = subsample_evenly(&data, quick.max_references);
+        assert_eq!(sample.names, vec!["r0", "r3", "r6"]);
+        assert_eq!(sample.sequences[1], "AAAA");
+        assert_eq!(subsample_evenly(&data, 20).len(), 10);
+    }
+}
@@ -0,0 +1,1014 @@
+//! Tabular exports of screening results
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::filter::{effective_min_mismatches, ExclusivityFilter};
+use super::taxon::{specificity_summary, taxon_mismatch_list};
+use super::thermo::{TmConditions, gc_percent, oligo_tm};
+use super::types::{ExclusivityResult, NominationStatus, PositionResult, ScreeningResults};
+
+/// Color scale of a metric: fully good at `green_at`, fully bad at `red_at`.
+/// Either end may be the larger value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricRange {
+    pub green_at: f64,
+    pub red_at: f64,
+}
+
+impl MetricRange {
+    /// 0 at or beyond `green_at`, 1 at or beyond `red_at`, linear between.
+    /// Equal ends make a step: values above them are bad.
+    pub fn badness(&self, value: f64) -> f64 {
+        if self.green_at == self.red_at {
+            return if value > self.green_at { 1.0 } else { 0.0 };
+        }
+        ((value - self.green_at) / (self.red_at - self.green_at)).clamp(0.0, 1.0)
+    }
+}
+
+/// Color scale of a metric with an optimal band: good between `min` and
+/// `max`, fully bad `tolerance` beyond either edge.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TargetBand {
+    pub min: f64,
+    pub max: f64,
+    pub tolerance: f64,
+}
+
+impl TargetBand {
+    pub fn badness(&self, value: f64) -> f64 {
+        let distance = (self.min - value).max(value - self.max).max(0.0);
+        if distance == 0.0 {
+            0.0
+        } else if self.tolerance <= 0.0 {
+            1.0
+        } else {
+            (distance / self.tolerance).min(1.0)
+        }
+    }
+}
+
+/// Scales combined by the composite score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CompositeScales {
+    pub variants_needed: MetricRange,
+    /// No-match percentage of the references
+    pub no_match: MetricRange,
+    /// Effective exclusivity min mismatches (ignored without exclusivity data)
+    pub exclusivity: MetricRange,
+    pub ignore_count: usize,
+}
+
+/// Per-window value plotted on the heatmap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowMetric {
+    VariantsNeeded,
+    /// Coverage reached by the variants needed for the threshold
+    Coverage,
+    NoMatchPercent,
+    MeanIdentity,
+    MinIdentity,
+    MeanMismatches,
+    /// GC content of the template oligo
+    GcPercent,
+    /// Nearest-neighbor melting temperature of the template oligo (the basic
+    /// estimate for results saved without it)
+    MeltingTemp,
+    /// Shannon entropy (bits) of the variant frequencies among matched references
+    Entropy,
+    /// Effective minimum exclusivity mismatches after ignoring the best N sequences
+    ExclusivityMinMismatches { ignore_count: usize },
+    /// 0-100 score (higher is better): 100 × (1 − mean badness) over variants
+    /// needed, no-match percentage, and exclusivity when available
+    Composite(CompositeScales),
+}
+
+impl WindowMetric {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::VariantsNeeded => "variants_needed",
+            Self::Coverage => "coverage",
+            Self::NoMatchPercent => "no_match_percent",
+            Self::MeanIdentity => "mean_identity",
+            Self::MinIdentity => "min_identity",
+            Self::MeanMismatches => "mean_mismatches",
+            Self::GcPercent => "gc_percent",
+            Self::MeltingTemp => "tm",
+            Self::Entropy => "entropy",
+            Self::ExclusivityMinMismatches { .. } => "exclusivity_min_mismatches",
+            Self::Composite(_) => "composite_score",
+        }
+    }
+
+    /// Metric value for a window whose template oligo is `oligo`, with Tm
+    /// under the job's `conditions`. None for skipped windows or missing data;
+    /// infinity when every exclusivity sequence is a no-match.
+    pub fn value(
+        &self,
+        pr: &PositionResult,
+        oligo: &str,
+        conditions: &TmConditions,
+    ) -> Option<f64> {
+        if pr.analysis.skipped {
+            return None;
+        }
+        match self {
+            Self::VariantsNeeded => Some(pr.variants_needed as f64),
+            Self::Coverage => Some(pr.analysis.coverage_at_threshold),
+            Self::NoMatchPercent => Some(no_match_percent(pr)),
+            Self::MeanIdentity => pr.analysis.identity_stats.map(|s| s.mean),
+            Self::MinIdentity => pr.analysis.identity_stats.map(|s| s.min),
+            Self::MeanMismatches => pr.analysis.mismatch_stats.map(|s| s.mean),
+            Self::GcPercent => Some(pr.gc_percent.unwrap_or_else(|| gc_percent(oligo))),
+            Self::MeltingTemp => Some(pr.tm.unwrap_or_else(|| oligo_tm(oligo, conditions))),
+            Self::Entropy => Some(variant_entropy(pr)),
+            Self::ExclusivityMinMismatches { ignore_count } => {
+                let excl = pr.exclusivity.as_ref()?;
+                Some(
+                    effective_min_mismatches(excl, *ignore_count)
+                        .map_or(f64::INFINITY, f64::from),
+                )
+            }
+            Self::Composite(scales) => {
+                let mut badness = vec![
+                    scales.variants_needed.badness(pr.variants_needed as f64),
+                    scales.no_match.badness(no_match_percent(pr)),
+                ];
+                if let Some(excl) = &pr.exclusivity {
+                    let mm = effective_min_mismatches(excl, scales.ignore_count)
+                        .map_or(f64::INFINITY, f64::from);
+                    badness.push(scales.exclusivity.badness(mm));
+                }
+                let mean = badness.iter().sum::<f64>() / badness.len() as f64;
+                Some(100.0 * (1.0 - mean))
+            }
+        }
+    }
+}
+
+/// Percentage of references without an accepted match in a window.
+pub fn no_match_percent(pr: &PositionResult) -> f64 {
+    if pr.analysis.total_sequences == 0 {
+        0.0
+    } else {
+        pr.analysis.no_match_count as f64 / pr.analysis.total_sequences as f64 * 100.0
+    }
+}
+
+/// Shannon entropy (bits) of the variant frequencies among the matched references.
+pub fn variant_entropy(pr: &PositionResult) -> f64 {
+    let total: usize = pr.analysis.variants.iter().map(|v| v.count).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    pr.analysis
+        .variants
+        .iter()
+        .filter(|v| v.count > 0)
+        .map(|v| {
+            let p = v.count as f64 / total as f64;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Render the heatmap as CSV: one row per oligo length, one column per template
+/// position (1-based, the union of positions across lengths). Skipped, missing,
+/// and filtered-out windows are left empty; all-no-match exclusivity is `Inf`.
+pub fn heatmap_matrix_csv(
+    results: &ScreeningResults,
+    metric: WindowMetric,
+    filter: Option<&ExclusivityFilter>,
+) -> String {
+    let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+    lengths.sort();
+    let positions: BTreeSet<usize> = results
+        .results_by_length
+        .values()
+        .flat_map(|lr| lr.positions.iter().map(|p| p.position))
+        .collect();
+
+    let mut csv = String::from("length/position");
+    for pos in &positions {
+        csv.push_str(&format!(",{}", pos + 1));
+    }
+    csv.push('\n');
+
+    for length in lengths {
+        let length_result = &results.results_by_length[&length];
+        csv.push_str(&length.to_string());
+
+        let mut row = length_result.positions.iter().peekable();
+        for &pos in &positions {
+            csv.push(',');
+            while row.peek().is_some_and(|p| p.position < pos) {
+                row.next();
+            }
+            let Some(pr) = row.peek().filter(|p| p.position == pos) else {
+                continue;
+            };
+            if filter.is_some_and(|f| !f.passes(pr)) {
+                continue;
+            }
+            let end = pos + length as usize;
+            let oligo = results.template_sequence.get(pos..end).unwrap_or("");
+            match metric.value(pr, oligo, &results.params.tm_conditions) {
+                Some(v) if v.is_infinite() => csv.push_str("Inf"),
+                Some(v) if v.fract() == 0.0 => csv.push_str(&format!("{}", v)),
+                Some(v) => csv.push_str(&format!("{:.2}", v)),
+                None => {}
+            }
+        }
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Genome-browser track format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackFormat {
+    BedGraph,
+    /// variableStep wiggle
+    Wiggle,
+}
+
+impl TrackFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::BedGraph => "bedgraph",
+            Self::Wiggle => "wig",
+        }
+    }
+}
+
+/// Render a metric as genome-browser tracks (bedGraph or wiggle) on template
+/// coordinates, one track per oligo length, for overlaying with other
+/// annotations in IGV or JBrowse. Each window's value covers the step size
+/// from its start (up to the next window, in refined screens), so windows
+/// tile the template without overlapping; wiggle tracks start a new
+/// variableStep block wherever that span changes.
+/// Skipped, missing, and filtered-out windows are left out; all-no-match
+/// exclusivity, which has no finite value, is written as the oligo length.
+pub fn metric_track(
+    results: &ScreeningResults,
+    metric: WindowMetric,
+    filter: Option<&ExclusivityFilter>,
+    chrom: &str,
+    format: TrackFormat,
+) -> String {
+    let template_len = results.template_sequence.len();
+    let step = results.params.resolution.max(1) as usize;
+    let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+    lengths.sort();
+
+    let mut track = String::new();
+    for length in lengths {
+        let name = format!("{} {} nt", metric.label(), length);
+        match format {
+            TrackFormat::BedGraph => {
+                track.push_str(&format!("track type=bedGraph name=\"{}\"\n", name));
+            }
+            TrackFormat::Wiggle => {
+                track.push_str(&format!("track type=wiggle_0 name=\"{}\"\n", name));
+            }
+        }
+
+        // Span of the current variableStep block; none is open yet
+        let mut span = 0;
+        let positions = &results.results_by_length[&length].positions;
+        for (i, pr) in positions.iter().enumerate() {
+            if filter.is_some_and(|f| !f.passes(pr)) {
+                continue;
+            }
+            let start = pr.position;
+            // Refined screens mix step sizes; a window ends where the next starts
+            let next = positions
+                .get(i + 1)
+                .map_or(template_len, |next| next.position);
+            let oligo = results
+                .template_sequence
+                .get(start..start + length as usize)
+                .unwrap_or("");
+            let Some(value) = metric.value(pr, oligo, &results.params.tm_conditions) else {
+                continue;
+            };
+            let value = if value.is_infinite() {
+                length as f64
+            } else {
+                value
+            };
+            let value = if value.fract() == 0.0 {
+                format!("{}", value)
+            } else {
+                format!("{:.2}", value)
+            };
+            let end = (start + step).min(next);
+            match format {
+                TrackFormat::BedGraph => {
+                    track.push_str(&format!("{}\t{}\t{}\t{}\n", chrom, start, end, value));
+                }
+                TrackFormat::Wiggle => {
+                    if end - start != span {
+                        span = end - start;
+                        track.push_str(&format!("variableStep chrom={} span={}\n", chrom, span));
+                    }
+                    track.push_str(&format!("{}\t{}\n", start + 1, value));
+                }
+            }
+        }
+    }
+    track
+}
+
+/// Per-length color ranges spanning each row's observed values, oriented
+/// like `scale`: green at the row's best value, red at its worst. Longer
+/// oligos need systematically more variants, so a global scale leaves their
+/// rows uniformly red; a per-row scale shows the best windows of each length.
+/// Skipped and filtered-out windows, non-finite values, and windows without
+/// any matched reference (zero variants needed) do not count toward a row's
+/// range. Rows with no values are left out.
+pub fn per_length_ranges(
+    results: &ScreeningResults,
+    metric: WindowMetric,
+    scale: MetricRange,
+    filter: Option<&ExclusivityFilter>,
+) -> HashMap<u32, MetricRange> {
+    let mut ranges = HashMap::new();
+    for (&length, length_result) in &results.results_by_length {
+        let mut bounds: Option<(f64, f64)> = None;
+        for pr in &length_result.positions {
+            if filter.is_some_and(|f| !f.passes(pr))
+                || (matches!(metric, WindowMetric::VariantsNeeded) && pr.variants_needed == 0)
+            {
+                continue;
+            }
+            let start = pr.position;
+            let oligo = results
+                .template_sequence
+                .get(start..start + length as usize)
+                .unwrap_or("");
+            let Some(value) = metric
+                .value(pr, oligo, &results.params.tm_conditions)
+                .filter(|v| v.is_finite())
+            else {
+                continue;
+            };
+            bounds = Some(bounds.map_or((value, value), |(lo, hi)| (lo.min(value), hi.max(value))));
+        }
+        if let Some((lo, hi)) = bounds {
+            let range = if scale.green_at <= scale.red_at {
+                MetricRange {
+                    green_at: lo,
+                    red_at: hi,
+                }
+            } else {
+                MetricRange {
+                    green_at: hi,
+                    red_at: lo,
+                }
+            };
+            ranges.insert(length, range);
+        }
+    }
+    ranges
+}
+
+/// The nominated candidates as a design list CSV, with each window's variant
+/// count, coverage, and per-taxon specificity (taxa within `near_mismatches`
+/// mismatches are named as near matches). `approved_only` keeps just the
+/// approved nominations.
+pub fn nominations_csv(
+    results: &ScreeningResults,
+    approved_only: bool,
+    near_mismatches: u32,
+) -> String {
+    let mut csv = String::from(
+        "length,position,sequence,status,variants_needed,coverage,specificity,\
+         taxon_min_mismatches,note\n",
+    );
+    for n in &results.nominations {
+        if approved_only && n.status != NominationStatus::Approved {
+            continue;
+        }
+        let window = results
+            .results_by_length
+            .get(&n.oligo_length)
+            .and_then(|lr| lr.positions.iter().find(|p| p.position == n.position))
+            .filter(|pr| !pr.analysis.skipped);
+        let (variants_needed, coverage) = match window {
+            Some(pr) => (
+                pr.variants_needed.to_string(),
+                format!("{:.2}", pr.analysis.coverage_at_threshold),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let (specificity, taxa) = taxon_columns(
+            results,
+            window.and_then(|pr| pr.exclusivity.as_ref()),
+            near_mismatches,
+        );
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            n.oligo_length,
+            n.position + 1,
+            n.sequence,
+            n.status.label(),
+            variants_needed,
+            coverage,
+            specificity,
+            taxa,
+            csv_field(&n.note)
+        ));
+    }
+    csv
+}
+
+/// Delimited-text flavor of a table export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Self::Csv => ",",
+            Self::Tsv => "\t",
+        }
+    }
+}
+
+/// Columns of `window_fields`
+const WINDOW_COLUMNS: [&str; 9] = [
+    "template_oligo",
+    "skipped",
+    "variants_needed",
+    "coverage",
+    "matched",
+    "no_match",
+    "mean_identity",
+    "min_identity",
+    "exclusivity_min_mismatches",
+];
+
+/// Per-window values shared by the position and batch exports. Metrics of
+/// skipped windows are empty; the exclusivity minimum ignores the closest
+/// `ignore_count` sequences, is `Inf` when all the rest are no-matches, and
+/// is empty without exclusivity data.
+fn window_fields(
+    results: &ScreeningResults,
+    length: u32,
+    pr: &PositionResult,
+    ignore_count: usize,
+) -> Vec<String> {
+    let end = pr.position + length as usize;
+    let oligo = results
+        .template_sequence
+        .get(pr.position..end)
+        .unwrap_or("");
+    let mut fields = vec![oligo.to_string(), pr.analysis.skipped.to_string()];
+    if pr.analysis.skipped {
+        fields.extend(std::iter::repeat_n(String::new(), 6));
+    } else {
+        let identity = pr.analysis.identity_stats;
+        fields.extend([
+            pr.variants_needed.to_string(),
+            format!("{:.2}", pr.analysis.coverage_at_threshold),
+            pr.analysis.sequences_analyzed.to_string(),
+            pr.analysis.no_match_count.to_string(),
+            identity.map_or(String::new(), |s| format!("{:.2}", s.mean)),
+            identity.map_or(String::new(), |s| format!("{:.2}", s.min)),
+        ]);
+    }
+    fields.push(match &pr.exclusivity {
+        Some(excl) => effective_min_mismatches(excl, ignore_count)
+            .map_or("Inf".to_string(), |mm| mm.to_string()),
+        None => String::new(),
+    });
+    fields
+}
+
+/// Results as a long-format table for spreadsheets and R/pandas: one row per
+/// (length, position), positions 1-based, with the template oligo and each
+/// window's metrics (see `window_fields`). Rows are sorted by length, then
+/// position. Windows failing `filter` are left out, and the exclusivity
+/// minimum ignores as many sequences as the filter does.
+pub fn positions_table(
+    results: &ScreeningResults,
+    filter: Option<&ExclusivityFilter>,
+    format: TableFormat,
+) -> String {
+    let ignore_count = filter.map_or(0, |f| f.ignore_count);
+    let sep = format.separator();
+    let mut table = format!("length{sep}position{sep}{}\n", WINDOW_COLUMNS.join(sep));
+    let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+    lengths.sort();
+    for length in lengths {
+        for pr in &results.results_by_length[&length].positions {
+            if filter.is_some_and(|f| !f.passes(pr)) {
+                continue;
+            }
+            table.push_str(&format!(
+                "{length}{sep}{}{sep}{}\n",
+                pr.position + 1,
+                window_fields(results, length, pr, ignore_count).join(sep)
+            ));
+        }
+    }
+    table
+}
+
+/// A completed job as input to the batch summary export
+pub struct BatchJob<'a> {
+    pub job_id: u64,
+    pub template_file: &'a str,
+    pub reference_file: &'a str,
+    pub results: &'a ScreeningResults,
+}
+
+/// One combined CSV for a whole batch: one row per (job, length, position),
+/// each carrying the job's metadata columns so the file pivots directly.
+/// Metrics of skipped windows are left empty. Taxa within `near_mismatches`
+/// mismatches are named as near matches in the specificity column. Each
+/// job's windows failing `filter` are left out and the exclusivity minimum
+/// ignores as many sequences as the filter does; the header then has an
+/// `exclusivity_filter` column stating the filter on every row.
+pub fn batch_summary_csv(
+    jobs: &[BatchJob<'_>],
+    filter: Option<&ExclusivityFilter>,
+    near_mismatches: u32,
+) -> String {
+    let ignore_count = filter.map_or(0, |f| f.ignore_count);
+    let filter_column = filter.map(|f| {
+        format!(
+            "> {} (ignoring {})",
+            f.min_mismatches_exceeding, f.ignore_count
+        )
+    });
+    let mut csv = format!(
+        "job_id,template_file,reference_file,reference_count,exclusivity_count,method,\
+         coverage_threshold,max_mismatches,resolution,{}length,position,{},specificity,\
+         taxon_min_mismatches\n",
+        if filter.is_some() {
+            "exclusivity_filter,"
+        } else {
+            ""
+        },
+        WINDOW_COLUMNS.join(",")
+    );
+    for job in jobs {
+        let results = job.results;
+        let params = &results.params;
+        let mut job_columns = format!(
+            "{},{},{},{},{},{},{},{},{}",
+            job.job_id,
+            csv_field(job.template_file),
+            csv_field(job.reference_file),
+            results.total_sequences,
+            results
+                .exclusivity_sequence_count
+                .map_or(String::new(), |n| n.to_string()),
+            csv_field(&params.method.description()),
+            params.coverage_threshold,
+            params.pairwise.max_mismatches,
+            params.resolution,
+        );
+        if let Some(column) = &filter_column {
+            job_columns.push(',');
+            job_columns.push_str(column);
+        }
+
+        let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+        lengths.sort();
+        for length in lengths {
+            for pr in &results.results_by_length[&length].positions {
+                if filter.is_some_and(|f| !f.passes(pr)) {
+                    continue;
+                }
+                csv.push_str(&format!(
+                    "{},{},{},{}",
+                    job_columns,
+                    length,
+                    pr.position + 1,
+                    window_fields(results, length, pr, ignore_count).join(",")
+                ));
+                let (specificity, taxa) =
+                    taxon_columns(results, pr.exclusivity.as_ref(), near_mismatches);
+                csv.push_str(&format!(",{},{}\n", specificity, taxa));
+            }
+        }
+    }
+    csv
+}
+
+/// Specificity sentence and per-taxon minimums of a window, as CSV fields
+/// (empty without per-taxon data).
+fn taxon_columns(
+    results: &ScreeningResults,
+    excl: Option<&ExclusivityResult>,
+    near_mismatches: u32,
+) -> (String, String) {
+    match excl {
+        Some(excl) => (
+            csv_field(&specificity_summary(
+                &results.exclusivity_taxa,
+                excl,
+                near_mismatches,
+            )),
+            csv_field(&taxon_mismatch_list(&results.exclusivity_taxa, excl)),
+        ),
+        None => (String::new(), String::new()),
+    }
+}
+
+/// Quote a free-text CSV field when it contains separators or quotes.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ExclusivityGroup, ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    const TEMPLATE: &str = "TATGGTACGTCATGTTCTAG";
+
+    fn screen(template: &str, params: AnalysisParams) -> ScreeningResults {
+        screen_with_exclusivity(template, params, &[])
+    }
+
+    /// Screen `template` against itself, with `exclusivity` as one
+    /// off-target group
+    fn screen_with_exclusivity(
+        template: &str,
+        params: AnalysisParams,
+        exclusivity: &[&str],
+    ) -> ScreeningResults {
+        let groups: Vec<ExclusivityGroup> = if exclusivity.is_empty() {
+            Vec::new()
+        } else {
+            vec![ExclusivityGroup {
+                name: "Off-targets".to_string(),
+                data: ReferenceData {
+                    names: (1..=exclusivity.len()).map(|i| format!("E{}", i)).collect(),
+                    sequences: exclusivity.iter().map(|s| s.to_string()).collect(),
+                },
+            }]
+        };
+        run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string()],
+                sequences: vec![template.to_string()],
+            },
+            &params,
+            &groups,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_heatmap_matrix_csv() {
+        let results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 11,
+            resolution: 5,
+            ..Default::default()
+            },
+        );
+
+        let csv = heatmap_matrix_csv(&results, WindowMetric::VariantsNeeded, None);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "length/position,1,6,11");
+        assert_eq!(lines[1], "10,1,1,1");
+        // Length 11 has no window at position 11 (template too short)
+        assert_eq!(lines[2], "11,1,1,");
+
+        let csv = heatmap_matrix_csv(&results, WindowMetric::MinIdentity, None);
+        assert_eq!(csv.lines().nth(1), Some("10,100,100,100"));
+
+        // TATGGTACGT, TACGTCATGT, CATGTTCTAG: 4 of 10 bases are G/C in each
+        let csv = heatmap_matrix_csv(&results, WindowMetric::GcPercent, None);
+        assert_eq!(csv.lines().nth(1), Some("10,40,40,40"));
+    }
+
+    #[test]
+    fn test_metric_track() {
+        let results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            resolution: 5,
+            ..Default::default()
+            },
+        );
+
+        let bedgraph = metric_track(
+            &results,
+            WindowMetric::GcPercent,
+            None,
+            "chr1",
+            TrackFormat::BedGraph,
+        );
+        let lines: Vec<&str> = bedgraph.lines().collect();
+        assert_eq!(lines[0], "track type=bedGraph name=\"gc_percent 10 nt\"");
+        assert_eq!(
+            &lines[1..],
+            ["chr1\t0\t5\t40", "chr1\t5\t10\t40", "chr1\t10\t15\t40"]
+        );
+
+        let wig = metric_track(
+            &results,
+            WindowMetric::VariantsNeeded,
+            None,
+            "chr1",
+            TrackFormat::Wiggle,
+        );
+        let lines: Vec<&str> = wig.lines().collect();
+        assert_eq!(lines[1], "variableStep chrom=chr1 span=5");
+        assert_eq!(&lines[2..], ["1\t1", "6\t1", "11\t1"]);
+
+        // Refined windows are 1 bp apart, so they get a block of span 1;
+        // the last window still covers a whole step
+        let refined = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            resolution: 5,
+            refine_max_variants: Some(1),
+            ..Default::default()
+            },
+        );
+        let wig = metric_track(
+            &refined,
+            WindowMetric::VariantsNeeded,
+            None,
+            "chr1",
+            TrackFormat::Wiggle,
+        );
+        let lines: Vec<&str> = wig.lines().collect();
+        assert_eq!(lines[1], "variableStep chrom=chr1 span=1");
+        let fine: Vec<String> = (1..=10).map(|p| format!("{}\t1", p)).collect();
+        assert_eq!(&lines[2..12], fine);
+        assert_eq!(&lines[12..], ["variableStep chrom=chr1 span=5", "11\t1"]);
+    }
+
+    #[test]
+    fn test_per_length_ranges() {
+        let results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 4,
+            max_oligo_length: 10,
+            resolution: 5,
+            ..Default::default()
+            },
+        );
+
+        // 4-mers TATG, TACG, CATG, TCTA: 25-50% GC; 10-mers are all 40%
+        let higher_worse = MetricRange {
+            green_at: 0.0,
+            red_at: 100.0,
+        };
+        let ranges = per_length_ranges(&results, WindowMetric::GcPercent, higher_worse, None);
+        assert_eq!(
+            ranges[&4],
+            MetricRange {
+                green_at: 25.0,
+                red_at: 50.0
+            }
+        );
+        assert_eq!(ranges[&10].green_at, 40.0);
+        assert_eq!(ranges[&10].badness(40.0), 0.0);
+
+        let higher_better = MetricRange {
+            green_at: 100.0,
+            red_at: 0.0,
+        };
+        let ranges = per_length_ranges(&results, WindowMetric::GcPercent, higher_better, None);
+        assert_eq!(
+            ranges[&4],
+            MetricRange {
+                green_at: 50.0,
+                red_at: 25.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_metric_scales() {
+        let lower_better = MetricRange {
+            green_at: 1.0,
+            red_at: 5.0,
+        };
+        assert_eq!(lower_better.badness(0.0), 0.0);
+        assert_eq!(lower_better.badness(3.0), 0.5);
+        assert_eq!(lower_better.badness(9.0), 1.0);
+        let higher_better = MetricRange {
+            green_at: 6.0,
+            red_at: 0.0,
+        };
+        assert_eq!(higher_better.badness(f64::INFINITY), 0.0);
+        assert_eq!(higher_better.badness(3.0), 0.5);
+
+        let band = TargetBand {
+            min: 40.0,
+            max: 60.0,
+            tolerance: 10.0,
+        };
+        assert_eq!(band.badness(50.0), 0.0);
+        assert_eq!(band.badness(35.0), 0.5);
+        assert_eq!(band.badness(75.0), 1.0);
+    }
+
+    #[test]
+    fn test_nominations_csv() {
+        let mut results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            ..Default::default()
+            },
+        );
+
+        assert!(results.nominate(10, 0));
+        assert!(!results.nominate(10, 0));
+        assert!(!results.nominate(10, 15));
+        assert!(results.nominate(10, 5));
+        results.nominations[1].status = NominationStatus::Approved;
+        results.nominations[1].note = "probe, maybe".to_string();
+
+        let csv = nominations_csv(&results, false, 2);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "10,1,TATGGTACGT,Proposed,1,100.00,,,");
+
+        let csv = nominations_csv(&results, true, 2);
+        assert_eq!(
+            csv.lines().skip(1).collect::<Vec<_>>(),
+            vec!["10,6,TACGTCATGT,Approved,1,100.00,,,\"probe, maybe\""]
+        );
+    }
+
+    #[test]
+    fn test_batch_summary_csv() {
+        let results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 11,
+            resolution: 5,
+            ..Default::default()
+            },
+        );
+        let jobs = [
+            BatchJob {
+                job_id: 1,
+                template_file: "t.fasta",
+                reference_file: "refs, set A.fasta",
+                results: &results,
+            },
+            BatchJob {
+                job_id: 2,
+                template_file: "t.fasta",
+                reference_file: "refs.fasta",
+                results: &results,
+            },
+        ];
+
+        let csv = batch_summary_csv(&jobs, None, 2);
+        let lines: Vec<&str> = csv.lines().collect();
+        // Header + 2 jobs × (3 windows at length 10 + 2 at length 11)
+        assert_eq!(lines.len(), 11);
+        let columns = lines[0].split(',').count();
+        assert_eq!(columns, 22);
+        assert!(lines[1].starts_with("1,t.fasta,\"refs, set A.fasta\",1,,"));
+        assert!(lines[1].ends_with(",10,1,TATGGTACGT,false,1,100.00,1,0,100.00,100.00,,,"));
+        assert!(lines[10].starts_with("2,"));
+
+        // Without exclusivity data every window passes, and the header and
+        // rows state the filter
+        let filter = ExclusivityFilter {
+            min_mismatches_exceeding: 2,
+            ignore_count: 1,
+        };
+        let filtered = batch_summary_csv(&jobs, Some(&filter), 2);
+        let lines: Vec<&str> = filtered.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert!(lines[0].contains(",resolution,exclusivity_filter,length,"));
+        assert!(lines[1].contains(",5,> 2 (ignoring 1),10,1,"));
+
+        // A job whose windows all hit an exclusivity sequence loses its rows
+        let off_target = screen_with_exclusivity(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 11,
+            resolution: 5,
+            ..Default::default()
+            },
+            &[TEMPLATE],
+        );
+        let jobs = [
+            BatchJob {
+                job_id: 3,
+                template_file: "t.fasta",
+                reference_file: "refs.fasta",
+                results: &off_target,
+            },
+            BatchJob {
+                job_id: 4,
+                template_file: "t.fasta",
+                reference_file: "refs.fasta",
+                results: &results,
+            },
+        ];
+        let strict = ExclusivityFilter {
+            ignore_count: 0,
+            ..filter
+        };
+        let filtered = batch_summary_csv(&jobs, Some(&strict), 2);
+        let lines: Vec<&str> = filtered.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1..].iter().all(|line| line.starts_with("4,")));
+    }
+
+    #[test]
+    fn test_positions_table() {
+        let results = screen(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 11,
+            resolution: 5,
+            ..Default::default()
+            },
+        );
+
+        let csv = positions_table(&results, None, TableFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("length,position,template_oligo,skipped,variants_needed"));
+        assert_eq!(
+            lines[1],
+            "10,1,TATGGTACGT,false,1,100.00,1,0,100.00,100.00,"
+        );
+        assert!(lines[5].starts_with("11,6,TACGTCATGTT,"));
+
+        let tsv = positions_table(&results, None, TableFormat::Tsv);
+        assert_eq!(tsv, csv.replace(',', "\t"));
+
+        // The filter drops windows and sets how many sequences are ignored
+        let results = screen_with_exclusivity(
+            TEMPLATE,
+            AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            resolution: 5,
+            ..Default::default()
+            },
+            &[TEMPLATE],
+        );
+        let csv = positions_table(&results, None, TableFormat::Csv);
+        assert!(csv.lines().skip(1).all(|line| line.ends_with(",0")));
+        let strict = ExclusivityFilter {
+            min_mismatches_exceeding: 0,
+            ignore_count: 0,
+        };
+        let csv = positions_table(&results, Some(&strict), TableFormat::Csv);
+        assert_eq!(csv.lines().count(), 1);
+        let ignoring = ExclusivityFilter {
+            ignore_count: 1,
+            ..strict
+        };
+        let csv = positions_table(&results, Some(&ignoring), TableFormat::Csv);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().skip(1).all(|line| line.ends_with(",Inf")));
+    }
+}
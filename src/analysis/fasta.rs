@@ -1,16 +1,26 @@
 //! FASTA file parsing for template and reference sequences
 
-use super::iupac::{is_ambiguous_base, is_gap, is_standard_base};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
+
+use super::iupac::{is_ambiguous_base, is_gap, is_standard_base, is_valid_dna};
 
 /// Parsed template sequence (single sequence)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateData {
     pub name: String,
     pub sequence: String,
+    /// The input was RNA; its U bases are stored as T
+    #[serde(default)]
+    pub rna: bool,
 }
 
 /// Parsed reference sequences (multiple, unaligned)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReferenceData {
     pub sequences: Vec<String>,
     pub names: Vec<String>,
@@ -39,41 +49,220 @@ impl Default for ReferenceData {
     }
 }
 
-/// Parse a single-sequence FASTA as template.
-/// Returns error if input contains 0 or more than 1 sequence.
-pub fn parse_template_fasta(text: &str) -> Result<TemplateData, String> {
-    let (names, sequences) = parse_fasta_sequences(text)?;
+/// A named set of exclusivity sequences (e.g. "human transcriptome",
+/// "related virus B"), usually one loaded file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExclusivityGroup {
+    pub name: String,
+    pub data: ReferenceData,
+}
 
-    if sequences.is_empty() {
-        return Err("No valid sequence found in template input".to_string());
+/// Read a FASTA file as text, decompressing gzip input. Compression is
+/// detected from the gzip magic bytes rather than the extension; multi-member
+/// files (bgzip, concatenated `.gz`) are read to the end.
+pub fn read_fasta_file(path: &Path) -> std::io::Result<String> {
+    let mut text = String::new();
+    fasta_reader(File::open(path)?)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Read a reference FASTA file record by record (decompressing gzip input as
+/// `read_fasta_file` does) without holding the file's text in memory.
+/// `progress` is called with (bytes read, file size) about once per MiB; for
+/// compressed files both count compressed bytes.
+pub fn read_reference_fasta(
+    path: &Path,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<ReferenceData, String> {
+    let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+    let file = File::open(path).map_err(read_error)?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let counting = ProgressReader {
+        inner: file,
+        read: 0,
+        reported: 0,
+        progress: |read| progress(read, total),
+    };
+
+    let mut data = ReferenceData::new();
+    for record in FastaRecords::new(fasta_reader(counting).map_err(read_error)?) {
+        let (name, sequence) = record.map_err(read_error)?;
+        data.names.push(name);
+        data.sequences.push(sequence);
+    }
+    if data.is_empty() {
+        return Err("No valid sequences found in reference input".to_string());
+    }
+    data.names.shrink_to_fit();
+    data.sequences.shrink_to_fit();
+    Ok(data)
+}
+
+/// Buffered reader over `inner`, decompressing it when it starts with the
+/// gzip magic bytes.
+fn fasta_reader<'a>(inner: impl Read + 'a) -> std::io::Result<Box<dyn BufRead + 'a>> {
+    let mut reader = BufReader::with_capacity(1 << 20, inner);
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::with_capacity(
+            1 << 20,
+            MultiGzDecoder::new(reader),
+        )))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Reader reporting the running byte count roughly once per MiB and at the end
+struct ProgressReader<R, F> {
+    inner: R,
+    read: u64,
+    reported: u64,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if n == 0 || self.read - self.reported >= 1 << 20 {
+            self.reported = self.read;
+            (self.progress)(self.read);
+        }
+        Ok(n)
+    }
+}
+
+/// Incremental FASTA parser yielding one `(name, sequence)` record at a
+/// time. Sequences are upper-cased with `.` gaps written as `-`, RNA `U`
+/// written as `T`, and other characters (whitespace, numbers, etc.) dropped,
+/// and each is stored at its exact length. Records without sequence are
+/// skipped; unnamed ones are called `Sequence_<n>`.
+pub struct FastaRecords<R> {
+    reader: R,
+    line: Vec<u8>,
+    /// Header line already read that starts the next record
+    next_name: Option<String>,
+    count: usize,
+    rna: bool,
+}
+
+impl<R: BufRead> FastaRecords<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            next_name: None,
+            count: 0,
+            rna: false,
+        }
     }
-    if sequences.len() > 1 {
+
+    /// Whether any record read so far contained `U` bases
+    pub fn saw_rna(&self) -> bool {
+        self.rna
+    }
+}
+
+impl<R: BufRead> Iterator for FastaRecords<R> {
+    type Item = std::io::Result<(String, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut name = self.next_name.take().unwrap_or_default();
+        let mut sequence = String::new();
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            let line = self.line.trim_ascii();
+            if let Some(header) = line.strip_prefix(b">") {
+                let header = String::from_utf8_lossy(header).into_owned();
+                if sequence.is_empty() {
+                    // A header without sequence is replaced by the next one
+                    name = header;
+                    continue;
+                }
+                self.next_name = Some(header);
+                break;
+            }
+            for &b in line {
+                let c = match (b as char).to_ascii_uppercase() {
+                    'U' => {
+                        self.rna = true;
+                        'T'
+                    }
+                    c => c,
+                };
+                if is_standard_base(c) || is_ambiguous_base(c) || is_gap(c) {
+                    sequence.push(if c == '.' { '-' } else { c });
+                }
+            }
+        }
+
+        if sequence.is_empty() {
+            return None;
+        }
+        self.count += 1;
+        if name.is_empty() {
+            name = format!("Sequence_{}", self.count);
+        }
+        sequence.shrink_to_fit();
+        Some(Ok((name, sequence)))
+    }
+}
+
+/// Parse a single-sequence FASTA as template. RNA input (U) is read as DNA
+/// with `rna` set. IUPAC ambiguity codes are kept, for consensus templates.
+/// Returns error if input contains 0 or more than 1 sequence.
+pub fn parse_template_fasta(text: &str) -> Result<TemplateData, String> {
+    let mut templates = parse_templates_fasta(text)?;
+    if templates.len() > 1 {
         return Err(format!(
             "Template must contain exactly 1 sequence, found {}",
-            sequences.len()
+            templates.len()
         ));
     }
+    Ok(templates.remove(0))
+}
+
+/// Parse a multi-FASTA of templates for a multi-template batch, validated
+/// like `parse_template_fasta`. Returns error if input contains no sequence.
+pub fn parse_templates_fasta(text: &str) -> Result<Vec<TemplateData>, String> {
+    let (names, sequences, rna) = parse_fasta_sequences(text)?;
 
-    // Validate template has only standard bases (no gaps or ambiguities)
-    let seq = &sequences[0];
-    for (i, c) in seq.chars().enumerate() {
-        if !is_standard_base(c) {
-            return Err(format!(
-                "Template contains invalid character '{}' at position {}. Only A, C, G, T are allowed.",
-                c, i + 1
-            ));
+    if sequences.is_empty() {
+        return Err("No valid sequence found in template input".to_string());
+    }
+
+    // Validate templates have only bases and ambiguity codes (no gaps)
+    for (name, seq) in names.iter().zip(&sequences) {
+        for (i, c) in seq.chars().enumerate() {
+            if !is_valid_dna(c) {
+                return Err(format!(
+                    "Template {} contains invalid character '{}' at position {}. Only A, C, G, T (or U) and IUPAC ambiguity codes are allowed.",
+                    name, c, i + 1
+                ));
+            }
         }
     }
 
-    Ok(TemplateData {
-        name: names[0].clone(),
-        sequence: sequences[0].clone(),
-    })
+    Ok(names
+        .into_iter()
+        .zip(sequences)
+        .map(|(name, sequence)| TemplateData {
+            name,
+            sequence,
+            rna,
+        })
+        .collect())
 }
 
 /// Parse multi-sequence FASTA as reference set (unaligned, no length normalization).
+/// RNA sequences have their U bases read as T.
 pub fn parse_reference_fasta(text: &str) -> Result<ReferenceData, String> {
-    let (names, sequences) = parse_fasta_sequences(text)?;
+    let (names, sequences, _) = parse_fasta_sequences(text)?;
 
     if sequences.is_empty() {
         return Err("No valid sequences found in reference input".to_string());
@@ -86,85 +275,74 @@ pub fn parse_reference_fasta(text: &str) -> Result<ReferenceData, String> {
 }
 
 /// Core FASTA parsing: extract names and sequences from FASTA text.
-/// Does NOT normalize lengths (suitable for unaligned sequences).
-fn parse_fasta_sequences(text: &str) -> Result<(Vec<String>, Vec<String>), String> {
+/// Does NOT normalize lengths (suitable for unaligned sequences). The flag
+/// is set when any sequence was RNA.
+fn parse_fasta_sequences(text: &str) -> Result<(Vec<String>, Vec<String>, bool), String> {
     let mut names = Vec::new();
     let mut sequences = Vec::new();
-    let mut current_name = String::new();
-    let mut current_seq = String::new();
+    let mut records = FastaRecords::new(text.as_bytes());
+    for record in records.by_ref() {
+        let (name, sequence) = record.map_err(|e| e.to_string())?;
+        names.push(name);
+        sequences.push(sequence);
+    }
+    Ok((names, sequences, records.saw_rna()))
+}
 
-    for line in text.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
 
-        if let Some(name) = line.strip_prefix('>') {
-            // Save previous sequence if exists
-            if !current_seq.is_empty() {
-                names.push(current_name.clone());
-                sequences.push(current_seq.clone());
-                current_seq.clear();
-            }
-            current_name = name.to_string();
-        } else {
-            // Append to current sequence, converting to uppercase
-            for c in line.chars() {
-                let c = c.to_ascii_uppercase();
-                if is_standard_base(c) || is_ambiguous_base(c) || is_gap(c) {
-                    if c == '.' {
-                        current_seq.push('-');
-                    } else {
-                        current_seq.push(c);
-                    }
-                }
-                // Ignore other characters (whitespace, numbers, etc.)
-            }
-        }
-    }
+    #[test]
+    fn test_read_gzip_fasta() {
+        let gzip = |text: &str| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(text.as_bytes()).unwrap();
+            encoder.finish().unwrap()
+        };
+        // Two members, as bgzip writes them
+        let mut bytes = gzip(">r1\nACGT\n");
+        bytes.extend(gzip(">r2\nGGCC\n"));
 
-    // Don't forget the last sequence
-    if !current_seq.is_empty() {
-        if current_name.is_empty() {
-            current_name = format!("Sequence_{}", sequences.len() + 1);
-        }
-        names.push(current_name);
-        sequences.push(current_seq);
-    }
+        let dir = std::env::temp_dir();
+        let gz_path = dir.join(format!("oligoscreen_fasta_{}.fasta.gz", std::process::id()));
+        let plain_path = dir.join(format!("oligoscreen_fasta_{}.fasta", std::process::id()));
+        std::fs::write(&gz_path, &bytes).unwrap();
+        std::fs::write(&plain_path, ">r1\nACGT\n>r2\nGGCC\n").unwrap();
 
-    // If no FASTA headers found, try treating each line as a sequence
-    if sequences.is_empty() {
-        for (i, line) in text.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('>') {
-                continue;
-            }
+        let text = read_fasta_file(&gz_path).unwrap();
+        assert_eq!(text, read_fasta_file(&plain_path).unwrap());
+        let data = parse_reference_fasta(&text).unwrap();
+        assert_eq!(data.names, vec!["r1", "r2"]);
 
-            let mut seq = String::new();
-            for c in line.chars() {
-                let c = c.to_ascii_uppercase();
-                if is_standard_base(c) || is_ambiguous_base(c) || is_gap(c) {
-                    if c == '.' {
-                        seq.push('-');
-                    } else {
-                        seq.push(c);
-                    }
-                }
-            }
+        // Streamed, with the last progress report at the end of the file
+        let mut last = (0, 0);
+        let streamed = read_reference_fasta(&gz_path, |read, total| last = (read, total)).unwrap();
+        assert_eq!(streamed.sequences, data.sequences);
+        assert_eq!(last, (bytes.len() as u64, bytes.len() as u64));
 
-            if !seq.is_empty() {
-                names.push(format!("Sequence_{}", i + 1));
-                sequences.push(seq);
-            }
-        }
+        std::fs::remove_file(&gz_path).unwrap();
+        std::fs::remove_file(&plain_path).unwrap();
     }
 
-    Ok((names, sequences))
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_fasta_records() {
+        let text = "ACGT\n>empty\n>r2 desc\r\nac.gt\n  nn12\n>\nTT\n";
+        let records: Vec<(String, String)> = FastaRecords::new(text.as_bytes())
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                ("Sequence_1".to_string(), "ACGT".to_string()),
+                ("r2 desc".to_string(), "AC-GTNN".to_string()),
+                ("Sequence_3".to_string(), "TT".to_string()),
+            ]
+        );
+    }
 
     #[test]
     fn test_parse_template() {
@@ -172,12 +350,34 @@ mod tests {
         let data = parse_template_fasta(fasta).unwrap();
         assert_eq!(data.name, "Template");
         assert_eq!(data.sequence, "ACGTACGT");
+        assert!(!data.rna);
+    }
+
+    #[test]
+    fn test_parse_rna() {
+        let data = parse_template_fasta(">Template\nACGUacgu").unwrap();
+        assert_eq!(data.sequence, "ACGTACGT");
+        assert!(data.rna);
+
+        let data = parse_reference_fasta(">Ref1\nAUGC\n>Ref2\nATGC").unwrap();
+        assert_eq!(data.sequences, vec!["ATGC", "ATGC"]);
+    }
+
+    #[test]
+    fn test_parse_degenerate_template() {
+        let data = parse_template_fasta(">Consensus\nACGRYNacgt").unwrap();
+        assert_eq!(data.sequence, "ACGRYNACGT");
     }
 
     #[test]
     fn test_parse_template_rejects_multiple() {
         let fasta = ">Seq1\nACGT\n>Seq2\nACGT";
         assert!(parse_template_fasta(fasta).is_err());
+
+        let templates = parse_templates_fasta(fasta).unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[1].name, "Seq2");
+        assert!(parse_templates_fasta(">Seq1\nACGT\n>Seq2\nAC-T").is_err());
     }
 
     #[test]
@@ -0,0 +1,561 @@
+//! Heatmap figures written as PNG or SVG files
+//!
+//! The caller supplies the colors (cells, sequence track, legend swatches);
+//! this module lays them out and writes the file. Both formats share one
+//! layout, so a PNG and an SVG of the same figure line up pixel for pixel.
+//! PNG text uses a built-in 5×7 pixel font (upper case only).
+
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+/// An 8-bit RGB color
+pub type Rgb = [u8; 3];
+
+/// Largest PNG that will be rendered, in pixels (about 800 MB of RGB)
+pub const MAX_FIGURE_PIXELS: u64 = 1 << 28;
+
+const BACKGROUND: Rgb = [255, 255, 255];
+const TEXT: Rgb = [0, 0, 0];
+const MUTED_TEXT: Rgb = [110, 110, 110];
+
+/// The whole heatmap: one row per oligo length, one column per position
+#[derive(Debug, Clone, Default)]
+pub struct HeatmapFigure {
+    pub title: String,
+    /// Row labels, top to bottom
+    pub lengths: Vec<u32>,
+    /// 0-based template positions, left to right
+    pub positions: Vec<usize>,
+    /// Cell colors, row-major: `cells[row * positions.len() + col]`
+    pub cells: Vec<Rgb>,
+    /// Template base at each position with its color, drawn above the cells
+    pub sequence_track: Vec<(char, Rgb)>,
+    pub legend: Vec<LegendEntry>,
+}
+
+/// A legend swatch and its label, or plain text when `color` is None
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub color: Option<Rgb>,
+    pub label: String,
+}
+
+/// Output resolution of a figure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FigureSize {
+    /// Pixels per position (heatmap column)
+    pub cell_width: u32,
+    /// Pixels per oligo length (heatmap row)
+    pub cell_height: u32,
+    /// Text size multiplier; 1 draws 5×7 pixel glyphs on a 6×10 grid
+    pub text_scale: u32,
+}
+
+impl Default for FigureSize {
+    fn default() -> Self {
+        Self {
+            cell_width: 4,
+            cell_height: 24,
+            text_scale: 2,
+        }
+    }
+}
+
+/// Drawing target shared by the PNG and SVG writers
+trait Canvas {
+    fn rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Rgb);
+    /// Text with its top-left corner at (x, y)
+    fn text(&mut self, x: u32, y: u32, text: &str, color: Rgb);
+}
+
+/// Pixel positions of every part of the figure
+struct Layout {
+    size: FigureSize,
+    width: u32,
+    height: u32,
+    margin: u32,
+    /// Left edge of the cells (right of the row labels)
+    grid_x: u32,
+    axis_y: u32,
+    track_y: u32,
+    grid_y: u32,
+    legend_y: u32,
+    /// Columns between position labels
+    label_step: usize,
+    /// Legend entries per line, as index ranges into `legend`
+    legend_lines: Vec<std::ops::Range<usize>>,
+}
+
+impl Layout {
+    fn new(figure: &HeatmapFigure, size: FigureSize) -> Self {
+        let s = size.text_scale.max(1);
+        let size = FigureSize {
+            cell_width: size.cell_width.max(1),
+            cell_height: size.cell_height.max(1),
+            text_scale: s,
+        };
+        let margin = 8 * s;
+        let label_width = figure
+            .lengths
+            .iter()
+            .map(|l| text_width(&format!("{} bp", l), s))
+            .max()
+            .unwrap_or(0)
+            .max(text_width("Seq", s));
+        let grid_x = margin + label_width + 6 * s;
+        let grid_width = figure.positions.len() as u32 * size.cell_width;
+        let width = (grid_x + grid_width + margin)
+            .max(2 * margin + text_width(&figure.title, s))
+            .max(2 * margin + 60 * 6 * s);
+
+        // Position labels at a 1-2-5 step wide enough for the longest number
+        let widest = figure
+            .positions
+            .last()
+            .map_or(1, |p| (p + 1).to_string().len());
+        let needed = (widest as u32 + 2) * 6 * s;
+        let mut label_step = 1;
+        'step: for magnitude in [1, 10, 100, 1_000, 10_000, 100_000, 1_000_000] {
+            for factor in [1, 2, 5] {
+                label_step = factor * magnitude;
+                if label_step as u32 * size.cell_width >= needed {
+                    break 'step;
+                }
+            }
+        }
+
+        let axis_y = margin + 14 * s;
+        let track_y = axis_y + 10 * s;
+        let grid_y = track_y + 11 * s;
+        let legend_y = grid_y + figure.lengths.len() as u32 * size.cell_height + 8 * s;
+
+        let mut legend_lines = Vec::new();
+        let (mut start, mut x) = (0, margin);
+        for (i, entry) in figure.legend.iter().enumerate() {
+            let w = legend_entry_width(entry, s);
+            if i > start && x + w > width - margin {
+                legend_lines.push(start..i);
+                (start, x) = (i, margin);
+            }
+            x += w;
+        }
+        if start < figure.legend.len() {
+            legend_lines.push(start..figure.legend.len());
+        }
+        let height = legend_y + legend_lines.len() as u32 * 12 * s + margin;
+
+        Self {
+            size,
+            width,
+            height,
+            margin,
+            grid_x,
+            axis_y,
+            track_y,
+            grid_y,
+            legend_y,
+            label_step,
+            legend_lines,
+        }
+    }
+}
+
+fn text_width(text: &str, scale: u32) -> u32 {
+    text.chars().count() as u32 * 6 * scale
+}
+
+/// Swatch, label, and the gap before the next entry
+fn legend_entry_width(entry: &LegendEntry, scale: u32) -> u32 {
+    let swatch = if entry.color.is_some() { 10 * scale } else { 0 };
+    swatch + text_width(&entry.label, scale) + 12 * scale
+}
+
+impl HeatmapFigure {
+    /// Image size in pixels at `size`
+    pub fn dimensions(&self, size: FigureSize) -> (u32, u32) {
+        let layout = Layout::new(self, size);
+        (layout.width, layout.height)
+    }
+
+    fn draw(&self, layout: &Layout, canvas: &mut impl Canvas) {
+        let s = layout.size.text_scale;
+        let (cw, ch) = (layout.size.cell_width, layout.size.cell_height);
+        let cols = self.positions.len();
+
+        canvas.rect(0, 0, layout.width, layout.height, BACKGROUND);
+        canvas.text(layout.margin, layout.margin, &self.title, TEXT);
+
+        for col in (0..cols).step_by(layout.label_step) {
+            let x = layout.grid_x + col as u32 * cw;
+            canvas.rect(x, layout.axis_y + 8 * s, s, 2 * s, MUTED_TEXT);
+            canvas.text(
+                x,
+                layout.axis_y,
+                &(self.positions[col] + 1).to_string(),
+                MUTED_TEXT,
+            );
+        }
+
+        canvas.text(layout.margin, layout.track_y + s, "Seq", MUTED_TEXT);
+        for (col, &(base, color)) in self.sequence_track.iter().enumerate().take(cols) {
+            let x = layout.grid_x + col as u32 * cw;
+            canvas.rect(x, layout.track_y, cw, 9 * s, color);
+            if cw >= 6 * s {
+                let mut buf = [0; 4];
+                canvas.text(
+                    x + (cw - 5 * s) / 2,
+                    layout.track_y + s,
+                    base.encode_utf8(&mut buf),
+                    TEXT,
+                );
+            }
+        }
+
+        for (row, length) in self.lengths.iter().enumerate() {
+            let y = layout.grid_y + row as u32 * ch;
+            canvas.text(
+                layout.margin,
+                y + ch.saturating_sub(7 * s) / 2,
+                &format!("{} bp", length),
+                TEXT,
+            );
+            let row_cells = self.cells.get(row * cols..(row + 1) * cols).unwrap_or(&[]);
+            // Runs of equal color as one rectangle keep the SVG small
+            let mut col = 0;
+            while col < row_cells.len() {
+                let run = row_cells[col..]
+                    .iter()
+                    .take_while(|&&c| c == row_cells[col])
+                    .count();
+                canvas.rect(
+                    layout.grid_x + col as u32 * cw,
+                    y,
+                    run as u32 * cw,
+                    ch,
+                    row_cells[col],
+                );
+                col += run;
+            }
+        }
+
+        for (line, range) in layout.legend_lines.iter().enumerate() {
+            let y = layout.legend_y + line as u32 * 12 * s;
+            let mut x = layout.margin;
+            for entry in &self.legend[range.clone()] {
+                let mut label_x = x;
+                if let Some(color) = entry.color {
+                    canvas.rect(x, y, 7 * s, 7 * s, color);
+                    label_x += 10 * s;
+                }
+                canvas.text(label_x, y, &entry.label, TEXT);
+                x += legend_entry_width(entry, s);
+            }
+        }
+    }
+
+    /// Render to SVG. Text is set in the viewer's monospace font, so its
+    /// width matches the PNG layout closely but not exactly.
+    pub fn to_svg(&self, size: FigureSize) -> String {
+        let layout = Layout::new(self, size);
+        let mut svg = SvgCanvas {
+            out: String::new(),
+            scale: layout.size.text_scale,
+        };
+        svg.out.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"{f}\" \
+             shape-rendering=\"crispEdges\">\n",
+            w = layout.width,
+            h = layout.height,
+            f = 10 * layout.size.text_scale
+        ));
+        self.draw(&layout, &mut svg);
+        svg.out.push_str("</svg>\n");
+        svg.out
+    }
+
+    /// Render to PNG (8-bit RGB, zlib-compressed).
+    pub fn to_png(&self, size: FigureSize) -> Result<Vec<u8>, String> {
+        let layout = Layout::new(self, size);
+        let pixels = layout.width as u64 * layout.height as u64;
+        if pixels > MAX_FIGURE_PIXELS {
+            return Err(format!(
+                "Image would be {}×{} px; reduce the cell size",
+                layout.width, layout.height
+            ));
+        }
+        let mut canvas = PixelCanvas {
+            width: layout.width,
+            height: layout.height,
+            scale: layout.size.text_scale,
+            pixels: vec![BACKGROUND; pixels as usize],
+        };
+        self.draw(&layout, &mut canvas);
+        encode_png(canvas.width, canvas.height, &canvas.pixels).map_err(|e| e.to_string())
+    }
+}
+
+struct SvgCanvas {
+    out: String,
+    scale: u32,
+}
+
+fn svg_color([r, g, b]: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+impl Canvas for SvgCanvas {
+    fn rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Rgb) {
+        self.out.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            x,
+            y,
+            w,
+            h,
+            svg_color(color)
+        ));
+    }
+
+    fn text(&mut self, x: u32, y: u32, text: &str, color: Rgb) {
+        let escaped = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        // Baseline at the bottom of the 7-unit glyph cell
+        self.out.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>\n",
+            x,
+            y + 7 * self.scale,
+            svg_color(color),
+            escaped
+        ));
+    }
+}
+
+struct PixelCanvas {
+    width: u32,
+    height: u32,
+    scale: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Canvas for PixelCanvas {
+    fn rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: Rgb) {
+        let x_end = (x + w).min(self.width) as usize;
+        for row in y.min(self.height)..(y + h).min(self.height) {
+            let start = row as usize * self.width as usize;
+            self.pixels[start + (x as usize).min(x_end)..start + x_end].fill(color);
+        }
+    }
+
+    fn text(&mut self, x: u32, y: u32, text: &str, color: Rgb) {
+        let s = self.scale;
+        for (i, c) in text.chars().enumerate() {
+            let glyph_x = x + i as u32 * 6 * s;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0x10 >> col) != 0 {
+                        self.rect(glyph_x + col * s, y + row as u32 * s, s, s, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 5×7 bitmap of a character, one row per byte (bit 4 = left column).
+/// Lower case is drawn as upper case; unknown characters are blank.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '±' => [0x04, 0x04, 0x1F, 0x04, 0x04, 0x00, 0x1F],
+        '×' => [0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        _ => [0; 7],
+    }
+}
+
+/// Minimal PNG writer: 8-bit RGB, no filtering, one IDAT chunk
+fn encode_png(width: u32, height: u32, pixels: &[Rgb]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks(width as usize) {
+        // Filter type 0 (None) before each scanline
+        encoder.write_all(&[0])?;
+        encoder.write_all(row.as_flattened())?;
+    }
+    let data = encoder.finish()?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, color type 2 (RGB), default compression/filter, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, body) in [(b"IHDR", &header), (b"IDAT", &data), (b"IEND", &Vec::new())] {
+        png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(body);
+        let mut crc = flate2::Crc::new();
+        crc.update(kind);
+        crc.update(body);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    fn figure() -> HeatmapFigure {
+        HeatmapFigure {
+            title: "Variants <= 95%".to_string(),
+            lengths: vec![18, 20],
+            positions: vec![0, 5, 10],
+            cells: vec![
+                [10, 200, 10],
+                [10, 200, 10],
+                [200, 10, 10],
+                [40, 40, 40],
+                [10, 200, 10],
+                [10, 200, 10],
+            ],
+            sequence_track: vec![
+                ('A', [100, 200, 100]),
+                ('C', [100, 150, 255]),
+                ('G', [255, 200, 60]),
+            ],
+            legend: vec![
+                LegendEntry {
+                    color: None,
+                    label: "Legend:".to_string(),
+                },
+                LegendEntry {
+                    color: Some([10, 200, 10]),
+                    label: "<=1".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_png_pixels() {
+        let size = FigureSize {
+            cell_width: 8,
+            cell_height: 10,
+            text_scale: 1,
+        };
+        let figure = figure();
+        let png = figure.to_png(size).unwrap();
+        let (width, height) = figure.dimensions(size);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(
+            &png[16..24],
+            [width.to_be_bytes(), height.to_be_bytes()].concat()
+        );
+        // Constant CRC of an empty IEND chunk
+        assert_eq!(&png[png.len() - 4..], [0xAE, 0x42, 0x60, 0x82]);
+
+        // IDAT follows the 8-byte signature and the 25-byte IHDR chunk
+        let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&png[41..41 + idat_len])
+            .read_to_end(&mut raw)
+            .unwrap();
+        let stride = 1 + 3 * width as usize;
+        assert_eq!(raw.len(), stride * height as usize);
+
+        let layout = Layout::new(&figure, size);
+        let pixel = |x: u32, y: u32| {
+            let i = y as usize * stride + 1 + 3 * x as usize;
+            [raw[i], raw[i + 1], raw[i + 2]]
+        };
+        // Middle of the third cell of the first row, and of the second row's first
+        assert_eq!(
+            pixel(layout.grid_x + 2 * 8 + 4, layout.grid_y + 5),
+            [200, 10, 10]
+        );
+        assert_eq!(pixel(layout.grid_x + 4, layout.grid_y + 15), [40, 40, 40]);
+        assert_eq!(pixel(0, 0), BACKGROUND);
+    }
+
+    #[test]
+    fn test_svg_output() {
+        let svg = figure().to_svg(FigureSize::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(">Variants &lt;= 95%</text>"));
+        assert!(svg.contains(">20 bp</text>"));
+        // The two green cells of the first row are one rectangle
+        assert!(svg.contains("width=\"8\" height=\"24\" fill=\"#0ac80a\""));
+    }
+
+    #[test]
+    fn test_png_size_limit() {
+        let figure = HeatmapFigure {
+            lengths: vec![20],
+            positions: (0..100_000).collect(),
+            cells: vec![[0, 0, 0]; 100_000],
+            ..Default::default()
+        };
+        let size = FigureSize {
+            cell_width: 100,
+            cell_height: 1000,
+            text_scale: 1,
+        };
+        assert!(figure.to_png(size).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Window filters shared by the heatmap, candidate searches, and exports
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{ExclusivityResult, PositionResult, ScreeningResults};
+
+/// Calculate effective minimum mismatches after leaving out the sequences
+/// ignored by name, then ignoring the best N of the rest. Returns None when
+/// every remaining exclusivity sequence is a no-match.
+pub fn effective_min_mismatches(excl: &ExclusivityResult, ignore_count: usize) -> Option<u32> {
+    if ignore_count == 0 && excl.ignored_matches() == 0 {
+        return excl.min_mismatches;
+    }
+
+    let mut remaining_ignore = ignore_count;
+    for bucket in &excl.mismatch_histogram {
+        if bucket.mismatches == u32::MAX {
+            // No-match bucket — these are already "infinite", skip them
+            continue;
+        }
+        let count = bucket.count.saturating_sub(bucket.ignored_count);
+        if count <= remaining_ignore {
+            remaining_ignore -= count;
+        } else {
+            // This bucket has sequences remaining after ignoring
+            return Some(bucket.mismatches);
+        }
+    }
+
+    // All matched sequences were ignored — effectively all are no-match
+    None
+}
+
+/// Keeps only windows whose effective minimum exclusivity mismatches exceed a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExclusivityFilter {
+    /// Windows pass only if effective min mismatches is strictly greater than this
+    pub min_mismatches_exceeding: u32,
+    /// Number of closest exclusivity sequences ignored before taking the minimum
+    pub ignore_count: usize,
+}
+
+impl ExclusivityFilter {
+    /// Windows without exclusivity data are not filtered.
+    pub fn passes(&self, pr: &PositionResult) -> bool {
+        match &pr.exclusivity {
+            None => true,
+            Some(excl) => match effective_min_mismatches(excl, self.ignore_count) {
+                None => true,
+                Some(mm) => mm > self.min_mismatches_exceeding,
+            },
+        }
+    }
+}
+
+/// Copy of `results` containing only the windows that pass the filter.
+pub fn filter_results(results: &ScreeningResults, filter: &ExclusivityFilter) -> ScreeningResults {
+    let mut filtered = results.clone();
+    for length_result in filtered.results_by_length.values_mut() {
+        length_result.positions.retain(|pr| filter.passes(pr));
+    }
+    filtered
+}
+
+/// Copy of `results` for archiving: every per-window metric, but each
+/// variant list cut to its `keep_variants` most frequent variants (none for
+/// 0). Recounting variants at another coverage threshold needs the full
+/// lists, so the copy records how many were kept.
+pub fn summary_results(results: &ScreeningResults, keep_variants: usize) -> ScreeningResults {
+    let mut summary = results.clone();
+    for length_result in summary.results_by_length.values_mut() {
+        for pr in &mut length_result.positions {
+            pr.analysis.variants.truncate(keep_variants);
+        }
+    }
+    summary.variants_kept = Some(
+        results
+            .variants_kept
+            .map_or(keep_variants, |kept| kept.min(keep_variants)),
+    );
+    summary
+}
+
+/// Case-insensitive glob match of a whole sequence name: `*` matches any
+/// run of characters and `?` a single character.
+pub fn name_matches_pattern(name: &str, pattern: &str) -> bool {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let pattern: Vec<char> = pattern.trim().to_lowercase().chars().collect();
+
+    // Iterative wildcard matching with backtracking to the last `*`
+    let (mut n, mut p) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            n += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = last_star {
+            p = star_p + 1;
+            n = star_n + 1;
+            last_star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::{
+        AnalysisParams, LengthResult, MismatchBucket, Variant, WindowAnalysisResult,
+    };
+
+    fn excl(buckets: &[(u32, usize)]) -> ExclusivityResult {
+        let mismatch_histogram: Vec<MismatchBucket> = buckets
+            .iter()
+            .map(|&(mismatches, count)| MismatchBucket {
+                mismatches,
+                count,
+                ignored_count: 0,
+                example_name: String::new(),
+            })
+            .collect();
+        ExclusivityResult {
+            total_sequences: buckets.iter().map(|b| b.1).sum(),
+            no_match_count: 0,
+            min_mismatches: buckets.first().map(|b| b.0).filter(|&m| m != u32::MAX),
+            mismatch_histogram,
+            taxon_min_mismatches: Vec::new(),
+            min_three_prime_mismatches: None,
+        }
+    }
+
+    #[test]
+    fn test_effective_min_mismatches_ignores_best() {
+        let e = excl(&[(1, 2), (3, 1), (u32::MAX, 4)]);
+        assert_eq!(effective_min_mismatches(&e, 0), Some(1));
+        assert_eq!(effective_min_mismatches(&e, 2), Some(3));
+        assert_eq!(effective_min_mismatches(&e, 3), None);
+
+        // Sequences ignored by name come off before the best N
+        let ignoring = |n: usize| {
+            let mut named = e.clone();
+            named.mismatch_histogram[0].ignored_count = n;
+            named
+        };
+        assert_eq!(effective_min_mismatches(&ignoring(1), 0), Some(1));
+        assert_eq!(effective_min_mismatches(&ignoring(1), 1), Some(3));
+        assert_eq!(effective_min_mismatches(&ignoring(2), 0), Some(3));
+    }
+
+    #[test]
+    fn test_filter_passes() {
+        let pr = PositionResult {
+            position: 0,
+            variants_needed: 1,
+            analysis: WindowAnalysisResult::default(),
+            exclusivity: Some(excl(&[(2, 1), (5, 3)])),
+            group_exclusivity: Vec::new(),
+            tm: None,
+            gc_percent: None,
+        };
+        let strict = ExclusivityFilter {
+            min_mismatches_exceeding: 2,
+            ignore_count: 0,
+        };
+        assert!(!strict.passes(&pr));
+        let ignoring = ExclusivityFilter {
+            ignore_count: 1,
+            ..strict
+        };
+        assert!(ignoring.passes(&pr));
+    }
+
+    #[test]
+    fn test_summary_results() {
+        let variant = |sequence: &str| Variant {
+            sequence: sequence.to_string(),
+            count: 1,
+            percentage: 25.0,
+        };
+        let mut results =
+            ScreeningResults::new(AnalysisParams::default(), 4, 4, "ACGT".into(), false, None);
+        results.results_by_length.insert(
+            4,
+            LengthResult {
+                oligo_length: 4,
+                positions: vec![PositionResult {
+                    position: 0,
+                    variants_needed: 3,
+                    analysis: WindowAnalysisResult {
+                        variants: vec![variant("ACGT"), variant("ACGA"), variant("ACGC")],
+                        coverage_at_threshold: 75.0,
+                        ..Default::default()
+                    },
+                    exclusivity: None,
+                    group_exclusivity: Vec::new(),
+                    tm: None,
+                    gc_percent: None,
+                }],
+            },
+        );
+
+        let summary = summary_results(&results, 2);
+        let pr = &summary.results_by_length[&4].positions[0];
+        assert_eq!(pr.analysis.variants.len(), 2);
+        assert_eq!(pr.variants_needed, 3);
+        assert_eq!(pr.analysis.coverage_at_threshold, 75.0);
+        assert_eq!(summary.variants_kept, Some(2));
+        assert_eq!(results.variants_kept, None);
+
+        // Summarizing a summary never claims more variants than it has
+        assert_eq!(summary_results(&summary, 10).variants_kept, Some(2));
+        let metrics_only = summary_results(&results, 0);
+        let pr = &metrics_only.results_by_length[&4].positions[0];
+        assert!(pr.analysis.variants.is_empty());
+    }
+
+    #[test]
+    fn test_name_matches_pattern() {
+        assert!(name_matches_pattern("Synthetic construct 12", "synthetic*"));
+        assert!(name_matches_pattern("NZ_CP0001 vector", "*VECTOR"));
+        assert!(name_matches_pattern("abc", "a?c"));
+        assert!(name_matches_pattern("abc", "*"));
+        assert!(!name_matches_pattern("abcd", "a?c"));
+        assert!(!name_matches_pattern("xsynthetic", "synthetic*"));
+        assert!(name_matches_pattern("a_b_c", "*_*_c"));
+    }
+}
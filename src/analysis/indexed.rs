@@ -0,0 +1,235 @@
+//! Indexed results files with lazily loaded window detail
+//!
+//! Variant lists dominate a results file but only the detail window needs
+//! them. The indexed format stores each window's variant list as its own
+//! record, followed by everything else (every per-window metric and
+//! exclusivity histogram, enough to draw the heatmap) as a summary with the
+//! byte offset of each record. Opening a file reads only the summary; a
+//! window's variants are read from disk when its detail window opens.
+//!
+//! Layout: a magic line, one JSON line per window's variant list, the summary
+//! JSON line, and a last line holding the summary's byte offset.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::filter::summary_results;
+use super::types::{ScreeningResults, Variant};
+
+/// File extension of indexed results files
+pub const INDEXED_RESULTS_EXTENSION: &str = "osidx";
+
+const MAGIC: &[u8] = b"OLIGOSCREEN-INDEXED 1\n";
+/// Digits of the summary offset on the last line
+const TRAILER_DIGITS: usize = 20;
+
+#[derive(Serialize, Deserialize)]
+struct IndexedSummary {
+    results: ScreeningResults,
+    /// (oligo length, position, byte offset, byte length) of each variant record
+    index: Vec<(u32, usize, u64, u64)>,
+}
+
+/// Write `results` as an indexed results file.
+pub fn write_indexed_results(results: &ScreeningResults, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut out = BufWriter::new(file);
+    out.write_all(MAGIC).map_err(|e| e.to_string())?;
+    let mut offset = MAGIC.len() as u64;
+
+    let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+    lengths.sort();
+    let mut index = Vec::new();
+    for length in lengths {
+        for pr in &results.results_by_length[&length].positions {
+            let mut record =
+                serde_json::to_vec(&pr.analysis.variants).map_err(|e| e.to_string())?;
+            record.push(b'\n');
+            out.write_all(&record).map_err(|e| e.to_string())?;
+            index.push((length, pr.position, offset, record.len() as u64));
+            offset += record.len() as u64;
+        }
+    }
+
+    let mut summary = summary_results(results, 0);
+    // The records hold the variant lists exactly as `results` has them
+    summary.variants_kept = results.variants_kept;
+    let summary = IndexedSummary {
+        results: summary,
+        index,
+    };
+    serde_json::to_writer(&mut out, &summary).map_err(|e| e.to_string())?;
+    writeln!(out).map_err(|e| e.to_string())?;
+    writeln!(out, "{:0width$}", offset, width = TRAILER_DIGITS).map_err(|e| e.to_string())?;
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// An open indexed results file
+#[derive(Debug)]
+pub struct IndexedResults {
+    path: PathBuf,
+    index: HashMap<(u32, usize), (u64, u64)>,
+}
+
+impl IndexedResults {
+    /// Read the summary and index of an indexed results file. The returned
+    /// results have empty variant lists; see `load_variants`.
+    pub fn open(path: &Path) -> Result<(Self, ScreeningResults), String> {
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        let mut magic = vec![0u8; MAGIC.len()];
+        if file.read_exact(&mut magic).is_err() || magic != MAGIC {
+            return Err("not an indexed results file".to_string());
+        }
+
+        let trailer_len = TRAILER_DIGITS as u64 + 1;
+        let end = file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+        let summary_end = end
+            .checked_sub(trailer_len)
+            .filter(|&e| e >= MAGIC.len() as u64)
+            .ok_or("indexed results file is truncated")?;
+        file.seek(SeekFrom::Start(summary_end))
+            .map_err(|e| e.to_string())?;
+        let mut trailer = String::new();
+        Read::by_ref(&mut file)
+            .take(TRAILER_DIGITS as u64)
+            .read_to_string(&mut trailer)
+            .map_err(|e| e.to_string())?;
+        let summary_start: u64 = trailer
+            .parse()
+            .ok()
+            .filter(|&start| start <= summary_end)
+            .ok_or("indexed results file has a damaged trailer")?;
+
+        file.seek(SeekFrom::Start(summary_start))
+            .map_err(|e| e.to_string())?;
+        let mut json = Vec::new();
+        file.take(summary_end - summary_start)
+            .read_to_end(&mut json)
+            .map_err(|e| e.to_string())?;
+        let summary: IndexedSummary = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+
+        let index = summary
+            .index
+            .into_iter()
+            .map(|(length, position, offset, len)| ((length, position), (offset, len)))
+            .collect();
+        let indexed = Self {
+            path: path.to_path_buf(),
+            index,
+        };
+        Ok((indexed, summary.results))
+    }
+
+    /// Read one window's variant list from disk.
+    pub fn load_variants(&self, length: u32, position: usize) -> Result<Vec<Variant>, String> {
+        let &(offset, len) = self.index.get(&(length, position)).ok_or_else(|| {
+            format!(
+                "no stored variants for {} bp at position {}",
+                length,
+                position + 1
+            )
+        })?;
+        let mut file = File::open(&self.path).map_err(|e| e.to_string())?;
+        read_variant_record(&mut file, offset, len)
+    }
+
+    /// Read every window's variant list into `results`, the summary returned
+    /// by `open`.
+    pub fn load_all_variants(&self, results: &mut ScreeningResults) -> Result<(), String> {
+        let mut file = File::open(&self.path).map_err(|e| e.to_string())?;
+        for (&length, length_result) in results.results_by_length.iter_mut() {
+            for pr in &mut length_result.positions {
+                if let Some(&(offset, len)) = self.index.get(&(length, pr.position)) {
+                    pr.analysis.variants = read_variant_record(&mut file, offset, len)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_variant_record(file: &mut File, offset: u64, len: u64) -> Result<Vec<Variant>, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+    let mut record = vec![0u8; len as usize];
+    file.read_exact(&mut record).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&record).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    #[test]
+    fn test_indexed_results_round_trip() {
+        let template = "TATGGTACGTCATGTTCTAG";
+        let mut variant_ref = template.to_string();
+        variant_ref.replace_range(6..7, "C");
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string(), "R2".to_string()],
+                sequences: vec![template.to_string(), variant_ref],
+            },
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 11,
+                resolution: 5,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "oligoscreen_indexed_{}.{}",
+            std::process::id(),
+            INDEXED_RESULTS_EXTENSION
+        ));
+        write_indexed_results(&results, &path).unwrap();
+        let (indexed, summary) = IndexedResults::open(&path).unwrap();
+
+        // The summary carries the metrics but no variant lists
+        for (length, length_result) in &results.results_by_length {
+            let summary_positions = &summary.results_by_length[length].positions;
+            assert_eq!(summary_positions.len(), length_result.positions.len());
+            for (pr, stored) in length_result.positions.iter().zip(summary_positions) {
+                assert_eq!(stored.variants_needed, pr.variants_needed);
+                assert!(stored.analysis.variants.is_empty());
+
+                let variants = indexed.load_variants(*length, pr.position).unwrap();
+                let sequences: Vec<&str> = variants.iter().map(|v| v.sequence.as_str()).collect();
+                let expected: Vec<&str> = pr
+                    .analysis
+                    .variants
+                    .iter()
+                    .map(|v| v.sequence.as_str())
+                    .collect();
+                assert_eq!(sequences, expected);
+            }
+        }
+        assert_eq!(summary.variants_kept, None);
+        assert!(indexed.load_variants(10, 3).is_err());
+
+        let mut full = summary.clone();
+        indexed.load_all_variants(&mut full).unwrap();
+        let first = &full.results_by_length[&10].positions[0];
+        assert_eq!(first.analysis.variants.len(), 2);
+
+        // Plain JSON is rejected rather than misread
+        std::fs::write(&path, "{}").unwrap();
+        assert!(IndexedResults::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+//! Reference panel inspection
+//!
+//! Summarizes a loaded reference set before screening: length distribution,
+//! ambiguity and gap content per record, exact duplicates, and records that
+//! share unusually little sequence with the template. Problems in the panel
+//! (truncated records, low-quality assemblies, mislabeled species) show up
+//! here rather than as odd results after a long run.
+
+use std::collections::{HashMap, HashSet};
+
+use super::fasta::ReferenceData;
+use super::iupac::{is_ambiguous_base, is_gap, reverse_complement};
+
+/// Thresholds for flagging records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InspectParams {
+    pub histogram_bins: usize,
+    /// Flag records with more than this percentage of ambiguous bases
+    pub max_ambiguity_percent: f64,
+    /// Flag records shorter than median / factor or longer than median × factor
+    pub length_outlier_factor: f64,
+    /// k-mer size used to compare records with the template
+    pub kmer_size: usize,
+    /// Flag records sharing less than this fraction of the panel's median
+    /// template k-mer share
+    pub divergence_ratio: f64,
+}
+
+impl Default for InspectParams {
+    fn default() -> Self {
+        Self {
+            histogram_bins: 20,
+            max_ambiguity_percent: 1.0,
+            length_outlier_factor: 2.0,
+            kmer_size: 12,
+            divergence_ratio: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordIssue {
+    HighAmbiguity,
+    LengthOutlier,
+    Duplicate,
+    Divergent,
+}
+
+impl RecordIssue {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::HighAmbiguity => "high ambiguity",
+            Self::LengthOutlier => "length outlier",
+            Self::Duplicate => "duplicate",
+            Self::Divergent => "divergent",
+        }
+    }
+}
+
+/// Per-record statistics
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordStats {
+    /// Index into the reference set
+    pub index: usize,
+    pub length: usize,
+    pub ambiguity_count: usize,
+    pub gap_count: usize,
+    /// Fraction of the template's k-mers found in the record (either strand);
+    /// None without a template
+    pub template_kmer_share: Option<f64>,
+    pub issues: Vec<RecordIssue>,
+}
+
+impl RecordStats {
+    pub fn ambiguity_percent(&self) -> f64 {
+        if self.length == 0 {
+            0.0
+        } else {
+            self.ambiguity_count as f64 / self.length as f64 * 100.0
+        }
+    }
+}
+
+/// One bar of the length histogram, covering lengths `start..=end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthBin {
+    pub start: usize,
+    pub end: usize,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelReport {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub median_length: usize,
+    pub length_histogram: Vec<LengthBin>,
+    pub records: Vec<RecordStats>,
+    /// Groups of record indices with identical sequences (groups of 2+)
+    pub duplicate_groups: Vec<Vec<usize>>,
+    /// Names used by more than one record
+    pub duplicate_names: Vec<String>,
+    pub median_template_kmer_share: Option<f64>,
+}
+
+impl PanelReport {
+    /// Records with at least one issue
+    pub fn flagged(&self) -> impl Iterator<Item = &RecordStats> {
+        self.records.iter().filter(|r| !r.issues.is_empty())
+    }
+}
+
+/// Inspect a reference panel. With a template, records are additionally
+/// compared by the share of template k-mers they contain.
+pub fn inspect_references(
+    references: &ReferenceData,
+    template: Option<&str>,
+    params: &InspectParams,
+) -> PanelReport {
+    let lengths: Vec<usize> = references.sequences.iter().map(|s| s.len()).collect();
+    let min_length = lengths.iter().copied().min().unwrap_or(0);
+    let max_length = lengths.iter().copied().max().unwrap_or(0);
+    let median_length = median(lengths.iter().map(|&l| l as f64)).unwrap_or(0.0) as usize;
+
+    let template_rc = template.map(reverse_complement);
+    let template_kmers = template.zip(template_rc.as_deref()).map(|(t, rc)| {
+        let mut kmers = kmer_set(t, params.kmer_size);
+        kmers.extend(kmer_set(rc, params.kmer_size));
+        let total = t.len().saturating_sub(params.kmer_size.saturating_sub(1));
+        (kmers, total.max(1))
+    });
+
+    let mut records: Vec<RecordStats> = references
+        .sequences
+        .iter()
+        .enumerate()
+        .map(|(index, seq)| {
+            let template_kmer_share = template_kmers.as_ref().map(|(kmers, total)| {
+                let shared = kmer_set(seq, params.kmer_size).intersection(kmers).count();
+                // Both strands are in the set, so a perfect match shares `total` k-mers
+                (shared as f64 / *total as f64).min(1.0)
+            });
+            RecordStats {
+                index,
+                length: seq.len(),
+                ambiguity_count: seq.chars().filter(|&c| is_ambiguous_base(c)).count(),
+                gap_count: seq.chars().filter(|&c| is_gap(c)).count(),
+                template_kmer_share,
+                issues: Vec::new(),
+            }
+        })
+        .collect();
+
+    let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, seq) in references.sequences.iter().enumerate() {
+        groups.entry(seq.as_str()).or_default().push(i);
+    }
+    let mut duplicate_groups: Vec<Vec<usize>> =
+        groups.into_values().filter(|g| g.len() > 1).collect();
+    duplicate_groups.sort();
+
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for name in &references.names {
+        *name_counts.entry(name.as_str()).or_default() += 1;
+    }
+    let mut duplicate_names: Vec<String> = name_counts
+        .into_iter()
+        .filter(|&(_, n)| n > 1)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    duplicate_names.sort();
+
+    let median_template_kmer_share = median(records.iter().filter_map(|r| r.template_kmer_share));
+
+    for record in &mut records {
+        if record.ambiguity_percent() > params.max_ambiguity_percent {
+            record.issues.push(RecordIssue::HighAmbiguity);
+        }
+        let len = record.length as f64;
+        let med = median_length as f64;
+        if med > 0.0
+            && (len < med / params.length_outlier_factor
+                || len > med * params.length_outlier_factor)
+        {
+            record.issues.push(RecordIssue::LengthOutlier);
+        }
+        if record
+            .template_kmer_share
+            .zip(median_template_kmer_share)
+            .is_some_and(|(share, med_share)| share < med_share * params.divergence_ratio)
+        {
+            record.issues.push(RecordIssue::Divergent);
+        }
+    }
+    for group in &duplicate_groups {
+        // The first record of a group is kept; later copies are flagged
+        for &i in &group[1..] {
+            records[i].issues.push(RecordIssue::Duplicate);
+        }
+    }
+
+    PanelReport {
+        min_length,
+        max_length,
+        median_length,
+        length_histogram: length_histogram(&lengths, params.histogram_bins),
+        records,
+        duplicate_groups,
+        duplicate_names,
+        median_template_kmer_share,
+    }
+}
+
+/// Equal-width bins spanning min..=max length.
+fn length_histogram(lengths: &[usize], bins: usize) -> Vec<LengthBin> {
+    let (Some(&min), Some(&max)) = (lengths.iter().min(), lengths.iter().max()) else {
+        return Vec::new();
+    };
+    let span = max - min + 1;
+    let bins = bins.clamp(1, span);
+    let width = span.div_ceil(bins);
+    let mut histogram: Vec<LengthBin> = (0..bins)
+        .map(|b| LengthBin {
+            start: min + b * width,
+            end: (min + (b + 1) * width - 1).min(max),
+            count: 0,
+        })
+        .filter(|bin| bin.start <= max)
+        .collect();
+    for &len in lengths {
+        histogram[(len - min) / width].count += 1;
+    }
+    histogram
+}
+
+fn kmer_set(seq: &str, k: usize) -> HashSet<&[u8]> {
+    let bytes = seq.as_bytes();
+    if k == 0 || bytes.len() < k {
+        return HashSet::new();
+    }
+    bytes.windows(k).collect()
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_references() {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let references = ReferenceData {
+            names: vec!["A", "B", "B", "C", "D"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            sequences: vec![
+                format!("GG{}GG", template),
+                format!("GG{}GG", template),
+                reverse_complement(template),
+                format!("{}N{}", &template[..29], &template[30..]),
+                "CCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCC"
+                    .to_string(),
+            ],
+        };
+
+        let report = inspect_references(&references, Some(template), &InspectParams::default());
+        assert_eq!(report.min_length, 31);
+        assert_eq!(report.max_length, 71);
+        assert_eq!(report.median_length, 35);
+        assert_eq!(
+            report
+                .length_histogram
+                .iter()
+                .map(|b| b.count)
+                .sum::<usize>(),
+            5
+        );
+        assert_eq!(report.duplicate_groups, vec![vec![0, 1]]);
+        assert_eq!(report.duplicate_names, vec!["B".to_string()]);
+
+        let issues: Vec<&[RecordIssue]> =
+            report.records.iter().map(|r| r.issues.as_slice()).collect();
+        assert!(issues[0].is_empty());
+        assert_eq!(issues[1], [RecordIssue::Duplicate]);
+        // Reverse-strand records still share all template k-mers
+        assert!(issues[2].is_empty());
+        assert_eq!(report.records[2].template_kmer_share, Some(1.0));
+        assert_eq!(issues[3], [RecordIssue::HighAmbiguity]);
+        assert_eq!(
+            issues[4],
+            [RecordIssue::LengthOutlier, RecordIssue::Divergent]
+        );
+    }
+
+    #[test]
+    fn test_length_histogram() {
+        let histogram = length_histogram(&[10, 11, 19, 20], 2);
+        assert_eq!(
+            histogram,
+            vec![
+                LengthBin {
+                    start: 10,
+                    end: 15,
+                    count: 2
+                },
+                LengthBin {
+                    start: 16,
+                    end: 20,
+                    count: 2
+                },
+            ]
+        );
+    }
+}
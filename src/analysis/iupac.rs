@@ -107,19 +107,26 @@ pub fn iupac_to_bases(code: char) -> Option<&'static HashSet<char>> {
     IUPAC_TO_BASES.get(&code)
 }
 
-/// Check if a sequence matches a consensus (with ambiguity codes)
+/// Check if a sequence matches a consensus (with ambiguity codes). An
+/// ambiguity code in the sequence matches only when the consensus covers
+/// every base it stands for.
 pub fn sequence_matches_consensus(seq: &str, consensus: &str) -> bool {
     if seq.len() != consensus.len() {
         return false;
     }
 
     for (s, c) in seq.chars().zip(consensus.chars()) {
-        if let Some(allowed) = IUPAC_TO_BASES.get(&c) {
-            if !allowed.contains(&s) {
-                return false;
+        match (IUPAC_TO_BASES.get(&s), IUPAC_TO_BASES.get(&c)) {
+            (Some(bases), Some(allowed)) => {
+                if !bases.is_subset(allowed) {
+                    return false;
+                }
+            }
+            _ => {
+                if s != c {
+                    return false;
+                }
             }
-        } else if s != c {
-            return false;
         }
     }
     true
@@ -160,14 +167,30 @@ pub fn create_consensus(sequences: &[&str], exclude_n: bool) -> (String, usize,
     (consensus, ambiguity_count, true)
 }
 
-/// Compute the reverse complement of a DNA sequence
+/// Compute the reverse complement of a DNA sequence. Lowercase bases (such
+/// as inserted bases in gap notation) stay lowercase.
 pub fn reverse_complement(seq: &str) -> String {
     seq.chars()
         .rev()
-        .map(|c| *COMPLEMENT.get(&c).unwrap_or(&c))
+        .map(|c| {
+            let complement = *COMPLEMENT.get(&c.to_ascii_uppercase()).unwrap_or(&c);
+            if c.is_ascii_lowercase() {
+                complement.to_ascii_lowercase()
+            } else {
+                complement
+            }
+        })
         .collect()
 }
 
+/// Inserted plus deleted bases of a sequence in gap notation: `-` for an
+/// oligo base deleted in the reference, lowercase for a base inserted in it
+pub fn indel_count(seq: &str) -> usize {
+    seq.bytes()
+        .filter(|&b| b == b'-' || b.is_ascii_lowercase())
+        .count()
+}
+
 /// Count ambiguities in a sequence
 pub fn count_ambiguities(seq: &str) -> usize {
     seq.chars().filter(|&c| is_ambiguous_base(c)).count()
@@ -176,7 +199,7 @@ pub fn count_ambiguities(seq: &str) -> usize {
 // ── Bitmask-based IUPAC operations (zero heap allocation) ──────────────────
 
 /// Bitmask representation: bit 0 = A, bit 1 = C, bit 2 = G, bit 3 = T
-
+///
 /// Lookup table: 4-bit bitmask index -> IUPAC code byte.
 /// Index 0 (no bases) maps to b'?' and should not occur with valid DNA data.
 pub const IUPAC_FROM_MASK: [u8; 16] = [
@@ -239,13 +262,50 @@ pub fn sequence_matches_consensus_bytes(seq: &[u8], consensus: &[u8]) -> bool {
     for i in 0..seq.len() {
         let base_mask = base_to_bit(seq[i]);
         let cons_mask = iupac_to_mask(consensus[i]);
-        if base_mask & cons_mask == 0 {
+        if base_mask == 0 || base_mask & !cons_mask != 0 {
             return false;
         }
     }
     true
 }
 
+/// Number of concrete sequences a (possibly degenerate) sequence expands to.
+/// Saturates at `u64::MAX` for very long, highly degenerate sequences.
+pub fn degeneracy(seq: &[u8]) -> u64 {
+    seq.iter().fold(1u64, |acc, &b| {
+        acc.saturating_mul(iupac_to_mask(b).count_ones().max(1) as u64)
+    })
+}
+
+/// The concrete sequences a degenerate sequence stands for, or None when
+/// there are more than `limit`.
+pub fn expand_degenerate(seq: &[u8], limit: u64) -> Option<Vec<Vec<u8>>> {
+    if degeneracy(seq) > limit {
+        return None;
+    }
+    let mut expanded = vec![Vec::with_capacity(seq.len())];
+    for &b in seq {
+        let mask = iupac_to_mask(b);
+        if mask.count_ones() <= 1 {
+            expanded.iter_mut().for_each(|s| s.push(b));
+            continue;
+        }
+        expanded = expanded
+            .into_iter()
+            .flat_map(|prefix| {
+                (0..4)
+                    .filter(move |&bit| mask & (1 << bit) != 0)
+                    .map(move |bit| {
+                        let mut s = prefix.clone();
+                        s.push(IUPAC_FROM_MASK[1 << bit]);
+                        s
+                    })
+            })
+            .collect();
+    }
+    Some(expanded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,6 +340,28 @@ mod tests {
         assert!(!sequence_matches_consensus_bytes(b"ACG", b"ACGT"));
     }
 
+    #[test]
+    fn test_degeneracy() {
+        assert_eq!(degeneracy(b"ACGT"), 1);
+        assert_eq!(degeneracy(b"ACGW"), 2);
+        assert_eq!(degeneracy(b"RYGN"), 16);
+    }
+
+    #[test]
+    fn test_expand_degenerate() {
+        assert_eq!(expand_degenerate(b"ACGT", 1), Some(vec![b"ACGT".to_vec()]));
+        assert_eq!(
+            expand_degenerate(b"ARGY", 4),
+            Some(vec![
+                b"AAGC".to_vec(),
+                b"AAGT".to_vec(),
+                b"AGGC".to_vec(),
+                b"AGGT".to_vec()
+            ])
+        );
+        assert_eq!(expand_degenerate(b"NN", 15), None);
+    }
+
     #[test]
     fn test_bitmask_matches_hashset_impl() {
         let cases = vec![
@@ -289,6 +371,10 @@ mod tests {
             ("TCGT", "RCGT", false),
             ("ACGT", "MCGT", true),
             ("GCGT", "MCGT", false),
+            ("RCGT", "RCGT", true),
+            ("RCGT", "NCGT", true),
+            ("RCGT", "ACGT", false),
+            ("NCGT", "MCGT", false),
         ];
         for (seq, cons, expected) in cases {
             assert_eq!(
@@ -0,0 +1,106 @@
+//! Masked template regions
+//!
+//! Windows overlapping a masked region (known primer sites, repeats, ...) are
+//! recorded as skipped without aligning, so they never come up as candidates.
+
+use super::types::AnalysisParams;
+
+/// Skip reason of windows overlapping a masked region
+pub const MASKED_SKIP_REASON: &str = "Overlaps a masked region";
+
+impl AnalysisParams {
+    /// Whether the window of `length` bases at `position` overlaps a masked
+    /// region
+    pub fn window_masked(&self, position: usize, length: usize) -> bool {
+        self.masked_regions
+            .iter()
+            .any(|&(start, end)| position < end && start < position + length)
+    }
+}
+
+/// Parse masked regions given as 1-based inclusive `start-end` (or a single
+/// position), separated by newlines, commas, or semicolons, into sorted and
+/// merged 0-based half-open ranges.
+pub fn parse_masked_regions(text: &str) -> Result<Vec<(usize, usize)>, String> {
+    let mut regions = Vec::new();
+    for item in text
+        .split(['\n', ',', ';'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let number = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid masked region '{}'", item))
+        };
+        let (start, end) = match item.split_once('-') {
+            Some((a, b)) => (number(a)?, number(b)?),
+            None => {
+                let position = number(item)?;
+                (position, position)
+            }
+        };
+        if end < start {
+            return Err(format!("Masked region '{}' ends before it starts", item));
+        }
+        regions.push((start - 1, end));
+    }
+    Ok(merge_regions(regions))
+}
+
+/// Sort regions and merge those that overlap or touch.
+pub fn merge_regions(mut regions: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    regions.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(regions.len());
+    for (start, end) in regions {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Regions as `parse_masked_regions` text, one 1-based `start-end` per line.
+pub fn format_masked_regions(regions: &[(usize, usize)]) -> String {
+    regions
+        .iter()
+        .map(|&(start, end)| format!("{}-{}", start + 1, end))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_masked_regions() {
+        let regions = parse_masked_regions("101-120\n 50 ; 110-130,1-10\n").unwrap();
+        assert_eq!(regions, vec![(0, 10), (49, 50), (100, 130)]);
+        assert_eq!(format_masked_regions(&regions), "1-10\n50-50\n101-130");
+        assert_eq!(
+            parse_masked_regions(&format_masked_regions(&regions)),
+            Ok(regions)
+        );
+
+        assert!(parse_masked_regions("20-10").is_err());
+        assert!(parse_masked_regions("0-10").is_err());
+        assert!(parse_masked_regions("a-b").is_err());
+        assert_eq!(parse_masked_regions(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_window_masked() {
+        let params = AnalysisParams {
+            masked_regions: vec![(100, 130)],
+            ..Default::default()
+        };
+        assert!(!params.window_masked(80, 20));
+        assert!(params.window_masked(81, 20));
+        assert!(params.window_masked(129, 20));
+        assert!(!params.window_masked(130, 20));
+    }
+}
@@ -9,6 +9,39 @@ mod fasta;
 mod analyzer;
 mod pairwise;
 mod screener;
+mod amplicon;
+mod filter;
+mod panels;
+mod estimate;
+mod export;
+mod audit;
+mod config;
+mod query;
+mod inspect;
+mod trim;
+mod thermo;
+mod repro;
+mod compact;
+mod indexed;
+mod panel_cache;
+mod taxon;
+mod figure;
+mod sweep;
+mod comparison;
+mod ranking;
+mod order;
+mod assignment;
+mod annotation;
+mod mask;
+mod anchor;
+mod dedup;
+mod striped;
+mod partial;
+mod store;
+mod consensus;
+mod pair_design;
+mod multiplex;
+mod worklist;
 
 pub use types::*;
 pub use iupac::*;
@@ -16,3 +49,50 @@ pub use fasta::*;
 pub use analyzer::*;
 pub use pairwise::*;
 pub use screener::*;
+pub use amplicon::*;
+pub use filter::*;
+pub use panels::*;
+pub use estimate::*;
+pub use export::*;
+pub use audit::*;
+pub use config::*;
+pub use query::*;
+pub use inspect::*;
+pub use trim::*;
+pub use thermo::*;
+pub use repro::*;
+pub use compact::*;
+pub use indexed::*;
+pub use panel_cache::*;
+pub use taxon::*;
+pub use figure::*;
+pub use sweep::*;
+pub use comparison::*;
+pub use ranking::*;
+pub use order::*;
+pub use assignment::*;
+pub use annotation::*;
+pub use mask::*;
+pub use anchor::*;
+pub use dedup::*;
+pub use striped::*;
+pub use partial::*;
+pub use store::*;
+pub use consensus::*;
+pub use pair_design::*;
+pub use multiplex::*;
+pub use worklist::*;
+
+/// Deterministic pseudo-random sequence for tests
+#[cfg(test)]
+pub(crate) fn random_seq(len: usize, seed: u64) -> String {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            b"ACGT"[(state >> 33) as usize % 4] as char
+        })
+        .collect()
+}
@@ -0,0 +1,182 @@
+//! Multiplex compatibility of the oligos picked for ordering
+//!
+//! Oligos run together in one reaction can pair with each other (cross-
+//! dimers) and bind each other's targets. Every pair of the order selection
+//! gets the ΔG of its most stable dimer, and every oligo is slid along each
+//! other oligo's target region (both strands, without gaps) for the site
+//! with the fewest mismatches.
+
+use super::iupac::{base_to_bit, reverse_complement};
+use super::order::OrderOligo;
+use super::thermo::dimer_dg;
+
+/// The closest site of an oligo in another oligo's target region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossSite {
+    pub mismatches: u32,
+    /// Template position of the site
+    pub position: usize,
+    /// The oligo binds as its reverse complement
+    pub antisense: bool,
+}
+
+/// Pairwise checks of a set of oligos, indexed like the oligos
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiplexCheck {
+    /// ΔG (kcal/mol at 37 °C) of the most stable dimer of each pair, self-
+    /// dimers on the diagonal; 0 when none is stable
+    pub dimer_dg: Vec<Vec<f64>>,
+    /// `[i][j]`: the closest site of oligo `i` in oligo `j`'s target region.
+    /// None on the diagonal, when `j` has no recorded target, or when the
+    /// region is shorter than oligo `i`.
+    pub cross_sites: Vec<Vec<Option<CrossSite>>>,
+}
+
+/// Uppercase DNA bases with U read as T
+fn dna_bases(seq: &str) -> Vec<u8> {
+    seq.bytes()
+        .map(|b| match b.to_ascii_uppercase() {
+            b'U' => b'T',
+            b => b,
+        })
+        .collect()
+}
+
+/// Mismatches of `oligo` against `site`, position by position; an IUPAC
+/// code matches any base it represents
+fn mismatches(oligo: &[u8], site: &[u8]) -> u32 {
+    oligo
+        .iter()
+        .zip(site)
+        .filter(|&(&o, &s)| base_to_bit(o) & base_to_bit(s) == 0)
+        .count() as u32
+}
+
+/// The closest site of `oligo` in `target`'s region, leaving out the
+/// oligo's own window when both come from the same template
+fn closest_site(oligo: &OrderOligo, target: &OrderOligo) -> Option<CrossSite> {
+    let region = dna_bases(&target.target);
+    let sense = dna_bases(&oligo.sequence);
+    let antisense = dna_bases(&reverse_complement(&String::from_utf8_lossy(&sense)));
+    if sense.is_empty() || region.len() < sense.len() {
+        return None;
+    }
+    let same_template = !oligo.template.is_empty() && oligo.template == target.template;
+
+    let mut best: Option<CrossSite> = None;
+    for (offset, site) in region.windows(sense.len()).enumerate() {
+        let position = target.target_start + offset;
+        if same_template && position == oligo.position {
+            continue;
+        }
+        for (bases, is_antisense) in [(&sense, false), (&antisense, true)] {
+            let mm = mismatches(bases, site);
+            if best.is_none_or(|b| mm < b.mismatches) {
+                best = Some(CrossSite {
+                    mismatches: mm,
+                    position,
+                    antisense: is_antisense,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Cross-dimers and off-target sites between every pair of `oligos`
+pub fn multiplex_check(oligos: &[OrderOligo]) -> MultiplexCheck {
+    let n = oligos.len();
+    let mut dimers = vec![vec![0.0; n]; n];
+    for (i, a) in oligos.iter().enumerate() {
+        for (j, b) in oligos.iter().enumerate().skip(i) {
+            let dg = dimer_dg(&a.sequence, &b.sequence);
+            dimers[i][j] = dg;
+            dimers[j][i] = dg;
+        }
+    }
+    let cross_sites = oligos
+        .iter()
+        .enumerate()
+        .map(|(i, oligo)| {
+            oligos
+                .iter()
+                .enumerate()
+                .map(|(j, target)| {
+                    if i == j {
+                        None
+                    } else {
+                        closest_site(oligo, target)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    MultiplexCheck {
+        dimer_dg: dimers,
+        cross_sites,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::order::target_region;
+
+    fn oligo(template: &str, sequence: &str, position: usize) -> OrderOligo {
+        let (target_start, target) = target_region(sequence, 0, 0);
+        OrderOligo {
+            name: format!("{}_{}", template, position),
+            sequence: sequence[position..position + 20].to_string(),
+            length: 20,
+            position,
+            source: "Candidate 1".to_string(),
+            template: template.to_string(),
+            target,
+            target_start,
+        }
+    }
+
+    #[test]
+    fn test_multiplex_check() {
+        let a = "ATGCGTACGTTAGCCATGACTGGATCCGTAACGTTGCAAGCTTGGCACTGGCCGTCGTTTTAC";
+        let b = "TTGACCAGTAGGCATCGAATCCTTAGGCTACCGATGCAATTCGGACTAGCATGCCTAGTAAC";
+        // Two oligos on template A (own region is the whole template here)
+        // and one on B carrying A's first oligo with one substitution
+        let mut on_b = oligo("B", b, 10);
+        on_b.sequence = a[5..25].to_string();
+        on_b.sequence.replace_range(3..4, "A");
+        let oligos = vec![oligo("A", a, 5), oligo("A", a, 30), on_b];
+        let check = multiplex_check(&oligos);
+
+        assert_eq!(check.dimer_dg.len(), 3);
+        for (i, row) in check.dimer_dg.iter().enumerate() {
+            assert!(check.cross_sites[i][i].is_none());
+            for (j, dg) in row.iter().enumerate() {
+                assert_eq!(*dg, check.dimer_dg[j][i]);
+            }
+        }
+        // The same template skips each oligo's own site
+        let own = check.cross_sites[0][1].unwrap();
+        assert_ne!(own.position, 5);
+        assert!(own.mismatches > 0);
+        // B's oligo finds its near copy on template A
+        let cross = check.cross_sites[2][0].unwrap();
+        assert_eq!(
+            (cross.mismatches, cross.position, cross.antisense),
+            (1, 5, false)
+        );
+        // Reverse complemented, it binds the other strand at the same site
+        let mut antisense = oligos.clone();
+        antisense[2].sequence = reverse_complement(&antisense[2].sequence);
+        let cross = multiplex_check(&antisense).cross_sites[2][0].unwrap();
+        assert_eq!(
+            (cross.mismatches, cross.position, cross.antisense),
+            (1, 5, true)
+        );
+
+        // No recorded target: nothing to check
+        let mut untargeted = oligos;
+        untargeted[0].target.clear();
+        assert!(multiplex_check(&untargeted).cross_sites[1][0].is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Oligo ordering
+//!
+//! Holds the sequences picked for synthesis from the detail window and the
+//! candidate table, and writes them as FASTA or as a vendor order sheet in
+//! the column layout of the IDT and Sigma bulk-ordering upload forms.
+
+use serde::{Deserialize, Serialize};
+
+use super::export::csv_field;
+use super::iupac::reverse_complement;
+
+/// An oligo picked for ordering
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderOligo {
+    pub name: String,
+    /// 5'-3' sequence as it will be ordered
+    pub sequence: String,
+    pub length: u32,
+    /// 0-based template position of the window it came from
+    pub position: usize,
+    /// Where it was picked (e.g. "Variant 2", "Candidate 5")
+    pub source: String,
+    /// Name of the template it was designed on; empty for oligos saved
+    /// before target regions were recorded
+    #[serde(default)]
+    pub template: String,
+    /// Template sequence around the window (`TARGET_FLANK` bases either
+    /// side), the region the oligo is meant to bind
+    #[serde(default)]
+    pub target: String,
+    /// Template position of the first base of `target`
+    #[serde(default)]
+    pub target_start: usize,
+}
+
+/// Template bases kept either side of an ordered oligo's window as its
+/// target region
+pub const TARGET_FLANK: usize = 100;
+
+/// The target region of the window at `position` of `length` bases: its
+/// start on the template and its sequence, clipped to the template
+pub fn target_region(template: &str, position: usize, length: u32) -> (usize, String) {
+    let start = position.saturating_sub(TARGET_FLANK).min(template.len());
+    let end = (position + length as usize + TARGET_FLANK).min(template.len());
+    (start, template.get(start..end).unwrap_or("").to_string())
+}
+
+impl OrderOligo {
+    /// Replace the sequence with its reverse complement, keeping U in RNA
+    /// sequences
+    pub fn reverse_complement(&mut self) {
+        let rna = self.sequence.contains('U');
+        let rc = reverse_complement(&self.sequence.replace('U', "T"));
+        self.sequence = if rna { rc.replace('T', "U") } else { rc };
+    }
+}
+
+/// Column layout of an order sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OrderSheetFormat {
+    #[default]
+    Idt,
+    Sigma,
+}
+
+impl OrderSheetFormat {
+    pub const ALL: [Self; 2] = [Self::Idt, Self::Sigma];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Idt => "IDT",
+            Self::Sigma => "Sigma",
+        }
+    }
+
+    /// Synthesis scales offered by the vendor, as written in the sheet
+    pub fn scales(&self) -> &'static [&'static str] {
+        match self {
+            Self::Idt => &["25nm", "100nm", "250nm", "1um"],
+            Self::Sigma => &["0.025", "0.05", "0.2", "1.0"],
+        }
+    }
+
+    /// Purification codes offered by the vendor, desalting first
+    pub fn purifications(&self) -> &'static [&'static str] {
+        match self {
+            Self::Idt => &["STD", "PAGE", "HPLC"],
+            Self::Sigma => &["DST", "HPLC", "PAGE"],
+        }
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            Self::Idt => "Name,Sequence,Scale,Purification",
+            Self::Sigma => "Oligo Name,Sequence (5' to 3'),Scale (µmol),Purification",
+        }
+    }
+}
+
+/// `base`, or `base_2`, `base_3`, ... if an oligo in `existing` already has
+/// that name
+pub fn unique_order_name(existing: &[OrderOligo], base: &str) -> String {
+    let taken = |name: &str| existing.iter().any(|o| o.name == name);
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|i| format!("{}_{}", base, i))
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
+
+/// The oligos as FASTA, one record per oligo named by its order name.
+pub fn order_fasta(oligos: &[OrderOligo]) -> String {
+    let mut fasta = String::new();
+    for o in oligos {
+        fasta.push_str(&format!(">{}\n{}\n", o.name, o.sequence));
+    }
+    fasta
+}
+
+/// The oligos as a vendor order sheet CSV, every oligo at the same scale
+/// and purification.
+pub fn order_sheet_csv(
+    oligos: &[OrderOligo],
+    format: OrderSheetFormat,
+    scale: &str,
+    purification: &str,
+) -> String {
+    let mut csv = String::from(format.header());
+    csv.push('\n');
+    for o in oligos {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&o.name),
+            o.sequence,
+            scale,
+            purification
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oligo(name: &str, sequence: &str) -> OrderOligo {
+        OrderOligo {
+            name: name.to_string(),
+            sequence: sequence.to_string(),
+            length: sequence.len() as u32,
+            position: 0,
+            source: "Variant 1".to_string(),
+            template: String::new(),
+            target: String::new(),
+            target_start: 0,
+        }
+    }
+
+    #[test]
+    fn test_order_exports() {
+        let mut oligos = vec![oligo("T_pos1_10nt", "ACGTRACGTA")];
+        assert_eq!(unique_order_name(&oligos, "T_pos5_10nt"), "T_pos5_10nt");
+        let name = unique_order_name(&oligos, "T_pos1_10nt");
+        assert_eq!(name, "T_pos1_10nt_2");
+        oligos.push(oligo(&name, "TTTTGGGGCC"));
+        assert_eq!(unique_order_name(&oligos, "T_pos1_10nt"), "T_pos1_10nt_3");
+        oligos.push(oligo("probe, FAM", "ACACACACAC"));
+
+        let mut rna = oligo("R", "AUGGCA");
+        rna.reverse_complement();
+        assert_eq!(rna.sequence, "UGCCAU");
+
+        assert_eq!(
+            order_fasta(&oligos[..2]),
+            ">T_pos1_10nt\nACGTRACGTA\n>T_pos1_10nt_2\nTTTTGGGGCC\n"
+        );
+
+        let csv = order_sheet_csv(&oligos, OrderSheetFormat::Idt, "25nm", "STD");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "Name,Sequence,Scale,Purification");
+        assert_eq!(lines[1], "T_pos1_10nt,ACGTRACGTA,25nm,STD");
+        assert_eq!(lines[3], "\"probe, FAM\",ACACACACAC,25nm,STD");
+
+        let template = "ACGT".repeat(60);
+        assert_eq!(
+            target_region(&template, 150, 20),
+            (50, template[50..].to_string())
+        );
+        assert_eq!(target_region(&template, 10, 20).0, 0);
+        assert_eq!(target_region(&template, 10, 20).1.len(), 130);
+
+        let sigma = order_sheet_csv(&oligos[..1], OrderSheetFormat::Sigma, "0.025", "DST");
+        assert!(sigma.starts_with("Oligo Name,"));
+        assert!(sigma.ends_with("T_pos1_10nt,ACGTRACGTA,0.025,DST\n"));
+    }
+}
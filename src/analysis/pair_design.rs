@@ -0,0 +1,821 @@
+//! Primer/probe pair design from screening results
+//!
+//! Unlike the amplicon search, which merges each window into one degenerate
+//! oligo, pair design keeps the variants each window needs as screened.
+//! Windows needing few variants within a Tm window become oligos, and
+//! forward/reverse oligos spanning an amplicon in range are scored together:
+//! the references both of them cover (from realigning the reference panel to
+//! each oligo), the difference of their Tms, and the closer exclusivity hit of
+//! the two.
+//! Triplet design adds a hydrolysis probe between the primers, under its own
+//! Tm window and spacing, and scores all three oligos together.
+
+use serde::{Deserialize, Serialize};
+
+use rayon::prelude::*;
+
+use super::assignment::{assignment_references, reference_assignments};
+use super::fasta::ReferenceData;
+use super::filter::{ExclusivityFilter, effective_min_mismatches};
+use super::iupac::reverse_complement;
+use super::types::{PositionResult, ScreeningResults, Variant};
+
+/// Constraints of a pair design
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairDesignParams {
+    pub min_amplicon_length: usize,
+    pub max_amplicon_length: usize,
+    /// Windows needing more variants than this are not used
+    pub max_variants: usize,
+    /// Tm window (°C) of the template oligo of each window
+    pub min_tm: f64,
+    pub max_tm: f64,
+    /// Largest Tm difference (°C) between the forward and reverse oligo
+    pub max_tm_difference: f64,
+    /// Minimum percentage of references covered by both oligos
+    pub min_pair_coverage: f64,
+    pub max_results: usize,
+    /// Optional exclusivity filter; windows failing it are not used
+    #[serde(default)]
+    pub exclusivity_filter: Option<ExclusivityFilter>,
+}
+
+impl Default for PairDesignParams {
+    fn default() -> Self {
+        Self {
+            min_amplicon_length: 70,
+            max_amplicon_length: 250,
+            max_variants: 2,
+            min_tm: 55.0,
+            max_tm: 65.0,
+            max_tm_difference: 3.0,
+            min_pair_coverage: 90.0,
+            max_results: 200,
+            exclusivity_filter: None,
+        }
+    }
+}
+
+/// One window used as an oligo of a designed pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesignOligo {
+    pub position: usize,
+    pub length: u32,
+    /// The variants the window needs, in binding orientation (reverse
+    /// complemented for reverse oligos)
+    pub variants: Vec<String>,
+    pub tm: f64,
+    /// Percentage of all references covered by the variants
+    pub coverage: f64,
+    /// Fewest mismatches to an exclusivity sequence; None without a hit or
+    /// without exclusivity data
+    pub exclusivity_mismatches: Option<u32>,
+}
+
+/// A forward/reverse oligo pair scored jointly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesignedPair {
+    pub forward: DesignOligo,
+    pub reverse: DesignOligo,
+    pub amplicon_length: usize,
+    /// Percentage of references covered by both oligos
+    pub coverage: f64,
+    /// Member lists were not saved with the results, so `coverage` is the
+    /// lower of the two oligos' coverage (an upper bound)
+    pub coverage_upper_bound: bool,
+    pub tm_difference: f64,
+    /// The closer exclusivity hit of the two oligos; None when neither has one
+    pub exclusivity_mismatches: Option<u32>,
+}
+
+impl DesignedPair {
+    /// Variants of both oligos together, each to be synthesized
+    pub fn total_variants(&self) -> usize {
+        self.forward.variants.len() + self.reverse.variants.len()
+    }
+}
+
+/// An oligo candidate with the references its variants cover, as a bitset;
+/// None without the reference sequences to realign
+struct Candidate {
+    oligo: DesignOligo,
+    members: Option<Vec<u64>>,
+}
+
+/// The variants a window needs to reach the coverage threshold
+fn needed_variants(pr: &PositionResult) -> &[Variant] {
+    &pr.analysis.variants[..pr.variants_needed.min(pr.analysis.variants.len())]
+}
+
+/// The references (indices into `references`) whose window in the realigned
+/// panel one of `variants` covers, as a bitset
+fn covered_references(
+    oligo: &str,
+    variants: &[Variant],
+    references: &ReferenceData,
+    results: &ScreeningResults,
+) -> Vec<u64> {
+    let mut members = vec![0u64; references.len().div_ceil(64)];
+    let assignments =
+        reference_assignments(oligo.as_bytes(), variants, references, &results.params);
+    for a in assignments.iter().filter(|a| a.variant.is_some()) {
+        members[a.reference / 64] |= 1 << (a.reference % 64);
+    }
+    members
+}
+
+/// The window of each template position best suited to the design: highest
+/// coverage, then fewest variants, then Tm closest to the middle of the
+/// window. Windows outside the Tm window, needing more than `max_variants`,
+/// failing the exclusivity filter, or whose variants `accept` rejects are
+/// left out. With `references` (the screened panel, in `reference_names`
+/// order) each candidate's covered references are found by realignment.
+fn design_candidates(
+    results: &ScreeningResults,
+    references: Option<&ReferenceData>,
+    (min_tm, max_tm): (f64, f64),
+    max_variants: usize,
+    exclusivity_filter: Option<ExclusivityFilter>,
+    accept: impl Fn(&[Variant]) -> bool,
+) -> Vec<Candidate> {
+    // Trimmed once, as in the screen, for every candidate's realignment
+    let references = references
+        .filter(|r| r.len() == results.reference_names.len())
+        .map(|r| assignment_references(&results.template_sequence, r, &results.params));
+    let references = references.as_deref();
+    let tm_middle = (min_tm + max_tm) / 2.0;
+    let rank = |pr: &PositionResult, tm: f64| {
+        (
+            pr.analysis.coverage_at_threshold,
+            std::cmp::Reverse(pr.variants_needed),
+            -(tm - tm_middle).abs(),
+        )
+    };
+
+    let mut best: std::collections::BTreeMap<usize, (&PositionResult, u32, f64)> =
+        std::collections::BTreeMap::new();
+    for (&length, length_result) in &results.results_by_length {
+        for pr in &length_result.positions {
+            let Some(tm) = pr.tm else {
+                continue;
+            };
+            if pr.analysis.skipped
+                || pr.variants_needed == 0
+                || pr.variants_needed > max_variants
+                || tm < min_tm
+                || tm > max_tm
+                || exclusivity_filter.is_some_and(|f| !f.passes(pr))
+                || !accept(needed_variants(pr))
+            {
+                continue;
+            }
+            let replace =
+                best.get(&pr.position)
+                    .is_none_or(|&(current, current_length, current_tm)| {
+                        let (new, old) = (rank(pr, tm), rank(current, current_tm));
+                        new.partial_cmp(&old)
+                            .is_some_and(|o| o.is_gt() || (o.is_eq() && length < current_length))
+                    });
+            if replace {
+                best.insert(pr.position, (pr, length, tm));
+            }
+        }
+    }
+
+    let best: Vec<(&PositionResult, u32, f64)> = best.into_values().collect();
+    best.into_par_iter()
+        .map(|(pr, length, tm)| {
+            let needed = needed_variants(pr);
+            let oligo = results
+                .template_sequence
+                .get(pr.position..pr.position + length as usize)
+                .unwrap_or("");
+            Candidate {
+                oligo: DesignOligo {
+                    position: pr.position,
+                    length,
+                    variants: needed.iter().map(|v| v.sequence.clone()).collect(),
+                    tm,
+                    coverage: needed.iter().map(|v| v.percentage).sum::<f64>().min(100.0),
+                    exclusivity_mismatches: pr
+                        .exclusivity
+                        .as_ref()
+                        .and_then(|e| effective_min_mismatches(e, 0)),
+                },
+                members: references.map(|r| covered_references(oligo, needed, r, results)),
+            }
+        })
+        .collect()
+}
+
+/// Percentage of the references every candidate covers, and whether it is
+/// only an upper bound (the lowest single coverage, as the covered references
+/// are unknown)
+fn joint_coverage(candidates: &[&Candidate], total_sequences: usize) -> (f64, bool) {
+    let Some(members) = candidates
+        .iter()
+        .map(|c| c.members.as_deref())
+        .collect::<Option<Vec<&[u64]>>>()
+    else {
+        let lowest = candidates
+            .iter()
+            .map(|c| c.oligo.coverage)
+            .fold(100.0, f64::min);
+        return (lowest, true);
+    };
+    let words = members.first().map_or(0, |m| m.len());
+    let shared: u32 = (0..words)
+        .map(|w| {
+            members
+                .iter()
+                .fold(u64::MAX, |acc, m| acc & m[w])
+                .count_ones()
+        })
+        .sum();
+    (shared as f64 / total_sequences.max(1) as f64 * 100.0, false)
+}
+
+/// The closer of two exclusivity hits; None (no hit) is the farthest
+fn closer_hit(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (hit, None) | (None, hit) => hit,
+    }
+}
+
+/// Forward/reverse candidate index pairs that span an amplicon in range with
+/// Tms close enough, with the amplicon length and Tm difference
+fn candidate_pairs<'a>(
+    candidates: &'a [Candidate],
+    params: &'a PairDesignParams,
+) -> impl Iterator<Item = (usize, usize, usize, f64)> + 'a {
+    candidates.iter().enumerate().flat_map(move |(fi, fwd)| {
+        let fwd = &fwd.oligo;
+        let fwd_end = fwd.position + fwd.length as usize;
+        let start = candidates.partition_point(|c| c.oligo.position < fwd_end);
+        candidates[start..]
+            .iter()
+            .enumerate()
+            .take_while(move |(_, rev)| {
+                rev.oligo.position <= fwd.position + params.max_amplicon_length
+            })
+            .filter_map(move |(offset, rev)| {
+                let amplicon_length = rev.oligo.position + rev.oligo.length as usize - fwd.position;
+                let tm_difference = (fwd.tm - rev.oligo.tm).abs();
+                (amplicon_length >= params.min_amplicon_length
+                    && amplicon_length <= params.max_amplicon_length
+                    && tm_difference <= params.max_tm_difference)
+                    .then_some((fi, start + offset, amplicon_length, tm_difference))
+            })
+    })
+}
+
+/// Joint score of a pair or triplet under consideration
+#[derive(Clone, Copy)]
+struct DesignScore {
+    coverage: f64,
+    exclusivity_mismatches: Option<u32>,
+    total_variants: usize,
+    tm_difference: f64,
+    amplicon_length: usize,
+}
+
+impl DesignScore {
+    /// Ranking: coverage (descending), then exclusivity mismatches
+    /// (descending, no hit first), total variants, Tm difference, and
+    /// amplicon length (ascending)
+    fn rank(&self, other: &Self) -> std::cmp::Ordering {
+        let exclusivity = |s: &Self| s.exclusivity_mismatches.unwrap_or(u32::MAX);
+        other
+            .coverage
+            .partial_cmp(&self.coverage)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(exclusivity(other).cmp(&exclusivity(self)))
+            .then(self.total_variants.cmp(&other.total_variants))
+            .then(
+                self.tm_difference
+                    .partial_cmp(&other.tm_difference)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+            .then(self.amplicon_length.cmp(&other.amplicon_length))
+    }
+}
+
+/// Sort by score and keep the best `max_results`
+fn keep_best<T>(designs: &mut Vec<T>, max_results: usize, score: impl Fn(&T) -> &DesignScore) {
+    designs.sort_by(|a, b| score(a).rank(score(b)));
+    designs.truncate(max_results);
+}
+
+/// A candidate's oligo with its variants reverse complemented
+fn reverse_oligo(candidate: &Candidate) -> DesignOligo {
+    DesignOligo {
+        variants: candidate
+            .oligo
+            .variants
+            .iter()
+            .map(|v| reverse_complement(v))
+            .collect(),
+        ..candidate.oligo.clone()
+    }
+}
+
+/// Enumerate and rank forward/reverse pairs under `params`, keeping at most
+/// `max_results`. The reverse oligo's variants are given as their reverse
+/// complements. Pair coverage needs the screened `references`; without them
+/// it is the lower of the two oligos' coverage.
+pub fn design_pairs(
+    results: &ScreeningResults,
+    references: Option<&ReferenceData>,
+    params: &PairDesignParams,
+) -> Vec<DesignedPair> {
+    let candidates = design_candidates(
+        results,
+        references,
+        (params.min_tm, params.max_tm),
+        params.max_variants,
+        params.exclusivity_filter,
+        |_| true,
+    );
+    let max_results = params.max_results.max(1);
+
+    let mut pairs: Vec<(usize, usize, DesignScore)> = Vec::new();
+    for (fi, ri, amplicon_length, tm_difference) in candidate_pairs(&candidates, params) {
+        let (fwd, rev) = (&candidates[fi], &candidates[ri]);
+        let (coverage, _) = joint_coverage(&[fwd, rev], results.total_sequences);
+        if coverage < params.min_pair_coverage {
+            continue;
+        }
+        pairs.push((
+            fi,
+            ri,
+            DesignScore {
+                coverage,
+                exclusivity_mismatches: closer_hit(
+                    fwd.oligo.exclusivity_mismatches,
+                    rev.oligo.exclusivity_mismatches,
+                ),
+                total_variants: fwd.oligo.variants.len() + rev.oligo.variants.len(),
+                tm_difference,
+                amplicon_length,
+            },
+        ));
+        if pairs.len() > max_results * 4 {
+            keep_best(&mut pairs, max_results, |p| &p.2);
+        }
+    }
+    keep_best(&mut pairs, max_results, |p| &p.2);
+
+    pairs
+        .into_iter()
+        .map(|(fi, ri, score)| {
+            let (fwd, rev) = (&candidates[fi], &candidates[ri]);
+            DesignedPair {
+                forward: fwd.oligo.clone(),
+                reverse: reverse_oligo(rev),
+                amplicon_length: score.amplicon_length,
+                coverage: score.coverage,
+                coverage_upper_bound: fwd.members.is_none() || rev.members.is_none(),
+                tm_difference: score.tm_difference,
+                exclusivity_mismatches: score.exclusivity_mismatches,
+            }
+        })
+        .collect()
+}
+
+/// Hydrolysis (TaqMan) probe constraints of a triplet design
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeParams {
+    pub max_variants: usize,
+    /// Tm window (°C) of the probe's template oligo
+    pub min_tm: f64,
+    pub max_tm: f64,
+    /// The probe's Tm must exceed the higher primer Tm by at least this (°C)
+    pub min_tm_above_primers: f64,
+    /// Fewest bases between the probe and either primer
+    pub min_spacing: usize,
+    /// Most bases between the probe and the closer primer
+    pub max_spacing: usize,
+    /// Reject probes with a G at the 5' end (it quenches the reporter dye).
+    /// A probe failing this on the template strand is tried on the other one.
+    pub no_5prime_g: bool,
+}
+
+impl Default for ProbeParams {
+    fn default() -> Self {
+        Self {
+            max_variants: 2,
+            min_tm: 65.0,
+            max_tm: 75.0,
+            min_tm_above_primers: 5.0,
+            min_spacing: 0,
+            max_spacing: 30,
+            no_5prime_g: true,
+        }
+    }
+}
+
+/// A primer–probe–primer assay scored jointly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesignedTriplet {
+    pub forward: DesignOligo,
+    /// Variants in probe orientation (reverse complemented when `probe_antisense`)
+    pub probe: DesignOligo,
+    pub reverse: DesignOligo,
+    /// The probe binds the template strand, i.e. reads as the reverse complement
+    pub probe_antisense: bool,
+    pub amplicon_length: usize,
+    /// Percentage of references covered by all three oligos
+    pub coverage: f64,
+    /// Member lists were not saved with the results, so `coverage` is the
+    /// lowest single-oligo coverage (an upper bound)
+    pub coverage_upper_bound: bool,
+    /// Tm difference of the two primers
+    pub primer_tm_difference: f64,
+    /// How far the probe's Tm exceeds the higher primer Tm
+    pub probe_tm_margin: f64,
+    /// The closest exclusivity hit of the three oligos; None when none has one
+    pub exclusivity_mismatches: Option<u32>,
+}
+
+impl DesignedTriplet {
+    /// Variants of all three oligos together, each to be synthesized
+    pub fn total_variants(&self) -> usize {
+        self.forward.variants.len() + self.probe.variants.len() + self.reverse.variants.len()
+    }
+}
+
+/// Probe strand satisfying the 5' G rule: template sense first, then
+/// antisense; None when neither does
+fn probe_strand<'a>(
+    mut variants: impl Iterator<Item = &'a str> + Clone,
+    no_5prime_g: bool,
+) -> Option<bool> {
+    if !no_5prime_g || !variants.clone().any(|v| v.starts_with('G')) {
+        Some(false)
+    } else if !variants.any(|v| v.ends_with('C')) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Enumerate and rank forward primer, probe, and reverse primer triplets:
+/// primer pairs under `params`, each with the probes lying between the
+/// primers under `probe`. Coverage counts the references all three oligos
+/// cover; otherwise ranked like pairs (Tm difference of the primers).
+pub fn design_triplets(
+    results: &ScreeningResults,
+    references: Option<&ReferenceData>,
+    params: &PairDesignParams,
+    probe: &ProbeParams,
+) -> Vec<DesignedTriplet> {
+    let primers = design_candidates(
+        results,
+        references,
+        (params.min_tm, params.max_tm),
+        params.max_variants,
+        params.exclusivity_filter,
+        |_| true,
+    );
+    let probes = design_candidates(
+        results,
+        references,
+        (probe.min_tm, probe.max_tm),
+        probe.max_variants,
+        params.exclusivity_filter,
+        |needed| {
+            let sequences = needed.iter().map(|v| v.sequence.as_str());
+            probe_strand(sequences, probe.no_5prime_g).is_some()
+        },
+    );
+    let max_results = params.max_results.max(1);
+
+    let mut triplets: Vec<(usize, usize, usize, DesignScore)> = Vec::new();
+    for (fi, ri, amplicon_length, tm_difference) in candidate_pairs(&primers, params) {
+        let (fwd, rev) = (&primers[fi], &primers[ri]);
+        let fwd_end = fwd.oligo.position + fwd.oligo.length as usize;
+        let min_probe_tm = fwd.oligo.tm.max(rev.oligo.tm) + probe.min_tm_above_primers;
+        let start = probes.partition_point(|p| p.oligo.position < fwd_end + probe.min_spacing);
+
+        for (pi, pr) in probes.iter().enumerate().skip(start) {
+            if pr.oligo.position >= rev.oligo.position {
+                break;
+            }
+            let probe_end = pr.oligo.position + pr.oligo.length as usize;
+            if probe_end + probe.min_spacing > rev.oligo.position {
+                continue;
+            }
+            let gap = (pr.oligo.position - fwd_end).min(rev.oligo.position - probe_end);
+            if gap > probe.max_spacing || pr.oligo.tm < min_probe_tm {
+                continue;
+            }
+            let (coverage, _) = joint_coverage(&[fwd, pr, rev], results.total_sequences);
+            if coverage < params.min_pair_coverage {
+                continue;
+            }
+            let exclusivity = [fwd, pr, rev].iter().fold(None, |hit, c| {
+                closer_hit(hit, c.oligo.exclusivity_mismatches)
+            });
+            triplets.push((
+                fi,
+                pi,
+                ri,
+                DesignScore {
+                    coverage,
+                    exclusivity_mismatches: exclusivity,
+                    total_variants: fwd.oligo.variants.len()
+                        + pr.oligo.variants.len()
+                        + rev.oligo.variants.len(),
+                    tm_difference,
+                    amplicon_length,
+                },
+            ));
+        }
+        if triplets.len() > max_results * 4 {
+            keep_best(&mut triplets, max_results, |t| &t.3);
+        }
+    }
+    keep_best(&mut triplets, max_results, |t| &t.3);
+
+    triplets
+        .into_iter()
+        .map(|(fi, pi, ri, score)| {
+            let (fwd, pr, rev) = (&primers[fi], &probes[pi], &primers[ri]);
+            let probe_antisense = probe_strand(
+                pr.oligo.variants.iter().map(String::as_str),
+                probe.no_5prime_g,
+            ) == Some(true);
+            DesignedTriplet {
+                forward: fwd.oligo.clone(),
+                probe: if probe_antisense {
+                    reverse_oligo(pr)
+                } else {
+                    pr.oligo.clone()
+                },
+                reverse: reverse_oligo(rev),
+                probe_antisense,
+                amplicon_length: score.amplicon_length,
+                coverage: score.coverage,
+                coverage_upper_bound: fwd.members.is_none()
+                    || pr.members.is_none()
+                    || rev.members.is_none(),
+                primer_tm_difference: score.tm_difference,
+                probe_tm_margin: pr.oligo.tm - fwd.oligo.tm.max(rev.oligo.tm),
+                exclusivity_mismatches: score.exclusivity_mismatches,
+            }
+        })
+        .collect()
+}
+
+/// Designed pairs as CSV, one row per pair with 1-based positions and each
+/// oligo's variants separated by `;`. Exclusivity is empty without a hit.
+pub fn designed_pairs_csv(pairs: &[DesignedPair]) -> String {
+    let mut csv = String::from(
+        "rank,forward_position,forward_length,forward_variants,forward_tm,\
+         reverse_position,reverse_length,reverse_variants,reverse_tm,amplicon_length,\
+         pair_coverage,coverage_upper_bound,tm_difference,exclusivity_min_mismatches\n",
+    );
+    for (i, pair) in pairs.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{},{},{},{:.1},{},{},{},{:.1},{},{:.2},{},{:.1},{}\n",
+            i + 1,
+            pair.forward.position + 1,
+            pair.forward.length,
+            pair.forward.variants.join(";"),
+            pair.forward.tm,
+            pair.reverse.position + 1,
+            pair.reverse.length,
+            pair.reverse.variants.join(";"),
+            pair.reverse.tm,
+            pair.amplicon_length,
+            pair.coverage,
+            pair.coverage_upper_bound,
+            pair.tm_difference,
+            pair.exclusivity_mismatches
+                .map(|m| m.to_string())
+                .unwrap_or_default()
+        ));
+    }
+    csv
+}
+
+/// Assay summary sheet of designed triplets as CSV: one row per oligo
+/// (forward, probe, reverse) with its variants separated by `;`, followed by
+/// the assay's joint figures. Positions are 1-based on the template.
+pub fn triplet_assay_sheet(triplets: &[DesignedTriplet]) -> String {
+    let mut csv = String::from(
+        "assay,oligo,position,length,strand,variants,tm,coverage,exclusivity_min_mismatches,\
+         amplicon_length,assay_coverage,coverage_upper_bound,primer_tm_difference,\
+         probe_tm_margin,assay_exclusivity_min_mismatches\n",
+    );
+    let mismatches = |m: Option<u32>| m.map(|m| m.to_string()).unwrap_or_default();
+    for (i, t) in triplets.iter().enumerate() {
+        let oligos = [
+            ("forward", &t.forward, "+"),
+            ("probe", &t.probe, if t.probe_antisense { "-" } else { "+" }),
+            ("reverse", &t.reverse, "-"),
+        ];
+        for (name, oligo, strand) in oligos {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{:.1},{:.2},{},{},{:.2},{},{:.1},{:.1},{}\n",
+                i + 1,
+                name,
+                oligo.position + 1,
+                oligo.length,
+                strand,
+                oligo.variants.join(";"),
+                oligo.tm,
+                oligo.coverage,
+                mismatches(oligo.exclusivity_mismatches),
+                t.amplicon_length,
+                t.coverage,
+                t.coverage_upper_bound,
+                t.primer_tm_difference,
+                t.probe_tm_margin,
+                mismatches(t.exclusivity_mismatches),
+            ));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::TemplateData;
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::{AnalysisMethod, AnalysisParams, PairwiseParams};
+
+    const TEMPLATE: &str = "ATGCGTACGTTAGCCATGACTGGATCCGTAACGTTGCAAGCTTGGCACTGGCCGTCGTTTTAC";
+
+    fn screened_example() -> (ScreeningResults, ReferenceData) {
+        let template = TEMPLATE;
+        // R2 has every base of the first 20 complemented, so windows over three
+        // or more of them find R1 only
+        let complemented: String = template[..20]
+            .chars()
+            .map(|c| match c {
+                'A' => 'T',
+                'T' => 'A',
+                'C' => 'G',
+                _ => 'C',
+            })
+            .collect();
+        let references = ReferenceData {
+            names: vec!["R1".to_string(), "R2".to_string()],
+            sequences: vec![
+                template.to_string(),
+                format!("{}{}", complemented, &template[20..]),
+            ],
+        };
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &references,
+            &AnalysisParams {
+                method: AnalysisMethod::NoAmbiguities,
+                min_oligo_length: 18,
+                max_oligo_length: 18,
+                coverage_threshold: 100.0,
+                pairwise: PairwiseParams {
+                    max_mismatches: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+        (results, references)
+    }
+
+    fn example_params() -> PairDesignParams {
+        PairDesignParams {
+            min_amplicon_length: 40,
+            max_amplicon_length: 60,
+            max_variants: 2,
+            min_tm: 0.0,
+            max_tm: 100.0,
+            max_tm_difference: 100.0,
+            min_pair_coverage: 0.0,
+            max_results: 50,
+            exclusivity_filter: None,
+        }
+    }
+
+    #[test]
+    fn test_design_pairs() {
+        let template = TEMPLATE;
+        let (results, references) = screened_example();
+        let params = example_params();
+        let pairs = design_pairs(&results, Some(&references), &params);
+        assert!(!pairs.is_empty());
+        for pair in &pairs {
+            assert!(pair.amplicon_length >= 40 && pair.amplicon_length <= 60);
+            assert!(pair.reverse.position >= pair.forward.position + 18);
+            assert!(!pair.coverage_upper_bound);
+            let site = &template[pair.reverse.position..pair.reverse.position + 18];
+            assert!(pair.reverse.variants.contains(&reverse_complement(site)));
+            // Both references are covered only when the forward window found R2
+            let forward = results.results_by_length[&pair.forward.length]
+                .positions
+                .iter()
+                .find(|pr| pr.position == pair.forward.position)
+                .unwrap();
+            let expected = if forward.analysis.no_match_count == 0 {
+                100.0
+            } else {
+                50.0
+            };
+            assert_eq!(pair.coverage, expected);
+        }
+        // Full coverage ranks first
+        assert_eq!(pairs[0].coverage, 100.0);
+        assert!(pairs.windows(2).all(|w| w[0].coverage >= w[1].coverage));
+
+        // The joint coverage requirement drops the pairs R2 misses
+        let strict = design_pairs(
+            &results,
+            Some(&references),
+            &PairDesignParams {
+                min_pair_coverage: 100.0,
+                ..params.clone()
+            },
+        );
+        assert!(!strict.is_empty());
+        assert!(strict.iter().all(|p| p.forward.position >= 18));
+
+        // Without the references the pair coverage is only an upper bound
+        let unaligned = design_pairs(&results, None, &params);
+        assert_eq!(unaligned.len(), pairs.len());
+        assert!(unaligned.iter().all(|p| p.coverage_upper_bound));
+        assert!(
+            unaligned
+                .iter()
+                .all(|p| p.coverage == 50.0 || p.coverage == 100.0)
+        );
+
+        let csv = designed_pairs_csv(&pairs);
+        assert_eq!(csv.lines().count(), pairs.len() + 1);
+        assert!(csv.starts_with("rank,forward_position"));
+    }
+
+    #[test]
+    fn test_design_triplets() {
+        let (results, references) = screened_example();
+        let params = PairDesignParams {
+            max_amplicon_length: 63,
+            ..example_params()
+        };
+        let probe = ProbeParams {
+            min_tm: 0.0,
+            max_tm: 100.0,
+            min_tm_above_primers: -100.0,
+            ..Default::default()
+        };
+        let triplets = design_triplets(&results, Some(&references), &params, &probe);
+        assert!(!triplets.is_empty());
+        let all_pairs = PairDesignParams {
+            max_results: 100_000,
+            ..params.clone()
+        };
+        let pairs = design_pairs(&results, Some(&references), &all_pairs);
+        for t in &triplets {
+            let forward_end = t.forward.position + t.forward.length as usize;
+            let probe_end = t.probe.position + t.probe.length as usize;
+            assert!(t.probe.position >= forward_end && probe_end <= t.reverse.position);
+            assert!(t.probe.variants.iter().all(|v| !v.starts_with('G')));
+            let site = &TEMPLATE[t.probe.position..probe_end];
+            let expected = if t.probe_antisense {
+                reverse_complement(site)
+            } else {
+                site.to_string()
+            };
+            assert!(t.probe.variants.contains(&expected));
+            // A probe never raises coverage above the primer pair's
+            let pair = pairs
+                .iter()
+                .find(|p| {
+                    p.forward.position == t.forward.position
+                        && p.reverse.position == t.reverse.position
+                })
+                .unwrap();
+            assert!(t.coverage <= pair.coverage);
+        }
+
+        // A probe Tm far above any primer's leaves no triplet
+        let hot = ProbeParams {
+            min_tm_above_primers: 100.0,
+            ..probe.clone()
+        };
+        assert!(design_triplets(&results, Some(&references), &params, &hot).is_empty());
+
+        let sheet = triplet_assay_sheet(&triplets);
+        assert_eq!(sheet.lines().count(), triplets.len() * 3 + 1);
+        assert!(sheet.lines().nth(2).unwrap().starts_with("1,probe,"));
+    }
+}
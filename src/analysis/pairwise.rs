@@ -1,15 +1,55 @@
 //! Pairwise alignment logic for matching oligos against reference sequences
 //!
-//! Uses Smith-Waterman local alignment from the bio crate to find the best
-//! match for each template oligo in each reference sequence.
+//! Uses the bio crate's aligner (Smith-Waterman local alignment by default,
+//! or semi-global or global) to find the best match for each template oligo
+//! in each reference sequence.
 
-use bio::alignment::pairwise::{Aligner, MatchFunc, MatchParams};
-use bio::alignment::AlignmentOperation;
+use std::cell::RefCell;
 
-use super::types::PairwiseParams;
+use bio::alignment::pairwise::{Aligner, MatchFunc};
+use bio::alignment::{Alignment, AlignmentOperation};
 
-/// Concrete Aligner type using MatchParams (nameable, unlike closure-based Aligners).
-pub type DnaAligner = Aligner<MatchParams>;
+use super::iupac::{base_to_bit, is_ambiguous_base, reverse_complement};
+use super::striped::striped_local_score;
+use super::types::{
+    AlignerBackend, AlignmentMode, NoMatchRule, PairwiseParams, ReferenceAmbiguityPolicy,
+};
+
+/// Concrete Aligner type using IupacMatch (nameable, unlike closure-based Aligners).
+pub type DnaAligner = Aligner<IupacMatch>;
+
+/// Match scoring that, when `compatible`, also counts an IUPAC code as a
+/// match for any base it includes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IupacMatch {
+    pub match_score: i32,
+    pub mismatch_score: i32,
+    pub compatible: bool,
+}
+
+impl IupacMatch {
+    pub fn new(params: &PairwiseParams) -> Self {
+        Self {
+            match_score: params.match_score,
+            mismatch_score: params.mismatch_score,
+            compatible: params.reference_ambiguity != ReferenceAmbiguityPolicy::Mismatch,
+        }
+    }
+
+    fn is_match(&self, a: u8, b: u8) -> bool {
+        a == b || (self.compatible && base_to_bit(a) & base_to_bit(b) != 0)
+    }
+}
+
+impl MatchFunc for IupacMatch {
+    fn score(&self, a: u8, b: u8) -> i32 {
+        if self.is_match(a, b) {
+            self.match_score
+        } else {
+            self.mismatch_score
+        }
+    }
+}
 
 /// Create an Aligner sized for the given dimensions.
 pub fn create_aligner(
@@ -17,41 +57,171 @@ pub fn create_aligner(
     max_ref_len: usize,
     params: &PairwiseParams,
 ) -> DnaAligner {
-    let match_fn = MatchParams::new(params.match_score, params.mismatch_score);
     Aligner::with_capacity(
         oligo_len,
         max_ref_len,
         params.gap_open_penalty,
         params.gap_extend_penalty,
-        match_fn,
+        IupacMatch::new(params),
     )
 }
 
+/// Aligner cached on a thread with the sizes and scores it was built for
+struct CachedAligner {
+    oligo_len: usize,
+    max_ref_len: usize,
+    scores: [i32; 4],
+    compatible: bool,
+    aligner: DnaAligner,
+}
+
+thread_local! {
+    static THREAD_ALIGNER: RefCell<Option<CachedAligner>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with this thread's aligner, creating it on first use. The aligner
+/// is kept for later calls and only rebuilt when the scores change or a
+/// larger oligo or reference is needed, so long-lived pool threads allocate
+/// it once per batch rather than once per job.
+pub fn with_thread_aligner<R>(
+    oligo_len: usize,
+    max_ref_len: usize,
+    params: &PairwiseParams,
+    f: impl FnOnce(&mut DnaAligner) -> R,
+) -> R {
+    let scores = [
+        params.match_score,
+        params.mismatch_score,
+        params.gap_open_penalty,
+        params.gap_extend_penalty,
+    ];
+    let compatible = IupacMatch::new(params).compatible;
+    THREAD_ALIGNER.with(|cell| {
+        let mut cached = cell.borrow_mut();
+        let previous = cached
+            .as_ref()
+            .filter(|c| c.scores == scores && c.compatible == compatible);
+        let reusable =
+            previous.is_some_and(|c| c.oligo_len >= oligo_len && c.max_ref_len >= max_ref_len);
+        if !reusable {
+            // Grow to the larger of the old and new sizes so alternating jobs don't thrash
+            let (oligo_len, max_ref_len) = previous.map_or((oligo_len, max_ref_len), |c| {
+                (c.oligo_len.max(oligo_len), c.max_ref_len.max(max_ref_len))
+            });
+            *cached = Some(CachedAligner {
+                oligo_len,
+                max_ref_len,
+                scores,
+                compatible,
+                aligner: create_aligner(oligo_len, max_ref_len, params),
+            });
+        }
+        f(&mut cached.as_mut().unwrap().aligner)
+    })
+}
+
 /// Result of aligning an oligo against a single reference sequence
 #[derive(Debug, Clone)]
 pub struct PairwiseMatch {
-    /// The matched region extracted from the reference (gap-free)
+    /// The full-length oligo window extracted from the reference, in gap
+    /// notation when the alignment has indels: `-` for an oligo base deleted
+    /// in the reference, lowercase for a base inserted in it. Empty if the
+    /// window runs past the reference ends.
     pub matched_sequence: String,
     /// Alignment score
     pub score: i32,
-    /// Number of mismatches in the alignment
+    /// Number of mismatches over the full-length window (or the aligned region
+    /// when no window could be extracted)
     pub mismatches: usize,
     /// Whether the alignment contains gaps (insertions or deletions)
     pub has_gaps: bool,
+    /// Inserted plus deleted bases in the alignment
+    pub indels: usize,
+    /// Mismatches within the 3'-end bases weighted by `three_prime` (0
+    /// without 3' weighting)
+    pub three_prime_mismatches: usize,
     /// Whether the alignment covers the full query (oligo)
     pub full_coverage: bool,
+    /// Number of oligo bases covered by the alignment
+    pub aligned_length: usize,
+    /// Index of the aligned reference within the batch (0 for single alignments)
+    pub reference_index: usize,
+}
+
+impl PairwiseMatch {
+    /// Whether this alignment counts as a match under the configured no-match
+    /// rule. Alignments with more indel bases than `max_indels` never do.
+    pub fn is_accepted(&self, params: &PairwiseParams) -> bool {
+        if self.indels > params.max_indels as usize || self.matched_sequence.is_empty() {
+            return false;
+        }
+        if params.reference_ambiguity == ReferenceAmbiguityPolicy::Skip
+            && self
+                .matched_sequence
+                .chars()
+                .any(|c| is_ambiguous_base(c.to_ascii_uppercase()))
+        {
+            return false;
+        }
+        match params.no_match_rule {
+            NoMatchRule::MismatchCap => {
+                let mismatches = if params.three_prime.is_some_and(|t| t.references) {
+                    self.weighted_mismatches(params)
+                } else {
+                    self.mismatches
+                };
+                self.full_coverage && mismatches <= params.max_mismatches as usize
+            }
+            NoMatchRule::MinScore(min_score) => self.score >= min_score,
+            NoMatchRule::MinAlignedFraction(pct) => {
+                self.aligned_length as f64 / self.oligo_length() as f64 * 100.0 >= pct
+            }
+            NoMatchRule::MinPercentIdentity(pct) => self.percent_identity() >= pct,
+        }
+    }
+
+    /// Percent identity over the full-length window, counting indel bases as
+    /// differences (0 if no window was extracted).
+    pub fn percent_identity(&self) -> f64 {
+        let len = self.oligo_length();
+        if len == 0 {
+            return 0.0;
+        }
+        let differences = (self.mismatches + self.indels).min(len);
+        (len - differences) as f64 / len as f64 * 100.0
+    }
+
+    /// Mismatches with those in the 3'-end bases counted `weight` times
+    pub fn weighted_mismatches(&self, params: &PairwiseParams) -> usize {
+        let extra = params
+            .three_prime
+            .map_or(0, |t| t.weight.saturating_sub(1) as usize);
+        self.mismatches + extra * self.three_prime_mismatches
+    }
+
+    /// Oligo columns of the window: its length without inserted bases
+    fn oligo_length(&self) -> usize {
+        self.matched_sequence
+            .bytes()
+            .filter(|b| !b.is_ascii_lowercase())
+            .count()
+    }
 }
 
 /// Process an alignment result from a pre-existing aligner.
 /// Shared logic used by both standalone alignment and batch collection.
+/// `reverse_strand` marks a reverse-complemented oligo, whose 3' end is at
+/// its start.
 fn process_alignment<F: MatchFunc>(
     aligner: &mut Aligner<F>,
     oligo: &[u8],
     reference: &[u8],
+    params: &PairwiseParams,
+    reverse_strand: bool,
 ) -> PairwiseMatch {
-    let alignment = aligner.local(oligo, reference);
+    let alignment = run_alignment(aligner, oligo, reference, params);
 
-    let mut has_gaps = false;
+    let mut indels = 0;
     let mut mismatches = 0;
 
     for op in &alignment.operations {
@@ -61,51 +231,367 @@ fn process_alignment<F: MatchFunc>(
                 mismatches += 1;
             }
             AlignmentOperation::Del | AlignmentOperation::Ins => {
-                has_gaps = true;
+                indels += 1;
             }
             AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => {}
         }
     }
 
-    let aligned_query_len = alignment.xend - alignment.xstart;
-    let full_coverage = aligned_query_len == oligo.len();
+    let aligned_length = alignment.xend - alignment.xstart;
+    let full_coverage = aligned_length == oligo.len();
 
-    let matched_sequence = if !has_gaps && full_coverage {
-        String::from_utf8_lossy(&reference[alignment.ystart..alignment.yend]).to_string()
+    let flanks = window_bounds(&alignment, oligo, reference);
+
+    // Oligo columns in the weighted 3'-end bases
+    let three_prime_bases = params
+        .three_prime
+        .map_or(0, |t| (t.bases as usize).min(oligo.len()));
+    let three_prime = if reverse_strand {
+        0..three_prime_bases
     } else {
-        String::new()
+        oligo.len() - three_prime_bases..oligo.len()
+    };
+    let mut three_prime_mismatches = 0;
+
+    let matched_sequence = match flanks {
+        Some((start, end)) => {
+            let scoring = IupacMatch::new(params);
+            let resolve = params.reference_ambiguity == ReferenceAmbiguityPolicy::Compatible;
+            let mut window = String::with_capacity(oligo.len() + indels);
+            let mut column = 0;
+            mismatches = 0;
+            for (o, r) in window_columns(&alignment, oligo, reference, start, end) {
+                if o.is_some() {
+                    column += 1;
+                }
+                match (o, r) {
+                    (Some(o), Some(r)) => {
+                        if !scoring.is_match(o, r) {
+                            mismatches += 1;
+                            if three_prime.contains(&(column - 1)) {
+                                three_prime_mismatches += 1;
+                            }
+                        }
+                        window.push(if resolve {
+                            resolve_reference_code(o, r)
+                        } else {
+                            r as char
+                        });
+                    }
+                    // Oligo base deleted in the reference
+                    (Some(_), None) => window.push('-'),
+                    // Base inserted in the reference
+                    (None, Some(r)) => window.push(r.to_ascii_lowercase() as char),
+                    (None, None) => {}
+                }
+            }
+            window
+        }
+        None => String::new(),
     };
 
     PairwiseMatch {
         matched_sequence,
         score: alignment.score,
         mismatches,
-        has_gaps,
+        has_gaps: indels > 0,
+        indels,
+        three_prime_mismatches,
         full_coverage,
+        aligned_length,
+        reference_index: 0,
     }
 }
 
-/// Align an oligo against a single reference sequence using local alignment.
-/// Creates its own aligner — use `collect_matches` for batch alignment.
-pub fn align_oligo_to_reference(
+/// Align in the configured mode
+fn run_alignment<F: MatchFunc>(
+    aligner: &mut Aligner<F>,
     oligo: &[u8],
     reference: &[u8],
     params: &PairwiseParams,
-) -> PairwiseMatch {
-    let match_score = params.match_score;
-    let mismatch_score = params.mismatch_score;
+) -> Alignment {
+    match params.alignment_mode {
+        AlignmentMode::Local if params.aligner_backend == AlignerBackend::Striped => {
+            striped_local(aligner, oligo, reference, params)
+                .unwrap_or_else(|| aligner.local(oligo, reference))
+        }
+        AlignmentMode::Local => aligner.local(oligo, reference),
+        AlignmentMode::SemiGlobal => aligner.semiglobal(oligo, reference),
+        AlignmentMode::Global => aligner.global(oligo, reference),
+    }
+}
 
-    let mut aligner = Aligner::with_capacity(
-        oligo.len(),
-        reference.len(),
+/// Local alignment through the striped kernel: locate the best hit's end,
+/// then align against the reference stretch that can hold the hit and its
+/// full-length window. None (use the full aligner) when the kernel does not
+/// apply or nothing scores above zero.
+fn striped_local<F: MatchFunc>(
+    aligner: &mut Aligner<F>,
+    oligo: &[u8],
+    reference: &[u8],
+    params: &PairwiseParams,
+) -> Option<Alignment> {
+    let (score, end) = striped_local_score(
+        oligo,
+        reference,
+        &IupacMatch::new(params),
         params.gap_open_penalty,
         params.gap_extend_penalty,
-        |a: u8, b: u8| -> i32 {
-            if a == b { match_score } else { mismatch_score }
-        },
-    );
+    )?;
+    if score <= 0 {
+        return None;
+    }
+    // Reference bases a hit can span beyond the oligo length: each costs at
+    // least the gap extension, and the hit cannot score above all matches
+    let m = oligo.len();
+    let max_deleted = match -params.gap_extend_penalty {
+        extend if extend > 0 => (params.match_score.max(0) as usize * m) / extend as usize,
+        _ => reference.len(),
+    };
+    let from = (end + 1).saturating_sub(2 * m + max_deleted);
+    let to = (end + 1 + m).min(reference.len());
+    let mut alignment = aligner.local(oligo, &reference[from..to]);
+    alignment.ystart += from;
+    alignment.yend += from;
+    alignment.ylen = reference.len();
+    // Clips before the aligned columns count from the reference start, and
+    // those after them to its end
+    let mut before_columns = true;
+    for op in &mut alignment.operations {
+        match op {
+            AlignmentOperation::Yclip(n) if before_columns => *n += from,
+            AlignmentOperation::Yclip(n) => *n += reference.len() - to,
+            AlignmentOperation::Xclip(_) => {}
+            _ => before_columns = false,
+        }
+    }
+    Some(alignment)
+}
+
+/// Reference range of the full-length window: the alignment extended without
+/// gaps to the full oligo length, so partially aligned references still yield
+/// a window. None when the extension runs past the reference ends.
+fn window_bounds(alignment: &Alignment, oligo: &[u8], reference: &[u8]) -> Option<(usize, usize)> {
+    alignment
+        .ystart
+        .checked_sub(alignment.xstart)
+        .map(|start| (start, alignment.yend + (oligo.len() - alignment.xend)))
+        .filter(|&(_, end)| end <= reference.len())
+}
+
+/// Oligo and reference base of each column of the window `start..end`: the
+/// unaligned oligo ends against the flanking reference bases, and the
+/// aligned region with None on the gapped side of each indel.
+fn window_columns<'a>(
+    alignment: &'a Alignment,
+    oligo: &'a [u8],
+    reference: &'a [u8],
+    start: usize,
+    end: usize,
+) -> impl Iterator<Item = (Option<u8>, Option<u8>)> + 'a {
+    let pair = |(&o, &r): (&u8, &u8)| (Some(o), Some(r));
+    let leading = oligo[..alignment.xstart]
+        .iter()
+        .zip(&reference[start..alignment.ystart])
+        .map(pair);
+    let trailing = oligo[alignment.xend..]
+        .iter()
+        .zip(&reference[alignment.yend..end])
+        .map(pair);
+    let (mut x, mut y) = (alignment.xstart, alignment.ystart);
+    let aligned = alignment.operations.iter().filter_map(move |op| match op {
+        AlignmentOperation::Match | AlignmentOperation::Subst => {
+            let column = (Some(oligo[x]), Some(reference[y]));
+            x += 1;
+            y += 1;
+            Some(column)
+        }
+        AlignmentOperation::Ins => {
+            let column = (Some(oligo[x]), None);
+            x += 1;
+            Some(column)
+        }
+        AlignmentOperation::Del => {
+            let column = (None, Some(reference[y]));
+            y += 1;
+            Some(column)
+        }
+        AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => None,
+    });
+    leading.chain(aligned).chain(trailing)
+}
+
+/// Reference base as recorded in the window: an IUPAC code that includes
+/// the oligo's base is read as that base; other codes are kept.
+fn resolve_reference_code(o: u8, r: u8) -> char {
+    let covers_oligo = base_to_bit(r) & base_to_bit(o) == base_to_bit(o);
+    if is_ambiguous_base(r as char) && covers_oligo {
+        o as char
+    } else {
+        r as char
+    }
+}
+
+/// Kind of one column of a displayed alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Match,
+    Mismatch,
+    /// A base on one side against a gap on the other
+    Gap,
+}
 
-    process_alignment(&mut aligner, oligo, reference)
+/// One oligo aligned against one sequence, laid out for display
+#[derive(Debug, Clone)]
+pub struct AlignmentView {
+    /// Oligo row, '-' against a base inserted in the target
+    pub oligo: String,
+    /// Target row, '-' against an oligo base deleted in it
+    pub target: String,
+    pub columns: Vec<ColumnKind>,
+    /// Columns covered by the alignment itself; those outside it are the
+    /// gapless extension to the full oligo length
+    pub aligned_columns: std::ops::Range<usize>,
+    /// 0-based position on the target of its first shown base
+    pub target_start: usize,
+    /// The reverse strand won, so the oligo row is its reverse complement
+    pub reverse_strand: bool,
+    /// The alignment as scored in the screen
+    pub result: PairwiseMatch,
+    pub accepted: bool,
+}
+
+/// Align an oligo against one sequence the way the screen does (both strands
+/// when enabled) and lay out the winning alignment for display. When the
+/// full-length window runs past the target ends, only the aligned region is
+/// shown.
+pub fn alignment_view(oligo: &[u8], target: &[u8], params: &PairwiseParams) -> AlignmentView {
+    let mut aligner = create_aligner(oligo.len(), target.len(), params);
+    let oligo_rc = reverse_strand_oligo(oligo, params);
+    let forward = process_alignment(&mut aligner, oligo, target, params, false);
+    let reverse = oligo_rc
+        .as_deref()
+        .map(|rc| process_alignment(&mut aligner, rc, target, params, true))
+        .filter(|reverse| reverse_wins(&forward, reverse, params));
+    let (shown, result, reverse_strand) = match (oligo_rc.as_deref(), reverse) {
+        (Some(rc), Some(reverse)) => (rc, in_oligo_orientation(reverse), true),
+        _ => (oligo, forward, false),
+    };
+
+    let alignment = run_alignment(&mut aligner, shown, target, params);
+    // Unaligned oligo bases shown before and after the aligned region
+    let (start, end, lead, trail) = match window_bounds(&alignment, shown, target) {
+        Some((start, end)) => (start, end, alignment.xstart, shown.len() - alignment.xend),
+        None => (alignment.ystart, alignment.yend, 0, 0),
+    };
+    let scoring = IupacMatch::new(params);
+    let mut view = AlignmentView {
+        oligo: String::new(),
+        target: String::new(),
+        columns: Vec::new(),
+        aligned_columns: 0..0,
+        target_start: start,
+        reverse_strand,
+        accepted: result.is_accepted(params),
+        result,
+    };
+    for (o, r) in window_columns(&alignment, shown, target, start, end) {
+        view.oligo.push(o.map_or('-', char::from));
+        view.target.push(r.map_or('-', char::from));
+        view.columns.push(match (o, r) {
+            (Some(o), Some(r)) if scoring.is_match(o, r) => ColumnKind::Match,
+            (Some(_), Some(_)) => ColumnKind::Mismatch,
+            _ => ColumnKind::Gap,
+        });
+    }
+    view.aligned_columns = lead..view.columns.len() - trail;
+    view
+}
+
+/// Reverse complement of the oligo when the reverse strand is searched
+fn reverse_strand_oligo(oligo: &[u8], params: &PairwiseParams) -> Option<Vec<u8>> {
+    params
+        .both_strands
+        .then(|| reverse_complement(&String::from_utf8_lossy(oligo)).into_bytes())
+}
+
+/// Align on the forward strand and, given the reverse-complemented oligo,
+/// on the reverse strand (aligning the reverse-complemented oligo is the
+/// same as aligning the oligo to the reverse-complemented reference). The
+/// accepted hit wins, then the higher score; ties keep the forward strand.
+/// A reverse-strand `matched_sequence` is given in the oligo's orientation.
+fn align_both_strands<F: MatchFunc>(
+    aligner: &mut Aligner<F>,
+    oligo: &[u8],
+    oligo_rc: Option<&[u8]>,
+    reference: &[u8],
+    params: &PairwiseParams,
+) -> PairwiseMatch {
+    let forward = process_alignment(aligner, oligo, reference, params, false);
+    let Some(oligo_rc) = oligo_rc else {
+        return forward;
+    };
+    let reverse = process_alignment(aligner, oligo_rc, reference, params, true);
+    if reverse_wins(&forward, &reverse, params) {
+        in_oligo_orientation(reverse)
+    } else {
+        forward
+    }
+}
+
+/// Whether the reverse-strand hit beats the forward one (see
+/// `align_both_strands`)
+fn reverse_wins(forward: &PairwiseMatch, reverse: &PairwiseMatch, params: &PairwiseParams) -> bool {
+    (reverse.is_accepted(params), reverse.score) > (forward.is_accepted(params), forward.score)
+}
+
+/// A reverse-strand hit with its matched window turned to the oligo's orientation
+fn in_oligo_orientation(reverse: PairwiseMatch) -> PairwiseMatch {
+    PairwiseMatch {
+        matched_sequence: reverse_complement(&reverse.matched_sequence),
+        ..reverse
+    }
+}
+
+/// Whether an exact occurrence of the oligo in a reference is always its
+/// best alignment: nothing scores above a full-length run of matches, and no
+/// gap adds to the score. Global alignment scores the whole reference, so an
+/// exact occurrence says nothing about it.
+pub fn exact_hits_are_best(params: &PairwiseParams) -> bool {
+    params.alignment_mode != AlignmentMode::Global
+        && params.match_score > 0
+        && params.mismatch_score <= params.match_score
+        && params.gap_open_penalty <= 0
+        && params.gap_extend_penalty <= 0
+}
+
+/// The match of an oligo found verbatim in a reference: full length, no
+/// mismatches or gaps, scoring a match for every base. Only the best
+/// alignment when `exact_hits_are_best`; on the reverse strand it would only
+/// tie, and ties keep the forward strand.
+pub fn exact_match(oligo: &[u8], params: &PairwiseParams) -> PairwiseMatch {
+    PairwiseMatch {
+        matched_sequence: String::from_utf8_lossy(oligo).into_owned(),
+        score: params.match_score * oligo.len() as i32,
+        mismatches: 0,
+        has_gaps: false,
+        indels: 0,
+        three_prime_mismatches: 0,
+        full_coverage: true,
+        aligned_length: oligo.len(),
+        reference_index: 0,
+    }
+}
+
+/// Align an oligo against a single reference sequence in the configured mode.
+/// Creates its own aligner — use `collect_matches` for batch alignment.
+pub fn align_oligo_to_reference(
+    oligo: &[u8],
+    reference: &[u8],
+    params: &PairwiseParams,
+) -> PairwiseMatch {
+    let mut aligner = create_aligner(oligo.len(), reference.len(), params);
+    process_alignment(&mut aligner, oligo, reference, params, false)
 }
 
 /// Align an oligo against all reference sequences and collect valid matches.
@@ -114,10 +600,10 @@ pub fn align_oligo_to_reference(
 /// for all alignments, avoiding repeated large allocations.
 ///
 /// Returns (matched_sequences, no_match_count).
-/// A match is rejected (counted as "no match") if:
-/// - The alignment contains gaps
-/// - The alignment doesn't cover the full oligo
-/// - The number of mismatches exceeds max_mismatches
+/// A match is rejected (counted as "no match") if the alignment has more
+/// indel bases than `max_indels` or fails the configured `NoMatchRule` (by
+/// default: doesn't cover the full oligo, or the number of mismatches
+/// exceeds max_mismatches).
 pub fn collect_matches(
     oligo: &[u8],
     references: &[Vec<u8>],
@@ -133,27 +619,17 @@ pub fn collect_matches(
     // Create a single aligner sized for the longest reference, reused for all alignments.
     // This avoids re-allocating the O(m*n) DP matrices for every reference.
     let max_ref_len = references.iter().map(|r| r.len()).max().unwrap();
-    let match_score = params.match_score;
-    let mismatch_score = params.mismatch_score;
-
-    let mut aligner = Aligner::with_capacity(
-        oligo.len(),
-        max_ref_len,
-        params.gap_open_penalty,
-        params.gap_extend_penalty,
-        |a: u8, b: u8| -> i32 {
-            if a == b { match_score } else { mismatch_score }
-        },
-    );
+    let mut aligner = create_aligner(oligo.len(), max_ref_len, params);
 
+    let oligo_rc = reverse_strand_oligo(oligo, params);
     for reference in references {
-        let result = process_alignment(&mut aligner, oligo, reference);
+        let result =
+            align_both_strands(&mut aligner, oligo, oligo_rc.as_deref(), reference, params);
 
-        if !result.full_coverage || result.has_gaps || result.mismatches > params.max_mismatches as usize
-        {
-            no_match_count += 1;
-        } else {
+        if result.is_accepted(params) {
             matched.push(result.matched_sequence);
+        } else {
+            no_match_count += 1;
         }
     }
 
@@ -162,23 +638,45 @@ pub fn collect_matches(
 
 /// Align an oligo against all references using a pre-existing aligner.
 /// The aligner must be sized for at least (oligo.len(), max_ref_len).
+///
+/// Returns (accepted matches, no_match_count).
 pub fn collect_matches_with_aligner(
     aligner: &mut DnaAligner,
     oligo: &[u8],
     references: &[Vec<u8>],
     params: &PairwiseParams,
-) -> (Vec<String>, usize) {
+) -> (Vec<PairwiseMatch>, usize) {
+    collect_matches_with_anchors(aligner, oligo, references, params, |_| false)
+}
+
+/// Like `collect_matches_with_aligner`, skipping the alignment of references
+/// for which `contains_oligo` says the oligo occurs verbatim: their match is
+/// the exact one (see `exact_match`), which is what the alignment would find.
+pub fn collect_matches_with_anchors(
+    aligner: &mut DnaAligner,
+    oligo: &[u8],
+    references: &[Vec<u8>],
+    params: &PairwiseParams,
+    contains_oligo: impl Fn(usize) -> bool,
+) -> (Vec<PairwiseMatch>, usize) {
     let mut matched = Vec::new();
     let mut no_match_count = 0;
 
-    for reference in references {
-        let result = process_alignment(aligner, oligo, reference);
+    let oligo_rc = reverse_strand_oligo(oligo, params);
+    for (reference_index, reference) in references.iter().enumerate() {
+        let result = if contains_oligo(reference_index) {
+            exact_match(oligo, params)
+        } else {
+            align_both_strands(aligner, oligo, oligo_rc.as_deref(), reference, params)
+        };
 
-        if !result.full_coverage || result.has_gaps || result.mismatches > params.max_mismatches as usize
-        {
-            no_match_count += 1;
+        if result.is_accepted(params) {
+            matched.push(PairwiseMatch {
+                reference_index,
+                ..result
+            });
         } else {
-            matched.push(result.matched_sequence);
+            no_match_count += 1;
         }
     }
 
@@ -189,32 +687,74 @@ pub fn collect_matches_with_aligner(
 /// return per-sequence mismatch counts for exclusivity analysis.
 ///
 /// Returns a Vec with one entry per reference: Some(mismatches) for valid
-/// alignments, None for no-match (gaps, partial coverage, or exceeds max_mismatches).
+/// alignments, None for no-match (too many indels, or fails the configured
+/// `NoMatchRule`). Indel bases of accepted alignments count as mismatches,
+/// and with 3' weighting the mismatches are weighted.
 pub fn collect_mismatch_counts_with_aligner(
     aligner: &mut DnaAligner,
     oligo: &[u8],
     references: &[Vec<u8>],
     params: &PairwiseParams,
 ) -> Vec<Option<u32>> {
+    collect_exclusivity_hits_with_aligner(aligner, oligo, references, params)
+        .into_iter()
+        .map(|hit| hit.map(|(mismatches, _)| mismatches))
+        .collect()
+}
+
+/// Like `collect_mismatch_counts_with_aligner`, with each count paired with
+/// the unweighted mismatches within the 3'-end bases (0 without 3'
+/// weighting).
+pub fn collect_exclusivity_hits_with_aligner(
+    aligner: &mut DnaAligner,
+    oligo: &[u8],
+    references: &[Vec<u8>],
+    params: &PairwiseParams,
+) -> Vec<Option<(u32, u32)>> {
+    let oligo_rc = reverse_strand_oligo(oligo, params);
     references
         .iter()
-        .map(|reference| {
-            let result = process_alignment(aligner, oligo, reference);
-            if !result.full_coverage
-                || result.has_gaps
-                || result.mismatches > params.max_mismatches as usize
-            {
-                None
-            } else {
-                Some(result.mismatches as u32)
-            }
-        })
+        .map(|reference| exclusivity_hit(aligner, oligo, oligo_rc.as_deref(), reference, params))
         .collect()
 }
 
+/// `collect_exclusivity_hits_with_aligner` aligning only the references at
+/// `candidates`; the others are no match.
+pub fn collect_exclusivity_hits_among(
+    aligner: &mut DnaAligner,
+    oligo: &[u8],
+    references: &[Vec<u8>],
+    candidates: &[usize],
+    params: &PairwiseParams,
+) -> Vec<Option<(u32, u32)>> {
+    let oligo_rc = reverse_strand_oligo(oligo, params);
+    let mut hits = vec![None; references.len()];
+    for &i in candidates {
+        hits[i] = exclusivity_hit(aligner, oligo, oligo_rc.as_deref(), &references[i], params);
+    }
+    hits
+}
+
+/// Weighted mismatches plus indels, and 3'-end mismatches, of an accepted
+/// hit on either strand
+fn exclusivity_hit(
+    aligner: &mut DnaAligner,
+    oligo: &[u8],
+    oligo_rc: Option<&[u8]>,
+    reference: &[u8],
+    params: &PairwiseParams,
+) -> Option<(u32, u32)> {
+    let result = align_both_strands(aligner, oligo, oligo_rc, reference, params);
+    let mismatches = result.weighted_mismatches(params) + result.indels;
+    result
+        .is_accepted(params)
+        .then_some((mismatches as u32, result.three_prime_mismatches as u32))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::types::ThreePrimeWeighting;
 
     fn default_params() -> PairwiseParams {
         PairwiseParams::default()
@@ -254,6 +794,7 @@ mod tests {
         assert!(result.full_coverage);
         assert_eq!(result.mismatches, 1);
         assert_eq!(result.matched_sequence, "TATGGTTCGT");
+        assert!((result.percent_identity() - 90.0).abs() < 1e-9);
     }
 
     #[test]
@@ -289,4 +830,290 @@ mod tests {
         assert_eq!(matched.len(), 1);
         assert_eq!(no_match, 1);
     }
+
+    #[test]
+    fn test_no_match_rules() {
+        let oligo = b"TATGGTACGT";
+        // Last three bases mismatch: local alignment only covers the first 7
+        let reference = b"CCTATGGTAGCACCC";
+        let result = align_oligo_to_reference(oligo, reference, &default_params());
+        assert!(!result.full_coverage);
+        assert_eq!(result.matched_sequence, "TATGGTAGCA");
+        assert_eq!(result.mismatches, 3);
+
+        let mut params = default_params();
+        assert!(!result.is_accepted(&params));
+        params.no_match_rule = NoMatchRule::MinAlignedFraction(70.0);
+        assert!(result.is_accepted(&params));
+        params.no_match_rule = NoMatchRule::MinPercentIdentity(80.0);
+        assert!(!result.is_accepted(&params));
+        params.no_match_rule = NoMatchRule::MinPercentIdentity(70.0);
+        assert!(result.is_accepted(&params));
+        params.no_match_rule = NoMatchRule::MinScore(result.score + 1);
+        assert!(!result.is_accepted(&params));
+    }
+
+    #[test]
+    fn test_alignment_modes() {
+        let oligo = b"TATGGTACGT";
+        // Last three bases mismatch
+        let reference = b"CCTATGGTAGCACCC";
+        // Gaps costly enough that no gapped alignment ties the ungapped one
+        let with_mode = |mode| PairwiseParams {
+            alignment_mode: mode,
+            gap_open_penalty: -5,
+            ..default_params()
+        };
+
+        // Local drops the mismatching end, semi-global aligns the whole oligo
+        let local = align_oligo_to_reference(oligo, reference, &with_mode(AlignmentMode::Local));
+        assert!(!local.full_coverage);
+        let params = with_mode(AlignmentMode::SemiGlobal);
+        let fit = align_oligo_to_reference(oligo, reference, &params);
+        assert!(fit.full_coverage);
+        assert_eq!(fit.matched_sequence, "TATGGTAGCA");
+        assert_eq!(fit.mismatches, 3);
+        assert!(fit.is_accepted(&params));
+
+        // Global needs the reference cut to the oligo site
+        let params = with_mode(AlignmentMode::Global);
+        let site = align_oligo_to_reference(oligo, b"TATGGTTCGT", &params);
+        assert_eq!(site.mismatches, 1);
+        assert!(site.is_accepted(&params));
+        let genome = align_oligo_to_reference(oligo, reference, &params);
+        assert!(genome.has_gaps);
+        assert!(!genome.is_accepted(&params));
+    }
+
+    #[test]
+    fn test_indel_windows() {
+        let oligo = b"TATGGTACGT";
+        let mut params = default_params();
+
+        // The T at oligo base 6 is deleted in the reference
+        let result = align_oligo_to_reference(oligo, b"CCTATGGACGTCCC", &params);
+        assert_eq!(result.matched_sequence, "TATGG-ACGT");
+        assert_eq!((result.indels, result.mismatches), (1, 0));
+        assert!(result.full_coverage);
+        assert!((result.percent_identity() - 90.0).abs() < 1e-9);
+        assert!(!result.is_accepted(&params));
+        params.max_indels = 1;
+        assert!(result.is_accepted(&params));
+
+        // An extra T in the reference is written in lowercase
+        let result = align_oligo_to_reference(oligo, b"CCTATGGTTACGTCCC", &params);
+        assert_eq!(result.indels, 1);
+        assert_eq!(result.matched_sequence.to_uppercase(), "TATGGTTACGT");
+        assert_eq!(result.matched_sequence.matches('t').count(), 1);
+        assert!(result.is_accepted(&params));
+    }
+
+    #[test]
+    fn test_three_prime_weighting() {
+        let oligo = b"TATGGTACGT";
+        let mut params = PairwiseParams {
+            alignment_mode: AlignmentMode::SemiGlobal,
+            max_mismatches: 2,
+            three_prime: Some(ThreePrimeWeighting::default()),
+            ..default_params()
+        };
+        let at_end = align_oligo_to_reference(oligo, b"CCTATGGTACGACC", &params);
+        assert_eq!((at_end.mismatches, at_end.three_prime_mismatches), (1, 1));
+        assert_eq!(at_end.weighted_mismatches(&params), 3);
+        let at_start = align_oligo_to_reference(oligo, b"CCAATGGTACGTCC", &params);
+        assert_eq!(
+            (at_start.mismatches, at_start.three_prime_mismatches),
+            (1, 0)
+        );
+
+        // Reference matching is only weighted on request
+        assert!(at_end.is_accepted(&params));
+        params.three_prime = Some(ThreePrimeWeighting {
+            references: true,
+            ..Default::default()
+        });
+        assert!(!at_end.is_accepted(&params));
+        assert!(at_start.is_accepted(&params));
+
+        // Exclusivity counts are weighted; on the reverse strand the oligo's
+        // 3' end is the start of its reverse complement
+        params.three_prime = Some(ThreePrimeWeighting::default());
+        params.both_strands = true;
+        let references = vec![b"CCTATGGTACGACC".to_vec(), b"GGTCGTACCATAGG".to_vec()];
+        let mut aligner = create_aligner(oligo.len(), 14, &params);
+        let hits = collect_exclusivity_hits_with_aligner(&mut aligner, oligo, &references, &params);
+        assert_eq!(hits, vec![Some((3, 1)), Some((3, 1))]);
+    }
+
+    #[test]
+    fn test_alignment_view() {
+        let oligo = b"TATGGTACGT";
+        let params = default_params();
+
+        let view = alignment_view(oligo, b"CCTATGGTCCGTCC", &params);
+        assert_eq!(view.oligo, "TATGGTACGT");
+        assert_eq!(view.target, "TATGGTCCGT");
+        assert_eq!(view.columns[6], ColumnKind::Mismatch);
+        assert_eq!(view.target_start, 2);
+        assert_eq!(view.aligned_columns, 0..10);
+        assert!(view.accepted && !view.reverse_strand);
+
+        // A local hit clipped before the last base is extended to show it,
+        // and fails the mismatch cap for not covering the oligo
+        let clipped = alignment_view(oligo, b"CCTATGGTACGACC", &params);
+        assert_eq!(clipped.aligned_columns, 0..9);
+        assert_eq!(clipped.columns[9], ColumnKind::Mismatch);
+        assert!(!clipped.accepted);
+
+        let both = PairwiseParams {
+            both_strands: true,
+            ..default_params()
+        };
+        let reverse = alignment_view(oligo, b"GGACGTACCATAGG", &both);
+        assert!(reverse.reverse_strand && reverse.accepted);
+        assert_eq!(reverse.oligo, "ACGTACCATA");
+    }
+
+    #[test]
+    fn test_thread_aligner_reuse() {
+        let references = vec![
+            b"TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_vec(),
+            b"AATATGGTACGTCATGTTCTAG".to_vec(),
+        ];
+        let params = default_params();
+        let expected = collect_matches(b"TATGGTACGT", &references, &params);
+
+        // A larger cached aligner gives the same results for a smaller job
+        with_thread_aligner(20, 100, &params, |_| {});
+        let reused = with_thread_aligner(10, 31, &params, |aligner| {
+            collect_matches_with_aligner(aligner, b"TATGGTACGT", &references, &params)
+        });
+        assert_eq!(reused.1, expected.1);
+        assert_eq!(reused.0.len(), expected.0.len());
+        for (a, b) in reused.0.iter().zip(&expected.0) {
+            assert_eq!(&a.matched_sequence, b);
+        }
+
+        // Changed scores rebuild the aligner
+        let mut stricter = params;
+        stricter.match_score = 3;
+        let rescored = with_thread_aligner(10, 31, &stricter, |aligner| {
+            collect_matches_with_aligner(aligner, b"TATGGTACGT", &references, &stricter)
+        });
+        assert_eq!(rescored.0[0].score, 30);
+    }
+
+    #[test]
+    fn test_reference_ambiguity_policies() {
+        let oligo = b"TATGGTACGT";
+        let reference = b"AATATGRTACGTCATG";
+        let with_policy = |policy| PairwiseParams {
+            reference_ambiguity: policy,
+            ..default_params()
+        };
+
+        let params = with_policy(ReferenceAmbiguityPolicy::Compatible);
+        let result = align_oligo_to_reference(oligo, reference, &params);
+        assert_eq!(result.matched_sequence, "TATGGTACGT");
+        assert_eq!(result.mismatches, 0);
+        assert!(result.is_accepted(&params));
+
+        let params = with_policy(ReferenceAmbiguityPolicy::Expand);
+        let result = align_oligo_to_reference(oligo, reference, &params);
+        assert_eq!(result.matched_sequence, "TATGRTACGT");
+        assert_eq!(result.mismatches, 0);
+        assert!(result.is_accepted(&params));
+
+        let params = with_policy(ReferenceAmbiguityPolicy::Mismatch);
+        let result = align_oligo_to_reference(oligo, reference, &params);
+        assert_eq!(result.matched_sequence, "TATGRTACGT");
+        assert_eq!(result.mismatches, 1);
+        assert!(result.is_accepted(&params));
+
+        let params = with_policy(ReferenceAmbiguityPolicy::Skip);
+        let result = align_oligo_to_reference(oligo, reference, &params);
+        assert!(!result.is_accepted(&params));
+    }
+
+    #[test]
+    fn test_reverse_strand_matches() {
+        // Forward reference, then one stored reverse-complemented with a mismatch
+        let references = vec![
+            b"TATGGTACGTCATGTTCTAG".to_vec(),
+            reverse_complement("AATATGGTTCGTCATGTT").into_bytes(),
+        ];
+        let forward_only = default_params();
+        let (matches, no_match) = with_thread_aligner(10, 20, &forward_only, |aligner| {
+            collect_matches_with_aligner(aligner, b"TATGGTACGT", &references, &forward_only)
+        });
+        assert_eq!((matches.len(), no_match), (1, 1));
+
+        let both = PairwiseParams {
+            both_strands: true,
+            ..forward_only
+        };
+        let (matches, no_match) = with_thread_aligner(10, 20, &both, |aligner| {
+            collect_matches_with_aligner(aligner, b"TATGGTACGT", &references, &both)
+        });
+        assert_eq!(no_match, 0);
+        // Reported in the oligo's orientation
+        assert_eq!(matches[1].reference_index, 1);
+        assert_eq!(matches[1].matched_sequence, "TATGGTTCGT");
+        assert_eq!(matches[1].mismatches, 1);
+
+        let counts = with_thread_aligner(10, 20, &both, |aligner| {
+            collect_mismatch_counts_with_aligner(aligner, b"TATGGTACGT", &references, &both)
+        });
+        assert_eq!(counts, vec![Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn test_striped_backend_matches_standard() {
+        let background = b"CAGGACTTCAGCTTAGCCGATCCTGAACGTAGGCTTACA".repeat(8);
+        let oligo = b"TATGGTACGTCATGTTCTAG";
+        let standard = PairwiseParams {
+            max_indels: 2,
+            ..default_params()
+        };
+        let striped = PairwiseParams {
+            aligner_backend: AlignerBackend::Striped,
+            ..standard
+        };
+
+        let sites: [&[u8]; 4] = [
+            b"TATGGTACGTCATGTTCTAG",
+            b"TATGGTACCTCATGTTCTAG",
+            b"TATGGTACGTCAGTTCTAG",
+            b"TATGGTACGTCAATGTTCTAG",
+        ];
+        for site in sites {
+            for at in [0, 150, background.len()] {
+                let mut reference = background[..at].to_vec();
+                reference.extend_from_slice(site);
+                reference.extend_from_slice(&background[at..]);
+                let expected = align_oligo_to_reference(oligo, &reference, &standard);
+                let result = align_oligo_to_reference(oligo, &reference, &striped);
+                let summary = |m: &PairwiseMatch| {
+                    (m.matched_sequence.clone(), m.score, m.mismatches, m.indels)
+                };
+                assert_eq!(
+                    summary(&result),
+                    summary(&expected),
+                    "site {:?} at {}",
+                    site,
+                    at
+                );
+            }
+        }
+
+        // A window running past the reference start yields no window either way
+        let mut reference = oligo[5..].to_vec();
+        reference.extend_from_slice(&background);
+        let result = align_oligo_to_reference(oligo, &reference, &striped);
+        assert!(result.matched_sequence.is_empty());
+        assert_eq!(
+            result.score,
+            align_oligo_to_reference(oligo, &reference, &standard).score
+        );
+    }
 }
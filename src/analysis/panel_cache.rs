@@ -0,0 +1,604 @@
+//! Prepared exclusivity panels and their seed index
+//!
+//! Before a screen, the exclusivity files are merged, identical sequences are
+//! collapsed so each is aligned once, the sequences are converted to bytes,
+//! and the names are grouped by taxon. Under the mismatch cap, the panel also
+//! gets a seed index: which sequences hold each k-mer, so a window is only
+//! aligned against the sequences it can match (see `SeedIndex::candidates`).
+//! Building the index is the expensive step for a genome-scale panel, so it
+//! is written to a cache directory under the checksum of its inputs, and
+//! every later job (or session) screening the same files reads it back
+//! instead. The checksum is verified on every read, and the least recently
+//! used files are removed once the cache grows past its size limit.
+
+use std::collections::HashMap;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use super::fasta::ExclusivityGroup;
+use super::iupac::{is_ambiguous_base, reverse_complement};
+use super::repro::hex;
+use super::taxon::{MAX_SUMMARIZED_TAXA, TaxonGroups};
+use super::types::{AnalysisParams, NoMatchRule, PairwiseParams, ReferenceAmbiguityPolicy};
+
+/// Extension of seed index files
+pub const PANEL_CACHE_EXTENSION: &str = "ospanel";
+
+/// Size the cache directory is kept under; the least recently used index
+/// files are removed first
+pub const PANEL_CACHE_MAX_BYTES: u64 = 4 << 30;
+
+/// Length of the k-mers the seed index is built from
+pub const SEED_LENGTH: usize = 10;
+
+/// First bytes of a seed index file
+const SEED_INDEX_MAGIC: &[u8; 8] = b"OSPANEL1";
+
+/// Merged exclusivity groups ready for screening
+#[derive(Debug, Clone, Default)]
+pub struct PreparedPanel {
+    /// SHA-256 of the groups the panel was prepared from
+    pub checksum: String,
+    /// Group names and sequence counts; each group is a contiguous range of
+    /// the panel
+    pub groups: Vec<(String, usize)>,
+    /// Sequence names, in panel order
+    pub names: Vec<String>,
+    /// Index into `distinct` of each sequence
+    pub distinct_of: Vec<usize>,
+    /// The distinct sequences, as bytes
+    pub distinct: Vec<Vec<u8>>,
+    /// Taxa of the sequences, unless the panel has more than
+    /// `MAX_SUMMARIZED_TAXA`
+    pub taxa: Option<TaxonGroups>,
+    /// Seed index of `distinct`; None when the parameters it was prepared
+    /// for can't use one (see `seed_index_usable`)
+    pub index: Option<SeedIndex>,
+}
+
+impl PreparedPanel {
+    /// Prepare the panel, with a seed index when `params` can use one
+    pub fn new(groups: &[ExclusivityGroup], params: &AnalysisParams) -> Self {
+        let mut panel = Self::collapse(groups);
+        if seed_index_usable(params) {
+            panel.index = Some(SeedIndex::build(&panel.distinct));
+        }
+        panel
+    }
+
+    /// The panel without a seed index
+    fn collapse(groups: &[ExclusivityGroup]) -> Self {
+        let mut panel = Self {
+            checksum: panel_checksum(groups),
+            ..Default::default()
+        };
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for group in groups {
+            panel.groups.push((group.name.clone(), group.data.len()));
+            panel.names.extend_from_slice(&group.data.names);
+            for sequence in &group.data.sequences {
+                let distinct = *index.entry(sequence).or_insert_with(|| {
+                    panel.distinct.push(sequence.as_bytes().to_vec());
+                    panel.distinct.len() - 1
+                });
+                panel.distinct_of.push(distinct);
+            }
+        }
+        panel.taxa =
+            Some(TaxonGroups::new(&panel.names)).filter(|t| t.labels.len() <= MAX_SUMMARIZED_TAXA);
+        panel
+    }
+
+    /// Number of sequences, copies included
+    pub fn len(&self) -> usize {
+        self.distinct_of.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distinct_of.is_empty()
+    }
+
+    /// Per-sequence values from values of the distinct sequences.
+    pub fn expand<T: Copy>(&self, distinct: &[T]) -> Vec<T> {
+        self.distinct_of.iter().map(|&d| distinct[d]).collect()
+    }
+
+    /// The distinct sequences the window `oligo` can have an accepted hit
+    /// in, ascending, or None when all of them have to be aligned.
+    pub fn candidates(&self, oligo: &[u8], params: &PairwiseParams) -> Option<Vec<usize>> {
+        self.index.as_ref()?.candidates(oligo, params)
+    }
+}
+
+/// Pieces a window is split into so that an accepted hit under the mismatch
+/// cap matches at least one of them exactly: one more than the mismatches
+/// and indel bases it may have. None under other no-match rules.
+fn seed_pieces(params: &PairwiseParams) -> Option<usize> {
+    (params.no_match_rule == NoMatchRule::MismatchCap)
+        .then(|| params.max_mismatches as usize + params.max_indels as usize + 1)
+}
+
+/// Whether windows of the longest oligo length split into pieces of at least
+/// `SEED_LENGTH` bases, so a seed index saves alignments. With the default
+/// mismatch cap of 8 that takes oligos of 90 bases or more.
+pub fn seed_index_usable(params: &AnalysisParams) -> bool {
+    seed_pieces(&params.pairwise)
+        .is_some_and(|pieces| params.max_oligo_length as usize / pieces >= SEED_LENGTH)
+}
+
+/// Which distinct panel sequences hold each k-mer of `SEED_LENGTH` bases
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeedIndex {
+    /// The k-mers found in the panel, 2-bit packed, ascending
+    kmers: Vec<u32>,
+    /// Start of each k-mer's sequences in `sequences`, and the end of the last
+    offsets: Vec<u64>,
+    /// Sequences holding each k-mer, ascending per k-mer
+    sequences: Vec<u32>,
+    /// Sequences with ambiguity codes, which can match a window through a
+    /// code without holding any of its k-mers
+    ambiguous: Vec<u32>,
+}
+
+impl SeedIndex {
+    pub fn build(distinct: &[Vec<u8>]) -> Self {
+        let mut entries: Vec<(u32, u32)> = distinct
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, sequence)| {
+                let mut kmers = packed_kmers(sequence);
+                kmers.sort_unstable();
+                kmers.dedup();
+                kmers.into_iter().map(move |kmer| (kmer, i as u32))
+            })
+            .collect();
+        entries.par_sort_unstable();
+
+        let mut index = Self::default();
+        for (kmer, sequence) in entries {
+            if index.kmers.last() != Some(&kmer) {
+                index.kmers.push(kmer);
+                index.offsets.push(index.sequences.len() as u64);
+            }
+            index.sequences.push(sequence);
+        }
+        index.offsets.push(index.sequences.len() as u64);
+        index.ambiguous = distinct
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                s.iter()
+                    .any(|&b| is_ambiguous_base(b.to_ascii_uppercase() as char))
+            })
+            .map(|(i, _)| i as u32)
+            .collect();
+        index
+    }
+
+    /// Sequences holding `kmer`
+    fn holding(&self, kmer: u32) -> &[u32] {
+        match self.kmers.binary_search(&kmer) {
+            Ok(i) => &self.sequences[self.offsets[i] as usize..self.offsets[i + 1] as usize],
+            Err(_) => &[],
+        }
+    }
+
+    /// The sequences the window `oligo` can have an accepted hit in,
+    /// ascending, or None when all of them have to be aligned. An accepted
+    /// hit under the mismatch cap leaves one of `seed_pieces` disjoint pieces
+    /// of the oligo (or, on both strands, of its reverse complement) intact,
+    /// so the sequence holds every k-mer of that piece; each piece is looked
+    /// up by its rarest k-mer. Sequences with ambiguity codes are always
+    /// candidates when a code can match.
+    pub fn candidates(&self, oligo: &[u8], params: &PairwiseParams) -> Option<Vec<usize>> {
+        let pieces = seed_pieces(params)?;
+        let piece_length = oligo.len() / pieces;
+        if piece_length < SEED_LENGTH {
+            return None;
+        }
+        let mut strands = vec![oligo.to_vec()];
+        if params.both_strands {
+            strands.push(reverse_complement(&String::from_utf8_lossy(oligo)).into_bytes());
+        }
+
+        let mut found: Vec<u32> = Vec::new();
+        for strand in &strands {
+            for piece in strand.chunks_exact(piece_length).take(pieces) {
+                let kmers = packed_kmers(piece);
+                // A piece with other than plain bases can't be looked up
+                if kmers.len() != piece_length - SEED_LENGTH + 1 {
+                    return None;
+                }
+                let rarest = kmers
+                    .iter()
+                    .map(|&kmer| self.holding(kmer))
+                    .min_by_key(|sequences| sequences.len())
+                    .unwrap_or_default();
+                found.extend_from_slice(rarest);
+            }
+        }
+        if matches!(
+            params.reference_ambiguity,
+            ReferenceAmbiguityPolicy::Compatible | ReferenceAmbiguityPolicy::Expand
+        ) {
+            found.extend_from_slice(&self.ambiguous);
+        }
+        found.sort_unstable();
+        found.dedup();
+        Some(found.into_iter().map(|i| i as usize).collect())
+    }
+}
+
+/// Every k-mer of `SEED_LENGTH` plain bases in `sequence`, 2-bit packed, in
+/// order; k-mers across other characters are left out.
+fn packed_kmers(sequence: &[u8]) -> Vec<u32> {
+    let mask = (1u32 << (2 * SEED_LENGTH)) - 1;
+    let mut kmers = Vec::with_capacity((sequence.len() + 1).saturating_sub(SEED_LENGTH));
+    let (mut kmer, mut run) = (0u32, 0usize);
+    for &b in sequence {
+        let code = match b.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' | b'U' => 3,
+            _ => {
+                run = 0;
+                continue;
+            }
+        };
+        kmer = ((kmer << 2) | code) & mask;
+        run += 1;
+        if run >= SEED_LENGTH {
+            kmers.push(kmer);
+        }
+    }
+    kmers
+}
+
+/// SHA-256 of the group names and the names and sequences of their members,
+/// as hex.
+pub fn panel_checksum(groups: &[ExclusivityGroup]) -> String {
+    let mut hasher = Sha256::new();
+    for group in groups {
+        for field in [&group.name, &group.data.len().to_string()] {
+            hasher.update(field.as_bytes());
+            hasher.update([0]);
+        }
+        for (name, sequence) in group.data.names.iter().zip(&group.data.sequences) {
+            hasher.update(name.as_bytes());
+            hasher.update([0]);
+            hasher.update(sequence.as_bytes());
+            hasher.update([0]);
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+/// File of the seed index of the panel with `checksum` in the cache
+/// directory `dir`
+pub fn panel_cache_path(dir: &Path, checksum: &str) -> PathBuf {
+    dir.join(checksum).with_extension(PANEL_CACHE_EXTENSION)
+}
+
+/// Write a panel's seed index after the panel's checksum, as little-endian
+/// arrays. The file is written next to `path` first, so jobs reading it
+/// never see half an index.
+pub fn write_seed_index(index: &SeedIndex, checksum: &str, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let tmp = path.with_extension("tmp");
+    let file = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    write_index_to(&mut writer, index, checksum)
+        .and_then(|_| writer.flush())
+        .map_err(|e| e.to_string())?;
+    drop(writer);
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn write_index_to(writer: &mut impl Write, index: &SeedIndex, checksum: &str) -> io::Result<()> {
+    writer.write_all(SEED_INDEX_MAGIC)?;
+    writer.write_all(&(checksum.len() as u64).to_le_bytes())?;
+    writer.write_all(checksum.as_bytes())?;
+    writer.write_all(&(index.kmers.len() as u64).to_le_bytes())?;
+    for kmer in &index.kmers {
+        writer.write_all(&kmer.to_le_bytes())?;
+    }
+    for offset in &index.offsets {
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+    for sequence in &index.sequences {
+        writer.write_all(&sequence.to_le_bytes())?;
+    }
+    writer.write_all(&(index.ambiguous.len() as u64).to_le_bytes())?;
+    for sequence in &index.ambiguous {
+        writer.write_all(&sequence.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Read the seed index at `path`, which must have been built from the panel
+/// with `checksum`.
+pub fn read_seed_index(path: &Path, checksum: &str) -> Result<SeedIndex, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    read_index_from(&mut BufReader::new(file), checksum)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The checksum is checked before the arrays are read, so a file of
+/// another panel costs no more than its header.
+fn read_index_from(reader: &mut impl Read, checksum: &str) -> io::Result<SeedIndex> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != SEED_INDEX_MAGIC {
+        return Err(invalid("not a seed index file"));
+    }
+    let mut built_from = vec![0u8; read_u64(reader)?.min(1024) as usize];
+    reader.read_exact(&mut built_from)?;
+    if built_from != checksum.as_bytes() {
+        return Err(invalid("built from other sequences"));
+    }
+
+    let count = read_u64(reader)? as usize;
+    let kmers = (0..count)
+        .map(|_| read_u32(reader))
+        .collect::<io::Result<_>>()?;
+    let offsets: Vec<u64> = (0..=count)
+        .map(|_| read_u64(reader))
+        .collect::<io::Result<_>>()?;
+    let sequences = (0..offsets[count])
+        .map(|_| read_u32(reader))
+        .collect::<io::Result<_>>()?;
+    let ambiguous = (0..read_u64(reader)?)
+        .map(|_| read_u32(reader))
+        .collect::<io::Result<_>>()?;
+    Ok(SeedIndex {
+        kmers,
+        offsets,
+        sequences,
+        ambiguous,
+    })
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Remove the least recently used index files in `dir` until the rest take
+/// at most `max_bytes`, keeping `keep`.
+pub fn trim_panel_cache(dir: &Path, max_bytes: u64, keep: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == PANEL_CACHE_EXTENSION))
+        .filter_map(|path| {
+            let metadata = std::fs::metadata(&path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), path))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort();
+    for (_, size, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path != keep && std::fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
+
+/// The prepared panel of `groups` for a job with `params`, and the
+/// seed index file it uses. `cached` is the file a job recorded earlier; it,
+/// then the file in `cache_dir` named by checksum, is used when it was built
+/// from this panel. Otherwise the index is built when `params` can use one
+/// and written to `cache_dir`, removing the least recently used files past
+/// `PANEL_CACHE_MAX_BYTES`. The file is None without an index, a cache
+/// directory, or when writing failed, which only costs the next job the
+/// build.
+pub fn load_prepared_panel(
+    groups: &[ExclusivityGroup],
+    params: &AnalysisParams,
+    cached: Option<&Path>,
+    cache_dir: Option<&Path>,
+) -> (PreparedPanel, Option<PathBuf>) {
+    let mut panel = PreparedPanel::collapse(groups);
+    let cache_path = cache_dir.map(|dir| panel_cache_path(dir, &panel.checksum));
+    for path in cached.into_iter().chain(cache_path.as_deref()) {
+        if let Ok(index) = read_seed_index(path, &panel.checksum) {
+            // Marks the file as recently used
+            if let Ok(file) = std::fs::File::options().append(true).open(path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            panel.index = Some(index);
+            return (panel, Some(path.to_path_buf()));
+        }
+    }
+    if !seed_index_usable(params) {
+        return (panel, None);
+    }
+    let index = SeedIndex::build(&panel.distinct);
+    let written = cache_path.filter(|path| write_seed_index(&index, &panel.checksum, path).is_ok());
+    if let (Some(dir), Some(path)) = (cache_dir, &written) {
+        trim_panel_cache(dir, PANEL_CACHE_MAX_BYTES, path);
+    }
+    panel.index = Some(index);
+    (panel, written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::ReferenceData;
+    use crate::analysis::pairwise::{
+        collect_exclusivity_hits_among, collect_exclusivity_hits_with_aligner, create_aligner,
+    };
+    use crate::analysis::random_seq;
+
+    fn group(name: &str, entries: &[(&str, &str)]) -> ExclusivityGroup {
+        ExclusivityGroup {
+            name: name.to_string(),
+            data: ReferenceData {
+                names: entries.iter().map(|(n, _)| n.to_string()).collect(),
+                sequences: entries.iter().map(|(_, s)| s.to_string()).collect(),
+            },
+        }
+    }
+
+    fn groups() -> Vec<ExclusivityGroup> {
+        vec![
+            group("a.fasta", &[("Excl1", "ACGT"), ("Excl2", "ACGT")]),
+            group("b.fasta", &[("Excl3", "TTTT"), ("Excl4", "ACGT")]),
+        ]
+    }
+
+    /// Parameters a seed index is built for
+    fn seeded_params() -> AnalysisParams {
+        let mut params = AnalysisParams {
+            min_oligo_length: 36,
+            max_oligo_length: 36,
+            ..Default::default()
+        };
+        params.pairwise.max_mismatches = 2;
+        params
+    }
+
+    #[test]
+    fn test_prepared_panel() {
+        let panel = PreparedPanel::new(&groups(), &AnalysisParams::default());
+        assert_eq!(panel.len(), 4);
+        assert_eq!(panel.distinct, vec![b"ACGT".to_vec(), b"TTTT".to_vec()]);
+        assert_eq!(panel.distinct_of, vec![0, 0, 1, 0]);
+        assert_eq!(panel.expand(&['x', 'y']), vec!['x', 'x', 'y', 'x']);
+        assert_eq!(
+            panel.groups,
+            vec![("a.fasta".to_string(), 2), ("b.fasta".to_string(), 2)]
+        );
+        // Windows of the default lengths can't be split into seeds under the
+        // default mismatch cap
+        assert!(panel.index.is_none());
+        assert!(
+            PreparedPanel::new(&groups(), &seeded_params())
+                .index
+                .is_some()
+        );
+
+        // Any change to a name or sequence changes the checksum
+        let mut renamed = groups();
+        renamed[1].data.names[0] = "Excl5".to_string();
+        assert_ne!(panel_checksum(&renamed), panel.checksum);
+    }
+
+    #[test]
+    fn test_seed_index_candidates() {
+        let mut distinct: Vec<Vec<u8>> = (0..20).map(|i| random_seq(300, i).into_bytes()).collect();
+        let mut ambiguous = random_seq(300, 100).into_bytes();
+        ambiguous[150] = b'N';
+        distinct.push(ambiguous);
+        let index = SeedIndex::build(&distinct);
+        assert_eq!(index.ambiguous, vec![20]);
+
+        let params = seeded_params().pairwise;
+        // A window of the first sequence with two mismatches
+        let mut oligo = distinct[0][100..136].to_vec();
+        oligo[5] = if oligo[5] == b'A' { b'C' } else { b'A' };
+        oligo[30] = if oligo[30] == b'G' { b'T' } else { b'G' };
+        let candidates = index.candidates(&oligo, &params).unwrap();
+        assert!(candidates.contains(&0));
+        assert!(candidates.contains(&20));
+        assert!(candidates.len() < distinct.len());
+
+        // Aligning only the candidates finds every accepted hit
+        let mut aligner = create_aligner(oligo.len(), 300, &params);
+        let all = collect_exclusivity_hits_with_aligner(&mut aligner, &oligo, &distinct, &params);
+        let among =
+            collect_exclusivity_hits_among(&mut aligner, &oligo, &distinct, &candidates, &params);
+        assert_eq!(all, among);
+        assert_eq!(all[0], Some((2, 0)));
+
+        // Codes that are mismatches need no alignment
+        let mismatch = PairwiseParams {
+            reference_ambiguity: ReferenceAmbiguityPolicy::Mismatch,
+            ..params
+        };
+        assert!(!index.candidates(&oligo, &mismatch).unwrap().contains(&20));
+
+        // Too many mismatches for the oligo to hold a seed per piece
+        let loose = PairwiseParams {
+            max_mismatches: 3,
+            ..params
+        };
+        assert_eq!(index.candidates(&oligo, &loose), None);
+        assert_eq!(index.candidates(&oligo[..29], &params), None);
+    }
+
+    #[test]
+    fn test_load_prepared_panel() {
+        let dir = std::env::temp_dir().join(format!("oligoscreen_panels_{}", std::process::id()));
+        let groups = groups();
+        let params = seeded_params();
+
+        let (panel, path) = load_prepared_panel(&groups, &params, None, Some(&dir));
+        let path = path.unwrap();
+        assert_eq!(path, panel_cache_path(&dir, &panel.checksum));
+        let read = read_seed_index(&path, &panel.checksum).unwrap();
+        assert_eq!(Some(&read), panel.index.as_ref());
+
+        // A recorded file is used only for the groups it was built from, even
+        // when another panel has the same names and sizes
+        let (reused, reused_path) = load_prepared_panel(&groups, &params, Some(&path), None);
+        assert_eq!(reused_path.as_ref(), Some(&path));
+        assert_eq!(reused.index, panel.index);
+        let mut edited = groups.clone();
+        edited[1].data.sequences[0] = "TTTA".to_string();
+        let (edited_panel, edited_path) =
+            load_prepared_panel(&edited, &params, Some(&path), Some(&dir));
+        assert_ne!(edited_path.as_ref(), Some(&path));
+        assert_eq!(edited_panel.distinct[1], b"TTTA".to_vec());
+
+        // Without a cache directory the index is built in memory, and not at
+        // all when the parameters can't use it
+        let (in_memory, no_path) = load_prepared_panel(&edited, &params, None, None);
+        assert!(in_memory.index.is_some() && no_path.is_none());
+        let defaults = AnalysisParams::default();
+        assert!(
+            load_prepared_panel(&edited, &defaults, None, None)
+                .0
+                .index
+                .is_none()
+        );
+
+        // Past the size limit the least recently used files go first
+        let old = edited_path.unwrap();
+        let stale = SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options()
+            .append(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+        trim_panel_cache(&dir, std::fs::metadata(&path).unwrap().len(), &path);
+        assert!(path.exists());
+        assert!(!old.exists());
+        trim_panel_cache(&dir, 0, &path);
+        assert!(path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
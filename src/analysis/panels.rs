@@ -0,0 +1,179 @@
+//! Inclusivity/exclusivity panel scoring matrix
+//!
+//! Re-aligns the template oligo of a small set of top candidate windows against
+//! each sequence panel separately, so specificity can be reported per panel
+//! (candidates × panels) rather than against the merged exclusivity set.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::filter::{effective_min_mismatches, ExclusivityFilter};
+use super::pairwise::{collect_mismatch_counts_with_aligner, create_aligner};
+use super::types::{PairwiseParams, ScreeningResults};
+
+/// A named set of sequences scored as one matrix column
+#[derive(Debug, Clone)]
+pub struct SequencePanel {
+    pub name: String,
+    /// True for the inclusivity (reference) panel, false for exclusivity panels
+    pub inclusivity: bool,
+    pub sequences: Vec<Vec<u8>>,
+}
+
+/// Summary of one oligo aligned against one panel
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PanelScore {
+    pub total: usize,
+    /// Sequences with an accepted alignment (within max mismatches)
+    pub hits: usize,
+    /// Minimum mismatches among hits (None = no sequence in the panel matched)
+    pub min_mismatches: Option<u32>,
+}
+
+impl PanelScore {
+    /// Percentage of panel sequences with an accepted alignment
+    pub fn coverage(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+/// One candidate row of the panel matrix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelMatrixRow {
+    pub position: usize,
+    pub length: u32,
+    pub oligo: String,
+    pub variants_needed: usize,
+    pub coverage_at_threshold: f64,
+    /// One score per panel, in panel order
+    pub scores: Vec<PanelScore>,
+}
+
+/// Score a single oligo against every sequence of a panel.
+pub fn score_panel(oligo: &[u8], sequences: &[Vec<u8>], params: &PairwiseParams) -> PanelScore {
+    if sequences.is_empty() {
+        return PanelScore::default();
+    }
+    let max_len = sequences.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut aligner = create_aligner(oligo.len(), max_len, params);
+    let counts = collect_mismatch_counts_with_aligner(&mut aligner, oligo, sequences, params);
+
+    PanelScore {
+        total: sequences.len(),
+        hits: counts.iter().filter(|c| c.is_some()).count(),
+        min_mismatches: counts.iter().flatten().copied().min(),
+    }
+}
+
+/// Pick the top `n` windows: fewest variants needed, then fewest no-matches,
+/// then highest effective exclusivity mismatches. Returns (length, position).
+pub fn select_top_candidates(
+    results: &ScreeningResults,
+    n: usize,
+    ignore_count: usize,
+    filter: Option<&ExclusivityFilter>,
+) -> Vec<(u32, usize)> {
+    let mut windows: Vec<(u32, usize, usize, usize, i64)> = Vec::new();
+    for (&length, length_result) in &results.results_by_length {
+        for pr in &length_result.positions {
+            if pr.analysis.skipped || filter.is_some_and(|f| !f.passes(pr)) {
+                continue;
+            }
+            // All-no-match exclusivity is the best possible outcome
+            let excl_mm = pr
+                .exclusivity
+                .as_ref()
+                .map(|e| effective_min_mismatches(e, ignore_count).map_or(i64::MAX, i64::from))
+                .unwrap_or(0);
+            windows.push((
+                length,
+                pr.position,
+                pr.variants_needed,
+                pr.analysis.no_match_count,
+                excl_mm,
+            ));
+        }
+    }
+
+    windows.sort_by(|a, b| {
+        a.2.cmp(&b.2)
+            .then(a.3.cmp(&b.3))
+            .then(b.4.cmp(&a.4))
+            .then(a.1.cmp(&b.1))
+            .then(a.0.cmp(&b.0))
+    });
+    windows
+        .into_iter()
+        .take(n)
+        .map(|(length, position, ..)| (length, position))
+        .collect()
+}
+
+/// Build the candidates × panels matrix. Candidates are scored in parallel.
+pub fn build_panel_matrix(
+    results: &ScreeningResults,
+    candidates: &[(u32, usize)],
+    panels: &[SequencePanel],
+    params: &PairwiseParams,
+) -> Vec<PanelMatrixRow> {
+    let template = results.template_sequence.as_bytes();
+
+    candidates
+        .par_iter()
+        .filter_map(|&(length, position)| {
+            let pr = results
+                .results_by_length
+                .get(&length)?
+                .positions
+                .iter()
+                .find(|p| p.position == position)?;
+            let oligo = template.get(position..position + length as usize)?;
+
+            let scores = panels
+                .iter()
+                .map(|panel| score_panel(oligo, &panel.sequences, params))
+                .collect();
+
+            Some(PanelMatrixRow {
+                position,
+                length,
+                oligo: String::from_utf8_lossy(oligo).to_string(),
+                variants_needed: pr.variants_needed,
+                coverage_at_threshold: pr.analysis.coverage_at_threshold,
+                scores,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_panel() {
+        let oligo = b"TATGGTACGT";
+        let sequences = vec![
+            b"AATATGGTACGTCATG".to_vec(),
+            b"TATGGTTCGTCATGTT".to_vec(), // 1 mismatch
+            b"GGGGGGGGGGGGGGGG".to_vec(),
+        ];
+        let score = score_panel(oligo, &sequences, &PairwiseParams::default());
+        assert_eq!(score.total, 3);
+        assert_eq!(score.hits, 2);
+        assert_eq!(score.min_mismatches, Some(0));
+        assert!((score.coverage() - 66.666).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_empty_panel() {
+        let score = score_panel(b"ACGT", &[], &PairwiseParams::default());
+        assert_eq!(score.total, 0);
+        assert_eq!(score.min_mismatches, None);
+        assert_eq!(score.coverage(), 0.0);
+    }
+}
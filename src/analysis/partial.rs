@@ -0,0 +1,169 @@
+//! Per-length partial results files
+//!
+//! A multi-length job can run for many hours. Each oligo length is written
+//! next to the job's results file as soon as it finishes, so a crash loses
+//! at most the length in progress: when the job is started again, the
+//! lengths found there are taken over and only the rest are screened. The
+//! files are ordinary results files holding one length, and are removed
+//! once the job's full results are saved.
+
+use std::path::{Path, PathBuf};
+
+use super::audit::param_changes;
+use super::compact::{results_from_json, write_results_json};
+use super::types::{AnalysisParams, LengthResult, ScreeningResults};
+
+/// Path of the partial file for one oligo length of the results saved at
+/// `results_path`
+pub fn partial_results_path(results_path: &Path, oligo_length: u32) -> PathBuf {
+    results_path.with_extension(format!("len{}.partial.json", oligo_length))
+}
+
+/// Write a finished length next to `results_path`, with the job's
+/// parameters and template so a restarted job can tell whether it belongs
+/// to the same screen. Written to a temporary file first, so a crash
+/// mid-write never leaves a truncated partial file.
+pub fn write_partial_results(
+    results_path: &Path,
+    params: &AnalysisParams,
+    template_sequence: &str,
+    length_result: LengthResult,
+) -> Result<(), String> {
+    let oligo_length = length_result.oligo_length;
+    let mut partial = ScreeningResults::new(
+        params.clone(),
+        template_sequence.len(),
+        0,
+        template_sequence.to_string(),
+        false,
+        None,
+    );
+    partial.cancelled = true;
+    partial
+        .results_by_length
+        .insert(oligo_length, length_result);
+
+    let path = partial_results_path(results_path, oligo_length);
+    let tmp = path.with_extension("tmp");
+    let file = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    write_results_json(&partial, file, |_| {})?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+/// The lengths of a job saved by `write_partial_results`, gathered into one
+/// set of results (flagged `cancelled`) to resume from. None when there are
+/// none; files of a screen with other parameters or another template are
+/// ignored.
+pub fn load_partial_results(
+    results_path: &Path,
+    params: &AnalysisParams,
+    template_sequence: &str,
+) -> Option<ScreeningResults> {
+    let mut loaded: Option<ScreeningResults> = None;
+    for oligo_length in params.min_oligo_length..=params.max_oligo_length {
+        let path = partial_results_path(results_path, oligo_length);
+        let Ok(json) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let Ok(mut partial) = results_from_json(&json) else {
+            continue;
+        };
+        if partial.template_sequence != template_sequence || !same_screen(&partial.params, params) {
+            continue;
+        }
+        if let Some(length_result) = partial.results_by_length.remove(&oligo_length) {
+            loaded
+                .get_or_insert(partial)
+                .results_by_length
+                .insert(oligo_length, length_result);
+        }
+    }
+    loaded
+}
+
+/// Delete the partial files of a job, once its full results are saved.
+pub fn remove_partial_results(results_path: &Path, params: &AnalysisParams) {
+    for oligo_length in params.min_oligo_length..=params.max_oligo_length {
+        let _ = std::fs::remove_file(partial_results_path(results_path, oligo_length));
+    }
+}
+
+/// Whether two parameter sets screen the same windows the same way. The
+/// thread count and progress reporting only change how a run is carried out.
+fn same_screen(a: &AnalysisParams, b: &AnalysisParams) -> bool {
+    param_changes(a, b)
+        .iter()
+        .all(|change| change.starts_with("thread_count") || change.starts_with("progress_interval"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::ThreadCount;
+
+    #[test]
+    fn test_partial_results_round_trip() {
+        let template = "TATGGTACGTCATGTTCTAG";
+        let params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 12,
+            resolution: 5,
+            ..Default::default()
+        };
+        let mut results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string()],
+                sequences: vec![template.to_string()],
+            },
+            &params,
+            &[],
+            None,
+        );
+
+        let results_path =
+            std::env::temp_dir().join(format!("oligoscreen_partial_{}.json", std::process::id()));
+        assert_eq!(
+            partial_results_path(&results_path, 11).file_name().unwrap(),
+            format!(
+                "oligoscreen_partial_{}.len11.partial.json",
+                std::process::id()
+            )
+            .as_str()
+        );
+        assert!(load_partial_results(&results_path, &params, template).is_none());
+
+        // Lengths 10 and 12 finished; the thread count does not matter
+        for length in [10, 12] {
+            let length_result = results.results_by_length.remove(&length).unwrap();
+            write_partial_results(&results_path, &params, template, length_result).unwrap();
+        }
+        let resumed = AnalysisParams {
+            thread_count: ThreadCount::Fixed(3),
+            ..params.clone()
+        };
+        let loaded = load_partial_results(&results_path, &resumed, template).unwrap();
+        assert!(loaded.cancelled);
+        let mut lengths: Vec<u32> = loaded.results_by_length.keys().copied().collect();
+        lengths.sort_unstable();
+        assert_eq!(lengths, vec![10, 12]);
+        assert_eq!(loaded.results_by_length[&10].positions.len(), 3);
+
+        // Another screen or template does not pick them up
+        let coarser = AnalysisParams {
+            resolution: 2,
+            ..params.clone()
+        };
+        assert!(load_partial_results(&results_path, &coarser, template).is_none());
+        assert!(load_partial_results(&results_path, &params, "ACGTACGTACGTACGT").is_none());
+
+        remove_partial_results(&results_path, &params);
+        assert!(load_partial_results(&results_path, &params, template).is_none());
+    }
+}
@@ -0,0 +1,574 @@
+//! Cross-job candidate queries
+//!
+//! Runs one set of window criteria over the results of several completed jobs
+//! and returns a single ranked list, so candidates for a multi-target panel
+//! can be compared regardless of which job produced them.
+
+use serde::{Deserialize, Serialize};
+
+use super::export::no_match_percent;
+use super::filter::effective_min_mismatches;
+use super::iupac::{base_to_bit, reverse_complement, sequence_matches_consensus_bytes};
+use super::thermo::oligo_tm;
+use super::types::{NominationStatus, ScreeningResults};
+
+/// Window criteria applied to every job
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CandidateQuery {
+    /// Keep windows needing at most this many variants
+    pub max_variants_needed: usize,
+    /// Keep windows whose effective exclusivity min mismatches are at least
+    /// this (windows of jobs without exclusivity data are dropped when set)
+    pub min_exclusivity_mismatches: Option<u32>,
+    /// Closest exclusivity sequences ignored before taking the minimum
+    pub ignore_count: usize,
+    /// Keep windows with at most this percentage of no-match references
+    pub max_no_match_percent: Option<f64>,
+    pub max_results: usize,
+}
+
+impl Default for CandidateQuery {
+    fn default() -> Self {
+        Self {
+            max_variants_needed: 2,
+            min_exclusivity_mismatches: Some(4),
+            ignore_count: 0,
+            max_no_match_percent: None,
+            max_results: 500,
+        }
+    }
+}
+
+/// A window matching the query, tagged with the index of its job in the input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryHit {
+    pub job_index: usize,
+    pub oligo_length: u32,
+    pub position: usize,
+    pub oligo: String,
+    pub variants_needed: usize,
+    pub coverage_at_threshold: f64,
+    pub no_match_percent: f64,
+    /// Effective exclusivity min mismatches: None without exclusivity data,
+    /// Some(None) when every exclusivity sequence is a no-match
+    pub exclusivity_min_mismatches: Option<Option<u32>>,
+}
+
+/// Windows of all jobs that satisfy the query, ranked by fewest variants
+/// needed, then highest exclusivity mismatches, then fewest no-matches, then
+/// highest coverage.
+pub fn query_candidates(jobs: &[&ScreeningResults], query: &CandidateQuery) -> Vec<QueryHit> {
+    let mut hits = Vec::new();
+    for (job_index, results) in jobs.iter().enumerate() {
+        for (&length, length_result) in &results.results_by_length {
+            for pr in &length_result.positions {
+                if pr.analysis.skipped || pr.variants_needed > query.max_variants_needed {
+                    continue;
+                }
+                let no_match_percent = no_match_percent(pr);
+                if query
+                    .max_no_match_percent
+                    .is_some_and(|max| no_match_percent > max)
+                {
+                    continue;
+                }
+                let exclusivity_min_mismatches = pr
+                    .exclusivity
+                    .as_ref()
+                    .map(|e| effective_min_mismatches(e, query.ignore_count));
+                if let Some(min) = query.min_exclusivity_mismatches {
+                    match exclusivity_min_mismatches {
+                        None => continue,
+                        Some(Some(mm)) if mm < min => continue,
+                        _ => {}
+                    }
+                }
+                let end = pr.position + length as usize;
+                hits.push(QueryHit {
+                    job_index,
+                    oligo_length: length,
+                    position: pr.position,
+                    oligo: results
+                        .template_sequence
+                        .get(pr.position..end)
+                        .unwrap_or("")
+                        .to_string(),
+                    variants_needed: pr.variants_needed,
+                    coverage_at_threshold: pr.analysis.coverage_at_threshold,
+                    no_match_percent,
+                    exclusivity_min_mismatches,
+                });
+            }
+        }
+    }
+
+    // All-no-match exclusivity ranks as the best possible specificity
+    let excl_rank = |h: &QueryHit| match h.exclusivity_min_mismatches {
+        None => -1,
+        Some(None) => i64::MAX,
+        Some(Some(mm)) => i64::from(mm),
+    };
+    hits.sort_by(|a, b| {
+        a.variants_needed
+            .cmp(&b.variants_needed)
+            .then_with(|| excl_rank(b).cmp(&excl_rank(a)))
+            .then_with(|| a.no_match_percent.total_cmp(&b.no_match_percent))
+            .then_with(|| b.coverage_at_threshold.total_cmp(&a.coverage_at_threshold))
+            .then_with(|| a.job_index.cmp(&b.job_index))
+            .then_with(|| a.oligo_length.cmp(&b.oligo_length))
+            .then_with(|| a.position.cmp(&b.position))
+    });
+    hits.truncate(query.max_results);
+    hits
+}
+
+/// How two nominated oligos relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlapKind {
+    Identical,
+    /// One oligo is the reverse complement of the other
+    ReverseComplement,
+    /// The shorter oligo lies within the longer one
+    Contained,
+    /// The end of one oligo overlaps the start of the other by this many bases
+    Overlapping(usize),
+}
+
+impl OverlapKind {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Identical => "identical".to_string(),
+            Self::ReverseComplement => "reverse complement".to_string(),
+            Self::Contained => "contained".to_string(),
+            Self::Overlapping(n) => format!("{} bp overlap", n),
+        }
+    }
+}
+
+/// A nominated oligo identified by job index and index within that job's nominations
+pub type NominationRef = (usize, usize);
+
+/// Two nominations whose oligos are duplicates or overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NominationOverlap {
+    pub a: NominationRef,
+    pub b: NominationRef,
+    pub kind: OverlapKind,
+}
+
+/// Compare every pair of nominated oligos across (and within) jobs and report
+/// identical, reverse-complement, contained, or end-overlapping sequences.
+/// Overlaps shorter than `min_overlap` bases are ignored.
+pub fn find_nomination_overlaps(
+    jobs: &[&ScreeningResults],
+    min_overlap: usize,
+) -> Vec<NominationOverlap> {
+    let nominated: Vec<(NominationRef, &str)> = jobs
+        .iter()
+        .enumerate()
+        .flat_map(|(job, results)| {
+            results
+                .nominations
+                .iter()
+                .enumerate()
+                .map(move |(i, n)| ((job, i), n.sequence.as_str()))
+        })
+        .collect();
+
+    let mut overlaps = Vec::new();
+    for (i, &(a, seq_a)) in nominated.iter().enumerate() {
+        for &(b, seq_b) in &nominated[i + 1..] {
+            if let Some(kind) = oligo_overlap(seq_a, seq_b, min_overlap) {
+                overlaps.push(NominationOverlap { a, b, kind });
+            }
+        }
+    }
+    overlaps
+}
+
+/// Nominations whose melting temperatures all lie within a spread, as
+/// needed for oligos run together in one multiplex reaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TmMatchedSet {
+    /// Members with their Tm (°C), coolest first
+    pub members: Vec<(NominationRef, f64)>,
+}
+
+impl TmMatchedSet {
+    /// Tm difference between the warmest and coolest member
+    pub fn spread(&self) -> f64 {
+        match (self.members.first(), self.members.last()) {
+            (Some((_, low)), Some((_, high))) => high - low,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Largest sets of nominated oligos (across and within jobs, rejected ones
+/// excluded) whose Tm, as shown under each job's Tm conditions, spans at most
+/// `max_spread` °C, with at least `min_size` members. Every subset of a set is
+/// also Tm-matched, so only maximal sets are returned: largest first, then
+/// tightest spread.
+pub fn tm_matched_sets(
+    jobs: &[&ScreeningResults],
+    max_spread: f64,
+    min_size: usize,
+) -> Vec<TmMatchedSet> {
+    let mut nominated: Vec<(NominationRef, f64)> = jobs
+        .iter()
+        .enumerate()
+        .flat_map(|(job, results)| {
+            let conditions = &results.params.tm_conditions;
+            results
+                .nominations
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.status != NominationStatus::Rejected)
+                .map(move |(i, n)| ((job, i), oligo_tm(&n.sequence, conditions)))
+        })
+        .collect();
+    nominated.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+    // A matched set is a run of the Tm-sorted list; keep each start's longest
+    // run unless the previous start's run already reached as far
+    let mut sets = Vec::new();
+    let mut end = 0;
+    let mut previous_end = 0;
+    for start in 0..nominated.len() {
+        end = end.max(start);
+        while end + 1 < nominated.len() && nominated[end + 1].1 - nominated[start].1 <= max_spread {
+            end += 1;
+        }
+        if end + 1 > previous_end && end + 1 - start >= min_size.max(2) {
+            sets.push(TmMatchedSet {
+                members: nominated[start..=end].to_vec(),
+            });
+        }
+        previous_end = previous_end.max(end + 1);
+    }
+    sets.sort_by(|a, b| {
+        b.members
+            .len()
+            .cmp(&a.members.len())
+            .then_with(|| a.spread().total_cmp(&b.spread()))
+    });
+    sets
+}
+
+/// Relationship between two oligos, strongest first.
+pub fn oligo_overlap(a: &str, b: &str, min_overlap: usize) -> Option<OverlapKind> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    if a == b {
+        return Some(OverlapKind::Identical);
+    }
+    if reverse_complement(a) == b {
+        return Some(OverlapKind::ReverseComplement);
+    }
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if longer.contains(shorter) {
+        return Some(OverlapKind::Contained);
+    }
+    let overlap = suffix_prefix_overlap(a, b).max(suffix_prefix_overlap(b, a));
+    (overlap >= min_overlap.max(1)).then_some(OverlapKind::Overlapping(overlap))
+}
+
+/// Length of the longest suffix of `a` that is a prefix of `b`.
+fn suffix_prefix_overlap(a: &str, b: &str) -> usize {
+    (1..a.len().min(b.len()))
+        .rev()
+        .find(|&n| a.ends_with(&b[..n]))
+        .unwrap_or(0)
+}
+
+/// A window whose top variants match a searched oligo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OligoHit {
+    pub job_index: usize,
+    pub oligo_length: u32,
+    pub position: usize,
+    /// 1-based ranks of the matching variants
+    pub variant_ranks: Vec<usize>,
+    /// Combined percentage of the references carrying a matching variant
+    pub coverage: f64,
+    /// The oligo matches as its reverse complement
+    pub antisense: bool,
+}
+
+/// Whether `oligo` (IUPAC codes allowed) covers a variant: the variant
+/// contains the oligo, or the oligo contains the variant when the window is
+/// shorter than the oligo.
+fn oligo_covers(variant: &[u8], oligo: &[u8]) -> bool {
+    if variant.len() >= oligo.len() {
+        variant
+            .windows(oligo.len())
+            .any(|site| sequence_matches_consensus_bytes(site, oligo))
+    } else {
+        oligo
+            .windows(variant.len())
+            .any(|site| sequence_matches_consensus_bytes(variant, site))
+    }
+}
+
+/// Windows of all jobs among whose `top_variants` most common variants the
+/// oligo (or its reverse complement) matches, ranked by highest coverage.
+/// The pattern may use IUPAC codes; U is read as T.
+pub fn search_oligo(
+    jobs: &[&ScreeningResults],
+    pattern: &str,
+    top_variants: usize,
+    max_results: usize,
+) -> Result<Vec<OligoHit>, String> {
+    let sense: Vec<u8> = pattern
+        .trim()
+        .bytes()
+        .map(|b| match b.to_ascii_uppercase() {
+            b'U' => b'T',
+            b => b,
+        })
+        .collect();
+    if sense.is_empty() {
+        return Err("Enter an oligo sequence to search for".to_string());
+    }
+    if let Some(&bad) = sense.iter().find(|&&b| base_to_bit(b) == 0) {
+        return Err(format!("'{}' is not an IUPAC base", bad as char));
+    }
+    let antisense = reverse_complement(&String::from_utf8_lossy(&sense)).into_bytes();
+
+    let mut hits = Vec::new();
+    for (job_index, results) in jobs.iter().enumerate() {
+        for (&length, length_result) in &results.results_by_length {
+            for pr in &length_result.positions {
+                if pr.analysis.skipped {
+                    continue;
+                }
+                // Gaps are not template bases; inserted bases are
+                let top: Vec<(usize, Vec<u8>, f64)> = pr
+                    .analysis
+                    .variants
+                    .iter()
+                    .take(top_variants)
+                    .enumerate()
+                    .map(|(i, v)| {
+                        let bases = v
+                            .sequence
+                            .bytes()
+                            .filter(|&b| b != b'-')
+                            .map(|b| b.to_ascii_uppercase())
+                            .collect();
+                        (i + 1, bases, v.percentage)
+                    })
+                    .collect();
+                let mut best: Option<OligoHit> = None;
+                for (oligo, is_antisense) in [(&sense, false), (&antisense, true)] {
+                    let matching: Vec<&(usize, Vec<u8>, f64)> = top
+                        .iter()
+                        .filter(|(_, bases, _)| !bases.is_empty() && oligo_covers(bases, oligo))
+                        .collect();
+                    if matching.is_empty() {
+                        continue;
+                    }
+                    let coverage = matching.iter().map(|(_, _, pct)| pct).sum();
+                    if best.as_ref().is_none_or(|b| coverage > b.coverage) {
+                        best = Some(OligoHit {
+                            job_index,
+                            oligo_length: length,
+                            position: pr.position,
+                            variant_ranks: matching.iter().map(|(rank, _, _)| *rank).collect(),
+                            coverage,
+                            antisense: is_antisense,
+                        });
+                    }
+                }
+                hits.extend(best);
+            }
+        }
+    }
+
+    hits.sort_by(|a, b| {
+        b.coverage
+            .total_cmp(&a.coverage)
+            .then(a.job_index.cmp(&b.job_index))
+            .then(a.oligo_length.cmp(&b.oligo_length))
+            .then(a.position.cmp(&b.position))
+    });
+    hits.truncate(max_results);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ExclusivityGroup, ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    fn screen(references: &[&str], exclusivity: Option<&[&str]>) -> ScreeningResults {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let data = |seqs: &[&str]| ReferenceData {
+            names: (0..seqs.len()).map(|i| format!("S{}", i)).collect(),
+            sequences: seqs.iter().map(|s| s.to_string()).collect(),
+        };
+        run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &data(references),
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 10,
+                resolution: 10,
+                ..Default::default()
+            },
+            exclusivity
+                .map(|seqs| ExclusivityGroup {
+                    name: "Off-targets".to_string(),
+                    data: data(seqs),
+                })
+                .as_slice(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_query_candidates_across_jobs() {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let conserved = screen(&[template, template], Some(&["A".repeat(31).as_str()]));
+        let cross_reactive = screen(&[template], Some(&[template]));
+
+        let query = CandidateQuery {
+            max_variants_needed: 1,
+            min_exclusivity_mismatches: Some(1),
+            ..Default::default()
+        };
+        let hits = query_candidates(&[&cross_reactive, &conserved], &query);
+        // Only the job whose off-target doesn't match contributes windows
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|h| h.job_index == 1));
+        assert_eq!(hits[0].oligo.len(), 10);
+
+        let no_excl = CandidateQuery {
+            min_exclusivity_mismatches: None,
+            max_results: 2,
+            ..query
+        };
+        let hits = query_candidates(&[&cross_reactive, &conserved], &no_excl);
+        assert_eq!(hits.len(), 2);
+        // Specific windows rank ahead of cross-reactive ones with equal variant counts
+        assert_eq!(hits[0].job_index, 1);
+    }
+
+    #[test]
+    fn test_oligo_overlap() {
+        assert_eq!(
+            oligo_overlap("ACGTAC", "ACGTAC", 3),
+            Some(OverlapKind::Identical)
+        );
+        assert_eq!(
+            oligo_overlap("AACCG", "CGGTT", 3),
+            Some(OverlapKind::ReverseComplement)
+        );
+        assert_eq!(
+            oligo_overlap("CGTA", "ACGTAC", 3),
+            Some(OverlapKind::Contained)
+        );
+        assert_eq!(
+            oligo_overlap("TTTACGT", "ACGTGGG", 3),
+            Some(OverlapKind::Overlapping(4))
+        );
+        assert_eq!(
+            oligo_overlap("ACGTGGG", "TTTACGT", 3),
+            Some(OverlapKind::Overlapping(4))
+        );
+        assert_eq!(oligo_overlap("TTTACGT", "ACGTGGG", 5), None);
+    }
+
+    #[test]
+    fn test_find_nomination_overlaps() {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let mut job_a = screen(&[template], None);
+        let mut job_b = screen(&[template], None);
+        job_a.nominate(10, 0);
+        job_b.nominate(10, 0);
+        job_b.nominate(10, 20);
+
+        let overlaps = find_nomination_overlaps(&[&job_a, &job_b], 5);
+        assert_eq!(
+            overlaps,
+            vec![NominationOverlap {
+                a: (0, 0),
+                b: (1, 0),
+                kind: OverlapKind::Identical,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tm_matched_sets() {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let mut job_a = screen(&[template], None);
+        let mut job_b = screen(&[template], None);
+        job_a.nominate(10, 0);
+        job_a.nominate(10, 20);
+        job_b.nominate(10, 10);
+        job_b.nominate(10, 0);
+        job_b.nominations[1].status = NominationStatus::Rejected;
+        // Nearest-neighbor Tm under the jobs' conditions: position 10 is about
+        // 24.1 °C, 0 about 28.0 °C, 20 about 33.2 °C (the Wallace rule would
+        // put 0 and 10 both at 28 °C)
+        let tm = |pos: usize| oligo_tm(&template[pos..pos + 10], &job_a.params.tm_conditions);
+
+        assert!(tm_matched_sets(&[&job_a, &job_b], 1.0, 2).is_empty());
+        let sets = tm_matched_sets(&[&job_a, &job_b], 4.0, 2);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].members, vec![((1, 0), tm(10)), ((0, 0), tm(0))]);
+        assert_eq!(sets[0].spread(), tm(0) - tm(10));
+
+        let sets = tm_matched_sets(&[&job_a, &job_b], 10.0, 2);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].members.len(), 3);
+        assert!(tm_matched_sets(&[&job_a, &job_b], 10.0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_search_oligo() {
+        let template = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+        let mut variant = template.to_string();
+        variant.replace_range(12..13, "A");
+        let job_a = screen(&[template, template, &variant], None);
+        let job_b = screen(&[template], None);
+
+        // TACG lies in the conserved window at 0 (TATGGTACGT) of both jobs
+        let hits = search_oligo(&[&job_a, &job_b], "tacg", 5, 100).unwrap();
+        let found: Vec<(usize, usize)> = hits.iter().map(|h| (h.job_index, h.position)).collect();
+        assert_eq!(found, vec![(0, 0), (1, 0)]);
+        assert!(hits.iter().all(|h| h.coverage == 100.0 && !h.antisense));
+
+        // CATGTTC is only carried by the major variant of the window at 10
+        let hits = search_oligo(&[&job_a], "CATGTTC", 5, 100).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            (hits[0].position, hits[0].variant_ranks.clone()),
+            (10, vec![1])
+        );
+        assert!(hits[0].coverage > 60.0 && hits[0].coverage < 70.0);
+        // The minor variant only counts when it is among the top variants
+        assert_eq!(search_oligo(&[&job_a], "CAAGTTC", 2, 100).unwrap().len(), 1);
+        assert!(
+            search_oligo(&[&job_a], "CAAGTTC", 1, 100)
+                .unwrap()
+                .is_empty()
+        );
+
+        // The reverse complement of TTCTAG, written with an IUPAC code
+        let hits = search_oligo(&[&job_b], "CTAGRA", 5, 100).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].position, hits[0].antisense), (10, true));
+
+        assert!(search_oligo(&[&job_a], "  ", 5, 100).is_err());
+        assert!(search_oligo(&[&job_a], "ACGX", 5, 100).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Candidate oligo ranking
+//!
+//! Scores every window of a screen on one 0-100 scale combining the criteria
+//! a designer weighs when picking oligos (few variants needed, few
+//! non-matching references, distance from the exclusivity panel, GC and Tm
+//! in range), so the best candidates can be listed and exported directly
+//! instead of read off the heatmap.
+
+use serde::{Deserialize, Serialize};
+
+use super::export::{no_match_percent, MetricRange, TargetBand};
+use super::filter::{effective_min_mismatches, ExclusivityFilter};
+use super::thermo::{gc_percent, oligo_tm};
+use super::types::ScreeningResults;
+
+/// Relative weight of each criterion in the ranking score; 0 leaves a
+/// criterion out
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RankingWeights {
+    pub variants_needed: f64,
+    pub no_match: f64,
+    /// Only counted for windows with exclusivity data
+    pub exclusivity: f64,
+    pub gc: f64,
+    pub tm: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            variants_needed: 3.0,
+            no_match: 2.0,
+            exclusivity: 2.0,
+            gc: 1.0,
+            tm: 1.0,
+        }
+    }
+}
+
+/// Scales, weights, and cut-offs of a ranking
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankingParams {
+    pub weights: RankingWeights,
+    pub variants_needed: MetricRange,
+    /// No-match percentage of the references
+    pub no_match: MetricRange,
+    /// Effective exclusivity min mismatches
+    pub exclusivity: MetricRange,
+    pub ignore_count: usize,
+    pub gc: TargetBand,
+    pub tm: TargetBand,
+    /// Windows scoring below this are left out
+    pub min_score: f64,
+    pub max_variants_needed: Option<usize>,
+    pub max_no_match_percent: Option<f64>,
+}
+
+/// A window with its ranking score and the values behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedCandidate {
+    /// 1-based place in the ranking by score
+    pub rank: usize,
+    pub length: u32,
+    pub position: usize,
+    /// Template oligo of the window
+    pub oligo: String,
+    pub score: f64,
+    pub variants_needed: usize,
+    pub coverage_at_threshold: f64,
+    pub no_match_percent: f64,
+    /// Effective exclusivity min mismatches: None without exclusivity data,
+    /// Some(None) when every exclusivity sequence is a no-match
+    pub exclusivity_min_mismatches: Option<Option<u32>>,
+    pub gc_percent: f64,
+    pub tm: f64,
+}
+
+/// Column a ranked candidate list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankingColumn {
+    Score,
+    Length,
+    Position,
+    VariantsNeeded,
+    Coverage,
+    NoMatch,
+    Exclusivity,
+    GcPercent,
+    Tm,
+}
+
+impl RankingColumn {
+    /// Whether the column's best values are its highest, so it sorts
+    /// descending by default
+    pub fn higher_is_better(&self) -> bool {
+        matches!(self, Self::Score | Self::Coverage | Self::Exclusivity)
+    }
+}
+
+/// Score every analyzed window that passes `filter` and the cut-offs of
+/// `params`, best first (ties by position, then length).
+pub fn rank_candidates(
+    results: &ScreeningResults,
+    params: &RankingParams,
+    filter: Option<&ExclusivityFilter>,
+) -> Vec<RankedCandidate> {
+    let w = &params.weights;
+    let mut candidates = Vec::new();
+    for (&length, length_result) in &results.results_by_length {
+        for pr in &length_result.positions {
+            if pr.analysis.skipped || filter.is_some_and(|f| !f.passes(pr)) {
+                continue;
+            }
+            let no_match = no_match_percent(pr);
+            if params
+                .max_variants_needed
+                .is_some_and(|max| pr.variants_needed > max)
+                || params
+                    .max_no_match_percent
+                    .is_some_and(|max| no_match > max)
+            {
+                continue;
+            }
+            let oligo = results
+                .template_sequence
+                .get(pr.position..pr.position + length as usize)
+                .unwrap_or("")
+                .to_string();
+            let gc = pr.gc_percent.unwrap_or_else(|| gc_percent(&oligo));
+            let tm = pr
+                .tm
+                .unwrap_or_else(|| oligo_tm(&oligo, &results.params.tm_conditions));
+            let exclusivity = pr
+                .exclusivity
+                .as_ref()
+                .map(|e| effective_min_mismatches(e, params.ignore_count));
+
+            // Weighted mean badness over the criteria that apply
+            let mut terms = vec![
+                (
+                    w.variants_needed,
+                    params.variants_needed.badness(pr.variants_needed as f64),
+                ),
+                (w.no_match, params.no_match.badness(no_match)),
+                (w.gc, params.gc.badness(gc)),
+                (w.tm, params.tm.badness(tm)),
+            ];
+            if let Some(mm) = exclusivity {
+                let mm = mm.map_or(f64::INFINITY, f64::from);
+                terms.push((w.exclusivity, params.exclusivity.badness(mm)));
+            }
+            let total_weight: f64 = terms.iter().map(|(weight, _)| weight.max(0.0)).sum();
+            let badness = if total_weight > 0.0 {
+                terms
+                    .iter()
+                    .map(|(weight, badness)| weight.max(0.0) * badness)
+                    .sum::<f64>()
+                    / total_weight
+            } else {
+                0.0
+            };
+            let score = 100.0 * (1.0 - badness);
+            if score < params.min_score {
+                continue;
+            }
+
+            candidates.push(RankedCandidate {
+                rank: 0,
+                length,
+                position: pr.position,
+                oligo,
+                score,
+                variants_needed: pr.variants_needed,
+                coverage_at_threshold: pr.analysis.coverage_at_threshold,
+                no_match_percent: no_match,
+                exclusivity_min_mismatches: exclusivity,
+                gc_percent: gc,
+                tm,
+            });
+        }
+    }
+    sort_candidates(&mut candidates, RankingColumn::Score, true);
+    for (i, candidate) in candidates.iter_mut().enumerate() {
+        candidate.rank = i + 1;
+    }
+    candidates
+}
+
+/// Sort by one column, best first when `best_first` (see
+/// `RankingColumn::higher_is_better`), breaking ties by score, then
+/// position, then length. All-no-match exclusivity sorts above any
+/// mismatch count, and windows without exclusivity data below.
+pub fn sort_candidates(
+    candidates: &mut [RankedCandidate],
+    column: RankingColumn,
+    best_first: bool,
+) {
+    let descending = column.higher_is_better() == best_first;
+    let exclusivity_key = |c: &RankedCandidate| match c.exclusivity_min_mismatches {
+        None => -1,
+        Some(None) => i64::MAX,
+        Some(Some(mm)) => i64::from(mm),
+    };
+    candidates.sort_by(|a, b| {
+        let order = match column {
+            RankingColumn::Score => a.score.total_cmp(&b.score),
+            RankingColumn::Length => a.length.cmp(&b.length),
+            RankingColumn::Position => a.position.cmp(&b.position),
+            RankingColumn::VariantsNeeded => a.variants_needed.cmp(&b.variants_needed),
+            RankingColumn::Coverage => a.coverage_at_threshold.total_cmp(&b.coverage_at_threshold),
+            RankingColumn::NoMatch => a.no_match_percent.total_cmp(&b.no_match_percent),
+            RankingColumn::Exclusivity => exclusivity_key(a).cmp(&exclusivity_key(b)),
+            RankingColumn::GcPercent => a.gc_percent.total_cmp(&b.gc_percent),
+            RankingColumn::Tm => a.tm.total_cmp(&b.tm),
+        };
+        let order = if descending { order.reverse() } else { order };
+        order
+            .then(b.score.total_cmp(&a.score))
+            .then(a.position.cmp(&b.position))
+            .then(a.length.cmp(&b.length))
+    });
+}
+
+/// Candidates as FASTA, one record per oligo in list order, named
+/// `<prefix>_<rank>_pos<position>_<length>nt` (1-based position) with the
+/// score and variants needed in the description. RNA templates are written
+/// with U.
+pub fn candidates_fasta(candidates: &[RankedCandidate], prefix: &str, rna: bool) -> String {
+    let mut fasta = String::new();
+    for c in candidates {
+        let sequence = if rna {
+            c.oligo.replace('T', "U")
+        } else {
+            c.oligo.clone()
+        };
+        fasta.push_str(&format!(
+            ">{}_{}_pos{}_{}nt score={:.1} variants_needed={}\n{}\n",
+            prefix,
+            c.rank,
+            c.position + 1,
+            c.length,
+            c.score,
+            c.variants_needed,
+            sequence
+        ));
+    }
+    fasta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    const TEMPLATE: &str = "TATGGTACGTCATGTTCTAGAAATGGGCTGT";
+
+    fn params() -> RankingParams {
+        RankingParams {
+            weights: RankingWeights {
+                gc: 0.0,
+                tm: 0.0,
+                ..Default::default()
+            },
+            variants_needed: MetricRange {
+                green_at: 1.0,
+                red_at: 3.0,
+            },
+            no_match: MetricRange {
+                green_at: 0.0,
+                red_at: 50.0,
+            },
+            exclusivity: MetricRange {
+                green_at: 5.0,
+                red_at: 0.0,
+            },
+            ignore_count: 0,
+            gc: TargetBand {
+                min: 40.0,
+                max: 60.0,
+                tolerance: 10.0,
+            },
+            tm: TargetBand {
+                min: 55.0,
+                max: 65.0,
+                tolerance: 5.0,
+            },
+            min_score: 0.0,
+            max_variants_needed: None,
+            max_no_match_percent: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        // The second reference differs from the template at base 6 only, so
+        // the first window needs two variants and the others one
+        let mut mutated = TEMPLATE.as_bytes().to_vec();
+        mutated[5] = b'A';
+        let mutated = String::from_utf8(mutated).unwrap();
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: TEMPLATE.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string(), "R2".to_string()],
+                sequences: vec![TEMPLATE.to_string(), mutated],
+            },
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 10,
+                resolution: 10,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let ranked = rank_candidates(&results, &params(), None);
+        let positions: Vec<usize> = ranked.iter().map(|c| c.position).collect();
+        assert_eq!(positions, vec![10, 20, 0]);
+        assert_eq!(ranked[2].rank, 3);
+        assert_eq!(ranked[0].score, 100.0);
+        // Variants needed 2 is halfway along its scale, weighted 3 of 5
+        assert!((ranked[2].score - 70.0).abs() < 1e-9);
+        assert_eq!(ranked[0].oligo, &TEMPLATE[10..20]);
+
+        let mut cut = params();
+        cut.max_variants_needed = Some(1);
+        assert_eq!(rank_candidates(&results, &cut, None).len(), 2);
+        cut.min_score = 100.5;
+        assert!(rank_candidates(&results, &cut, None).is_empty());
+
+        let mut sorted = ranked.clone();
+        sort_candidates(&mut sorted, RankingColumn::VariantsNeeded, false);
+        assert_eq!(sorted[0].position, 0);
+        sort_candidates(&mut sorted, RankingColumn::Position, true);
+        assert_eq!(sorted[0].position, 0);
+        assert!(candidates_fasta(&sorted[..1], "T", false).starts_with(">T_3_pos1_10nt"));
+
+        let fasta = candidates_fasta(&ranked[..1], "T", true);
+        assert_eq!(
+            fasta,
+            format!(
+                ">T_1_pos11_10nt score=100.0 variants_needed=1\n{}\n",
+                TEMPLATE[10..20].replace('T', "U")
+            )
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Reproducibility records for completed jobs
+//!
+//! A small standalone JSON file, suitable for attaching to a manuscript,
+//! recording how a screen was run and on what: the exact analysis
+//! parameters plus the name, size, and checksum of each input file.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::types::AnalysisParams;
+
+/// One input FASTA file of a job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputFileRecord {
+    pub file_name: String,
+    pub sequence_count: usize,
+    pub total_bases: usize,
+    /// SHA-256 (hex) of the parsed records, not of the file's bytes: see
+    /// `sequence_sha256`. None when the sequences were not kept (results
+    /// loaded from a file)
+    pub records_sha256: Option<String>,
+}
+
+impl InputFileRecord {
+    pub fn new(file_name: &str, names: &[String], sequences: &[String]) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            sequence_count: sequences.len(),
+            total_bases: sequences.iter().map(|s| s.len()).sum(),
+            records_sha256: Some(sequence_sha256(names, sequences)),
+        }
+    }
+
+    /// Input whose sequences are no longer available, only its size.
+    pub fn without_sequences(file_name: &str, sequence_count: usize) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            sequence_count,
+            total_bases: 0,
+            records_sha256: None,
+        }
+    }
+}
+
+/// Everything needed to repeat a screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReproducibilityRecord {
+    pub tool: String,
+    pub tool_version: String,
+    /// Seconds since the Unix epoch, UTC
+    pub created: u64,
+    pub params: AnalysisParams,
+    pub template: InputFileRecord,
+    pub references: InputFileRecord,
+    /// One entry per exclusivity file, in panel order
+    pub exclusivity: Vec<InputFileRecord>,
+}
+
+impl ReproducibilityRecord {
+    pub fn new(
+        params: AnalysisParams,
+        template: InputFileRecord,
+        references: InputFileRecord,
+        exclusivity: Vec<InputFileRecord>,
+    ) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created,
+            params,
+            template,
+            references,
+            exclusivity,
+        }
+    }
+}
+
+/// SHA-256 (hex) of sequences written as single-line FASTA
+/// (`>name\nSEQUENCE\n` per record). Sequences are hashed as parsed (upper
+/// case, `.` gaps as `-`), so the value matches `sha256sum` of a file in
+/// that form regardless of the original line wrapping, but not of the
+/// original file.
+pub fn sequence_sha256(names: &[String], sequences: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for (name, seq) in names.iter().zip(sequences) {
+        hasher.update(b">");
+        hasher.update(name.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(seq.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex(&hasher.finalize())
+}
+
+pub(crate) fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_file_record() {
+        let names = vec!["a".to_string(), "b".to_string()];
+        let sequences = vec!["ACGT".to_string(), "GG".to_string()];
+        let record = InputFileRecord::new("refs.fasta", &names, &sequences);
+        assert_eq!(record.sequence_count, 2);
+        assert_eq!(record.total_bases, 6);
+        // `sha256sum` of the records as single-line FASTA
+        let expected = hex(&Sha256::digest(b">a\nACGT\n>b\nGG\n"));
+        assert_eq!(record.records_sha256, Some(expected));
+    }
+}
@@ -4,181 +4,602 @@
 //! using pairwise alignment to find best matches in each reference sequence.
 
 use super::analyzer::analyze_sequences;
-use super::fasta::{ReferenceData, TemplateData};
+use super::anchor::ReferenceAnchors;
+use super::consensus::template_conservation;
+use super::dedup::ReferenceGroups;
+use super::fasta::{ExclusivityGroup, ReferenceData, TemplateData};
+use super::filter::name_matches_pattern;
+use super::iupac::{expand_degenerate, is_ambiguous_base};
+use super::mask::MASKED_SKIP_REASON;
 use super::pairwise::{
-    collect_matches_with_aligner, collect_mismatch_counts_with_aligner, create_aligner, DnaAligner,
+    collect_exclusivity_hits_among, collect_exclusivity_hits_with_aligner,
+    collect_matches_with_aligner, collect_matches_with_anchors, exact_hits_are_best,
+    with_thread_aligner, DnaAligner, PairwiseMatch,
 };
+use super::panel_cache::PreparedPanel;
+use super::taxon::TaxonGroups;
+use super::thermo::{gc_percent, nearest_neighbor_tm};
+use super::trim::trim_references;
 use super::types::{
-    AnalysisParams, ExclusivityResult, LengthResult, MismatchBucket, PairwiseParams,
-    PositionResult, ProgressUpdate, ScreeningResults, WindowAnalysisResult,
+    AlignmentScoreStats, AnalysisParams, ExclusivityResult, LengthResult, MismatchBucket,
+    MismatchStats, PairwiseParams, PercentIdentityStats, PositionResult, ProgressInterval,
+    ProgressUpdate, ScreeningResults, TemplateAmbiguityPolicy, WindowAnalysisResult,
 };
 use rayon::prelude::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-/// Run the complete screening analysis using pairwise alignment.
+/// Rayon thread pool kept across screening jobs, so a worklist of many
+/// small jobs does not pay thread startup for each one. Pool workers also
+/// keep their aligner between jobs (see `with_thread_aligner`).
+pub struct ScreeningPool {
+    pool: rayon::ThreadPool,
+    threads: usize,
+}
+
+impl ScreeningPool {
+    pub fn new(threads: usize) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap_or_else(|_| rayon::ThreadPoolBuilder::new().build().unwrap());
+        Self { pool, threads }
+    }
+
+    /// Thread count the pool was requested with
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+/// Shared flag for stopping a running screen early. Windows already being
+/// aligned finish; no further window or oligo length is started once it is
+/// set, and the results are flagged `cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Run the complete screening analysis using pairwise alignment, on a
+/// pool built for this job only. Differential analysis runs when at least
+/// one exclusivity group is given.
 pub fn run_screening(
     template: &TemplateData,
     references: &ReferenceData,
     params: &AnalysisParams,
-    exclusivity: Option<&ReferenceData>,
+    exclusivity: &[ExclusivityGroup],
+    progress_tx: Option<Sender<ProgressUpdate>>,
+) -> ScreeningResults {
+    let pool = ScreeningPool::new(params.thread_count.get_count());
+    run_screening_in(&pool, template, references, params, exclusivity, progress_tx, None)
+}
+
+/// Run the complete screening analysis on an existing pool. When `cancel` is
+/// set during the run, the results hold only the windows completed by then.
+pub fn run_screening_in(
+    pool: &ScreeningPool,
+    template: &TemplateData,
+    references: &ReferenceData,
+    params: &AnalysisParams,
+    exclusivity: &[ExclusivityGroup],
     progress_tx: Option<Sender<ProgressUpdate>>,
+    cancel: Option<&CancelToken>,
 ) -> ScreeningResults {
-    // Configure rayon thread pool
-    let num_threads = params.thread_count.get_count();
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build()
-        .unwrap_or_else(|_| rayon::ThreadPoolBuilder::new().build().unwrap());
+    let panel = (!exclusivity.is_empty()).then(|| PreparedPanel::new(exclusivity, params));
+    resume_screening_in(
+        pool,
+        template,
+        references,
+        params,
+        panel.as_ref(),
+        progress_tx,
+        cancel,
+        None,
+        None,
+    )
+}
 
-    let differential_enabled = exclusivity.is_some();
-    let exclusivity_sequence_count = exclusivity.map(|e| e.len());
+/// Continue a cancelled run from its partial results (`checkpoint`), which
+/// must come from the same inputs and parameters: windows it holds are kept
+/// instead of being screened again, so the results match an uninterrupted
+/// run. Without a checkpoint this is `run_screening_in` with the
+/// exclusivity panel already prepared, e.g. with a seed index read back by
+/// `load_prepared_panel`. Each oligo length screened to completion is also
+/// sent to `length_tx` as soon as it finishes.
+#[allow(clippy::too_many_arguments)]
+pub fn resume_screening_in(
+    pool: &ScreeningPool,
+    template: &TemplateData,
+    references: &ReferenceData,
+    params: &AnalysisParams,
+    exclusivity: Option<&PreparedPanel>,
+    progress_tx: Option<Sender<ProgressUpdate>>,
+    cancel: Option<&CancelToken>,
+    checkpoint: Option<&ScreeningResults>,
+    length_tx: Option<Sender<LengthResult>>,
+) -> ScreeningResults {
+    let inputs = SharedInputs::prepare(&pool.pool, references, params, exclusivity);
+    let job_progress = JobProgress::new(params, std::slice::from_ref(template));
+    screen_template(
+        &pool.pool,
+        template,
+        &inputs,
+        params,
+        "",
+        &job_progress,
+        &progress_tx,
+        cancel,
+        checkpoint,
+        length_tx,
+    )
+}
+
+/// Screen several templates against the same references and prepared
+/// exclusivity panel, preparing the references (duplicate collapsing, byte
+/// conversion) and applying the ignore patterns once for all templates.
+/// Returns one set of results per template, in order; when `cancel` is set,
+/// the template in progress is the last one returned, flagged as cancelled.
+pub fn run_templates_in(
+    pool: &ScreeningPool,
+    templates: &[TemplateData],
+    references: &ReferenceData,
+    params: &AnalysisParams,
+    exclusivity: Option<&PreparedPanel>,
+    progress_tx: Option<Sender<ProgressUpdate>>,
+    cancel: Option<&CancelToken>,
+) -> Vec<ScreeningResults> {
+    let inputs = SharedInputs::prepare(&pool.pool, references, params, exclusivity);
+    let job_progress = JobProgress::new(params, templates);
+    let mut batch = Vec::with_capacity(templates.len());
+    for (i, template) in templates.iter().enumerate() {
+        if cancel.is_some_and(|c| c.is_cancelled()) {
+            break;
+        }
+        let label = format!(
+            "Template {}/{} ({}), ",
+            i + 1,
+            templates.len(),
+            template.name
+        );
+        batch.push(screen_template(
+            &pool.pool,
+            template,
+            &inputs,
+            params,
+            &label,
+            &job_progress,
+            &progress_tx,
+            cancel,
+            None,
+            None,
+        ));
+    }
+    batch
+}
+
+/// The inputs of a screen that do not depend on the template
+struct SharedInputs<'a> {
+    /// The references, or none for exclusivity-only runs
+    references: Cow<'a, ReferenceData>,
+    /// Distinct references and their bytes, unless each template trims the
+    /// references to its own region first
+    distinct: Option<(ReferenceGroups, Arc<Vec<Vec<u8>>>)>,
+    exclusivity: Option<ExclusivityInputs<'a>>,
+}
+
+/// A prepared exclusivity panel with the settings of one run applied
+struct ExclusivityInputs<'a> {
+    panel: &'a PreparedPanel,
+    /// Which sequences the ignore patterns match; None when none do
+    ignored: Option<Vec<bool>>,
+    /// Sequences per group, when there are two or more
+    group_sizes: Vec<usize>,
+}
+
+impl<'a> ExclusivityInputs<'a> {
+    fn new(panel: &'a PreparedPanel, params: &AnalysisParams) -> Self {
+        // Sequences matching the ignore patterns keep their hits, which are
+        // left out of the effective minimum mismatches
+        let patterns = &params.exclusivity_ignore_patterns;
+        let ignored: Vec<bool> = panel
+            .names
+            .iter()
+            .map(|name| {
+                patterns
+                    .iter()
+                    .any(|p| !p.trim().is_empty() && name_matches_pattern(name, p))
+            })
+            .collect();
+        // A single group is the whole panel, so per-group results need two or more
+        let group_sizes = if panel.groups.len() < 2 {
+            Vec::new()
+        } else {
+            panel.groups.iter().map(|(_, size)| *size).collect()
+        };
+        Self {
+            panel,
+            ignored: ignored.contains(&true).then_some(ignored),
+            group_sizes,
+        }
+    }
+
+    /// Names of the sequences matching the ignore patterns
+    fn ignored_names(&self) -> Vec<String> {
+        let Some(ignored) = &self.ignored else {
+            return Vec::new();
+        };
+        self.panel
+            .names
+            .iter()
+            .zip(ignored)
+            .filter(|&(_, &ignored)| ignored)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Group names, when results are kept per group
+    fn group_names(&self) -> Vec<String> {
+        if self.group_sizes.is_empty() {
+            return Vec::new();
+        }
+        self.panel
+            .groups
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+impl<'a> SharedInputs<'a> {
+    fn prepare(
+        pool: &rayon::ThreadPool,
+        references: &'a ReferenceData,
+        params: &AnalysisParams,
+        exclusivity: Option<&'a PreparedPanel>,
+    ) -> Self {
+        // Exclusivity-only runs never look at the references
+        let references = if params.exclusivity_only {
+            Cow::Owned(ReferenceData::new())
+        } else {
+            Cow::Borrowed(references)
+        };
+
+        // Trimmed references are collapsed per template instead
+        let trims = params.reference_trim_flank.is_some() && !params.exclusivity_only;
+        let distinct = (!trims).then(|| pool.install(|| distinct_references(&references)));
+
+        Self {
+            references,
+            distinct,
+            exclusivity: exclusivity.map(|panel| ExclusivityInputs::new(panel, params)),
+        }
+    }
+}
+
+/// The distinct sequences of a reference set, converted to byte vectors for
+/// alignment; copies share the alignment of their representative
+fn distinct_references(references: &ReferenceData) -> (ReferenceGroups, Arc<Vec<Vec<u8>>>) {
+    let reference_groups = ReferenceGroups::new(references);
+    let ref_bytes: Vec<Vec<u8>> = reference_groups
+        .representatives
+        .iter()
+        .map(|&i| references.sequences[i].as_bytes().to_vec())
+        .collect();
+    (reference_groups, Arc::new(ref_bytes))
+}
+
+/// Screen one template with the prepared inputs; see `resume_screening_in`.
+/// `label` starts every progress message.
+#[allow(clippy::too_many_arguments)]
+fn screen_template(
+    pool: &rayon::ThreadPool,
+    template: &TemplateData,
+    inputs: &SharedInputs,
+    params: &AnalysisParams,
+    label: &str,
+    job_progress: &JobProgress,
+    progress_tx: &Option<Sender<ProgressUpdate>>,
+    cancel: Option<&CancelToken>,
+    checkpoint: Option<&ScreeningResults>,
+    length_tx: Option<Sender<LengthResult>>,
+) -> ScreeningResults {
+    let references: &ReferenceData = &inputs.references;
+    let exclusivity = inputs.exclusivity.as_ref();
 
     let mut results = ScreeningResults::new(
         params.clone(),
         template.sequence.len(),
         references.len(),
         template.sequence.clone(),
-        differential_enabled,
-        exclusivity_sequence_count,
+        exclusivity.is_some(),
+        exclusivity.map(|e| e.panel.len()),
     );
+    results.reference_names = references.names.clone();
+    if let Some(exclusivity) = exclusivity {
+        results.ignored_exclusivity_names = exclusivity.ignored_names();
+        results.exclusivity_groups = exclusivity.group_names();
+        if let Some(taxa) = &exclusivity.panel.taxa {
+            results.exclusivity_taxa = taxa.labels.clone();
+        }
+    }
+    results.rna = template.rna;
+    results.variants_kept = params.keep_variants;
 
-    // Pre-convert reference sequences to byte vectors for alignment
-    let ref_bytes: Vec<Vec<u8>> = references
-        .sequences
-        .iter()
-        .map(|s| s.as_bytes().to_vec())
-        .collect();
-    let ref_bytes = Arc::new(ref_bytes);
-
-    // Pre-convert exclusivity sequences if provided
-    let excl_bytes: Option<Arc<Vec<Vec<u8>>>> = exclusivity.map(|e| {
-        Arc::new(
-            e.sequences
-                .iter()
-                .map(|s| s.as_bytes().to_vec())
-                .collect(),
-        )
+    // Optionally shrink references to the template region before any window
+    // is aligned, then collapse the trimmed set
+    let trimmed;
+    let (reference_groups, ref_bytes) = match &inputs.distinct {
+        Some((reference_groups, ref_bytes)) => (reference_groups, Arc::clone(ref_bytes)),
+        None => {
+            let flank = params.reference_trim_flank.unwrap_or_default();
+            let (data, summary) =
+                pool.install(|| trim_references(&template.sequence, references, flank));
+            results.reference_trim = Some(summary);
+            trimmed = distinct_references(&data);
+            (&trimmed.0, Arc::clone(&trimmed.1))
+        }
+    };
+    // Map the references to the template once, so windows they contain
+    // verbatim skip alignment
+    let anchors = (exact_hits_are_best(&params.pairwise) && !ref_bytes.is_empty()).then(|| {
+        pool.install(|| ReferenceAnchors::build(template.sequence.as_bytes(), &ref_bytes))
     });
-    let excl_names: Option<Arc<Vec<String>>> =
-        exclusivity.map(|e| Arc::new(e.names.clone()));
+    // Per-base conservation, each distinct reference counted once per copy
+    if !ref_bytes.is_empty() {
+        let copies: Vec<u32> = reference_groups
+            .members
+            .iter()
+            .map(|m| m.len() as u32)
+            .collect();
+        results.conservation =
+            pool.install(|| template_conservation(&template.sequence, &ref_bytes, &copies));
+    }
 
     let total_lengths = params.max_oligo_length - params.min_oligo_length + 1;
+    let template_len = template.sequence.len();
 
     for (length_idx, oligo_length) in
         (params.min_oligo_length..=params.max_oligo_length).enumerate()
     {
+        if cancel.is_some_and(|c| c.is_cancelled()) {
+            break;
+        }
         let ref_bytes = Arc::clone(&ref_bytes);
-        let excl_bytes = excl_bytes.clone();
-        let excl_names = excl_names.clone();
-        let length_result = pool.install(|| {
-            analyze_length(
-                template,
-                &ref_bytes,
-                excl_bytes.as_ref().map(|v| v.as_slice()),
-                excl_names.as_ref().map(|v| v.as_slice()),
-                params,
-                oligo_length,
-                length_idx as u32,
-                total_lengths,
-                &progress_tx,
-            )
-        });
+        let screen = |positions: &[usize], refining: bool| {
+            pool.install(|| {
+                analyze_length(
+                    template,
+                    &ref_bytes,
+                    reference_groups,
+                    anchors.as_ref(),
+                    exclusivity,
+                    params,
+                    positions,
+                    refining,
+                    oligo_length,
+                    length_idx as u32,
+                    total_lengths,
+                    label,
+                    job_progress,
+                    progress_tx,
+                    cancel,
+                )
+            })
+        };
+
+        let length = oligo_length as usize;
+        let coarse_positions = params.window_positions(template_len, length);
+        // Windows of this length screened before the checkpoint, by pass
+        let (mut kept_coarse, kept_fine): (Vec<PositionResult>, Vec<PositionResult>) = checkpoint
+            .and_then(|c| c.results_by_length.get(&oligo_length))
+            .map(|r| {
+                r.positions
+                    .iter()
+                    .cloned()
+                    .partition(|r| coarse_positions.binary_search(&r.position).is_ok())
+            })
+            .unwrap_or_default();
+        let kept = kept_coarse.len() + kept_fine.len();
+        job_progress
+            .completed
+            .fetch_add(kept_coarse.len(), Ordering::Relaxed);
+        let mut length_result = screen(&unscreened(&coarse_positions, &kept_coarse), false);
+        length_result.positions.append(&mut kept_coarse);
+        length_result.positions.sort_by_key(|r| r.position);
+
+        // Second pass: re-screen around the promising coarse windows at step 1
+        let fine_positions =
+            params.refinement_positions(template_len, length, &length_result.positions);
+        if !fine_positions.is_empty() && !cancel.is_some_and(|c| c.is_cancelled()) {
+            job_progress
+                .total
+                .fetch_add(fine_positions.len(), Ordering::Relaxed);
+            job_progress
+                .completed
+                .fetch_add(kept_fine.len(), Ordering::Relaxed);
+            let fine = screen(&unscreened(&fine_positions, &kept_fine), true);
+            length_result.positions.extend(fine.positions);
+        }
+        length_result.positions.extend(kept_fine);
+        length_result.positions.sort_by_key(|r| r.position);
+
+        // Lengths taken over whole from the checkpoint are not sent again
+        if let Some(tx) = &length_tx
+            && length_result.positions.len() > kept
+            && !cancel.is_some_and(|c| c.is_cancelled())
+        {
+            let _ = tx.send(length_result.clone());
+        }
 
         results
             .results_by_length
             .insert(oligo_length, length_result);
     }
+    results.cancelled = cancel.is_some_and(|c| c.is_cancelled());
+    // Lengths not reached again before a second cancel keep their checkpoint
+    if let Some(checkpoint) = checkpoint {
+        for (&oligo_length, length_result) in &checkpoint.results_by_length {
+            results
+                .results_by_length
+                .entry(oligo_length)
+                .or_insert_with(|| length_result.clone());
+        }
+    }
 
     results
 }
 
-/// Analyze all positions for a specific oligo length.
-/// Uses `map_init` to create one Aligner per rayon task (roughly per thread).
+/// `positions` without those already screened in `kept`
+fn unscreened(positions: &[usize], kept: &[PositionResult]) -> Vec<usize> {
+    let kept: HashSet<usize> = kept.iter().map(|r| r.position).collect();
+    positions
+        .iter()
+        .copied()
+        .filter(|p| !kept.contains(p))
+        .collect()
+}
+
+/// Analyze the given positions for a specific oligo length.
+/// Each pool thread reuses its own Aligner across positions and jobs.
+#[allow(clippy::too_many_arguments)]
 fn analyze_length(
     template: &TemplateData,
     ref_bytes: &[Vec<u8>],
-    excl_bytes: Option<&[Vec<u8>]>,
-    excl_names: Option<&[String]>,
+    reference_groups: &ReferenceGroups,
+    anchors: Option<&ReferenceAnchors>,
+    exclusivity: Option<&ExclusivityInputs>,
     params: &AnalysisParams,
+    positions: &[usize],
+    refining: bool,
     oligo_length: u32,
     length_idx: u32,
     total_lengths: u32,
+    label: &str,
+    job_progress: &JobProgress,
     progress_tx: &Option<Sender<ProgressUpdate>>,
+    cancel: Option<&CancelToken>,
 ) -> LengthResult {
     let length = oligo_length as usize;
-    let resolution = params.resolution as usize;
-    let template_len = template.sequence.len();
-
-    // Calculate positions to analyze
-    let max_start = if template_len >= length {
-        template_len - length
-    } else {
-        0
-    };
-
-    let positions: Vec<usize> = (0..=max_start).step_by(resolution).collect();
     let total_positions = positions.len();
+    let pass = if refining { " (refining)" } else { "" };
 
     let completed_count = Arc::new(AtomicUsize::new(0));
+    let throttle = ProgressThrottle::new(params.progress_interval);
     let template_bytes = template.sequence.as_bytes();
 
     // Pre-compute max reference length for aligner sizing (include exclusivity seqs)
     let max_ref_len = ref_bytes.iter().map(|r| r.len()).max().unwrap_or(0);
-    let max_excl_len = excl_bytes
-        .map(|eb| eb.iter().map(|r| r.len()).max().unwrap_or(0))
+    let max_excl_len = exclusivity
+        .and_then(|e| e.panel.distinct.iter().map(|r| r.len()).max())
         .unwrap_or(0);
     let max_seq_len = max_ref_len.max(max_excl_len);
     let pw_params = params.pairwise;
 
-    // Process positions in parallel, one Aligner per rayon task
+    // Process positions in parallel, one Aligner per pool thread
     let mut position_results: Vec<PositionResult> = positions
         .par_iter()
-        .map_init(
-            move || create_aligner(length, max_seq_len, &pw_params),
-            |aligner, &position| {
-                let analysis = analyze_window(
-                    template_bytes,
-                    ref_bytes,
-                    params,
-                    position,
-                    length,
-                    aligner,
+        .filter(|_| !cancel.is_some_and(|c| c.is_cancelled()))
+        .map(|&position| {
+            with_thread_aligner(length, max_seq_len, &pw_params, |aligner| {
+                // Masked windows, and windows over template ambiguity codes
+                // that are rejected or expand too far, are recorded as
+                // skipped without aligning
+                let expansions = template_expansions(
+                    &template_bytes[position..position + length],
+                    params.template_ambiguity,
                 );
-
-                // Run exclusivity analysis if data is provided
-                let exclusivity = excl_bytes.map(|eb| {
-                    analyze_exclusivity(
+                let skip_reason = if params.window_masked(position, length) {
+                    Some(MASKED_SKIP_REASON.to_string())
+                } else {
+                    expansions.as_ref().err().cloned()
+                };
+                let expansions = expansions.unwrap_or_default();
+                let mut analysis = if let Some(skip_reason) = &skip_reason {
+                    WindowAnalysisResult {
+                        total_sequences: reference_groups.reference_count(),
+                        skipped: true,
+                        skip_reason: Some(skip_reason.clone()),
+                        ..Default::default()
+                    }
+                } else if params.exclusivity_only {
+                    WindowAnalysisResult::default()
+                } else {
+                    analyze_window(
                         template_bytes,
-                        eb,
-                        excl_names.unwrap(),
-                        &params.pairwise,
+                        ref_bytes,
+                        reference_groups,
+                        anchors,
+                        params,
                         position,
                         length,
+                        expansions.as_deref(),
                         aligner,
                     )
-                });
+                };
+
+                // Run exclusivity analysis if data is provided
+                let (exclusivity, group_exclusivity) = match exclusivity {
+                    Some(inputs) if skip_reason.is_none() => {
+                        let (overall, groups) = analyze_exclusivity(
+                            template_bytes,
+                            inputs,
+                            &params.pairwise,
+                            position,
+                            length,
+                            expansions.as_deref(),
+                            aligner,
+                        );
+                        (Some(overall), groups)
+                    }
+                    _ => (None, Vec::new()),
+                };
+
+                let oligo = &template.sequence[position..position + length];
+                // Pruned after the metrics, which count the full list
+                if let Some(keep) = params.keep_variants {
+                    analysis.variants.truncate(keep);
+                }
 
                 // Update progress
                 let completed = completed_count.fetch_add(1, Ordering::Relaxed) + 1;
-                if let Some(tx) = progress_tx {
-                    if completed % 10 == 0 || completed == total_positions {
-                        let _ = tx.send(ProgressUpdate {
-                            current_length: oligo_length,
-                            current_position: position,
-                            total_positions,
-                            lengths_completed: length_idx,
+                let windows_completed = job_progress.completed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(tx) = progress_tx
+                    && throttle.should_send(completed, total_positions)
+                {
+                    let _ = tx.send(ProgressUpdate {
+                        current_length: oligo_length,
+                        current_position: position,
+                        total_positions,
+                        lengths_completed: length_idx,
+                        total_lengths,
+                        windows_completed,
+                        total_windows: job_progress.total.load(Ordering::Relaxed),
+                        message: format!(
+                            "{}Length {}/{}{}: Position {}/{}",
+                            label,
+                            length_idx + 1,
                             total_lengths,
-                            message: format!(
-                                "Length {}/{}: Position {}/{}",
-                                length_idx + 1,
-                                total_lengths,
-                                completed,
-                                total_positions
-                            ),
-                        });
-                    }
+                            pass,
+                            completed,
+                            total_positions
+                        ),
+                    });
                 }
 
                 PositionResult {
@@ -186,9 +607,12 @@ fn analyze_length(
                     variants_needed: analysis.variants_for_threshold,
                     analysis,
                     exclusivity,
+                    group_exclusivity,
+                    tm: nearest_neighbor_tm(oligo, &params.tm_conditions),
+                    gc_percent: Some(gc_percent(oligo)),
                 }
-            },
-        )
+            })
+        })
         .collect();
 
     // Sort results by position
@@ -200,22 +624,160 @@ fn analyze_length(
     }
 }
 
-/// Analyze a single window at a specific position using a pre-existing aligner.
+/// Window counts over the whole job, shared by the templates, lengths and
+/// passes
+struct JobProgress {
+    completed: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl JobProgress {
+    /// Starting with the coarse windows of every template; refinement adds
+    /// its windows as they are chosen
+    fn new(params: &AnalysisParams, templates: &[TemplateData]) -> Self {
+        let total = templates
+            .iter()
+            .flat_map(|template| {
+                (params.min_oligo_length..=params.max_oligo_length).map(|length| {
+                    params
+                        .window_positions(template.sequence.len(), length as usize)
+                        .len()
+                })
+            })
+            .sum();
+        Self {
+            completed: AtomicUsize::new(0),
+            total: AtomicUsize::new(total),
+        }
+    }
+}
+
+/// Decides which completed positions trigger a progress update.
+/// Shared across rayon tasks; the final position always reports.
+struct ProgressThrottle {
+    interval: ProgressInterval,
+    start: Instant,
+    last_sent_ms: AtomicU64,
+}
+
+impl ProgressThrottle {
+    fn new(interval: ProgressInterval) -> Self {
+        Self {
+            interval,
+            start: Instant::now(),
+            last_sent_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn should_send(&self, completed: usize, total: usize) -> bool {
+        if completed == total {
+            return true;
+        }
+        match self.interval {
+            ProgressInterval::Positions(n) => completed.is_multiple_of(n.max(1)),
+            ProgressInterval::Millis(ms) => {
+                let now = self.start.elapsed().as_millis() as u64;
+                let last = self.last_sent_ms.load(Ordering::Relaxed);
+                // Only the thread that wins the exchange sends for this interval
+                now.saturating_sub(last) >= ms
+                    && self
+                        .last_sent_ms
+                        .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+            }
+        }
+    }
+}
+
+/// Most concrete oligos a window over template ambiguity codes is expanded
+/// into; windows standing for more are skipped
+pub const MAX_TEMPLATE_EXPANSIONS: u64 = 256;
+
+/// The concrete oligos a template window over ambiguity codes is screened
+/// as (None for a window of plain bases), or why the window is skipped.
+fn template_expansions(
+    oligo: &[u8],
+    policy: TemplateAmbiguityPolicy,
+) -> Result<Option<Vec<Vec<u8>>>, String> {
+    if !oligo.iter().any(|&b| is_ambiguous_base(b as char)) {
+        return Ok(None);
+    }
+    match policy {
+        TemplateAmbiguityPolicy::Reject => {
+            Err("Template window contains ambiguity codes".to_string())
+        }
+        TemplateAmbiguityPolicy::Expand => expand_degenerate(oligo, MAX_TEMPLATE_EXPANSIONS)
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "Template window stands for more than {} oligos",
+                    MAX_TEMPLATE_EXPANSIONS
+                )
+            }),
+    }
+}
+
+/// Each reference's accepted match for the template window `oligo`, aligned
+/// as a screen aligns it: a window over ambiguity codes as its concrete
+/// oligos, each reference keeping its best match among them. None when the
+/// template ambiguity policy skips the window.
+pub fn screened_window_matches(
+    aligner: &mut DnaAligner,
+    oligo: &[u8],
+    ref_bytes: &[Vec<u8>],
+    params: &AnalysisParams,
+) -> Option<Vec<PairwiseMatch>> {
+    let matches = match template_expansions(oligo, params.template_ambiguity).ok()? {
+        Some(oligos) => best_matches(oligos.iter().map(|oligo| {
+            collect_matches_with_aligner(aligner, oligo, ref_bytes, &params.pairwise).0
+        })),
+        None => collect_matches_with_aligner(aligner, oligo, ref_bytes, &params.pairwise).0,
+    };
+    Some(matches)
+}
+
+/// Analyze a single window at a specific position using a pre-existing
+/// aligner. A window over template ambiguity codes is aligned as each of
+/// its `expansions`, and every reference keeps its best match among them.
+#[allow(clippy::too_many_arguments)]
 fn analyze_window(
     template_bytes: &[u8],
     ref_bytes: &[Vec<u8>],
+    reference_groups: &ReferenceGroups,
+    anchors: Option<&ReferenceAnchors>,
     params: &AnalysisParams,
     position: usize,
     length: usize,
+    expansions: Option<&[Vec<u8>]>,
     aligner: &mut DnaAligner,
 ) -> WindowAnalysisResult {
+    // The window's time budget covers alignment and merging
+    let deadline = params
+        .window_time_budget_ms
+        .map(|ms| Instant::now() + Duration::from_millis(ms));
+
     // Extract oligo from template
     let oligo = &template_bytes[position..position + length];
-    let total_refs = ref_bytes.len();
+    let total_refs = reference_groups.reference_count();
 
-    // Pairwise align against all references using the shared aligner
-    let (matched_sequences, no_match_count) =
-        collect_matches_with_aligner(aligner, oligo, ref_bytes, &params.pairwise);
+    // Pairwise align against all references using the shared aligner, taking
+    // the matches of references containing the window verbatim from the anchors
+    let distinct_matches = match expansions {
+        Some(oligos) => best_matches(oligos.iter().map(|oligo| {
+            collect_matches_with_aligner(aligner, oligo, ref_bytes, &params.pairwise).0
+        })),
+        None => {
+            collect_matches_with_anchors(aligner, oligo, ref_bytes, &params.pairwise, |i| {
+                anchors.is_some_and(|a| a.contains_window(i, position, length))
+            })
+            .0
+        }
+    };
+    // Every copy of a distinct sequence gets its match
+    let matches = reference_groups.expand_matches(distinct_matches);
+    let no_match_count = total_refs - matches.len();
+    let matched_sequences: Vec<&str> =
+        matches.iter().map(|m| m.matched_sequence.as_str()).collect();
 
     if matched_sequences.is_empty() {
         return WindowAnalysisResult {
@@ -228,20 +790,26 @@ fn analyze_window(
         };
     }
 
-    // Convert to &str for the analyzer
-    let seq_refs: Vec<&str> = matched_sequences.iter().map(|s| s.as_str()).collect();
-
     // Run the variant analysis on matched sequences
     let mut result = analyze_sequences(
-        &seq_refs,
+        &matched_sequences,
         &params.method,
         params.exclude_n,
+        &params.ambiguity_costs,
+        params.merge_strategy,
         params.coverage_threshold,
+        deadline,
     );
 
     result.total_sequences = total_refs;
     result.sequences_analyzed = matched_sequences.len();
     result.no_match_count = no_match_count;
+    let scores: Vec<i32> = matches.iter().map(|m| m.score).collect();
+    let identities: Vec<f64> = matches.iter().map(|m| m.percent_identity()).collect();
+    result.score_stats = AlignmentScoreStats::from_scores(&scores);
+    result.identity_stats = PercentIdentityStats::from_identities(&identities);
+    let mismatches: Vec<usize> = matches.iter().map(|m| m.mismatches).collect();
+    result.mismatch_stats = MismatchStats::from_counts(&mismatches);
 
     // Rescale variant percentages against total references (including no-matches)
     // so that no-match sequences count toward reducing coverage
@@ -272,24 +840,109 @@ fn analyze_window(
     result
 }
 
+/// Each reference's best accepted match over the concrete oligos of an
+/// expanded window: the highest score, ties keeping the earlier oligo.
+fn best_matches(per_oligo: impl Iterator<Item = Vec<PairwiseMatch>>) -> Vec<PairwiseMatch> {
+    let mut best: BTreeMap<usize, PairwiseMatch> = BTreeMap::new();
+    for m in per_oligo.flatten() {
+        match best.get(&m.reference_index) {
+            Some(current) if current.score >= m.score => {}
+            _ => {
+                best.insert(m.reference_index, m);
+            }
+        }
+    }
+    best.into_values().collect()
+}
+
 /// Analyze exclusivity for a single window position.
-/// Aligns the template oligo against each exclusivity sequence and records
-/// the number of mismatches (or no-match) per sequence.
+/// Aligns the template oligo against each distinct exclusivity sequence the
+/// panel's seed index leaves as a candidate (all of them without one) and
+/// records the number of mismatches (or no-match) per sequence, 3'-weighted
+/// when enabled; copies share the count of their sequence. An expanded window
+/// counts each sequence's closest hit among its oligos. Returns the result
+/// over all sequences and one per group.
 fn analyze_exclusivity(
     template_bytes: &[u8],
-    excl_bytes: &[Vec<u8>],
-    excl_names: &[String],
+    exclusivity: &ExclusivityInputs,
     params: &PairwiseParams,
     position: usize,
     length: usize,
+    expansions: Option<&[Vec<u8>]>,
     aligner: &mut DnaAligner,
-) -> ExclusivityResult {
+) -> (ExclusivityResult, Vec<ExclusivityResult>) {
     let oligo = &template_bytes[position..position + length];
-    let mismatch_counts =
-        collect_mismatch_counts_with_aligner(aligner, oligo, excl_bytes, params);
+    let excl_bytes = &exclusivity.panel.distinct;
+    let excl_names = &exclusivity.panel.names;
+    let excl_ignored = exclusivity.ignored.as_deref();
+    // Only the sequences the seed index can't rule out are aligned
+    let mut panel_hits = |oligo: &[u8]| match exclusivity.panel.candidates(oligo, params) {
+        Some(candidates) => {
+            collect_exclusivity_hits_among(aligner, oligo, excl_bytes, &candidates, params)
+        }
+        None => collect_exclusivity_hits_with_aligner(aligner, oligo, excl_bytes, params),
+    };
+    let distinct_hits = match expansions {
+        Some(oligos) => oligos
+            .iter()
+            .map(|oligo| panel_hits(oligo))
+            .reduce(|mut closest, hits| {
+                for (current, hit) in closest.iter_mut().zip(hits) {
+                    if let Some(hit) = hit
+                        && current.is_none_or(|c| hit.0 < c.0)
+                    {
+                        *current = Some(hit);
+                    }
+                }
+                closest
+            })
+            .unwrap_or_default(),
+        None => panel_hits(oligo),
+    };
+    let hits = exclusivity.panel.expand(&distinct_hits);
+
+    let mut start = 0;
+    let groups = exclusivity
+        .group_sizes
+        .iter()
+        .map(|&size| {
+            let range = start..start + size;
+            start += size;
+            let ignored = excl_ignored.map(|i| &i[range.clone()]);
+            exclusivity_from_hits(
+                &hits[range.clone()],
+                &excl_names[range],
+                ignored,
+                None,
+                params,
+            )
+        })
+        .collect();
+    let taxa = exclusivity.panel.taxa.as_ref();
+    let overall = exclusivity_from_hits(&hits, excl_names, excl_ignored, taxa, params);
+    (overall, groups)
+}
+
+/// Summarize per-sequence exclusivity hits (weighted mismatches and 3'-end
+/// mismatches, None = no match) into a histogram and minimums. Hits of the
+/// `ignored` sequences are counted in the histogram, and per bucket on their
+/// own, but not in the per-taxon minimums.
+fn exclusivity_from_hits(
+    hits: &[Option<(u32, u32)>],
+    excl_names: &[String],
+    ignored: Option<&[bool]>,
+    excl_taxa: Option<&TaxonGroups>,
+    params: &PairwiseParams,
+) -> ExclusivityResult {
+    let mismatch_counts: Vec<Option<u32>> = hits
+        .iter()
+        .map(|hit| hit.map(|(mismatches, _)| mismatches))
+        .collect();
+
+    let is_ignored = |i: usize| ignored.is_some_and(|ignored| ignored[i]);
 
     // Build histogram: group by mismatch count
-    let mut buckets: std::collections::HashMap<u32, (usize, String)> =
+    let mut buckets: std::collections::HashMap<u32, (usize, usize, String)> =
         std::collections::HashMap::new();
     let mut no_match_count = 0usize;
     let mut no_match_example = String::new();
@@ -298,8 +951,13 @@ fn analyze_exclusivity(
     for (i, mm) in mismatch_counts.iter().enumerate() {
         match mm {
             Some(m) => {
-                let entry = buckets.entry(*m).or_insert_with(|| (0, excl_names[i].clone()));
+                let entry = buckets
+                    .entry(*m)
+                    .or_insert_with(|| (0, 0, excl_names[i].clone()));
                 entry.0 += 1;
+                if is_ignored(i) {
+                    entry.1 += 1;
+                }
                 match min_mismatches {
                     None => min_mismatches = Some(*m),
                     Some(current) if *m < current => min_mismatches = Some(*m),
@@ -317,11 +975,14 @@ fn analyze_exclusivity(
 
     let mut mismatch_histogram: Vec<MismatchBucket> = buckets
         .into_iter()
-        .map(|(mismatches, (count, example_name))| MismatchBucket {
-            mismatches,
-            count,
-            example_name,
-        })
+        .map(
+            |(mismatches, (count, ignored_count, example_name))| MismatchBucket {
+                mismatches,
+                count,
+                ignored_count,
+                example_name,
+            },
+        )
         .collect();
     mismatch_histogram.sort_by_key(|b| b.mismatches);
 
@@ -330,28 +991,55 @@ fn analyze_exclusivity(
         mismatch_histogram.push(MismatchBucket {
             mismatches: u32::MAX,
             count: no_match_count,
+            ignored_count: 0,
             example_name: no_match_example,
         });
     }
 
+    // Sequences ignored by name don't make their taxon a near match
+    let taxon_min_mismatches = excl_taxa.map_or_else(Vec::new, |t| {
+        let counted: Vec<Option<u32>> = mismatch_counts
+            .iter()
+            .enumerate()
+            .map(|(i, mm)| mm.filter(|_| !is_ignored(i)))
+            .collect();
+        t.min_mismatches(&counted)
+    });
+
     ExclusivityResult {
-        total_sequences: excl_bytes.len(),
+        total_sequences: hits.len(),
         no_match_count,
         mismatch_histogram,
         min_mismatches,
+        taxon_min_mismatches,
+        min_three_prime_mismatches: params.three_prime.and_then(|_| {
+            hits.iter()
+                .flatten()
+                .map(|&(_, three_prime)| three_prime)
+                .min()
+        }),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::filter::{ExclusivityFilter, effective_min_mismatches};
     use crate::analysis::types::AnalysisMethod;
 
+    fn exclusivity_group(data: ReferenceData) -> ExclusivityGroup {
+        ExclusivityGroup {
+            name: "Exclusivity".to_string(),
+            data,
+        }
+    }
+
     #[test]
     fn test_screening_example() {
         let template = TemplateData {
             name: "Template".to_string(),
             sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
         };
 
         let references = ReferenceData {
@@ -378,37 +1066,377 @@ mod tests {
             ..Default::default()
         };
 
-        let results = run_screening(&template, &references, &params, None, None);
+        let results = run_screening(&template, &references, &params, &[], None);
         assert!(results.results_by_length.contains_key(&10));
 
         let length_result = results.results_by_length.get(&10).unwrap();
         // First position should have variants
         let first_pos = &length_result.positions[0];
         assert!(!first_pos.analysis.skipped);
-        assert!(first_pos.analysis.variants.len() >= 1);
+        assert!(!first_pos.analysis.variants.is_empty());
+        // 3 exact matches (score 20) + 1 with one mismatch (score 17)
+        let stats = first_pos.analysis.score_stats.unwrap();
+        assert_eq!((stats.min, stats.max), (17, 20));
+        assert!((stats.mean - 19.25).abs() < 1e-9);
+        let identity = first_pos.analysis.identity_stats.unwrap();
+        assert!((identity.min - 90.0).abs() < 1e-9);
+        assert!((identity.mean - 97.5).abs() < 1e-9);
+        let mismatches = first_pos.analysis.mismatch_stats.unwrap();
+        assert_eq!(mismatches.max, 1);
+        assert!((mismatches.mean - 0.25).abs() < 1e-9);
         // No exclusivity data when not provided
         assert!(first_pos.exclusivity.is_none());
+        // Tm of the template oligo under the job's conditions
+        assert_eq!(
+            first_pos.tm,
+            nearest_neighbor_tm("TATGGTACGT", &params.tm_conditions)
+        );
+        assert!(first_pos.tm.is_some());
+        assert_eq!(first_pos.gc_percent, Some(40.0));
+        // Every matched reference is counted under exactly one variant
+        let counted: usize = first_pos.analysis.variants.iter().map(|v| v.count).sum();
+        assert_eq!(counted, 4);
+        assert_eq!(results.reference_names[2], "Ref3");
+        // Ref3 differs from the template at the 7th base
+        assert_eq!(results.conservation.len(), 31);
+        assert_eq!(results.conservation[6].coverage, 4);
+        assert_eq!(results.conservation[6].identity, 0.75);
+        assert_eq!(results.conservation[0].identity, 1.0);
     }
 
     #[test]
-    fn test_screening_with_exclusivity() {
+    fn test_screening_with_masked_regions() {
         let template = TemplateData {
             name: "Template".to_string(),
             sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
         };
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            region_start: Some(5),
+            region_end: Some(25),
+            masked_regions: vec![(11, 13)],
+            ..Default::default()
+        };
+
+        let results = run_screening(&template, &references, &params, &[], None);
+        let positions = &results.results_by_length[&10].positions;
+        // Windows 5..=15 lie in the region; those overlapping 11..13 are masked
+        assert_eq!(positions.len(), 11);
+        for pr in positions {
+            let masked = pr.position <= 12;
+            assert_eq!(pr.analysis.skipped, masked, "position {}", pr.position);
+            if masked {
+                assert_eq!(pr.analysis.skip_reason.as_deref(), Some(MASKED_SKIP_REASON));
+                assert_eq!(pr.analysis.sequences_analyzed, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_screening_with_degenerate_template() {
+        // A consensus template with Y (C or T) at position 10
+        let template = TemplateData {
+            name: "Consensus".to_string(),
+            sequence: "TATGGTACGTYATGTTCTAG".to_string(),
+            rna: false,
+        };
+        let references = ReferenceData {
+            names: vec!["C".to_string(), "T".to_string()],
+            sequences: vec![
+                "TATGGTACGTCATGTTCTAG".to_string(),
+                "TATGGTACGTTATGTTCTAG".to_string(),
+            ],
+        };
+        let mut params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            ..Default::default()
+        };
+
+        // Rejected: every window over position 10 is skipped
+        let results = run_screening(&template, &references, &params, &[], None);
+        for pr in &results.results_by_length[&10].positions {
+            assert_eq!(
+                pr.analysis.skipped,
+                pr.position >= 1,
+                "position {}",
+                pr.position
+            );
+        }
+
+        // Expanded: both references match one of the concrete oligos exactly
+        params.template_ambiguity = TemplateAmbiguityPolicy::Expand;
+        let results = run_screening(&template, &references, &params, &[], None);
+        for pr in &results.results_by_length[&10].positions {
+            assert!(!pr.analysis.skipped, "position {}", pr.position);
+            assert_eq!(pr.analysis.sequences_analyzed, 2);
+            assert_eq!(pr.analysis.mismatch_stats.as_ref().unwrap().max, 0);
+            let expected = if pr.position >= 1 { 2 } else { 1 };
+            assert_eq!(pr.variants_needed, expected, "position {}", pr.position);
+        }
 
+        // Too many concrete oligos: skipped even when expanding
+        let template = TemplateData {
+            sequence: "TATGNNNNNTYATGTTCTAG".to_string(),
+            ..template
+        };
+        let results = run_screening(&template, &references, &params, &[], None);
+        assert!(results.results_by_length[&10].positions[0].analysis.skipped);
+    }
+
+    #[test]
+    fn test_multi_template_screening() {
+        let templates = vec![
+            TemplateData {
+                name: "A".to_string(),
+                sequence: "TATGGTACGTCATGTTCTAG".to_string(),
+                rna: false,
+            },
+            TemplateData {
+                name: "B".to_string(),
+                sequence: "AAATGGGCTGTCCGATCAGGTT".to_string(),
+                rna: false,
+            },
+        ];
+        let references = ReferenceData {
+            names: vec!["R1".to_string(), "R2".to_string(), "R3".to_string()],
+            sequences: vec![
+                "TATGGTACGTCATGTTCTAGAAATGGGCTGTCCGATCAGGTT".to_string(),
+                "TATGGTACCTCATGTTCTAGAAATGGGCTGACCGATCAGGTT".to_string(),
+                "TATGGTACGTCATGTTCTAGAAATGGGCTGTCCGATCAGGTT".to_string(),
+            ],
+        };
+        let needed = |r: &LengthResult| -> Vec<usize> {
+            r.positions.iter().map(|p| p.variants_needed).collect()
+        };
+        let pool = ScreeningPool::new(2);
+        for reference_trim_flank in [None, Some(3)] {
+            let params = AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 11,
+                reference_trim_flank,
+                ..Default::default()
+            };
+            let batch = run_templates_in(&pool, &templates, &references, &params, None, None, None);
+            assert_eq!(batch.len(), 2);
+            // Each template's results are those of screening it alone
+            for (template, results) in templates.iter().zip(&batch) {
+                let alone = run_screening(template, &references, &params, &[], None);
+                assert_eq!(results.template_sequence, template.sequence);
+                for (length, length_result) in &alone.results_by_length {
+                    assert_eq!(
+                        needed(&results.results_by_length[length]),
+                        needed(length_result)
+                    );
+                }
+            }
+        }
+
+        // Cancelled before the first template: nothing is screened
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let params = AnalysisParams::default();
+        let batch = run_templates_in(
+            &pool,
+            &templates,
+            &references,
+            &params,
+            None,
+            None,
+            Some(&cancel),
+        );
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn test_screening_with_kept_variants() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        // Three variants in every window
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string(), "Ref2".to_string(), "Ref3".to_string()],
+            sequences: vec![
+                template.sequence.clone(),
+                "TATGGTACGTCATGTTCTAGAAATGGGCTGT".replace('G', "C"),
+                "TATGGTACGTCATGTTCTAGAAATGGGCTGT".replace('A', "T"),
+            ],
+        };
+        let mut params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            resolution: 5,
+            coverage_threshold: 100.0,
+            pairwise: PairwiseParams {
+                max_mismatches: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let full = run_screening(&template, &references, &params, &[], None);
+        assert_eq!(full.variants_kept, None);
+
+        params.keep_variants = Some(1);
+        let pruned = run_screening(&template, &references, &params, &[], None);
+        assert_eq!(pruned.variants_kept, Some(1));
+        let windows = full.results_by_length[&10]
+            .positions
+            .iter()
+            .zip(&pruned.results_by_length[&10].positions);
+        for (full_pr, pruned_pr) in windows {
+            // The metrics still count every variant; only the list is cut
+            assert_eq!(pruned_pr.variants_needed, full_pr.variants_needed);
+            assert_eq!(pruned_pr.analysis.variants.len(), 1);
+            assert_eq!(
+                pruned_pr.analysis.variants[0].sequence,
+                full_pr.analysis.variants[0].sequence
+            );
+        }
+
+        params.keep_variants = Some(0);
+        let metrics_only = run_screening(&template, &references, &params, &[], None);
+        assert!(
+            metrics_only.results_by_length[&10]
+                .positions
+                .iter()
+                .all(|pr| pr.analysis.variants.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_screening_with_refinement() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
         let references = ReferenceData {
             names: vec!["Ref1".to_string()],
-            sequences: vec!["TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let mut params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            resolution: 5,
+            refine_max_variants: Some(0),
+            ..Default::default()
         };
 
-        let exclusivity = ReferenceData {
-            names: vec!["Excl1".to_string(), "Excl2".to_string()],
+        // Every window needs one variant, so nothing is refined at 0...
+        let results = run_screening(&template, &references, &params, &[], None);
+        let positions: Vec<usize> = results.results_by_length[&10]
+            .positions
+            .iter()
+            .map(|pr| pr.position)
+            .collect();
+        assert_eq!(positions, vec![0, 5, 10, 15, 20]);
+
+        // ...and everything around the coarse windows at 1, in position order
+        params.refine_max_variants = Some(1);
+        let results = run_screening(&template, &references, &params, &[], None);
+        let positions: Vec<usize> = results.results_by_length[&10]
+            .positions
+            .iter()
+            .map(|pr| pr.position)
+            .collect();
+        assert_eq!(positions, (0..=21).collect::<Vec<_>>());
+        assert!(
+            results.results_by_length[&10]
+                .positions
+                .iter()
+                .all(|pr| pr.variants_needed == 1)
+        );
+    }
+
+    #[test]
+    fn test_screening_with_reference_trimming() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        let background = "CAGGACTTCAGCTTAGCCGATCCTGAACGTAGGCTTACA".repeat(20);
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string(), "Ref2".to_string()],
             sequences: vec![
-                "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(), // exact match = 0 mismatches
-                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(), // very different
+                format!("{}{}{}", background, template.sequence, background),
+                format!("{}TATGGTTCGTCATGTTCTAGAAATGGGCTGT", background),
             ],
         };
+        let params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 10,
+            ..Default::default()
+        };
+        let trim_params = AnalysisParams {
+            reference_trim_flank: Some(20),
+            ..params.clone()
+        };
+
+        let full = run_screening(&template, &references, &params, &[], None);
+        let trimmed = run_screening(&template, &references, &trim_params, &[], None);
+        assert!(full.reference_trim.is_none());
+        let summary = trimmed.reference_trim.unwrap();
+        assert_eq!(summary.trimmed, 2);
+        assert!(summary.bases_after < summary.bases_before / 10);
+
+        // Trimming only removes sequence far from the template region
+        let needed = |r: &ScreeningResults| -> Vec<usize> {
+            r.results_by_length[&10]
+                .positions
+                .iter()
+                .map(|p| p.variants_needed)
+                .collect()
+        };
+        assert_eq!(needed(&full), needed(&trimmed));
+    }
+
+    #[test]
+    fn test_progress_throttle() {
+        let by_count = ProgressThrottle::new(ProgressInterval::Positions(4));
+        let sent: Vec<usize> = (1..=10).filter(|&c| by_count.should_send(c, 10)).collect();
+        assert_eq!(sent, vec![4, 8, 10]);
+
+        // A long interval suppresses everything but the final position
+        let by_time = ProgressThrottle::new(ProgressInterval::Millis(60_000));
+        let sent: Vec<usize> = (1..=10).filter(|&c| by_time.should_send(c, 10)).collect();
+        assert_eq!(sent, vec![10]);
+    }
+
+    #[test]
+    fn test_screening_with_exclusivity() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec!["TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string()],
+        };
+
+        let group = |name: &str, sequence: &str| ExclusivityGroup {
+            name: name.to_string(),
+            data: ReferenceData {
+                names: vec![format!("{} 1", name)],
+                sequences: vec![sequence.to_string()],
+            },
+        };
+        let exclusivity = [
+            // exact match = 0 mismatches
+            group("Related", "TATGGTACGTCATGTTCTAGAAATGGGCTGT"),
+            // very different
+            group("Unrelated", "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+        ];
 
         let params = AnalysisParams {
             method: AnalysisMethod::NoAmbiguities,
@@ -419,7 +1447,7 @@ mod tests {
             ..Default::default()
         };
 
-        let results = run_screening(&template, &references, &params, Some(&exclusivity), None);
+        let results = run_screening(&template, &references, &params, &exclusivity, None);
         let length_result = results.results_by_length.get(&10).unwrap();
         let first_pos = &length_result.positions[0];
 
@@ -428,5 +1456,222 @@ mod tests {
         assert_eq!(excl.total_sequences, 2);
         assert!(results.differential_enabled);
         assert_eq!(results.exclusivity_sequence_count, Some(2));
+
+        // Each group is scored on its own; the related one limits specificity
+        assert_eq!(results.exclusivity_groups, vec!["Related", "Unrelated"]);
+        let groups = &first_pos.group_exclusivity;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].total_sequences, 1);
+        assert_eq!(groups[0].min_mismatches, Some(0));
+        assert_eq!(groups[0].mismatch_histogram[0].example_name, "Related 1");
+        assert_eq!(groups[1].no_match_count, 1);
+        assert_eq!(first_pos.limiting_exclusivity_group(), Some(0));
+
+        // A single group is the whole panel
+        let single = run_screening(&template, &references, &params, &exclusivity[..1], None);
+        assert!(single.exclusivity_groups.is_empty());
+        let single_pos = &single.results_by_length[&10].positions[0];
+        assert!(single_pos.group_exclusivity.is_empty());
+    }
+
+    #[test]
+    fn test_screening_ignores_exclusivity_by_name() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let exclusivity = exclusivity_group(ReferenceData {
+            names: vec!["Vector artifact".to_string(), "Excl2".to_string()],
+            sequences: vec![
+                template.sequence.clone(), // exact match, but ignored by name
+                "TATGGTACGTGATGTTCTACAAATGGGCTGT".to_string(),
+            ],
+        });
+        let params = AnalysisParams {
+            method: AnalysisMethod::NoAmbiguities,
+            min_oligo_length: 30,
+            max_oligo_length: 30,
+            resolution: 1,
+            exclusivity_ignore_patterns: vec!["*artifact".to_string()],
+            ..Default::default()
+        };
+
+        let results = run_screening(&template, &references, &params, &[exclusivity], None);
+        assert_eq!(results.ignored_exclusivity_names, vec!["Vector artifact"]);
+        assert_eq!(
+            results.params.exclusivity_ignore_patterns,
+            vec!["*artifact"]
+        );
+        assert_eq!(results.exclusivity_sequence_count, Some(2));
+        // The ignored hit is kept, but not in the effective minimum
+        let excl = results.results_by_length[&30].positions[0]
+            .exclusivity
+            .as_ref()
+            .unwrap();
+        assert_eq!(excl.total_sequences, 2);
+        assert_eq!(excl.min_mismatches, Some(0));
+        assert_eq!(excl.ignored_matches(), 1);
+        assert_eq!(excl.mismatch_histogram[0].ignored_count, 1);
+        // ... nor in the per-taxon minimums
+        assert_eq!(results.exclusivity_taxa, vec!["Vector artifact", "Excl2"]);
+        assert_eq!(excl.taxon_min_mismatches[0], None);
+        assert!(excl.taxon_min_mismatches[1].is_some_and(|m| m > 0));
+        assert!(effective_min_mismatches(excl, 0).is_some_and(|m| m > 0));
+        assert!(
+            ExclusivityFilter {
+                min_mismatches_exceeding: 0,
+                ignore_count: 0,
+            }
+            .passes(&results.results_by_length[&30].positions[0])
+        );
+    }
+
+    #[test]
+    fn test_exclusivity_only_screening() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let exclusivity = exclusivity_group(ReferenceData {
+            names: vec!["Excl1".to_string()],
+            sequences: vec!["TATGGTACGTGATGTTCTACAAATGGGCTGT".to_string()],
+        });
+        let params = AnalysisParams {
+            min_oligo_length: 20,
+            max_oligo_length: 20,
+            resolution: 5,
+            exclusivity_only: true,
+            ..Default::default()
+        };
+
+        let results = run_screening(&template, &references, &params, &[exclusivity], None);
+        assert_eq!(results.total_sequences, 0);
+        let positions = &results.results_by_length[&20].positions;
+        assert_eq!(positions.len(), 3);
+        for pr in positions {
+            // No conservation analysis, but every window has exclusivity metrics
+            assert!(pr.analysis.variants.is_empty());
+            assert!(!pr.analysis.skipped);
+            assert_eq!(pr.analysis.total_sequences, 0);
+            assert_eq!(pr.exclusivity.as_ref().unwrap().total_sequences, 1);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_screening() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let params = AnalysisParams {
+            min_oligo_length: 18,
+            max_oligo_length: 20,
+            ..Default::default()
+        };
+        let pool = ScreeningPool::new(1);
+
+        let cancel = CancelToken::new();
+        let results = run_screening_in(
+            &pool,
+            &template,
+            &references,
+            &params,
+            &[],
+            None,
+            Some(&cancel),
+        );
+        assert!(!results.cancelled);
+        assert_eq!(results.results_by_length.len(), 3);
+
+        // Cancelled before the start: nothing is analyzed
+        cancel.cancel();
+        let results = run_screening_in(
+            &pool,
+            &template,
+            &references,
+            &params,
+            &[],
+            None,
+            Some(&cancel),
+        );
+        assert!(results.cancelled);
+        assert!(results.results_by_length.is_empty());
+    }
+
+    #[test]
+    fn test_resumed_screening() {
+        let template = TemplateData {
+            name: "Template".to_string(),
+            sequence: "TATGGTACGTCATGTTCTAGAAATGGGCTGT".to_string(),
+            rna: false,
+        };
+        let references = ReferenceData {
+            names: vec!["Ref1".to_string()],
+            sequences: vec![template.sequence.clone()],
+        };
+        let params = AnalysisParams {
+            min_oligo_length: 10,
+            max_oligo_length: 12,
+            resolution: 5,
+            refine_max_variants: Some(1),
+            ..Default::default()
+        };
+        let pool = ScreeningPool::new(1);
+        let full = run_screening_in(&pool, &template, &references, &params, &[], None, None);
+
+        // Paused partway through length 11, after a coarse and a fine window
+        let mut checkpoint = full.clone();
+        checkpoint.cancelled = true;
+        checkpoint.results_by_length.remove(&12);
+        let partial = checkpoint.results_by_length.get_mut(&11).unwrap();
+        partial.positions.retain(|pr| [0, 3].contains(&pr.position));
+        // Kept windows are not screened again
+        partial.positions[0].variants_needed = 7;
+
+        let (length_tx, length_rx) = std::sync::mpsc::channel();
+        let resumed = resume_screening_in(
+            &pool,
+            &template,
+            &references,
+            &params,
+            None,
+            None,
+            None,
+            Some(&checkpoint),
+            Some(length_tx),
+        );
+        assert!(!resumed.cancelled);
+        // Length 10 was complete in the checkpoint, so only 11 and 12 are sent
+        let sent: Vec<u32> = length_rx.iter().map(|r| r.oligo_length).collect();
+        assert_eq!(sent, vec![11, 12]);
+        for length in 10..=12 {
+            let summary = |results: &ScreeningResults| {
+                results.results_by_length[&length]
+                    .positions
+                    .iter()
+                    .map(|pr| (pr.position, pr.variants_needed))
+                    .collect::<Vec<_>>()
+            };
+            let mut expected = summary(&full);
+            if length == 11 {
+                expected[0].1 = 7;
+            }
+            assert_eq!(summary(&resumed), expected, "length {}", length);
+        }
     }
 }
@@ -0,0 +1,233 @@
+//! SQLite results database
+//!
+//! An alternative to one results file per job: jobs auto-saved to an output
+//! folder go into a single database there. Each job's metadata sits in plain
+//! columns, so the whole collection can be listed without reading any
+//! results; the results themselves are stored in the compressed binary
+//! format and read only when a job is opened.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, params};
+
+use super::compact::{results_from_bytes, write_results_binary};
+use super::types::ScreeningResults;
+
+/// File name of the results database in an output folder
+pub const RESULTS_DATABASE_FILE: &str = "oligoscreen_results.sqlite";
+
+/// How long a write waits for another one (a concurrent auto-save) to finish
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Metadata of a job in the results database
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredJob {
+    pub id: i64,
+    /// Name the job was saved under
+    pub name: String,
+    pub template_length: usize,
+    pub reference_count: usize,
+    pub min_oligo_length: u32,
+    pub max_oligo_length: u32,
+    /// Method description
+    pub method: String,
+    pub differential: bool,
+    pub cancelled: bool,
+    /// Seconds since the Unix epoch (UTC)
+    pub saved_at: u64,
+    pub tags: Vec<String>,
+    /// Size of the stored results in bytes
+    pub size: usize,
+}
+
+/// A results database file, created on first use
+pub struct ResultsDatabase {
+    conn: Connection,
+}
+
+impl ResultsDatabase {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                template_length INTEGER NOT NULL,
+                reference_count INTEGER NOT NULL,
+                min_oligo_length INTEGER NOT NULL,
+                max_oligo_length INTEGER NOT NULL,
+                method TEXT NOT NULL,
+                differential INTEGER NOT NULL,
+                cancelled INTEGER NOT NULL,
+                saved_at INTEGER NOT NULL,
+                tags TEXT NOT NULL DEFAULT '',
+                results BLOB NOT NULL
+            );",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Self { conn })
+    }
+
+    /// Store a job's results under `name`, returning its id.
+    pub fn insert(&self, name: &str, results: &ScreeningResults) -> Result<i64, String> {
+        let mut blob = Vec::new();
+        write_results_binary(results, &mut blob, |_| {})?;
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let analysis = &results.params;
+        self.conn
+            .execute(
+                "INSERT INTO jobs (name, template_length, reference_count, min_oligo_length,
+                    max_oligo_length, method, differential, cancelled, saved_at, results)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    name,
+                    results.template_length as i64,
+                    results.total_sequences as i64,
+                    analysis.min_oligo_length,
+                    analysis.max_oligo_length,
+                    analysis.method.description(),
+                    results.differential_enabled,
+                    results.cancelled,
+                    saved_at as i64,
+                    blob,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Metadata of every stored job, oldest first.
+    pub fn jobs(&self) -> Result<Vec<StoredJob>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT id, name, template_length, reference_count, min_oligo_length,
+                    max_oligo_length, method, differential, cancelled, saved_at, tags,
+                    length(results)
+                 FROM jobs ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(StoredJob {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    template_length: row.get::<_, i64>(2)? as usize,
+                    reference_count: row.get::<_, i64>(3)? as usize,
+                    min_oligo_length: row.get(4)?,
+                    max_oligo_length: row.get(5)?,
+                    method: row.get(6)?,
+                    differential: row.get(7)?,
+                    cancelled: row.get(8)?,
+                    saved_at: row.get::<_, i64>(9)? as u64,
+                    tags: parse_tags(&row.get::<_, String>(10)?),
+                    size: row.get::<_, i64>(11)? as usize,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    /// Read the results of a stored job.
+    pub fn load(&self, id: i64) -> Result<ScreeningResults, String> {
+        let blob: Vec<u8> = self
+            .conn
+            .query_row("SELECT results FROM jobs WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .map_err(|e| e.to_string())?;
+        results_from_bytes(&blob)
+    }
+
+    pub fn delete(&self, id: i64) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM jobs WHERE id = ?1", [id])
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn set_tags(&self, id: i64, tags: &[String]) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE jobs SET tags = ?1 WHERE id = ?2",
+                params![tags.join(", "), id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Tags from comma-separated text, trimmed, with empty ones dropped
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+    use crate::analysis::screener::run_screening;
+    use crate::analysis::types::AnalysisParams;
+
+    #[test]
+    fn test_results_database() {
+        let template = "TATGGTACGTCATGTTCTAG";
+        let results = run_screening(
+            &TemplateData {
+                name: "T".to_string(),
+                sequence: template.to_string(),
+                rna: false,
+            },
+            &ReferenceData {
+                names: vec!["R1".to_string()],
+                sequences: vec![template.to_string()],
+            },
+            &AnalysisParams {
+                min_oligo_length: 10,
+                max_oligo_length: 11,
+                resolution: 5,
+                ..Default::default()
+            },
+            &[],
+            None,
+        );
+
+        let db = ResultsDatabase::open(Path::new(":memory:")).unwrap();
+        let first = db.insert("run.fasta #1", &results).unwrap();
+        let second = db.insert("run.fasta #2", &results).unwrap();
+
+        let jobs = db.jobs().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].id, first);
+        assert_eq!(jobs[0].name, "run.fasta #1");
+        assert_eq!(
+            (jobs[0].min_oligo_length, jobs[0].max_oligo_length),
+            (10, 11)
+        );
+        assert_eq!((jobs[0].template_length, jobs[0].reference_count), (20, 1));
+        assert!(jobs[0].tags.is_empty() && jobs[0].size > 0);
+
+        let loaded = db.load(first).unwrap();
+        assert_eq!(loaded.template_sequence, template);
+        assert_eq!(
+            loaded.results_by_length[&10].positions.len(),
+            results.results_by_length[&10].positions.len()
+        );
+
+        db.set_tags(second, &parse_tags(" final, ,PCR ")).unwrap();
+        db.delete(first).unwrap();
+        let jobs = db.jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].tags, vec!["final", "PCR"]);
+        assert!(db.load(first).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Striped Smith-Waterman score kernel
+//!
+//! Farrar's striped layout splits the oligo into `LANES` interleaved
+//! segments, so each column of the DP matrix is computed `LANES` cells at a
+//! time on fixed-size arrays that the compiler turns into SIMD instructions.
+//! Only the best local score and the reference position where it ends are
+//! computed; the traceback is left to the regular aligner on the short
+//! stretch of reference that can hold the hit.
+
+use bio::alignment::pairwise::MatchFunc;
+
+use super::pairwise::IupacMatch;
+
+/// Cells per vector: eight 16-bit scores fill a 128-bit register
+const LANES: usize = 8;
+
+type Lanes = [i16; LANES];
+
+/// "Minus infinity", far enough above `i16::MIN` that subtracting
+/// penalties from it cannot wrap
+const NEG: i16 = i16::MIN / 2;
+
+fn add(a: Lanes, b: Lanes) -> Lanes {
+    std::array::from_fn(|k| a[k].saturating_add(b[k]).max(NEG))
+}
+
+fn sub(a: Lanes, penalty: i16) -> Lanes {
+    std::array::from_fn(|k| a[k].saturating_sub(penalty).max(NEG))
+}
+
+fn max(a: Lanes, b: Lanes) -> Lanes {
+    std::array::from_fn(|k| a[k].max(b[k]))
+}
+
+/// Each lane takes the value of the lane before it; the first takes `fill`
+fn shift(a: Lanes, fill: i16) -> Lanes {
+    std::array::from_fn(|k| if k == 0 { fill } else { a[k - 1] })
+}
+
+fn any_greater(a: Lanes, b: Lanes) -> bool {
+    a.iter().zip(&b).any(|(x, y)| x > y)
+}
+
+/// Best local alignment score of `query` against `target` and the target
+/// position of its last base, scoring like the bio aligner (a gap of `k`
+/// bases scores `gap_open + k * gap_extend`). Ties keep the earliest end.
+/// None when the scores could overflow 16 bits or the penalties are
+/// positive; the caller then falls back to the full aligner.
+pub fn striped_local_score(
+    query: &[u8],
+    target: &[u8],
+    scoring: &IupacMatch,
+    gap_open: i32,
+    gap_extend: i32,
+) -> Option<(i32, usize)> {
+    let m = query.len();
+    let limit = i16::MAX as i64 / 2;
+    let first_gap = -(gap_open as i64 + gap_extend as i64);
+    let gap_extend = -(gap_extend as i64);
+    if gap_extend < 0
+        || first_gap < gap_extend
+        || first_gap > limit
+        || scoring.match_score as i64 * m as i64 > limit
+        || (scoring.mismatch_score as i64).abs() > limit
+    {
+        return None;
+    }
+    if m == 0 {
+        return Some((0, 0));
+    }
+    let (first_gap, gap_extend) = (first_gap as i16, gap_extend as i16);
+
+    // Query profile per target byte, built when the byte first occurs:
+    // lane k of segment i scores query position k * segments + i
+    let segments = m.div_ceil(LANES);
+    let mut profile = vec![[NEG; LANES]; 256 * segments];
+    let mut profiled = [false; 256];
+
+    let mut h_store = vec![[0i16; LANES]; segments];
+    let mut h_load = vec![[0i16; LANES]; segments];
+    let mut e = vec![[NEG; LANES]; segments];
+    let zero = [0i16; LANES];
+    let (mut best, mut best_end) = (0i16, 0);
+
+    for (j, &b) in target.iter().enumerate() {
+        let byte = b as usize;
+        if !profiled[byte] {
+            for i in 0..segments {
+                profile[byte * segments + i] = std::array::from_fn(|k| {
+                    query
+                        .get(k * segments + i)
+                        .map_or(NEG, |&q| scoring.score(q, b) as i16)
+                });
+            }
+            profiled[byte] = true;
+        }
+        let scores = &profile[byte * segments..(byte + 1) * segments];
+
+        // H of the previous query position in the previous column
+        let mut h = shift(h_store[segments - 1], 0);
+        let mut f = [NEG; LANES];
+        std::mem::swap(&mut h_load, &mut h_store);
+        for i in 0..segments {
+            h = max(max(add(h, scores[i]), e[i]), max(f, zero));
+            h_store[i] = h;
+            let opened = sub(h, first_gap);
+            e[i] = max(sub(e[i], gap_extend), opened);
+            f = max(sub(f, gap_extend), opened);
+            h = h_load[i];
+        }
+
+        // Carry vertical gaps across segment boundaries until they can no
+        // longer raise any cell
+        f = shift(f, NEG);
+        let mut i = 0;
+        while any_greater(f, sub(h_store[i], first_gap)) {
+            h_store[i] = max(h_store[i], f);
+            e[i] = max(e[i], sub(h_store[i], first_gap));
+            f = sub(f, gap_extend);
+            i += 1;
+            if i == segments {
+                i = 0;
+                f = shift(f, NEG);
+            }
+        }
+
+        let column_best = h_store.iter().flatten().copied().max().unwrap_or(0);
+        if column_best > best {
+            best = column_best;
+            best_end = j;
+        }
+    }
+    Some((best as i32, best_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pairwise::create_aligner;
+    use crate::analysis::types::PairwiseParams;
+
+    /// Deterministic pseudo-random bases
+    fn random_bases(state: &mut u64, len: usize, alphabet: &[u8]) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                alphabet[(*state >> 33) as usize % alphabet.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_striped_score_matches_aligner() {
+        let mut state = 7;
+        for params in [
+            PairwiseParams::default(),
+            PairwiseParams {
+                match_score: 1,
+                mismatch_score: -3,
+                gap_open_penalty: -5,
+                gap_extend_penalty: -2,
+                ..Default::default()
+            },
+            PairwiseParams {
+                gap_open_penalty: 0,
+                gap_extend_penalty: -1,
+                ..Default::default()
+            },
+        ] {
+            let scoring = IupacMatch::new(&params);
+            for round in 0..200 {
+                let m = 1 + round % 40;
+                let n = round % 150;
+                let query = random_bases(&mut state, m, b"ACGT");
+                let target = random_bases(&mut state, n, b"ACGTN");
+                let mut aligner = create_aligner(m, n, &params);
+                let expected = aligner.local(&query, &target);
+                let (score, end) = striped_local_score(
+                    &query,
+                    &target,
+                    &scoring,
+                    params.gap_open_penalty,
+                    params.gap_extend_penalty,
+                )
+                .unwrap();
+                assert_eq!(score, expected.score, "query {:?}", query);
+                if score > 0 {
+                    // The reported end is where a best-scoring hit ends
+                    let upto = aligner.local(&query, &target[..=end]);
+                    assert_eq!(upto.score, score);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_striped_score_limits() {
+        let params = PairwiseParams::default();
+        let scoring = IupacMatch::new(&params);
+        assert_eq!(
+            striped_local_score(b"ACGT", b"TTACGTTT", &scoring, -2, -1),
+            Some((8, 5))
+        );
+        assert_eq!(
+            striped_local_score(b"ACGT", b"", &scoring, -2, -1),
+            Some((0, 0))
+        );
+        // Positive gap scores and overflowing oligos are left to the aligner
+        assert_eq!(striped_local_score(b"ACGT", b"ACGT", &scoring, 1, -1), None);
+        let long = vec![b'A'; 10_000];
+        assert_eq!(striped_local_score(&long, b"AAAA", &scoring, -2, -1), None);
+    }
+}
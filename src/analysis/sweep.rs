@@ -0,0 +1,234 @@
+//! Parameter sweeps
+//!
+//! A sweep turns one set of analysis parameters into several by enumerating
+//! the combinations of oligo length ranges, analysis methods, and mismatch
+//! caps, so the same inputs can be queued as one worklist job per
+//! combination.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{AnalysisMethod, AnalysisParams};
+
+/// Most jobs a single sweep may generate
+pub const MAX_SWEEP_JOBS: usize = 200;
+
+/// Values to enumerate, as entered in the Parameter Sweep dialog. Lists are
+/// comma-separated; an empty list keeps the base parameters' value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SweepSpec {
+    /// Oligo length ranges, each `min-max` or a single length (e.g. `18-22, 25`)
+    pub lengths: String,
+    /// Sweep the No Ambiguities method
+    pub no_ambiguities: bool,
+    /// Sweep the Fixed Ambiguities method with each listed maximum
+    pub fixed_ambiguities: bool,
+    pub fixed_values: String,
+    /// Sweep the Incremental method with each listed coverage per step (%),
+    /// keeping the base ambiguity limit
+    pub incremental: bool,
+    pub incremental_values: String,
+    /// Pairwise mismatch caps (e.g. `2, 4, 6`; `2-4` expands to 2, 3, 4)
+    pub max_mismatches: String,
+}
+
+impl Default for SweepSpec {
+    fn default() -> Self {
+        Self {
+            lengths: String::new(),
+            no_ambiguities: false,
+            fixed_ambiguities: false,
+            fixed_values: "1, 2".to_string(),
+            incremental: false,
+            incremental_values: "50".to_string(),
+            max_mismatches: String::new(),
+        }
+    }
+}
+
+impl SweepSpec {
+    /// One parameter set per combination of length range, method, and
+    /// mismatch cap, in that nesting order; the other parameters come from
+    /// `base`.
+    pub fn expand(&self, base: &AnalysisParams) -> Result<Vec<AnalysisParams>, String> {
+        let mut lengths = parse_length_ranges(&self.lengths)?;
+        if lengths.is_empty() {
+            lengths.push((base.min_oligo_length, base.max_oligo_length));
+        }
+
+        let mut methods = Vec::new();
+        if self.no_ambiguities {
+            methods.push(AnalysisMethod::NoAmbiguities);
+        }
+        if self.fixed_ambiguities {
+            let values = parse_value_list(&self.fixed_values, "max ambiguities")?;
+            if values.is_empty() {
+                return Err("List at least one max ambiguities value".to_string());
+            }
+            methods.extend(values.into_iter().map(AnalysisMethod::FixedAmbiguities));
+        }
+        if self.incremental {
+            let values = parse_value_list(&self.incremental_values, "coverage per step")?;
+            if values.is_empty() {
+                return Err("List at least one coverage per step value".to_string());
+            }
+            if let Some(&pct) = values.iter().find(|&&p| p == 0 || p > 100) {
+                return Err(format!(
+                    "Coverage per step {}% is not between 1 and 100",
+                    pct
+                ));
+            }
+            let max_amb = base.method.get_incremental_max_amb();
+            methods.extend(
+                values
+                    .into_iter()
+                    .map(|pct| AnalysisMethod::Incremental(pct, max_amb)),
+            );
+        }
+        if methods.is_empty() {
+            methods.push(base.method);
+        }
+
+        let mut mismatches = parse_value_list(&self.max_mismatches, "max mismatches")?;
+        if mismatches.is_empty() {
+            mismatches.push(base.pairwise.max_mismatches);
+        }
+
+        let count = lengths.len() * methods.len() * mismatches.len();
+        if count > MAX_SWEEP_JOBS {
+            return Err(format!(
+                "The sweep would create {} jobs; at most {} are allowed",
+                count, MAX_SWEEP_JOBS
+            ));
+        }
+
+        let mut sweep = Vec::with_capacity(count);
+        for &(min, max) in &lengths {
+            for &method in &methods {
+                for &max_mismatches in &mismatches {
+                    let mut params = base.clone();
+                    params.min_oligo_length = min;
+                    params.max_oligo_length = max;
+                    params.method = method;
+                    params.pairwise.max_mismatches = max_mismatches;
+                    sweep.push(params);
+                }
+            }
+        }
+        Ok(sweep)
+    }
+}
+
+/// Parse comma-separated oligo length ranges (`min-max` or a single length).
+pub fn parse_length_ranges(text: &str) -> Result<Vec<(u32, u32)>, String> {
+    let mut ranges = Vec::new();
+    for item in list_items(text) {
+        let (min, max) = parse_range(item, "length")?;
+        if min < 3 || max > 100 {
+            return Err(format!("Length range {} is outside 3-100", item));
+        }
+        ranges.push((min, max));
+    }
+    Ok(ranges)
+}
+
+/// Parse comma-separated values, expanding `a-b` to every value in between.
+fn parse_value_list(text: &str, what: &str) -> Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    for item in list_items(text) {
+        let (first, last) = parse_range(item, what)?;
+        if (last - first) as usize >= MAX_SWEEP_JOBS {
+            return Err(format!("Too many {} values in '{}'", what, item));
+        }
+        values.extend(first..=last);
+    }
+    Ok(values)
+}
+
+fn list_items(text: &str) -> impl Iterator<Item = &str> {
+    text.split([',', ';'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Parse `a-b` or `a` (as `a-a`), rejecting reversed ranges.
+fn parse_range(item: &str, what: &str) -> Result<(u32, u32), String> {
+    let number = |s: &str| {
+        s.trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid {} '{}'", what, item))
+    };
+    let (first, last) = match item.split_once('-') {
+        Some((a, b)) => (number(a)?, number(b)?),
+        None => {
+            let n = number(item)?;
+            (n, n)
+        }
+    };
+    if first > last {
+        return Err(format!("Invalid {} range '{}'", what, item));
+    }
+    Ok((first, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep_expand() {
+        let base = AnalysisParams::default();
+
+        // Nothing to sweep: the base parameters alone
+        let sweep = SweepSpec::default().expand(&base).unwrap();
+        assert_eq!(sweep.len(), 1);
+        assert_eq!(sweep[0].method, base.method);
+
+        let spec = SweepSpec {
+            lengths: "18-22; 25".to_string(),
+            no_ambiguities: true,
+            fixed_ambiguities: true,
+            fixed_values: "1-2".to_string(),
+            max_mismatches: "2, 4".to_string(),
+            ..Default::default()
+        };
+        let sweep = spec.expand(&base).unwrap();
+        assert_eq!(sweep.len(), 2 * 3 * 2);
+        assert_eq!(
+            (sweep[0].min_oligo_length, sweep[0].max_oligo_length),
+            (18, 22)
+        );
+        assert_eq!(sweep[0].method, AnalysisMethod::NoAmbiguities);
+        assert_eq!(sweep[1].pairwise.max_mismatches, 4);
+        assert_eq!(sweep[2].method, AnalysisMethod::FixedAmbiguities(1));
+        let last = sweep.last().unwrap();
+        assert_eq!((last.min_oligo_length, last.max_oligo_length), (25, 25));
+        assert_eq!(last.method, AnalysisMethod::FixedAmbiguities(2));
+        assert_eq!(last.pairwise.max_mismatches, 4);
+    }
+
+    #[test]
+    fn test_sweep_rejects_bad_input() {
+        let base = AnalysisParams::default();
+        let with_lengths = |lengths: &str| SweepSpec {
+            lengths: lengths.to_string(),
+            ..Default::default()
+        };
+        assert!(with_lengths("22-18").expand(&base).is_err());
+        assert!(with_lengths("2-8").expand(&base).is_err());
+        assert!(with_lengths("18, x").expand(&base).is_err());
+
+        let incremental = SweepSpec {
+            incremental: true,
+            incremental_values: "0".to_string(),
+            ..Default::default()
+        };
+        assert!(incremental.expand(&base).is_err());
+
+        let huge = SweepSpec {
+            max_mismatches: "0-1000".to_string(),
+            ..Default::default()
+        };
+        assert!(huge.expand(&base).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Taxon labels of exclusivity sequences
+//!
+//! Exclusivity panels usually hold several sequences per off-target organism.
+//! Grouping them by the taxon named in each FASTA header lets a window be
+//! reported as "specific except against P. vivax" rather than by individual
+//! sequence names.
+
+use std::collections::HashMap;
+
+use super::types::ExclusivityResult;
+
+/// Panels with more distinct taxa than this are not summarized per taxon,
+/// since every window would store one minimum per taxon.
+pub const MAX_SUMMARIZED_TAXA: usize = 200;
+
+/// Taxon of a FASTA header: the value of an `[organism=...]`, `[species=...]`
+/// or `OS=` tag when present, else the first "Genus species" pair of words
+/// (words split at whitespace, `_`, and `|`), else the first word.
+pub fn taxon_label(name: &str) -> String {
+    for tag in ["[organism=", "[species="] {
+        if let Some(start) = name.find(tag) {
+            let rest = &name[start + tag.len()..];
+            let value = rest.split(']').next().unwrap_or("").trim();
+            if !value.is_empty() {
+                return value.to_string();
+            }
+        }
+    }
+    if let Some(start) = name.find("OS=") {
+        let rest = &name[start + 3..];
+        // UniProt style: the organism runs until the next KEY= tag
+        let end = rest
+            .find(" OX=")
+            .or_else(|| rest.find(" GN="))
+            .unwrap_or(rest.len());
+        let value = rest[..end].trim();
+        if !value.is_empty() {
+            return value.to_string();
+        }
+    }
+
+    let words: Vec<&str> = name
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '|')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let is_genus = |w: &str| {
+        let mut chars = w.chars();
+        chars.next().is_some_and(|c| c.is_ascii_uppercase())
+            && w.len() > 1
+            && chars.all(|c| c.is_ascii_lowercase())
+    };
+    let is_species = |w: &str| {
+        w.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+            && w.chars()
+                .all(|c| c.is_ascii_lowercase() || c == '.' || c == '-')
+    };
+    for pair in words.windows(2) {
+        if is_genus(pair[0]) && is_species(pair[1]) {
+            return format!("{} {}", pair[0], pair[1]);
+        }
+    }
+    name.split_whitespace().next().unwrap_or(name).to_string()
+}
+
+/// "Plasmodium vivax" -> "P. vivax"; labels that are not a binomial are
+/// returned unchanged.
+pub fn short_taxon(label: &str) -> String {
+    match label.split_once(' ') {
+        Some((genus, species))
+            if !genus.is_empty()
+                && !species.contains(' ')
+                && genus.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            format!("{}. {}", &genus[..1], species)
+        }
+        _ => label.to_string(),
+    }
+}
+
+/// Taxon assignment of an exclusivity panel
+#[derive(Debug, Clone)]
+pub struct TaxonGroups {
+    /// Distinct taxon labels in first-seen order
+    pub labels: Vec<String>,
+    /// Index into `labels` of each sequence
+    pub taxon_of: Vec<usize>,
+}
+
+impl TaxonGroups {
+    pub fn new(names: &[String]) -> Self {
+        let mut labels = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let taxon_of = names
+            .iter()
+            .map(|name| {
+                let label = taxon_label(name);
+                *index.entry(label.clone()).or_insert_with(|| {
+                    labels.push(label);
+                    labels.len() - 1
+                })
+            })
+            .collect();
+        Self { labels, taxon_of }
+    }
+
+    /// Minimum accepted mismatches per taxon (None when no sequence of the
+    /// taxon aligned), from per-sequence counts in panel order.
+    pub fn min_mismatches(&self, mismatch_counts: &[Option<u32>]) -> Vec<Option<u32>> {
+        let mut mins = vec![None; self.labels.len()];
+        for (&taxon, &mm) in self.taxon_of.iter().zip(mismatch_counts) {
+            if let Some(mm) = mm {
+                mins[taxon] = Some(mins[taxon].map_or(mm, |m: u32| m.min(mm)));
+            }
+        }
+        mins
+    }
+}
+
+/// (taxon index, min mismatches) of the taxa with an accepted alignment in a
+/// window, closest first.
+pub fn closest_taxa(excl: &ExclusivityResult) -> Vec<(usize, u32)> {
+    let mut taxa: Vec<(usize, u32)> = excl
+        .taxon_min_mismatches
+        .iter()
+        .enumerate()
+        .filter_map(|(taxon, mm)| mm.map(|mm| (taxon, mm)))
+        .collect();
+    taxa.sort_by_key(|&(taxon, mm)| (mm, taxon));
+    taxa
+}
+
+/// Per-taxon minimums as "P. vivax:1; P. knowlesi:4", closest first.
+pub fn taxon_mismatch_list(labels: &[String], excl: &ExclusivityResult) -> String {
+    closest_taxa(excl)
+        .iter()
+        .filter_map(|&(taxon, mm)| {
+            labels
+                .get(taxon)
+                .map(|l| format!("{}:{}", short_taxon(l), mm))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Report sentence for a window, e.g. "Specific except against P. vivax
+/// (1 mm)". Taxa aligning with at most `near_mismatches` mismatches count as
+/// near matches. Empty when the window has no per-taxon data.
+pub fn specificity_summary(
+    labels: &[String],
+    excl: &ExclusivityResult,
+    near_mismatches: u32,
+) -> String {
+    if excl.taxon_min_mismatches.is_empty() || labels.is_empty() {
+        return String::new();
+    }
+    let near: Vec<String> = closest_taxa(excl)
+        .iter()
+        .filter(|&&(_, mm)| mm <= near_mismatches)
+        .filter_map(|&(taxon, mm)| {
+            labels
+                .get(taxon)
+                .map(|l| format!("{} ({} mm)", short_taxon(l), mm))
+        })
+        .collect();
+    if near.is_empty() {
+        format!("Specific against all {} taxa", labels.len())
+    } else if near.len() == labels.len() {
+        format!("Near matches in every taxon: {}", near.join(", "))
+    } else {
+        format!("Specific except against {}", near.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taxon_label() {
+        assert_eq!(
+            taxon_label("NC_009906.1 Plasmodium vivax Sal-1 chromosome 1"),
+            "Plasmodium vivax"
+        );
+        assert_eq!(
+            taxon_label("Plasmodium_knowlesi_strain_H|chr3"),
+            "Plasmodium knowlesi"
+        );
+        assert_eq!(
+            taxon_label("seq7 [organism=Homo sapiens] [chromosome=X]"),
+            "Homo sapiens"
+        );
+        assert_eq!(
+            taxon_label("sp|P69905|HBA_HUMAN Hemoglobin OS=Homo sapiens OX=9606 GN=HBA1"),
+            "Homo sapiens"
+        );
+        assert_eq!(taxon_label("PV_chr01 assembled"), "PV_chr01");
+        assert_eq!(short_taxon("Plasmodium vivax"), "P. vivax");
+        assert_eq!(short_taxon("PV_chr01"), "PV_chr01");
+    }
+
+    #[test]
+    fn test_specificity_summary() {
+        let names: Vec<String> = [
+            "a1 Plasmodium vivax",
+            "b1 Plasmodium knowlesi",
+            "a2 Plasmodium vivax",
+            "c1 Homo sapiens",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let groups = TaxonGroups::new(&names);
+        assert_eq!(groups.taxon_of, vec![0, 1, 0, 2]);
+
+        let excl = ExclusivityResult {
+            total_sequences: 4,
+            no_match_count: 1,
+            mismatch_histogram: Vec::new(),
+            min_mismatches: Some(1),
+            taxon_min_mismatches: groups.min_mismatches(&[Some(3), Some(5), Some(1), None]),
+            min_three_prime_mismatches: None,
+        };
+        assert_eq!(excl.taxon_min_mismatches, vec![Some(1), Some(5), None]);
+        assert_eq!(
+            specificity_summary(&groups.labels, &excl, 2),
+            "Specific except against P. vivax (1 mm)"
+        );
+        assert_eq!(
+            specificity_summary(&groups.labels, &excl, 0),
+            "Specific against all 3 taxa"
+        );
+        assert_eq!(
+            taxon_mismatch_list(&groups.labels, &excl),
+            "P. vivax:1; P. knowlesi:5"
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! Base-composition and thermodynamic properties of oligo sequences, and
+//! their secondary structures (hairpins and dimers)
+
+use serde::{Deserialize, Serialize};
+
+use super::iupac::reverse_complement;
+
+/// GC content in percent, counting S as G/C and ignoring gaps. Other
+/// ambiguity codes count toward the length only.
+pub fn gc_percent(seq: &str) -> f64 {
+    let mut gc = 0usize;
+    let mut len = 0usize;
+    for c in seq.chars() {
+        match c.to_ascii_uppercase() {
+            'G' | 'C' | 'S' => {
+                gc += 1;
+                len += 1;
+            }
+            '-' | '.' => {}
+            _ => len += 1,
+        }
+    }
+    if len == 0 {
+        0.0
+    } else {
+        gc as f64 / len as f64 * 100.0
+    }
+}
+
+/// Melting temperature in °C of an oligo as reported for its window: the
+/// nearest-neighbor Tm under `conditions`, or the basic estimate where that
+/// is undefined (ambiguity codes, gaps, no cations).
+pub fn oligo_tm(seq: &str, conditions: &TmConditions) -> f64 {
+    nearest_neighbor_tm(seq, conditions).unwrap_or_else(|| basic_tm(seq))
+}
+
+/// Basic melting temperature estimate in °C: the Wallace rule
+/// (2 °C per A/T, 4 °C per G/C) below 14 bases, otherwise
+/// 64.9 + 41 × (GC − 16.4) / N.
+pub fn basic_tm(seq: &str) -> f64 {
+    let len = seq.chars().filter(|&c| c != '-' && c != '.').count();
+    if len == 0 {
+        return 0.0;
+    }
+    let gc = gc_percent(seq) / 100.0 * len as f64;
+    if len < 14 {
+        2.0 * (len as f64 - gc) + 4.0 * gc
+    } else {
+        64.9 + 41.0 * (gc - 16.4) / len as f64
+    }
+}
+
+/// Reaction conditions for the nearest-neighbor melting temperature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TmConditions {
+    /// Monovalent cations (Na+, K+), mM
+    pub na_mm: f64,
+    /// Mg2+, mM
+    pub mg_mm: f64,
+    /// Concentration of each strand, nM
+    pub oligo_nm: f64,
+}
+
+impl Default for TmConditions {
+    fn default() -> Self {
+        Self {
+            na_mm: 50.0,
+            mg_mm: 1.5,
+            oligo_nm: 250.0,
+        }
+    }
+}
+
+/// SantaLucia (1998) unified parameters of the stack 5'-XY-3' / its
+/// complement: (ΔH in kcal/mol, ΔS in cal/(K·mol)). None for non-ACGT bases.
+fn nn_stack(x: u8, y: u8) -> Option<(f64, f64)> {
+    Some(match (x, y) {
+        (b'A', b'A') | (b'T', b'T') => (-7.9, -22.2),
+        (b'A', b'T') => (-7.2, -20.4),
+        (b'T', b'A') => (-7.2, -21.3),
+        (b'C', b'A') | (b'T', b'G') => (-8.5, -22.7),
+        (b'G', b'T') | (b'A', b'C') => (-8.4, -22.4),
+        (b'C', b'T') | (b'A', b'G') => (-7.8, -21.0),
+        (b'G', b'A') | (b'T', b'C') => (-8.2, -22.2),
+        (b'C', b'G') => (-10.6, -27.2),
+        (b'G', b'C') => (-9.8, -24.4),
+        (b'G', b'G') | (b'C', b'C') => (-8.0, -19.9),
+        _ => return None,
+    })
+}
+
+/// Nearest-neighbor melting temperature in °C of an oligo against its
+/// perfect complement (SantaLucia 1998 parameters and salt correction, with
+/// Mg2+ counted as 120·√[Mg2+] of Na+ after von Ahsen et al. 2001). Both
+/// strands are taken to be at `oligo_nm`. None for oligos shorter than 2
+/// bases, with gaps or ambiguity codes, or without cations.
+pub fn nearest_neighbor_tm(seq: &str, conditions: &TmConditions) -> Option<f64> {
+    let seq = seq.to_ascii_uppercase();
+    let bases = seq.as_bytes();
+    if bases.len() < 2 {
+        return None;
+    }
+
+    let (mut dh, mut ds) = (0.0, 0.0);
+    for stack in bases.windows(2) {
+        let (h, s) = nn_stack(stack[0], stack[1])?;
+        dh += h;
+        ds += s;
+    }
+    // Initiation, by terminal pair
+    for end in [bases[0], bases[bases.len() - 1]] {
+        let (h, s) = if matches!(end, b'G' | b'C') {
+            (0.1, -2.8)
+        } else {
+            (2.3, 4.1)
+        };
+        dh += h;
+        ds += s;
+    }
+    // A self-complementary oligo pairs with itself: symmetry correction, and
+    // the whole strand concentration takes part instead of a quarter
+    let strand_factor = if reverse_complement(&seq) == seq {
+        ds -= 1.4;
+        1.0
+    } else {
+        4.0
+    };
+
+    let na_equivalent = (conditions.na_mm + 120.0 * conditions.mg_mm.max(0.0).sqrt()) / 1000.0;
+    if na_equivalent <= 0.0 || conditions.oligo_nm <= 0.0 {
+        return None;
+    }
+    ds += 0.368 * (bases.len() - 1) as f64 * na_equivalent.ln();
+
+    const GAS_CONSTANT: f64 = 1.987;
+    let strands = conditions.oligo_nm * 1e-9 / strand_factor;
+    Some(dh * 1000.0 / (ds + GAS_CONSTANT * strands.ln()) - 273.15)
+}
+
+/// Temperature (K) secondary-structure free energies are given at, 37 °C
+const STRUCTURE_KELVIN: f64 = 310.15;
+
+/// Shortest hairpin loop, in bases
+const MIN_HAIRPIN_LOOP: usize = 3;
+
+/// ΔG (kcal/mol at 37 °C) of the stack 5'-XY-3' / its complement
+fn stack_dg(x: u8, y: u8) -> Option<f64> {
+    nn_stack(x, y).map(|(h, s)| h - STRUCTURE_KELVIN * s / 1000.0)
+}
+
+/// Initiation ΔG (kcal/mol at 37 °C) of a duplex end, by terminal pair
+fn initiation_dg(end: u8) -> f64 {
+    if matches!(end, b'G' | b'C') {
+        0.1 + STRUCTURE_KELVIN * 2.8 / 1000.0
+    } else {
+        2.3 - STRUCTURE_KELVIN * 4.1 / 1000.0
+    }
+}
+
+/// Hairpin loop ΔG (kcal/mol at 37 °C) by loop length (SantaLucia & Hicks
+/// 2004), extrapolated logarithmically beyond 9 bases
+fn hairpin_loop_dg(length: usize) -> f64 {
+    const LOOP_DG: [f64; 7] = [3.5, 3.5, 3.3, 4.0, 4.2, 4.3, 4.5];
+    match length {
+        0..MIN_HAIRPIN_LOOP => f64::INFINITY,
+        3..=9 => LOOP_DG[length - MIN_HAIRPIN_LOOP],
+        _ => 4.6 + 2.44 * 1.987 * STRUCTURE_KELVIN / 1000.0 * (length as f64 / 10.0).ln(),
+    }
+}
+
+/// Uppercase bases with U read as T
+fn structure_bases(seq: &str) -> Vec<u8> {
+    seq.bytes()
+        .map(|b| match b.to_ascii_uppercase() {
+            b'U' => b'T',
+            b => b,
+        })
+        .collect()
+}
+
+/// Watson-Crick pair; ambiguity codes and gaps never pair
+fn pairs(x: u8, y: u8) -> bool {
+    matches!(
+        (x, y),
+        (b'A', b'T') | (b'T', b'A') | (b'C', b'G') | (b'G', b'C')
+    )
+}
+
+/// ΔG (kcal/mol at 37 °C) of the most stable hairpin of an oligo: a
+/// perfectly paired stem of at least 2 bp closing a loop of at least 3
+/// bases, from nearest-neighbor stacks and the loop penalty. 0 when no
+/// hairpin is stable.
+pub fn hairpin_dg(seq: &str) -> f64 {
+    let bases = structure_bases(seq);
+    let n = bases.len();
+    let mut best: f64 = 0.0;
+    for i in 1..n {
+        for j in i + MIN_HAIRPIN_LOOP + 1..n {
+            if !pairs(bases[i], bases[j]) {
+                continue;
+            }
+            // Extend the stem outward from the pair closing the loop
+            let mut stem = 1;
+            while stem <= i && j + stem < n && pairs(bases[i - stem], bases[j + stem]) {
+                stem += 1;
+            }
+            if stem < 2 {
+                continue;
+            }
+            let stacks: Option<f64> = bases[i + 1 - stem..=i]
+                .windows(2)
+                .map(|w| stack_dg(w[0], w[1]))
+                .sum();
+            if let Some(stacks) = stacks {
+                best = best.min(stacks + hairpin_loop_dg(j - i - 1));
+            }
+        }
+    }
+    best
+}
+
+/// ΔG (kcal/mol at 37 °C) of the most stable dimer of two oligos, both
+/// 5'-3': the strongest perfectly paired run of at least 2 bp over every
+/// antiparallel offset, from nearest-neighbor stacks and initiation. Pass
+/// the same oligo twice for its self-dimer. 0 when no dimer is stable.
+pub fn dimer_dg(a: &str, b: &str) -> f64 {
+    let (a, b) = (structure_bases(a), structure_bases(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let run_dg = |run: &[u8]| -> Option<f64> {
+        let stacks: Option<f64> = run.windows(2).map(|w| stack_dg(w[0], w[1])).sum();
+        Some(stacks? + initiation_dg(run[0]) + initiation_dg(run[run.len() - 1]))
+    };
+
+    let mut best: f64 = 0.0;
+    // a[i] pairs with b[diagonal - i]; a[i + 1] then pairs with b[diagonal - i - 1]
+    for diagonal in 0..a.len() + b.len() - 1 {
+        let first = diagonal.saturating_sub(b.len() - 1);
+        let last = diagonal.min(a.len() - 1);
+        let mut run_start = None;
+        for i in first..=last + 1 {
+            let paired = i <= last && pairs(a[i], b[diagonal - i]);
+            match (paired, run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    if i - start >= 2
+                        && let Some(dg) = run_dg(&a[start..i])
+                    {
+                        best = best.min(dg);
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    best
+}
+
+/// Secondary structures of one oligo
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StructureCheck {
+    /// ΔG (kcal/mol at 37 °C) of the most stable hairpin; 0 without one
+    pub hairpin_dg: f64,
+    /// ΔG (kcal/mol at 37 °C) of the most stable self-dimer; 0 without one
+    pub self_dimer_dg: f64,
+}
+
+impl StructureCheck {
+    pub fn of(seq: &str) -> Self {
+        Self {
+            hairpin_dg: hairpin_dg(seq),
+            self_dimer_dg: dimer_dg(seq, seq),
+        }
+    }
+
+    /// The structures at or below their thresholds, e.g. "hairpin ΔG -4.1";
+    /// empty when the oligo is clean
+    pub fn warnings(&self, thresholds: &StructureThresholds) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.hairpin_dg <= thresholds.hairpin_dg {
+            warnings.push(format!("hairpin ΔG {:.1}", self.hairpin_dg));
+        }
+        if self.self_dimer_dg <= thresholds.dimer_dg {
+            warnings.push(format!("self-dimer ΔG {:.1}", self.self_dimer_dg));
+        }
+        warnings
+    }
+}
+
+/// ΔG (kcal/mol at 37 °C) at or below which a structure is flagged
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StructureThresholds {
+    pub hairpin_dg: f64,
+    /// Self- and hetero-dimers
+    pub dimer_dg: f64,
+}
+
+impl Default for StructureThresholds {
+    fn default() -> Self {
+        Self {
+            hairpin_dg: -3.0,
+            dimer_dg: -6.0,
+        }
+    }
+}
+
+/// Structure checks of the variants of one window, which are used as a mix
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantStructures {
+    /// Per variant, in variant order
+    pub checks: Vec<StructureCheck>,
+    /// The most stable hetero-dimer between two of the variants: (first
+    /// index, second index, ΔG); None with fewer than two variants
+    pub hetero_dimer: Option<(usize, usize, f64)>,
+}
+
+/// Hairpins and self-dimers of each variant, and the most stable
+/// hetero-dimer among the first `pooled` variants (those used together)
+pub fn variant_structures<S: AsRef<str>>(variants: &[S], pooled: usize) -> VariantStructures {
+    let checks = variants
+        .iter()
+        .map(|v| StructureCheck::of(v.as_ref()))
+        .collect();
+    let pooled = &variants[..pooled.min(variants.len())];
+    let mut hetero_dimer: Option<(usize, usize, f64)> = None;
+    for (i, a) in pooled.iter().enumerate() {
+        for (j, b) in pooled.iter().enumerate().skip(i + 1) {
+            let dg = dimer_dg(a.as_ref(), b.as_ref());
+            if hetero_dimer.is_none_or(|(_, _, best)| dg < best) {
+                hetero_dimer = Some((i, j, dg));
+            }
+        }
+    }
+    VariantStructures {
+        checks,
+        hetero_dimer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_and_tm() {
+        assert_eq!(gc_percent("ACGT"), 50.0);
+        assert_eq!(gc_percent("AC-GS"), 75.0);
+        assert_eq!(gc_percent(""), 0.0);
+
+        // Wallace rule: 4 A/T + 4 G/C
+        assert_eq!(basic_tm("AACCGGTT"), 24.0);
+        // 20-mer with 10 G/C: 64.9 + 41 × (10 - 16.4) / 20
+        let tm = basic_tm("ACGTACGTACGTACGTACGT");
+        assert!((tm - 51.78).abs() < 1e-9);
+    }
+    #[test]
+    fn test_nearest_neighbor_tm() {
+        let conditions = TmConditions {
+            na_mm: 50.0,
+            mg_mm: 0.0,
+            oligo_nm: 250.0,
+        };
+        let tm = nearest_neighbor_tm("AGCTGACCTGAAGTCTCTGG", &conditions).unwrap();
+        assert!((tm - 55.11).abs() < 0.01, "{}", tm);
+        // Case does not matter; gaps and ambiguity codes have no value
+        assert_eq!(
+            nearest_neighbor_tm("agctgacctgaagtctctgg", &conditions),
+            Some(tm)
+        );
+        assert_eq!(
+            nearest_neighbor_tm("AGCTGACCTGAAGTCTCTGN", &conditions),
+            None
+        );
+        assert_eq!(nearest_neighbor_tm("A", &conditions), None);
+
+        // More salt and more oligo stabilize the duplex
+        let with_mg = TmConditions {
+            mg_mm: 3.0,
+            ..conditions
+        };
+        assert!(nearest_neighbor_tm("AGCTGACCTGAAGTCTCTGG", &with_mg).unwrap() > tm + 5.0);
+        let more_oligo = TmConditions {
+            oligo_nm: 1000.0,
+            ..conditions
+        };
+        assert!(nearest_neighbor_tm("AGCTGACCTGAAGTCTCTGG", &more_oligo).unwrap() > tm);
+
+        // Self-complementary: symmetry correction and full strand concentration
+        let palindrome = nearest_neighbor_tm("GGAATTCC", &conditions).unwrap();
+        assert!((palindrome - 11.50).abs() < 0.01, "{}", palindrome);
+    }
+
+    #[test]
+    fn test_secondary_structures() {
+        // 4 bp G/C stem closing a 4-base loop: stacks GC + CG + GC and the loop
+        let stem = 2.0 * (-9.8 + 0.31015 * 24.4) + (-10.6 + 0.31015 * 27.2);
+        let dg = hairpin_dg("GCGCTTTTGCGC");
+        assert!((dg - (stem + 3.5)).abs() < 1e-9, "{}", dg);
+        // Lowercase and U read like DNA
+        assert_eq!(hairpin_dg("gcgcuuuugcgc"), dg);
+        // Two bases are too few for a loop: the inner C-G opens to close a
+        // 4-base loop on a shorter stem
+        assert!(hairpin_dg("GCGCTTGCGC") > dg);
+        // Nothing pairs in poly-A
+        assert_eq!(hairpin_dg("AAAAAAAAAAAA"), 0.0);
+        assert_eq!(dimer_dg("AAAAAAAAAAAA", "AAAAAAAAAAAA"), 0.0);
+
+        // The GCGC ends pair with each other's: stacks plus two G/C initiations
+        let self_dimer = dimer_dg("GCGCTTTTGCGC", "GCGCTTTTGCGC");
+        assert!((self_dimer - (stem + 2.0 * (0.1 + 0.31015 * 2.8))).abs() < 1e-9);
+
+        // A perfect complement is the most stable dimer, whichever comes first
+        let oligo = "AGCTGACCTGAAGTCTCTGG";
+        let complement = reverse_complement(oligo);
+        let full = dimer_dg(oligo, &complement);
+        assert!(full < -20.0, "{}", full);
+        assert!((dimer_dg(&complement, oligo) - full).abs() < 1e-9);
+        assert!(dimer_dg(oligo, "NNNNNNNNNN") == 0.0);
+
+        let thresholds = StructureThresholds::default();
+        let check = StructureCheck::of("GCGCTTTTGCGC");
+        assert_eq!(check.hairpin_dg, dg);
+        assert_eq!(
+            check.warnings(&thresholds),
+            vec!["hairpin ΔG -3.1".to_string()]
+        );
+        assert!(
+            StructureCheck::of("AAAAAAAAAAAA")
+                .warnings(&thresholds)
+                .is_empty()
+        );
+
+        // Hetero-dimers only among the pooled variants
+        let variants = ["AAAAAAAAAA", oligo, complement.as_str()];
+        let structures = variant_structures(&variants, 3);
+        assert_eq!(structures.checks.len(), 3);
+        assert_eq!(structures.hetero_dimer, Some((1, 2, full)));
+        assert_eq!(variant_structures(&variants, 1).hetero_dimer, None);
+        let pooled_two = variant_structures(&variants, 2).hetero_dimer.unwrap();
+        assert_eq!((pooled_two.0, pooled_two.1), (0, 1));
+    }
+}
@@ -0,0 +1,170 @@
+//! Trimming references to the template region
+//!
+//! Every window aligns its oligo against each full reference, so whole-genome
+//! references make each alignment scan millions of bases. Mapping each
+//! reference to the template once (by shared k-mers on a common diagonal)
+//! and keeping only that region plus flanks shrinks every later alignment
+//! to roughly template length.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::fasta::ReferenceData;
+
+/// k-mer size used to anchor references on the template
+const ANCHOR_K: usize = 16;
+
+/// Outcome of trimming a reference set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrimSummary {
+    /// References shortened to their template region
+    pub trimmed: usize,
+    /// References with no template k-mer anchor, kept whole
+    pub unmapped: usize,
+    pub bases_before: usize,
+    pub bases_after: usize,
+}
+
+/// Positions of each anchor k-mer in the template, for `map_to_template`
+pub fn template_kmers(template: &[u8]) -> HashMap<&[u8], Vec<usize>> {
+    let mut kmers: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    for (pos, kmer) in template.windows(ANCHOR_K).enumerate() {
+        kmers.entry(kmer).or_default().push(pos);
+    }
+    kmers
+}
+
+/// Region of `reference` matching `template`, or None when they share no
+/// anchor k-mer. Anchors are grouped by diagonal (reference position minus
+/// template position); the region spans the template's implied extent on
+/// the best-supported diagonal band, which tolerates indels up to the band
+/// width.
+pub fn map_to_template(
+    template_kmers: &HashMap<&[u8], Vec<usize>>,
+    template_len: usize,
+    reference: &[u8],
+) -> Option<(usize, usize)> {
+    if reference.len() < ANCHOR_K {
+        return None;
+    }
+    let band = (template_len / 10).max(100) as i64;
+
+    let mut diagonals: Vec<i64> = Vec::new();
+    for (ref_pos, kmer) in reference.windows(ANCHOR_K).enumerate() {
+        if let Some(template_positions) = template_kmers.get(kmer) {
+            diagonals.extend(
+                template_positions
+                    .iter()
+                    .map(|&t| ref_pos as i64 - t as i64),
+            );
+        }
+    }
+    if diagonals.is_empty() {
+        return None;
+    }
+    diagonals.sort_unstable();
+
+    // Densest window of diagonals no wider than the band
+    let (mut best_start, mut best_end) = (0, 0);
+    let mut lo = 0;
+    for hi in 0..diagonals.len() {
+        while diagonals[hi] - diagonals[lo] > band {
+            lo += 1;
+        }
+        if hi - lo > best_end - best_start {
+            (best_start, best_end) = (lo, hi);
+        }
+    }
+
+    let start = diagonals[best_start].max(0) as usize;
+    let end = (diagonals[best_end] + template_len as i64).max(0) as usize;
+    Some((start.min(reference.len()), end.min(reference.len())))
+}
+
+/// Trim each reference to its template region plus `flank` bases on either
+/// side. References without an anchor are kept whole so no data is lost.
+pub fn trim_references(
+    template: &str,
+    references: &ReferenceData,
+    flank: usize,
+) -> (ReferenceData, TrimSummary) {
+    let template = template.as_bytes();
+    let template_kmers = template_kmers(template);
+
+    let regions: Vec<Option<(usize, usize)>> = references
+        .sequences
+        .par_iter()
+        .map(|seq| map_to_template(&template_kmers, template.len(), seq.as_bytes()))
+        .collect();
+
+    let mut summary = TrimSummary::default();
+    let mut trimmed = ReferenceData::new();
+    trimmed.names = references.names.clone();
+    for (seq, region) in references.sequences.iter().zip(regions) {
+        summary.bases_before += seq.len();
+        let kept = match region {
+            Some((start, end)) => {
+                let start = start.saturating_sub(flank);
+                let end = (end + flank).min(seq.len());
+                if end - start < seq.len() {
+                    summary.trimmed += 1;
+                }
+                &seq[start..end]
+            }
+            None => {
+                summary.unmapped += 1;
+                seq.as_str()
+            }
+        };
+        summary.bases_after += kept.len();
+        trimmed.sequences.push(kept.to_string());
+    }
+    (trimmed, summary)
+}
+
+/// Expected length of a reference after trimming (template region plus
+/// flanks), for runtime estimates.
+pub fn trimmed_length_estimate(template_len: usize, seq_len: usize, flank: usize) -> usize {
+    seq_len.min(template_len + 2 * flank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::random_seq;
+
+    #[test]
+    fn test_trim_references() {
+        let template = random_seq(300, 1);
+        // Template embedded in a long background with a 5-base deletion inside
+        let mut edited = template.clone();
+        edited.replace_range(150..155, "");
+        let genome = format!("{}{}{}", random_seq(5_000, 2), edited, random_seq(5_000, 3));
+        let unrelated = random_seq(2_000, 4);
+        let references = ReferenceData {
+            names: vec!["genome".to_string(), "unrelated".to_string()],
+            sequences: vec![genome.clone(), unrelated.clone()],
+        };
+
+        let (trimmed, summary) = trim_references(&template, &references, 50);
+        assert_eq!(trimmed.names, references.names);
+        assert_eq!(summary.trimmed, 1);
+        assert_eq!(summary.unmapped, 1);
+        assert_eq!(trimmed.sequences[1], unrelated);
+
+        // Template region plus flanks; the deletion widens the diagonal band by 5
+        let kept = &trimmed.sequences[0];
+        assert!(kept.contains(&edited));
+        assert!(kept.len() <= 300 + 2 * 50 + 5);
+        assert_eq!(summary.bases_before, genome.len() + unrelated.len());
+        assert_eq!(summary.bases_after, kept.len() + unrelated.len());
+    }
+
+    #[test]
+    fn test_trimmed_length_estimate() {
+        assert_eq!(trimmed_length_estimate(300, 10_000, 50), 400);
+        assert_eq!(trimmed_length_estimate(300, 200, 50), 200);
+    }
+}
@@ -3,10 +3,18 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::audit::AuditEntry;
+use super::consensus::BaseConservation;
+use super::filter::effective_min_mismatches;
+use super::iupac::{degeneracy, indel_count};
+use super::thermo::TmConditions;
+use super::trim::TrimSummary;
+
 /// Analysis method selection
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AnalysisMethod {
     /// Find all unique variants without using ambiguity codes
+    #[default]
     NoAmbiguities,
     /// Find minimum variants using up to N ambiguity codes per variant
     FixedAmbiguities(u32),
@@ -15,12 +23,6 @@ pub enum AnalysisMethod {
     Incremental(u32, Option<u32>),
 }
 
-impl Default for AnalysisMethod {
-    fn default() -> Self {
-        Self::NoAmbiguities
-    }
-}
-
 impl AnalysisMethod {
     pub fn description(&self) -> String {
         match self {
@@ -29,23 +31,106 @@ impl AnalysisMethod {
             Self::Incremental(pct, _) => format!("Incremental ({}% coverage per step)", pct),
         }
     }
+
+    /// Max ambiguities of a FixedAmbiguities method (UI default otherwise)
+    pub fn get_fixed_ambiguities(&self) -> u32 {
+        match self {
+            AnalysisMethod::FixedAmbiguities(n) => *n,
+            _ => 1,
+        }
+    }
+
+    /// Target percentage of an Incremental method (UI default otherwise)
+    pub fn get_incremental_pct(&self) -> u32 {
+        match self {
+            AnalysisMethod::Incremental(pct, _) => *pct,
+            _ => 50,
+        }
+    }
+
+    /// Optional ambiguity limit of an Incremental method
+    pub fn get_incremental_max_amb(&self) -> Option<u32> {
+        match self {
+            AnalysisMethod::Incremental(_, max_amb) => *max_amb,
+            _ => None,
+        }
+    }
 }
 
-/// Thread count configuration
+/// Merge cost of each class of ambiguity code. The FixedAmbiguities and
+/// Incremental methods budget and minimize the total cost of a variant's
+/// ambiguous positions; unit costs reduce to counting them.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmbiguityCosts {
+    /// R, Y, S, W, K, M
+    pub two_fold: u32,
+    /// B, D, H, V
+    pub three_fold: u32,
+    /// N
+    pub four_fold: u32,
+}
+
+impl Default for AmbiguityCosts {
+    fn default() -> Self {
+        Self {
+            two_fold: 1,
+            three_fold: 1,
+            four_fold: 1,
+        }
+    }
+}
+
+impl AmbiguityCosts {
+    /// Cost of a position given its base bitmask (0 for a single base)
+    #[inline]
+    pub fn mask_cost(&self, mask: u8) -> usize {
+        match mask.count_ones() {
+            0 | 1 => 0,
+            2 => self.two_fold as usize,
+            3 => self.three_fold as usize,
+            _ => self.four_fold as usize,
+        }
+    }
+
+    pub fn max_cost(&self) -> usize {
+        self.two_fold.max(self.three_fold).max(self.four_fold) as usize
+    }
+}
+
+/// How the FixedAmbiguities method merges sequences into variants
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeStrategy {
+    /// Greedy set cover: repeatedly take the consensus covering the most
+    /// remaining sequences
+    #[default]
+    Greedy,
+    /// Branch-and-bound search for the fewest variants covering every
+    /// sequence, starting from the greedy solution and returning the best
+    /// found once `time_budget_ms` per window is spent
+    Optimal { time_budget_ms: u64 },
+}
+
+impl MergeStrategy {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Greedy => "Greedy".to_string(),
+            Self::Optimal { time_budget_ms } => {
+                format!("Optimal (branch and bound, {} ms per window)", time_budget_ms)
+            }
+        }
+    }
+}
+
+/// Thread count configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ThreadCount {
     /// Use all available CPU cores
+    #[default]
     Auto,
     /// Use a specific number of threads
     Fixed(usize),
 }
 
-impl Default for ThreadCount {
-    fn default() -> Self {
-        Self::Auto
-    }
-}
-
 impl ThreadCount {
     /// Get the actual number of threads to use
     pub fn get_count(&self) -> usize {
@@ -58,6 +143,162 @@ impl ThreadCount {
     }
 }
 
+/// How an alignment is classified as "no match".
+///
+/// Alignments with more inserted plus deleted bases than
+/// `PairwiseParams::max_indels` (so any gapped alignment at the default of 0),
+/// and alignments whose full-length oligo window would run past the end of
+/// the reference, are always no-match. Indel bases an alignment is allowed
+/// count as differences under `MinPercentIdentity`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum NoMatchRule {
+    /// Alignment must cover the whole oligo with at most `max_mismatches` mismatches
+    #[default]
+    MismatchCap,
+    /// Alignment score must be at least this value
+    MinScore(i32),
+    /// The local alignment must cover at least this percentage of the oligo
+    MinAlignedFraction(f64),
+    /// Identity over the full oligo window must be at least this percentage
+    MinPercentIdentity(f64),
+}
+
+impl NoMatchRule {
+    pub fn description(&self, max_mismatches: u32) -> String {
+        match self {
+            Self::MismatchCap => format!("Full-length, <= {} mismatches", max_mismatches),
+            Self::MinScore(s) => format!("Alignment score >= {}", s),
+            Self::MinAlignedFraction(f) => format!("Aligned fraction >= {:.0}%", f),
+            Self::MinPercentIdentity(p) => format!("Identity >= {:.0}%", p),
+        }
+    }
+}
+
+/// How windows over IUPAC ambiguity codes in a consensus template are
+/// screened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemplateAmbiguityPolicy {
+    /// Windows containing a code are skipped
+    #[default]
+    Reject,
+    /// A window is expanded into the concrete oligos it stands for, each is
+    /// aligned, and every sequence keeps its best hit among them
+    Expand,
+}
+
+impl TemplateAmbiguityPolicy {
+    pub const ALL: [Self; 2] = [Self::Reject, Self::Expand];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Reject => "Reject (skip the window)",
+            Self::Expand => "Expand (best of the concrete oligos)",
+        }
+    }
+}
+
+/// How IUPAC ambiguity codes (R, Y, N, ...) in reference and exclusivity
+/// sequences are treated by the alignment and the variant tallies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferenceAmbiguityPolicy {
+    /// A code matches the oligo bases it includes, and the matched window
+    /// reads it as the oligo base
+    #[default]
+    Compatible,
+    /// A code matches the bases it includes, but the window keeps it, so the
+    /// reference's variant must cover every base the code stands for
+    Expand,
+    /// Codes are mismatches (the window keeps them)
+    Mismatch,
+    /// References with a code in the window count as no match there
+    Skip,
+}
+
+impl ReferenceAmbiguityPolicy {
+    pub const ALL: [Self; 4] = [Self::Compatible, Self::Expand, Self::Mismatch, Self::Skip];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Compatible => "Compatible (read as the oligo base)",
+            Self::Expand => "Expand (variant covers all bases)",
+            Self::Mismatch => "Mismatch",
+            Self::Skip => "Skip the reference",
+        }
+    }
+}
+
+/// Which ends of the oligo and the reference an alignment may leave out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlignmentMode {
+    /// Smith-Waterman: the best-scoring stretch of the oligo against any
+    /// stretch of the reference
+    #[default]
+    Local,
+    /// Fit: the whole oligo against any stretch of the reference
+    SemiGlobal,
+    /// Needleman-Wunsch: the whole oligo against the whole reference, for
+    /// references already cut to the oligo site
+    Global,
+}
+
+impl AlignmentMode {
+    pub const ALL: [Self; 3] = [Self::Local, Self::SemiGlobal, Self::Global];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Local => "Local (Smith-Waterman)",
+            Self::SemiGlobal => "Semi-global (fit oligo into reference)",
+            Self::Global => "Global (end to end)",
+        }
+    }
+}
+
+/// Implementation used for local alignments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlignerBackend {
+    /// The bio crate's aligner over the whole reference
+    #[default]
+    Standard,
+    /// A striped SIMD kernel finds the best score and where it ends, and the
+    /// standard aligner traces back over the short stretch holding the hit.
+    /// Semi-global and global alignments always use the standard aligner.
+    Striped,
+}
+
+impl AlignerBackend {
+    pub const ALL: [Self; 2] = [Self::Standard, Self::Striped];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::Striped => "Striped SIMD (local mode)",
+        }
+    }
+}
+
+/// Extra weight of mismatches near the oligo's 3' end, where a primer
+/// mismatch hinders extension most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreePrimeWeighting {
+    /// Bases at the 3' end that are weighted
+    pub bases: u32,
+    /// What a mismatch in those bases counts as (1 = unweighted)
+    pub weight: u32,
+    /// Also weight reference mismatches against the mismatch cap, not only
+    /// exclusivity mismatches
+    pub references: bool,
+}
+
+impl Default for ThreePrimeWeighting {
+    fn default() -> Self {
+        Self {
+            bases: 5,
+            weight: 3,
+            references: false,
+        }
+    }
+}
+
 /// Pairwise alignment parameters
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PairwiseParams {
@@ -66,6 +307,26 @@ pub struct PairwiseParams {
     pub gap_open_penalty: i32,
     pub gap_extend_penalty: i32,
     pub max_mismatches: u32,
+    #[serde(default)]
+    pub no_match_rule: NoMatchRule,
+    /// Also search the reverse strand of each reference, keeping the better
+    /// hit, for references stored in the opposite orientation to the template
+    #[serde(default)]
+    pub both_strands: bool,
+    /// Treatment of IUPAC ambiguity codes in the references
+    #[serde(default)]
+    pub reference_ambiguity: ReferenceAmbiguityPolicy,
+    #[serde(default)]
+    pub alignment_mode: AlignmentMode,
+    #[serde(default)]
+    pub aligner_backend: AlignerBackend,
+    /// Most inserted plus deleted bases an accepted alignment may have; 0
+    /// makes every gapped alignment a no match
+    #[serde(default)]
+    pub max_indels: u32,
+    /// Weight mismatches in the last bases of the oligo more heavily
+    #[serde(default)]
+    pub three_prime: Option<ThreePrimeWeighting>,
 }
 
 impl Default for PairwiseParams {
@@ -76,6 +337,47 @@ impl Default for PairwiseParams {
             gap_open_penalty: -2,
             gap_extend_penalty: -1,
             max_mismatches: 8,
+            no_match_rule: NoMatchRule::MismatchCap,
+            both_strands: false,
+            reference_ambiguity: ReferenceAmbiguityPolicy::Compatible,
+            alignment_mode: AlignmentMode::Local,
+            aligner_backend: AlignerBackend::Standard,
+            max_indels: 0,
+            three_prime: None,
+        }
+    }
+}
+
+/// How often the screener sends progress updates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProgressInterval {
+    /// Send after every N completed positions
+    Positions(usize),
+    /// Send at most once per N milliseconds
+    Millis(u64),
+}
+
+impl Default for ProgressInterval {
+    fn default() -> Self {
+        Self::Positions(10)
+    }
+}
+
+/// Settings of a quick scan: a coarse screen of one oligo length against a
+/// sample of the references, giving an approximate heatmap in minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickScan {
+    /// Approximate number of positions screened (sets the resolution)
+    pub target_positions: usize,
+    /// References kept, spread evenly through the panel
+    pub max_references: usize,
+}
+
+impl Default for QuickScan {
+    fn default() -> Self {
+        Self {
+            target_positions: 300,
+            max_references: 50,
         }
     }
 }
@@ -85,12 +387,77 @@ impl Default for PairwiseParams {
 pub struct AnalysisParams {
     pub method: AnalysisMethod,
     pub pairwise: PairwiseParams,
+    /// Disallow N (any base) in merged variants
     pub exclude_n: bool,
+    /// Treatment of windows over IUPAC codes in the template
+    #[serde(default)]
+    pub template_ambiguity: TemplateAmbiguityPolicy,
+    /// Oligo lengths screened, inclusive
     pub min_oligo_length: u32,
     pub max_oligo_length: u32,
+    /// Step between screened template positions
     pub resolution: u32,
+    /// First template position (0-based) of the screened region; windows
+    /// starting before it are skipped
+    #[serde(default)]
+    pub region_start: Option<usize>,
+    /// End (0-based, exclusive) of the screened region; windows reaching past
+    /// it are skipped
+    #[serde(default)]
+    pub region_end: Option<usize>,
+    /// When set (with `resolution` above 1), the positions around coarse
+    /// windows needing at most this many variants are re-screened at step 1
+    /// in a second pass
+    #[serde(default)]
+    pub refine_max_variants: Option<usize>,
+    /// When set, each window keeps only its this many most frequent variants
+    /// (none for 0, leaving the metrics), to keep large outputs manageable
+    #[serde(default)]
+    pub keep_variants: Option<usize>,
+    /// Template regions (0-based, end exclusive; sorted and merged) whose
+    /// overlapping windows are skipped
+    #[serde(default)]
+    pub masked_regions: Vec<(usize, usize)>,
+    /// Percentage of references the counted variants must cover
     pub coverage_threshold: f64,
     pub thread_count: ThreadCount,
+    /// When set, `resolution` is derived from the template length to analyze
+    /// roughly this many positions per oligo length
+    #[serde(default)]
+    pub auto_resolution_target: Option<usize>,
+    /// Progress reporting granularity (applied at run time, like thread_count)
+    #[serde(default)]
+    pub progress_interval: ProgressInterval,
+    /// When set, each reference is mapped to the template once and trimmed to
+    /// the matching region plus this many flanking bases before screening
+    #[serde(default)]
+    pub reference_trim_flank: Option<usize>,
+    /// Exclusivity sequences whose names match any of these glob patterns
+    /// (`*`, `?`; case-insensitive) are screened, but their hits are left out
+    /// of the effective minimum mismatches
+    #[serde(default)]
+    pub exclusivity_ignore_patterns: Vec<String>,
+    /// Ambiguity code costs for the FixedAmbiguities and Incremental methods,
+    /// whose ambiguity limits are then budgets in cost units
+    #[serde(default)]
+    pub ambiguity_costs: AmbiguityCosts,
+    /// Merging strategy of the FixedAmbiguities method
+    #[serde(default)]
+    pub merge_strategy: MergeStrategy,
+    /// Per-window compute budget in milliseconds; windows over it are
+    /// finished with their best-so-far variants and flagged as truncated
+    #[serde(default)]
+    pub window_time_budget_ms: Option<u64>,
+    /// Skip the reference (conservation) analysis and only compute
+    /// exclusivity metrics per window; references are ignored
+    #[serde(default)]
+    pub exclusivity_only: bool,
+    /// Set on quick-scan jobs, whose results are approximate
+    #[serde(default)]
+    pub quick_scan: Option<QuickScan>,
+    /// Conditions of the per-window nearest-neighbor Tm
+    #[serde(default)]
+    pub tm_conditions: TmConditions,
 }
 
 impl Default for AnalysisParams {
@@ -99,11 +466,27 @@ impl Default for AnalysisParams {
             method: AnalysisMethod::NoAmbiguities,
             pairwise: PairwiseParams::default(),
             exclude_n: true,
+            template_ambiguity: TemplateAmbiguityPolicy::Reject,
             min_oligo_length: 18,
             max_oligo_length: 25,
             resolution: 1,
+            region_start: None,
+            region_end: None,
+            refine_max_variants: None,
+            keep_variants: None,
+            masked_regions: Vec::new(),
             coverage_threshold: 90.0,
             thread_count: ThreadCount::Auto,
+            auto_resolution_target: None,
+            progress_interval: ProgressInterval::default(),
+            reference_trim_flank: None,
+            exclusivity_ignore_patterns: Vec::new(),
+            ambiguity_costs: AmbiguityCosts::default(),
+            merge_strategy: MergeStrategy::Greedy,
+            window_time_budget_ms: None,
+            exclusivity_only: false,
+            quick_scan: None,
+            tm_conditions: TmConditions::default(),
         }
     }
 }
@@ -116,6 +499,73 @@ pub struct Variant {
     pub percentage: f64,
 }
 
+impl Variant {
+    /// Number of distinct concrete sequences this (possibly degenerate)
+    /// variant expands to; synthesis complexity and cost scale with it.
+    pub fn expansion_count(&self) -> u64 {
+        degeneracy(self.sequence.as_bytes())
+    }
+
+    /// Inserted plus deleted bases of a variant in gap notation
+    pub fn indel_count(&self) -> usize {
+        indel_count(&self.sequence)
+    }
+}
+
+/// Summary of the best alignment scores of the matched references in a window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AlignmentScoreStats {
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+}
+
+impl AlignmentScoreStats {
+    /// Returns None for an empty score list.
+    pub fn from_scores(scores: &[i32]) -> Option<Self> {
+        let min = *scores.iter().min()?;
+        let max = *scores.iter().max()?;
+        let mean = scores.iter().map(|&s| s as f64).sum::<f64>() / scores.len() as f64;
+        Some(Self { min, max, mean })
+    }
+}
+
+/// Summary of the percent identity of the matched references in a window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PercentIdentityStats {
+    pub min: f64,
+    pub mean: f64,
+}
+
+impl PercentIdentityStats {
+    /// Returns None for an empty identity list.
+    pub fn from_identities(identities: &[f64]) -> Option<Self> {
+        if identities.is_empty() {
+            return None;
+        }
+        let min = identities.iter().copied().fold(f64::INFINITY, f64::min);
+        let mean = identities.iter().sum::<f64>() / identities.len() as f64;
+        Some(Self { min, mean })
+    }
+}
+
+/// Summary of the mismatches between the template oligo and the matched
+/// references in a window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MismatchStats {
+    pub mean: f64,
+    pub max: usize,
+}
+
+impl MismatchStats {
+    /// Returns None for an empty mismatch list.
+    pub fn from_counts(mismatches: &[usize]) -> Option<Self> {
+        let max = *mismatches.iter().max()?;
+        let mean = mismatches.iter().sum::<usize>() as f64 / mismatches.len() as f64;
+        Some(Self { mean, max })
+    }
+}
+
 /// Result of analyzing a single window position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowAnalysisResult {
@@ -127,6 +577,22 @@ pub struct WindowAnalysisResult {
     pub coverage_at_threshold: f64,
     pub skipped: bool,
     pub skip_reason: Option<String>,
+    /// Alignment scores of the matched references (None if nothing matched)
+    #[serde(default)]
+    pub score_stats: Option<AlignmentScoreStats>,
+    /// Percent identity of the matched references over the full oligo window
+    #[serde(default)]
+    pub identity_stats: Option<PercentIdentityStats>,
+    /// Mismatches of the matched references against the template oligo
+    #[serde(default)]
+    pub mismatch_stats: Option<MismatchStats>,
+    /// Variant merging hit the per-window time budget; the variants are the
+    /// best found by then, with unmerged sequences left as exact variants
+    #[serde(default)]
+    pub truncated: bool,
+    /// Matched references whose alignment has insertions or deletions
+    #[serde(default)]
+    pub indel_matches: usize,
 }
 
 impl Default for WindowAnalysisResult {
@@ -140,6 +606,11 @@ impl Default for WindowAnalysisResult {
             coverage_at_threshold: 0.0,
             skipped: false,
             skip_reason: None,
+            score_stats: None,
+            identity_stats: None,
+            mismatch_stats: None,
+            truncated: false,
+            indel_matches: 0,
         }
     }
 }
@@ -154,11 +625,48 @@ pub struct LengthResult {
 /// Result at a specific template position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PositionResult {
+    /// 0-based start on the template
     pub position: usize,
+    /// Variants needed to reach the coverage threshold
     pub variants_needed: usize,
     pub analysis: WindowAnalysisResult,
     #[serde(default)]
     pub exclusivity: Option<ExclusivityResult>,
+    /// Exclusivity per group, indexed like `ScreeningResults::exclusivity_groups`
+    /// (empty with fewer than two groups)
+    #[serde(default)]
+    pub group_exclusivity: Vec<ExclusivityResult>,
+    /// Nearest-neighbor Tm (°C) of the template oligo under
+    /// `params.tm_conditions`; None in results saved before it was computed
+    #[serde(default)]
+    pub tm: Option<f64>,
+    /// GC content (%) of the template oligo; None in results saved before
+    /// it was computed
+    #[serde(default)]
+    pub gc_percent: Option<f64>,
+}
+
+impl PositionResult {
+    /// Index of the exclusivity group with the closest hit (fewest
+    /// mismatches, the first group on ties), i.e. the one limiting
+    /// specificity; None when no group has a hit or groups were not recorded.
+    pub fn limiting_exclusivity_group(&self) -> Option<usize> {
+        self.group_exclusivity
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| effective_min_mismatches(e, 0).map(|m| (m, i)))
+            .min()
+            .map(|(_, i)| i)
+    }
+
+    /// Concrete sequences the variants needed for the threshold expand to in total.
+    pub fn concrete_sequences_needed(&self) -> u64 {
+        self.analysis
+            .variants
+            .iter()
+            .take(self.variants_needed)
+            .fold(0u64, |acc, v| acc.saturating_add(v.expansion_count()))
+    }
 }
 
 /// Exclusivity analysis result for a single position/length
@@ -170,6 +678,28 @@ pub struct ExclusivityResult {
     pub mismatch_histogram: Vec<MismatchBucket>,
     /// Minimum mismatches across all exclusivity sequences (None = all are no-match)
     pub min_mismatches: Option<u32>,
+    /// Minimum mismatches per taxon, indexed like `ScreeningResults::exclusivity_taxa`
+    /// (None = no sequence of the taxon matched); empty without taxon data
+    #[serde(default)]
+    pub taxon_min_mismatches: Vec<Option<u32>>,
+    /// 3'-anchored mismatches: fewest mismatches within the weighted 3'-end
+    /// bases over the matched exclusivity sequences (None without 3'
+    /// weighting, or when all are no-match). With 3' weighting the other
+    /// counts are weighted.
+    #[serde(default)]
+    pub min_three_prime_mismatches: Option<u32>,
+}
+
+impl ExclusivityResult {
+    /// Matched sequences ignored by name. They count in the histogram and
+    /// `min_mismatches`, but not in `effective_min_mismatches`.
+    pub fn ignored_matches(&self) -> usize {
+        self.mismatch_histogram
+            .iter()
+            .filter(|b| b.mismatches != u32::MAX)
+            .map(|b| b.ignored_count)
+            .sum()
+    }
 }
 
 /// A single bucket in the mismatch histogram
@@ -177,9 +707,45 @@ pub struct ExclusivityResult {
 pub struct MismatchBucket {
     pub mismatches: u32,
     pub count: usize,
+    /// Of `count`, the sequences ignored by name (0 in the no-match bucket)
+    #[serde(default)]
+    pub ignored_count: usize,
     pub example_name: String,
 }
 
+/// Review status of a nominated candidate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NominationStatus {
+    #[default]
+    Proposed,
+    Approved,
+    Rejected,
+}
+
+impl NominationStatus {
+    pub const ALL: [Self; 3] = [Self::Proposed, Self::Approved, Self::Rejected];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Proposed => "Proposed",
+            Self::Approved => "Approved",
+            Self::Rejected => "Rejected",
+        }
+    }
+}
+
+/// A window nominated as a design candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Nomination {
+    pub oligo_length: u32,
+    pub position: usize,
+    /// Template oligo at the window
+    pub sequence: String,
+    pub status: NominationStatus,
+    #[serde(default)]
+    pub note: String,
+}
+
 /// Complete screening results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScreeningResults {
@@ -192,6 +758,43 @@ pub struct ScreeningResults {
     pub differential_enabled: bool,
     #[serde(default)]
     pub exclusivity_sequence_count: Option<usize>,
+    /// Reference names in input order
+    #[serde(default)]
+    pub reference_names: Vec<String>,
+    /// Candidates nominated for the final design list
+    #[serde(default)]
+    pub nominations: Vec<Nomination>,
+    /// Parameter changes and job submissions of the session that produced these results
+    #[serde(default)]
+    pub audit_trail: Vec<AuditEntry>,
+    /// Reference trimming outcome (None when trimming was off)
+    #[serde(default)]
+    pub reference_trim: Option<TrimSummary>,
+    /// Exclusivity sequences matching `params.exclusivity_ignore_patterns`
+    #[serde(default)]
+    pub ignored_exclusivity_names: Vec<String>,
+    /// Set when a summary save cut each window's variant list to its first N
+    /// variants; None when the lists are complete
+    #[serde(default)]
+    pub variants_kept: Option<usize>,
+    /// Taxon labels parsed from the exclusivity headers (see `taxon_label`);
+    /// empty when per-taxon minimums were not recorded
+    #[serde(default)]
+    pub exclusivity_taxa: Vec<String>,
+    /// Names of the exclusivity groups, in the order they were given; empty
+    /// when there were fewer than two
+    #[serde(default)]
+    pub exclusivity_groups: Vec<String>,
+    /// The run was cancelled; lengths and windows not reached are missing
+    #[serde(default)]
+    pub cancelled: bool,
+    /// The template was RNA; sequences are stored with T in place of U
+    #[serde(default)]
+    pub rna: bool,
+    /// Conservation of the references at each template position; empty for
+    /// exclusivity-only runs and results saved before it was recorded
+    #[serde(default)]
+    pub conservation: Vec<BaseConservation>,
 }
 
 impl ScreeningResults {
@@ -211,7 +814,40 @@ impl ScreeningResults {
             results_by_length: HashMap::new(),
             differential_enabled,
             exclusivity_sequence_count,
+            reference_names: Vec::new(),
+            nominations: Vec::new(),
+            audit_trail: Vec::new(),
+            reference_trim: None,
+            ignored_exclusivity_names: Vec::new(),
+            variants_kept: None,
+            exclusivity_taxa: Vec::new(),
+            exclusivity_groups: Vec::new(),
+            cancelled: false,
+            rna: false,
+            conservation: Vec::new(),
+        }
+    }
+
+    /// Nominate a window (template oligo as the sequence). Returns false if it
+    /// is already nominated or lies outside the template.
+    pub fn nominate(&mut self, oligo_length: u32, position: usize) -> bool {
+        let end = position + oligo_length as usize;
+        if end > self.template_sequence.len()
+            || self
+                .nominations
+                .iter()
+                .any(|n| n.oligo_length == oligo_length && n.position == position)
+        {
+            return false;
         }
+        self.nominations.push(Nomination {
+            oligo_length,
+            position,
+            sequence: self.template_sequence[position..end].to_string(),
+            status: NominationStatus::Proposed,
+            note: String::new(),
+        });
+        true
     }
 }
 
@@ -223,5 +859,9 @@ pub struct ProgressUpdate {
     pub total_positions: usize,
     pub lengths_completed: u32,
     pub total_lengths: u32,
+    /// Windows completed so far over all lengths of the job
+    pub windows_completed: usize,
+    /// Windows of the whole job; grows when a refinement pass adds windows
+    pub total_windows: usize,
     pub message: String,
 }
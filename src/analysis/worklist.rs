@@ -0,0 +1,258 @@
+//! Queued worklist kept between sessions
+//!
+//! The state file lists the queued job ids in order; each job, with its input
+//! sequences, sits in its own file in a directory beside it. A job's file is
+//! written when the job is queued or changes, so reordering, removing, or
+//! completing jobs rewrites only the small list. Writes run on a background
+//! thread, in the order they were requested.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::{self, JoinHandle};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Queued jobs with the id the next job gets. Also the format of worklist
+/// manifests written by File → Save Worklist, which embed each job's input
+/// sequences, and of state files written before jobs had their own files.
+#[derive(Serialize, Deserialize)]
+pub struct SavedWorklist<J> {
+    pub next_job_id: u64,
+    pub jobs: Vec<J>,
+}
+
+/// Contents of the state file
+#[derive(Serialize, Deserialize)]
+struct WorklistIndex {
+    next_job_id: u64,
+    job_ids: Vec<u64>,
+}
+
+/// One change of the queue to write
+pub struct WorklistUpdate<J> {
+    pub next_job_id: u64,
+    /// Ids of every queued job, in queue order
+    pub job_ids: Vec<u64>,
+    /// Jobs queued or changed since the last update, by id
+    pub changed: Vec<(u64, J)>,
+}
+
+/// Directory holding the job files of the state file at `path`
+fn job_dir(path: &Path) -> PathBuf {
+    path.with_extension("jobs")
+}
+
+/// File holding job `id` of the state file at `path`
+pub fn worklist_job_path(path: &Path, id: u64) -> PathBuf {
+    job_dir(path).join(format!("{}.json", id))
+}
+
+/// Write `contents` next to `path` first, so a crash mid-write leaves the
+/// previous copy intact.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn remove_if_present(result: std::io::Result<()>) -> Result<(), String> {
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Apply one update to the state file at `path`: write the changed jobs, then
+/// the job list, then remove the files of jobs no longer queued. An empty
+/// queue removes the state file and job directory.
+pub fn write_worklist_update<J: Serialize>(
+    path: &Path,
+    update: &WorklistUpdate<J>,
+) -> Result<(), String> {
+    let dir = job_dir(path);
+    if update.job_ids.is_empty() {
+        remove_if_present(std::fs::remove_file(path))?;
+        return remove_if_present(std::fs::remove_dir_all(&dir));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    for (id, job) in &update.changed {
+        let json = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        write_atomic(&worklist_job_path(path, *id), &json)?;
+    }
+    let index = WorklistIndex {
+        next_job_id: update.next_job_id,
+        job_ids: update.job_ids.clone(),
+    };
+    let json = serde_json::to_vec(&index).map_err(|e| e.to_string())?;
+    write_atomic(path, &json)?;
+
+    let entries = std::fs::read_dir(&dir).map_err(|e| e.to_string())?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let queued = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+            .is_some_and(|id| update.job_ids.contains(&id));
+        if !queued {
+            remove_if_present(std::fs::remove_file(&path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Read the queue saved at `path`, including state files that embed the
+/// jobs.
+pub fn read_worklist<J: DeserializeOwned>(path: &Path) -> Result<SavedWorklist<J>, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let Ok(index) = serde_json::from_str::<WorklistIndex>(&json) else {
+        return serde_json::from_str(&json).map_err(|e| e.to_string());
+    };
+    let jobs = index
+        .job_ids
+        .iter()
+        .map(|&id| {
+            let file = worklist_job_path(path, id);
+            let json =
+                std::fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", file.display(), e))
+        })
+        .collect::<Result<Vec<J>, String>>()?;
+    Ok(SavedWorklist {
+        next_job_id: index.next_job_id,
+        jobs,
+    })
+}
+
+/// Background thread applying worklist updates to one state file in order
+pub struct WorklistWriter<J> {
+    tx: Option<Sender<WorklistUpdate<J>>>,
+    outcomes: Receiver<Result<(), String>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<J: Serialize + Send + 'static> WorklistWriter<J> {
+    pub fn spawn(path: PathBuf) -> Self {
+        let (tx, rx) = channel::<WorklistUpdate<J>>();
+        let (outcome_tx, outcomes) = channel();
+        let handle = thread::spawn(move || {
+            while let Ok(mut update) = rx.recv() {
+                // Only the latest job list matters; changed jobs of skipped
+                // updates are still written
+                while let Ok(next) = rx.try_recv() {
+                    let mut changed = std::mem::take(&mut update.changed);
+                    changed.retain(|(id, _)| !next.changed.iter().any(|(n, _)| n == id));
+                    changed.extend(next.changed);
+                    update = WorklistUpdate { changed, ..next };
+                }
+                let _ = outcome_tx.send(write_worklist_update(&path, &update));
+            }
+        });
+        Self {
+            tx: Some(tx),
+            outcomes,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn send(&self, update: WorklistUpdate<J>) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(update);
+        }
+    }
+
+    /// Outcome of the latest write finished since the last call
+    pub fn poll(&self) -> Option<Result<(), String>> {
+        self.outcomes.try_iter().last()
+    }
+
+    /// Wait for the pending writes, returning the outcome of the last one.
+    pub fn finish(&mut self) -> Option<Result<(), String>> {
+        self.tx = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        self.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::fasta::{ReferenceData, TemplateData};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Job {
+        id: u64,
+        template: TemplateData,
+        references: ReferenceData,
+    }
+
+    fn job(id: u64) -> Job {
+        Job {
+            id,
+            template: TemplateData {
+                name: format!("T{}", id),
+                sequence: "ACGTACGT".to_string(),
+                rna: false,
+            },
+            references: ReferenceData {
+                names: vec!["R1".to_string()],
+                sequences: vec!["ACGTACGTAA".to_string()],
+            },
+        }
+    }
+
+    /// Ids of the read jobs, checking each came back whole
+    fn read_ids(path: &Path) -> Vec<u64> {
+        let saved: SavedWorklist<Job> = read_worklist(path).unwrap();
+        for job in &saved.jobs {
+            assert_eq!(job.template.name, format!("T{}", job.id));
+            assert_eq!(job.references.sequences, vec!["ACGTACGTAA"]);
+        }
+        saved.jobs.iter().map(|j| j.id).collect()
+    }
+
+    fn update(job_ids: &[u64], changed: &[Job]) -> WorklistUpdate<Job> {
+        WorklistUpdate {
+            next_job_id: 10,
+            job_ids: job_ids.to_vec(),
+            changed: changed.iter().map(|j| (j.id, j.clone())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_worklist_round_trip() {
+        let dir = std::env::temp_dir().join(format!("oligoscreen_worklist_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("worklist.json");
+
+        write_worklist_update(&path, &update(&[1, 2], &[job(1), job(2)])).unwrap();
+        // Reordering and removing rewrite only the list
+        write_worklist_update(&path, &update(&[2], &[])).unwrap();
+        let saved: SavedWorklist<Job> = read_worklist(&path).unwrap();
+        assert_eq!(saved.next_job_id, 10);
+        assert_eq!(read_ids(&path), vec![2]);
+        assert!(!worklist_job_path(&path, 1).exists());
+
+        // Writer updates arrive in order
+        let mut writer = WorklistWriter::spawn(path.clone());
+        writer.send(update(&[2, 3], &[job(3)]));
+        writer.send(update(&[3, 2], &[]));
+        assert_eq!(writer.finish(), Some(Ok(())));
+        assert_eq!(read_ids(&path), vec![3, 2]);
+
+        // State files embedding the jobs still load
+        let embedded = SavedWorklist {
+            next_job_id: 4,
+            jobs: vec![job(1)],
+        };
+        std::fs::write(&path, serde_json::to_string(&embedded).unwrap()).unwrap();
+        assert_eq!(read_ids(&path), vec![1]);
+
+        write_worklist_update(&path, &update(&[], &[])).unwrap();
+        assert!(!path.exists() && !job_dir(&path).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
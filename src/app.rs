@@ -1,16 +1,51 @@
 //! Main application state and UI
 
 use eframe::egui;
-use std::sync::mpsc::{channel, Receiver};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
+use std::time::Instant;
 
 use crate::analysis::{
-    parse_reference_fasta, parse_template_fasta, reverse_complement, run_screening,
-    AnalysisMethod, AnalysisParams, ProgressUpdate, ReferenceData, ScreeningResults, TemplateData,
-    ThreadCount,
+    alignment_view, assignment_references, assignments_csv, auto_resolution, batch_summary_csv,
+    build_panel_matrix, calibrate_ns_per_cell, candidates_fasta, closest_taxa, count_ambiguities,
+    default_config_paths, design_pairs, design_triplets, designed_pairs_csv,
+    effective_min_mismatches, estimate_runtime, feature_lanes, features_for_template,
+    filter_results, find_amplicon_candidates, find_nomination_overlaps, format_duration,
+    format_masked_regions, format_timestamp, heatmap_matrix_csv, inspect_references,
+    is_binary_results_path, load_defaults, load_partial_results, load_prepared_panel, merge_regions,
+    metric_track, multiplex_check, name_matches_pattern, nominations_csv, order_fasta,
+    order_sheet_csv, panel_cache_dir, param_changes, parse_annotations, parse_masked_regions,
+    parse_reference_fasta, parse_tags, parse_templates_fasta, per_length_ranges, positions_table,
+    query_candidates, rank_candidates, read_fasta_file, read_reference_fasta, read_worklist,
+    reference_assignments, reference_consensus, remove_partial_results, results_from_bytes,
+    results_to_json, resume_screening_in, reverse_complement, run_templates_in, search_oligo,
+    select_top_candidates, sort_assignments, sort_candidates, specificity_summary, subsample_evenly,
+    summary_results, target_region, tm_matched_sets, trimmed_length_estimate, triplet_assay_sheet,
+    unique_order_name, variant_members, variant_members_csv, variant_structures, worklist_job_path,
+    worklist_state_path, write_indexed_results, write_partial_results, write_results_binary,
+    write_results_json, AlignerBackend, AlignmentMode, AlignmentView, AmpliconCandidate,
+    AmpliconSearchParams, AnalysisMethod, AnalysisParams, AssignmentColumn, AuditEntry, AuditEvent,
+    BINARY_RESULTS_EXTENSION, BaseConservation, BatchJob, CancelToken, CandidateQuery, ColumnKind,
+    CompositeScales, ConsensusMode, ConsensusParams, ConsensusResult, DedupSummary, DesignedPair,
+    DesignedTriplet, ExclusivityFilter, ExclusivityGroup, Feature, FigureSize, HeatmapFigure,
+    INDEXED_RESULTS_EXTENSION, IndexedResults, InputFileRecord, InspectParams, JobComparison,
+    LegendEntry, MAX_BASE_ENTROPY, MAX_TEMPLATE_EXPANSIONS, MergeStrategy, MetricRange,
+    MultiplexCheck, NoMatchRule, NominationStatus, OligoHit, OrderOligo, OrderSheetFormat,
+    PairDesignParams, PanelMatrixRow, PanelReport, PanelScore, PositionResult, ProbeParams,
+    ProgressInterval, ProgressUpdate, QueryHit, QuickScan, RESULTS_DATABASE_FILE, RankedCandidate,
+    RankingColumn, RankingParams, RankingWeights, ReferenceAmbiguityPolicy, ReferenceAssignment,
+    ReferenceData, ReferenceGroups, ReproducibilityRecord, ResultsDatabase, RuntimeEstimate,
+    SavedWorklist, ScreeningPool, ScreeningResults, SequencePanel, StoredJob, StructureCheck,
+    StructureThresholds, SweepSpec, TARGET_FLANK, TableFormat, TargetBand, TemplateAmbiguityPolicy,
+    TemplateData, ThreadCount, ThreePrimeWeighting, TmConditions, TrackFormat, Variant,
+    VariantStructures, ViewerDefaults, WindowMetric, WorklistUpdate, WorklistWriter,
 };
 
-/// Info about an imported exclusivity file (UI-only, not serialized)
+/// Info about an imported exclusivity file (serialized only in sessions)
+#[derive(Serialize, Deserialize)]
 struct ExclusivityFileEntry {
     file_name: String,
     file_content: String,
@@ -24,72 +59,317 @@ pub struct OligoscreenApp {
     // Input tab state - template
     template_file_name: Option<String>,
     template_data: Option<TemplateData>,
+    /// Further templates of a multi-FASTA template file, screened with
+    /// `template_data` as one multi-template job
+    batch_templates: Vec<TemplateData>,
     template_error: Option<String>,
 
     // Input tab state - references
     reference_file_name: Option<String>,
     reference_data: Option<ReferenceData>,
     reference_error: Option<String>,
+    /// Reference file being parsed on a background thread
+    reference_load: Option<ReferenceLoad>,
+    // Reference panel inspection (computed on demand, cleared when inputs change)
+    show_inspect_window: bool,
+    inspect_params: InspectParams,
+    panel_report: Option<PanelReport>,
+    /// Identical-sequence groups of the loaded references, computed on first display
+    reference_dedup: Option<DedupSummary>,
+    inspect_show_all: bool,
+    // Reference consensus (built on a background thread, cleared when inputs change)
+    show_consensus_window: bool,
+    consensus_params: ConsensusParams,
+    consensus: Option<ConsensusResult>,
+    consensus_rx: Option<Receiver<ConsensusResult>>,
 
     // Differential analysis input
     use_differential: bool,
     exclusivity_files: Vec<ExclusivityFileEntry>,
     exclusivity_data: Option<ReferenceData>,
     exclusivity_error: Option<String>,
+    /// Exclusivity name patterns to ignore, one per line
+    exclusivity_ignore_text: String,
 
     // Analysis parameters
     params: AnalysisParams,
     method_selection: MethodSelection,
     thread_selection: ThreadSelection,
     manual_thread_count: usize,
+    progress_by_time: bool,
+    progress_every_positions: usize,
+    progress_every_ms: u64,
 
     // Incremental method options
     incremental_limit_ambiguities: bool,
     incremental_max_ambiguities: u32,
+    resolution_auto: bool,
+    resolution_target_positions: usize,
+    /// Re-screen at step 1 around coarse windows needing at most
+    /// `refine_max_variants` variants
+    refine: bool,
+    refine_max_variants: usize,
+    /// Keep only the `keep_variants` most frequent variants per window
+    prune_variants: bool,
+    keep_variants: usize,
+    trim_references: bool,
+    trim_flank: usize,
+    /// Screen only template positions `region_from..=region_to` (1-based)
+    restrict_region: bool,
+    region_from: usize,
+    region_to: usize,
+    /// Masked template regions, one 1-based `start-end` per line
+    mask_text: String,
+    /// Template position where a drag on the mask strip started
+    mask_drag_start: Option<usize>,
+    mask_load_error: Option<String>,
+    optimal_merging: bool,
+    optimal_merge_budget_ms: u64,
+    limit_window_time: bool,
+    window_time_budget_ms: u64,
 
     // Analysis state
     is_analyzing: bool,
     analysis_progress: Option<ProgressUpdate>,
+    /// When the running job started, for the ETA
+    job_started: Option<Instant>,
+    /// The running job was paused rather than cancelled
+    pause_requested: bool,
     progress_rx: Option<Receiver<ProgressUpdate>>,
-    results_rx: Option<Receiver<ScreeningResults>>,
+    results_rx: Option<Receiver<Vec<ScreeningResults>>>,
+    /// Cancels the running job's screen
+    cancel_token: Option<CancelToken>,
+    /// Keep the windows finished before a cancel as a completed job
+    keep_partial_results: bool,
 
     // Results state
     results: Option<ScreeningResults>,
+    /// Indexed file the shown results were opened from; variant lists are
+    /// read from it per window until Load All Variants
+    results_detail_source: Option<Arc<IndexedResults>>,
+    /// Windows whose variant lists have been read from `results_detail_source`
+    detail_loaded_windows: std::collections::HashSet<(u32, usize)>,
     selected_position: Option<usize>,
     selected_length_for_detail: Option<u32>,
     show_detail_window: bool,
+    /// (length, position) of detail windows pinned open independently
+    pinned_details: Vec<(u32, usize)>,
+
+    // Side-by-side comparison of two windows, as (length, position)
+    show_compare_window: bool,
+    compare_a: Option<(u32, usize)>,
+    compare_b: Option<(u32, usize)>,
+    /// Heatmap cell moved with the arrow keys, as (length, position)
+    heatmap_cursor: Option<(u32, usize)>,
+    /// Ctrl+clicked heatmap cells, in selection order
+    heatmap_selection: Vec<(u32, usize)>,
+    show_cell_comparison_window: bool,
+
+    // Comparison of the selected job with another completed job
+    compare_job_index: Option<usize>,
+    compare_job_mode: JobCompareMode,
+    /// Difference in variants needed at which delta colors saturate
+    compare_delta_saturation: usize,
 
     // Detail window display options
     detail_show_reverse_complement: bool,
     detail_show_codon_spacing: bool,
+    detail_show_rna: bool,
 
     // View state
     current_tab: Tab,
     zoom_level: f32,
+    heatmap_bin_mode: BinAggregate,
+    conservation_track: ConservationTrack,
 
     // Results viewer settings (adjustable without re-running analysis)
     view_coverage_threshold: f64,
+    /// Threshold the displayed variant counts were computed at; the color
+    /// controls below take effect immediately, this one only on Apply
+    applied_coverage_threshold: f64,
     color_green_at: usize,
     color_red_at: usize,
     nomatch_ok_percent: f64,
     nomatch_bad_percent: f64,
+    heatmap_metric: HeatmapMetric,
+    identity_green_at: f64,
+    identity_red_at: f64,
+    mismatch_green_at: f64,
+    mismatch_red_at: f64,
+    /// Stretch the color scale over each oligo length's own value range
+    normalize_per_length: bool,
+    coverage_range: MetricRange,
+    entropy_range: MetricRange,
+    composite_range: MetricRange,
+    gc_band: TargetBand,
+    tm_band: TargetBand,
+    /// Darken cells whose nearest-neighbor Tm is outside `tm_band`
+    tm_darkening: bool,
+
+    // Heatmap image copy: last on-screen viewport and a pending screenshot request
+    heatmap_viewport: Option<egui::Rect>,
+    /// Heatmap scroll offset last frame, for the minimap's viewport box
+    heatmap_scroll_offset: egui::Vec2,
+    /// Heatmap column to scroll to once a zoom change has been laid out
+    heatmap_scroll_to_column: Option<usize>,
+
+    // Annotation track (GFF3/BED features of the template) over the heatmap
+    annotation_file_name: Option<String>,
+    annotations: Vec<Feature>,
+    annotation_error: Option<String>,
+    pending_heatmap_screenshot: bool,
+    heatmap_copy_status: Option<String>,
+    /// Resolution of exported heatmap PNG/SVG files
+    heatmap_figure_size: FigureSize,
 
     // Differential mode display settings
-    differential_mode: bool,
     diff_green_at: u32,
     diff_red_at: u32,
     diff_ignore_count: usize,
 
+    // Exclusivity candidate filter (uses diff_ignore_count for the ignore-best-N adjustment)
+    excl_filter_enabled: bool,
+    excl_filter_threshold: u32,
+
+    // GC filter: windows whose GC% is outside the range are greyed out
+    gc_filter_enabled: bool,
+    gc_filter_min: f64,
+    gc_filter_max: f64,
+
+    // Nominated candidates (stored in the results' `nominations`)
+    show_nominations_window: bool,
+    nominations_approved_only: bool,
+    /// Shortest end overlap flagged between nominated oligos (identical,
+    /// reverse-complement, and contained oligos are always flagged)
+    nomination_min_overlap: usize,
+    /// Widest Tm range (°C) of a Tm-matched set of nominations
+    tm_match_spread: f64,
+    tm_match_min_size: usize,
+
+    // Top candidates table (scales follow the heatmap color settings)
+    show_top_candidates_window: bool,
+    ranking_weights: RankingWeights,
+    ranking_min_score: f64,
+    ranking_limit_variants: bool,
+    ranking_max_variants: usize,
+    ranking_limit_no_match: bool,
+    ranking_max_no_match: f64,
+    ranking_top_n: usize,
+    /// Sort column, and whether its best values come first
+    ranking_sort: (RankingColumn, bool),
+    candidate_table: Option<CandidateTable>,
+    /// ΔG at or below which hairpins and dimers are flagged
+    structure_thresholds: StructureThresholds,
+    /// Structure checks run from the detail window, by (length, position)
+    structure_checks: std::collections::HashMap<(u32, usize), VariantStructures>,
+
+    // Oligos picked for ordering (kept across jobs and saved with the session)
+    show_order_window: bool,
+    order_selection: Vec<OrderOligo>,
+    order_format: OrderSheetFormat,
+    order_scale: usize,
+    order_purification: usize,
+    /// Multiplex check of the selection it was computed for; rerun when the
+    /// selection changes
+    multiplex_check: Option<(Vec<OrderOligo>, MultiplexCheck)>,
+    /// Off-target sites with at most this many mismatches are flagged
+    multiplex_max_mismatches: u32,
+
+    // Per-reference assignment table of one window (detail window → References)
+    show_assignment_window: bool,
+    assignment_table: Option<AssignmentTable>,
+    assignment_rx: Option<Receiver<AssignmentTable>>,
+    assignment_search: String,
+    assignment_sort: (AssignmentColumn, bool),
+    /// Export the variants' reference names once the pending table arrives
+    assignment_export_members: bool,
+
+    // Single alignment viewer: the aligned sequence's title and layout
+    show_alignment_window: bool,
+    alignment_view: Option<(String, AlignmentView)>,
+
+    // Cross-job candidate search (hit job indices refer to completed_jobs)
+    show_query_window: bool,
+    candidate_query: CandidateQuery,
+    query_hits: Option<Vec<QueryHit>>,
+    oligo_search: String,
+    oligo_search_top_variants: usize,
+    oligo_hits: Option<Result<Vec<OligoHit>, String>>,
+
+    // Amplicon-constrained degenerate primer search
+    show_amplicon_window: bool,
+    amplicon_params: AmpliconSearchParams,
+    amplicon_candidates: Option<Vec<AmpliconCandidate>>,
+
+    // Forward/reverse pair design
+    show_pair_design_window: bool,
+    pair_design_params: PairDesignParams,
+    designed_pairs: Option<Vec<DesignedPair>>,
+    /// Design primer–probe–primer triplets instead of pairs
+    pair_design_triplets: bool,
+    probe_params: ProbeParams,
+    designed_triplets: Option<Vec<DesignedTriplet>>,
+
+    // Results database browser
+    show_database_window: bool,
+    database_path: Option<std::path::PathBuf>,
+    /// Jobs listed from `database_path`; reread on Refresh and after changes
+    database_jobs: Option<Result<Vec<StoredJob>, String>>,
+    /// Tag text being edited, by stored job id
+    database_tag_edits: std::collections::HashMap<i64, String>,
+    /// Stored job whose Delete was clicked, awaiting confirmation
+    database_delete_pending: Option<i64>,
+    database_error: Option<String>,
+
+    // Parameter sweep job generator
+    show_sweep_window: bool,
+    sweep_spec: SweepSpec,
+    sweep_error: Option<String>,
+
+    // Inclusivity/exclusivity panel scoring matrix
+    show_panel_matrix_window: bool,
+    panel_matrix_top_n: usize,
+    panel_matrix_show_coverage: bool,
+    panel_matrix: Option<PanelMatrix>,
+    panel_matrix_rx: Option<Receiver<PanelMatrix>>,
+    panel_matrix_error: Option<String>,
+
+    // Default-parameter config files applied at startup
+    config_sources: Vec<std::path::PathBuf>,
+    config_errors: Vec<String>,
+    /// Job whose parameters were last copied into the Analysis tab
+    params_imported_from: Option<String>,
+    /// Id of the queued job whose parameters are being edited in the Analysis tab
+    editing_job: Option<u64>,
+
+    // Audit trail of parameter changes and job submissions (copied into each job)
+    audit_trail: Vec<AuditEntry>,
+    /// Parameters as of the last recorded change
+    audit_params: AnalysisParams,
+
+    // Command palette (Ctrl+P)
+    show_command_palette: bool,
+    palette_query: String,
+    palette_selected: usize,
+
     // Save/Load
     save_error: Option<String>,
     load_error: Option<String>,
 
     // Deferred actions
     pending_save: bool,
+    /// Variants kept per window by Save Summary (0 = metrics only)
+    summary_keep_variants: usize,
     pending_remove_excl: Option<usize>,
 
     // Output folder for auto-save
     output_folder: Option<String>,
+    /// Auto-save new jobs as compressed binary rather than JSON
+    save_binary_results: bool,
+    /// Auto-save new jobs into the output folder's results database
+    store_in_database: bool,
+    /// Settings for the Quick Scan button
+    quick_scan: QuickScan,
 
     // Worklist
     next_job_id: u64,
@@ -97,10 +377,40 @@ pub struct OligoscreenApp {
     completed_jobs: Vec<CompletedJob>,
     worklist_state: WorklistState,
     current_job_index: usize,
+    /// Id of the job whose screening thread is running; its results and
+    /// checkpoint go to this job wherever it sits in the queue
+    running_job_id: Option<u64>,
     selected_completed_job_index: Option<usize>,
     auto_save_error: Option<String>,
+    /// Auto-saves run on background threads so large results don't block the UI
+    auto_save_tx: Sender<AutoSaveUpdate>,
+    auto_save_rx: Receiver<AutoSaveUpdate>,
+    /// Job id -> bytes written, for auto-saves still running
+    auto_saves_running: std::collections::BTreeMap<u64, u64>,
+    /// Exclusivity panel seed index files reported by running jobs, by job id
+    exclusivity_panel_tx: Sender<(u64, std::path::PathBuf)>,
+    exclusivity_panel_rx: Receiver<(u64, std::path::PathBuf)>,
     /// Total jobs at the start of a processing batch (for overall progress bar)
     worklist_total_at_start: usize,
+    /// Thread pool shared by worklist jobs; rebuilt when the thread count changes
+    screening_pool: Option<Arc<ScreeningPool>>,
+    /// Queue changed since it was last written to disk
+    worklist_dirty: bool,
+    /// Jobs restored from the previous session at startup
+    worklist_restored: usize,
+    worklist_persist_error: Option<String>,
+    /// Writes the queue to disk in the background, started on the first change
+    worklist_writer: Option<WorklistWriter<WorklistJob>>,
+    /// Jobs whose current state is on disk; the rest are written with the
+    /// next change of the queue
+    worklist_written: std::collections::HashSet<u64>,
+    /// Error of the last worklist manifest save or load
+    worklist_manifest_error: Option<String>,
+
+    // Exit confirmation for completed jobs that were never saved
+    show_exit_dialog: bool,
+    allow_close: bool,
+    exit_save_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -113,30 +423,215 @@ enum Tab {
 
 /// A single job in the worklist queue.
 /// Captures all inputs and analysis parameters at the time of "Add to Worklist".
+#[derive(Clone, Serialize, Deserialize)]
 struct WorklistJob {
     id: u64,
     // Captured inputs
     template_file_name: String,
     template_data: TemplateData,
+    /// Further templates of a multi-template job, screened after
+    /// `template_data` against the same references
+    #[serde(default)]
+    batch_templates: Vec<TemplateData>,
+    /// On the completed job of one template of a multi-template job, the id
+    /// of that job
+    #[serde(default)]
+    batch_id: Option<u64>,
     reference_file_name: String,
     reference_data: ReferenceData,
     use_differential: bool,
     exclusivity_file_names: Vec<String>,
+    /// Sequence count per exclusivity file, in file order (panels within exclusivity_data)
+    exclusivity_panel_sizes: Vec<usize>,
     exclusivity_data: Option<ReferenceData>,
+    /// Seed index file of the exclusivity panel, recorded when the job first
+    /// runs
+    #[serde(default)]
+    exclusivity_panel: Option<std::path::PathBuf>,
     // Captured params (fully resolved method, thread count applied at run time)
     params: AnalysisParams,
     // Output folder (optional, for auto-save)
     output_folder: Option<String>,
+    /// Auto-save in the compressed binary format
+    #[serde(default)]
+    binary_results: bool,
+    /// Auto-save into the output folder's results database instead of a file
+    #[serde(default)]
+    store_in_database: bool,
     // Summary info for display
     template_length: usize,
     reference_count: usize,
     exclusivity_count: usize,
+    runtime_estimate: RuntimeEstimate,
+    /// Session audit trail up to and including this job's submission
+    audit_trail: Vec<AuditEntry>,
+    /// Windows screened before the job was paused; the job resumes from them
+    #[serde(default)]
+    checkpoint: Option<ScreeningResults>,
+}
+
+impl WorklistJob {
+    /// Completed jobs for the results of a multi-template job, one per
+    /// template under ids from `next_id`, each holding its own template
+    fn split_batch(mut self, next_id: &mut u64) -> Vec<WorklistJob> {
+        let batch_templates = std::mem::take(&mut self.batch_templates);
+        std::iter::once(self.template_data.clone())
+            .chain(batch_templates)
+            .map(|template| {
+                let job = WorklistJob {
+                    id: *next_id,
+                    template_file_name: format!("{} [{}]", self.template_file_name, template.name),
+                    template_length: template.sequence.len(),
+                    template_data: template,
+                    batch_id: Some(self.id),
+                    ..self.clone()
+                };
+                *next_id += 1;
+                job
+            })
+            .collect()
+    }
+
+    /// The exclusivity panel split back into one named group per file
+    fn exclusivity_groups(&self) -> Vec<ExclusivityGroup> {
+        let Some(ref excl) = self.exclusivity_data else {
+            return Vec::new();
+        };
+        let mut offset = 0;
+        self.exclusivity_file_names
+            .iter()
+            .zip(&self.exclusivity_panel_sizes)
+            .map(|(name, &size)| {
+                let end = (offset + size).min(excl.len());
+                let data = ReferenceData {
+                    names: excl.names[offset..end].to_vec(),
+                    sequences: excl.sequences[offset..end].to_vec(),
+                };
+                offset = end;
+                ExclusivityGroup {
+                    name: name.clone(),
+                    data,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Rows of the Top Candidates table and the settings they were computed
+/// with; recomputed when the settings change.
+struct CandidateTable {
+    params: RankingParams,
+    filter: Option<ExclusivityFilter>,
+    top_n: usize,
+    sort: (RankingColumn, bool),
+    /// Windows passing the cut-offs
+    ranked: usize,
+    /// The top `top_n` by score, in `sort` order
+    rows: Vec<RankedCandidate>,
+    /// Hairpin and self-dimer check of each row's template oligo
+    structures: Vec<StructureCheck>,
 }
 
 /// A completed job with its results.
+#[derive(Serialize, Deserialize)]
 struct CompletedJob {
     job: WorklistJob,
     results: ScreeningResults,
+    /// Results were written to disk (manual save, auto-save, or loaded from a file)
+    saved: bool,
+    /// Indexed file holding the variant lists `results` was opened without
+    #[serde(skip)]
+    detail_source: Option<Arc<IndexedResults>>,
+}
+
+/// Extension of session files written by File → Save Session
+const SESSION_EXTENSION: &str = "osproj";
+
+/// Hover text of actions that realign the screened references
+const REFERENCES_NOT_LOADED: &str =
+    "The reference sequences of these results are not loaded (open the job that produced them)";
+
+/// The whole application state saved by File → Save Session: loaded
+/// inputs, Analysis tab parameters, queued and completed jobs, and the
+/// results viewer settings.
+#[derive(Serialize, Deserialize)]
+struct SavedSession {
+    template_file_name: Option<String>,
+    template_data: Option<TemplateData>,
+    #[serde(default)]
+    batch_templates: Vec<TemplateData>,
+    reference_file_name: Option<String>,
+    reference_data: Option<ReferenceData>,
+    use_differential: bool,
+    exclusivity_files: Vec<ExclusivityFileEntry>,
+    exclusivity_data: Option<ReferenceData>,
+    /// Analysis tab parameters, with the worklist thread count
+    params: AnalysisParams,
+    output_folder: Option<String>,
+    #[serde(default)]
+    save_binary_results: bool,
+    #[serde(default)]
+    store_in_database: bool,
+    quick_scan: QuickScan,
+    viewer: ViewerDefaults,
+    normalize_per_length: bool,
+    tm_darkening: bool,
+    excl_filter_enabled: bool,
+    gc_filter_enabled: bool,
+    gc_filter_min: f64,
+    gc_filter_max: f64,
+    next_job_id: u64,
+    worklist: Vec<WorklistJob>,
+    completed_jobs: Vec<CompletedJob>,
+    selected_completed_job_index: Option<usize>,
+    audit_trail: Vec<AuditEntry>,
+    #[serde(default)]
+    order_selection: Vec<OrderOligo>,
+}
+
+/// Reference file being read on a background thread
+struct ReferenceLoad {
+    file_name: String,
+    rx: Receiver<ReferenceLoadUpdate>,
+    /// (bytes read, file size)
+    progress: (u64, u64),
+}
+
+/// Message from a background reference-loading thread
+enum ReferenceLoadUpdate {
+    Progress { read: u64, total: u64 },
+    Finished(Result<ReferenceData, String>),
+}
+
+/// Message from a background auto-save thread
+enum AutoSaveUpdate {
+    Progress {
+        job_id: u64,
+        bytes: u64,
+    },
+    Finished {
+        job_id: u64,
+        result: Result<(), String>,
+    },
+    /// A finished oligo length of a running job could not be written
+    PartialFailed {
+        job_id: u64,
+        error: String,
+    },
+}
+
+/// Which variant each reference of one window went to.
+struct AssignmentTable {
+    length: u32,
+    position: usize,
+    rows: Vec<ReferenceAssignment>,
+}
+
+/// Computed candidates × panels matrix with its column names.
+struct PanelMatrix {
+    panel_names: Vec<String>,
+    panel_is_inclusivity: Vec<bool>,
+    rows: Vec<PanelMatrixRow>,
 }
 
 /// Worklist processing state.
@@ -154,6 +649,392 @@ enum MethodSelection {
     Incremental,
 }
 
+/// Action requested from a detail window's header buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DetailAction {
+    Pin,
+    CompareA,
+    CompareB,
+    ExportMembers,
+    /// Open the per-reference assignment table
+    ShowReferences,
+    /// Show the alignment against the example sequence of this exclusivity
+    /// histogram bucket
+    AlignExclusivity(usize),
+    Nominate,
+    /// Add the variant at this index to the order selection
+    AddToOrder(usize),
+    /// Check the variants for hairpins and dimers
+    CheckStructure,
+}
+
+/// An action reachable from the command palette (Ctrl+P).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    LoadTemplate,
+    LoadReferences,
+    InspectReferences,
+    BuildConsensus,
+    AddExclusivityFile,
+    AddToWorklist,
+    QuickScan,
+    ParameterSweep,
+    SelectOutputFolder,
+    ProcessWorklist,
+    StopAfterCurrent,
+    CancelCurrentJob,
+    OpenSession,
+    SaveSession,
+    LoadWorklist,
+    SaveWorklist,
+    LoadResults,
+    SaveResults,
+    SaveSummaryResults,
+    SaveIndexedResults,
+    SaveFilteredResults,
+    ExportMatrixCsv,
+    ExportPositionsTable,
+    ExportTrack,
+    ExportBatchSummary,
+    ExportParameters,
+    UseJobParameters,
+    CopyHeatmapImage,
+    ExportHeatmapImage,
+    OpenNominations,
+    ExportDesignList,
+    OpenTopCandidates,
+    OpenOrderSelection,
+    OpenCrossJobSearch,
+    OpenAmpliconSearch,
+    OpenPairDesign,
+    OpenPanelMatrix,
+    OpenCompare,
+    GoToTab(Tab),
+}
+
+impl Command {
+    const ALL: [Self; 42] = [
+        Self::LoadTemplate,
+        Self::LoadReferences,
+        Self::InspectReferences,
+        Self::BuildConsensus,
+        Self::AddExclusivityFile,
+        Self::AddToWorklist,
+        Self::QuickScan,
+        Self::ParameterSweep,
+        Self::SelectOutputFolder,
+        Self::ProcessWorklist,
+        Self::StopAfterCurrent,
+        Self::CancelCurrentJob,
+        Self::OpenSession,
+        Self::SaveSession,
+        Self::LoadWorklist,
+        Self::SaveWorklist,
+        Self::LoadResults,
+        Self::SaveResults,
+        Self::SaveSummaryResults,
+        Self::SaveIndexedResults,
+        Self::SaveFilteredResults,
+        Self::ExportMatrixCsv,
+        Self::ExportPositionsTable,
+        Self::ExportTrack,
+        Self::ExportBatchSummary,
+        Self::ExportParameters,
+        Self::UseJobParameters,
+        Self::CopyHeatmapImage,
+        Self::ExportHeatmapImage,
+        Self::OpenNominations,
+        Self::ExportDesignList,
+        Self::OpenTopCandidates,
+        Self::OpenOrderSelection,
+        Self::OpenCrossJobSearch,
+        Self::OpenAmpliconSearch,
+        Self::OpenPairDesign,
+        Self::OpenPanelMatrix,
+        Self::OpenCompare,
+        Self::GoToTab(Tab::Input),
+        Self::GoToTab(Tab::Analysis),
+        Self::GoToTab(Tab::Worklist),
+        Self::GoToTab(Tab::Results),
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::LoadTemplate => "Load Template...",
+            Self::LoadReferences => "Load References...",
+            Self::InspectReferences => "Inspect Reference Panel",
+            Self::BuildConsensus => "Reference Consensus...",
+            Self::AddExclusivityFile => "Add Exclusivity File...",
+            Self::AddToWorklist => "Add to Worklist",
+            Self::QuickScan => "Quick Scan",
+            Self::ParameterSweep => "Parameter Sweep...",
+            Self::SelectOutputFolder => "Select Output Folder...",
+            Self::ProcessWorklist => "Process Worklist",
+            Self::StopAfterCurrent => "Stop After Current Job",
+            Self::CancelCurrentJob => "Cancel Running Job",
+            Self::OpenSession => "Open Session...",
+            Self::SaveSession => "Save Session...",
+            Self::LoadWorklist => "Load Worklist...",
+            Self::SaveWorklist => "Save Worklist...",
+            Self::LoadResults => "Load Results from File...",
+            Self::SaveResults => "Save Results...",
+            Self::SaveSummaryResults => "Save Summary Results...",
+            Self::SaveIndexedResults => "Save Indexed Results...",
+            Self::SaveFilteredResults => "Save Filtered Results...",
+            Self::ExportMatrixCsv => "Export Matrix CSV...",
+            Self::ExportPositionsTable => "Export Positions Table (CSV/TSV)...",
+            Self::ExportTrack => "Export Genome Browser Track...",
+            Self::ExportBatchSummary => "Export Batch Summary CSV...",
+            Self::ExportParameters => "Export Parameters...",
+            Self::UseJobParameters => "Use These Parameters",
+            Self::CopyHeatmapImage => "Copy Whole Heatmap Image",
+            Self::ExportHeatmapImage => "Export Heatmap Image (PNG/SVG)...",
+            Self::OpenNominations => "Show Nominations",
+            Self::OpenTopCandidates => "Show Top Candidates",
+            Self::OpenOrderSelection => "Show Order Selection",
+            Self::ExportDesignList => "Export Design List...",
+            Self::OpenCrossJobSearch => "Show Cross-Job Candidate Search",
+            Self::OpenAmpliconSearch => "Show Amplicon Search",
+            Self::OpenPairDesign => "Show Pair Design",
+            Self::OpenPanelMatrix => "Show Panel Matrix",
+            Self::OpenCompare => "Show Compare Window",
+            Self::GoToTab(Tab::Input) => "Go to Input Data",
+            Self::GoToTab(Tab::Analysis) => "Go to Analysis Setup",
+            Self::GoToTab(Tab::Worklist) => "Go to Worklist",
+            Self::GoToTab(Tab::Results) => "Go to Results",
+        }
+    }
+}
+
+/// Per-window value used to color the heatmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeatmapMetric {
+    VariantsNeeded,
+    Coverage,
+    NoMatchPercent,
+    MeanIdentity,
+    MinIdentity,
+    MeanMismatches,
+    GcPercent,
+    MeltingTemp,
+    Entropy,
+    /// Exclusivity min mismatches, darkened by conservation (differential mode)
+    Exclusivity,
+    Composite,
+}
+
+impl HeatmapMetric {
+    const ALL: [Self; 11] = [
+        Self::VariantsNeeded,
+        Self::Coverage,
+        Self::NoMatchPercent,
+        Self::MeanIdentity,
+        Self::MinIdentity,
+        Self::MeanMismatches,
+        Self::GcPercent,
+        Self::MeltingTemp,
+        Self::Entropy,
+        Self::Exclusivity,
+        Self::Composite,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::VariantsNeeded => "Variants needed",
+            Self::Coverage => "Coverage at threshold",
+            Self::NoMatchPercent => "No-match %",
+            Self::MeanIdentity => "Mean % identity",
+            Self::MinIdentity => "Min % identity",
+            Self::MeanMismatches => "Mean mismatches",
+            Self::GcPercent => "GC content",
+            Self::MeltingTemp => "Melting temperature",
+            Self::Entropy => "Variant entropy",
+            Self::Exclusivity => "Exclusivity (differential)",
+            Self::Composite => "Composite score",
+        }
+    }
+
+    /// Whether the metric is computed from the reference alignments, and so
+    /// is unavailable for exclusivity-only results
+    fn needs_references(&self) -> bool {
+        !matches!(self, Self::GcPercent | Self::MeltingTemp | Self::Exclusivity)
+    }
+}
+
+/// Color scale of a heatmap metric without a dedicated color function
+#[derive(Debug, Clone, Copy)]
+enum MetricScale {
+    Range(MetricRange),
+    Band(TargetBand),
+}
+
+impl MetricScale {
+    fn badness(&self, value: f64) -> f64 {
+        match self {
+            Self::Range(range) => range.badness(value),
+            Self::Band(band) => band.badness(value),
+        }
+    }
+
+    /// Legend labels for badness 0, 0.5, and 1.
+    fn legend_labels(&self, unit: &str) -> [String; 3] {
+        match self {
+            Self::Range(r) => [
+                format!("{:.1}{}", r.green_at, unit),
+                format!("{:.1}{}", (r.green_at + r.red_at) / 2.0, unit),
+                format!("{:.1}{}", r.red_at, unit),
+            ],
+            Self::Band(b) => [
+                format!("{:.0}-{:.0}{}", b.min, b.max, unit),
+                format!("±{:.1}{} outside", b.tolerance / 2.0, unit),
+                format!("±{:.1}{} outside", b.tolerance, unit),
+            ],
+        }
+    }
+}
+
+/// How a zoomed-out heatmap bin combines the windows it covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinAggregate {
+    Worst,
+    Mean,
+}
+
+impl BinAggregate {
+    const ALL: [Self; 2] = [Self::Worst, Self::Mean];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Worst => "Worst",
+            Self::Mean => "Mean",
+        }
+    }
+}
+
+/// Per-base conservation drawn above the heatmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConservationTrack {
+    Off,
+    /// Share of references with the template's base
+    Identity,
+    /// Shannon entropy of the bases and deletions
+    Entropy,
+}
+
+impl ConservationTrack {
+    const ALL: [Self; 3] = [Self::Off, Self::Identity, Self::Entropy];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Identity => "Identity",
+            Self::Entropy => "Entropy",
+        }
+    }
+
+    /// Variability at a covered position, 0 (conserved) to 1
+    fn variability(&self, base: &BaseConservation) -> f32 {
+        match self {
+            Self::Off => 0.0,
+            Self::Identity => 1.0 - base.identity,
+            Self::Entropy => base.entropy / MAX_BASE_ENTROPY,
+        }
+    }
+}
+
+/// How the Results tab shows the selected job against a second one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobCompareMode {
+    /// Variants needed by the selected job minus the other's, per window
+    Delta,
+    /// The current metric of both jobs in adjacent rows per oligo length
+    Stacked,
+}
+
+impl JobCompareMode {
+    const ALL: [Self; 2] = [Self::Delta, Self::Stacked];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Delta => "Difference in variants needed",
+            Self::Stacked => "Stacked rows",
+        }
+    }
+}
+
+/// One element of the heatmap legend
+enum LegendItem {
+    Swatch(egui::Color32, String),
+    Text(String),
+    Separator,
+}
+
+/// A heatmap cell before it is turned into a color
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellShade {
+    /// No window at this length and position
+    Missing,
+    /// Skipped window, or no value for the metric
+    NoData,
+    /// Removed by the exclusivity candidate filter
+    Filtered,
+    /// `t`: 0 = green … 1 = red; `darkening`: blend toward dark red (0..=1)
+    Graded { t: f64, darkening: f64 },
+}
+
+impl CellShade {
+    fn color(self) -> egui::Color32 {
+        match self {
+            Self::Missing => egui::Color32::from_rgb(30, 30, 30),
+            Self::NoData => egui::Color32::from_rgb(40, 40, 40),
+            Self::Filtered => FILTERED_CELL_COLOR,
+            Self::Graded { t, darkening } => {
+                blend_toward_dark_red(green_yellow_red_from_t(t), darkening)
+            }
+        }
+    }
+
+    /// Combine the cells of a bin. Gradient and darkening are combined
+    /// separately (both maxima for Worst, both means for Mean); bins without
+    /// graded cells show filtered, then no data, then missing.
+    fn aggregate(shades: impl IntoIterator<Item = Self>, mode: BinAggregate) -> Self {
+        let mut count = 0usize;
+        let (mut t_acc, mut dark_acc) = (0.0f64, 0.0f64);
+        let mut fallback = Self::Missing;
+        for shade in shades {
+            match shade {
+                Self::Graded { t, darkening } => {
+                    count += 1;
+                    match mode {
+                        BinAggregate::Worst => {
+                            t_acc = t_acc.max(t);
+                            dark_acc = dark_acc.max(darkening);
+                        }
+                        BinAggregate::Mean => {
+                            t_acc += t;
+                            dark_acc += darkening;
+                        }
+                    }
+                }
+                Self::Filtered => fallback = Self::Filtered,
+                Self::NoData if fallback == Self::Missing => fallback = Self::NoData,
+                _ => {}
+            }
+        }
+        match (count, mode) {
+            (0, _) => fallback,
+            (_, BinAggregate::Worst) => Self::Graded {
+                t: t_acc,
+                darkening: dark_acc,
+            },
+            (n, BinAggregate::Mean) => Self::Graded {
+                t: t_acc / n as f64,
+                darkening: dark_acc / n as f64,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ThreadSelection {
     Auto,
@@ -165,64 +1046,493 @@ impl Default for OligoscreenApp {
         let available_threads = std::thread::available_parallelism()
             .map(|n| n.get())
             .unwrap_or(1);
+        let (auto_save_tx, auto_save_rx) = channel();
+        let (exclusivity_panel_tx, exclusivity_panel_rx) = channel();
         Self {
             template_file_name: None,
             template_data: None,
+            batch_templates: Vec::new(),
             template_error: None,
             reference_file_name: None,
             reference_data: None,
             reference_error: None,
+            reference_load: None,
+            show_inspect_window: false,
+            inspect_params: InspectParams::default(),
+            panel_report: None,
+            reference_dedup: None,
+            inspect_show_all: false,
+            show_consensus_window: false,
+            consensus_params: ConsensusParams::default(),
+            consensus: None,
+            consensus_rx: None,
             use_differential: false,
             exclusivity_files: Vec::new(),
             exclusivity_data: None,
             exclusivity_error: None,
+            exclusivity_ignore_text: String::new(),
             params: AnalysisParams::default(),
             method_selection: MethodSelection::NoAmbiguities,
             thread_selection: ThreadSelection::Auto,
             manual_thread_count: available_threads,
+            progress_by_time: false,
+            progress_every_positions: 10,
+            progress_every_ms: 200,
             incremental_limit_ambiguities: false,
             incremental_max_ambiguities: 3,
+            resolution_auto: false,
+            resolution_target_positions: 2000,
+            refine: false,
+            refine_max_variants: 2,
+            prune_variants: false,
+            keep_variants: 50,
+            trim_references: false,
+            trim_flank: 100,
+            restrict_region: false,
+            region_from: 1,
+            region_to: 1_000,
+            mask_text: String::new(),
+            mask_drag_start: None,
+            mask_load_error: None,
+            optimal_merging: false,
+            optimal_merge_budget_ms: 200,
+            limit_window_time: false,
+            window_time_budget_ms: 2000,
             is_analyzing: false,
             analysis_progress: None,
+            job_started: None,
+            pause_requested: false,
             progress_rx: None,
             results_rx: None,
+            cancel_token: None,
+            keep_partial_results: true,
             results: None,
+            results_detail_source: None,
+            detail_loaded_windows: std::collections::HashSet::new(),
             selected_position: None,
             selected_length_for_detail: None,
             show_detail_window: false,
+            pinned_details: Vec::new(),
+            show_compare_window: false,
+            compare_a: None,
+            compare_b: None,
+            heatmap_cursor: None,
+            heatmap_selection: Vec::new(),
+            show_cell_comparison_window: false,
+            compare_job_index: None,
+            compare_job_mode: JobCompareMode::Delta,
+            compare_delta_saturation: 3,
             detail_show_reverse_complement: false,
             detail_show_codon_spacing: true,
+            detail_show_rna: false,
             current_tab: Tab::Input,
             zoom_level: 1.0,
+            heatmap_bin_mode: BinAggregate::Worst,
+            conservation_track: ConservationTrack::Identity,
             view_coverage_threshold: 95.0,
+            applied_coverage_threshold: 95.0,
             color_green_at: 1,
             color_red_at: 10,
             nomatch_ok_percent: 5.0,
             nomatch_bad_percent: 50.0,
-            differential_mode: false,
+            heatmap_metric: HeatmapMetric::VariantsNeeded,
+            identity_green_at: 100.0,
+            identity_red_at: 90.0,
+            mismatch_green_at: 0.5,
+            mismatch_red_at: 3.0,
+            normalize_per_length: false,
+            coverage_range: MetricRange {
+                green_at: 100.0,
+                red_at: 80.0,
+            },
+            entropy_range: MetricRange {
+                green_at: 0.0,
+                red_at: 2.0,
+            },
+            composite_range: MetricRange {
+                green_at: 80.0,
+                red_at: 40.0,
+            },
+            gc_band: TargetBand {
+                min: 40.0,
+                max: 60.0,
+                tolerance: 15.0,
+            },
+            tm_band: TargetBand {
+                min: 55.0,
+                max: 65.0,
+                tolerance: 10.0,
+            },
+            tm_darkening: false,
+            heatmap_viewport: None,
+            heatmap_scroll_offset: egui::Vec2::ZERO,
+            heatmap_scroll_to_column: None,
+            annotation_file_name: None,
+            annotations: Vec::new(),
+            annotation_error: None,
+            pending_heatmap_screenshot: false,
+            heatmap_copy_status: None,
+            heatmap_figure_size: FigureSize::default(),
             diff_green_at: 5,
             diff_red_at: 0,
             diff_ignore_count: 0,
+            excl_filter_enabled: false,
+            excl_filter_threshold: 2,
+            gc_filter_enabled: false,
+            gc_filter_min: 40.0,
+            gc_filter_max: 60.0,
+            show_nominations_window: false,
+            nominations_approved_only: true,
+            nomination_min_overlap: 8,
+            tm_match_spread: 2.0,
+            tm_match_min_size: 2,
+            show_top_candidates_window: false,
+            ranking_weights: RankingWeights::default(),
+            ranking_min_score: 0.0,
+            ranking_limit_variants: false,
+            ranking_max_variants: 4,
+            ranking_limit_no_match: false,
+            ranking_max_no_match: 10.0,
+            ranking_top_n: 50,
+            ranking_sort: (RankingColumn::Score, true),
+            candidate_table: None,
+            structure_thresholds: StructureThresholds::default(),
+            structure_checks: std::collections::HashMap::new(),
+            show_order_window: false,
+            order_selection: Vec::new(),
+            order_format: OrderSheetFormat::default(),
+            order_scale: 0,
+            order_purification: 0,
+            multiplex_check: None,
+            multiplex_max_mismatches: 3,
+            show_assignment_window: false,
+            assignment_table: None,
+            assignment_rx: None,
+            assignment_search: String::new(),
+            assignment_sort: (AssignmentColumn::Reference, false),
+            assignment_export_members: false,
+            show_alignment_window: false,
+            alignment_view: None,
+            show_query_window: false,
+            candidate_query: CandidateQuery::default(),
+            query_hits: None,
+            oligo_search: String::new(),
+            oligo_search_top_variants: 5,
+            oligo_hits: None,
+            show_amplicon_window: false,
+            amplicon_params: AmpliconSearchParams::default(),
+            amplicon_candidates: None,
+            show_pair_design_window: false,
+            pair_design_params: PairDesignParams::default(),
+            designed_pairs: None,
+            pair_design_triplets: false,
+            probe_params: ProbeParams::default(),
+            designed_triplets: None,
+            show_database_window: false,
+            database_path: None,
+            database_jobs: None,
+            database_tag_edits: std::collections::HashMap::new(),
+            database_delete_pending: None,
+            database_error: None,
+            show_sweep_window: false,
+            sweep_spec: SweepSpec::default(),
+            sweep_error: None,
+            show_panel_matrix_window: false,
+            panel_matrix_top_n: 20,
+            panel_matrix_show_coverage: false,
+            panel_matrix: None,
+            panel_matrix_rx: None,
+            panel_matrix_error: None,
+            config_sources: Vec::new(),
+            config_errors: Vec::new(),
+            params_imported_from: None,
+            editing_job: None,
+            audit_trail: Vec::new(),
+            audit_params: AnalysisParams::default(),
+            show_command_palette: false,
+            palette_query: String::new(),
+            palette_selected: 0,
             save_error: None,
             load_error: None,
             pending_save: false,
+            summary_keep_variants: 0,
             pending_remove_excl: None,
             output_folder: None,
+            save_binary_results: false,
+            store_in_database: false,
+            quick_scan: QuickScan::default(),
             next_job_id: 1,
             worklist: Vec::new(),
             completed_jobs: Vec::new(),
             worklist_state: WorklistState::Idle,
             current_job_index: 0,
+            running_job_id: None,
             selected_completed_job_index: None,
             auto_save_error: None,
+            auto_save_tx,
+            auto_save_rx,
+            auto_saves_running: std::collections::BTreeMap::new(),
+            exclusivity_panel_tx,
+            exclusivity_panel_rx,
             worklist_total_at_start: 0,
+            screening_pool: None,
+            worklist_dirty: false,
+            worklist_restored: 0,
+            worklist_persist_error: None,
+            worklist_writer: None,
+            worklist_written: std::collections::HashSet::new(),
+            worklist_manifest_error: None,
+            show_exit_dialog: false,
+            allow_close: false,
+            exit_save_error: None,
         }
     }
 }
 
 impl OligoscreenApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::default()
+        let mut app = Self::default();
+        let loaded = load_defaults(&default_config_paths());
+        app.apply_params(loaded.config.analysis);
+        app.apply_viewer_defaults(&loaded.config.viewer);
+        app.audit_params = app.current_params();
+        app.config_sources = loaded.sources;
+        app.config_errors = loaded.errors;
+        app.restore_worklist();
+        app
+    }
+
+    /// Reload jobs queued in a previous session. Completed jobs are not kept;
+    /// their results are saved separately.
+    fn restore_worklist(&mut self) {
+        let Some(path) = worklist_state_path() else {
+            return;
+        };
+        if !path.is_file() {
+            return;
+        }
+        match read_worklist::<WorklistJob>(&path) {
+            Ok(saved) => {
+                let max_id = saved.jobs.iter().map(|j| j.id).max().unwrap_or(0);
+                self.next_job_id = saved.next_job_id.max(max_id + 1);
+                self.worklist_restored = saved.jobs.len();
+                // Files that embed the jobs get them written separately on
+                // the next change
+                self.worklist_written = saved
+                    .jobs
+                    .iter()
+                    .map(|j| j.id)
+                    .filter(|&id| worklist_job_path(&path, id).is_file())
+                    .collect();
+                self.worklist = saved.jobs;
+            }
+            Err(e) => {
+                self.worklist_persist_error =
+                    Some(format!("Could not restore worklist from {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Queue the current worklist for writing on the background writer,
+    /// passing along only jobs not yet on disk in their current state.
+    fn persist_worklist(&mut self) {
+        let Some(path) = worklist_state_path() else {
+            return;
+        };
+        let changed = self
+            .worklist
+            .iter()
+            .filter(|j| !self.worklist_written.contains(&j.id))
+            .map(|j| (j.id, j.clone()))
+            .collect();
+        let job_ids: Vec<u64> = self.worklist.iter().map(|j| j.id).collect();
+        self.worklist_written = job_ids.iter().copied().collect();
+        self.worklist_writer
+            .get_or_insert_with(|| WorklistWriter::spawn(path))
+            .send(WorklistUpdate {
+                next_job_id: self.next_job_id,
+                job_ids,
+                changed,
+            });
+    }
+
+    /// Record the outcome of the latest worklist write.
+    fn show_worklist_write_outcome(&mut self, outcome: Result<(), String>) {
+        let path = worklist_state_path().unwrap_or_default();
+        self.worklist_persist_error = outcome
+            .err()
+            .map(|e| format!("Could not save worklist to {}: {}", path.display(), e));
+    }
+
+    /// Write the queued jobs, with their input sequences and parameters, to a
+    /// manifest file that can be loaded on another machine.
+    fn save_worklist_manifest(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("worklist_manifest.json")
+            .save_file()
+        else {
+            return;
+        };
+        let saved = SavedWorklist {
+            next_job_id: self.next_job_id,
+            jobs: std::mem::take(&mut self.worklist),
+        };
+        let json = serde_json::to_string(&saved);
+        self.worklist = saved.jobs;
+        self.worklist_manifest_error = match json {
+            Ok(json) => std::fs::write(&path, json)
+                .err()
+                .map(|e| format!("Failed to write file: {}", e)),
+            Err(e) => Some(format!("Failed to serialize: {}", e)),
+        };
+    }
+
+    /// Append the jobs of a worklist manifest to the queue, renumbered after
+    /// the existing jobs.
+    fn load_worklist_manifest(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let saved = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read file: {}", e))
+            .and_then(|json| {
+                serde_json::from_str::<SavedWorklist<WorklistJob>>(&json)
+                    .map_err(|e| format!("Failed to parse worklist: {}", e))
+            });
+        match saved {
+            Ok(saved) => {
+                for mut job in saved.jobs {
+                    job.id = self.next_job_id;
+                    self.next_job_id += 1;
+                    self.worklist.push(job);
+                }
+                self.worklist_dirty = true;
+                self.worklist_manifest_error = None;
+                self.current_tab = Tab::Worklist;
+            }
+            Err(e) => self.worklist_manifest_error = Some(e),
+        }
+    }
+
+    /// Load a parameter set into the Analysis tab, including the method,
+    /// resolution, thread, and progress controls derived from it.
+    fn apply_params(&mut self, params: AnalysisParams) {
+        self.method_selection = match params.method {
+            AnalysisMethod::NoAmbiguities => MethodSelection::NoAmbiguities,
+            AnalysisMethod::FixedAmbiguities(_) => MethodSelection::FixedAmbiguities,
+            AnalysisMethod::Incremental(_, max_amb) => {
+                self.incremental_limit_ambiguities = max_amb.is_some();
+                if let Some(n) = max_amb {
+                    self.incremental_max_ambiguities = n;
+                }
+                MethodSelection::Incremental
+            }
+        };
+        match params.thread_count {
+            ThreadCount::Auto => self.thread_selection = ThreadSelection::Auto,
+            ThreadCount::Fixed(n) => {
+                self.thread_selection = ThreadSelection::Manual;
+                self.manual_thread_count = n;
+            }
+        }
+        match params.progress_interval {
+            ProgressInterval::Positions(n) => {
+                self.progress_by_time = false;
+                self.progress_every_positions = n;
+            }
+            ProgressInterval::Millis(ms) => {
+                self.progress_by_time = true;
+                self.progress_every_ms = ms;
+            }
+        }
+        self.resolution_auto = params.auto_resolution_target.is_some();
+        if let Some(target) = params.auto_resolution_target {
+            self.resolution_target_positions = target;
+        }
+        self.refine = params.refine_max_variants.is_some();
+        if let Some(max) = params.refine_max_variants {
+            self.refine_max_variants = max;
+        }
+        self.prune_variants = params.keep_variants.is_some();
+        if let Some(keep) = params.keep_variants {
+            self.keep_variants = keep;
+        }
+        self.trim_references = params.reference_trim_flank.is_some();
+        if let Some(flank) = params.reference_trim_flank {
+            self.trim_flank = flank;
+        }
+        self.restrict_region = params.region_start.is_some() || params.region_end.is_some();
+        if let Some(start) = params.region_start {
+            self.region_from = start + 1;
+        }
+        if let Some(end) = params.region_end {
+            self.region_to = end;
+        }
+        self.mask_text = format_masked_regions(&params.masked_regions);
+        self.exclusivity_ignore_text = params.exclusivity_ignore_patterns.join("\n");
+        self.optimal_merging = match params.merge_strategy {
+            MergeStrategy::Greedy => false,
+            MergeStrategy::Optimal { time_budget_ms } => {
+                self.optimal_merge_budget_ms = time_budget_ms;
+                true
+            }
+        };
+        self.limit_window_time = params.window_time_budget_ms.is_some();
+        if let Some(ms) = params.window_time_budget_ms {
+            self.window_time_budget_ms = ms;
+        }
+        self.params = params;
+    }
+
+    /// Results viewer settings in the form `apply_viewer_defaults` takes.
+    fn current_viewer_settings(&self) -> ViewerDefaults {
+        ViewerDefaults {
+            coverage_threshold: self.view_coverage_threshold,
+            color_green_at: self.color_green_at,
+            color_red_at: self.color_red_at,
+            nomatch_ok_percent: self.nomatch_ok_percent,
+            nomatch_bad_percent: self.nomatch_bad_percent,
+            identity_green_at: self.identity_green_at,
+            identity_red_at: self.identity_red_at,
+            mismatch_green_at: self.mismatch_green_at,
+            mismatch_red_at: self.mismatch_red_at,
+            coverage_range: self.coverage_range,
+            entropy_range: self.entropy_range,
+            composite_range: self.composite_range,
+            gc_band: self.gc_band,
+            tm_band: self.tm_band,
+            diff_green_at: self.diff_green_at,
+            diff_red_at: self.diff_red_at,
+            diff_ignore_count: self.diff_ignore_count,
+            excl_filter_threshold: self.excl_filter_threshold,
+            zoom_level: self.zoom_level,
+        }
+    }
+
+    fn apply_viewer_defaults(&mut self, viewer: &ViewerDefaults) {
+        self.view_coverage_threshold = viewer.coverage_threshold;
+        self.color_green_at = viewer.color_green_at;
+        self.color_red_at = viewer.color_red_at;
+        self.nomatch_ok_percent = viewer.nomatch_ok_percent;
+        self.nomatch_bad_percent = viewer.nomatch_bad_percent;
+        self.identity_green_at = viewer.identity_green_at;
+        self.identity_red_at = viewer.identity_red_at;
+        self.mismatch_green_at = viewer.mismatch_green_at;
+        self.mismatch_red_at = viewer.mismatch_red_at;
+        self.coverage_range = viewer.coverage_range;
+        self.entropy_range = viewer.entropy_range;
+        self.composite_range = viewer.composite_range;
+        self.gc_band = viewer.gc_band;
+        self.tm_band = viewer.tm_band;
+        self.diff_green_at = viewer.diff_green_at;
+        self.diff_red_at = viewer.diff_red_at;
+        self.diff_ignore_count = viewer.diff_ignore_count;
+        self.excl_filter_threshold = viewer.excl_filter_threshold;
+        self.zoom_level = viewer.zoom_level;
     }
 
     /// Recalculate variants_for_threshold and coverage_at_threshold for all
@@ -233,6 +1543,7 @@ impl OligoscreenApp {
         let Some(results) = &mut self.results else {
             return;
         };
+        self.applied_coverage_threshold = threshold;
 
         for length_result in results.results_by_length.values_mut() {
             for pos_result in &mut length_result.positions {
@@ -258,6 +1569,8 @@ impl OligoscreenApp {
                 pos_result.variants_needed = new_needed;
             }
         }
+        self.candidate_table = None;
+        self.structure_checks.clear();
     }
 
     /// Resolve the current UI method selection into a concrete AnalysisMethod.
@@ -278,92 +1591,415 @@ impl OligoscreenApp {
         }
     }
 
-    /// Capture current inputs + params into a WorklistJob and clear the inputs.
-    fn add_to_worklist(&mut self) {
-        let Some(template_data) = self.template_data.clone() else {
-            return;
-        };
-        let Some(reference_data) = self.reference_data.clone() else {
-            return;
-        };
-
-        let template_file_name = self.template_file_name.clone().unwrap_or_default();
-        let reference_file_name = self.reference_file_name.clone().unwrap_or_default();
-
+    /// Analysis tab parameters with the method selection resolved.
+    fn current_params(&self) -> AnalysisParams {
         let mut params = self.params.clone();
         params.method = self.resolve_method();
-
-        let exclusivity_file_names: Vec<String> = self
-            .exclusivity_files
-            .iter()
-            .map(|e| e.file_name.clone())
-            .collect();
-        let exclusivity_data = if self.use_differential {
-            self.exclusivity_data.clone()
+        params.auto_resolution_target =
+            self.resolution_auto.then_some(self.resolution_target_positions);
+        params.refine_max_variants = self.refine.then_some(self.refine_max_variants);
+        params.keep_variants = self.prune_variants.then_some(self.keep_variants);
+        params.reference_trim_flank = self.trim_references.then_some(self.trim_flank);
+        params.region_start = self.restrict_region.then_some(self.region_from - 1);
+        params.region_end = self.restrict_region.then_some(self.region_to);
+        params.masked_regions = parse_masked_regions(&self.mask_text).unwrap_or_default();
+        params.merge_strategy = if self.optimal_merging {
+            MergeStrategy::Optimal {
+                time_budget_ms: self.optimal_merge_budget_ms,
+            }
         } else {
-            None
+            MergeStrategy::Greedy
         };
+        params.window_time_budget_ms =
+            self.limit_window_time.then_some(self.window_time_budget_ms);
+        params.exclusivity_ignore_patterns = self
+            .exclusivity_ignore_text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+        params
+    }
 
-        let template_length = template_data.sequence.len();
-        let reference_count = reference_data.len();
-        let exclusivity_count = exclusivity_data.as_ref().map(|d| d.len()).unwrap_or(0);
-
-        let job = WorklistJob {
-            id: self.next_job_id,
-            template_file_name,
-            template_data,
-            reference_file_name,
-            reference_data,
-            use_differential: self.use_differential,
-            exclusivity_file_names,
-            exclusivity_data,
-            params,
-            output_folder: self.output_folder.clone(),
-            template_length,
-            reference_count,
-            exclusivity_count,
-        };
+    /// Record edits to the analysis parameters in the audit trail. Changes are
+    /// committed once no widget is being dragged or edited, so a drag or a
+    /// typed number becomes a single entry.
+    fn record_param_changes(&mut self, ctx: &egui::Context) {
+        let editing =
+            ctx.input(|i| i.pointer.any_down()) || ctx.memory(|m| m.focused().is_some());
+        if editing {
+            return;
+        }
+        let params = self.current_params();
+        let changes = param_changes(&self.audit_params, &params);
+        if !changes.is_empty() {
+            self.audit_trail
+                .push(AuditEntry::now(AuditEvent::ParamsChanged { changes }));
+            self.audit_params = params;
+        }
+    }
 
-        self.next_job_id += 1;
-        self.worklist.push(job);
+    /// Resolve the Worklist tab thread controls into a ThreadCount.
+    fn resolve_thread_count(&self) -> ThreadCount {
+        match self.thread_selection {
+            ThreadSelection::Auto => ThreadCount::Auto,
+            ThreadSelection::Manual => ThreadCount::Fixed(self.manual_thread_count),
+        }
+    }
 
-        // Clear input fields for next job
-        self.template_file_name = None;
-        self.template_data = None;
-        self.template_error = None;
-        self.reference_file_name = None;
-        self.reference_data = None;
-        self.reference_error = None;
-        self.exclusivity_files.clear();
-        self.exclusivity_data = None;
-        self.exclusivity_error = None;
-        self.use_differential = false;
+    /// A session replaces the worklist, so it can only be opened while no job
+    /// runs and no reference file is loading.
+    fn can_open_session(&self) -> bool {
+        self.worklist_state == WorklistState::Idle
+            && !self.is_analyzing
+            && self.reference_load.is_none()
     }
 
-    fn select_output_folder(&mut self) {
-        if let Some(path) = rfd::FileDialog::new().pick_folder() {
-            self.output_folder = Some(path.to_string_lossy().to_string());
-        }
+    /// Template plus references are loaded, or exclusivity files for an
+    /// exclusivity-only job.
+    fn can_add_to_worklist(&self) -> bool {
+        let exclusivity_only = self.params.exclusivity_only && self.use_differential;
+        self.template_data.is_some()
+            && parse_masked_regions(&self.mask_text).is_ok()
+            && if exclusivity_only {
+                self.exclusivity_data.is_some()
+            } else {
+                self.reference_data.is_some()
+            }
     }
 
-    fn remove_worklist_job(&mut self, index: usize) {
-        if index < self.worklist.len() {
+    /// Capture current inputs + params into a WorklistJob and clear the inputs.
+    fn add_to_worklist(&mut self) {
+        let Some(job) = self.capture_job(None) else {
+            return;
+        };
+        self.worklist.push(job);
+        self.worklist_dirty = true;
+        self.clear_inputs();
+    }
+
+    /// Queue one job per parameter combination of the sweep, all on the
+    /// current inputs, then clear the inputs as "Add to Worklist" does.
+    fn add_sweep_to_worklist(&mut self) {
+        if !self.can_add_to_worklist() {
+            return;
+        }
+        let sweep = match self.sweep_spec.expand(&self.current_params()) {
+            Ok(sweep) => sweep,
+            Err(e) => {
+                self.sweep_error = Some(e);
+                return;
+            }
+        };
+        for params in sweep {
+            if let Some(job) = self.capture_job_with_params(params, None) {
+                self.worklist.push(job);
+            }
+        }
+        self.worklist_dirty = true;
+        self.sweep_error = None;
+        self.show_sweep_window = false;
+        self.clear_inputs();
+        self.current_tab = Tab::Worklist;
+    }
+
+    /// Clear the loaded inputs for the next job
+    fn clear_inputs(&mut self) {
+        self.template_file_name = None;
+        self.template_data = None;
+        self.batch_templates.clear();
+        self.template_error = None;
+        self.reference_file_name = None;
+        self.reference_data = None;
+        self.reference_error = None;
+        self.panel_report = None;
+        self.consensus = None;
+        self.consensus_rx = None;
+        self.reference_dedup = None;
+        self.exclusivity_files.clear();
+        self.exclusivity_data = None;
+        self.exclusivity_error = None;
+        self.use_differential = false;
+    }
+
+    /// Queue a quick scan of the current inputs to run next, keeping the
+    /// inputs loaded so the full screen can be queued afterwards. When the
+    /// worklist is idle only the quick scan is processed.
+    fn start_quick_scan(&mut self) {
+        let Some(job) = self.capture_job(Some(self.quick_scan)) else {
+            return;
+        };
+        self.worklist_dirty = true;
+        if self.worklist_state == WorklistState::Idle {
+            self.worklist.insert(0, job);
+            self.start_worklist_processing();
+            if self.worklist.len() > 1 {
+                self.worklist_state = WorklistState::StopRequested;
+            }
+        } else {
+            self.worklist.insert(self.current_job_index + 1, job);
+            self.worklist_total_at_start += 1;
+        }
+    }
+
+    /// Build a job from the current inputs and Analysis tab parameters (as a
+    /// quick scan when given), recording its submission in the audit trail.
+    fn capture_job(&mut self, quick_scan: Option<QuickScan>) -> Option<WorklistJob> {
+        self.capture_job_with_params(self.current_params(), quick_scan)
+    }
+
+    /// `capture_job` with the given parameters in place of the Analysis tab's
+    fn capture_job_with_params(
+        &mut self,
+        mut params: AnalysisParams,
+        quick_scan: Option<QuickScan>,
+    ) -> Option<WorklistJob> {
+        if !self.can_add_to_worklist() {
+            return None;
+        }
+        let template_data = self.template_data.clone()?;
+
+        params.resolve_auto_resolution(template_data.sequence.len());
+        params.exclusivity_only &= self.use_differential;
+        if let Some(quick_scan) = quick_scan {
+            quick_scan.apply(&mut params, template_data.sequence.len());
+        }
+
+        let template_file_name = self.template_file_name.clone().unwrap_or_default();
+        let (reference_file_name, mut reference_data) = if params.exclusivity_only {
+            (String::new(), ReferenceData::new())
+        } else {
+            (
+                self.reference_file_name.clone().unwrap_or_default(),
+                self.reference_data.clone().unwrap_or_default(),
+            )
+        };
+        if let Some(quick_scan) = quick_scan {
+            reference_data = subsample_evenly(&reference_data, quick_scan.max_references);
+        }
+
+        let exclusivity_file_names: Vec<String> = self
+            .exclusivity_files
+            .iter()
+            .map(|e| e.file_name.clone())
+            .collect();
+        let exclusivity_panel_sizes: Vec<usize> = self
+            .exclusivity_files
+            .iter()
+            .map(|e| e.sequence_count)
+            .collect();
+        let exclusivity_data = if self.use_differential {
+            self.exclusivity_data.clone()
+        } else {
+            None
+        };
+
+        let template_length = template_data.sequence.len();
+        let reference_count = reference_data.len();
+        let exclusivity_count = exclusivity_data.as_ref().map(|d| d.len()).unwrap_or(0);
+        let runtime_estimate = estimate_job_runtime(
+            &template_data,
+            &self.batch_templates,
+            &reference_data,
+            exclusivity_data.as_ref(),
+            &params,
+        );
+
+        self.audit_trail.push(AuditEntry::now(AuditEvent::JobSubmitted {
+            job_id: self.next_job_id,
+            template_file: template_file_name.clone(),
+            reference_file: reference_file_name.clone(),
+            params: Box::new(params.clone()),
+        }));
+
+        let job = WorklistJob {
+            id: self.next_job_id,
+            template_file_name,
+            template_data,
+            batch_templates: self.batch_templates.clone(),
+            batch_id: None,
+            reference_file_name,
+            reference_data,
+            use_differential: self.use_differential,
+            exclusivity_file_names,
+            exclusivity_panel_sizes,
+            exclusivity_data,
+            exclusivity_panel: None,
+            params,
+            output_folder: self.output_folder.clone(),
+            binary_results: self.save_binary_results,
+            store_in_database: self.store_in_database,
+            template_length,
+            reference_count,
+            exclusivity_count,
+            runtime_estimate,
+            audit_trail: self.audit_trail.clone(),
+            checkpoint: None,
+        };
+
+        self.next_job_id += 1;
+        Some(job)
+    }
+
+    fn select_output_folder(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+            self.output_folder = Some(path.to_string_lossy().to_string());
+        }
+    }
+
+    fn remove_worklist_job(&mut self, index: usize) {
+        if index < self.worklist.len() {
             // Don't allow removing the currently-processing job
-            if self.worklist_state == WorklistState::Processing && index == self.current_job_index {
+            if self.is_running_job(index) {
                 return;
             }
             self.worklist.remove(index);
-            if self.worklist_state == WorklistState::Processing && index < self.current_job_index {
+            self.worklist_dirty = true;
+            if self.worklist_busy() && index < self.current_job_index {
                 self.current_job_index -= 1;
             }
         }
     }
 
+    /// A job is running, including while a stop or pause waits for it to
+    /// finish.
+    fn worklist_busy(&self) -> bool {
+        self.worklist_state != WorklistState::Idle
+    }
+
+    /// Whether the job at `index` is the one running.
+    fn is_running_job(&self, index: usize) -> bool {
+        self.worklist_busy() && index == self.current_job_index
+    }
+
+    /// Whether the queued job at `from` can move to `to`. While a job runs,
+    /// jobs only move among those after it, so `current_job_index` keeps
+    /// pointing at the running job.
+    fn can_move_worklist_job(&self, from: usize, to: usize) -> bool {
+        from != to
+            && from < self.worklist.len()
+            && to < self.worklist.len()
+            && (!self.worklist_busy() || from.min(to) > self.current_job_index)
+    }
+
+    fn move_worklist_job(&mut self, from: usize, to: usize) {
+        if self.can_move_worklist_job(from, to) {
+            let job = self.worklist.remove(from);
+            self.worklist.insert(to, job);
+            self.worklist_dirty = true;
+        }
+    }
+
+    /// Queue a copy of the job at `index` right after it, as a new submission
+    /// of the same inputs and parameters.
+    fn duplicate_worklist_job(&mut self, index: usize) {
+        let Some(job) = self.worklist.get(index) else {
+            return;
+        };
+        let mut copy = job.clone();
+        copy.id = self.next_job_id;
+        copy.checkpoint = None;
+        self.next_job_id += 1;
+        let submitted = AuditEvent::JobSubmitted {
+            job_id: copy.id,
+            template_file: copy.template_file_name.clone(),
+            reference_file: copy.reference_file_name.clone(),
+            params: Box::new(copy.params.clone()),
+        };
+        self.audit_trail.push(AuditEntry::now(submitted));
+        copy.audit_trail = self.audit_trail.clone();
+        self.worklist.insert(index + 1, copy);
+        self.worklist_dirty = true;
+        if self.worklist_busy() && index < self.current_job_index {
+            self.current_job_index += 1;
+        }
+    }
+
+    /// Open a queued job's parameters in the Analysis tab for editing; see
+    /// `update_edited_job`.
+    fn edit_worklist_job(&mut self, index: usize) {
+        if self.is_running_job(index) {
+            return;
+        }
+        let Some(job) = self.worklist.get(index) else {
+            return;
+        };
+        let id = job.id;
+        let source = format!("queued job #{} ({})", id, job.template_file_name);
+        let params = job.params.clone();
+        self.apply_params(params);
+        self.params_imported_from = Some(source);
+        self.editing_job = Some(id);
+        self.current_tab = Tab::Analysis;
+    }
+
+    /// Index of the job being edited, while it is still queued and not running
+    fn edited_job_index(&self) -> Option<usize> {
+        let id = self.editing_job?;
+        let index = self.worklist.iter().position(|j| j.id == id)?;
+        (!self.is_running_job(index)).then_some(index)
+    }
+
+    /// Replace the edited job's parameters with the Analysis tab's, keeping
+    /// its inputs, and re-estimate its runtime.
+    fn update_edited_job(&mut self) {
+        let Some(index) = self.edited_job_index() else {
+            return;
+        };
+        let mut params = self.current_params();
+        let job = &self.worklist[index];
+        let template_length = job.template_length;
+        params.resolve_auto_resolution(template_length);
+        params.exclusivity_only &= job.use_differential;
+        if let Some(quick_scan) = job.params.quick_scan {
+            quick_scan.apply(&mut params, template_length);
+        }
+        let runtime_estimate = estimate_job_runtime(
+            &job.template_data,
+            &job.batch_templates,
+            &job.reference_data,
+            job.exclusivity_data.as_ref(),
+            &params,
+        );
+
+        let submitted = AuditEvent::JobSubmitted {
+            job_id: job.id,
+            template_file: job.template_file_name.clone(),
+            reference_file: job.reference_file_name.clone(),
+            params: Box::new(params.clone()),
+        };
+        self.audit_trail.push(AuditEntry::now(submitted));
+        let audit_trail = self.audit_trail.clone();
+        let job = &mut self.worklist[index];
+        job.params = params;
+        job.runtime_estimate = runtime_estimate;
+        job.audit_trail = audit_trail;
+        // Windows screened with the old parameters no longer apply
+        job.checkpoint = None;
+        self.worklist_written.remove(&job.id);
+        self.worklist_dirty = true;
+        self.editing_job = None;
+        self.params_imported_from = None;
+        self.current_tab = Tab::Worklist;
+    }
+
+    /// Position a job moved to the front takes: first in the queue, or next
+    /// after the running job.
+    fn worklist_front(&self) -> usize {
+        if self.worklist_busy() {
+            self.current_job_index + 1
+        } else {
+            0
+        }
+    }
+
     fn start_worklist_processing(&mut self) {
         if self.worklist.is_empty() || self.worklist_state == WorklistState::Processing {
             return;
         }
         self.worklist_state = WorklistState::Processing;
+        self.worklist_restored = 0;
         self.current_job_index = 0;
         self.worklist_total_at_start = self.worklist.len();
         self.start_next_job();
@@ -386,35 +2022,162 @@ impl OligoscreenApp {
 
         // Apply thread count from Worklist tab controls (not from job snapshot)
         let mut params = job.params.clone();
-        params.thread_count = match self.thread_selection {
-            ThreadSelection::Auto => ThreadCount::Auto,
-            ThreadSelection::Manual => ThreadCount::Fixed(self.manual_thread_count),
+        params.thread_count = self.resolve_thread_count();
+        params.progress_interval = if self.progress_by_time {
+            ProgressInterval::Millis(self.progress_every_ms)
+        } else {
+            ProgressInterval::Positions(self.progress_every_positions)
         };
 
         let template_clone = job.template_data.clone();
+        let batch_templates = job.batch_templates.clone();
         let references_clone = job.reference_data.clone();
-        let exclusivity_clone = job.exclusivity_data.clone();
+        let exclusivity_groups = job.exclusivity_groups();
+        let exclusivity_panel = job.exclusivity_panel.clone();
+        let panel_tx = self.exclusivity_panel_tx.clone();
+        let job_id = job.id;
+        let checkpoint = job.checkpoint.clone();
+        // Multi-length jobs saving their results keep each finished length
+        // next to the results file
+        let partial_path = job
+            .output_folder
+            .as_deref()
+            .filter(|_| job.params.min_oligo_length < job.params.max_oligo_length)
+            .filter(|_| job.batch_templates.is_empty())
+            .map(|folder| job_results_path(folder, job));
+        let auto_save_tx = self.auto_save_tx.clone();
+        let pool = self.screening_pool_for(params.thread_count.get_count());
+        let cancel = CancelToken::new();
 
         let (progress_tx, progress_rx) = channel();
         let (results_tx, results_rx) = channel();
 
         self.progress_rx = Some(progress_rx);
         self.results_rx = Some(results_rx);
+        self.cancel_token = Some(cancel.clone());
         self.is_analyzing = true;
+        self.running_job_id = Some(job_id);
         self.analysis_progress = None;
+        self.job_started = Some(Instant::now());
 
         thread::spawn(move || {
-            let results = run_screening(
+            // The panel's seed index is built once and read back from the
+            // panel cache by later jobs screening the same files
+            let panel = (!exclusivity_groups.is_empty()).then(|| {
+                let cache_dir = panel_cache_dir();
+                let (panel, path) = load_prepared_panel(
+                    &exclusivity_groups,
+                    &params,
+                    exclusivity_panel.as_deref(),
+                    cache_dir.as_deref(),
+                );
+                if let Some(path) = path.filter(|p| exclusivity_panel.as_ref() != Some(p)) {
+                    let _ = panel_tx.send((job_id, path));
+                }
+                panel
+            });
+            if !batch_templates.is_empty() {
+                // Multi-template jobs share the reference preparation and
+                // run without checkpoints
+                let templates: Vec<TemplateData> = std::iter::once(template_clone)
+                    .chain(batch_templates)
+                    .collect();
+                let batch = run_templates_in(
+                    &pool,
+                    &templates,
+                    &references_clone,
+                    &params,
+                    panel.as_ref(),
+                    Some(progress_tx),
+                    Some(&cancel),
+                );
+                let _ = results_tx.send(batch);
+                return;
+            }
+            // Without a checkpoint, resume from the lengths a crashed run saved
+            let checkpoint = checkpoint.or_else(|| {
+                let path = partial_path.as_deref()?;
+                load_partial_results(path, &params, &template_clone.sequence)
+            });
+            let (length_tx, length_rx) = channel();
+            let writer = partial_path.map(|path| {
+                let params = params.clone();
+                let template = template_clone.sequence.clone();
+                thread::spawn(move || {
+                    for length_result in length_rx {
+                        if let Err(error) =
+                            write_partial_results(&path, &params, &template, length_result)
+                        {
+                            let _ =
+                                auto_save_tx.send(AutoSaveUpdate::PartialFailed { job_id, error });
+                        }
+                    }
+                })
+            });
+            let results = resume_screening_in(
+                &pool,
                 &template_clone,
                 &references_clone,
                 &params,
-                exclusivity_clone.as_ref(),
+                panel.as_ref(),
                 Some(progress_tx),
+                Some(&cancel),
+                checkpoint.as_ref(),
+                writer.is_some().then_some(length_tx),
             );
-            let _ = results_tx.send(results);
+            // Every finished length is on disk before the results arrive
+            if let Some(writer) = writer {
+                let _ = writer.join();
+            }
+            let _ = results_tx.send(vec![results]);
         });
     }
 
+    /// " | ETA ..." for the running job at its observed windows/second rate,
+    /// or nothing before the first window completes.
+    fn eta_text(&self, progress: &ProgressUpdate) -> String {
+        self.job_started
+            .and_then(|started| progress.eta_seconds(started.elapsed().as_secs_f64()))
+            .map(|eta| format!(" | ETA {}", format_duration(eta)))
+            .unwrap_or_default()
+    }
+
+    /// Stop the running job now, between windows, and the worklist after it.
+    /// The windows finished so far become a completed job flagged as
+    /// cancelled, or are discarded (leaving the job queued) when
+    /// `keep_partial_results` is off.
+    fn cancel_current_job(&mut self) {
+        if let Some(cancel) = &self.cancel_token {
+            cancel.cancel();
+            self.worklist_state = WorklistState::StopRequested;
+        }
+    }
+
+    /// Stop the running job now and the worklist after it, keeping the
+    /// windows finished so far as the job's checkpoint. The job stays queued
+    /// (and is saved with the worklist) and resumes from the checkpoint when
+    /// the worklist is started again.
+    fn pause_current_job(&mut self) {
+        if let Some(cancel) = &self.cancel_token {
+            cancel.cancel();
+            self.worklist_state = WorklistState::StopRequested;
+            self.pause_requested = true;
+        }
+    }
+
+    /// Pool for the given thread count, reusing the previous job's pool when
+    /// the count is unchanged.
+    fn screening_pool_for(&mut self, threads: usize) -> Arc<ScreeningPool> {
+        match &self.screening_pool {
+            Some(pool) if pool.threads() == threads => Arc::clone(pool),
+            _ => {
+                let pool = Arc::new(ScreeningPool::new(threads));
+                self.screening_pool = Some(Arc::clone(&pool));
+                pool
+            }
+        }
+    }
+
     fn check_analysis_progress(&mut self) {
         if let Some(rx) = &self.progress_rx {
             while let Ok(progress) = rx.try_recv() {
@@ -422,72 +2185,277 @@ impl OligoscreenApp {
             }
         }
 
-        if let Some(rx) = &self.results_rx {
-            if let Ok(results) = rx.try_recv() {
+        if let Some(rx) = &self.results_rx
+            && let Ok(mut batch) = rx.try_recv()
+        {
                 self.is_analyzing = false;
                 self.progress_rx = None;
                 self.results_rx = None;
+                self.cancel_token = None;
+
+                // The queue may have changed around the running job
+                let running_id = self.running_job_id.take();
+                let Some(index) = self.worklist.iter().position(|j| Some(j.id) == running_id)
+                else {
+                    self.pause_requested = false;
+                    self.start_next_job();
+                    return;
+                };
+                self.current_job_index = index;
+
+                let cancelled = batch.iter().any(|results| results.cancelled);
+                if cancelled && std::mem::take(&mut self.pause_requested) && batch.len() == 1 {
+                    // The job stays queued with its windows; the worklist stops here
+                    if let Some(job) = self.worklist.get_mut(index) {
+                        job.checkpoint = batch.pop();
+                        self.worklist_written.remove(&job.id);
+                        self.worklist_dirty = true;
+                    }
+                    self.start_next_job();
+                    return;
+                }
+                self.pause_requested = false;
+
+                if cancelled && !self.keep_partial_results {
+                    // The job stays queued; the worklist stops here
+                    self.start_next_job();
+                    return;
+                }
 
                 // Remove the completed job from the worklist
-                let job = self.worklist.remove(self.current_job_index);
+                let job = self.worklist.remove(index);
+                self.worklist_dirty = true;
+                // A multi-template job completes as one job per template;
+                // templates a cancellation never reached are dropped
+                let members = if job.batch_templates.is_empty() {
+                    vec![job]
+                } else {
+                    job.split_batch(&mut self.next_job_id)
+                };
+                let first_new = self.completed_jobs.len();
+                for (job, mut results) in members.into_iter().zip(batch) {
+                    results.audit_trail = job.audit_trail.clone();
+
+                    // Auto-save if output folder is set; the job counts as saved
+                    // once the background write finishes. Partial results of a
+                    // cancelled job are only saved by hand.
+                    if !results.cancelled
+                        && let Some(folder) = job.output_folder.clone()
+                    {
+                        self.start_auto_save(&results, &folder, &job);
+                    }
 
-                // Auto-save if output folder is set
-                if let Some(ref folder) = job.output_folder {
-                    let folder = folder.clone();
-                    self.auto_save_results(&results, &folder, &job);
+                    self.completed_jobs.push(CompletedJob {
+                        job,
+                        results,
+                        saved: false,
+                        detail_source: None,
+                    });
                 }
 
-                self.completed_jobs.push(CompletedJob { job, results });
-
-                // Select the newly completed job for viewing
-                let idx = self.completed_jobs.len() - 1;
-                self.selected_completed_job_index = Some(idx);
-                self.results = Some(self.completed_jobs[idx].results.clone());
-                self.view_coverage_threshold =
-                    self.completed_jobs[idx].results.params.coverage_threshold;
-                self.differential_mode = self.completed_jobs[idx].results.differential_enabled;
+                // Select the (first) newly completed job for viewing
+                self.select_completed_job(first_new);
 
                 // current_job_index stays the same because we removed the element at it
                 self.start_next_job();
             }
+    }
+
+    /// Show a completed job in the Results tab, resetting views derived from
+    /// the previously selected results.
+    fn select_completed_job(&mut self, idx: usize) {
+        let Some(cj) = self.completed_jobs.get(idx) else {
+            return;
+        };
+        self.selected_completed_job_index = Some(idx);
+        self.results = Some(cj.results.clone());
+        self.results_detail_source = cj.detail_source.clone();
+        self.candidate_table = None;
+        self.structure_checks.clear();
+        self.detail_show_rna = cj.results.rna;
+        self.detail_loaded_windows.clear();
+        self.view_coverage_threshold = cj.results.params.coverage_threshold;
+        self.applied_coverage_threshold = cj.results.params.coverage_threshold;
+        if cj.results.differential_enabled {
+            self.heatmap_metric = HeatmapMetric::Exclusivity;
+        } else if self.heatmap_metric == HeatmapMetric::Exclusivity {
+            self.heatmap_metric = HeatmapMetric::VariantsNeeded;
+        }
+        self.pinned_details.clear();
+        self.compare_a = None;
+        self.compare_b = None;
+        self.show_compare_window = false;
+        self.heatmap_cursor = None;
+        self.heatmap_selection.clear();
+        if self.compare_job_index == Some(idx) {
+            self.compare_job_index = None;
         }
+        self.amplicon_candidates = None;
+        self.designed_pairs = None;
+        self.designed_triplets = None;
+        self.panel_matrix = None;
+        self.panel_matrix_rx = None;
+        self.panel_matrix_error = None;
+        self.assignment_table = None;
+        self.assignment_rx = None;
+        self.assignment_export_members = false;
+        self.alignment_view = None;
     }
 
-    fn auto_save_results(
-        &mut self,
-        results: &ScreeningResults,
-        folder: &str,
-        job: &WorklistJob,
-    ) {
-        let sanitized_name: String = job
-            .template_file_name
-            .chars()
-            .map(|c| {
-                if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
-                    c
-                } else {
-                    '_'
-                }
-            })
-            .collect();
-        let file_name = format!("{}_{}.json", sanitized_name, job.id);
-        let path = std::path::Path::new(folder).join(file_name);
+    /// Auto-save a finished job's results on a background thread; see
+    /// `check_auto_saves`.
+    fn start_auto_save(&mut self, results: &ScreeningResults, folder: &str, job: &WorklistJob) {
+        let results = results.clone();
+        let path = job_results_path(folder, job);
+        let database = job
+            .store_in_database
+            .then(|| (database_path(folder), job_database_name(job)));
+        let job_id = job.id;
+        let tx = self.auto_save_tx.clone();
+        self.auto_saves_running.insert(job_id, 0);
+        thread::spawn(move || {
+            let result = match database {
+                Some((db_path, name)) => store_results(&results, &db_path, &name),
+                None => write_results_file(&results, &path, |bytes| {
+                    let _ = tx.send(AutoSaveUpdate::Progress { job_id, bytes });
+                }),
+            };
+            let _ = tx.send(AutoSaveUpdate::Finished { job_id, result });
+        });
+    }
 
-        match serde_json::to_string_pretty(results) {
-            Ok(json) => {
-                if let Err(e) = std::fs::write(&path, json) {
-                    self.auto_save_error = Some(format!("Auto-save failed: {}", e));
-                } else {
-                    self.auto_save_error = None;
+    /// Collect progress and outcomes of background auto-saves.
+    fn check_auto_saves(&mut self) {
+        while let Ok(update) = self.auto_save_rx.try_recv() {
+            match update {
+                AutoSaveUpdate::Progress { job_id, bytes } => {
+                    if let Some(written) = self.auto_saves_running.get_mut(&job_id) {
+                        *written = bytes;
+                    }
+                }
+                AutoSaveUpdate::Finished { job_id, result } => {
+                    self.auto_saves_running.remove(&job_id);
+                    match result {
+                        Ok(()) => {
+                            self.auto_save_error = None;
+                            let job = self
+                                .completed_jobs
+                                .iter_mut()
+                                .find(|cj| cj.job.id == job_id);
+                            if let Some(cj) = job {
+                                cj.saved = true;
+                                // The full results replace the per-length files
+                                if let Some(folder) = &cj.job.output_folder {
+                                    let path = job_results_path(folder, &cj.job);
+                                    remove_partial_results(&path, &cj.job.params);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            self.auto_save_error = Some(format!("Auto-save #{} {}", job_id, e));
+                        }
+                    }
+                }
+                AutoSaveUpdate::PartialFailed { job_id, error } => {
+                    self.auto_save_error = Some(format!(
+                        "Auto-save #{} of a finished oligo length failed: {}",
+                        job_id, error
+                    ));
                 }
             }
-            Err(e) => {
-                self.auto_save_error = Some(format!("Auto-save serialize failed: {}", e));
+        }
+    }
+
+    /// Record the seed index files reported by running jobs, so a job resumed
+    /// later reads its index even if the cache directory changed.
+    fn check_exclusivity_panels(&mut self) {
+        while let Ok((job_id, path)) = self.exclusivity_panel_rx.try_recv() {
+            if let Some(job) = self.worklist.iter_mut().find(|j| j.id == job_id) {
+                job.exclusivity_panel = Some(path);
+                self.worklist_written.remove(&job_id);
+                self.worklist_dirty = true;
+            }
+        }
+    }
+
+    fn unsaved_jobs(&self) -> impl Iterator<Item = &CompletedJob> {
+        self.completed_jobs.iter().filter(|cj| !cj.saved)
+    }
+
+    /// Save every unsaved completed job into a chosen folder, named as for
+    /// auto-save. Returns true when all of them were written.
+    fn save_all_unsaved(&mut self) -> bool {
+        let Some(folder) = rfd::FileDialog::new().pick_folder() else {
+            return false;
+        };
+        let folder = folder.to_string_lossy().to_string();
+        let mut errors = Vec::new();
+        for cj in self.completed_jobs.iter_mut().filter(|cj| !cj.saved) {
+            match write_job_results(&cj.results, &folder, &cj.job) {
+                Ok(()) => cj.saved = true,
+                Err(e) => errors.push(format!("#{} {}", cj.job.id, e)),
             }
         }
+        self.exit_save_error = if errors.is_empty() {
+            None
+        } else {
+            Some(errors.join("\n"))
+        };
+        errors.is_empty()
+    }
+
+    /// Confirmation shown when closing the app with unsaved completed jobs.
+    fn show_exit_dialog(&mut self, ctx: &egui::Context) {
+        let mut quit = false;
+        let mut cancel = false;
+        egui::Window::new("Unsaved Results")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label("These completed jobs have not been saved and will be lost:");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for cj in self.unsaved_jobs() {
+                        ui.label(format!("#{} - {}", cj.job.id, cj.job.template_file_name));
+                    }
+                });
+                if !self.auto_saves_running.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 180, 100),
+                        "Auto-save is still writing; quitting now leaves its files incomplete.",
+                    );
+                }
+                if let Some(ref err) = self.exit_save_error {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Save All and Quit").clicked() && self.save_all_unsaved() {
+                        quit = true;
+                    }
+                    if ui.button("Quit Without Saving").clicked() {
+                        quit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if quit {
+            self.allow_close = true;
+            self.show_exit_dialog = false;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        } else if cancel {
+            self.show_exit_dialog = false;
+            self.exit_save_error = None;
+        }
     }
 
     fn save_results(&mut self) {
+        if !self.load_all_window_variants() {
+            return;
+        }
         let Some(results) = &self.results else {
             self.save_error = Some("No results to save".to_string());
             return;
@@ -495,15 +2463,32 @@ impl OligoscreenApp {
 
         if let Some(path) = rfd::FileDialog::new()
             .add_filter("JSON", &["json"])
+            .add_filter("Compressed binary", &[BINARY_RESULTS_EXTENSION])
             .set_file_name("screening_results.json")
             .save_file()
         {
-            match serde_json::to_string_pretty(results) {
+            if is_binary_results_path(&path) {
+                self.save_error = write_results_file(results, &path, |_| {})
+                    .err()
+                    .map(|e| format!("Saving {}", e));
+                let selected = self.selected_completed_job_index;
+                if self.save_error.is_none()
+                    && let Some(cj) = selected.and_then(|i| self.completed_jobs.get_mut(i))
+                {
+                    cj.saved = true;
+                }
+                return;
+            }
+            match results_to_json(results) {
                 Ok(json) => {
                     if let Err(e) = std::fs::write(&path, json) {
                         self.save_error = Some(format!("Failed to write file: {}", e));
                     } else {
                         self.save_error = None;
+                        let selected = self.selected_completed_job_index;
+                        if let Some(cj) = selected.and_then(|i| self.completed_jobs.get_mut(i)) {
+                            cj.saved = true;
+                        }
                     }
                 }
                 Err(e) => {
@@ -513,1944 +2498,9675 @@ impl OligoscreenApp {
         }
     }
 
-    fn load_results_into_completed(&mut self) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("JSON", &["json"])
-            .pick_file()
-        {
-            match std::fs::read_to_string(&path) {
-                Ok(json) => match serde_json::from_str::<ScreeningResults>(&json) {
-                    Ok(results) => {
-                        let file_name = path
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_else(|| "loaded".to_string());
-
-                        let job = WorklistJob {
-                            id: self.next_job_id,
-                            template_file_name: format!("(loaded) {}", file_name),
-                            template_data: TemplateData {
-                                name: "Loaded".to_string(),
-                                sequence: results.template_sequence.clone(),
-                            },
-                            reference_file_name: String::new(),
-                            reference_data: ReferenceData {
-                                names: Vec::new(),
-                                sequences: Vec::new(),
-                            },
-                            use_differential: results.differential_enabled,
-                            exclusivity_file_names: Vec::new(),
-                            exclusivity_data: None,
-                            params: results.params.clone(),
-                            output_folder: None,
-                            template_length: results.template_length,
-                            reference_count: results.total_sequences,
-                            exclusivity_count: results
-                                .exclusivity_sequence_count
-                                .unwrap_or(0),
-                        };
-                        self.next_job_id += 1;
+    /// Save the whole application state to a session file. Variant lists of
+    /// jobs opened from indexed files are read in first, so the session does
+    /// not depend on those files. The large inputs and job lists are moved
+    /// into the session for writing and back afterwards rather than copied.
+    fn save_session(&mut self) {
+        if !self.load_all_window_variants() {
+            return;
+        }
+        for cj in &mut self.completed_jobs {
+            let Some(source) = cj.detail_source.take() else {
+                continue;
+            };
+            if let Err(e) = source.load_all_variants(&mut cj.results) {
+                cj.detail_source = Some(source);
+                self.save_error = Some(format!("Failed to read variants: {}", e));
+                return;
+            }
+        }
 
-                        self.view_coverage_threshold = results.params.coverage_threshold;
-                        self.differential_mode = results.differential_enabled;
-                        self.results = Some(results.clone());
-                        self.completed_jobs.push(CompletedJob { job, results });
-                        self.selected_completed_job_index =
-                            Some(self.completed_jobs.len() - 1);
-                        self.load_error = None;
-                        self.current_tab = Tab::Results;
-                    }
-                    Err(e) => {
-                        self.load_error = Some(format!("Failed to parse: {}", e));
-                    }
-                },
-                Err(e) => {
-                    self.load_error = Some(format!("Failed to read file: {}", e));
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Oligoscreen session", &[SESSION_EXTENSION])
+            .set_file_name(format!("session.{}", SESSION_EXTENSION))
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut params = self.current_params();
+        params.thread_count = self.resolve_thread_count();
+        let session = SavedSession {
+            template_file_name: self.template_file_name.clone(),
+            template_data: self.template_data.take(),
+            batch_templates: std::mem::take(&mut self.batch_templates),
+            reference_file_name: self.reference_file_name.clone(),
+            reference_data: self.reference_data.take(),
+            use_differential: self.use_differential,
+            exclusivity_files: std::mem::take(&mut self.exclusivity_files),
+            exclusivity_data: self.exclusivity_data.take(),
+            params,
+            output_folder: self.output_folder.clone(),
+            save_binary_results: self.save_binary_results,
+            store_in_database: self.store_in_database,
+            quick_scan: self.quick_scan,
+            viewer: self.current_viewer_settings(),
+            normalize_per_length: self.normalize_per_length,
+            tm_darkening: self.tm_darkening,
+            excl_filter_enabled: self.excl_filter_enabled,
+            gc_filter_enabled: self.gc_filter_enabled,
+            gc_filter_min: self.gc_filter_min,
+            gc_filter_max: self.gc_filter_max,
+            next_job_id: self.next_job_id,
+            worklist: std::mem::take(&mut self.worklist),
+            completed_jobs: std::mem::take(&mut self.completed_jobs),
+            selected_completed_job_index: self.selected_completed_job_index,
+            audit_trail: self.audit_trail.clone(),
+            order_selection: self.order_selection.clone(),
+        };
+        let result = std::fs::File::create(&path)
+            .map_err(|e| format!("Failed to write file: {}", e))
+            .and_then(|file| {
+                let mut out = std::io::BufWriter::new(file);
+                serde_json::to_writer(&mut out, &session)
+                    .map_err(|e| format!("Failed to serialize: {}", e))?;
+                out.flush()
+                    .map_err(|e| format!("Failed to write file: {}", e))
+            });
+        self.template_data = session.template_data;
+        self.batch_templates = session.batch_templates;
+        self.reference_data = session.reference_data;
+        self.exclusivity_files = session.exclusivity_files;
+        self.exclusivity_data = session.exclusivity_data;
+        self.worklist = session.worklist;
+        self.completed_jobs = session.completed_jobs;
+
+        match result {
+            Ok(()) => {
+                self.save_error = None;
+                for cj in &mut self.completed_jobs {
+                    cj.saved = true;
                 }
             }
+            Err(e) => self.save_error = Some(e),
         }
     }
 
-    fn load_template_file(&mut self) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt"])
+    /// Replace the application state with a saved session. Only offered
+    /// while no job is running.
+    fn open_session(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Oligoscreen session", &[SESSION_EXTENSION])
             .pick_file()
+        else {
+            return;
+        };
+        let session = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read file: {}", e))
+            .and_then(|json| {
+                serde_json::from_str::<SavedSession>(&json)
+                    .map_err(|e| format!("Failed to parse session: {}", e))
+            });
+        let session = match session {
+            Ok(session) => session,
+            Err(e) => {
+                self.load_error = Some(e);
+                return;
+            }
+        };
+
+        self.template_file_name = session.template_file_name;
+        self.template_data = session.template_data;
+        self.batch_templates = session.batch_templates;
+        self.template_error = None;
+        self.reference_file_name = session.reference_file_name;
+        self.reference_data = session.reference_data;
+        self.reference_error = None;
+        self.panel_report = None;
+        self.consensus = None;
+        self.consensus_rx = None;
+        self.reference_dedup = None;
+        self.use_differential = session.use_differential;
+        self.exclusivity_files = session.exclusivity_files;
+        self.exclusivity_data = session.exclusivity_data;
+        self.exclusivity_error = None;
+
+        self.apply_params(session.params);
+        self.output_folder = session.output_folder;
+        self.save_binary_results = session.save_binary_results;
+        self.store_in_database = session.store_in_database;
+        self.quick_scan = session.quick_scan;
+        self.audit_trail = session.audit_trail;
+        self.order_selection = session.order_selection;
+        self.audit_params = self.current_params();
+
+        self.apply_viewer_defaults(&session.viewer);
+        self.normalize_per_length = session.normalize_per_length;
+        self.tm_darkening = session.tm_darkening;
+        self.excl_filter_enabled = session.excl_filter_enabled;
+        self.gc_filter_enabled = session.gc_filter_enabled;
+        self.gc_filter_min = session.gc_filter_min;
+        self.gc_filter_max = session.gc_filter_max;
+
+        let max_id = session
+            .worklist
+            .iter()
+            .map(|j| j.id)
+            .chain(session.completed_jobs.iter().map(|cj| cj.job.id))
+            .max()
+            .unwrap_or(0);
+        self.next_job_id = session.next_job_id.max(max_id + 1);
+        self.worklist = session.worklist;
+        self.editing_job = None;
+        self.compare_job_index = None;
+        self.worklist_dirty = true;
+        self.completed_jobs = session.completed_jobs;
+        for cj in &mut self.completed_jobs {
+            cj.saved = true;
+        }
+        self.query_hits = None;
+        self.oligo_hits = None;
+        match session
+            .selected_completed_job_index
+            .filter(|&i| i < self.completed_jobs.len())
         {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => match parse_template_fasta(&content) {
-                    Ok(data) => {
-                        self.template_file_name = Some(
-                            path.file_name()
-                                .map(|n| n.to_string_lossy().to_string())
-                                .unwrap_or_else(|| "unknown".to_string()),
-                        );
-                        self.template_data = Some(data);
-                        self.template_error = None;
-                    }
-                    Err(e) => {
-                        self.template_error = Some(e);
-                    }
-                },
-                Err(e) => {
-                    self.template_error = Some(format!("Failed to read file: {}", e));
-                }
+            Some(idx) => self.select_completed_job(idx),
+            None => {
+                self.selected_completed_job_index = None;
+                self.results = None;
+                self.results_detail_source = None;
+                self.candidate_table = None;
+                self.structure_checks.clear();
+                self.pinned_details.clear();
+                self.show_detail_window = false;
             }
         }
+        self.load_error = None;
     }
 
-    fn load_reference_file(&mut self) {
+    /// Save the per-window metrics with each variant list cut to the top
+    /// `summary_keep_variants`, for archiving. The file cannot restore the
+    /// full results, so the job still counts as unsaved.
+    fn save_summary_results(&mut self) {
+        if !self.load_all_window_variants() {
+            return;
+        }
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to save".to_string());
+            return;
+        };
+
         if let Some(path) = rfd::FileDialog::new()
-            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt"])
-            .pick_file()
+            .add_filter("JSON", &["json"])
+            .set_file_name("screening_results_summary.json")
+            .save_file()
         {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => match parse_reference_fasta(&content) {
-                    Ok(data) => {
-                        self.reference_file_name = Some(
-                            path.file_name()
-                                .map(|n| n.to_string_lossy().to_string())
-                                .unwrap_or_else(|| "unknown".to_string()),
-                        );
-                        self.reference_data = Some(data);
-                        self.reference_error = None;
-                    }
-                    Err(e) => {
-                        self.reference_error = Some(e);
+            let summary = summary_results(results, self.summary_keep_variants);
+            match results_to_json(&summary) {
+                Ok(json) => {
+                    if let Err(e) = std::fs::write(&path, json) {
+                        self.save_error = Some(format!("Failed to write file: {}", e));
+                    } else {
+                        self.save_error = None;
                     }
-                },
+                }
                 Err(e) => {
-                    self.reference_error = Some(format!("Failed to read file: {}", e));
+                    self.save_error = Some(format!("Failed to serialize: {}", e));
                 }
             }
         }
     }
 
-    fn add_exclusivity_file(&mut self) {
+    /// Save the results as an indexed file, whose variant lists are read per
+    /// window when it is opened.
+    fn save_indexed_results(&mut self) {
+        if !self.load_all_window_variants() {
+            return;
+        }
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to save".to_string());
+            return;
+        };
+
         if let Some(path) = rfd::FileDialog::new()
-            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt"])
-            .pick_file()
+            .add_filter("Indexed results", &[INDEXED_RESULTS_EXTENSION])
+            .set_file_name(format!("screening_results.{}", INDEXED_RESULTS_EXTENSION))
+            .save_file()
         {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => match parse_reference_fasta(&content) {
-                    Ok(data) => {
-                        let file_name = path
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_else(|| "unknown".to_string());
-                        let min_len =
-                            data.sequences.iter().map(|s| s.len()).min().unwrap_or(0);
-                        let max_len =
-                            data.sequences.iter().map(|s| s.len()).max().unwrap_or(0);
-                        self.exclusivity_files.push(ExclusivityFileEntry {
-                            file_name,
-                            file_content: content,
-                            sequence_count: data.len(),
-                            min_length: min_len,
-                            max_length: max_len,
-                        });
-                        self.rebuild_exclusivity_data();
-                        self.exclusivity_error = None;
-                    }
-                    Err(e) => {
-                        self.exclusivity_error = Some(e);
+            match write_indexed_results(results, &path) {
+                Ok(()) => {
+                    self.save_error = None;
+                    // A summary's cut lists stay cut, so only full results count as saved
+                    if results.variants_kept.is_none() {
+                        let selected = self.selected_completed_job_index;
+                        if let Some(cj) = selected.and_then(|i| self.completed_jobs.get_mut(i)) {
+                            cj.saved = true;
+                        }
                     }
-                },
+                }
                 Err(e) => {
-                    self.exclusivity_error = Some(format!("Failed to read file: {}", e));
+                    self.save_error = Some(format!("Failed to write file: {}", e));
                 }
             }
         }
     }
 
-    fn remove_exclusivity_file(&mut self, index: usize) {
-        if index < self.exclusivity_files.len() {
-            self.exclusivity_files.remove(index);
-            self.rebuild_exclusivity_data();
+    /// The exclusivity filter, if enabled and the current results carry exclusivity data.
+    fn active_exclusivity_filter(&self) -> Option<ExclusivityFilter> {
+        let has_exclusivity = self
+            .results
+            .as_ref()
+            .is_some_and(|r| r.differential_enabled);
+        if self.excl_filter_enabled && has_exclusivity {
+            Some(ExclusivityFilter {
+                min_mismatches_exceeding: self.excl_filter_threshold,
+                ignore_count: self.diff_ignore_count,
+            })
+        } else {
+            None
         }
     }
 
-    fn rebuild_exclusivity_data(&mut self) {
-        if self.exclusivity_files.is_empty() {
-            self.exclusivity_data = None;
+    fn save_filtered_results(&mut self) {
+        if !self.load_all_window_variants() {
             return;
         }
+        let (Some(results), Some(filter)) = (&self.results, self.active_exclusivity_filter())
+        else {
+            self.save_error = Some("No filtered results to save".to_string());
+            return;
+        };
 
-        let mut combined = ReferenceData::new();
-        for entry in &self.exclusivity_files {
-            if let Ok(data) = parse_reference_fasta(&entry.file_content) {
-                combined.names.extend(data.names);
-                combined.sequences.extend(data.sequences);
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("screening_results_filtered.json")
+            .save_file()
+        {
+            let filtered = filter_results(results, &filter);
+            match results_to_json(&filtered) {
+                Ok(json) => {
+                    if let Err(e) = std::fs::write(&path, json) {
+                        self.save_error = Some(format!("Failed to write file: {}", e));
+                    } else {
+                        self.save_error = None;
+                    }
+                }
+                Err(e) => {
+                    self.save_error = Some(format!("Failed to serialize: {}", e));
+                }
             }
         }
+    }
 
-        if combined.sequences.is_empty() {
-            self.exclusivity_data = None;
-        } else {
-            self.exclusivity_data = Some(combined);
+    /// Whether the heatmap shows exclusivity with conservation darkening.
+    fn differential_mode(&self) -> bool {
+        self.heatmap_metric == HeatmapMetric::Exclusivity
+    }
+
+    /// Tm conditions of the shown results, for window Tm values
+    fn results_tm_conditions(&self) -> TmConditions {
+        self.results
+            .as_ref()
+            .map(|r| r.params.tm_conditions)
+            .unwrap_or_default()
+    }
+
+    /// Metric currently shown on the heatmap.
+    fn current_window_metric(&self) -> WindowMetric {
+        match self.heatmap_metric {
+            HeatmapMetric::VariantsNeeded => WindowMetric::VariantsNeeded,
+            HeatmapMetric::Coverage => WindowMetric::Coverage,
+            HeatmapMetric::NoMatchPercent => WindowMetric::NoMatchPercent,
+            HeatmapMetric::MeanIdentity => WindowMetric::MeanIdentity,
+            HeatmapMetric::MinIdentity => WindowMetric::MinIdentity,
+            HeatmapMetric::MeanMismatches => WindowMetric::MeanMismatches,
+            HeatmapMetric::GcPercent => WindowMetric::GcPercent,
+            HeatmapMetric::MeltingTemp => WindowMetric::MeltingTemp,
+            HeatmapMetric::Entropy => WindowMetric::Entropy,
+            HeatmapMetric::Exclusivity => WindowMetric::ExclusivityMinMismatches {
+                ignore_count: self.diff_ignore_count,
+            },
+            HeatmapMetric::Composite => WindowMetric::Composite(CompositeScales {
+                variants_needed: MetricRange {
+                    green_at: self.color_green_at as f64,
+                    red_at: self.color_red_at as f64,
+                },
+                no_match: self.nomatch_range(),
+                exclusivity: MetricRange {
+                    green_at: f64::from(self.diff_green_at),
+                    red_at: f64::from(self.diff_red_at),
+                },
+                ignore_count: self.diff_ignore_count,
+            }),
         }
     }
-}
 
-impl AnalysisMethod {
-    fn get_fixed_ambiguities(&self) -> u32 {
-        match self {
-            AnalysisMethod::FixedAmbiguities(n) => *n,
-            _ => 1,
+    /// No-match percentage thresholds as a color range.
+    fn nomatch_range(&self) -> MetricRange {
+        MetricRange {
+            green_at: self.nomatch_ok_percent,
+            red_at: self.nomatch_bad_percent,
         }
     }
 
-    fn get_incremental_pct(&self) -> u32 {
-        match self {
-            AnalysisMethod::Incremental(pct, _) => *pct,
-            _ => 50,
+    /// Scale and unit of the metrics colored through the generic scale.
+    fn metric_scale(&self) -> Option<(MetricScale, &'static str)> {
+        match self.heatmap_metric {
+            HeatmapMetric::Coverage => Some((MetricScale::Range(self.coverage_range), "%")),
+            HeatmapMetric::NoMatchPercent => Some((MetricScale::Range(self.nomatch_range()), "%")),
+            HeatmapMetric::Entropy => Some((MetricScale::Range(self.entropy_range), " bits")),
+            HeatmapMetric::Composite => Some((MetricScale::Range(self.composite_range), "")),
+            HeatmapMetric::GcPercent => Some((MetricScale::Band(self.gc_band), "%")),
+            HeatmapMetric::MeltingTemp => Some((MetricScale::Band(self.tm_band), " °C")),
+            HeatmapMetric::VariantsNeeded
+            | HeatmapMetric::MeanIdentity
+            | HeatmapMetric::MinIdentity
+            | HeatmapMetric::MeanMismatches
+            | HeatmapMetric::Exclusivity => None,
         }
     }
 
-    fn get_incremental_max_amb(&self) -> Option<u32> {
-        match self {
-            AnalysisMethod::Incremental(_, max_amb) => *max_amb,
-            _ => None,
+    /// Green/red range of the current metric in normal mode; None for target
+    /// band metrics, which cannot be rescaled per length.
+    fn metric_color_range(&self) -> Option<MetricRange> {
+        match self.heatmap_metric {
+            HeatmapMetric::VariantsNeeded => Some(MetricRange {
+                green_at: self.color_green_at as f64,
+                red_at: self.color_red_at as f64,
+            }),
+            HeatmapMetric::MeanMismatches => Some(MetricRange {
+                green_at: self.mismatch_green_at,
+                red_at: self.mismatch_red_at,
+            }),
+            HeatmapMetric::MeanIdentity | HeatmapMetric::MinIdentity => Some(MetricRange {
+                green_at: self.identity_green_at,
+                red_at: self.identity_red_at,
+            }),
+            _ => match self.metric_scale() {
+                Some((MetricScale::Range(range), _)) => Some(range),
+                _ => None,
+            },
         }
     }
-}
 
-impl eframe::App for OligoscreenApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.is_analyzing {
-            self.check_analysis_progress();
-            ctx.request_repaint();
+    /// Per-length color ranges when per-length normalization applies to the
+    /// current view, otherwise empty (cells use the global scale).
+    fn row_color_ranges(
+        &self,
+        results: &ScreeningResults,
+        excl_filter: Option<&ExclusivityFilter>,
+    ) -> std::collections::HashMap<u32, MetricRange> {
+        match self.metric_color_range() {
+            Some(scale) if self.normalize_per_length && !self.differential_mode() => {
+                per_length_ranges(results, self.current_window_metric(), scale, excl_filter)
+            }
+            _ => std::collections::HashMap::new(),
         }
+    }
 
-        if self.pending_save {
-            self.pending_save = false;
-            self.save_results();
+    /// Save the heatmap (current metric and filter) as a length × position CSV matrix.
+    fn export_heatmap_matrix(&mut self) {
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to export".to_string());
+            return;
+        };
+
+        let metric = self.current_window_metric();
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("heatmap_{}.csv", metric.label()))
+            .save_file()
+        {
+            let filter = self.active_exclusivity_filter();
+            let csv = heatmap_matrix_csv(results, metric, filter.as_ref());
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
         }
+    }
 
-        // Handle deferred exclusivity file removal
-        if let Some(idx) = self.pending_remove_excl.take() {
-            self.remove_exclusivity_file(idx);
+    /// Save every window passing the exclusivity filter, one row per (length,
+    /// position), as CSV or TSV; the format follows the chosen file extension.
+    fn export_positions_table(&mut self) {
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to export".to_string());
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .add_filter("TSV", &["tsv", "tab", "txt"])
+            .set_file_name(format!("positions.{}", TableFormat::Csv.extension()))
+            .save_file()
+        {
+            let is_csv = path
+                .extension()
+                .is_none_or(|e| e.eq_ignore_ascii_case("csv"));
+            let format = if is_csv {
+                TableFormat::Csv
+            } else {
+                TableFormat::Tsv
+            };
+            let filter = self.active_exclusivity_filter();
+            let table = positions_table(results, filter.as_ref(), format);
+            if let Err(e) = std::fs::write(&path, table) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
         }
+    }
 
-        // Top menu bar
-        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Load Template...").clicked() {
-                        self.load_template_file();
-                        ui.close_menu();
-                    }
-                    if ui.button("Load References...").clicked() {
-                        self.load_reference_file();
-                        ui.close_menu();
-                    }
-                    ui.separator();
-                    if ui.button("Load Results from File...").clicked() {
-                        self.load_results_into_completed();
-                        ui.close_menu();
-                    }
-                    let can_save = self.results.is_some();
-                    if ui
-                        .add_enabled(can_save, egui::Button::new("Save Results..."))
-                        .clicked()
-                    {
-                        self.save_results();
-                        ui.close_menu();
-                    }
-                });
-            });
-        });
+    /// Save the current heatmap metric as a bedGraph or wiggle track on the
+    /// template coordinates; the format follows the chosen file extension.
+    fn export_metric_track(&mut self) {
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to export".to_string());
+            return;
+        };
 
-        // Tab bar
-        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.selectable_value(&mut self.current_tab, Tab::Input, "Input Data");
-                ui.selectable_value(&mut self.current_tab, Tab::Analysis, "Analysis Setup");
-                ui.selectable_value(
-                    &mut self.current_tab,
-                    Tab::Worklist,
-                    format!("Worklist ({})", self.worklist.len()),
-                );
-                ui.selectable_value(
-                    &mut self.current_tab,
-                    Tab::Results,
-                    format!("Results ({})", self.completed_jobs.len()),
-                );
-            });
-        });
+        // Browsers match tracks to sequences by the first word of the FASTA header
+        let chrom = self
+            .selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+            .and_then(|cj| cj.job.template_data.name.split_whitespace().next())
+            .unwrap_or("template")
+            .to_string();
+        let metric = self.current_window_metric();
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("bedGraph", &["bedgraph", "bg"])
+            .add_filter("Wiggle", &["wig"])
+            .set_file_name(format!("{}.{}", metric.label(), TrackFormat::BedGraph.extension()))
+            .save_file()
+        {
+            let format = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("wig")) {
+                TrackFormat::Wiggle
+            } else {
+                TrackFormat::BedGraph
+            };
+            let filter = self.active_exclusivity_filter();
+            let track = metric_track(results, metric, filter.as_ref(), &chrom, format);
+            if let Err(e) = std::fs::write(&path, track) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
 
-        // Status bar
-        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if self.is_analyzing {
-                    ui.spinner();
-                    if let Some(ref progress) = self.analysis_progress {
-                        ui.label(format!("Processing: {}", &progress.message));
-                    } else {
-                        ui.label("Starting job...");
-                    }
-                } else if self.worklist_state == WorklistState::StopRequested {
-                    ui.label("Stopping after current job...");
-                } else {
-                    let mut parts = Vec::new();
-                    if !self.completed_jobs.is_empty() {
-                        parts.push(format!(
-                            "{} completed",
-                            self.completed_jobs.len()
-                        ));
-                    }
-                    if !self.worklist.is_empty() {
-                        parts.push(format!("{} queued", self.worklist.len()));
-                    }
-                    if let Some(ref t) = self.template_data {
-                        parts.push(format!("Template: {} bp", t.sequence.len()));
-                    }
-                    if let Some(ref r) = self.reference_data {
-                        parts.push(format!("References: {} seqs", r.len()));
-                    }
-                    if parts.is_empty() {
-                        ui.label("Load template and reference sequences to begin");
+    /// Save every completed job's windows as one CSV with job metadata
+    /// columns, leaving out the windows the exclusivity filter hides.
+    fn export_batch_summary(&mut self) {
+        if self.completed_jobs.is_empty() {
+            self.save_error = Some("No completed jobs to export".to_string());
+            return;
+        }
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("batch_summary.csv")
+            .save_file()
+        {
+            let jobs: Vec<BatchJob> = self
+                .completed_jobs
+                .iter()
+                .map(|cj| BatchJob {
+                    job_id: cj.job.id,
+                    template_file: &cj.job.template_file_name,
+                    reference_file: &cj.job.reference_file_name,
+                    results: &cj.results,
+                })
+                .collect();
+            // Every job is filtered, whichever one is open; windows without
+            // exclusivity data pass
+            let filter = self.excl_filter_enabled.then_some(ExclusivityFilter {
+                min_mismatches_exceeding: self.excl_filter_threshold,
+                ignore_count: self.diff_ignore_count,
+            });
+            let csv = batch_summary_csv(&jobs, filter.as_ref(), self.excl_filter_threshold);
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// Copy the selected job's parameters into the Analysis tab, so a run
+    /// (including one loaded from a colleague's results file) can be repeated
+    /// with identical settings.
+    fn use_job_parameters(&mut self) {
+        let Some(cj) = self
+            .selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+        else {
+            return;
+        };
+        let source = format!("job #{} ({})", cj.job.id, cj.job.template_file_name);
+        let params = cj.results.params.clone();
+        self.apply_params(params);
+        self.params_imported_from = Some(source);
+        self.current_tab = Tab::Analysis;
+    }
+
+    /// Save the selected job's parameters, input file names, and input
+    /// checksums as a standalone reproducibility file.
+    fn export_parameters(&mut self) {
+        let Some(cj) = self
+            .selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+        else {
+            self.save_error = Some("No job selected".to_string());
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name(format!("job_{}_parameters.json", cj.job.id))
+            .save_file()
+        {
+            let record = reproducibility_record(cj);
+            match serde_json::to_string_pretty(&record) {
+                Ok(json) => {
+                    if let Err(e) = std::fs::write(&path, json) {
+                        self.save_error = Some(format!("Failed to write file: {}", e));
                     } else {
-                        ui.label(parts.join(" | "));
+                        self.save_error = None;
                     }
                 }
-            });
+                Err(e) => {
+                    self.save_error = Some(format!("Failed to serialize: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Request a screenshot of the window; the heatmap viewport is cropped out
+    /// when it arrives (see `receive_heatmap_screenshot`).
+    fn request_heatmap_screenshot(&mut self, ctx: &egui::Context) {
+        if self.heatmap_viewport.is_none() {
+            self.heatmap_copy_status = Some("Heatmap is not visible".to_string());
+            return;
+        }
+        self.pending_heatmap_screenshot = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+    }
+
+    fn receive_heatmap_screenshot(&mut self, ctx: &egui::Context) {
+        let image = ctx.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
         });
+        let Some(image) = image else {
+            ctx.request_repaint();
+            return;
+        };
+        self.pending_heatmap_screenshot = false;
+
+        if let Some(rect) = self.heatmap_viewport {
+            let cropped = image.region(&rect, Some(ctx.pixels_per_point()));
+            self.heatmap_copy_status = Some(format!(
+                "Copied visible heatmap ({}×{} px)",
+                cropped.width(),
+                cropped.height()
+            ));
+            ctx.copy_image(cropped);
+        }
+    }
 
-        // Main content
-        egui::CentralPanel::default().show(ctx, |ui| {
-            match self.current_tab {
-                Tab::Input => self.show_input_tab(ui),
-                Tab::Analysis => self.show_analysis_tab(ui),
-                Tab::Worklist => self.show_worklist_tab(ui),
-                Tab::Results => self.show_results_tab(ui),
+    /// Heatmap caption for the current metric and mode
+    fn heatmap_caption(&self, coverage_threshold: f64) -> String {
+        if self.differential_mode() {
+            return "Exclusivity: min mismatches (green=specific, red=similar to off-targets). \
+                    Darkened by conservation metrics."
+                .to_string();
+        }
+        match self.heatmap_metric {
+            HeatmapMetric::VariantsNeeded => format!(
+                "Variants needed to reach {:.0}% coverage",
+                coverage_threshold
+            ),
+            metric => metric.label().to_string(),
+        }
+    }
+
+    /// The whole heatmap (all positions, all lengths) with the current
+    /// colors, independent of zoom and scroll. None without results.
+    fn heatmap_figure(&self) -> Option<HeatmapFigure> {
+        let results = self.results.as_ref()?;
+        let excl_filter = self.active_exclusivity_filter();
+        let rgb = |c: egui::Color32| [c.r(), c.g(), c.b()];
+
+        let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+        lengths.sort();
+        let positions: Vec<usize> = results
+            .results_by_length
+            .values()
+            .flat_map(|lr| lr.positions.iter().map(|p| p.position))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let row_ranges = self.row_color_ranges(results, excl_filter.as_ref());
+        let mut cells = Vec::with_capacity(lengths.len() * positions.len());
+        for length in &lengths {
+            let lr = &results.results_by_length[length];
+            for &pos in &positions {
+                let pr = lr.positions.iter().find(|p| p.position == pos);
+                let oligo = results
+                    .template_sequence
+                    .get(pos..pos + *length as usize)
+                    .unwrap_or("");
+                let color = self.heatmap_cell_color(
+                    pr,
+                    oligo,
+                    excl_filter.as_ref(),
+                    row_ranges.get(length),
+                );
+                cells.push(rgb(color));
+            }
+        }
+
+        let sequence_track = positions
+            .iter()
+            .map(|&pos| {
+                let base = results.template_sequence.as_bytes().get(pos).copied();
+                let base = base.map_or('N', |b| b as char);
+                (base, rgb(base_color(base)))
+            })
+            .collect();
+        let legend = self
+            .heatmap_legend()
+            .into_iter()
+            .filter_map(|item| match item {
+                LegendItem::Swatch(color, label) => Some(LegendEntry {
+                    color: Some(rgb(color)),
+                    label,
+                }),
+                LegendItem::Text(label) => Some(LegendEntry { color: None, label }),
+                LegendItem::Separator => None,
+            })
+            .collect();
+        let caption = self.heatmap_caption(self.applied_coverage_threshold);
+        let title = match self
+            .selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+        {
+            Some(cj) => format!("{}: {}", cj.job.template_data.name, caption),
+            None => caption,
+        };
+
+        Some(HeatmapFigure {
+            title,
+            lengths,
+            positions,
+            cells,
+            sequence_track,
+            legend,
+        })
+    }
+
+    /// Render every heatmap cell (all positions, all lengths) to an image and
+    /// copy it to the clipboard, independent of zoom and scroll.
+    fn copy_whole_heatmap(&mut self, ctx: &egui::Context) {
+        let Some(figure) = self.heatmap_figure() else {
+            return;
+        };
+        let positions = figure.positions.len();
+        if positions == 0 {
+            return;
+        }
+        if positions > MAX_HEATMAP_IMAGE_WIDTH {
+            self.heatmap_copy_status = Some(format!(
+                "Too many positions ({}) for an image; use Export Matrix CSV instead",
+                positions
+            ));
+            return;
+        }
+
+        let cell_w = (MAX_HEATMAP_IMAGE_WIDTH / positions).clamp(1, 8);
+        let cell_h = 24;
+        let mut image = egui::ColorImage::new(
+            [positions * cell_w, figure.lengths.len() * cell_h],
+            egui::Color32::BLACK,
+        );
+        for (row, colors) in figure.cells.chunks(positions).enumerate() {
+            for (col, &[r, g, b]) in colors.iter().enumerate() {
+                for y in row * cell_h..(row + 1) * cell_h {
+                    let start = y * image.width() + col * cell_w;
+                    image.pixels[start..start + cell_w].fill(egui::Color32::from_rgb(r, g, b));
+                }
+            }
+        }
+
+        self.heatmap_copy_status = Some(format!(
+            "Copied whole heatmap ({}×{} px, {} px per position)",
+            image.width(),
+            image.height(),
+            cell_w
+        ));
+        ctx.copy_image(image);
+    }
+
+    /// Save the whole heatmap with its legend and sequence track as a PNG or
+    /// SVG file at `heatmap_figure_size`; the format follows the extension.
+    fn export_heatmap_image(&mut self) {
+        let Some(figure) = self.heatmap_figure() else {
+            self.save_error = Some("No results to export".to_string());
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .add_filter("SVG", &["svg"])
+            .set_file_name("heatmap.png")
+            .save_file()
+        {
+            let is_svg = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+            let written = if is_svg {
+                std::fs::write(&path, figure.to_svg(self.heatmap_figure_size))
+                    .map_err(|e| format!("Failed to write file: {}", e))
+            } else {
+                figure.to_png(self.heatmap_figure_size).and_then(|png| {
+                    std::fs::write(&path, png).map_err(|e| format!("Failed to write file: {}", e))
+                })
+            };
+            match written {
+                Ok(()) => {
+                    let (w, h) = figure.dimensions(self.heatmap_figure_size);
+                    self.save_error = None;
+                    self.heatmap_copy_status =
+                        Some(format!("Exported heatmap image ({}×{} px)", w, h));
+                }
+                Err(e) => self.save_error = Some(e),
+            }
+        }
+    }
+
+    fn load_results_into_completed(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(
+                "Results",
+                &["json", BINARY_RESULTS_EXTENSION, INDEXED_RESULTS_EXTENSION],
+            )
+            .pick_file()
+        {
+            let indexed = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case(INDEXED_RESULTS_EXTENSION));
+            let loaded = if indexed {
+                IndexedResults::open(&path)
+                    .map(|(source, results)| (results, Some(Arc::new(source))))
+                    .map_err(|e| format!("Failed to open indexed results: {}", e))
+            } else {
+                // JSON or compressed binary, told apart by the contents
+                std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read file: {}", e))
+                    .and_then(|bytes| {
+                        results_from_bytes(&bytes)
+                            .map(|results| (results, None))
+                            .map_err(|e| format!("Failed to parse: {}", e))
+                    })
+            };
+            match loaded {
+                Ok((results, detail_source)) => {
+                    let file_name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "loaded".to_string());
+
+                    self.add_loaded_results(results, detail_source, &file_name);
+                    self.load_error = None;
+                }
+                Err(e) => {
+                    self.load_error = Some(e);
+                }
             }
+        }
+    }
+
+    /// Add results read from a file or the results database as a completed
+    /// (already saved) job, and show it.
+    fn add_loaded_results(
+        &mut self,
+        results: ScreeningResults,
+        detail_source: Option<Arc<IndexedResults>>,
+        file_name: &str,
+    ) {
+        let job = WorklistJob {
+            id: self.next_job_id,
+            template_file_name: format!("(loaded) {}", file_name),
+            template_data: TemplateData {
+                name: "Loaded".to_string(),
+                sequence: results.template_sequence.clone(),
+                rna: results.rna,
+            },
+            batch_templates: Vec::new(),
+            batch_id: None,
+            reference_file_name: String::new(),
+            reference_data: ReferenceData {
+                names: Vec::new(),
+                sequences: Vec::new(),
+            },
+            use_differential: results.differential_enabled,
+            exclusivity_file_names: Vec::new(),
+            exclusivity_panel_sizes: Vec::new(),
+            exclusivity_data: None,
+            exclusivity_panel: None,
+            params: results.params.clone(),
+            output_folder: None,
+            binary_results: false,
+            store_in_database: false,
+            template_length: results.template_length,
+            reference_count: results.total_sequences,
+            exclusivity_count: results.exclusivity_sequence_count.unwrap_or(0),
+            runtime_estimate: estimate_runtime(results.template_length, &[], &results.params, 0.0),
+            audit_trail: results.audit_trail.clone(),
+            checkpoint: None,
+        };
+        self.next_job_id += 1;
+
+        self.completed_jobs.push(CompletedJob {
+            job,
+            results,
+            saved: true,
+            detail_source,
         });
+        self.select_completed_job(self.completed_jobs.len() - 1);
+        self.current_tab = Tab::Results;
+    }
 
-        // Detail window
-        if self.show_detail_window {
-            self.show_variant_detail_window(ctx);
+    /// Read one window's variant list from the indexed file the results were
+    /// opened from, the first time the window is shown.
+    fn load_window_variants(&mut self, length: u32, position: usize) {
+        let Some(source) = self.results_detail_source.clone() else {
+            return;
+        };
+        if !self.detail_loaded_windows.insert((length, position)) {
+            return;
+        }
+        match source.load_variants(length, position) {
+            Ok(variants) => {
+                let pos_result = self
+                    .results
+                    .as_mut()
+                    .and_then(|r| r.results_by_length.get_mut(&length))
+                    .and_then(|lr| lr.positions.iter_mut().find(|p| p.position == position));
+                if let Some(pr) = pos_result {
+                    pr.analysis.variants = variants;
+                }
+            }
+            Err(e) => {
+                self.load_error = Some(format!("Failed to read window variants: {}", e));
+            }
         }
     }
-}
 
-impl OligoscreenApp {
-    fn show_input_tab(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Input Data");
-        ui.separator();
+    /// Read every variant list of results opened from an indexed file, for
+    /// operations that need them all. Returns false (with `load_error` set)
+    /// when the file could not be read.
+    fn load_all_window_variants(&mut self) -> bool {
+        let Some(source) = self.results_detail_source.clone() else {
+            return true;
+        };
+        let Some(idx) = self.selected_completed_job_index else {
+            return true;
+        };
+        let Some(cj) = self.completed_jobs.get_mut(idx) else {
+            return true;
+        };
+        if let Err(e) = source.load_all_variants(&mut cj.results) {
+            self.load_error = Some(format!("Failed to read variants: {}", e));
+            return false;
+        }
+        cj.detail_source = None;
+        self.results = Some(cj.results.clone());
+        self.results_detail_source = None;
+        self.candidate_table = None;
+        self.structure_checks.clear();
+        self.detail_loaded_windows.clear();
+        self.load_error = None;
+        true
+    }
 
-        // --- Template Sequence ---
-        ui.group(|ui| {
-            ui.horizontal(|ui| {
-                ui.heading("Template Sequence");
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.button("Clear").clicked() {
-                        self.template_file_name = None;
-                        self.template_data = None;
+    fn load_template_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt", "gz", "bgz"])
+            .pick_file()
+        {
+            match read_fasta_file(&path) {
+                Ok(content) => match parse_templates_fasta(&content) {
+                    Ok(mut templates) => {
+                        self.template_file_name = Some(
+                            path.file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| "unknown".to_string()),
+                        );
+                        // Several sequences make a multi-template job
+                        self.template_data = Some(templates.remove(0));
+                        self.batch_templates = templates;
+                        self.panel_report = None;
+                        self.consensus = None;
+                        self.consensus_rx = None;
+                        self.reference_dedup = None;
                         self.template_error = None;
                     }
-                    if ui.button("Load File").clicked() {
-                        self.load_template_file();
+                    Err(e) => {
+                        self.template_error = Some(e);
                     }
-                });
-            });
-
-            ui.label("Single sequence in FASTA format (A, C, G, T only)");
+                },
+                Err(e) => {
+                    self.template_error = Some(format!("Failed to read file: {}", e));
+                }
+            }
+        }
+    }
 
-            if let Some(ref error) = self.template_error {
-                ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+    fn load_annotation_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Annotations", &["gff", "gff3", "bed", "txt"])
+            .pick_file()
+        {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => match parse_annotations(&content) {
+                    Ok(features) => {
+                        self.annotation_file_name = Some(
+                            path.file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| "unknown".to_string()),
+                        );
+                        self.annotations = features;
+                        self.annotation_error = None;
+                    }
+                    Err(e) => {
+                        self.annotation_error = Some(e);
+                    }
+                },
+                Err(e) => {
+                    self.annotation_error = Some(format!("Failed to read file: {}", e));
+                }
             }
-            if let Some(ref data) = self.template_data {
-                ui.horizontal(|ui| {
-                    ui.colored_label(
-                        egui::Color32::from_rgb(100, 200, 100),
-                        format!(
-                            "File: {}",
-                            self.template_file_name.as_deref().unwrap_or("unknown")
-                        ),
+        }
+    }
+
+    /// Add the features of a GFF3/BED file (those on the current template) to
+    /// the masked regions.
+    fn load_mask_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Annotations", &["bed", "gff", "gff3", "txt"])
+            .pick_file()
+        {
+            let features = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read file: {}", e))
+                .and_then(|content| parse_annotations(&content));
+            match features {
+                Ok(features) => {
+                    let template_name = self.template_data.as_ref().map_or("", |t| t.name.as_str());
+                    let mut regions = parse_masked_regions(&self.mask_text).unwrap_or_default();
+                    regions.extend(
+                        features_for_template(&features, template_name)
+                            .iter()
+                            .map(|f| (f.start, f.end)),
                     );
-                });
-                ui.colored_label(
-                    egui::Color32::from_rgb(100, 200, 100),
-                    format!("Sequence: {} ({} bp)", data.name, data.sequence.len()),
-                );
-            } else {
-                ui.colored_label(egui::Color32::GRAY, "No template loaded");
+                    self.mask_text = format_masked_regions(&merge_regions(regions));
+                    self.mask_load_error = None;
+                }
+                Err(e) => {
+                    self.mask_load_error = Some(e);
+                }
             }
-        });
+        }
+    }
 
-        ui.add_space(5.0);
+    /// Pick a reference file and parse it on a background thread, streaming
+    /// records so multi-GB files need not fit in memory as text; see
+    /// `check_reference_load`.
+    fn load_reference_file(&mut self) {
+        if self.reference_load.is_some() {
+            return;
+        }
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt", "gz", "bgz"])
+            .pick_file()
+        {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let (tx, rx) = channel();
+            thread::spawn(move || {
+                let result = read_reference_fasta(&path, |read, total| {
+                    let _ = tx.send(ReferenceLoadUpdate::Progress { read, total });
+                });
+                let _ = tx.send(ReferenceLoadUpdate::Finished(result));
+            });
+            self.reference_load = Some(ReferenceLoad {
+                file_name,
+                rx,
+                progress: (0, 0),
+            });
+            self.reference_error = None;
+        }
+    }
+
+    /// Collect progress and the outcome of a background reference load.
+    fn check_reference_load(&mut self) {
+        let Some(load) = &mut self.reference_load else {
+            return;
+        };
+        while let Ok(update) = load.rx.try_recv() {
+            match update {
+                ReferenceLoadUpdate::Progress { read, total } => load.progress = (read, total),
+                ReferenceLoadUpdate::Finished(result) => {
+                    let file_name = std::mem::take(&mut load.file_name);
+                    self.reference_load = None;
+                    match result {
+                        Ok(data) => {
+                            self.reference_file_name = Some(file_name);
+                            self.reference_data = Some(data);
+                            self.panel_report = None;
+                            self.consensus = None;
+                            self.consensus_rx = None;
+                            self.reference_dedup = None;
+                            self.reference_error = None;
+                        }
+                        Err(e) => {
+                            self.reference_error = Some(e);
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn add_exclusivity_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("FASTA", &["fasta", "fa", "fna", "fas", "txt", "gz", "bgz"])
+            .pick_file()
+        {
+            match read_fasta_file(&path) {
+                Ok(content) => match parse_reference_fasta(&content) {
+                    Ok(data) => {
+                        let file_name = path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| "unknown".to_string());
+                        let min_len =
+                            data.sequences.iter().map(|s| s.len()).min().unwrap_or(0);
+                        let max_len =
+                            data.sequences.iter().map(|s| s.len()).max().unwrap_or(0);
+                        self.exclusivity_files.push(ExclusivityFileEntry {
+                            file_name,
+                            file_content: content,
+                            sequence_count: data.len(),
+                            min_length: min_len,
+                            max_length: max_len,
+                        });
+                        self.rebuild_exclusivity_data();
+                        self.exclusivity_error = None;
+                    }
+                    Err(e) => {
+                        self.exclusivity_error = Some(e);
+                    }
+                },
+                Err(e) => {
+                    self.exclusivity_error = Some(format!("Failed to read file: {}", e));
+                }
+            }
+        }
+    }
+
+    fn remove_exclusivity_file(&mut self, index: usize) {
+        if index < self.exclusivity_files.len() {
+            self.exclusivity_files.remove(index);
+            self.rebuild_exclusivity_data();
+        }
+    }
+
+    fn rebuild_exclusivity_data(&mut self) {
+        if self.exclusivity_files.is_empty() {
+            self.exclusivity_data = None;
+            return;
+        }
+
+        let mut combined = ReferenceData::new();
+        for entry in &self.exclusivity_files {
+            if let Ok(data) = parse_reference_fasta(&entry.file_content) {
+                combined.names.extend(data.names);
+                combined.sequences.extend(data.sequences);
+            }
+        }
+
+        if combined.sequences.is_empty() {
+            self.exclusivity_data = None;
+        } else {
+            self.exclusivity_data = Some(combined);
+        }
+    }
+}
+
+impl eframe::App for OligoscreenApp {
+    /// Wait for the worklist writes, so the last change of the queue is not
+    /// lost on closing.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.worklist_dirty {
+            self.persist_worklist();
+        }
+        if let Some(writer) = &mut self.worklist_writer {
+            writer.finish();
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.is_analyzing {
+            self.check_analysis_progress();
+            ctx.request_repaint();
+        }
+
+        self.check_auto_saves();
+        self.check_exclusivity_panels();
+        if !self.auto_saves_running.is_empty() {
+            ctx.request_repaint();
+        }
+
+        self.check_reference_load();
+        if self.reference_load.is_some() {
+            ctx.request_repaint();
+        }
+
+        if let Some(rx) = &self.panel_matrix_rx {
+            if let Ok(matrix) = rx.try_recv() {
+                self.panel_matrix = Some(matrix);
+                self.panel_matrix_rx = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+
+        if let Some(rx) = &self.consensus_rx {
+            if let Ok(consensus) = rx.try_recv() {
+                self.consensus = Some(consensus);
+                self.consensus_rx = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+
+        if let Some(rx) = &self.assignment_rx {
+            if let Ok(mut table) = rx.try_recv() {
+                let (column, descending) = self.assignment_sort;
+                sort_assignments(&mut table.rows, column, descending);
+                let (length, position) = (table.length, table.position);
+                self.assignment_table = Some(table);
+                self.assignment_rx = None;
+                if std::mem::take(&mut self.assignment_export_members) {
+                    self.export_variant_members(length, position);
+                }
+            } else {
+                ctx.request_repaint();
+            }
+        }
+
+        if self.pending_save {
+            self.pending_save = false;
+            self.save_results();
+        }
+
+        if self.worklist_dirty {
+            self.worklist_dirty = false;
+            self.persist_worklist();
+        }
+        if let Some(outcome) = self.worklist_writer.as_ref().and_then(|w| w.poll()) {
+            self.show_worklist_write_outcome(outcome);
+        }
+
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.allow_close
+            && self.unsaved_jobs().next().is_some()
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.show_exit_dialog = true;
+        }
+
+        self.record_param_changes(ctx);
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.show_command_palette = !self.show_command_palette;
+            self.palette_query.clear();
+            self.palette_selected = 0;
+        }
+
+        if self.pending_heatmap_screenshot {
+            self.receive_heatmap_screenshot(ctx);
+        }
+
+        // Handle deferred exclusivity file removal
+        if let Some(idx) = self.pending_remove_excl.take() {
+            self.remove_exclusivity_file(idx);
+        }
+
+        // Top menu bar
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Load Template...").clicked() {
+                        self.load_template_file();
+                        ui.close_menu();
+                    }
+                    if ui.button("Load References...").clicked() {
+                        self.load_reference_file();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(
+                            self.can_open_session(),
+                            egui::Button::new("Open Session..."),
+                        )
+                        .on_hover_text("Replace inputs, jobs, and settings with a saved session")
+                        .clicked()
+                    {
+                        self.open_session();
+                        ui.close_menu();
+                    }
+                    if ui.button("Load Worklist...").clicked() {
+                        self.load_worklist_manifest();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.worklist.is_empty(),
+                            egui::Button::new("Save Worklist..."),
+                        )
+                        .on_hover_text("Queued jobs with their input sequences, as one file")
+                        .clicked()
+                    {
+                        self.save_worklist_manifest();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("Save Session...")
+                        .on_hover_text(
+                            "Save inputs, queued and completed jobs, parameters, and viewer \
+                             settings to one project file",
+                        )
+                        .clicked()
+                    {
+                        self.save_session();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Load Results from File...").clicked() {
+                        self.load_results_into_completed();
+                        ui.close_menu();
+                    }
+                    let can_save = self.results.is_some();
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save Results..."))
+                        .clicked()
+                    {
+                        self.save_results();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save Summary Results..."))
+                        .on_hover_text(
+                            "Per-window metrics; variant lists cut to the top N set under \
+                             Save Summary in the Results tab",
+                        )
+                        .clicked()
+                    {
+                        self.save_summary_results();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save Indexed Results..."))
+                        .on_hover_text(
+                            "Variant lists stored per window and read only when a window's \
+                             detail is opened, so large results open quickly",
+                        )
+                        .clicked()
+                    {
+                        self.save_indexed_results();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.completed_jobs.is_empty(),
+                            egui::Button::new("Export Batch Summary CSV..."),
+                        )
+                        .clicked()
+                    {
+                        self.export_batch_summary();
+                        ui.close_menu();
+                    }
+                });
+            });
+        });
+
+        // Tab bar
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.current_tab, Tab::Input, "Input Data");
+                ui.selectable_value(&mut self.current_tab, Tab::Analysis, "Analysis Setup");
+                ui.selectable_value(
+                    &mut self.current_tab,
+                    Tab::Worklist,
+                    format!("Worklist ({})", self.worklist.len()),
+                );
+                ui.selectable_value(
+                    &mut self.current_tab,
+                    Tab::Results,
+                    format!("Results ({})", self.completed_jobs.len()),
+                );
+            });
+        });
+
+        // Status bar
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if self.is_analyzing {
+                    ui.spinner();
+                    if let Some(ref progress) = self.analysis_progress {
+                        ui.label(format!(
+                            "Processing: {}{}",
+                            &progress.message,
+                            self.eta_text(progress)
+                        ));
+                    } else {
+                        ui.label("Starting job...");
+                    }
+                } else if self.worklist_state == WorklistState::StopRequested {
+                    ui.label("Stopping after current job...");
+                } else {
+                    let mut parts = Vec::new();
+                    if !self.completed_jobs.is_empty() {
+                        parts.push(format!(
+                            "{} completed",
+                            self.completed_jobs.len()
+                        ));
+                    }
+                    if !self.worklist.is_empty() {
+                        parts.push(format!("{} queued", self.worklist.len()));
+                    }
+                    if let Some(ref t) = self.template_data {
+                        parts.push(format!("Template: {} bp", t.sequence.len()));
+                    }
+                    if let Some(ref r) = self.reference_data {
+                        parts.push(format!("References: {} seqs", r.len()));
+                    }
+                    if parts.is_empty() {
+                        ui.label("Load template and reference sequences to begin");
+                    } else {
+                        ui.label(parts.join(" | "));
+                    }
+                }
+            });
+        });
+
+        // Main content
+        egui::CentralPanel::default().show(ctx, |ui| {
+            match self.current_tab {
+                Tab::Input => self.show_input_tab(ui),
+                Tab::Analysis => self.show_analysis_tab(ui),
+                Tab::Worklist => self.show_worklist_tab(ui),
+                Tab::Results => self.show_results_tab(ui),
+            }
+        });
+
+        // Detail window
+        if self.show_detail_window {
+            self.show_variant_detail_window(ctx);
+        }
+        self.show_pinned_detail_windows(ctx);
+
+        if self.show_cell_comparison_window {
+            self.show_cell_comparison_window(ctx);
+        }
+
+        if self.show_compare_window {
+            self.show_compare_window(ctx);
+        }
+
+        if self.show_nominations_window {
+            self.show_nominations_window(ctx);
+        }
+
+        if self.show_top_candidates_window {
+            self.show_top_candidates_window(ctx);
+        }
+
+        if self.show_order_window {
+            self.show_order_window(ctx);
+        }
+
+        if self.show_assignment_window {
+            self.show_assignment_window(ctx);
+        }
+
+        if self.show_alignment_window {
+            self.show_alignment_window(ctx);
+        }
+
+        if self.show_query_window {
+            self.show_query_window(ctx);
+        }
+
+        if self.show_inspect_window {
+            self.show_inspect_window(ctx);
+        }
+
+        if self.show_consensus_window {
+            self.show_consensus_window(ctx);
+        }
+
+        if self.show_amplicon_window {
+            self.show_amplicon_search_window(ctx);
+        }
+
+        if self.show_pair_design_window {
+            self.show_pair_design_window(ctx);
+        }
+
+        if self.show_panel_matrix_window {
+            self.show_panel_matrix_window(ctx);
+        }
+
+        if self.show_sweep_window {
+            self.show_sweep_window(ctx);
+        }
+
+        if self.show_database_window {
+            self.show_database_window(ctx);
+        }
+
+        if self.show_command_palette {
+            self.show_command_palette(ctx);
+        }
+
+        if self.show_exit_dialog {
+            self.show_exit_dialog(ctx);
+        }
+    }
+}
+
+impl OligoscreenApp {
+    /// Whether a palette command can run in the current state (mirrors the
+    /// enabled state of the corresponding buttons).
+    fn command_enabled(&self, command: Command) -> bool {
+        let has_results = self.results.is_some();
+        match command {
+            Command::AddToWorklist | Command::QuickScan | Command::ParameterSweep => {
+                self.can_add_to_worklist()
+            }
+            Command::ProcessWorklist => {
+                !self.worklist.is_empty() && self.worklist_state == WorklistState::Idle
+            }
+            Command::StopAfterCurrent => self.worklist_state == WorklistState::Processing,
+            Command::CancelCurrentJob => self.cancel_token.is_some(),
+            Command::OpenSession => self.can_open_session(),
+            Command::SaveWorklist => !self.worklist.is_empty(),
+            Command::InspectReferences => self.reference_data.is_some(),
+            Command::BuildConsensus => {
+                self.template_data.is_some() && self.reference_data.is_some()
+            }
+            Command::ExportBatchSummary | Command::OpenCrossJobSearch => {
+                !self.completed_jobs.is_empty()
+            }
+            Command::SaveFilteredResults => self.active_exclusivity_filter().is_some(),
+            Command::ExportDesignList => self
+                .results
+                .as_ref()
+                .is_some_and(|r| !r.nominations.is_empty()),
+            Command::SaveResults
+            | Command::SaveSummaryResults
+            | Command::SaveIndexedResults
+            | Command::ExportMatrixCsv
+            | Command::ExportPositionsTable
+            | Command::ExportTrack
+            | Command::ExportParameters
+            | Command::UseJobParameters
+            | Command::CopyHeatmapImage
+            | Command::ExportHeatmapImage
+            | Command::OpenNominations
+            | Command::OpenTopCandidates
+            | Command::OpenAmpliconSearch
+            | Command::OpenPairDesign
+            | Command::OpenPanelMatrix
+            | Command::OpenCompare => has_results,
+            Command::LoadTemplate
+            | Command::LoadReferences
+            | Command::AddExclusivityFile
+            | Command::SelectOutputFolder
+            | Command::SaveSession
+            | Command::LoadWorklist
+            | Command::LoadResults
+            | Command::OpenOrderSelection
+            | Command::GoToTab(_) => true,
+        }
+    }
+
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::LoadTemplate => self.load_template_file(),
+            Command::LoadReferences => self.load_reference_file(),
+            Command::InspectReferences => self.show_inspect_window = true,
+            Command::BuildConsensus => self.show_consensus_window = true,
+            Command::AddExclusivityFile => {
+                self.use_differential = true;
+                self.add_exclusivity_file();
+            }
+            Command::AddToWorklist => self.add_to_worklist(),
+            Command::QuickScan => self.start_quick_scan(),
+            Command::ParameterSweep => self.show_sweep_window = true,
+            Command::SelectOutputFolder => self.select_output_folder(),
+            Command::ProcessWorklist => self.start_worklist_processing(),
+            Command::StopAfterCurrent => self.worklist_state = WorklistState::StopRequested,
+            Command::CancelCurrentJob => self.cancel_current_job(),
+            Command::OpenSession => self.open_session(),
+            Command::SaveSession => self.save_session(),
+            Command::LoadWorklist => self.load_worklist_manifest(),
+            Command::SaveWorklist => self.save_worklist_manifest(),
+            Command::LoadResults => self.load_results_into_completed(),
+            Command::SaveResults => self.save_results(),
+            Command::SaveSummaryResults => self.save_summary_results(),
+            Command::SaveIndexedResults => self.save_indexed_results(),
+            Command::SaveFilteredResults => self.save_filtered_results(),
+            Command::ExportMatrixCsv => self.export_heatmap_matrix(),
+            Command::ExportPositionsTable => self.export_positions_table(),
+            Command::ExportTrack => self.export_metric_track(),
+            Command::ExportBatchSummary => self.export_batch_summary(),
+            Command::ExportParameters => self.export_parameters(),
+            Command::UseJobParameters => self.use_job_parameters(),
+            Command::CopyHeatmapImage => self.copy_whole_heatmap(ctx),
+            Command::ExportHeatmapImage => self.export_heatmap_image(),
+            Command::OpenNominations => self.show_nominations_window = true,
+            Command::OpenTopCandidates => self.show_top_candidates_window = true,
+            Command::OpenOrderSelection => self.show_order_window = true,
+            Command::ExportDesignList => self.export_nominations(),
+            Command::OpenCrossJobSearch => self.show_query_window = true,
+            Command::OpenAmpliconSearch => self.show_amplicon_window = true,
+            Command::OpenPairDesign => self.show_pair_design_window = true,
+            Command::OpenPanelMatrix => self.show_panel_matrix_window = true,
+            Command::OpenCompare => self.show_compare_window = true,
+            Command::GoToTab(tab) => self.current_tab = tab,
+        }
+    }
+
+    /// Fuzzy-searchable list of all actions; arrows move, Enter runs, Escape closes.
+    fn show_command_palette(&mut self, ctx: &egui::Context) {
+        let mut matches: Vec<(i32, Command, bool)> = Command::ALL
+            .iter()
+            .filter_map(|&c| {
+                fuzzy_score(&self.palette_query, c.label())
+                    .map(|score| (score, c, self.command_enabled(c)))
+            })
+            .collect();
+        matches.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.show_command_palette = false;
+            return;
+        }
+        if down {
+            self.palette_selected += 1;
+        }
+        if up {
+            self.palette_selected = self.palette_selected.saturating_sub(1);
+        }
+        self.palette_selected = self.palette_selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = None;
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.palette_query)
+                        .hint_text("Type a command...")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.palette_selected = 0;
+                }
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.colored_label(egui::Color32::GRAY, "No matching commands");
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("command_palette_scroll")
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (i, &(_, command, enabled)) in matches.iter().enumerate() {
+                            let selected = i == self.palette_selected;
+                            let row = ui.add_enabled(
+                                enabled,
+                                egui::SelectableLabel::new(selected, command.label()),
+                            );
+                            if selected {
+                                row.scroll_to_me(None);
+                            }
+                            if row.clicked() {
+                                chosen = Some(command);
+                            }
+                        }
+                    });
+            });
+
+        if enter
+            && let Some(&(_, command, true)) = matches.get(self.palette_selected)
+        {
+            chosen = Some(command);
+        }
+        if let Some(command) = chosen {
+            self.show_command_palette = false;
+            self.run_command(command, ctx);
+        }
+    }
+}
+
+impl OligoscreenApp {
+    fn show_input_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Input Data");
+        ui.separator();
+
+        // --- Template Sequence ---
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Template Sequence");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Clear").clicked() {
+                        self.template_file_name = None;
+                        self.template_data = None;
+                        self.batch_templates.clear();
+                        self.template_error = None;
+                        self.panel_report = None;
+                        self.consensus = None;
+                        self.consensus_rx = None;
+                        self.reference_dedup = None;
+                    }
+                    if ui.button("Load File").clicked() {
+                        self.load_template_file();
+                    }
+                });
+            });
+
+            ui.label(
+                "FASTA format (A, C, G, T and IUPAC codes; RNA U is read as T). Several \
+                 sequences are screened as a multi-template job.",
+            );
+
+            if let Some(ref error) = self.template_error {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+            }
+            if let Some(ref data) = self.template_data {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(100, 200, 100),
+                        format!(
+                            "File: {}",
+                            self.template_file_name.as_deref().unwrap_or("unknown")
+                        ),
+                    );
+                });
+                ui.colored_label(
+                    egui::Color32::from_rgb(100, 200, 100),
+                    format!("Sequence: {} ({} bp)", data.name, data.sequence.len()),
+                );
+                if !self.batch_templates.is_empty() {
+                    let total = 1 + self.batch_templates.len();
+                    ui.colored_label(
+                        egui::Color32::from_rgb(100, 200, 100),
+                        format!(
+                            "Multi-template batch: {} templates against the same references",
+                            total
+                        ),
+                    )
+                    .on_hover_text(
+                        std::iter::once(data)
+                            .chain(&self.batch_templates)
+                            .map(|t| format!("{} ({} bp)", t.name, t.sequence.len()))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                    ui.label(
+                        "Region, masking, and the Analysis Setup previews refer to the first \
+                         template",
+                    );
+                }
+                if data.rna {
+                    ui.label("RNA input: U bases are analysed as T");
+                }
+                let ambiguities = count_ambiguities(&data.sequence);
+                if ambiguities > 0 {
+                    ui.label(format!(
+                        "Consensus template: {} ambiguity code(s), {} (Analysis Setup)",
+                        ambiguities,
+                        match self.params.template_ambiguity {
+                            TemplateAmbiguityPolicy::Reject => "windows over them are skipped",
+                            TemplateAmbiguityPolicy::Expand => "windows over them are expanded",
+                        }
+                    ));
+                }
+            } else {
+                ui.colored_label(egui::Color32::GRAY, "No template loaded");
+            }
+        });
+
+        ui.add_space(5.0);
+
+        // --- Reference Sequences ---
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Reference Sequences");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let loading = self.reference_load.is_some();
+                    if ui
+                        .add_enabled(!loading, egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        self.reference_file_name = None;
+                        self.reference_data = None;
+                        self.reference_error = None;
+                        self.panel_report = None;
+                        self.consensus = None;
+                        self.consensus_rx = None;
+                        self.reference_dedup = None;
+                    }
+                    if ui
+                        .add_enabled(!loading, egui::Button::new("Load File"))
+                        .clicked()
+                    {
+                        self.load_reference_file();
+                    }
+                    if ui
+                        .add_enabled(self.reference_data.is_some(), egui::Button::new("Inspect"))
+                        .on_hover_text(
+                            "Length distribution, ambiguities, duplicates, and divergent records",
+                        )
+                        .clicked()
+                    {
+                        self.show_inspect_window = true;
+                    }
+                    let can_build = self.template_data.is_some() && self.reference_data.is_some();
+                    if ui
+                        .add_enabled(can_build, egui::Button::new("Consensus"))
+                        .on_hover_text(
+                            "Majority or IUPAC consensus of the references on the template, \
+                             to use as the template",
+                        )
+                        .clicked()
+                    {
+                        self.show_consensus_window = true;
+                    }
+                });
+            });
+
+            ui.label("Multiple sequences in FASTA format (unaligned)");
+
+            if let Some(ref load) = self.reference_load {
+                let (read, total) = load.progress;
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!(
+                        "Loading {}: {:.0} / {:.0} MB",
+                        load.file_name,
+                        read as f64 / 1e6,
+                        total as f64 / 1e6
+                    ));
+                });
+                if total > 0 {
+                    ui.add(egui::ProgressBar::new(read as f32 / total as f32).show_percentage());
+                }
+            }
+            if let Some(ref error) = self.reference_error {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+            }
+            if let (None, Some(references)) = (&self.reference_dedup, &self.reference_data) {
+                self.reference_dedup = Some(ReferenceGroups::new(references).summary());
+            }
+            if let Some(ref data) = self.reference_data {
+                let min_len = data.sequences.iter().map(|s| s.len()).min().unwrap_or(0);
+                let max_len = data.sequences.iter().map(|s| s.len()).max().unwrap_or(0);
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(100, 200, 100),
+                        format!(
+                            "File: {}",
+                            self.reference_file_name.as_deref().unwrap_or("unknown")
+                        ),
+                    );
+                });
+                ui.colored_label(
+                    egui::Color32::from_rgb(100, 200, 100),
+                    format!(
+                        "{} sequences ({}-{} bp)",
+                        data.len(),
+                        min_len,
+                        max_len
+                    ),
+                );
+                if let Some(dedup) = self.reference_dedup.filter(|d| d.duplicates() > 0) {
+                    ui.label(format!(
+                        "{} distinct sequences: {} duplicates share their alignments \
+                         (largest group {} copies)",
+                        dedup.distinct,
+                        dedup.duplicates(),
+                        dedup.largest_group
+                    ))
+                    .on_hover_text(
+                        "Identical references are aligned once per window and counted once \
+                         per copy",
+                    );
+                }
+            } else {
+                ui.colored_label(egui::Color32::GRAY, "No references loaded");
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // --- Differential Analysis / Exclusivity Sequences ---
+        ui.checkbox(&mut self.use_differential, "Use differential analysis");
+
+        if self.use_differential {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Exclusivity Sequences");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Add File").clicked() {
+                            self.add_exclusivity_file();
+                        }
+                    });
+                });
+
+                ui.label("Sequences that oligos must be distinct from (off-targets)");
+                ui.checkbox(
+                    &mut self.params.exclusivity_only,
+                    "Exclusivity only (skip conservation analysis)",
+                )
+                .on_hover_text(
+                    "Only compute exclusivity metrics per window, without aligning references. \
+                     References are not needed; use this for a fast specificity map of a known \
+                     target region.",
+                );
+
+                if let Some(ref error) = self.exclusivity_error {
+                    ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+                }
+
+                if self.exclusivity_files.is_empty() {
+                    ui.colored_label(egui::Color32::GRAY, "No exclusivity files loaded");
+                } else {
+                    let mut remove_idx = None;
+                    for (i, entry) in self.exclusivity_files.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("X").clicked() {
+                                remove_idx = Some(i);
+                            }
+                            ui.label(format!(
+                                "{} - {} sequences ({}-{} bp)",
+                                entry.file_name,
+                                entry.sequence_count,
+                                entry.min_length,
+                                entry.max_length
+                            ));
+                        });
+                    }
+                    if let Some(idx) = remove_idx {
+                        self.pending_remove_excl = Some(idx);
+                    }
+
+                    // Summary
+                    if let Some(ref data) = self.exclusivity_data {
+                        ui.separator();
+                        ui.colored_label(
+                            egui::Color32::from_rgb(100, 200, 100),
+                            format!(
+                                "Total: {} exclusivity sequences from {} file(s)",
+                                data.len(),
+                                self.exclusivity_files.len()
+                            ),
+                        )
+                        .on_hover_text(
+                            "With two or more files, each file is also scored as its own group, \
+                             so results show which group limits specificity",
+                        );
+                    }
+                }
+
+                ui.separator();
+                ui.label("Ignore sequences by name (one pattern per line, * and ? wildcards):")
+                    .on_hover_text(
+                        "Matching sequences (e.g. known database artifacts) are still screened, \
+                         but their hits are left out of the effective minimum mismatches. \
+                         Patterns match whole names, case-insensitively; the patterns and \
+                         ignored names are recorded in the results.",
+                    );
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.exclusivity_ignore_text)
+                        .desired_rows(2)
+                        .hint_text("e.g. *synthetic construct*"),
+                );
+                if let Some(ref data) = self.exclusivity_data {
+                    let patterns = self.current_params().exclusivity_ignore_patterns;
+                    if !patterns.is_empty() {
+                        let ignored = data
+                            .names
+                            .iter()
+                            .filter(|n| patterns.iter().any(|p| name_matches_pattern(n, p)))
+                            .count();
+                        ui.label(format!(
+                            "{} of {} loaded sequences will be ignored",
+                            ignored,
+                            data.len()
+                        ));
+                    }
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+
+        // --- Output Folder ---
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Output Folder (Optional)");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if self.output_folder.is_some()
+                        && ui.button("Clear").clicked()
+                    {
+                            self.output_folder = None;
+                        }
+                    if ui.button("Select Folder").clicked() {
+                        self.select_output_folder();
+                    }
+                });
+            });
+            ui.label(
+                "If set, results will be auto-saved as JSON to this folder after analysis.",
+            );
+            ui.checkbox(
+                &mut self.save_binary_results,
+                format!("Save as compressed binary (.{})", BINARY_RESULTS_EXTENSION),
+            )
+            .on_hover_text(
+                "Several times smaller than JSON and much faster to load; opens with \
+                 Load Results from File",
+            );
+            ui.checkbox(
+                &mut self.store_in_database,
+                format!("Store in a results database ({})", RESULTS_DATABASE_FILE),
+            )
+            .on_hover_text(
+                "Add each job to one SQLite database in the folder instead of writing a file \
+                 per job; browse, tag, and open stored jobs from the Results tab",
+            );
+            if let Some(ref folder) = self.output_folder {
+                ui.colored_label(egui::Color32::from_rgb(100, 200, 100), format!("Folder: {}", folder));
+            } else {
+                ui.colored_label(egui::Color32::GRAY, "No output folder selected (manual save only)");
+            }
+        });
+
+        ui.add_space(10.0);
+
+        // --- Add to Worklist ---
+        let can_add = self.can_add_to_worklist();
+        let warn_excl =
+            self.use_differential && self.exclusivity_data.is_none();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(can_add, egui::Button::new("Add to Worklist"))
+                .clicked()
+            {
+                self.add_to_worklist();
+            }
+            if !can_add {
+                let needed = if self.params.exclusivity_only && self.use_differential {
+                    "Load template and exclusivity files first"
+                } else {
+                    "Load template and references first"
+                };
+                ui.colored_label(egui::Color32::GRAY, needed);
+            }
+            if warn_excl {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Differential enabled but no exclusivity files loaded",
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(can_add, egui::Button::new("Quick Scan"))
+                .on_hover_text(
+                    "Run an approximate screen now: the middle oligo length only, at a coarse \
+                     step size, against an even sample of the references. The inputs stay \
+                     loaded so the full screen can be queued afterwards.",
+                )
+                .clicked()
+            {
+                self.start_quick_scan();
+            }
+            ui.label("about");
+            ui.add(
+                egui::DragValue::new(&mut self.quick_scan.target_positions)
+                    .range(10..=100_000),
+            );
+            ui.label("positions, at most");
+            ui.add(
+                egui::DragValue::new(&mut self.quick_scan.max_references).range(1..=100_000),
+            );
+            ui.label("references");
+        });
+        if ui
+            .add_enabled(can_add, egui::Button::new("Parameter Sweep..."))
+            .on_hover_text(
+                "Queue one job per combination of oligo length ranges, methods, and \
+                 mismatch caps on the current inputs",
+            )
+            .clicked()
+        {
+            self.show_sweep_window = true;
+        }
+    }
+
+    fn show_analysis_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Analysis Setup");
+        ui.separator();
+        ui.label("These settings apply to all jobs added to the worklist.");
+        for source in &self.config_sources {
+            ui.label(format!("Defaults loaded from {}", source.display()));
+        }
+        for error in &self.config_errors {
+            ui.colored_label(egui::Color32::RED, format!("Ignored defaults file {}", error));
+        }
+        if let Some(ref source) = self.params_imported_from {
+            ui.label(format!("Parameters copied from {}", source));
+        }
+        if let Some(id) = self.editing_job {
+            let editable = self.edited_job_index().is_some();
+            ui.horizontal(|ui| {
+                if editable {
+                    ui.label(format!(
+                        "Editing queued job #{}: changes apply to it when updated",
+                        id
+                    ));
+                } else {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("Job #{} is no longer queued and cannot be updated", id),
+                    );
+                }
+                if ui
+                    .add_enabled(editable, egui::Button::new("Update Job"))
+                    .clicked()
+                {
+                    self.update_edited_job();
+                }
+                if ui.button("Cancel Edit").clicked() {
+                    self.editing_job = None;
+                }
+            });
+        }
+        ui.add_space(5.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // Pairwise Aligner Settings
+            ui.group(|ui| {
+                ui.heading("Pairwise Aligner Settings");
+
+                ui.horizontal(|ui| {
+                    ui.label("Match score:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.pairwise.match_score).range(0..=10),
+                    );
+                    ui.add_space(20.0);
+                    ui.label("Mismatch score:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.pairwise.mismatch_score)
+                            .range(-10..=0),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Gap open penalty:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.pairwise.gap_open_penalty)
+                            .range(-20..=0),
+                    );
+                    ui.add_space(20.0);
+                    ui.label("Gap extend penalty:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.pairwise.gap_extend_penalty)
+                            .range(-20..=0),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Alignment mode:");
+                    let mode = &mut self.params.pairwise.alignment_mode;
+                    egui::ComboBox::from_id_salt("alignment_mode")
+                        .selected_text(mode.label())
+                        .show_ui(ui, |ui| {
+                            for option in AlignmentMode::ALL {
+                                ui.selectable_value(mode, option, option.label());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    "Local finds the best-matching stretch and may leave out oligo ends; \
+                     semi-global aligns the whole oligo within the reference; global aligns \
+                     oligo and reference end to end, for references already cut to the site",
+                );
+
+                ui.horizontal(|ui| {
+                    ui.label("Aligner:");
+                    let backend = &mut self.params.pairwise.aligner_backend;
+                    egui::ComboBox::from_id_salt("aligner_backend")
+                        .selected_text(backend.label())
+                        .show_ui(ui, |ui| {
+                            for option in AlignerBackend::ALL {
+                                ui.selectable_value(backend, option, option.label());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    "Striped SIMD scores each reference with a vectorized kernel and only \
+                     traces back around the best hit; much faster against long references. \
+                     Results match the standard aligner except where equally good hits tie. \
+                     Applies to local mode only.",
+                );
+
+                ui.horizontal(|ui| {
+                    ui.label("No-match rule:");
+                    let rule = &mut self.params.pairwise.no_match_rule;
+                    egui::ComboBox::from_id_salt("no_match_rule")
+                        .selected_text(match rule {
+                            NoMatchRule::MismatchCap => "Mismatch cap",
+                            NoMatchRule::MinScore(_) => "Minimum alignment score",
+                            NoMatchRule::MinAlignedFraction(_) => "Minimum aligned fraction",
+                            NoMatchRule::MinPercentIdentity(_) => "Minimum percent identity",
+                        })
+                        .show_ui(ui, |ui| {
+                            let is_cap = matches!(rule, NoMatchRule::MismatchCap);
+                            if ui.selectable_label(is_cap, "Mismatch cap").clicked() {
+                                *rule = NoMatchRule::MismatchCap;
+                            }
+                            let is_score = matches!(rule, NoMatchRule::MinScore(_));
+                            if ui.selectable_label(is_score, "Minimum alignment score").clicked()
+                                && !is_score
+                            {
+                                *rule = NoMatchRule::MinScore(30);
+                            }
+                            let is_frac = matches!(rule, NoMatchRule::MinAlignedFraction(_));
+                            if ui.selectable_label(is_frac, "Minimum aligned fraction").clicked()
+                                && !is_frac
+                            {
+                                *rule = NoMatchRule::MinAlignedFraction(80.0);
+                            }
+                            let is_ident = matches!(rule, NoMatchRule::MinPercentIdentity(_));
+                            if ui.selectable_label(is_ident, "Minimum percent identity").clicked()
+                                && !is_ident
+                            {
+                                *rule = NoMatchRule::MinPercentIdentity(80.0);
+                            }
+                        });
+                });
+
+                ui.horizontal(|ui| match &mut self.params.pairwise.no_match_rule {
+                    NoMatchRule::MismatchCap => {
+                        ui.label("Maximum allowed mismatches:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.params.pairwise.max_mismatches)
+                                .range(0..=50),
+                        );
+                    }
+                    NoMatchRule::MinScore(score) => {
+                        ui.label("Minimum alignment score:");
+                        ui.add(egui::DragValue::new(score).range(-100..=500));
+                    }
+                    NoMatchRule::MinAlignedFraction(pct) => {
+                        ui.label("Minimum aligned fraction of oligo (%):");
+                        ui.add(egui::DragValue::new(pct).range(1.0..=100.0).speed(1.0));
+                    }
+                    NoMatchRule::MinPercentIdentity(pct) => {
+                        ui.label("Minimum identity over full oligo (%):");
+                        ui.add(egui::DragValue::new(pct).range(1.0..=100.0).speed(1.0));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Maximum indel bases:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.pairwise.max_indels).range(0..=10),
+                    );
+                })
+                .response
+                .on_hover_text(
+                    "Alignments with up to this many inserted plus deleted bases are kept, their \
+                     variants written with '-' for deleted and lowercase for inserted bases. \
+                     Such variants are never merged into degenerate variants.",
+                );
+                ui.label(
+                    "Alignments failing this rule, or with more indel bases than allowed, are \
+                     recorded as 'no match'; partial alignments are extended to the full oligo length.",
+                );
+                ui.checkbox(
+                    &mut self.params.pairwise.both_strands,
+                    "Also search the reverse strand of references",
+                )
+                .on_hover_text(
+                    "Each oligo is also aligned against the reverse complement of every \
+                     reference (including exclusivity sequences); the better hit is kept.",
+                );
+                let mut weight_three_prime = self.params.pairwise.three_prime.is_some();
+                if ui
+                    .checkbox(&mut weight_three_prime, "Weight mismatches at the oligo's 3' end")
+                    .on_hover_text(
+                        "Mismatches in the last bases of the oligo count several times in the \
+                         exclusivity mismatch counts, since a 3'-end mismatch is what keeps a \
+                         primer from extending.",
+                    )
+                    .changed()
+                {
+                    self.params.pairwise.three_prime =
+                        weight_three_prime.then(ThreePrimeWeighting::default);
+                }
+                if let Some(three_prime) = &mut self.params.pairwise.three_prime {
+                    ui.horizontal(|ui| {
+                        ui.label("3' end bases:");
+                        ui.add(egui::DragValue::new(&mut three_prime.bases).range(1..=15));
+                        ui.label("Weight:");
+                        ui.add(egui::DragValue::new(&mut three_prime.weight).range(1..=10));
+                    });
+                    ui.checkbox(
+                        &mut three_prime.references,
+                        "Also weight the mismatch cap for reference matching",
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Ambiguity codes in references:");
+                    let policy = &mut self.params.pairwise.reference_ambiguity;
+                    egui::ComboBox::from_id_salt("reference_ambiguity")
+                        .selected_text(policy.label())
+                        .show_ui(ui, |ui| {
+                            for option in ReferenceAmbiguityPolicy::ALL {
+                                ui.selectable_value(policy, option, option.label());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    "How R, Y, N and other IUPAC codes in reference and exclusivity sequences \
+                     are aligned and tallied",
+                );
+
+                ui.horizontal(|ui| {
+                    ui.label("Ambiguity codes in the template:");
+                    let policy = &mut self.params.template_ambiguity;
+                    egui::ComboBox::from_id_salt("template_ambiguity")
+                        .selected_text(policy.label())
+                        .show_ui(ui, |ui| {
+                            for option in TemplateAmbiguityPolicy::ALL {
+                                ui.selectable_value(policy, option, option.label());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(format!(
+                    "For consensus templates: skip the windows over IUPAC codes, or align each \
+                     of the concrete oligos a window stands for (up to {}) and keep every \
+                     sequence's best hit",
+                    MAX_TEMPLATE_EXPANSIONS
+                ));
+            });
+
+            ui.add_space(10.0);
+
+            // Analysis method selection
+            ui.group(|ui| {
+                ui.heading("Analysis Method");
+
+                ui.radio_value(
+                    &mut self.method_selection,
+                    MethodSelection::NoAmbiguities,
+                    "No Ambiguities - Find all unique exact variants",
+                );
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.method_selection,
+                        MethodSelection::FixedAmbiguities,
+                        "Fixed Ambiguities - Use up to N ambiguity codes per variant",
+                    );
+                });
+
+                if self.method_selection == MethodSelection::FixedAmbiguities {
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.label("Max ambiguities:");
+                        let mut n = self.params.method.get_fixed_ambiguities();
+                        if ui.add(egui::DragValue::new(&mut n).range(0..=100)).changed() {
+                            self.params.method = AnalysisMethod::FixedAmbiguities(n);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.checkbox(&mut self.optimal_merging, "Optimal merging, up to")
+                            .on_hover_text(
+                                "Branch-and-bound search for the fewest variants covering every \
+                                 matched sequence, starting from the greedy result. Windows where \
+                                 the search runs out of time keep the best solution found.",
+                            );
+                        ui.add_enabled(
+                            self.optimal_merging,
+                            egui::DragValue::new(&mut self.optimal_merge_budget_ms)
+                                .range(1..=60_000)
+                                .suffix(" ms"),
+                        );
+                        ui.label("per window");
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.method_selection,
+                        MethodSelection::Incremental,
+                        "Incremental - Find variants covering X% of remaining sequences",
+                    );
+                });
+
+                if self.method_selection == MethodSelection::Incremental {
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.label("Target coverage per step (%):");
+                        let mut pct = self.params.method.get_incremental_pct();
+                        let max_amb = self.params.method.get_incremental_max_amb();
+                        if ui
+                            .add(egui::DragValue::new(&mut pct).range(1..=100))
+                            .changed()
+                        {
+                            self.params.method = AnalysisMethod::Incremental(pct, max_amb);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.checkbox(
+                            &mut self.incremental_limit_ambiguities,
+                            "Limit ambiguities:",
+                        );
+                        ui.add_enabled(
+                            self.incremental_limit_ambiguities,
+                            egui::DragValue::new(&mut self.incremental_max_ambiguities)
+                                .range(0..=100),
+                        );
+                        ui.label("max");
+                    });
+                    if self.incremental_limit_ambiguities {
+                        ui.horizontal(|ui| {
+                            ui.add_space(20.0);
+                            ui.label(
+                                "If target % cannot be reached, accepts best variant within limit.",
+                            );
+                        });
+                    }
+                }
+
+                if self.method_selection != MethodSelection::NoAmbiguities {
+                    let costs = &mut self.params.ambiguity_costs;
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.label("Ambiguity code costs - 2-fold (RYSWKM):")
+                            .on_hover_text(
+                                "Each ambiguous position adds its code's cost; the ambiguity limit \
+                                 above is a budget of total cost per variant, and cheaper variants \
+                                 are preferred. All costs 1 counts ambiguous positions.",
+                            );
+                        ui.add(egui::DragValue::new(&mut costs.two_fold).range(0..=20));
+                        ui.label("3-fold (BDHV):");
+                        ui.add(egui::DragValue::new(&mut costs.three_fold).range(0..=20));
+                        ui.label("N:");
+                        ui.add(egui::DragValue::new(&mut costs.four_fold).range(0..=20));
+                    });
+                }
+            });
+
+            ui.add_space(10.0);
+
+            // Global options
+            ui.group(|ui| {
+                ui.heading("Global Options");
+                ui.checkbox(
+                    &mut self.params.exclude_n,
+                    "Exclude N (any base) as ambiguity code",
+                );
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.limit_window_time, "Limit compute time per window to")
+                        .on_hover_text(
+                            "Windows over the budget (e.g. merging on hypervariable regions) keep \
+                             the variants merged so far, leave the rest as exact variants, and \
+                             are flagged as truncated.",
+                        );
+                    ui.add_enabled(
+                        self.limit_window_time,
+                        egui::DragValue::new(&mut self.window_time_budget_ms)
+                            .range(10..=600_000)
+                            .suffix(" ms"),
+                    );
+                });
+                let tm = &mut self.params.tm_conditions;
+                ui.horizontal(|ui| {
+                    ui.label("Tm conditions - Na+:").on_hover_text(
+                        "Used for each window's nearest-neighbor melting temperature (detail \
+                         view, Tm heatmap metric, and Tm darkening)",
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut tm.na_mm)
+                            .range(0.0..=2000.0)
+                            .speed(1.0)
+                            .suffix(" mM"),
+                    );
+                    ui.label("Mg2+:");
+                    ui.add(
+                        egui::DragValue::new(&mut tm.mg_mm)
+                            .range(0.0..=100.0)
+                            .speed(0.1)
+                            .suffix(" mM"),
+                    );
+                    ui.label("Oligo:");
+                    ui.add(
+                        egui::DragValue::new(&mut tm.oligo_nm)
+                            .range(1.0..=100_000.0)
+                            .speed(5.0)
+                            .suffix(" nM"),
+                    );
+                });
+            });
+
+            ui.add_space(10.0);
+
+            // Oligo length range
+            ui.group(|ui| {
+                ui.heading("Oligo Length Range");
+                ui.horizontal(|ui| {
+                    ui.label("Minimum length:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.min_oligo_length).range(3..=100),
+                    );
+                    ui.add_space(20.0);
+                    ui.label("Maximum length:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.max_oligo_length).range(3..=100),
+                    );
+                });
+
+                if self.params.min_oligo_length > self.params.max_oligo_length {
+                    self.params.max_oligo_length = self.params.min_oligo_length;
+                }
+
+                let range = self.params.max_oligo_length - self.params.min_oligo_length + 1;
+                if range > 20 {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "Warning: Large length range ({}) may take significant time",
+                            range
+                        ),
+                    );
+                }
+            });
+
+            ui.add_space(10.0);
+
+            // Template region
+            ui.group(|ui| {
+                ui.heading("Template Region");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.restrict_region, "Screen only template positions");
+                    ui.add_enabled(
+                        self.restrict_region,
+                        egui::DragValue::new(&mut self.region_from).range(1..=usize::MAX),
+                    );
+                    ui.label("to");
+                    ui.add_enabled(
+                        self.restrict_region,
+                        egui::DragValue::new(&mut self.region_to).range(1..=usize::MAX),
+                    );
+                    if !self.annotations.is_empty() {
+                        let template_name =
+                            self.template_data.as_ref().map_or("", |t| t.name.as_str());
+                        let features = features_for_template(&self.annotations, template_name);
+                        let mut picked: Option<(usize, usize)> = None;
+                        egui::ComboBox::from_id_salt("region_feature")
+                            .selected_text("From feature")
+                            .show_ui(ui, |ui| {
+                                for feature in features {
+                                    let label = format!(
+                                        "{} ({}, {}-{})",
+                                        feature.name,
+                                        feature.kind,
+                                        feature.start + 1,
+                                        feature.end
+                                    );
+                                    if ui.selectable_label(false, label).clicked() {
+                                        picked = Some((feature.start, feature.end));
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text("Use a feature of the loaded annotation track");
+                        if let Some((start, end)) = picked {
+                            self.restrict_region = true;
+                            self.region_from = start + 1;
+                            self.region_to = end;
+                        }
+                    }
+                });
+                if self.region_to < self.region_from {
+                    self.region_to = self.region_from;
+                }
+                if self.restrict_region {
+                    ui.label(
+                        "Only windows lying entirely within these positions (1-based, \
+                         inclusive) are screened; the step size and runtime estimate count \
+                         this region only.",
+                    );
+                    let region_len = self.region_to - self.region_from + 1;
+                    if region_len < self.params.max_oligo_length as usize {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!(
+                                "Warning: region ({} bp) is shorter than the longest oligo",
+                                region_len
+                            ),
+                        );
+                    }
+                    if let Some(ref template) = self.template_data
+                        && self.region_from > template.sequence.len()
+                    {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!(
+                                "Warning: region starts past the end of the current template \
+                                 ({} bp)",
+                                template.sequence.len()
+                            ),
+                        );
+                    }
+                } else {
+                    ui.label("The whole template is screened");
+                }
+            });
+
+            ui.add_space(10.0);
+
+            // Masked regions
+            let mut load_mask = false;
+            ui.group(|ui| {
+                ui.heading("Masked Regions");
+                ui.label(
+                    "Windows overlapping these template positions are skipped, e.g. known \
+                     primer sites or repeats (1-based start-end, one per line):",
+                );
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.mask_text)
+                        .desired_rows(3)
+                        .hint_text("e.g. 1200-1260"),
+                );
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Load BED/GFF...")
+                        .on_hover_text("Mask the features of an annotation file")
+                        .clicked()
+                    {
+                        load_mask = true;
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.mask_text.clear();
+                    }
+                });
+                match parse_masked_regions(&self.mask_text) {
+                    Ok(regions) => {
+                        let template_len = self.template_data.as_ref().map(|t| t.sequence.len());
+                        if let Some(template_len) = template_len.filter(|&len| len > 0) {
+                            ui.label("Drag on the strip to mask a stretch of the template:");
+                            if let Some(region) = self.mask_strip(ui, template_len, &regions) {
+                                let mut regions = regions.clone();
+                                regions.push(region);
+                                self.mask_text = format_masked_regions(&merge_regions(regions));
+                            }
+                        }
+                        if !regions.is_empty() {
+                            let bases: usize = regions.iter().map(|(start, end)| end - start).sum();
+                            ui.label(format!("{} regions, {} bases masked", regions.len(), bases));
+                        }
+                    }
+                    Err(e) => {
+                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), e);
+                    }
+                }
+                if let Some(ref e) = self.mask_load_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), e);
+                }
+            });
+            if load_mask {
+                self.load_mask_file();
+            }
+
+            ui.add_space(10.0);
+
+            // Resolution
+            ui.group(|ui| {
+                ui.heading("Analysis Resolution");
+                ui.horizontal(|ui| {
+                    ui.label("Step size (bases):");
+                    ui.add_enabled(
+                        !self.resolution_auto,
+                        egui::DragValue::new(&mut self.params.resolution).range(1..=100),
+                    );
+                    ui.add_space(20.0);
+                    ui.checkbox(&mut self.resolution_auto, "Auto, targeting");
+                    ui.add_enabled(
+                        self.resolution_auto,
+                        egui::DragValue::new(&mut self.resolution_target_positions)
+                            .range(10..=1_000_000),
+                    );
+                    ui.label("positions per length");
+                });
+                if self.resolution_auto {
+                    ui.label(
+                        "Step size is chosen per job from the template length when the job is added.",
+                    );
+                    if let Some(ref template) = self.template_data {
+                        let (start, end) =
+                            self.current_params().screened_region(template.sequence.len());
+                        ui.label(format!(
+                            "Current template ({} bp screened): step size {}",
+                            end - start,
+                            auto_resolution(
+                                end - start,
+                                self.params.min_oligo_length,
+                                self.resolution_target_positions,
+                            )
+                        ));
+                    }
+                } else {
+                    ui.label("Lower values = more positions analyzed, higher resolution");
+                }
+                ui.horizontal(|ui| {
+                    ui.checkbox(
+                        &mut self.refine,
+                        "Refine: re-screen at step 1 around windows needing",
+                    )
+                    .on_hover_text(
+                        "Two passes: a coarse scan at the step size above, then every \
+                         position within one step of a promising coarse window. Has no \
+                         effect at step size 1 or in exclusivity-only screens.",
+                    );
+                    ui.add_enabled(
+                        self.refine,
+                        egui::DragValue::new(&mut self.refine_max_variants).range(0..=1000),
+                    );
+                    ui.label("or fewer variants");
+                });
+            });
+
+            ui.add_space(10.0);
+
+            // Reference trimming
+            ui.group(|ui| {
+                ui.heading("Reference Trimming");
+                ui.horizontal(|ui| {
+                    ui.checkbox(
+                        &mut self.trim_references,
+                        "Trim references to the template region, keeping",
+                    );
+                    ui.add_enabled(
+                        self.trim_references,
+                        egui::DragValue::new(&mut self.trim_flank).range(0..=100_000),
+                    );
+                    ui.label("flanking bases");
+                });
+                ui.label(
+                    "Each reference is mapped to the template once (shared 16-mers) and cut to \
+                     the matching region before screening. Speeds up whole-genome references; \
+                     references without a match are kept whole. Exclusivity sequences are not \
+                     trimmed.",
+                );
+            });
+
+            ui.add_space(10.0);
+
+            // Coverage threshold
+            ui.group(|ui| {
+                ui.heading("Coverage Threshold");
+                ui.horizontal(|ui| {
+                    ui.label("Target coverage (%):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.coverage_threshold)
+                            .range(1.0..=100.0),
+                    );
+                });
+                ui.label("Number of variants needed to reach this coverage will be reported");
+            });
+
+            ui.add_space(10.0);
+
+            // Stored variants
+            ui.group(|ui| {
+                ui.heading("Stored Variants");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.prune_variants, "Keep only the top");
+                    ui.add_enabled(
+                        self.prune_variants,
+                        egui::DragValue::new(&mut self.keep_variants).range(0..=10_000),
+                    );
+                    ui.label("variants per window (0 = metrics only)");
+                });
+                ui.label(
+                    "Every metric and the heatmap are computed from the full variant lists; \
+                     only the stored lists are cut, keeping large batch outputs small. Variants \
+                     needed cannot be recounted at another coverage for pruned results.",
+                );
+            });
+
+            ui.add_space(10.0);
+
+            // Audit trail
+            ui.group(|ui| {
+                ui.heading("Change History");
+                ui.label(
+                    "Parameter changes and job submissions this session. The history is \
+                     stored in each job's results file.",
+                );
+                show_audit_trail(ui, &self.audit_trail, "session_audit_trail");
+            });
+
+        });
+    }
+
+    /// The template as a strip with the masked regions hatched. Dragging
+    /// across it returns the stretch dragged over (0-based, end exclusive).
+    fn mask_strip(
+        &mut self,
+        ui: &mut egui::Ui,
+        template_len: usize,
+        regions: &[(usize, usize)],
+    ) -> Option<(usize, usize)> {
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(ui.available_width(), 22.0), egui::Sense::drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 2.0, egui::Color32::from_gray(50));
+        let x_of = |pos: usize| rect.left() + pos as f32 / template_len as f32 * rect.width();
+        let pos_at = |x: f32| {
+            let t = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            (t * template_len as f32) as usize
+        };
+        let span = |start: usize, end: usize| {
+            let left = x_of(start);
+            egui::Rect::from_x_y_ranges(left..=x_of(end).max(left + 1.0), rect.y_range())
+        };
+        for &(start, end) in regions {
+            paint_hatched(&painter, span(start, end), MASK_COLOR);
+        }
+
+        let pointer = response
+            .interact_pointer_pos()
+            .or(ui.ctx().pointer_latest_pos());
+        if response.drag_started() {
+            self.mask_drag_start = pointer.map(|p| pos_at(p.x));
+        }
+        let mut added = None;
+        if let (Some(anchor), Some(pointer)) = (self.mask_drag_start, pointer) {
+            let here = pos_at(pointer.x);
+            let start = anchor.min(here);
+            let end = (anchor.max(here)).clamp(start + 1, template_len);
+            painter.rect_stroke(
+                span(start, end),
+                0.0,
+                egui::Stroke::new(1.5, egui::Color32::WHITE),
+                egui::StrokeKind::Inside,
+            );
+            if response.drag_stopped() {
+                added = Some((start, end));
+                self.mask_drag_start = None;
+            }
+        }
+        if let Some(pointer) = response.hover_pos() {
+            response.on_hover_text(format!("Position {}", pos_at(pointer.x) + 1));
+        }
+        added
+    }
+
+    fn show_worklist_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Worklist");
+        ui.separator();
+
+        // === Parallelization (moved from Analysis Setup) ===
+        ui.group(|ui| {
+            ui.heading("Parallelization");
+
+            let available_threads = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1);
+
+            ui.label(format!("Available parallelism: {} threads", available_threads));
+
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut self.thread_selection,
+                    ThreadSelection::Auto,
+                    format!("Auto ({} threads)", available_threads),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut self.thread_selection,
+                    ThreadSelection::Manual,
+                    "Manual:",
+                );
+                let enabled = self.thread_selection == ThreadSelection::Manual;
+                ui.add_enabled(
+                    enabled,
+                    egui::DragValue::new(&mut self.manual_thread_count)
+                        .range(1..=available_threads.max(32)),
+                );
+                ui.label("threads");
+            });
+        });
+
+        ui.add_space(10.0);
+
+        // === Progress reporting ===
+        ui.group(|ui| {
+            ui.heading("Progress Reporting");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.progress_by_time, false, "Every");
+                ui.add_enabled(
+                    !self.progress_by_time,
+                    egui::DragValue::new(&mut self.progress_every_positions).range(1..=100_000),
+                );
+                ui.label("positions");
+            });
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.progress_by_time, true, "At most every");
+                ui.add_enabled(
+                    self.progress_by_time,
+                    egui::DragValue::new(&mut self.progress_every_ms)
+                        .range(10..=60_000)
+                        .suffix(" ms"),
+                );
+            });
+            ui.label("Time-based updates suit both very fast and very slow jobs.");
+        });
+
+        ui.add_space(10.0);
+
+        // === Process / Stop Controls ===
+        ui.horizontal(|ui| {
+            let can_process =
+                !self.worklist.is_empty() && self.worklist_state == WorklistState::Idle;
+            if ui
+                .add_enabled(can_process, egui::Button::new("Process Worklist"))
+                .clicked()
+            {
+                self.start_worklist_processing();
+            }
+
+            let can_stop = self.worklist_state == WorklistState::Processing;
+            if ui
+                .add_enabled(can_stop, egui::Button::new("Stop After Current"))
+                .clicked()
+            {
+                self.worklist_state = WorklistState::StopRequested;
+            }
+            let can_cancel = self
+                .cancel_token
+                .as_ref()
+                .is_some_and(|c| !c.is_cancelled());
+            if ui
+                .add_enabled(can_cancel, egui::Button::new("Cancel Job"))
+                .on_hover_text("Stop the running job now, and the worklist after it")
+                .clicked()
+            {
+                self.cancel_current_job();
+            }
+            // Multi-template jobs have no checkpoint to resume from
+            let can_pause = can_cancel
+                && self
+                    .worklist
+                    .get(self.current_job_index)
+                    .is_some_and(|job| job.batch_templates.is_empty());
+            let pause = ui
+                .add_enabled(can_pause, egui::Button::new("Pause"))
+                .on_hover_text(
+                    "Stop the running job now and keep its finished windows on disk; \
+                     starting the worklist again resumes it, even after a restart",
+                );
+            let pause = if can_cancel && !can_pause {
+                pause.on_disabled_hover_text("Multi-template jobs can be cancelled but not paused")
+            } else {
+                pause
+            };
+            if pause.clicked() {
+                self.pause_current_job();
+            }
+            ui.checkbox(&mut self.keep_partial_results, "Keep partial results")
+                .on_hover_text(
+                    "Keep the windows a cancelled job finished as a completed job; \
+                     otherwise they are discarded and the job stays queued",
+                );
+
+            match self.worklist_state {
+                WorklistState::Idle => {}
+                WorklistState::Processing => {
+                    ui.spinner();
+                    let jobs_done =
+                        self.worklist_total_at_start - self.worklist.len();
+                    ui.label(format!(
+                        "Processing job {} of {}",
+                        jobs_done + 1,
+                        self.worklist_total_at_start
+                    ));
+                }
+                WorklistState::StopRequested => {
+                    ui.spinner();
+                    let cancelling = self.cancel_token.as_ref().is_some_and(|c| c.is_cancelled());
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        if cancelling && self.pause_requested {
+                            "Pausing current job..."
+                        } else if cancelling {
+                            "Cancelling current job..."
+                        } else {
+                            "Stopping after current job..."
+                        },
+                    );
+                }
+            }
+        });
+
+        ui.add_space(5.0);
+
+        // === Progress Bars ===
+        if self.worklist_state != WorklistState::Idle {
+            let jobs_done = self.worklist_total_at_start - self.worklist.len();
+            let overall_frac = if self.worklist_total_at_start > 0 {
+                jobs_done as f32 / self.worklist_total_at_start as f32
+            } else {
+                0.0
+            };
+            ui.horizontal(|ui| {
+                ui.label("Overall:");
+                ui.add(
+                    egui::ProgressBar::new(overall_frac).text(format!(
+                        "{}/{} jobs",
+                        jobs_done, self.worklist_total_at_start
+                    )),
+                );
+            });
+
+            if let Some(ref progress) = self.analysis_progress {
+                let text = format!("{}{}", progress.message, self.eta_text(progress));
+                ui.horizontal(|ui| {
+                    ui.label("Current job:");
+                    ui.add(egui::ProgressBar::new(progress.fraction()).text(text));
+                });
+            }
+        }
+
+        ui.add_space(10.0);
+
+        // === Queued Jobs Table ===
+        ui.heading("Queued Jobs");
+        if self.worklist_restored > 0 {
+            ui.label(format!(
+                "Restored {} queued job{} from the previous session.",
+                self.worklist_restored,
+                if self.worklist_restored == 1 { "" } else { "s" }
+            ));
+        }
+        if let Some(ref err) = self.worklist_persist_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if let Some(ref err) = self.worklist_manifest_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if self.worklist.is_empty() {
+            ui.colored_label(
+                egui::Color32::GRAY,
+                "No jobs queued. Use the Input Data tab to add jobs.",
+            );
+        } else {
+            let mut pending_remove: Option<usize> = None;
+            let mut pending_move: Option<(usize, usize)> = None;
+            let mut pending_duplicate: Option<usize> = None;
+            let mut pending_edit: Option<usize> = None;
+            let front = self.worklist_front();
+
+            egui::ScrollArea::vertical()
+                .id_salt("worklist_scroll")
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("worklist_grid")
+                        .striped(true)
+                        .min_col_width(40.0)
+                        .show(ui, |ui| {
+                            // Header
+                            ui.strong("");
+                            ui.strong("#");
+                            ui.strong("Template");
+                            ui.strong("References");
+                            ui.strong("Exclusivity");
+                            ui.strong("Oligo Range");
+                            ui.strong("Method");
+                            ui.strong("Est. Time");
+                            ui.strong("Output");
+                            ui.end_row();
+
+                            let threads = self.resolve_thread_count().get_count();
+
+                            for (i, job) in self.worklist.iter().enumerate() {
+                                let is_current = self.is_running_job(i);
+
+                                ui.horizontal(|ui| {
+                                    if is_current {
+                                        ui.spinner();
+                                    } else if ui.small_button("X").clicked() {
+                                        pending_remove = Some(i);
+                                    }
+                                    if ui
+                                        .small_button("Duplicate")
+                                        .on_hover_text("Queue a copy of this job after it")
+                                        .clicked()
+                                    {
+                                        pending_duplicate = Some(i);
+                                    }
+                                    if !is_current
+                                        && ui
+                                            .small_button("Edit")
+                                            .on_hover_text(
+                                                "Change this job's parameters in the Analysis \
+                                                 Setup tab",
+                                            )
+                                            .clicked()
+                                    {
+                                        pending_edit = Some(i);
+                                    }
+                                    let moves = [
+                                        ("⏫", front, "Move to the front of the queue"),
+                                        ("⏶", i.wrapping_sub(1), "Move up"),
+                                        ("⏷", i + 1, "Move down"),
+                                    ];
+                                    for (label, to, hover) in moves {
+                                        if ui
+                                            .add_enabled(
+                                                self.can_move_worklist_job(i, to),
+                                                egui::Button::new(label).small(),
+                                            )
+                                            .on_hover_text(hover)
+                                            .clicked()
+                                        {
+                                            pending_move = Some((i, to));
+                                        }
+                                    }
+                                    if !is_current && let Some(checkpoint) = &job.checkpoint {
+                                        let windows: usize = checkpoint
+                                            .results_by_length
+                                            .values()
+                                            .map(|r| r.positions.len())
+                                            .sum();
+                                        ui.label("⏸").on_hover_text(format!(
+                                            "Paused with {} windows screened; resumes \
+                                             where it stopped",
+                                            windows
+                                        ));
+                                    }
+                                });
+
+                                ui.label(format!("{}", job.id));
+                                let mut template_label = job.template_file_name.clone();
+                                if !job.batch_templates.is_empty() {
+                                    template_label +=
+                                        &format!(" ({} templates)", 1 + job.batch_templates.len());
+                                }
+                                if job.params.quick_scan.is_some() {
+                                    template_label += " (quick scan)";
+                                }
+                                ui.label(template_label).on_hover_text(format!(
+                                    "{} ({} bp)",
+                                    job.template_data.name, job.template_length
+                                ));
+                                ui.label(format!("{} seqs", job.reference_count))
+                                    .on_hover_text(&job.reference_file_name);
+                                if job.use_differential {
+                                    ui.label(format!("{} seqs", job.exclusivity_count))
+                                        .on_hover_text(job.exclusivity_file_names.join("\n"));
+                                } else {
+                                    ui.label("-");
+                                }
+                                ui.label(format!(
+                                    "{}-{} bp",
+                                    job.params.min_oligo_length,
+                                    job.params.max_oligo_length
+                                ))
+                                .on_hover_text(format!(
+                                    "Step size: {} bp{}{}",
+                                    job.params.resolution,
+                                    if job.params.auto_resolution_target.is_some() {
+                                        " (auto)"
+                                    } else {
+                                        ""
+                                    },
+                                    if job.params.refine_max_variants.is_some() {
+                                        ", refined"
+                                    } else {
+                                        ""
+                                    }
+                                ));
+                                ui.label(job.params.method.description()).on_hover_text(
+                                    format!(
+                                        "No match: {}",
+                                        job.params
+                                            .pairwise
+                                            .no_match_rule
+                                            .description(job.params.pairwise.max_mismatches)
+                                    ),
+                                );
+                                let estimate = &job.runtime_estimate;
+                                ui.label(format_duration(estimate.wall_seconds(threads)))
+                                    .on_hover_text(format!(
+                                        "{} windows, {} alignments on {} thread(s)\n\
+                                         Coarsen the resolution to shorten long jobs.",
+                                        estimate.windows, estimate.alignments, threads
+                                    ));
+                                if job.output_folder.is_some() {
+                                    ui.label("Auto-save")
+                                        .on_hover_text(if job.store_in_database {
+                                            "Results database"
+                                        } else if job.binary_results {
+                                            "Compressed binary"
+                                        } else {
+                                            "JSON"
+                                        });
+                                } else {
+                                    ui.label("-");
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if let Some(idx) = pending_remove {
+                self.remove_worklist_job(idx);
+            }
+            if let Some((from, to)) = pending_move {
+                self.move_worklist_job(from, to);
+            }
+            if let Some(idx) = pending_duplicate {
+                self.duplicate_worklist_job(idx);
+            }
+            if let Some(idx) = pending_edit {
+                self.edit_worklist_job(idx);
+            }
+        }
+
+        // === Completed Jobs Summary ===
+        if !self.completed_jobs.is_empty() {
+            ui.add_space(10.0);
+            ui.separator();
+            ui.label(format!(
+                "{} completed job(s) available in the Results tab.",
+                self.completed_jobs.len()
+            ));
+        }
+
+        // === Auto-save ===
+        for (job_id, bytes) in &self.auto_saves_running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!(
+                    "Auto-saving job #{}: {:.1} MB written",
+                    job_id,
+                    *bytes as f64 / 1_000_000.0
+                ));
+            });
+        }
+        if let Some(ref err) = self.auto_save_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    fn show_results_tab(&mut self, ui: &mut egui::Ui) {
+        if self.completed_jobs.is_empty() {
+            ui.heading("Results");
+            ui.separator();
+            ui.label(
+                "No completed jobs yet. Add jobs in the Input tab and process them in the Worklist tab.",
+            );
+            ui.add_space(10.0);
+            if ui.button("Load Results from File").clicked() {
+                self.load_results_into_completed();
+            }
+            if ui.button("Results Database...").clicked() {
+                self.open_database_browser();
+            }
+            if let Some(ref error) = self.load_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            return;
+        }
+
+        // Job selector + header
+        ui.horizontal(|ui| {
+            ui.heading("Results");
+
+            ui.separator();
+            ui.label("Job:");
+
+            let selected_label = self
+                .selected_completed_job_index
+                .and_then(|i| self.completed_jobs.get(i))
+                .map(|cj| {
+                    format!("#{} - {}", cj.job.id, cj.job.template_file_name)
+                })
+                .unwrap_or_else(|| "Select a job".to_string());
+
+            let mut new_selection = self.selected_completed_job_index;
+            egui::ComboBox::from_id_salt("completed_job_selector")
+                .selected_text(&selected_label)
+                .show_ui(ui, |ui| {
+                    for (i, cj) in self.completed_jobs.iter().enumerate() {
+                        let label = format!(
+                            "#{} - {} ({} refs, {}-{} bp)",
+                            cj.job.id,
+                            cj.job.template_file_name,
+                            cj.job.reference_count,
+                            cj.job.params.min_oligo_length,
+                            cj.job.params.max_oligo_length,
+                        );
+                        ui.selectable_value(&mut new_selection, Some(i), label);
+                    }
+                });
+
+            // Templates screened together in a multi-template job
+            let batch = self
+                .selected_completed_job_index
+                .and_then(|i| self.completed_jobs.get(i))
+                .and_then(|cj| {
+                    cj.job
+                        .batch_id
+                        .map(|id| (id, cj.job.template_data.name.clone()))
+                });
+            if let Some((batch_id, template_name)) = batch {
+                ui.label("Template:");
+                egui::ComboBox::from_id_salt("batch_template_selector")
+                    .selected_text(template_name)
+                    .show_ui(ui, |ui| {
+                        for (i, cj) in self.completed_jobs.iter().enumerate() {
+                            if cj.job.batch_id == Some(batch_id) {
+                                let label = format!(
+                                    "{} ({} bp)",
+                                    cj.job.template_data.name, cj.job.template_length
+                                );
+                                ui.selectable_value(&mut new_selection, Some(i), label);
+                            }
+                        }
+                    });
+            }
+
+            // Sync results when selection changes
+            if new_selection != self.selected_completed_job_index
+                && let Some(idx) = new_selection
+            {
+                self.select_completed_job(idx);
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Load Results from File").clicked() {
+                    self.load_results_into_completed();
+                }
+                if ui
+                    .button("Results Database...")
+                    .on_hover_text("Browse the jobs stored in a results database")
+                    .clicked()
+                {
+                    self.open_database_browser();
+                }
+                let has_results = self.results.is_some();
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Save Results"))
+                    .clicked()
+                {
+                    self.pending_save = true;
+                }
+                ui.add_enabled_ui(has_results, |ui| {
+                    ui.menu_button("Save Summary", |ui| {
+                        ui.label("Per-window metrics without the full variant lists");
+                        ui.horizontal(|ui| {
+                            ui.label("Keep top");
+                            ui.add(
+                                egui::DragValue::new(&mut self.summary_keep_variants)
+                                    .range(0..=1000),
+                            );
+                            ui.label("variants per window");
+                        });
+                        if ui.button("Save...").clicked() {
+                            self.save_summary_results();
+                            ui.close_menu();
+                        }
+                    });
+                });
+                if self.results_detail_source.is_some()
+                    && ui
+                        .button("Load All Variants")
+                        .on_hover_text(
+                            "Read every window's variant list from the indexed file \
+                             (needed to recount at another coverage threshold)",
+                        )
+                        .clicked()
+                {
+                    self.load_all_window_variants();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Use These Parameters"))
+                    .on_hover_text("Copy this job's analysis parameters into the Analysis tab")
+                    .clicked()
+                {
+                    self.use_job_parameters();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Export Parameters"))
+                    .on_hover_text("Parameters and input file checksums for reproducing this job")
+                    .clicked()
+                {
+                    self.export_parameters();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Export Matrix CSV"))
+                    .on_hover_text("Heatmap values (current metric) as lengths × positions")
+                    .clicked()
+                {
+                    self.export_heatmap_matrix();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Export Table"))
+                    .on_hover_text(
+                        "Every window's metrics and template oligo as CSV or TSV, one row per \
+                         length and position; windows the exclusivity filter hides are left out",
+                    )
+                    .clicked()
+                {
+                    self.export_positions_table();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Export Track"))
+                    .on_hover_text(
+                        "Current metric as a bedGraph or wiggle track on template coordinates, \
+                         one track per oligo length",
+                    )
+                    .clicked()
+                {
+                    self.export_metric_track();
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Top Candidates"))
+                    .on_hover_text("All windows ranked by a weighted score, exportable as FASTA")
+                    .clicked()
+                {
+                    self.show_top_candidates_window = true;
+                }
+                let nomination_count = self.results.as_ref().map_or(0, |r| r.nominations.len());
+                if ui
+                    .add_enabled(
+                        has_results,
+                        egui::Button::new(format!("Nominations ({})", nomination_count)),
+                    )
+                    .clicked()
+                {
+                    self.show_nominations_window = true;
+                }
+                let order_label = format!("Order Selection ({})", self.order_selection.len());
+                if ui
+                    .button(order_label)
+                    .on_hover_text("Oligos picked for ordering, exportable as FASTA or order sheet")
+                    .clicked()
+                {
+                    self.show_order_window = true;
+                }
+                if ui
+                    .add_enabled(
+                        !self.completed_jobs.is_empty(),
+                        egui::Button::new("Cross-Job Search"),
+                    )
+                    .on_hover_text("Find windows matching criteria across all completed jobs")
+                    .clicked()
+                {
+                    self.show_query_window = true;
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Amplicon Search"))
+                    .clicked()
+                {
+                    self.show_amplicon_window = true;
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Design Pairs"))
+                    .on_hover_text("Rank forward/reverse pairs of low-variant windows")
+                    .clicked()
+                {
+                    self.show_pair_design_window = true;
+                }
+                if ui
+                    .add_enabled(has_results, egui::Button::new("Panel Matrix"))
+                    .clicked()
+                {
+                    self.show_panel_matrix_window = true;
+                }
+            });
+        });
+        ui.separator();
+
+        if self.results.is_none() {
+            ui.label("Select a completed job to view its results.");
+            return;
+        }
+
+        // Extract data we need
+        let (
+            lengths,
+            template_seq,
+            total_seqs,
+            has_differential,
+            exclusivity_only,
+            no_match_desc,
+            reference_trim,
+            ignored_exclusivity,
+            truncated_windows,
+            quick_scan,
+            cancelled,
+            region,
+            refined,
+        ) = {
+            let results = self.results.as_ref().unwrap();
+            let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
+            lengths.sort();
+            let pairwise = &results.params.pairwise;
+            let params = &results.params;
+            let region = (params.region_start.is_some() || params.region_end.is_some())
+                .then(|| params.screened_region(results.template_sequence.len()));
+            let refined = params
+                .refine_max_variants
+                .filter(|_| params.resolution > 1 && !params.exclusivity_only)
+                .map(|max| (params.resolution, max));
+            (
+                lengths,
+                results.template_sequence.clone(),
+                results.total_sequences,
+                results.differential_enabled,
+                results.params.exclusivity_only,
+                pairwise.no_match_rule.description(pairwise.max_mismatches),
+                results.reference_trim,
+                (
+                    results.params.exclusivity_ignore_patterns.clone(),
+                    results.ignored_exclusivity_names.clone(),
+                ),
+                results
+                    .results_by_length
+                    .values()
+                    .flat_map(|lr| &lr.positions)
+                    .filter(|pr| pr.analysis.truncated)
+                    .count(),
+                results.params.quick_scan,
+                results.cancelled,
+                region,
+                refined,
+            )
+        };
+
+        if lengths.is_empty() {
+            ui.label("No length results available.");
+            return;
+        }
+
+        self.job_comparison_controls(ui);
+
+        // Controls row 1: zoom + info + differential toggle
+        ui.horizontal(|ui| {
+            ui.label("Zoom:");
+            ui.add(egui::Slider::new(&mut self.zoom_level, 0.01..=3.0).logarithmic(true));
+            if 14.0 * self.zoom_level < MIN_CELL_WIDTH {
+                egui::ComboBox::from_id_salt("heatmap_bin_mode")
+                    .selected_text(format!("Bins: {}", self.heatmap_bin_mode.label()))
+                    .show_ui(ui, |ui| {
+                        for mode in BinAggregate::ALL {
+                            ui.selectable_value(&mut self.heatmap_bin_mode, mode, mode.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Cells are narrower than 2 px, so adjacent positions are drawn as one \
+                         bin colored by the worst or mean of its windows",
+                    );
+            }
+            ui.add_space(20.0);
+            if exclusivity_only {
+                ui.label(format!(
+                    "Exclusivity only | Template: {} bp | Match rule: {}",
+                    template_seq.len(),
+                    no_match_desc
+                ));
+            } else {
+                ui.label(format!(
+                    "{} reference sequences | Template: {} bp | Match rule: {}",
+                    total_seqs,
+                    template_seq.len(),
+                    no_match_desc
+                ));
+            }
+            if quick_scan.is_some() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    "| Quick scan (approximate)",
+                )
+                .on_hover_text(
+                    "One oligo length at a coarse step size against a sample of the \
+                     references; queue the full screen for exact results.",
+                );
+            }
+            if cancelled {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    "| Cancelled (partial results)",
+                )
+                .on_hover_text(
+                    "The job was cancelled while running; oligo lengths and windows it had \
+                     not reached are missing.",
+                );
+            }
+            if let Some((start, end)) = region {
+                ui.label(format!("| Region {}-{}", start + 1, end))
+                    .on_hover_text("Only windows within this template region were screened");
+            }
+            if let Some((step, max)) = refined {
+                let hover = format!(
+                    "Screened at step {}, then at step 1 around windows needing {} or fewer \
+                     variants",
+                    step, max
+                );
+                ui.label(format!("| Refined from step {}", step))
+                    .on_hover_text(hover);
+            }
+            if let Some(trim) = reference_trim {
+                ui.label(format!(
+                    "| Trimmed {} of {} references ({} unmapped)",
+                    trim.trimmed, total_seqs, trim.unmapped
+                ))
+                .on_hover_text(format!(
+                    "Reference bases: {} before trimming, {} after",
+                    trim.bases_before, trim.bases_after
+                ));
+            }
+            let (ignore_patterns, ignored_names) = ignored_exclusivity;
+            if !ignored_names.is_empty() {
+                ui.label(format!(
+                    "| {} exclusivity sequences ignored by name",
+                    ignored_names.len()
+                ))
+                .on_hover_text(format!(
+                    "Patterns: {}\nTheir hits are kept but left out of the effective minimum \
+                     mismatches.\n\n{}",
+                    ignore_patterns.join(", "),
+                    ignored_names.join("\n")
+                ));
+            }
+            if truncated_windows > 0 {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    format!("| ⚠ {} windows truncated", truncated_windows),
+                )
+                .on_hover_text(
+                    "These windows hit the per-window time budget; their variants are the best \
+                     found by then, with unmerged sequences left as exact variants.",
+                );
+            }
+            ui.separator();
+            ui.label("Color by:");
+            egui::ComboBox::from_id_salt("heatmap_metric")
+                .selected_text(self.heatmap_metric.label())
+                .show_ui(ui, |ui| {
+                    for metric in HeatmapMetric::ALL {
+                        let available = if exclusivity_only {
+                            !metric.needs_references()
+                        } else {
+                            metric != HeatmapMetric::Exclusivity || has_differential
+                        };
+                        ui.add_enabled_ui(available, |ui| {
+                            ui.selectable_value(&mut self.heatmap_metric, metric, metric.label());
+                        });
+                    }
+                });
+        });
+        if !has_differential && self.differential_mode() {
+            self.heatmap_metric = HeatmapMetric::VariantsNeeded;
+        }
+        if exclusivity_only && self.heatmap_metric.needs_references() {
+            self.heatmap_metric = HeatmapMetric::Exclusivity;
+        }
+        // Entropy is computed from the variant lists
+        if self.heatmap_metric == HeatmapMetric::Entropy && !self.load_all_window_variants() {
+            self.heatmap_metric = HeatmapMetric::VariantsNeeded;
+        }
+
+        if !self.differential_mode() {
+            // === NORMAL MODE CONTROLS ===
+
+            // Controls row 2: coverage threshold + color range
+            ui.horizontal(|ui| {
+                self.coverage_threshold_controls(ui);
+                ui.separator();
+                match self.heatmap_metric {
+                    HeatmapMetric::Coverage => {
+                        metric_range_controls(ui, &mut self.coverage_range, "%", 0.5)
+                    }
+                    HeatmapMetric::Entropy => {
+                        metric_range_controls(ui, &mut self.entropy_range, " bits", 0.05)
+                    }
+                    HeatmapMetric::Composite => {
+                        metric_range_controls(ui, &mut self.composite_range, "", 1.0);
+                        ui.label("(0-100; combines the variant, no-match, and exclusivity ranges)");
+                    }
+                    HeatmapMetric::GcPercent => target_band_controls(ui, &mut self.gc_band, "%"),
+                    HeatmapMetric::MeltingTemp => {
+                        target_band_controls(ui, &mut self.tm_band, " °C");
+                        ui.label("(nearest-neighbor Tm; basic estimate for older results)");
+                    }
+                    HeatmapMetric::NoMatchPercent => {
+                        ui.label("Colored by the no-match thresholds below");
+                    }
+                    _ => {}
+                }
+                if self.heatmap_metric == HeatmapMetric::VariantsNeeded
+                    || self.heatmap_metric == HeatmapMetric::Composite
+                {
+                    ui.label("Color range - Green at:");
+                    ui.add(egui::DragValue::new(&mut self.color_green_at).range(1..=1000));
+                    ui.label("variants, Red at:");
+                    ui.add(egui::DragValue::new(&mut self.color_red_at).range(1..=1000));
+                    ui.label("variants");
+                } else if self.heatmap_metric == HeatmapMetric::MeanMismatches {
+                    ui.label("Color range - Green at:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.mismatch_green_at)
+                            .range(0.0..=50.0)
+                            .speed(0.05),
+                    );
+                    ui.label("mismatches, Red at:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.mismatch_red_at)
+                            .range(0.0..=50.0)
+                            .speed(0.05),
+                    );
+                    ui.label("mismatches");
+                } else if self.heatmap_metric == HeatmapMetric::MeanIdentity
+                    || self.heatmap_metric == HeatmapMetric::MinIdentity
+                {
+                    ui.label("Color range - Green at:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.identity_green_at)
+                            .range(0.0..=100.0)
+                            .speed(0.5)
+                            .suffix("%"),
+                    );
+                    ui.label("identity, Red at:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.identity_red_at)
+                            .range(0.0..=100.0)
+                            .speed(0.5)
+                            .suffix("%"),
+                    );
+                    ui.label("identity");
+                }
+                if self.metric_color_range().is_some() {
+                    ui.separator();
+                    ui.checkbox(&mut self.normalize_per_length, "Normalize per length")
+                        .on_hover_text(
+                            "Scale colors over each oligo length's own range (green at the \
+                             length's best window, red at its worst), keeping the direction \
+                             of the color range",
+                        );
+                }
+            });
+
+            // Ensure green <= red (variants) and green >= red (identity)
+            if self.color_green_at > self.color_red_at {
+                self.color_red_at = self.color_green_at;
+            }
+            if self.identity_red_at > self.identity_green_at {
+                self.identity_red_at = self.identity_green_at;
+            }
+            if self.mismatch_green_at > self.mismatch_red_at {
+                self.mismatch_red_at = self.mismatch_green_at;
+            }
+
+            // Controls row 3: no-match darkening thresholds
+            ui.horizontal(|ui| {
+                ui.label("No-match darkening - OK at:");
+                ui.add(
+                    egui::DragValue::new(&mut self.nomatch_ok_percent)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                );
+                ui.label(", Dark red at:");
+                ui.add(
+                    egui::DragValue::new(&mut self.nomatch_bad_percent)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                );
+                if self.heatmap_metric != HeatmapMetric::MeltingTemp {
+                    ui.separator();
+                    ui.checkbox(&mut self.tm_darkening, "Darken outside Tm")
+                        .on_hover_text(
+                            "Darken cells toward dark red as the window's nearest-neighbor Tm \
+                             leaves the target band",
+                        );
+                    if self.tm_darkening {
+                        target_band_controls(ui, &mut self.tm_band, " °C");
+                    }
+                }
+            });
+
+            if self.nomatch_ok_percent > self.nomatch_bad_percent {
+                self.nomatch_bad_percent = self.nomatch_ok_percent;
+            }
+        } else {
+            // === DIFFERENTIAL MODE CONTROLS ===
+
+            // Exclusivity color controls
+            ui.horizontal(|ui| {
+                ui.label("Exclusivity color - Green at:");
+                ui.add(egui::DragValue::new(&mut self.diff_green_at).range(0..=50));
+                ui.label("mismatches, Red at:");
+                ui.add(egui::DragValue::new(&mut self.diff_red_at).range(0..=50));
+                ui.label("mismatches");
+                ui.separator();
+                ui.label("Ignore best:");
+                ui.add(egui::DragValue::new(&mut self.diff_ignore_count).range(0..=1000));
+                ui.label("sequences");
+            });
+
+            // Candidate filter by exclusivity threshold
+            ui.horizontal(|ui| {
+                ui.checkbox(
+                    &mut self.excl_filter_enabled,
+                    "Only keep windows with min mismatches >",
+                );
+                ui.add_enabled(
+                    self.excl_filter_enabled,
+                    egui::DragValue::new(&mut self.excl_filter_threshold).range(0..=50),
+                );
+                ui.label("(after ignoring best sequences)");
+                if ui
+                    .add_enabled(
+                        self.excl_filter_enabled,
+                        egui::Button::new("Save Filtered Results..."),
+                    )
+                    .clicked()
+                {
+                    self.save_filtered_results();
+                }
+            });
+
+            // Darkening controls (conservation metrics)
+            ui.horizontal(|ui| {
+                ui.label("Darkening - Variant count: Green at:");
+                ui.add(egui::DragValue::new(&mut self.color_green_at).range(1..=1000));
+                ui.label(", Red at:");
+                ui.add(egui::DragValue::new(&mut self.color_red_at).range(1..=1000));
+                ui.separator();
+                ui.label("No-match: OK at:");
+                ui.add(
+                    egui::DragValue::new(&mut self.nomatch_ok_percent)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                );
+                ui.label(", Bad at:");
+                ui.add(
+                    egui::DragValue::new(&mut self.nomatch_bad_percent)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                );
+            });
+
+            if self.color_green_at > self.color_red_at {
+                self.color_red_at = self.color_green_at;
+            }
+            if self.nomatch_ok_percent > self.nomatch_bad_percent {
+                self.nomatch_bad_percent = self.nomatch_ok_percent;
+            }
+
+            // Coverage threshold (still needed for variant count)
+            ui.horizontal(|ui| self.coverage_threshold_controls(ui));
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(
+                &mut self.gc_filter_enabled,
+                "Grey out windows with GC outside",
+            )
+            .on_hover_text("GC content of the template oligo of each window");
+            ui.add_enabled(
+                self.gc_filter_enabled,
+                egui::DragValue::new(&mut self.gc_filter_min)
+                    .range(0.0..=100.0)
+                    .speed(0.5)
+                    .suffix("%"),
+            );
+            ui.label("to");
+            ui.add_enabled(
+                self.gc_filter_enabled,
+                egui::DragValue::new(&mut self.gc_filter_max)
+                    .range(0.0..=100.0)
+                    .speed(0.5)
+                    .suffix("%"),
+            );
+        });
+        if self.gc_filter_min > self.gc_filter_max {
+            self.gc_filter_max = self.gc_filter_min;
+        }
+
+        ui.add_space(5.0);
+
+        // Heatmap display
+        let coverage_threshold = self.applied_coverage_threshold;
+        match self
+            .compare_job_index
+            .filter(|&i| i < self.completed_jobs.len())
+        {
+            Some(other) => self.show_job_comparison(ui, other),
+            None => self.show_heatmap(ui, &lengths, &template_seq, coverage_threshold),
+        }
+
+        if let Some(results) = &self.results
+            && !results.audit_trail.is_empty()
+        {
+            egui::CollapsingHeader::new(format!(
+                "Change history ({} entries)",
+                results.audit_trail.len()
+            ))
+            .id_salt("results_audit_trail")
+            .show(ui, |ui| show_audit_trail(ui, &results.audit_trail, "results_audit_scroll"));
+        }
+
+        // Error messages
+        if let Some(ref error) = self.save_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        if let Some(ref error) = self.load_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    }
+
+    /// Selector of a second completed job to compare the selected one with
+    fn job_comparison_controls(&mut self, ui: &mut egui::Ui) {
+        let selected = self.selected_completed_job_index;
+        ui.horizontal(|ui| {
+            ui.label("Compare with:");
+            let job_label =
+                |cj: &CompletedJob| format!("#{} - {}", cj.job.id, cj.job.template_file_name);
+            let selected_text = self
+                .compare_job_index
+                .and_then(|i| self.completed_jobs.get(i))
+                .map_or_else(|| "None".to_string(), job_label);
+            egui::ComboBox::from_id_salt("compare_job_selector")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.compare_job_index, None, "None");
+                    for (i, cj) in self.completed_jobs.iter().enumerate() {
+                        if Some(i) != selected {
+                            let label = format!(
+                                "{} ({} refs, {}-{} bp)",
+                                job_label(cj),
+                                cj.job.reference_count,
+                                cj.job.params.min_oligo_length,
+                                cj.job.params.max_oligo_length,
+                            );
+                            ui.selectable_value(&mut self.compare_job_index, Some(i), label);
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Show this job (A) against another completed job (B), e.g. the same \
+                     template screened with other parameters or references",
+                );
+            if self.compare_job_index.is_none() {
+                return;
+            }
+            ui.separator();
+            for mode in JobCompareMode::ALL {
+                ui.radio_value(&mut self.compare_job_mode, mode, mode.label());
+            }
+            if self.compare_job_mode == JobCompareMode::Delta {
+                ui.separator();
+                ui.label("Full color at ±");
+                ui.add(egui::DragValue::new(&mut self.compare_delta_saturation).range(1..=100));
+                ui.label("variants");
+            }
+        });
+    }
+
+    /// Heatmap of the selected job (A) against completed job `other` (B):
+    /// A's variants needed minus B's per window, or both jobs' rows of the
+    /// current metric stacked per oligo length.
+    fn show_job_comparison(&self, ui: &mut egui::Ui, other: usize) {
+        let Some(a) = self.results.as_ref() else {
+            return;
+        };
+        let b = &self.completed_jobs[other].results;
+        let comparison = JobComparison::new(a, b);
+        if comparison.positions.is_empty() {
+            ui.label("No positions analyzed.");
+            return;
+        }
+        let job_id = |index: Option<usize>| {
+            index
+                .and_then(|i| self.completed_jobs.get(i))
+                .map_or(0, |cj| cj.job.id)
+        };
+        let a_id = job_id(self.selected_completed_job_index);
+        let b_id = job_id(Some(other));
+        let stacked = self.compare_job_mode == JobCompareMode::Stacked;
+
+        if !comparison.same_template {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 180, 100),
+                "⚠ The jobs screened different templates; windows are compared by position only",
+            );
+        }
+        if stacked {
+            ui.label(format!(
+                "{}: A = job #{}, B = job #{} (hover a cell for values)",
+                self.heatmap_metric.label(),
+                a_id,
+                b_id
+            ));
+        } else {
+            let (fewer, same, more) = comparison.tally();
+            ui.label(format!(
+                "Variants needed, A (job #{}) − B (job #{}): A needs fewer in {} windows, the \
+                 same in {}, more in {}",
+                a_id, b_id, fewer, same, more
+            ));
+        }
+
+        let (a_windows, b_windows) = if stacked {
+            (window_lookup(a), window_lookup(b))
+        } else {
+            Default::default()
+        };
+
+        // Rows: (length, Some(true) = A / Some(false) = B, None = delta)
+        let rows: Vec<(u32, Option<bool>)> = comparison
+            .lengths
+            .iter()
+            .flat_map(|&length| {
+                if stacked {
+                    vec![(length, Some(true)), (length, Some(false))]
+                } else {
+                    vec![(length, None)]
+                }
+            })
+            .collect();
+
+        let positions = &comparison.positions;
+        let cell_w = 14.0 * self.zoom_level.max(0.01);
+        let bin_size = if cell_w < MIN_CELL_WIDTH {
+            (MIN_CELL_WIDTH / cell_w).ceil() as usize
+        } else {
+            1
+        };
+        let cell_h: f32 = if stacked { 27.0 } else { 54.0 };
+        let label_width: f32 = 60.0;
+        let pos_label_height: f32 = 14.0;
+        let total_width = label_width + positions.len() as f32 * cell_w;
+        let total_height = pos_label_height + rows.len() as f32 * cell_h + 10.0;
+
+        egui::ScrollArea::horizontal()
+            .id_salt("job_comparison_scroll")
+            .show(ui, |ui| {
+                let (response, painter) = ui
+                    .allocate_painter(egui::vec2(total_width, total_height), egui::Sense::hover());
+                let origin = response.rect.min;
+
+                let show_every_n = if cell_w < 12.0 {
+                    (12.0 / cell_w).ceil() as usize
+                } else {
+                    1
+                };
+                for (col, &pos) in positions.iter().enumerate().step_by(show_every_n) {
+                    painter.text(
+                        egui::pos2(
+                            origin.x + label_width + col as f32 * cell_w + cell_w / 2.0,
+                            origin.y + pos_label_height / 2.0,
+                        ),
+                        egui::Align2::CENTER_CENTER,
+                        format!("{}", pos + 1),
+                        egui::FontId::proportional(9.0),
+                        egui::Color32::GRAY,
+                    );
+                }
+
+                let grid_y_start = origin.y + pos_label_height;
+                let gap = if bin_size > 1 { 0.0 } else { 1.0 };
+                let mut hovered: Option<(u32, usize)> = None;
+                for (row, &(length, job)) in rows.iter().enumerate() {
+                    let row_label = match job {
+                        Some(true) => format!("A {} bp", length),
+                        Some(false) => format!("B {} bp", length),
+                        None => format!("{} bp", length),
+                    };
+                    painter.text(
+                        egui::pos2(
+                            origin.x + label_width - 5.0,
+                            grid_y_start + row as f32 * cell_h + cell_h / 2.0,
+                        ),
+                        egui::Align2::RIGHT_CENTER,
+                        row_label,
+                        egui::FontId::proportional(11.0),
+                        egui::Color32::LIGHT_GRAY,
+                    );
+
+                    for (bin, bin_positions) in positions.chunks(bin_size).enumerate() {
+                        let cell_x = origin.x + label_width + (bin * bin_size) as f32 * cell_w;
+                        let cell_rect = egui::Rect::from_min_size(
+                            egui::pos2(cell_x, grid_y_start + row as f32 * cell_h),
+                            egui::vec2(bin_positions.len() as f32 * cell_w - gap, cell_h - 1.0),
+                        );
+                        let color = match job {
+                            Some(is_a) => {
+                                let (results, windows) = if is_a {
+                                    (a, &a_windows)
+                                } else {
+                                    (b, &b_windows)
+                                };
+                                let shades = bin_positions.iter().map(|&pos| {
+                                    let oligo = results
+                                        .template_sequence
+                                        .get(pos..pos + length as usize)
+                                        .unwrap_or("");
+                                    self.heatmap_cell_shade(
+                                        windows.get(&(length, pos)).copied(),
+                                        oligo,
+                                        None,
+                                        None,
+                                    )
+                                });
+                                CellShade::aggregate(shades, self.heatmap_bin_mode).color()
+                            }
+                            None => {
+                                let deltas: Vec<f64> = bin_positions
+                                    .iter()
+                                    .filter_map(|&pos| comparison.get(length, pos).delta())
+                                    .map(|d| d as f64)
+                                    .collect();
+                                let delta = match self.heatmap_bin_mode {
+                                    BinAggregate::Worst => deltas.iter().copied().reduce(f64::max),
+                                    BinAggregate::Mean => (!deltas.is_empty())
+                                        .then(|| deltas.iter().sum::<f64>() / deltas.len() as f64),
+                                };
+                                match delta {
+                                    Some(delta) => {
+                                        delta_color(delta, self.compare_delta_saturation)
+                                    }
+                                    None => CellShade::NoData.color(),
+                                }
+                            }
+                        };
+                        painter.rect_filled(cell_rect, 1.0, color);
+
+                        if let Some(pointer) = response.hover_pos()
+                            && cell_rect.contains(pointer)
+                        {
+                            let offset = ((pointer.x - cell_x) / cell_w).max(0.0) as usize;
+                            hovered =
+                                Some((length, bin_positions[offset.min(bin_positions.len() - 1)]));
+                            painter.rect_stroke(
+                                cell_rect,
+                                1.0,
+                                egui::Stroke::new(1.5, egui::Color32::WHITE),
+                                egui::StrokeKind::Outside,
+                            );
+                        }
+                    }
+                }
+
+                if let Some((length, pos)) = hovered {
+                    let pair = comparison.get(length, pos);
+                    let needed = |n: Option<usize>| {
+                        n.map_or_else(|| "not analyzed".to_string(), |n| n.to_string())
+                    };
+                    let mut text = format!(
+                        "Position: {}, Length: {} bp\nA (job #{}): {} variants needed\n\
+                         B (job #{}): {} variants needed",
+                        pos + 1,
+                        length,
+                        a_id,
+                        needed(pair.a),
+                        b_id,
+                        needed(pair.b)
+                    );
+                    if let Some(delta) = pair.delta() {
+                        text.push_str(&format!("\nDifference (A − B): {:+}", delta));
+                    }
+                    response.on_hover_text(text);
+                }
+            });
+
+        ui.add_space(5.0);
+        if stacked {
+            self.show_heatmap_legend(ui);
+        } else {
+            let saturation = self.compare_delta_saturation;
+            show_legend(
+                ui,
+                vec![
+                    LegendItem::Swatch(
+                        delta_color(-(saturation as f64), saturation),
+                        format!("A needs {}+ fewer", saturation),
+                    ),
+                    LegendItem::Swatch(delta_color(0.0, saturation), "Same".to_string()),
+                    LegendItem::Swatch(
+                        delta_color(saturation as f64, saturation),
+                        format!("A needs {}+ more", saturation),
+                    ),
+                    LegendItem::Separator,
+                    LegendItem::Swatch(
+                        CellShade::NoData.color(),
+                        "Not analyzed by both jobs".to_string(),
+                    ),
+                ],
+            );
+        }
+    }
+
+    /// Coverage threshold editor. Changing it recounts variants for every
+    /// window, so unlike the color controls it waits for Apply.
+    fn coverage_threshold_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label("Coverage threshold (%):");
+        ui.add(
+            egui::DragValue::new(&mut self.view_coverage_threshold)
+                .range(1.0..=100.0)
+                .speed(0.5),
+        );
+        let pending = self.view_coverage_threshold != self.applied_coverage_threshold;
+        // Summary files keep only the top variants, too few to recount
+        let variants_kept = self.results.as_ref().and_then(|r| r.variants_kept);
+        let lazy = self.results_detail_source.is_some();
+        let disabled_hover = match variants_kept {
+            Some(n) => format!(
+                "Only the top {} variants per window were saved; recounting needs the full lists",
+                n
+            ),
+            None if lazy => "Variant lists of indexed results are read per window; \
+                             use Load All Variants to recount"
+                .to_string(),
+            None => format!(
+                "Variants needed are counted at {:.1}%",
+                self.applied_coverage_threshold
+            ),
+        };
+        if ui
+            .add_enabled(
+                pending && variants_kept.is_none() && !lazy,
+                egui::Button::new("Apply"),
+            )
+            .on_hover_text("Recount variants needed at this threshold")
+            .on_disabled_hover_text(disabled_hover)
+            .clicked()
+        {
+            self.recalculate_coverage_threshold();
+        }
+    }
+
+    fn show_heatmap(
+        &mut self,
+        ui: &mut egui::Ui,
+        lengths: &[u32],
+        template_seq: &str,
+        coverage_threshold: f64,
+    ) {
+        let scroll_to_column = self.heatmap_scroll_to_column.take();
+        let mut cursor = self.heatmap_cursor;
+        let keys_active = !self.show_command_palette && !ui.ctx().wants_keyboard_input();
+        let excl_filter = self.active_exclusivity_filter();
+        let results = self.results.as_ref().unwrap();
+        let row_ranges = self.row_color_ranges(results, excl_filter.as_ref());
+
+        // Positions across all lengths (filtered result files may have gaps per length)
+        let positions: Vec<usize> = {
+            let set: std::collections::BTreeSet<usize> = results
+                .results_by_length
+                .values()
+                .flat_map(|lr| lr.positions.iter().map(|p| p.position))
+                .collect();
+            set.into_iter().collect()
+        };
+
+        if positions.is_empty() {
+            ui.label("No positions analyzed.");
+            return;
+        }
+
+        // Cell dimensions: zoom only affects horizontal width, height is fixed
+        let cell_w = 14.0 * self.zoom_level.max(0.01);
+        // Positions per drawn column; above 1 when cells would be sub-pixel
+        let bin_size = if cell_w < MIN_CELL_WIDTH {
+            (MIN_CELL_WIDTH / cell_w).ceil() as usize
+        } else {
+            1
+        };
+        let cell_h: f32 = 54.0;
+        let label_width: f32 = 50.0;
+        let header_height: f32 = 20.0;
+        let pos_label_height: f32 = 14.0;
+
+        let num_cols = positions.len();
+        let num_rows = lengths.len();
+
+        // Summary stats per length
+        ui.group(|ui| {
+            ui.horizontal_wrapped(|ui| {
+                for &length in lengths {
+                    if let Some(lr) = results.results_by_length.get(&length) {
+                        let non_skipped: Vec<_> =
+                            lr.positions.iter().filter(|p| !p.analysis.skipped).collect();
+                        if !non_skipped.is_empty() {
+                            let avg: f64 =
+                                non_skipped.iter().map(|p| p.variants_needed).sum::<usize>()
+                                    as f64
+                                    / non_skipped.len() as f64;
+                            let min = non_skipped
+                                .iter()
+                                .map(|p| p.variants_needed)
+                                .min()
+                                .unwrap_or(0);
+                            let max = non_skipped
+                                .iter()
+                                .map(|p| p.variants_needed)
+                                .max()
+                                .unwrap_or(0);
+                            ui.label(format!(
+                                "{}bp: {}-{} (avg {:.1})",
+                                length, min, max, avg
+                            ));
+                            ui.separator();
+                        }
+                    }
+                }
+            });
+        });
+
+        ui.add_space(5.0);
+
+        // Deferred until the results borrow ends: Some(true) = whole, Some(false) = visible
+        let mut copy_whole_heatmap: Option<bool> = None;
+        let mut export_image = false;
+        let mut load_annotations = false;
+        let mut clear_annotations = false;
+        let mut compare_selected = false;
+        let mut figure_size = self.heatmap_figure_size;
+        let mut conservation_track = self.conservation_track;
+        ui.horizontal(|ui| {
+            let caption = self.heatmap_caption(coverage_threshold);
+            if self.differential_mode() {
+                ui.label(caption);
+            } else {
+                ui.label(format!("{} (click cell for details):", caption));
+            }
+            ui.menu_button("Copy Image", |ui| {
+                if ui.button("Visible heatmap").clicked() {
+                    copy_whole_heatmap = Some(false);
+                    ui.close_menu();
+                }
+                if ui.button("Whole heatmap").clicked() {
+                    copy_whole_heatmap = Some(true);
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Export Image", |ui| {
+                ui.label("Whole heatmap with legend and sequence track");
+                egui::Grid::new("heatmap_figure_size").show(ui, |ui| {
+                    ui.label("Pixels per position:");
+                    ui.add(egui::DragValue::new(&mut figure_size.cell_width).range(1..=64));
+                    ui.end_row();
+                    ui.label("Row height (px):");
+                    ui.add(egui::DragValue::new(&mut figure_size.cell_height).range(4..=256));
+                    ui.end_row();
+                    ui.label("Text scale:");
+                    ui.add(egui::DragValue::new(&mut figure_size.text_scale).range(1..=8))
+                        .on_hover_text("1 = 7 px tall text");
+                    ui.end_row();
+                });
+                if ui.button("Save PNG/SVG...").clicked() {
+                    export_image = true;
+                    ui.close_menu();
+                }
+            });
+            if ui
+                .button("Annotations...")
+                .on_hover_text(
+                    "Load a GFF3 or BED file of template features to draw above the heatmap",
+                )
+                .clicked()
+            {
+                load_annotations = true;
+            }
+            if let Some(name) = &self.annotation_file_name {
+                ui.label(egui::RichText::new(name).weak());
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Remove the annotation track")
+                    .clicked()
+                {
+                    clear_annotations = true;
+                }
+            }
+            if !results.conservation.is_empty() {
+                ui.label("Conservation:");
+                egui::ComboBox::from_id_salt("conservation_track")
+                    .selected_text(conservation_track.label())
+                    .show_ui(ui, |ui| {
+                        for track in ConservationTrack::ALL {
+                            ui.selectable_value(&mut conservation_track, track, track.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Per-base track above the heatmap: share of references with the \
+                         template's base, or entropy of the bases and deletions",
+                    );
+            }
+            let selected = self.heatmap_selection.len();
+            if selected > 0
+                && ui
+                    .button(format!("Compare Selected ({})", selected))
+                    .on_hover_text(
+                        "Ctrl+click cells to add them to or remove them from the selection",
+                    )
+                    .clicked()
+            {
+                compare_selected = true;
+            }
+            if let Some(ref status) = self.heatmap_copy_status {
+                ui.label(egui::RichText::new(status).weak());
+            }
+        });
+        if let Some(ref e) = self.annotation_error {
+            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), e);
+        }
+
+        // Build heatmap data: lookup by (length, position)
+        let heatmap_data: std::collections::HashMap<
+            (u32, usize),
+            &PositionResult,
+        > = {
+            let mut map = std::collections::HashMap::new();
+            for &length in lengths {
+                if let Some(lr) = results.results_by_length.get(&length) {
+                    for pr in &lr.positions {
+                        map.insert((length, pr.position), pr);
+                    }
+                }
+            }
+            map
+        };
+        let shade_at = |length: u32, pos: usize| {
+            let oligo = results
+                .template_sequence
+                .get(pos..pos + length as usize)
+                .unwrap_or("");
+            self.heatmap_cell_shade(
+                heatmap_data.get(&(length, pos)).copied(),
+                oligo,
+                excl_filter.as_ref(),
+                row_ranges.get(&length),
+            )
+        };
+
+        // Annotation features on the template, stacked into lanes
+        let template_name = self
+            .selected_job()
+            .map_or("", |job| job.template_data.name.as_str());
+        let features = features_for_template(&self.annotations, template_name);
+        let (lanes, lane_count) = feature_lanes(&features);
+        let feature_height = if lane_count > 0 {
+            lane_count as f32 * ANNOTATION_LANE_HEIGHT + 4.0
+        } else {
+            0.0
+        };
+        // Per-base conservation below the features
+        let conservation_height =
+            if conservation_track != ConservationTrack::Off && !results.conservation.is_empty() {
+                CONSERVATION_TRACK_HEIGHT
+            } else {
+                0.0
+            };
+        let track_height = feature_height + conservation_height;
+        // Column of a template position (the first drawn at or after it)
+        let column_of = |pos: usize| positions.partition_point(|&p| p < pos);
+
+        // Total width/height for the heatmap area
+        let total_width = label_width + (num_cols as f32 * cell_w);
+        let total_height =
+            pos_label_height + track_height + header_height + (num_rows as f32 * cell_h) + 30.0;
+
+        // Arrow keys move the cursor cell a drawn column or a row at a time;
+        // Enter opens its detail window
+        let mut open_cursor = false;
+        let mut cursor_moved = false;
+        if let Some((length, pos)) = cursor
+            && keys_active
+        {
+            let (dx, dy, enter) = ui.input_mut(|i| {
+                let mut key = |k: egui::Key| i.consume_key(egui::Modifiers::NONE, k) as isize;
+                (
+                    key(egui::Key::ArrowRight) - key(egui::Key::ArrowLeft),
+                    key(egui::Key::ArrowDown) - key(egui::Key::ArrowUp),
+                    key(egui::Key::Enter) != 0,
+                )
+            });
+            let col = column_of(pos) as isize + dx * bin_size as isize;
+            let col = col.clamp(0, num_cols as isize - 1) as usize;
+            let row = lengths.iter().position(|&l| l == length).unwrap_or(0) as isize + dy;
+            let row = row.clamp(0, num_rows as isize - 1) as usize;
+            cursor = Some((lengths[row], positions[col]));
+            cursor_moved = dx != 0 || dy != 0;
+            open_cursor = enter;
+        }
+
+        // Genome-wide minimap when the heatmap is wider than the view
+        let mut jump_offset = scroll_to_column.map(|col| col as f32 * cell_w);
+        let mut scroll_y: Option<f32> = None;
+        // Keep a moved cursor in view
+        if cursor_moved
+            && let Some((length, pos)) = cursor
+            && let Some(view) = self.heatmap_viewport
+        {
+            let offset = self.heatmap_scroll_offset;
+            let x = label_width + column_of(pos) as f32 * cell_w;
+            if x < offset.x + label_width || x + cell_w > offset.x + view.width() {
+                jump_offset = Some((x - view.width() / 2.0).max(0.0));
+            }
+            let row = lengths.iter().position(|&l| l == length).unwrap_or(0);
+            let y = pos_label_height + track_height + header_height + row as f32 * cell_h;
+            if y < offset.y {
+                scroll_y = Some(y);
+            } else if y + cell_h > offset.y + view.height() {
+                scroll_y = Some(y + cell_h - view.height());
+            }
+        }
+        if total_width > ui.available_width() {
+            let view_width = self
+                .heatmap_viewport
+                .map_or(ui.available_width(), |r| r.width());
+            let first = ((self.heatmap_scroll_offset.x - label_width) / cell_w).max(0.0) as usize;
+            let shown = (view_width / cell_w).ceil() as usize;
+            let visible = first.min(num_cols)..(first + shown).min(num_cols);
+            if let Some(col) = self.show_heatmap_minimap(ui, lengths, &positions, visible, shade_at)
+            {
+                jump_offset = Some((label_width + col as f32 * cell_w - view_width / 2.0).max(0.0));
+            }
+            ui.add_space(3.0);
+        }
+
+        // Rows beyond HEATMAP_MAX_ROWS scroll vertically; the mouse wheel
+        // scrolls sideways only while every row fits
+        let max_height = pos_label_height
+            + track_height
+            + header_height
+            + (HEATMAP_MAX_ROWS.min(num_rows) as f32 * cell_h)
+            + 30.0;
+        let scrolls_vertically = total_height > max_height;
+        let mut scroll_area = egui::ScrollArea::both()
+            .id_salt("heatmap_scroll")
+            .max_height(max_height);
+        if let Some(x) = jump_offset {
+            scroll_area = scroll_area.horizontal_scroll_offset(x);
+        }
+        if let Some(y) = scroll_y {
+            scroll_area = scroll_area.vertical_scroll_offset(y);
+        }
+        let scroll_output = scroll_area.show(ui, |ui| {
+            let (response, painter) = ui.allocate_painter(
+                egui::vec2(total_width, total_height),
+                egui::Sense::click_and_drag(),
+            );
+            let origin = response.rect.min;
+
+            // --- Position numbers row ---
+            let show_every_n = if cell_w < 12.0 {
+                (12.0 / cell_w).ceil() as usize
+            } else {
+                1
+            };
+
+            for (col, &pos) in positions.iter().enumerate() {
+                if col % show_every_n != 0 {
+                    continue;
+                }
+                let x = origin.x + label_width + (col as f32 * cell_w) + cell_w / 2.0;
+                let y = origin.y + pos_label_height / 2.0;
+                painter.text(
+                    egui::pos2(x, y),
+                    egui::Align2::CENTER_CENTER,
+                    format!("{}", pos + 1),
+                    egui::FontId::proportional(9.0),
+                    egui::Color32::GRAY,
+                );
+            }
+
+            // --- Annotation track ---
+            let track_y_start = origin.y + pos_label_height;
+            let mut clicked_feature: Option<(usize, usize)> = None;
+            if lane_count > 0 {
+                painter.text(
+                    egui::pos2(origin.x + label_width - 5.0, track_y_start + feature_height / 2.0),
+                    egui::Align2::RIGHT_CENTER,
+                    "Features",
+                    egui::FontId::proportional(9.0),
+                    egui::Color32::GRAY,
+                );
+            }
+            for (feature, &lane) in features.iter().zip(&lanes) {
+                let (first, last) = (column_of(feature.start), column_of(feature.end));
+                let x0 = origin.x + label_width + first as f32 * cell_w;
+                let x1 = (origin.x + label_width + last as f32 * cell_w).max(x0 + 1.0);
+                let y0 = track_y_start + 2.0 + lane as f32 * ANNOTATION_LANE_HEIGHT;
+                let rect = egui::Rect::from_min_max(
+                    egui::pos2(x0, y0),
+                    egui::pos2(x1, y0 + ANNOTATION_LANE_HEIGHT - 2.0),
+                );
+                let color = annotation_color(&feature.kind);
+                painter.rect_filled(rect, 2.0, color);
+                if rect.width() > feature.name.len() as f32 * 6.0 + 4.0 {
+                    painter.with_clip_rect(rect).text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        &feature.name,
+                        egui::FontId::proportional(9.0),
+                        egui::Color32::BLACK,
+                    );
+                }
+
+                if let Some(pointer_pos) = response.hover_pos()
+                    && rect.contains(pointer_pos)
+                {
+                    painter.rect_stroke(
+                        rect,
+                        2.0,
+                        egui::Stroke::new(1.5, egui::Color32::WHITE),
+                        egui::StrokeKind::Outside,
+                    );
+                    response.clone().on_hover_text(format!(
+                        "{} ({})\n{}-{} ({} bp), {} strand\nClick to zoom to this feature",
+                        feature.name,
+                        feature.kind,
+                        feature.start + 1,
+                        feature.end,
+                        feature.end - feature.start,
+                        if feature.reverse { "reverse" } else { "forward" }
+                    ));
+                    if response.clicked() {
+                        clicked_feature = Some((first, last));
+                    }
+                }
+            }
+
+            // --- Conservation track: bar height and color by variability ---
+            let conservation_y = track_y_start + feature_height;
+            if conservation_height > 0.0 {
+                painter.text(
+                    egui::pos2(
+                        origin.x + label_width - 5.0,
+                        conservation_y + conservation_height / 2.0,
+                    ),
+                    egui::Align2::RIGHT_CENTER,
+                    conservation_track.label(),
+                    egui::FontId::proportional(9.0),
+                    egui::Color32::GRAY,
+                );
+                let bar_bottom = conservation_y + conservation_height - 2.0;
+                let bar_max = conservation_height - 4.0;
+                for (bin, bin_positions) in positions.chunks(bin_size).enumerate() {
+                    let covered: Vec<&BaseConservation> = bin_positions
+                        .iter()
+                        .filter_map(|&pos| results.conservation.get(pos))
+                        .filter(|c| c.coverage > 0)
+                        .collect();
+                    if covered.is_empty() {
+                        continue;
+                    }
+                    let variability = covered
+                        .iter()
+                        .map(|c| conservation_track.variability(c))
+                        .sum::<f32>()
+                        / covered.len() as f32;
+                    // Identity bars are tall where conserved, entropy bars where variable
+                    let height = match conservation_track {
+                        ConservationTrack::Identity => 1.0 - variability,
+                        _ => variability,
+                    };
+                    let x = origin.x + label_width + (bin * bin_size) as f32 * cell_w;
+                    let width = (bin_positions.len() as f32 * cell_w - 1.0).max(1.0);
+                    let (r, g, b) = green_yellow_red_from_t(variability as f64);
+                    painter.rect_filled(
+                        egui::Rect::from_min_max(
+                            egui::pos2(x, bar_bottom - (bar_max * height).max(1.0)),
+                            egui::pos2(x + width, bar_bottom),
+                        ),
+                        0.0,
+                        egui::Color32::from_rgb(r as u8, g as u8, b as u8),
+                    );
+
+                    let column = egui::Rect::from_min_size(
+                        egui::pos2(x, conservation_y),
+                        egui::vec2(width, conservation_height),
+                    );
+                    if let Some(pointer_pos) = response.hover_pos()
+                        && column.contains(pointer_pos)
+                    {
+                        let offset = ((pointer_pos.x - x) / cell_w).max(0.0) as usize;
+                        let pos = bin_positions[offset.min(bin_positions.len() - 1)];
+                        if let Some(base) = results.conservation.get(pos) {
+                            response.clone().on_hover_text(format!(
+                                "Position {}: {:.1}% identity, entropy {:.2} bits ({} references)",
+                                pos + 1,
+                                base.identity * 100.0,
+                                base.entropy,
+                                base.coverage
+                            ));
+                        }
+                    }
+                }
+            }
+
+            // --- Template sequence row ---
+            let seq_y_start = track_y_start + track_height;
+            if cell_w >= 8.0 {
+                for (col, &pos) in positions.iter().enumerate() {
+                    if pos < template_seq.len() {
+                        let base = &template_seq[pos..pos + 1];
+                        let x =
+                            origin.x + label_width + (col as f32 * cell_w) + cell_w / 2.0;
+                        let y = seq_y_start + header_height / 2.0;
+
+                        let color = base_color(base.chars().next().unwrap_or('N'));
+                        painter.text(
+                            egui::pos2(x, y),
+                            egui::Align2::CENTER_CENTER,
+                            base,
+                            egui::FontId::monospace(11.0),
+                            color,
+                        );
+                    }
+                }
+            } else if bin_size == 1 {
+                for (col, &pos) in positions.iter().enumerate() {
+                    if pos < template_seq.len() {
+                        let base_char = template_seq.as_bytes()[pos] as char;
+                        let color = base_color(base_char);
+                        let x = origin.x + label_width + (col as f32 * cell_w);
+                        let tick_rect = egui::Rect::from_min_size(
+                            egui::pos2(x, seq_y_start + 2.0),
+                            egui::vec2((cell_w - 1.0).max(1.0), header_height - 4.0),
+                        );
+                        painter.rect_filled(tick_rect, 0.0, color);
+                    }
+                }
+            }
+
+            // --- Row labels (oligo lengths) ---
+            let grid_y_start = seq_y_start + header_height;
+            for (row, &length) in lengths.iter().enumerate() {
+                let y = grid_y_start + (row as f32 * cell_h) + cell_h / 2.0;
+                painter.text(
+                    egui::pos2(origin.x + label_width - 5.0, y),
+                    egui::Align2::RIGHT_CENTER,
+                    format!("{} bp", length),
+                    egui::FontId::proportional(11.0),
+                    egui::Color32::LIGHT_GRAY,
+                );
+            }
+
+            // --- Heatmap cells ---
+            let mut hovered_cell: Option<(u32, usize)> = None;
+            let mut clicked_cell: Option<(u32, usize)> = None;
+
+            // Bins are drawn without the 1 px gap between cells
+            let gap = if bin_size > 1 { 0.0 } else { 1.0 };
+            for (row, &length) in lengths.iter().enumerate() {
+                for (bin, bin_positions) in positions.chunks(bin_size).enumerate() {
+                    let col = bin * bin_size;
+                    let cell_x = origin.x + label_width + (col as f32 * cell_w);
+                    let cell_y = grid_y_start + (row as f32 * cell_h);
+                    let cell_rect = egui::Rect::from_min_size(
+                        egui::pos2(cell_x, cell_y),
+                        egui::vec2(bin_positions.len() as f32 * cell_w - gap, cell_h - 1.0),
+                    );
+
+                    let shades = bin_positions.iter().map(|&pos| shade_at(length, pos));
+                    let color = CellShade::aggregate(shades, self.heatmap_bin_mode).color();
+
+                    painter.rect_filled(cell_rect, 1.0, color);
+                    if !results.params.masked_regions.is_empty()
+                        && bin_positions
+                            .iter()
+                            .all(|&pos| results.params.window_masked(pos, length as usize))
+                    {
+                        paint_hatched(&painter, cell_rect, egui::Color32::from_gray(150));
+                    }
+                    let marked = |cell: &(u32, usize)| {
+                        cell.0 == length && bin_positions.binary_search(&cell.1).is_ok()
+                    };
+                    if cursor.as_ref().is_some_and(marked) {
+                        painter.rect_stroke(
+                            cell_rect,
+                            1.0,
+                            egui::Stroke::new(2.0, egui::Color32::YELLOW),
+                            egui::StrokeKind::Inside,
+                        );
+                    } else if self.heatmap_selection.iter().any(marked) {
+                        painter.rect_stroke(
+                            cell_rect,
+                            1.0,
+                            egui::Stroke::new(2.0, egui::Color32::from_rgb(80, 200, 255)),
+                            egui::StrokeKind::Inside,
+                        );
+                    }
+
+                    // The window under the pointer, also within a bin
+                    let window_at = |pointer: egui::Pos2| {
+                        let offset = ((pointer.x - cell_x) / cell_w).max(0.0) as usize;
+                        bin_positions[offset.min(bin_positions.len() - 1)]
+                    };
+                    if let Some(pointer_pos) = response.hover_pos()
+                        && cell_rect.contains(pointer_pos)
+                    {
+                            hovered_cell = Some((length, window_at(pointer_pos)));
+                            painter.rect_stroke(
+                                cell_rect,
+                                1.0,
+                                egui::Stroke::new(1.5, egui::Color32::WHITE),
+                                egui::StrokeKind::Outside,
+                            );
+                        }
+
+                    if response.clicked()
+                        && let Some(pointer_pos) = ui.ctx().pointer_latest_pos()
+                        && cell_rect.contains(pointer_pos)
+                    {
+                                clicked_cell = Some((length, window_at(pointer_pos)));
+                            }
+                }
+            }
+
+            // Handle tooltip
+            if let Some((length, pos)) = hovered_cell
+                && let Some(pr) = heatmap_data.get(&(length, pos))
+            {
+                    let mut tooltip_text = if pr.analysis.skipped {
+                        format!(
+                            "Position: {}, Length: {} bp\nSkipped: {}",
+                            pos + 1,
+                            length,
+                            pr.analysis
+                                .skip_reason
+                                .as_deref()
+                                .unwrap_or("Unknown")
+                        )
+                    } else {
+                        format!(
+                            "Position: {}, Length: {} bp\nVariants needed: {}\nCoverage: {:.1}%\nMatched: {}/{}\nNo match: {}",
+                            pos + 1,
+                            length,
+                            pr.variants_needed,
+                            pr.analysis.coverage_at_threshold,
+                            pr.analysis.sequences_analyzed,
+                            pr.analysis.total_sequences,
+                            pr.analysis.no_match_count,
+                        )
+                    };
+
+                    if let Some(stats) = pr.analysis.score_stats {
+                        tooltip_text.push_str(&format!(
+                            "\nAlignment score: {} - {} (mean {:.1})",
+                            stats.min, stats.max, stats.mean
+                        ));
+                    }
+                    if let Some(identity) = pr.analysis.identity_stats {
+                        tooltip_text.push_str(&format!(
+                            "\nIdentity: mean {:.1}%, min {:.1}%",
+                            identity.mean, identity.min
+                        ));
+                    }
+                    if let Some(mm) = pr.analysis.mismatch_stats {
+                        tooltip_text.push_str(&format!(
+                            "\nMismatches: mean {:.2}, max {}",
+                            mm.mean, mm.max
+                        ));
+                    }
+                    if matches!(
+                        self.heatmap_metric,
+                        HeatmapMetric::GcPercent
+                            | HeatmapMetric::MeltingTemp
+                            | HeatmapMetric::Entropy
+                            | HeatmapMetric::Composite
+                    ) {
+                        let oligo = results
+                            .template_sequence
+                            .get(pos..pos + length as usize)
+                            .unwrap_or("");
+                        let conditions = self.results_tm_conditions();
+                        if let Some(value) =
+                            self.current_window_metric().value(pr, oligo, &conditions)
+                        {
+                            tooltip_text.push_str(&format!(
+                                "\n{}: {:.1}",
+                                self.heatmap_metric.label(),
+                                value
+                            ));
+                        }
+                    }
+
+                    if let Some(gc) = pr.gc_percent {
+                        tooltip_text.push_str(&format!("\nGC: {:.1}%", gc));
+                    }
+                    if pr.analysis.truncated {
+                        tooltip_text.push_str("\nTruncated: time budget reached");
+                    }
+                    if excl_filter.is_some_and(|f| !f.passes(pr)) {
+                        tooltip_text.push_str("\nFiltered out by exclusivity threshold");
+                    }
+                    let oligo = results
+                        .template_sequence
+                        .get(pos..pos + length as usize)
+                        .unwrap_or("");
+                    if !self.gc_filter_passes(pr, oligo) {
+                        tooltip_text.push_str("\nGreyed out by GC filter");
+                    }
+
+                    // Add exclusivity info to tooltip
+                    if let Some(ref excl) = pr.exclusivity {
+                        let eff = effective_min_mismatches(excl, self.diff_ignore_count);
+                        let mm_str = match eff {
+                            Some(mm) => format!("{}", mm),
+                            None => "all no-match".to_string(),
+                        };
+                        tooltip_text.push_str(&format!(
+                            "\nExclusivity: min mismatches = {} ({} sequences)",
+                            mm_str, excl.total_sequences
+                        ));
+                        if let Some(group) = pr
+                            .limiting_exclusivity_group()
+                            .and_then(|g| results.exclusivity_groups.get(g))
+                        {
+                            tooltip_text.push_str(&format!("\nLimiting group: {}", group));
+                        }
+                    }
+
+                    response.clone().on_hover_text(tooltip_text);
+                }
+
+            (clicked_cell, clicked_feature)
+        });
+
+        // Handle click
+        let (clicked_cell, clicked_feature) = scroll_output.inner;
+        let mut open_cell = if open_cursor { cursor } else { None };
+        if let Some(cell) = clicked_cell {
+            cursor = Some(cell);
+            // Ctrl+click toggles the cell's selection instead of opening it
+            if ui.input(|i| i.modifiers.command) {
+                match self.heatmap_selection.iter().position(|&c| c == cell) {
+                    Some(i) => {
+                        self.heatmap_selection.remove(i);
+                    }
+                    None => self.heatmap_selection.push(cell),
+                }
+            } else {
+                open_cell = Some(cell);
+            }
+        }
+        self.heatmap_cursor = cursor;
+        if let Some((length, pos)) = open_cell {
+            self.selected_position = Some(pos);
+            self.selected_length_for_detail = Some(length);
+            self.show_detail_window = true;
+        }
+        if compare_selected {
+            self.show_cell_comparison_window = true;
+        }
+        // Zoom so the feature fills the view, then scroll to it next frame
+        if let Some((first, last)) = clicked_feature {
+            let view_width = scroll_output.inner_rect.width() - label_width;
+            let columns = last.saturating_sub(first).max(1);
+            self.zoom_level = (view_width / (columns as f32 * 14.0)).clamp(0.01, 3.0);
+            self.heatmap_scroll_to_column = Some(first);
+        }
+        if load_annotations {
+            self.load_annotation_file();
+        }
+        if clear_annotations {
+            self.annotations.clear();
+            self.annotation_file_name = None;
+            self.annotation_error = None;
+        }
+        self.heatmap_viewport = Some(scroll_output.inner_rect);
+        self.heatmap_scroll_offset = scroll_output.state.offset;
+        match copy_whole_heatmap {
+            Some(true) => self.copy_whole_heatmap(ui.ctx()),
+            Some(false) => self.request_heatmap_screenshot(ui.ctx()),
+            None => {}
+        }
+        self.heatmap_figure_size = figure_size;
+        self.conservation_track = conservation_track;
+        if export_image {
+            self.export_heatmap_image();
+        }
+
+        // Redirect vertical mouse wheel to horizontal scroll when hovering over heatmap
+        if let Some(hover_pos) = ui.ctx().pointer_hover_pos()
+            && !scrolls_vertically
+            && scroll_output.inner_rect.contains(hover_pos)
+        {
+                let vertical_delta = ui.input(|i| i.smooth_scroll_delta.y);
+                if vertical_delta.abs() > 0.1 {
+                    let mut state = scroll_output.state;
+                    state.offset.x -= vertical_delta;
+                    state.offset.x = state.offset.x.clamp(
+                        0.0,
+                        (total_width - scroll_output.inner_rect.width()).max(0.0),
+                    );
+                    state.store(ui.ctx(), scroll_output.id);
+                    ui.ctx().request_repaint();
+                }
+            }
+
+        // Legend
+        ui.add_space(5.0);
+        self.show_heatmap_legend(ui);
+    }
+
+    /// Genome-wide strip of the heatmap compressed to the available width: each
+    /// pixel column aggregates a run of positions over all lengths, and the
+    /// visible column range is outlined. Returns the column clicked or dragged
+    /// to.
+    fn show_heatmap_minimap(
+        &self,
+        ui: &mut egui::Ui,
+        lengths: &[u32],
+        positions: &[usize],
+        visible: std::ops::Range<usize>,
+        shade_at: impl Fn(u32, usize) -> CellShade,
+    ) -> Option<usize> {
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), MINIMAP_HEIGHT),
+            egui::Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+        let num_cols = positions.len();
+        let strip_cols = (rect.width() as usize).clamp(1, num_cols);
+        let strip_w = rect.width() / strip_cols as f32;
+        let shade_at = &shade_at;
+        for j in 0..strip_cols {
+            let bin = &positions[j * num_cols / strip_cols..(j + 1) * num_cols / strip_cols];
+            let shades = lengths
+                .iter()
+                .flat_map(|&length| bin.iter().map(move |&pos| shade_at(length, pos)));
+            let color = CellShade::aggregate(shades, self.heatmap_bin_mode).color();
+            let cell = egui::Rect::from_min_size(
+                egui::pos2(rect.left() + j as f32 * strip_w, rect.top()),
+                egui::vec2(strip_w, rect.height()),
+            );
+            painter.rect_filled(cell, 0.0, color);
+        }
+
+        let x_of = |col: usize| rect.left() + col as f32 / num_cols as f32 * rect.width();
+        let view = egui::Rect::from_x_y_ranges(
+            x_of(visible.start)..=x_of(visible.end.max(visible.start + 1)),
+            rect.y_range(),
+        );
+        painter.rect_stroke(
+            view,
+            0.0,
+            egui::Stroke::new(1.5, egui::Color32::WHITE),
+            egui::StrokeKind::Inside,
+        );
+
+        let col_at = |x: f32| {
+            let t = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            ((t * num_cols as f32) as usize).min(num_cols - 1)
+        };
+        let jump = if response.clicked() || response.dragged() {
+            response.interact_pointer_pos().map(|p| col_at(p.x))
+        } else {
+            None
+        };
+        if let Some(pointer) = response.hover_pos() {
+            response.on_hover_text(format!(
+                "Position {}: click or drag to scroll there",
+                positions[col_at(pointer.x)] + 1
+            ));
+        }
+        jump
+    }
+
+    /// Heatmap cell color for a window under the current display settings.
+    /// `oligo` is the template oligo of the window; `row_range`, when set,
+    /// replaces the metric's global color range (per-length normalization).
+    fn heatmap_cell_color(
+        &self,
+        pr: Option<&PositionResult>,
+        oligo: &str,
+        excl_filter: Option<&ExclusivityFilter>,
+        row_range: Option<&MetricRange>,
+    ) -> egui::Color32 {
+        self.heatmap_cell_shade(pr, oligo, excl_filter, row_range)
+            .color()
+    }
+
+    /// Whether a window's GC% is inside the GC filter range (always true with
+    /// the filter off)
+    fn gc_filter_passes(&self, pr: &PositionResult, oligo: &str) -> bool {
+        !self.gc_filter_enabled
+            || WindowMetric::GcPercent
+                .value(pr, oligo, &self.results_tm_conditions())
+                .is_none_or(|gc| (self.gc_filter_min..=self.gc_filter_max).contains(&gc))
+    }
+
+    /// Tm darkening applies on the normal heatmap, except when it already
+    /// shows Tm
+    fn tm_darkening_active(&self) -> bool {
+        self.tm_darkening
+            && !self.differential_mode()
+            && self.heatmap_metric != HeatmapMetric::MeltingTemp
+    }
+
+    /// Gradient position and darkening of a window's heatmap cell, kept
+    /// separate so zoomed-out bins can combine several windows.
+    fn heatmap_cell_shade(
+        &self,
+        pr: Option<&PositionResult>,
+        oligo: &str,
+        excl_filter: Option<&ExclusivityFilter>,
+        row_range: Option<&MetricRange>,
+    ) -> CellShade {
+        let Some(pr) = pr else {
+            return CellShade::Missing;
+        };
+        if pr.analysis.skipped {
+            return CellShade::NoData;
+        }
+        if excl_filter.is_some_and(|f| !f.passes(pr)) || !self.gc_filter_passes(pr, oligo) {
+            return CellShade::Filtered;
+        }
+
+        let no_match_frac = if pr.analysis.total_sequences > 0 {
+            pr.analysis.no_match_count as f64 / pr.analysis.total_sequences as f64
+        } else {
+            0.0
+        };
+        let nomatch_dark = ramp(
+            no_match_frac,
+            self.nomatch_ok_percent / 100.0,
+            self.nomatch_bad_percent / 100.0,
+        );
+        let tm_dark = match pr.tm {
+            Some(tm) if self.tm_darkening_active() => self.tm_band.badness(tm),
+            _ => 0.0,
+        };
+        let graded = |t: Option<f64>| match t {
+            Some(t) => CellShade::Graded {
+                t,
+                darkening: nomatch_dark.max(tm_dark),
+            },
+            None => CellShade::NoData,
+        };
+
+        if self.differential_mode() {
+            let eff_min_mm = pr
+                .exclusivity
+                .as_ref()
+                .and_then(|e| effective_min_mismatches(e, self.diff_ignore_count));
+            let t = exclusivity_gradient_t(eff_min_mm, self.diff_green_at, self.diff_red_at);
+            if self
+                .results
+                .as_ref()
+                .is_some_and(|r| r.params.exclusivity_only)
+            {
+                // No conservation analysis to darken by
+                return CellShade::Graded { t, darkening: 0.0 };
+            }
+            if pr.variants_needed == 0 {
+                return CellShade::NoData;
+            }
+            // Conservation darkening: either metric at its worst threshold
+            // makes the cell fully dark red, however specific it is
+            let variant_dark =
+                ramp_usize(pr.variants_needed, self.color_green_at, self.color_red_at);
+            CellShade::Graded {
+                t,
+                darkening: variant_dark.max(nomatch_dark),
+            }
+        } else if let Some(range) = row_range {
+            // Zero variants (no matched reference) stays "no data", as on the global scale
+            let value = self
+                .current_window_metric()
+                .value(pr, oligo, &self.results_tm_conditions())
+                .filter(|_| {
+                    self.heatmap_metric != HeatmapMetric::VariantsNeeded || pr.variants_needed > 0
+                });
+            graded(value.map(|v| range.badness(v)))
+        } else if self.heatmap_metric == HeatmapMetric::VariantsNeeded {
+            graded(
+                (pr.variants_needed > 0).then(|| {
+                    ramp_usize(pr.variants_needed, self.color_green_at, self.color_red_at)
+                }),
+            )
+        } else if let Some((scale, _)) = self.metric_scale() {
+            graded(
+                self.current_window_metric()
+                    .value(pr, oligo, &self.results_tm_conditions())
+                    .map(|v| scale.badness(v)),
+            )
+        } else if self.heatmap_metric == HeatmapMetric::MeanMismatches {
+            graded(
+                self.current_window_metric()
+                    .value(pr, oligo, &self.results_tm_conditions())
+                    .map(|v| mismatch_t(v, self.mismatch_green_at, self.mismatch_red_at)),
+            )
+        } else {
+            graded(
+                self.current_window_metric()
+                    .value(pr, oligo, &self.results_tm_conditions())
+                    .map(|v| identity_t(v, self.identity_green_at, self.identity_red_at)),
+            )
+        }
+    }
+
+    /// Legend for the current heatmap settings, shared by the on-screen
+    /// legend and exported images
+    fn heatmap_legend(&self) -> Vec<LegendItem> {
+        let swatch = |color, label: String| LegendItem::Swatch(color, label);
+        let mut items = Vec::new();
+
+        let nm_ok = self.nomatch_ok_percent / 100.0;
+        let nm_bad = self.nomatch_bad_percent / 100.0;
+        let g = self.color_green_at;
+        let r = self.color_red_at;
+
+        if self.differential_mode() {
+            items.push(LegendItem::Text("Legend (Differential):".to_string()));
+
+            // Exclusivity gradient samples (no darkening)
+            let dg = self.diff_green_at;
+            let dr = self.diff_red_at;
+
+            let sample_mms: Vec<(Option<u32>, String)> = if dg > dr {
+                vec![
+                    (Some(dg), format!(">={} mm", dg)),
+                    (Some((dg + dr) / 2), format!("{} mm", (dg + dr) / 2)),
+                    (Some(dr), format!("<={} mm", dr)),
+                ]
+            } else {
+                vec![
+                    (Some(dg), format!("{} mm", dg)),
+                    (Some(dr), format!("{} mm", dr)),
+                ]
+            };
+
+            for (mm_val, label) in sample_mms {
+                let color = differential_position_color(mm_val, 1, 0.0, dg, dr, g, r, 1.0, 1.0);
+                items.push(swatch(color, label));
+            }
+
+            items.push(LegendItem::Separator);
+            items.push(LegendItem::Text(
+                "+ darkening from conservation".to_string(),
+            ));
+        } else {
+            items.push(LegendItem::Text("Legend:".to_string()));
+
+            if self.normalize_per_length && self.metric_color_range().is_some() {
+                for (badness, label) in [
+                    (0.0, "best in length"),
+                    (0.5, "mid"),
+                    (1.0, "worst in length"),
+                ] {
+                    let color = scaled_position_color(Some(badness), 0.0, nm_ok, nm_bad);
+                    items.push(swatch(color, label.to_string()));
+                }
+            } else if let Some((scale, unit)) = self.metric_scale() {
+                let labels = scale.legend_labels(unit);
+                for (badness, label) in [0.0, 0.5, 1.0].into_iter().zip(labels) {
+                    let color = scaled_position_color(Some(badness), 0.0, nm_ok, nm_bad);
+                    items.push(swatch(color, label));
+                }
+            } else if self.heatmap_metric == HeatmapMetric::VariantsNeeded {
+                let sample_points: Vec<(usize, String)> = if r <= g {
+                    vec![(g, format!("<={}", g)), (g + 1, format!(">{}", g))]
+                } else {
+                    let mid = (g + r) / 2;
+                    let mut pts = vec![(g, format!("<={}", g))];
+                    if mid > g && mid < r {
+                        pts.push((mid, format!("{}", mid)));
+                    }
+                    pts.push((r, format!(">={}", r)));
+                    pts
+                };
+
+                for (count, label) in sample_points {
+                    let color = position_color(count, 0.0, g, r, nm_ok, nm_bad);
+                    items.push(swatch(color, label));
+                }
+            } else if self.heatmap_metric == HeatmapMetric::MeanMismatches {
+                let (mg, mr) = (self.mismatch_green_at, self.mismatch_red_at);
+                let sample_points = [
+                    (mg, format!("<={:.1}", mg)),
+                    ((mg + mr) / 2.0, format!("{:.1}", (mg + mr) / 2.0)),
+                    (mr, format!(">={:.1}", mr)),
+                ];
+
+                for (mismatches, label) in sample_points {
+                    let color =
+                        mismatch_position_color(Some(mismatches), 0.0, mg, mr, nm_ok, nm_bad);
+                    items.push(swatch(color, label));
+                }
+            } else {
+                let (ig, ir) = (self.identity_green_at, self.identity_red_at);
+                let mid = (ig + ir) / 2.0;
+                let sample_points = [
+                    (ig, format!(">={:.0}%", ig)),
+                    (mid, format!("{:.0}%", mid)),
+                    (ir, format!("<={:.0}%", ir)),
+                ];
+
+                for (identity, label) in sample_points {
+                    let color = identity_position_color(Some(identity), 0.0, ig, ir, nm_ok, nm_bad);
+                    items.push(swatch(color, label));
+                }
+            }
+
+            items.push(LegendItem::Separator);
+
+            let mid_count = (g + r) / 2;
+            let mid_count = if mid_count < 1 { 1 } else { mid_count };
+            let nm_samples = [
+                (nm_ok, format!("{}%", self.nomatch_ok_percent as u32)),
+                (nm_bad, format!("{}%", self.nomatch_bad_percent as u32)),
+            ];
+            items.push(LegendItem::Text("No-match:".to_string()));
+            for (nm_frac, label) in nm_samples {
+                let color = position_color(mid_count, nm_frac, g, r, nm_ok, nm_bad);
+                items.push(swatch(color, label));
+            }
+
+            if self.tm_darkening_active() {
+                let labels = MetricScale::Band(self.tm_band).legend_labels(" °C");
+                items.push(LegendItem::Separator);
+                items.push(LegendItem::Text(format!(
+                    "+ darkened outside Tm {} (dark red at {})",
+                    labels[0], labels[2]
+                )));
+            }
+        }
+
+        if self.active_exclusivity_filter().is_some() {
+            items.push(LegendItem::Separator);
+            items.push(swatch(
+                FILTERED_CELL_COLOR,
+                format!("filtered (<= {} mm)", self.excl_filter_threshold),
+            ));
+        }
+        if self.gc_filter_enabled {
+            items.push(LegendItem::Separator);
+            items.push(swatch(
+                FILTERED_CELL_COLOR,
+                format!(
+                    "GC outside {:.0}-{:.0}%",
+                    self.gc_filter_min, self.gc_filter_max
+                ),
+            ));
+        }
+
+        items.push(LegendItem::Separator);
+        items.push(swatch(
+            CellShade::NoData.color(),
+            "skipped/no data".to_string(),
+        ));
+        items
+    }
+
+    fn show_heatmap_legend(&self, ui: &mut egui::Ui) {
+        show_legend(ui, self.heatmap_legend());
+    }
+
+    fn show_variant_detail_window(&mut self, ctx: &egui::Context) {
+        let (Some(length), Some(position)) =
+            (self.selected_length_for_detail, self.selected_position)
+        else {
+            self.show_detail_window = false;
+            return;
+        };
+        if self.find_position_result(length, position).is_none() {
+            self.show_detail_window = false;
+            return;
+        }
+
+        let mut open = self.show_detail_window;
+        let mut action = None;
+        egui::Window::new(format!("Position {} Details", position + 1))
+            .open(&mut open)
+            .default_width(650.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                action = self.show_detail_contents(ui, length, position, true);
+            });
+        self.show_detail_window = open;
+        self.apply_detail_action(action, length, position);
+    }
+
+    fn apply_detail_action(&mut self, action: Option<DetailAction>, length: u32, position: usize) {
+        match action {
+            Some(DetailAction::Pin) if !self.pinned_details.contains(&(length, position)) => {
+                self.pinned_details.push((length, position));
+            }
+            Some(DetailAction::CompareA) => {
+                self.compare_a = Some((length, position));
+                self.show_compare_window = true;
+            }
+            Some(DetailAction::CompareB) => {
+                self.compare_b = Some((length, position));
+                self.show_compare_window = true;
+            }
+            Some(DetailAction::ExportMembers) => self.export_variant_members(length, position),
+            Some(DetailAction::ShowReferences) => self.start_assignment_table(length, position),
+            Some(DetailAction::AlignExclusivity(bucket)) => {
+                let name = self
+                    .find_position_result(length, position)
+                    .and_then(|pr| pr.exclusivity.as_ref())
+                    .and_then(|excl| excl.mismatch_histogram.get(bucket))
+                    .map(|b| b.example_name.clone());
+                let target = name.and_then(|name| {
+                    let excl = self.selected_job()?.exclusivity_data.as_ref()?;
+                    let i = excl.names.iter().position(|n| *n == name)?;
+                    Some((name, excl.sequences[i].clone()))
+                });
+                if let Some((name, sequence)) = target {
+                    self.open_alignment(length, position, name, &sequence);
+                }
+            }
+            Some(DetailAction::Nominate) => {
+                if let Some(results) = &mut self.results
+                    && results.nominate(length, position)
+                {
+                    self.store_nominations();
+                }
+                self.show_nominations_window = true;
+            }
+            Some(DetailAction::AddToOrder(i)) => {
+                let Some(variant) = self
+                    .find_position_result(length, position)
+                    .and_then(|pr| pr.analysis.variants.get(i))
+                else {
+                    return;
+                };
+                // Ordered as displayed: reverse complement and RNA, without codon spacing
+                let rc = self.detail_show_reverse_complement;
+                let sequence =
+                    format_sequence_for_display(&variant.sequence, rc, false, self.detail_show_rna);
+                let suffix = format!("_v{}{}", i + 1, if rc { "_rc" } else { "" });
+                let source = format!("Variant {}", i + 1);
+                self.add_to_order(sequence, length, position, &suffix, source);
+                self.show_order_window = true;
+            }
+            Some(DetailAction::CheckStructure) => {
+                let structures = self.find_position_result(length, position).map(|pr| {
+                    let sequences: Vec<&str> = pr
+                        .analysis
+                        .variants
+                        .iter()
+                        .map(|v| v.sequence.as_str())
+                        .collect();
+                    variant_structures(&sequences, pr.variants_needed)
+                });
+                if let Some(structures) = structures {
+                    self.structure_checks.insert((length, position), structures);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Pinned detail windows stay on their own window while the main detail
+    /// window keeps following heatmap clicks.
+    fn show_pinned_detail_windows(&mut self, ctx: &egui::Context) {
+        let mut closed = Vec::new();
+        for (length, position) in self.pinned_details.clone() {
+            let mut open = self.find_position_result(length, position).is_some();
+            let action = egui::Window::new(format!(
+                "Position {} / {} bp (pinned)",
+                position + 1,
+                length
+            ))
+                .id(egui::Id::new(("pinned_detail", length, position)))
+                .open(&mut open)
+                .default_width(650.0)
+                .default_height(500.0)
+                .show(ctx, |ui| self.show_detail_contents(ui, length, position, false))
+                .and_then(|r| r.inner.flatten());
+            self.apply_detail_action(action, length, position);
+            if !open {
+                closed.push((length, position));
+            }
+        }
+        self.pinned_details.retain(|d| !closed.contains(d));
+    }
+
+    /// Save the reference names carrying each variant of one window as CSV,
+    /// assigning the window's references first when they are not yet.
+    fn export_variant_members(&mut self, length: u32, position: usize) {
+        let table = self
+            .assignment_table
+            .as_ref()
+            .filter(|t| (t.length, t.position) == (length, position));
+        let (Some(table), Some(pr)) = (table, self.find_position_result(length, position)) else {
+            self.assignment_export_members = true;
+            self.start_assignment_table(length, position);
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("variant_references_{}bp_pos{}.csv", length, position + 1))
+            .save_file()
+        {
+            let csv = variant_members_csv(&table.rows, &pr.analysis.variants);
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// Assign every reference of one window on a background thread by
+    /// realigning the selected job's references.
+    fn start_assignment_table(&mut self, length: u32, position: usize) {
+        let (Some(results), Some(pr), Some(references)) = (
+            self.results.as_ref(),
+            self.find_position_result(length, position),
+            self.selected_references(),
+        ) else {
+            self.assignment_export_members = false;
+            return;
+        };
+        let references = references.clone();
+        let oligo = results
+            .template_sequence
+            .get(position..position + length as usize)
+            .unwrap_or("")
+            .as_bytes()
+            .to_vec();
+        let variants = pr.analysis.variants.clone();
+        let template = results.template_sequence.clone();
+        let params = results.params.clone();
+
+        let (tx, rx) = channel();
+        self.assignment_rx = Some(rx);
+        self.assignment_table = None;
+        self.show_assignment_window = true;
+        thread::spawn(move || {
+            let references = assignment_references(&template, &references, &params);
+            let rows = reference_assignments(&oligo, &variants, &references, &params);
+            let _ = tx.send(AssignmentTable {
+                length,
+                position,
+                rows,
+            });
+        });
+    }
+
+    /// The completed job whose results are viewed
+    fn selected_job(&self) -> Option<&WorklistJob> {
+        self.selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+            .map(|cj| &cj.job)
+    }
+
+    /// The selected job's references, when they are the ones its results
+    /// were screened against (results loaded from a file have none)
+    fn selected_references(&self) -> Option<&ReferenceData> {
+        let names = &self.results.as_ref()?.reference_names;
+        self.selected_job()
+            .map(|job| &job.reference_data)
+            .filter(|r| !r.is_empty() && r.len() == names.len())
+    }
+
+    /// Realign one window's template oligo against a single sequence and show
+    /// the alignment.
+    fn open_alignment(&mut self, length: u32, position: usize, name: String, target: &str) {
+        let Some(results) = &self.results else {
+            return;
+        };
+        let oligo = results
+            .template_sequence
+            .get(position..position + length as usize)
+            .unwrap_or("");
+        let params = &results.params.pairwise;
+        let view = alignment_view(oligo.as_bytes(), target.as_bytes(), params);
+        let title = format!("{} at position {} / {} bp", name, position + 1, length);
+        self.alignment_view = Some((title, view));
+        self.show_alignment_window = true;
+    }
+
+    /// Save the reference assignment table (all rows, in table order) as CSV.
+    fn export_assignments(&mut self) {
+        let Some(table) = &self.assignment_table else {
+            return;
+        };
+        let Some(pr) = self.find_position_result(table.length, table.position) else {
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!(
+                "reference_assignment_{}bp_pos{}.csv",
+                table.length,
+                table.position + 1
+            ))
+            .save_file()
+        {
+            let csv = assignments_csv(&table.rows, &pr.analysis.variants);
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// Copy the viewed results' nominations back to the selected completed job,
+    /// so they survive switching jobs.
+    fn store_nominations(&mut self) {
+        let (Some(results), Some(idx)) = (&self.results, self.selected_completed_job_index)
+        else {
+            return;
+        };
+        if let Some(cj) = self.completed_jobs.get_mut(idx) {
+            cj.results.nominations = results.nominations.clone();
+        }
+    }
+
+    /// Save the nominated candidates (optionally approved only) as a design list CSV.
+    fn export_nominations(&mut self) {
+        let Some(results) = &self.results else {
+            self.save_error = Some("No results to export".to_string());
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("design_list.csv")
+            .save_file()
+        {
+            let csv = nominations_csv(
+                results,
+                self.nominations_approved_only,
+                self.excl_filter_threshold,
+            );
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// Ranking settings of the Top Candidates table. The criteria's scales
+    /// are the heatmap's color settings.
+    fn ranking_params(&self) -> RankingParams {
+        RankingParams {
+            weights: self.ranking_weights,
+            variants_needed: MetricRange {
+                green_at: self.color_green_at as f64,
+                red_at: self.color_red_at as f64,
+            },
+            no_match: self.nomatch_range(),
+            exclusivity: MetricRange {
+                green_at: f64::from(self.diff_green_at),
+                red_at: f64::from(self.diff_red_at),
+            },
+            ignore_count: self.diff_ignore_count,
+            gc: self.gc_band,
+            tm: self.tm_band,
+            min_score: self.ranking_min_score,
+            max_variants_needed: self
+                .ranking_limit_variants
+                .then_some(self.ranking_max_variants),
+            max_no_match_percent: self
+                .ranking_limit_no_match
+                .then_some(self.ranking_max_no_match),
+        }
+    }
+
+    /// Rank the shown results' windows again if the table's settings changed.
+    fn update_candidate_table(&mut self) {
+        let params = self.ranking_params();
+        let filter = self.active_exclusivity_filter();
+        let (top_n, sort) = (self.ranking_top_n, self.ranking_sort);
+        if self.candidate_table.as_ref().is_some_and(|t| {
+            t.params == params && t.filter == filter && t.top_n == top_n && t.sort == sort
+        }) {
+            return;
+        }
+        let Some(results) = &self.results else {
+            self.candidate_table = None;
+            return;
+        };
+        let mut rows = rank_candidates(results, &params, filter.as_ref());
+        let ranked = rows.len();
+        rows.truncate(top_n);
+        sort_candidates(&mut rows, sort.0, sort.1);
+        let structures = rows.iter().map(|c| StructureCheck::of(&c.oligo)).collect();
+        self.candidate_table = Some(CandidateTable {
+            params,
+            filter,
+            top_n,
+            sort,
+            ranked,
+            rows,
+            structures,
+        });
+    }
+
+    /// Write the Top Candidates table's rows as FASTA.
+    fn export_top_candidates(&mut self) {
+        let (Some(table), Some(results)) = (&self.candidate_table, &self.results) else {
+            self.save_error = Some("No candidates to export".to_string());
+            return;
+        };
+        let prefix = self.template_stem("candidate");
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("FASTA", &["fasta", "fa"])
+            .set_file_name("top_candidates.fasta")
+            .save_file()
+        {
+            let fasta = candidates_fasta(&table.rows, &prefix, results.rna);
+            if let Err(e) = std::fs::write(&path, fasta) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// File stem of the viewed job's template, or `fallback`, for naming
+    /// exported oligos
+    fn template_stem(&self, fallback: &str) -> String {
+        self.selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+            .and_then(|cj| {
+                std::path::Path::new(&cj.job.template_file_name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .filter(|stem| !stem.is_empty())
+            .unwrap_or_else(|| fallback.to_string())
+    }
+
+    /// Add an oligo of the viewed job to the order selection, named
+    /// `<template>_pos<position>_<length>nt` plus `suffix`.
+    fn add_to_order(
+        &mut self,
+        sequence: String,
+        length: u32,
+        position: usize,
+        suffix: &str,
+        source: String,
+    ) {
+        let base = format!(
+            "{}_pos{}_{}nt{}",
+            self.template_stem("oligo"),
+            position + 1,
+            length,
+            suffix
+        );
+        let template = self
+            .selected_job()
+            .map(|job| job.template_data.name.clone())
+            .unwrap_or_default();
+        let (target_start, target) = self
+            .results
+            .as_ref()
+            .map(|r| target_region(&r.template_sequence, position, length))
+            .unwrap_or_default();
+        let oligo = OrderOligo {
+            name: unique_order_name(&self.order_selection, &base),
+            sequence,
+            length,
+            position,
+            source,
+            template,
+            target,
+            target_start,
+        };
+        self.order_selection.push(oligo);
+    }
+
+    /// Save the order selection as FASTA.
+    fn export_order_fasta(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("FASTA", &["fasta", "fa"])
+            .set_file_name("oligo_order.fasta")
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(&path, order_fasta(&self.order_selection)) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    /// Save the order selection as a vendor order sheet CSV.
+    fn export_order_sheet(&mut self) {
+        let format = self.order_format;
+        let (scales, purifications) = (format.scales(), format.purifications());
+        let scale = scales[self.order_scale.min(scales.len() - 1)];
+        let purification = purifications[self.order_purification.min(purifications.len() - 1)];
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}_order.csv", format.label().to_lowercase()))
+            .save_file()
+        {
+            let csv = order_sheet_csv(&self.order_selection, format, scale, purification);
+            if let Err(e) = std::fs::write(&path, csv) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+
+    fn find_position_result(&self, length: u32, position: usize) -> Option<&PositionResult> {
+        self.results
+            .as_ref()?
+            .results_by_length
+            .get(&length)?
+            .positions
+            .iter()
+            .find(|p| p.position == position)
+    }
+
+    /// Body of a detail window. Returns the header button clicked, if any.
+    fn show_detail_contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        length: u32,
+        position: usize,
+        pinnable: bool,
+    ) -> Option<DetailAction> {
+        self.load_window_variants(length, position);
+        let results = self.results.as_ref()?;
+        let pos_result = self.find_position_result(length, position)?.clone();
+        let coverage_threshold = results.params.coverage_threshold;
+        let variants_kept = results.variants_kept;
+        let exclusivity_taxa = results.exclusivity_taxa.clone();
+        let exclusivity_groups = results.exclusivity_groups.clone();
+        let near_mismatches = self.excl_filter_threshold;
+        let tm_conditions = results.params.tm_conditions;
+        let three_prime = results.params.pairwise.three_prime;
+        let exclusivity_loaded = self
+            .selected_job()
+            .is_some_and(|job| job.exclusivity_data.is_some());
+        let references_loaded = self.selected_references().is_some();
+        // Reference names of each variant, once this window's table is in
+        let variant_names: Option<Vec<Vec<String>>> = self
+            .assignment_table
+            .as_ref()
+            .filter(|t| (t.length, t.position) == (length, position))
+            .map(|t| {
+                variant_members(&t.rows, pos_result.analysis.variants.len())
+                    .into_iter()
+                    .map(|members| members.into_iter().map(|a| a.name.clone()).collect())
+                    .collect()
+            });
+
+        // Extract template oligo for display
+        let template_oligo = if position + length as usize <= results.template_sequence.len() {
+            &results.template_sequence[position..position + length as usize]
+        } else {
+            ""
+        };
+        let template_oligo = template_oligo.to_string();
+
+        let show_reverse_complement = self.detail_show_reverse_complement;
+        let show_codon_spacing = self.detail_show_codon_spacing;
+        let show_rna = self.detail_show_rna;
+        let structures = self.structure_checks.get(&(length, position)).cloned();
+        let thresholds = self.structure_thresholds;
+        let mut action = None;
+
+        // Unique id scope so grids and scroll areas don't collide across windows
+        ui.push_id((length, position), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("Position: {}", position + 1));
+                ui.separator();
+                ui.label(format!("Oligo length: {} bp", length));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if pinnable
+                        && ui
+                            .button("Pin")
+                            .on_hover_text("Keep this window open while browsing other cells")
+                            .clicked()
+                    {
+                        action = Some(DetailAction::Pin);
+                    }
+                    if ui
+                        .button("Nominate")
+                        .on_hover_text("Add this window to the nominated candidates")
+                        .clicked()
+                    {
+                        action = Some(DetailAction::Nominate);
+                    }
+                    if ui.button("Compare as B").clicked() {
+                        action = Some(DetailAction::CompareB);
+                    }
+                    if ui.button("Compare as A").clicked() {
+                        action = Some(DetailAction::CompareA);
+                    }
+                });
+            });
+
+            // Template oligo display
+            if !template_oligo.is_empty() {
+                let display_template = format_sequence_for_display(
+                    &template_oligo,
+                    show_reverse_complement,
+                    show_codon_spacing,
+                    show_rna,
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Template oligo:");
+                    ui.add(
+                        egui::Label::new(
+                            egui::RichText::new(&display_template)
+                                .monospace()
+                                .size(11.0)
+                                .color(egui::Color32::from_rgb(100, 180, 255)),
+                        )
+                        .wrap_mode(egui::TextWrapMode::Extend),
+                    );
+                    if let Some(gc) = pos_result.gc_percent {
+                        ui.separator();
+                        ui.label(format!("GC: {:.1}%", gc));
+                    }
+                    if let Some(tm) = pos_result.tm {
+                        ui.separator();
+                        ui.label(format!("Tm: {:.1} °C", tm)).on_hover_text(format!(
+                            "Nearest-neighbor Tm (SantaLucia 1998) at {} mM Na+, {} mM Mg2+, \
+                             {} nM oligo",
+                            tm_conditions.na_mm, tm_conditions.mg_mm, tm_conditions.oligo_nm
+                        ));
+                    }
+                });
+            }
+
+            ui.separator();
+
+            if pos_result.analysis.skipped {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "This window was skipped: {}",
+                        pos_result
+                            .analysis
+                            .skip_reason
+                            .as_deref()
+                            .unwrap_or("Unknown reason")
+                    ),
+                );
+                return;
+            }
+
+            ui.label(format!(
+                "Total references: {}",
+                pos_result.analysis.total_sequences
+            ));
+            ui.label(format!(
+                "Matched: {}",
+                pos_result.analysis.sequences_analyzed
+            ));
+            if pos_result.analysis.no_match_count > 0 {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    format!(
+                        "No match: {}/{} ({:.1}%)",
+                        pos_result.analysis.no_match_count,
+                        pos_result.analysis.total_sequences,
+                        (pos_result.analysis.no_match_count as f64
+                            / pos_result.analysis.total_sequences as f64)
+                            * 100.0
+                    ),
+                );
+            }
+            if pos_result.analysis.truncated {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    "⚠ Truncated: merging hit the per-window time budget; unmerged sequences \
+                     are listed as exact variants",
+                );
+            }
+            if let Some(kept) = variants_kept {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 180, 100),
+                    format!("Only the top {} variants per window were saved", kept),
+                );
+            }
+            ui.label(format!(
+                "Variants needed for {:.0}% coverage: {}",
+                coverage_threshold, pos_result.variants_needed
+            ));
+            ui.label(format!(
+                "Coverage at threshold: {:.1}%",
+                pos_result.analysis.coverage_at_threshold
+            ));
+            if let Some(stats) = pos_result.analysis.score_stats {
+                ui.label(format!(
+                    "Alignment score of matches: min {}, mean {:.1}, max {}",
+                    stats.min, stats.mean, stats.max
+                ));
+            }
+            if let Some(identity) = pos_result.analysis.identity_stats {
+                ui.label(format!(
+                    "Percent identity of matches: mean {:.1}%, min {:.1}%",
+                    identity.mean, identity.min
+                ));
+            }
+            if let Some(mm) = pos_result.analysis.mismatch_stats {
+                ui.label(format!(
+                    "Mismatches to template oligo: mean {:.2}, max {}",
+                    mm.mean, mm.max
+                ));
+            }
+            if pos_result.analysis.indel_matches > 0 {
+                ui.label(format!(
+                    "References matched with indels: {} ('-' deleted, lowercase inserted bases)",
+                    pos_result.analysis.indel_matches
+                ));
+            }
+
+            egui::CollapsingHeader::new("Coverage curve")
+                .default_open(true)
+                .show(ui, |ui| {
+                    draw_coverage_curve(
+                        ui,
+                        &pos_result.analysis.variants,
+                        pos_result.variants_needed,
+                        coverage_threshold,
+                    );
+                });
+
+            ui.separator();
+
+            // Display options
+            ui.horizontal(|ui| {
+                ui.heading("Variants");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .button("Structure")
+                        .on_hover_text(
+                            "Hairpin and self-dimer ΔG of each variant, and the most stable \
+                             hetero-dimer among the variants needed",
+                        )
+                        .clicked()
+                    {
+                        action = Some(DetailAction::CheckStructure);
+                    }
+                    if ui
+                        .add_enabled(references_loaded, egui::Button::new("Export names"))
+                        .on_hover_text("Save the reference names carrying each variant as CSV")
+                        .on_disabled_hover_text(REFERENCES_NOT_LOADED)
+                        .clicked()
+                    {
+                        action = Some(DetailAction::ExportMembers);
+                    }
+                    if ui
+                        .add_enabled(references_loaded, egui::Button::new("References"))
+                        .on_hover_text(
+                            "Which variant each reference matched, with its mismatch count",
+                        )
+                        .on_disabled_hover_text(REFERENCES_NOT_LOADED)
+                        .clicked()
+                    {
+                        action = Some(DetailAction::ShowReferences);
+                    }
+                    ui.checkbox(&mut self.detail_show_rna, "RNA")
+                        .on_hover_text("Show sequences with U in place of T");
+                    ui.checkbox(&mut self.detail_show_codon_spacing, "Codon spacing");
+                    ui.checkbox(
+                        &mut self.detail_show_reverse_complement,
+                        "Reverse complement",
+                    );
+                });
+            });
+
+            egui::ScrollArea::vertical()
+                .id_salt("detail_scroll")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    // Expansion counts only matter for the ambiguity methods
+                    let any_degenerate = pos_result
+                        .analysis
+                        .variants
+                        .iter()
+                        .any(|v| v.expansion_count() > 1);
+                    let any_indels = pos_result
+                        .analysis
+                        .variants
+                        .iter()
+                        .any(|v| v.indel_count() > 0);
+                    if any_degenerate {
+                        ui.label(format!(
+                            "The {} variants needed expand to {} concrete sequences",
+                            pos_result.variants_needed,
+                            pos_result.concrete_sequences_needed()
+                        ));
+                    }
+
+                    egui::Grid::new("variants_grid")
+                        .striped(true)
+                        .min_col_width(50.0)
+                        .show(ui, |ui| {
+                            ui.strong("#");
+                            ui.strong("Sequence");
+                            ui.strong("Count");
+                            ui.strong("Percentage");
+                            ui.strong("Cumulative");
+                            if any_degenerate {
+                                ui.strong("Expands to").on_hover_text(
+                                    "Distinct concrete sequences the degenerate variant \
+                                     represents",
+                                );
+                            }
+                            if any_indels {
+                                ui.strong("Indels").on_hover_text(
+                                    "Inserted plus deleted bases relative to the template oligo",
+                                );
+                            }
+                            if structures.is_some() {
+                                ui.strong("Structure");
+                            }
+                            ui.strong("References");
+                            ui.strong("");
+                            ui.end_row();
+
+                            let mut cumulative = 0.0;
+                            for (i, variant) in
+                                pos_result.analysis.variants.iter().enumerate()
+                            {
+                                cumulative += variant.percentage;
+
+                                let is_threshold = i + 1 == pos_result.variants_needed;
+
+                                if is_threshold {
+                                    ui.colored_label(
+                                        egui::Color32::GREEN,
+                                        format!("{}", i + 1),
+                                    );
+                                } else {
+                                    ui.label(format!("{}", i + 1));
+                                }
+
+                                let display_seq = format_sequence_for_display(
+                                    &variant.sequence,
+                                    show_reverse_complement,
+                                    show_codon_spacing,
+                                    show_rna,
+                                );
+
+                                ui.add(
+                                    egui::Label::new(
+                                        egui::RichText::new(&display_seq)
+                                            .monospace()
+                                            .size(11.0),
+                                    )
+                                    .wrap_mode(egui::TextWrapMode::Extend),
+                                );
+
+                                ui.label(format!("{}", variant.count));
+                                ui.label(format!("{:.1}%", variant.percentage));
+
+                                if is_threshold {
+                                    ui.colored_label(
+                                        egui::Color32::GREEN,
+                                        format!("{:.1}%", cumulative),
+                                    );
+                                } else {
+                                    ui.label(format!("{:.1}%", cumulative));
+                                }
+
+                                if any_degenerate {
+                                    ui.label(format!("{}", variant.expansion_count()));
+                                }
+                                if any_indels {
+                                    ui.label(format!("{}", variant.indel_count()));
+                                }
+                                if let Some(ref structures) = structures {
+                                    match structures.checks.get(i) {
+                                        Some(check) => structure_label(ui, check, &thresholds),
+                                        None => {
+                                            ui.label("");
+                                        }
+                                    }
+                                }
+
+                                match variant_names.as_ref().and_then(|n| n.get(i)) {
+                                    Some(names) if !names.is_empty() => {
+                                        egui::CollapsingHeader::new(format!(
+                                            "{} names",
+                                            names.len()
+                                        ))
+                                        .id_salt(("variant_members", i))
+                                        .show(ui, |ui| {
+                                            for name in names {
+                                                ui.label(name);
+                                            }
+                                        });
+                                    }
+                                    _ => {
+                                        ui.label("");
+                                    }
+                                }
+
+                                if ui
+                                    .small_button("+")
+                                    .on_hover_text(
+                                        "Add to the order selection, as displayed (orientation, \
+                                         RNA)",
+                                    )
+                                    .clicked()
+                                {
+                                    action = Some(DetailAction::AddToOrder(i));
+                                }
+
+                                ui.end_row();
+                            }
+
+                            // No match row
+                            if pos_result.analysis.no_match_count > 0 {
+                                ui.label("");
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 180, 100),
+                                    "No match",
+                                );
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 180, 100),
+                                    format!("{}", pos_result.analysis.no_match_count),
+                                );
+                                let no_match_pct = (pos_result.analysis.no_match_count
+                                    as f64
+                                    / pos_result.analysis.total_sequences as f64)
+                                    * 100.0;
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 180, 100),
+                                    format!("{:.1}%", no_match_pct),
+                                );
+                                ui.label("");
+                                if any_degenerate {
+                                    ui.label("");
+                                }
+                                if any_indels {
+                                    ui.label("");
+                                }
+                                if structures.is_some() {
+                                    ui.label("");
+                                }
+                                ui.label("");
+                                ui.end_row();
+                            }
+                        });
+
+                    if let Some(ref structures) = structures {
+                        if let Some((a, b, dg)) = structures.hetero_dimer {
+                            let text = format!(
+                                "Most stable hetero-dimer among the variants needed: {} × {}, \
+                                 ΔG {:.1} kcal/mol",
+                                a + 1,
+                                b + 1,
+                                dg
+                            );
+                            if dg <= thresholds.dimer_dg {
+                                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", text));
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            structure_threshold_controls(ui, &mut self.structure_thresholds);
+                        });
+                    }
+
+                    // === Exclusivity Analysis Section ===
+                    if let Some(ref excl) = pos_result.exclusivity {
+                        ui.add_space(10.0);
+                        ui.separator();
+                        ui.heading("Exclusivity Analysis");
+
+                        ui.label(format!(
+                            "Total exclusivity sequences: {}",
+                            excl.total_sequences
+                        ));
+                        if let Some(min_mm) = excl.min_mismatches {
+                            ui.label(format!("Minimum mismatches: {}", min_mm));
+                            let ignored = excl.ignored_matches();
+                            if ignored > 0 {
+                                ui.label(format!(
+                                    "Without the {} hits ignored by name: {}",
+                                    ignored,
+                                    effective_min_mismatches(excl, 0)
+                                        .map_or("no match".to_string(), |m| m.to_string())
+                                ));
+                            }
+                        } else {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(100, 200, 100),
+                                "All exclusivity sequences: no match (fully specific)",
+                            );
+                        }
+                        if let (Some(weighting), Some(three_prime_mm)) =
+                            (three_prime, excl.min_three_prime_mismatches)
+                        {
+                            ui.label(format!(
+                                "Fewest 3'-end mismatches (last {} bases): {}",
+                                weighting.bases, three_prime_mm
+                            ))
+                            .on_hover_text(format!(
+                                "Mismatch counts in this section weight 3'-end mismatches {}x",
+                                weighting.weight
+                            ));
+                        }
+                        let specificity =
+                            specificity_summary(&exclusivity_taxa, excl, near_mismatches);
+                        if !specificity.is_empty() {
+                            ui.strong(specificity).on_hover_text(format!(
+                                "Taxa parsed from the exclusivity headers; those aligning with \
+                                 at most {} mismatches (the exclusivity filter threshold) are \
+                                 near matches",
+                                near_mismatches
+                            ));
+                        }
+
+                        if !pos_result.group_exclusivity.is_empty() {
+                            let limiting = pos_result.limiting_exclusivity_group();
+                            ui.add_space(5.0);
+                            egui::Grid::new("exclusivity_groups_grid")
+                                .striped(true)
+                                .min_col_width(60.0)
+                                .show(ui, |ui| {
+                                    ui.strong("Group");
+                                    ui.strong("Sequences");
+                                    ui.strong("Min mismatches");
+                                    ui.strong("No match");
+                                    ui.end_row();
+
+                                    for (i, group) in
+                                        pos_result.group_exclusivity.iter().enumerate()
+                                    {
+                                        let name =
+                                            exclusivity_groups.get(i).map_or("?", |n| n.as_str());
+                                        if limiting == Some(i) {
+                                            ui.colored_label(
+                                                egui::Color32::from_rgb(255, 180, 100),
+                                                name,
+                                            )
+                                            .on_hover_text("Closest hit: limits specificity");
+                                        } else {
+                                            ui.label(name);
+                                        }
+                                        ui.label(format!("{}", group.total_sequences));
+                                        ui.label(match group.min_mismatches {
+                                            Some(mm) => format!("{}", mm),
+                                            None => "-".to_string(),
+                                        });
+                                        ui.label(format!("{}", group.no_match_count));
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+
+                        ui.add_space(5.0);
+
+                        egui::Grid::new("exclusivity_grid")
+                            .striped(true)
+                            .min_col_width(60.0)
+                            .show(ui, |ui| {
+                                ui.strong("Mismatches");
+                                ui.strong("Count");
+                                ui.strong("Example");
+                                if exclusivity_loaded {
+                                    ui.label("");
+                                }
+                                ui.end_row();
+
+                                for (i, bucket) in excl.mismatch_histogram.iter().enumerate() {
+                                    if bucket.mismatches == u32::MAX {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(100, 200, 100),
+                                            "No match",
+                                        );
+                                    } else {
+                                        let color = if bucket.mismatches == 0 {
+                                            egui::Color32::from_rgb(255, 80, 80)
+                                        } else if bucket.mismatches <= 2 {
+                                            egui::Color32::from_rgb(255, 180, 100)
+                                        } else {
+                                            egui::Color32::LIGHT_GRAY
+                                        };
+                                        ui.colored_label(
+                                            color,
+                                            format!("{}", bucket.mismatches),
+                                        );
+                                    }
+                                    ui.label(format!("{}", bucket.count));
+                                    ui.label(&bucket.example_name);
+                                    if exclusivity_loaded
+                                        && ui
+                                            .small_button("Align")
+                                            .on_hover_text("Show the alignment to the example")
+                                            .clicked()
+                                    {
+                                        action = Some(DetailAction::AlignExclusivity(i));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+
+                        if !excl.taxon_min_mismatches.is_empty() {
+                            let taxon_name = |taxon: usize| {
+                                exclusivity_taxa.get(taxon).map_or("?", String::as_str)
+                            };
+                            egui::CollapsingHeader::new("Minimum mismatches per taxon")
+                                .id_salt("taxon_min_mismatches")
+                                .show(ui, |ui| {
+                                    egui::Grid::new("taxon_grid").striped(true).show(ui, |ui| {
+                                        ui.strong("Taxon");
+                                        ui.strong("Min mismatches");
+                                        ui.end_row();
+                                        for (taxon, mm) in closest_taxa(excl) {
+                                            ui.label(taxon_name(taxon));
+                                            if mm <= near_mismatches {
+                                                ui.colored_label(
+                                                    egui::Color32::from_rgb(255, 180, 100),
+                                                    mm.to_string(),
+                                                );
+                                            } else {
+                                                ui.label(mm.to_string());
+                                            }
+                                            ui.end_row();
+                                        }
+                                        let unmatched = excl
+                                            .taxon_min_mismatches
+                                            .iter()
+                                            .enumerate()
+                                            .filter(|(_, mm)| mm.is_none());
+                                        for (taxon, _) in unmatched {
+                                            ui.label(taxon_name(taxon));
+                                            ui.colored_label(
+                                                egui::Color32::from_rgb(100, 200, 100),
+                                                "No match",
+                                            );
+                                            ui.end_row();
+                                        }
+                                    });
+                                });
+                        }
+                    }
+                });
+        });
+
+        action
+    }
+
+    /// Summary metrics of the selected heatmap cells, one column per cell.
+    fn show_cell_comparison_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_cell_comparison_window;
+        let template = self
+            .results
+            .as_ref()
+            .map(|r| r.template_sequence.as_str())
+            .unwrap_or("");
+        let ignore_count = self.diff_ignore_count;
+        let columns: Vec<((u32, usize), Option<SummaryRows>)> = self
+            .heatmap_selection
+            .iter()
+            .map(|&(length, position)| {
+                let rows = self
+                    .find_position_result(length, position)
+                    .map(|pr| window_summary_rows(pr, length, template, ignore_count));
+                ((length, position), rows)
+            })
+            .collect();
+
+        let mut open_cell: Option<(u32, usize)> = None;
+        let mut remove: Option<usize> = None;
+        let mut clear = false;
+        egui::Window::new("Compare Selected Cells")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                if columns.is_empty() {
+                    ui.label("Ctrl+click heatmap cells to select them for comparison.");
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} cells selected", columns.len()));
+                    if ui.button("Clear Selection").clicked() {
+                        clear = true;
+                    }
+                });
+                ui.add_space(5.0);
+
+                egui::ScrollArea::both()
+                    .id_salt("cell_comparison_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("cell_comparison_grid")
+                            .striped(true)
+                            .min_col_width(100.0)
+                            .show(ui, |ui| {
+                                ui.label("");
+                                for (i, ((length, position), _)) in columns.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .link(format!("Pos {} / {} bp", position + 1, length))
+                                            .on_hover_text("Open the detail window")
+                                            .clicked()
+                                        {
+                                            open_cell = Some((*length, *position));
+                                        }
+                                        if ui
+                                            .small_button("✖")
+                                            .on_hover_text("Remove from the selection")
+                                            .clicked()
+                                        {
+                                            remove = Some(i);
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                let names: Vec<&str> = columns
+                                    .iter()
+                                    .find_map(|(_, rows)| rows.as_ref())
+                                    .map(|rows| rows.iter().map(|(name, _)| *name).collect())
+                                    .unwrap_or_default();
+                                for (i, name) in names.iter().enumerate() {
+                                    ui.label(*name);
+                                    for (_, rows) in &columns {
+                                        let value = rows.as_ref().map_or("-", |r| r[i].1.as_str());
+                                        if i == 0 {
+                                            ui.monospace(value);
+                                        } else {
+                                            ui.label(value);
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if let Some((length, position)) = open_cell {
+            self.selected_position = Some(position);
+            self.selected_length_for_detail = Some(length);
+            self.show_detail_window = true;
+        }
+        if let Some(i) = remove {
+            self.heatmap_selection.remove(i);
+        }
+        if clear {
+            self.heatmap_selection.clear();
+        }
+        self.show_cell_comparison_window = open;
+    }
+
+    /// Two windows side by side: metrics, variant tables (aligned by rank), and
+    /// exclusivity histograms (aligned by mismatch count).
+    fn show_compare_window(&mut self, ctx: &egui::Context) {
+        for (length, position) in [self.compare_a, self.compare_b].into_iter().flatten() {
+            self.load_window_variants(length, position);
+        }
+        let mut open = self.show_compare_window;
+        let a = self.compare_a.and_then(|(l, p)| self.find_position_result(l, p));
+        let b = self.compare_b.and_then(|(l, p)| self.find_position_result(l, p));
+        let template = self
+            .results
+            .as_ref()
+            .map(|r| r.template_sequence.as_str())
+            .unwrap_or("");
+        let ignore_count = self.diff_ignore_count;
+
+        let title = |slot: Option<(u32, usize)>| match slot {
+            Some((length, position)) => format!("Pos {} / {} bp", position + 1, length),
+            None => "(not set)".to_string(),
+        };
+        let (title_a, title_b) = (title(self.compare_a), title(self.compare_b));
+
+        egui::Window::new("Compare Windows")
+            .open(&mut open)
+            .default_width(800.0)
+            .default_height(550.0)
+            .show(ctx, |ui| {
+                if a.is_none() || b.is_none() {
+                    ui.label(
+                        "Use \"Compare as A\" / \"Compare as B\" in a detail window to pick \
+                         the two windows to compare.",
+                    );
+                }
+
+                egui::ScrollArea::vertical().id_salt("compare_scroll").show(ui, |ui| {
+                    // === Metrics ===
+                    ui.heading("Metrics");
+                    egui::Grid::new("compare_metrics_grid")
+                        .striped(true)
+                        .min_col_width(120.0)
+                        .show(ui, |ui| {
+                            ui.strong("");
+                            ui.strong(format!("A: {}", title_a));
+                            ui.strong(format!("B: {}", title_b));
+                            ui.end_row();
+
+                            let rows = |slot: Option<(u32, usize)>, pr: Option<&PositionResult>| {
+                                let length = slot.map_or(0, |(l, _)| l);
+                                pr.map(|pr| window_summary_rows(pr, length, template, ignore_count))
+                            };
+                            let rows_a = rows(self.compare_a, a);
+                            let rows_b = rows(self.compare_b, b);
+                            let names =
+                                rows_a.as_ref().or(rows_b.as_ref()).cloned().unwrap_or_default();
+
+                            for (i, (name, _)) in names.iter().enumerate() {
+                                ui.label(*name);
+                                for rows in [&rows_a, &rows_b] {
+                                    let value = rows.as_ref().map_or("-", |r| r[i].1.as_str());
+                                    if i == 0 {
+                                        ui.monospace(value);
+                                    } else {
+                                        ui.label(value);
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+
+                    // === Variants, aligned by rank ===
+                    ui.add_space(10.0);
+                    ui.heading("Variants");
+                    let va = a.map_or(&[][..], |pr| pr.analysis.variants.as_slice());
+                    let vb = b.map_or(&[][..], |pr| pr.analysis.variants.as_slice());
+                    let needed_a = a.map_or(0, |pr| pr.variants_needed);
+                    let needed_b = b.map_or(0, |pr| pr.variants_needed);
+                    egui::Grid::new("compare_variants_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("#");
+                            ui.strong("A sequence");
+                            ui.strong("A %");
+                            ui.strong("B sequence");
+                            ui.strong("B %");
+                            ui.end_row();
+
+                            let mut cumulative = [0.0f64; 2];
+                            for rank in 0..va.len().max(vb.len()) {
+                                ui.label(format!("{}", rank + 1));
+                                for (side, (vs, needed)) in
+                                    [(va, needed_a), (vb, needed_b)].into_iter().enumerate()
+                                {
+                                    match vs.get(rank) {
+                                        Some(v) => {
+                                            cumulative[side] += v.percentage;
+                                            let mut seq = egui::RichText::new(&v.sequence)
+                                                .monospace()
+                                                .size(11.0);
+                                            if rank + 1 == needed {
+                                                seq = seq.color(egui::Color32::GREEN);
+                                            }
+                                            ui.label(seq);
+                                            ui.label(format!(
+                                                "{:.1}% (cum. {:.1}%)",
+                                                v.percentage, cumulative[side]
+                                            ));
+                                        }
+                                        None => {
+                                            ui.label("");
+                                            ui.label("");
+                                        }
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
 
-        // --- Reference Sequences ---
-        ui.group(|ui| {
-            ui.horizontal(|ui| {
-                ui.heading("Reference Sequences");
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.button("Clear").clicked() {
-                        self.reference_file_name = None;
-                        self.reference_data = None;
-                        self.reference_error = None;
-                    }
-                    if ui.button("Load File").clicked() {
-                        self.load_reference_file();
+                    // === Exclusivity histograms, aligned by mismatch count ===
+                    let excl_a = a.and_then(|pr| pr.exclusivity.as_ref());
+                    let excl_b = b.and_then(|pr| pr.exclusivity.as_ref());
+                    if excl_a.is_some() || excl_b.is_some() {
+                        ui.add_space(10.0);
+                        ui.heading("Exclusivity");
+                        let buckets: std::collections::BTreeSet<u32> = excl_a
+                            .into_iter()
+                            .chain(excl_b)
+                            .flat_map(|e| e.mismatch_histogram.iter().map(|b| b.mismatches))
+                            .collect();
+                        egui::Grid::new("compare_exclusivity_grid")
+                            .striped(true)
+                            .min_col_width(80.0)
+                            .show(ui, |ui| {
+                                ui.strong("Mismatches");
+                                ui.strong("A count");
+                                ui.strong("B count");
+                                ui.end_row();
+                                for mm in buckets {
+                                    if mm == u32::MAX {
+                                        ui.label("No match");
+                                    } else {
+                                        ui.label(mm.to_string());
+                                    }
+                                    for excl in [excl_a, excl_b] {
+                                        let count = excl
+                                            .and_then(|e| {
+                                                e.mismatch_histogram
+                                                    .iter()
+                                                    .find(|b| b.mismatches == mm)
+                                            })
+                                            .map_or(0, |b| b.count);
+                                        ui.label(count.to_string());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
                     }
                 });
             });
 
-            ui.label("Multiple sequences in FASTA format (unaligned)");
+        self.show_compare_window = open;
+    }
 
-            if let Some(ref error) = self.reference_error {
-                ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+    /// Nominated candidates with editable review status and notes.
+    fn show_nominations_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_nominations_window;
+        let mut jump_to: Option<(u32, usize)> = None;
+        let mut changed = false;
+        let mut export = false;
+
+        // Nominations of every completed job (plus results loaded from a file),
+        // checked together for duplicate or overlapping oligos
+        let mut job_labels: Vec<String> = self
+            .completed_jobs
+            .iter()
+            .map(|cj| format!("Job #{}", cj.job.id))
+            .collect();
+        let mut jobs: Vec<&ScreeningResults> =
+            self.completed_jobs.iter().map(|cj| &cj.results).collect();
+        let current_job = match (self.selected_completed_job_index, &self.results) {
+            (Some(idx), Some(results)) if idx < jobs.len() => {
+                jobs[idx] = results;
+                Some(idx)
             }
-            if let Some(ref data) = self.reference_data {
-                let min_len = data.sequences.iter().map(|s| s.len()).min().unwrap_or(0);
-                let max_len = data.sequences.iter().map(|s| s.len()).max().unwrap_or(0);
-                ui.horizontal(|ui| {
-                    ui.colored_label(
-                        egui::Color32::from_rgb(100, 200, 100),
-                        format!(
-                            "File: {}",
-                            self.reference_file_name.as_deref().unwrap_or("unknown")
-                        ),
-                    );
-                });
-                ui.colored_label(
-                    egui::Color32::from_rgb(100, 200, 100),
+            (_, Some(results)) => {
+                jobs.push(results);
+                job_labels.push("Loaded results".to_string());
+                Some(jobs.len() - 1)
+            }
+            _ => None,
+        };
+        let overlaps = find_nomination_overlaps(&jobs, self.nomination_min_overlap);
+        let describe = |(job, i): (usize, usize)| {
+            let n = &jobs[job].nominations[i];
+            format!(
+                "{} pos {} ({} bp)",
+                job_labels[job],
+                n.position + 1,
+                n.oligo_length
+            )
+        };
+        let overlap_lines: Vec<String> = overlaps
+            .iter()
+            .map(|o| format!("{} ~ {}: {}", describe(o.a), describe(o.b), o.kind.description()))
+            .collect();
+        let tm_lines: Vec<String> =
+            tm_matched_sets(&jobs, self.tm_match_spread, self.tm_match_min_size)
+                .iter()
+                .map(|set| {
+                    let members: Vec<String> = set
+                        .members
+                        .iter()
+                        .map(|&(n, tm)| format!("{} {:.1} °C", describe(n), tm))
+                        .collect();
                     format!(
-                        "{} sequences ({}-{} bp)",
-                        data.len(),
-                        min_len,
-                        max_len
-                    ),
-                );
-            } else {
-                ui.colored_label(egui::Color32::GRAY, "No references loaded");
+                        "{} oligos, spread {:.1} °C: {}",
+                        set.members.len(),
+                        set.spread(),
+                        members.join(", ")
+                    )
+                })
+                .collect();
+        // Per nomination of the current job: descriptions of the nominations it overlaps
+        let mut row_flags: Vec<Vec<String>> = current_job
+            .map(|job| vec![Vec::new(); jobs[job].nominations.len()])
+            .unwrap_or_default();
+        for o in &overlaps {
+            for (this, other) in [(o.a, o.b), (o.b, o.a)] {
+                if Some(this.0) == current_job {
+                    row_flags[this.1]
+                        .push(format!("{}: {}", describe(other), o.kind.description()));
+                }
             }
-        });
-
-        ui.add_space(10.0);
-
-        // --- Differential Analysis / Exclusivity Sequences ---
-        ui.checkbox(&mut self.use_differential, "Use differential analysis");
+        }
 
-        if self.use_differential {
-            ui.group(|ui| {
+        egui::Window::new("Nominated Candidates")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                let Some(results) = &mut self.results else {
+                    return;
+                };
                 ui.horizontal(|ui| {
-                    ui.heading("Exclusivity Sequences");
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("Add File").clicked() {
-                            self.add_exclusivity_file();
+                    ui.label(format!("{} nominated", results.nominations.len()));
+                    ui.separator();
+                    ui.checkbox(&mut self.nominations_approved_only, "Approved only");
+                    if ui
+                        .add_enabled(
+                            !results.nominations.is_empty(),
+                            egui::Button::new("Export Design List..."),
+                        )
+                        .clicked()
+                    {
+                        export = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Flag end overlaps ≥");
+                    ui.add(
+                        egui::DragValue::new(&mut self.nomination_min_overlap)
+                            .range(1..=100)
+                            .suffix(" bp"),
+                    );
+                    ui.separator();
+                    if overlap_lines.is_empty() {
+                        ui.label("No duplicate or overlapping nominations across jobs");
+                    } else {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(220, 140, 0),
+                            format!("⚠ {} duplicate/overlapping pairs", overlap_lines.len()),
+                        );
+                    }
+                });
+                if !overlap_lines.is_empty() {
+                    egui::CollapsingHeader::new("Duplicates and overlaps across jobs")
+                        .id_salt("nomination_overlaps")
+                        .show(ui, |ui| {
+                            for line in &overlap_lines {
+                                ui.label(line);
+                            }
+                        });
+                }
+                egui::CollapsingHeader::new(format!("Tm-matched sets ({})", tm_lines.len()))
+                    .id_salt("nomination_tm_sets")
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Max Tm spread:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.tm_match_spread)
+                                    .range(0.0..=20.0)
+                                    .speed(0.1)
+                                    .suffix(" °C"),
+                            )
+                            .on_hover_text(
+                                "Nearest-neighbor Tm of each nominated oligo under its job's Tm \
+                                 conditions, as on the heatmap (basic Wallace / GC estimate \
+                                 for degenerate nominations); rejected nominations are left \
+                                 out",
+                            );
+                            ui.label("Min set size:");
+                            ui.add(egui::DragValue::new(&mut self.tm_match_min_size).range(2..=50));
+                        });
+                        if tm_lines.is_empty() {
+                            ui.colored_label(
+                                egui::Color32::GRAY,
+                                "No nominations within this spread",
+                            );
+                        }
+                        for line in &tm_lines {
+                            ui.label(line);
                         }
                     });
-                });
-
-                ui.label("Sequences that oligos must be distinct from (off-targets)");
+                ui.separator();
 
-                if let Some(ref error) = self.exclusivity_error {
-                    ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
+                if results.nominations.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::GRAY,
+                        "Use Nominate in a detail window to add candidates.",
+                    );
+                    return;
                 }
 
-                if self.exclusivity_files.is_empty() {
-                    ui.colored_label(egui::Color32::GRAY, "No exclusivity files loaded");
-                } else {
-                    let mut remove_idx = None;
-                    for (i, entry) in self.exclusivity_files.iter().enumerate() {
-                        ui.horizontal(|ui| {
-                            if ui.small_button("X").clicked() {
-                                remove_idx = Some(i);
-                            }
-                            ui.label(format!(
-                                "{} - {} sequences ({}-{} bp)",
-                                entry.file_name,
-                                entry.sequence_count,
-                                entry.min_length,
-                                entry.max_length
-                            ));
-                        });
-                    }
-                    if let Some(idx) = remove_idx {
-                        self.pending_remove_excl = Some(idx);
-                    }
+                let mut remove = None;
+                egui::ScrollArea::both()
+                    .id_salt("nominations_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("nominations_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("Length");
+                                ui.strong("Pos");
+                                ui.strong("Sequence (5'-3')");
+                                ui.strong("Status");
+                                ui.strong("Note");
+                                ui.strong("Overlaps");
+                                ui.strong("");
+                                ui.end_row();
 
-                    // Summary
-                    if let Some(ref data) = self.exclusivity_data {
-                        ui.separator();
-                        ui.colored_label(
-                            egui::Color32::from_rgb(100, 200, 100),
-                            format!(
-                                "Total: {} exclusivity sequences from {} file(s)",
-                                data.len(),
-                                self.exclusivity_files.len()
-                            ),
-                        );
-                    }
+                                for (i, n) in results.nominations.iter_mut().enumerate() {
+                                    ui.label(format!("{} bp", n.oligo_length));
+                                    if ui
+                                        .small_button(format!("{}", n.position + 1))
+                                        .on_hover_text("Open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((n.oligo_length, n.position));
+                                    }
+                                    ui.monospace(&n.sequence);
+                                    egui::ComboBox::from_id_salt(("nomination_status", i))
+                                        .selected_text(n.status.label())
+                                        .show_ui(ui, |ui| {
+                                            for status in NominationStatus::ALL {
+                                                changed |= ui
+                                                    .selectable_value(
+                                                        &mut n.status,
+                                                        status,
+                                                        status.label(),
+                                                    )
+                                                    .changed();
+                                            }
+                                        });
+                                    changed |= ui
+                                        .add(
+                                            egui::TextEdit::singleline(&mut n.note)
+                                                .desired_width(200.0),
+                                        )
+                                        .changed();
+                                    match row_flags.get(i).filter(|f| !f.is_empty()) {
+                                        Some(flags) => {
+                                            ui.colored_label(
+                                                egui::Color32::from_rgb(220, 140, 0),
+                                                format!("⚠ {}", flags.len()),
+                                            )
+                                            .on_hover_text(flags.join("\n"));
+                                        }
+                                        None => {
+                                            ui.label("");
+                                        }
+                                    }
+                                    if ui.small_button("Remove").clicked() {
+                                        remove = Some(i);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(i) = remove {
+                    results.nominations.remove(i);
+                    changed = true;
                 }
             });
-        }
 
-        ui.add_space(10.0);
+        self.show_nominations_window = open;
+        if changed {
+            self.store_nominations();
+        }
+        if export {
+            self.export_nominations();
+        }
+        if let Some((length, pos)) = jump_to {
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
+        }
+    }
 
-        // --- Output Folder ---
-        ui.group(|ui| {
-            ui.horizontal(|ui| {
-                ui.heading("Output Folder (Optional)");
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if self.output_folder.is_some() {
-                        if ui.button("Clear").clicked() {
-                            self.output_folder = None;
+    /// Every window of the shown results ranked by a weighted score of
+    /// variants needed, no-match %, exclusivity, GC, and Tm; the top N can be
+    /// sorted by any column and exported as FASTA.
+    fn show_top_candidates_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_top_candidates_window;
+        let mut jump_to: Option<(u32, usize)> = None;
+        let mut export = false;
+        let mut add_to_order: Option<usize> = None;
+        self.update_candidate_table();
+        let filter_active = self.active_exclusivity_filter().is_some();
+
+        egui::Window::new("Top Candidates")
+            .open(&mut open)
+            .default_width(850.0)
+            .default_height(450.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Score 0-100: weighted mean over the criteria, each scaled like its \
+                     heatmap coloring (variant and no-match color ranges, exclusivity \
+                     green/red, GC and Tm target bands). Exclusivity counts only for \
+                     differential results.",
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Weights - Variants:");
+                    let weights = &mut self.ranking_weights;
+                    for (value, label) in [
+                        (&mut weights.variants_needed, None),
+                        (&mut weights.no_match, Some("No-match:")),
+                        (&mut weights.exclusivity, Some("Exclusivity:")),
+                        (&mut weights.gc, Some("GC:")),
+                        (&mut weights.tm, Some("Tm:")),
+                    ] {
+                        if let Some(label) = label {
+                            ui.label(label);
                         }
-                    }
-                    if ui.button("Select Folder").clicked() {
-                        self.select_output_folder();
+                        ui.add(egui::DragValue::new(value).range(0.0..=10.0).speed(0.1));
                     }
                 });
-            });
-            ui.label(
-                "If set, results will be auto-saved as JSON to this folder after analysis.",
-            );
-            if let Some(ref folder) = self.output_folder {
-                ui.colored_label(egui::Color32::from_rgb(100, 200, 100), format!("Folder: {}", folder));
-            } else {
-                ui.colored_label(egui::Color32::GRAY, "No output folder selected (manual save only)");
-            }
-        });
-
-        ui.add_space(10.0);
-
-        // --- Add to Worklist ---
-        let can_add = self.template_data.is_some() && self.reference_data.is_some();
-        let warn_excl =
-            self.use_differential && self.exclusivity_data.is_none();
-        ui.horizontal(|ui| {
-            if ui
-                .add_enabled(can_add, egui::Button::new("Add to Worklist"))
-                .clicked()
-            {
-                self.add_to_worklist();
-            }
-            if !can_add {
-                ui.colored_label(
-                    egui::Color32::GRAY,
-                    "Load template and references first",
-                );
-            }
-            if warn_excl {
-                ui.colored_label(
-                    egui::Color32::YELLOW,
-                    "Differential enabled but no exclusivity files loaded",
-                );
-            }
-        });
-    }
-
-    fn show_analysis_tab(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Analysis Setup");
-        ui.separator();
-        ui.label("These settings apply to all jobs added to the worklist.");
-        ui.add_space(5.0);
-
-        egui::ScrollArea::vertical().show(ui, |ui| {
-            // Pairwise Aligner Settings
-            ui.group(|ui| {
-                ui.heading("Pairwise Aligner Settings");
-
                 ui.horizontal(|ui| {
-                    ui.label("Match score:");
+                    ui.label("Min score:");
                     ui.add(
-                        egui::DragValue::new(&mut self.params.pairwise.match_score).range(0..=10),
+                        egui::DragValue::new(&mut self.ranking_min_score)
+                            .range(0.0..=100.0)
+                            .speed(0.5),
                     );
-                    ui.add_space(20.0);
-                    ui.label("Mismatch score:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.pairwise.mismatch_score)
-                            .range(-10..=0),
+                    ui.separator();
+                    ui.checkbox(&mut self.ranking_limit_variants, "Max variants needed:");
+                    ui.add_enabled(
+                        self.ranking_limit_variants,
+                        egui::DragValue::new(&mut self.ranking_max_variants).range(1..=1000),
+                    );
+                    ui.separator();
+                    ui.checkbox(&mut self.ranking_limit_no_match, "Max no-match:");
+                    ui.add_enabled(
+                        self.ranking_limit_no_match,
+                        egui::DragValue::new(&mut self.ranking_max_no_match)
+                            .range(0.0..=100.0)
+                            .speed(0.5)
+                            .suffix("%"),
                     );
                 });
-
                 ui.horizontal(|ui| {
-                    ui.label("Gap open penalty:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.pairwise.gap_open_penalty)
-                            .range(-20..=0),
-                    );
-                    ui.add_space(20.0);
-                    ui.label("Gap extend penalty:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.pairwise.gap_extend_penalty)
-                            .range(-20..=0),
-                    );
+                    structure_threshold_controls(ui, &mut self.structure_thresholds);
+                });
+                let Some(table) = &self.candidate_table else {
+                    return;
+                };
+                let thresholds = self.structure_thresholds;
+                ui.horizontal(|ui| {
+                    ui.label("Show top");
+                    ui.add(egui::DragValue::new(&mut self.ranking_top_n).range(1..=10_000));
+                    ui.label(format!("of {} ranked windows", table.ranked));
+                    if filter_active {
+                        ui.label("(exclusivity filter applied)");
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(!table.rows.is_empty(), egui::Button::new("Export FASTA..."))
+                        .on_hover_text("The template oligos of the rows shown, in table order")
+                        .clicked()
+                    {
+                        export = true;
+                    }
                 });
+                ui.separator();
 
-                ui.horizontal(|ui| {
-                    ui.label("Maximum allowed mismatches:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.pairwise.max_mismatches)
-                            .range(0..=50),
-                    );
-                });
-                ui.label("Matches exceeding this mismatch count are recorded as 'no match'.");
+                let mut sort = self.ranking_sort;
+                egui::ScrollArea::both()
+                    .id_salt("top_candidates_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("top_candidates_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                let headers = [
+                                    (Some(RankingColumn::Score), "Rank"),
+                                    (Some(RankingColumn::Score), "Score"),
+                                    (Some(RankingColumn::Length), "Length"),
+                                    (Some(RankingColumn::Position), "Pos"),
+                                    (None, "Sequence (5'-3')"),
+                                    (Some(RankingColumn::VariantsNeeded), "Variants"),
+                                    (Some(RankingColumn::Coverage), "Coverage"),
+                                    (Some(RankingColumn::NoMatch), "No-match"),
+                                    (Some(RankingColumn::Exclusivity), "Excl. min MM"),
+                                    (Some(RankingColumn::GcPercent), "GC"),
+                                    (Some(RankingColumn::Tm), "Tm"),
+                                    (None, "Structure"),
+                                    (None, ""),
+                                ];
+                                for (column, header) in headers {
+                                    let Some(column) = column else {
+                                        ui.strong(header);
+                                        continue;
+                                    };
+                                    let current = sort.0 == column;
+                                    let text = match (current, sort.1) {
+                                        (true, true) => format!("{} ⏷", header),
+                                        (true, false) => format!("{} ⏶", header),
+                                        (false, _) => header.to_string(),
+                                    };
+                                    if ui
+                                        .selectable_label(
+                                            current,
+                                            egui::RichText::new(text).strong(),
+                                        )
+                                        .on_hover_text(
+                                            "Sort by this column (click again to reverse)",
+                                        )
+                                        .clicked()
+                                    {
+                                        sort = (column, !current || !sort.1);
+                                    }
+                                }
+                                ui.end_row();
+
+                                for (row, c) in table.rows.iter().enumerate() {
+                                    ui.label(c.rank.to_string());
+                                    ui.label(format!("{:.1}", c.score));
+                                    ui.label(format!("{} bp", c.length));
+                                    if ui
+                                        .small_button(format!("{}", c.position + 1))
+                                        .on_hover_text("Open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((c.length, c.position));
+                                    }
+                                    ui.monospace(&c.oligo);
+                                    ui.label(c.variants_needed.to_string());
+                                    ui.label(format!("{:.1}%", c.coverage_at_threshold));
+                                    ui.label(format!("{:.1}%", c.no_match_percent));
+                                    ui.label(match c.exclusivity_min_mismatches {
+                                        None => "-".to_string(),
+                                        Some(None) => "all no-match".to_string(),
+                                        Some(Some(mm)) => mm.to_string(),
+                                    });
+                                    ui.label(format!("{:.1}%", c.gc_percent));
+                                    ui.label(format!("{:.1} °C", c.tm));
+                                    match table.structures.get(row) {
+                                        Some(check) => structure_label(ui, check, &thresholds),
+                                        None => {
+                                            ui.label("");
+                                        }
+                                    }
+                                    if ui
+                                        .small_button("+")
+                                        .on_hover_text("Add to the order selection")
+                                        .clicked()
+                                    {
+                                        add_to_order = Some(row);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                self.ranking_sort = sort;
             });
 
-            ui.add_space(10.0);
+        self.show_top_candidates_window = open;
+        if export {
+            self.export_top_candidates();
+        }
+        let candidate =
+            add_to_order.and_then(|row| self.candidate_table.as_ref()?.rows.get(row).cloned());
+        if let Some(c) = candidate {
+            let rna = self.results.as_ref().is_some_and(|r| r.rna);
+            let sequence = if rna {
+                c.oligo.replace('T', "U")
+            } else {
+                c.oligo
+            };
+            let source = format!("Candidate {}", c.rank);
+            self.add_to_order(sequence, c.length, c.position, "", source);
+            self.show_order_window = true;
+        }
+        if let Some((length, pos)) = jump_to {
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
+        }
+    }
 
-            // Analysis method selection
-            ui.group(|ui| {
-                ui.heading("Analysis Method");
+    /// Oligos picked for ordering, with editable names and FASTA and vendor
+    /// order sheet export.
+    fn show_order_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_order_window;
+        let mut export_fasta = false;
+        let mut export_sheet = false;
 
-                ui.radio_value(
-                    &mut self.method_selection,
-                    MethodSelection::NoAmbiguities,
-                    "No Ambiguities - Find all unique exact variants",
+        egui::Window::new("Order Selection")
+            .open(&mut open)
+            .default_width(650.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Add oligos with the + buttons of the detail window variants and the \
+                     Top Candidates table. Sequences are listed 5'-3' as they will be ordered.",
                 );
-
                 ui.horizontal(|ui| {
-                    ui.radio_value(
-                        &mut self.method_selection,
-                        MethodSelection::FixedAmbiguities,
-                        "Fixed Ambiguities - Use up to N ambiguity codes per variant",
+                    ui.label("Order sheet:");
+                    let before = self.order_format;
+                    egui::ComboBox::from_id_salt("order_format")
+                        .selected_text(self.order_format.label())
+                        .show_ui(ui, |ui| {
+                            for format in OrderSheetFormat::ALL {
+                                ui.selectable_value(&mut self.order_format, format, format.label());
+                            }
+                        });
+                    if self.order_format != before {
+                        self.order_scale = 0;
+                        self.order_purification = 0;
+                    }
+                    let format = self.order_format;
+                    ui.label("Scale:");
+                    egui::ComboBox::from_id_salt("order_scale").show_index(
+                        ui,
+                        &mut self.order_scale,
+                        format.scales().len(),
+                        |i| format.scales()[i],
+                    );
+                    ui.label("Purification:");
+                    egui::ComboBox::from_id_salt("order_purification").show_index(
+                        ui,
+                        &mut self.order_purification,
+                        format.purifications().len(),
+                        |i| format.purifications()[i],
                     );
                 });
-
-                if self.method_selection == MethodSelection::FixedAmbiguities {
-                    ui.horizontal(|ui| {
-                        ui.add_space(20.0);
-                        ui.label("Max ambiguities:");
-                        let mut n = self.params.method.get_fixed_ambiguities();
-                        if ui.add(egui::DragValue::new(&mut n).range(0..=20)).changed() {
-                            self.params.method = AnalysisMethod::FixedAmbiguities(n);
-                        }
-                    });
-                }
-
                 ui.horizontal(|ui| {
-                    ui.radio_value(
-                        &mut self.method_selection,
-                        MethodSelection::Incremental,
-                        "Incremental - Find variants covering X% of remaining sequences",
-                    );
+                    let any = !self.order_selection.is_empty();
+                    ui.label(format!("{} oligos", self.order_selection.len()));
+                    ui.separator();
+                    if ui
+                        .add_enabled(any, egui::Button::new("Export FASTA..."))
+                        .clicked()
+                    {
+                        export_fasta = true;
+                    }
+                    if ui
+                        .add_enabled(any, egui::Button::new("Export Order Sheet..."))
+                        .on_hover_text("CSV with name, sequence, scale, and purification columns")
+                        .clicked()
+                    {
+                        export_sheet = true;
+                    }
+                    if ui.add_enabled(any, egui::Button::new("Clear")).clicked() {
+                        self.order_selection.clear();
+                    }
                 });
+                egui::CollapsingHeader::new("Multiplex compatibility")
+                    .id_salt("multiplex_check")
+                    .show(ui, |ui| self.multiplex_ui(ui));
+                ui.separator();
 
-                if self.method_selection == MethodSelection::Incremental {
-                    ui.horizontal(|ui| {
-                        ui.add_space(20.0);
-                        ui.label("Target coverage per step (%):");
-                        let mut pct = self.params.method.get_incremental_pct();
-                        let max_amb = self.params.method.get_incremental_max_amb();
-                        if ui
-                            .add(egui::DragValue::new(&mut pct).range(1..=100))
-                            .changed()
-                        {
-                            self.params.method = AnalysisMethod::Incremental(pct, max_amb);
-                        }
-                    });
-                    ui.horizontal(|ui| {
-                        ui.add_space(20.0);
-                        ui.checkbox(
-                            &mut self.incremental_limit_ambiguities,
-                            "Limit ambiguities:",
-                        );
-                        ui.add_enabled(
-                            self.incremental_limit_ambiguities,
-                            egui::DragValue::new(&mut self.incremental_max_ambiguities)
-                                .range(0..=20),
-                        );
-                        ui.label("max");
+                let mut remove = None;
+                egui::ScrollArea::both()
+                    .id_salt("order_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("order_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("Name");
+                                ui.strong("Sequence (5'-3')");
+                                ui.strong("Length");
+                                ui.strong("Pos");
+                                ui.strong("From");
+                                ui.strong("");
+                                ui.end_row();
+
+                                for (i, oligo) in self.order_selection.iter_mut().enumerate() {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut oligo.name)
+                                            .desired_width(180.0),
+                                    );
+                                    ui.monospace(&oligo.sequence);
+                                    ui.label(format!("{} bp", oligo.length));
+                                    ui.label(format!("{}", oligo.position + 1));
+                                    ui.label(&oligo.source);
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .small_button("RC")
+                                            .on_hover_text("Replace with the reverse complement")
+                                            .clicked()
+                                        {
+                                            oligo.reverse_complement();
+                                        }
+                                        if ui.small_button("Remove").clicked() {
+                                            remove = Some(i);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
                     });
-                    if self.incremental_limit_ambiguities {
-                        ui.horizontal(|ui| {
-                            ui.add_space(20.0);
-                            ui.label(
-                                "If target % cannot be reached, accepts best variant within limit.",
-                            );
-                        });
-                    }
+                if let Some(i) = remove {
+                    self.order_selection.remove(i);
                 }
             });
 
-            ui.add_space(10.0);
-
-            // Global options
-            ui.group(|ui| {
-                ui.heading("Global Options");
-                ui.checkbox(
-                    &mut self.params.exclude_n,
-                    "Exclude N (any base) as ambiguity code",
-                );
-            });
+        self.show_order_window = open;
+        if export_fasta {
+            self.export_order_fasta();
+        }
+        if export_sheet {
+            self.export_order_sheet();
+        }
+    }
 
-            ui.add_space(10.0);
+    /// Cross-dimers and off-target sites between the oligos of the order
+    /// selection, as a multiplex; recomputed when the selection changes.
+    fn multiplex_ui(&mut self, ui: &mut egui::Ui) {
+        if self.order_selection.len() < 2 {
+            ui.label("Add at least two oligos to check them as a multiplex.");
+            return;
+        }
+        if self
+            .multiplex_check
+            .as_ref()
+            .is_none_or(|(oligos, _)| *oligos != self.order_selection)
+        {
+            let check = multiplex_check(&self.order_selection);
+            self.multiplex_check = Some((self.order_selection.clone(), check));
+        }
+        ui.horizontal(|ui| {
+            structure_threshold_controls(ui, &mut self.structure_thresholds);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Flag off-target sites with ≤");
+            ui.add(egui::DragValue::new(&mut self.multiplex_max_mismatches).range(0..=20));
+            ui.label("mismatches");
+        });
+        let Some((oligos, check)) = &self.multiplex_check else {
+            return;
+        };
+        let dimer_limit = self.structure_thresholds.dimer_dg;
+        let mismatch_limit = self.multiplex_max_mismatches;
+
+        let mut flags = Vec::new();
+        for (i, row) in check.dimer_dg.iter().enumerate() {
+            for (j, &dg) in row.iter().enumerate().skip(i) {
+                if dg <= dimer_limit {
+                    let pair = if i == j {
+                        format!("{} self-dimer", oligos[i].name)
+                    } else {
+                        format!("{} × {} dimer", oligos[i].name, oligos[j].name)
+                    };
+                    flags.push(format!("{}: ΔG {:.1} kcal/mol", pair, dg));
+                }
+            }
+        }
+        for (i, row) in check.cross_sites.iter().enumerate() {
+            for (j, site) in row.iter().enumerate() {
+                if let Some(site) = site.filter(|s| s.mismatches <= mismatch_limit) {
+                    flags.push(format!(
+                        "{} binds the target of {} at {} ({}) with {} mismatches",
+                        oligos[i].name,
+                        oligos[j].name,
+                        site.position + 1,
+                        if site.antisense { "antisense" } else { "sense" },
+                        site.mismatches
+                    ));
+                }
+            }
+        }
+        if flags.is_empty() {
+            ui.colored_label(
+                egui::Color32::from_rgb(100, 200, 100),
+                "No cross-dimers or off-target sites at these thresholds.",
+            );
+        } else {
+            for flag in &flags {
+                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", flag));
+            }
+        }
+        if oligos.iter().any(|o| o.target.is_empty()) {
+            ui.colored_label(
+                egui::Color32::GRAY,
+                "Oligos added before target regions were recorded are not checked as targets.",
+            );
+        }
 
-            // Oligo length range
-            ui.group(|ui| {
-                ui.heading("Oligo Length Range");
-                ui.horizontal(|ui| {
-                    ui.label("Minimum length:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.min_oligo_length).range(3..=100),
-                    );
-                    ui.add_space(20.0);
-                    ui.label("Maximum length:");
-                    ui.add(
-                        egui::DragValue::new(&mut self.params.max_oligo_length).range(3..=100),
-                    );
-                });
+        let cell = |ui: &mut egui::Ui, text: String, flagged: bool| {
+            if flagged {
+                ui.colored_label(egui::Color32::YELLOW, text);
+            } else {
+                ui.label(text);
+            }
+        };
+        let header = |ui: &mut egui::Ui| {
+            ui.label("");
+            for i in 0..oligos.len() {
+                ui.strong(format!("#{}", i + 1));
+            }
+            ui.end_row();
+        };
+        let row_label = |ui: &mut egui::Ui, i: usize| {
+            ui.strong(format!("#{}", i + 1))
+                .on_hover_text(&oligos[i].name);
+        };
 
-                if self.params.min_oligo_length > self.params.max_oligo_length {
-                    self.params.max_oligo_length = self.params.min_oligo_length;
+        ui.label("Dimer ΔG (kcal/mol, 37 °C):");
+        egui::Grid::new("multiplex_dimer_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                header(ui);
+                for (i, row) in check.dimer_dg.iter().enumerate() {
+                    row_label(ui, i);
+                    for &dg in row {
+                        cell(ui, format!("{:.1}", dg), dg <= dimer_limit);
+                    }
+                    ui.end_row();
                 }
-
-                let range = self.params.max_oligo_length - self.params.min_oligo_length + 1;
-                if range > 20 {
-                    ui.colored_label(
-                        egui::Color32::YELLOW,
-                        format!(
-                            "Warning: Large length range ({}) may take significant time",
-                            range
-                        ),
-                    );
+            });
+        ui.label("Fewest mismatches of the row oligo in the column oligo's target region:")
+            .on_hover_text(format!(
+                "Target regions span the window and {} template bases either side. Both \
+                 strands are searched without gaps; an oligo's own site is skipped.",
+                TARGET_FLANK
+            ));
+        egui::Grid::new("multiplex_site_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                header(ui);
+                for (i, row) in check.cross_sites.iter().enumerate() {
+                    row_label(ui, i);
+                    for site in row {
+                        match site {
+                            Some(site) => cell(
+                                ui,
+                                site.mismatches.to_string(),
+                                site.mismatches <= mismatch_limit,
+                            ),
+                            None => {
+                                ui.label("-");
+                            }
+                        }
+                    }
+                    ui.end_row();
                 }
             });
+    }
 
-            ui.add_space(10.0);
-
-            // Resolution
-            ui.group(|ui| {
-                ui.heading("Analysis Resolution");
-                ui.horizontal(|ui| {
-                    ui.label("Step size (bases):");
-                    ui.add(egui::DragValue::new(&mut self.params.resolution).range(1..=100));
-                });
-                ui.label("Lower values = more positions analyzed, higher resolution");
-            });
+    /// Per-reference assignment of one window: a searchable, sortable table
+    /// of each reference's variant and mismatch count.
+    fn show_assignment_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_assignment_window;
+        let mut sort = self.assignment_sort;
+        let mut export = false;
+        let mut align: Option<usize> = None;
 
-            ui.add_space(10.0);
+        egui::Window::new("Reference Assignment")
+            .open(&mut open)
+            .default_width(650.0)
+            .default_height(450.0)
+            .show(ctx, |ui| {
+                if self.assignment_rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Realigning references...");
+                    });
+                    return;
+                }
+                let Some(table) = &self.assignment_table else {
+                    ui.label("Click References in a window's details to list its references.");
+                    return;
+                };
 
-            // Coverage threshold
-            ui.group(|ui| {
-                ui.heading("Coverage Threshold");
+                ui.label(format!(
+                    "Position {} / {} bp: {} references",
+                    table.position + 1,
+                    table.length,
+                    table.rows.len()
+                ));
                 ui.horizontal(|ui| {
-                    ui.label("Target coverage (%):");
+                    ui.label("Search:");
                     ui.add(
-                        egui::DragValue::new(&mut self.params.coverage_threshold)
-                            .range(1.0..=100.0),
+                        egui::TextEdit::singleline(&mut self.assignment_search)
+                            .hint_text("reference name"),
                     );
+                    ui.separator();
+                    if ui
+                        .button("Export CSV...")
+                        .on_hover_text("All references, in table order")
+                        .clicked()
+                    {
+                        export = true;
+                    }
                 });
-                ui.label("Number of variants needed to reach this coverage will be reported");
+                ui.separator();
+
+                let search = self.assignment_search.trim().to_lowercase();
+                egui::ScrollArea::both()
+                    .id_salt("assignment_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("assignment_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                let headers = [
+                                    (Some(AssignmentColumn::Reference), "Reference"),
+                                    (Some(AssignmentColumn::Variant), "Variant"),
+                                    (Some(AssignmentColumn::Mismatches), "Mismatches"),
+                                    (None, "Matched window"),
+                                    (None, ""),
+                                ];
+                                for (column, header) in headers {
+                                    let Some(column) = column else {
+                                        ui.strong(header);
+                                        continue;
+                                    };
+                                    let current = sort.0 == column;
+                                    let text = match (current, sort.1) {
+                                        (true, true) => format!("{} ⏷", header),
+                                        (true, false) => format!("{} ⏶", header),
+                                        (false, _) => header.to_string(),
+                                    };
+                                    if ui
+                                        .selectable_label(
+                                            current,
+                                            egui::RichText::new(text).strong(),
+                                        )
+                                        .on_hover_text(
+                                            "Sort by this column (click again to reverse)",
+                                        )
+                                        .clicked()
+                                    {
+                                        sort = (column, current && !sort.1);
+                                    }
+                                }
+                                ui.end_row();
+
+                                for row in table
+                                    .rows
+                                    .iter()
+                                    .filter(|r| r.name.to_lowercase().contains(&search))
+                                {
+                                    ui.label(&row.name);
+                                    match row.variant {
+                                        Some(i) => ui.label(format!("{}", i + 1)),
+                                        None => ui.colored_label(
+                                            egui::Color32::from_rgb(255, 120, 120),
+                                            "No match",
+                                        ),
+                                    };
+                                    ui.label(match row.mismatches {
+                                        Some(mm) => format!("{}", mm),
+                                        None => "-".to_string(),
+                                    });
+                                    ui.label(
+                                        egui::RichText::new(
+                                            row.matched_sequence.as_deref().unwrap_or(""),
+                                        )
+                                        .monospace(),
+                                    );
+                                    if ui
+                                        .small_button("Align")
+                                        .on_hover_text("Show the alignment to this reference")
+                                        .clicked()
+                                    {
+                                        align = Some(row.reference);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
             });
 
-        });
+        self.show_assignment_window = open;
+        if sort != self.assignment_sort {
+            self.assignment_sort = sort;
+            if let Some(table) = &mut self.assignment_table {
+                sort_assignments(&mut table.rows, sort.0, sort.1);
+            }
+        }
+        if export {
+            self.export_assignments();
+        }
+        if let (Some(reference), Some(table)) = (align, &self.assignment_table) {
+            let (length, position) = (table.length, table.position);
+            let target = self.selected_job().and_then(|job| {
+                let data = &job.reference_data;
+                let name = data.names.get(reference)?.clone();
+                Some((name, data.sequences.get(reference)?.clone()))
+            });
+            if let Some((name, sequence)) = target {
+                self.open_alignment(length, position, name, &sequence);
+            }
+        }
     }
 
-    fn show_worklist_tab(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Worklist");
-        ui.separator();
-
-        // === Parallelization (moved from Analysis Setup) ===
-        ui.group(|ui| {
-            ui.heading("Parallelization");
-
-            let available_threads = std::thread::available_parallelism()
-                .map(|n| n.get())
-                .unwrap_or(1);
+    /// The alignment opened from the reference table or an exclusivity hit,
+    /// colored by match, mismatch, and gap.
+    fn show_alignment_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_alignment_window;
 
-            ui.label(format!("Available parallelism: {} threads", available_threads));
+        egui::Window::new("Alignment")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                let Some((title, view)) = &self.alignment_view else {
+                    ui.label("Nothing aligned yet.");
+                    return;
+                };
+                ui.strong(title);
+                let r = &view.result;
+                ui.label(format!(
+                    "Score {}, {} mismatches, {} indel bases{}",
+                    r.score,
+                    r.mismatches,
+                    r.indels,
+                    if view.reverse_strand {
+                        " (reverse strand: oligo shown reverse-complemented)"
+                    } else {
+                        ""
+                    }
+                ));
+                if view.accepted {
+                    ui.colored_label(egui::Color32::from_rgb(100, 200, 100), "Counted as a match");
+                } else {
+                    ui.colored_label(egui::Color32::from_rgb(255, 80, 80), "Counted as no match");
+                }
+                if view.aligned_columns.len() < view.columns.len() {
+                    ui.label(
+                        "Underlined columns lie outside the aligned region; the window is \
+                         extended over them without gaps.",
+                    );
+                }
+                if view.columns.is_empty() {
+                    ui.label("No aligned region.");
+                    return;
+                }
 
-            ui.horizontal(|ui| {
-                ui.radio_value(
-                    &mut self.thread_selection,
-                    ThreadSelection::Auto,
-                    format!("Auto ({} threads)", available_threads),
-                );
-            });
-            ui.horizontal(|ui| {
-                ui.radio_value(
-                    &mut self.thread_selection,
-                    ThreadSelection::Manual,
-                    "Manual:",
-                );
-                let enabled = self.thread_selection == ThreadSelection::Manual;
-                ui.add_enabled(
-                    enabled,
-                    egui::DragValue::new(&mut self.manual_thread_count)
-                        .range(1..=available_threads.max(32)),
-                );
-                ui.label("threads");
+                ui.add_space(5.0);
+                let markers: String = view
+                    .columns
+                    .iter()
+                    .map(|kind| match kind {
+                        ColumnKind::Match => '|',
+                        ColumnKind::Mismatch => '*',
+                        ColumnKind::Gap => ' ',
+                    })
+                    .collect();
+                egui::ScrollArea::horizontal()
+                    .id_salt("alignment_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("alignment_grid").show(ui, |ui| {
+                            ui.monospace("Oligo");
+                            ui.label(alignment_row(&view.oligo, view));
+                            ui.end_row();
+                            ui.label("");
+                            ui.label(alignment_row(&markers, view));
+                            ui.end_row();
+                            ui.monospace(format!("Target {}", view.target_start + 1));
+                            ui.label(alignment_row(&view.target, view));
+                            ui.end_row();
+                        });
+                    });
             });
-        });
 
-        ui.add_space(10.0);
+        self.show_alignment_window = open;
+    }
 
-        // === Process / Stop Controls ===
-        ui.horizontal(|ui| {
-            let can_process =
-                !self.worklist.is_empty() && self.worklist_state == WorklistState::Idle;
-            if ui
-                .add_enabled(can_process, egui::Button::new("Process Worklist"))
-                .clicked()
-            {
-                self.start_worklist_processing();
-            }
+    /// Reference panel inspection: length histogram, per-record ambiguity and
+    /// template k-mer share, duplicates, and flagged records.
+    fn show_inspect_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_inspect_window;
 
-            let can_stop = self.worklist_state == WorklistState::Processing;
-            if ui
-                .add_enabled(can_stop, egui::Button::new("Stop After Current"))
-                .clicked()
-            {
-                self.worklist_state = WorklistState::StopRequested;
-            }
+        if let (None, Some(references)) = (&self.panel_report, &self.reference_data) {
+            let template = self.template_data.as_ref().map(|t| t.sequence.as_str());
+            self.panel_report = Some(inspect_references(references, template, &self.inspect_params));
+        }
 
-            match self.worklist_state {
-                WorklistState::Idle => {}
-                WorklistState::Processing => {
-                    ui.spinner();
-                    let jobs_done =
-                        self.worklist_total_at_start - self.worklist.len();
+        let mut rerun = false;
+        egui::Window::new("Reference Panel Inspection")
+            .open(&mut open)
+            .default_width(700.0)
+            .default_height(550.0)
+            .show(ctx, |ui| {
+                let (Some(references), Some(report)) = (&self.reference_data, &self.panel_report)
+                else {
+                    ui.colored_label(egui::Color32::GRAY, "No references loaded");
+                    return;
+                };
+
+                ui.horizontal(|ui| {
                     ui.label(format!(
-                        "Processing job {} of {}",
-                        jobs_done + 1,
-                        self.worklist_total_at_start
+                        "{} sequences, {}-{} bp (median {} bp)",
+                        references.len(),
+                        report.min_length,
+                        report.max_length,
+                        report.median_length
                     ));
-                }
-                WorklistState::StopRequested => {
-                    ui.spinner();
+                    if let Some(share) = report.median_template_kmer_share {
+                        ui.separator();
+                        ui.label(format!("median template k-mer share {:.0}%", share * 100.0));
+                    }
+                });
+
+                egui::CollapsingHeader::new("Thresholds")
+                    .id_salt("inspect_thresholds")
+                    .show(ui, |ui| {
+                        let params = &mut self.inspect_params;
+                        ui.horizontal(|ui| {
+                            ui.label("Ambiguity >");
+                            ui.add(
+                                egui::DragValue::new(&mut params.max_ambiguity_percent)
+                                    .range(0.0..=100.0)
+                                    .speed(0.1)
+                                    .suffix("%"),
+                            );
+                            ui.separator();
+                            ui.label("Length outside median ÷/×");
+                            ui.add(
+                                egui::DragValue::new(&mut params.length_outlier_factor)
+                                    .range(1.0..=100.0)
+                                    .speed(0.1),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("k-mer size:");
+                            ui.add(egui::DragValue::new(&mut params.kmer_size).range(4..=32));
+                            ui.separator();
+                            ui.label("Divergent below");
+                            ui.add(
+                                egui::DragValue::new(&mut params.divergence_ratio)
+                                    .range(0.0..=1.0)
+                                    .speed(0.01),
+                            );
+                            ui.label("× median share");
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Histogram bins:");
+                            ui.add(egui::DragValue::new(&mut params.histogram_bins).range(1..=200));
+                            if ui.button("Recompute").clicked() {
+                                rerun = true;
+                            }
+                        });
+                    });
+                if self.template_data.is_none() {
                     ui.colored_label(
-                        egui::Color32::YELLOW,
-                        "Stopping after current job...",
+                        egui::Color32::GRAY,
+                        "Load a template to check records for divergence.",
                     );
                 }
-            }
-        });
-
-        ui.add_space(5.0);
+                ui.separator();
 
-        // === Progress Bars ===
-        if self.worklist_state != WorklistState::Idle {
-            let jobs_done = self.worklist_total_at_start - self.worklist.len();
-            let overall_frac = if self.worklist_total_at_start > 0 {
-                jobs_done as f32 / self.worklist_total_at_start as f32
-            } else {
-                0.0
-            };
-            ui.horizontal(|ui| {
-                ui.label("Overall:");
-                ui.add(
-                    egui::ProgressBar::new(overall_frac).text(format!(
-                        "{}/{} jobs",
-                        jobs_done, self.worklist_total_at_start
-                    )),
+                // Length histogram
+                ui.strong("Length distribution");
+                let max_count = report
+                    .length_histogram
+                    .iter()
+                    .map(|b| b.count)
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                let (rect, response) = ui.allocate_exact_size(
+                    egui::vec2(ui.available_width(), 100.0),
+                    egui::Sense::hover(),
                 );
-            });
+                let painter = ui.painter_at(rect);
+                let bar_width = rect.width() / report.length_histogram.len().max(1) as f32;
+                let mut hovered = None;
+                for (i, bin) in report.length_histogram.iter().enumerate() {
+                    let height = rect.height() * bin.count as f32 / max_count as f32;
+                    let left = rect.left() + i as f32 * bar_width;
+                    let bar = egui::Rect::from_min_max(
+                        egui::pos2(left + 1.0, rect.bottom() - height),
+                        egui::pos2(left + bar_width - 1.0, rect.bottom()),
+                    );
+                    painter.rect_filled(bar, 0.0, egui::Color32::from_rgb(100, 150, 220));
+                    if response
+                        .hover_pos()
+                        .is_some_and(|p| p.x >= bar.left() - 1.0 && p.x <= bar.right() + 1.0)
+                    {
+                        hovered = Some(bin);
+                    }
+                }
+                if let Some(bin) = hovered {
+                    response.on_hover_text(format!(
+                        "{}-{} bp: {} sequences",
+                        bin.start, bin.end, bin.count
+                    ));
+                }
+                ui.separator();
 
-            if let Some(ref progress) = self.analysis_progress {
-                let job_frac = if progress.total_lengths > 0 {
-                    let length_frac =
-                        progress.lengths_completed as f32 / progress.total_lengths as f32;
-                    let pos_frac = if progress.total_positions > 0 {
-                        // Use completed count from the message (parsed from "Position X/Y")
-                        // Fall back to a rough estimate from position index
-                        (progress.lengths_completed as f32
-                            + (1.0 / progress.total_lengths as f32))
-                            .min(1.0)
-                    } else {
-                        0.0
-                    };
-                    let _ = pos_frac;
-                    length_frac
+                // Duplicates
+                if report.duplicate_groups.is_empty() && report.duplicate_names.is_empty() {
+                    ui.label("No duplicate sequences or names");
                 } else {
-                    0.0
-                };
+                    let copies: usize = report.duplicate_groups.iter().map(|g| g.len() - 1).sum();
+                    egui::CollapsingHeader::new(format!(
+                        "Duplicates: {} identical groups ({} redundant copies), {} repeated names",
+                        report.duplicate_groups.len(),
+                        copies,
+                        report.duplicate_names.len()
+                    ))
+                    .id_salt("inspect_duplicates")
+                    .show(ui, |ui| {
+                        for group in &report.duplicate_groups {
+                            let names: Vec<&str> = group
+                                .iter()
+                                .map(|&i| references.names[i].as_str())
+                                .collect();
+                            ui.label(format!("identical: {}", names.join(", ")));
+                        }
+                        for name in &report.duplicate_names {
+                            ui.label(format!("repeated name: {}", name));
+                        }
+                    });
+                }
+                ui.separator();
+
+                // Per-record table
+                let flagged = report.flagged().count();
                 ui.horizontal(|ui| {
-                    ui.label("Current job:");
-                    ui.add(
-                        egui::ProgressBar::new(job_frac).text(&progress.message),
-                    );
+                    ui.strong(format!("{} flagged records", flagged));
+                    ui.checkbox(&mut self.inspect_show_all, "Show all records");
                 });
-            }
-        }
+                egui::ScrollArea::both()
+                    .id_salt("inspect_records_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("inspect_records_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("Name");
+                                ui.strong("Length");
+                                ui.strong("Ambig. %");
+                                ui.strong("Gaps");
+                                ui.strong("Template k-mers");
+                                ui.strong("Issues");
+                                ui.end_row();
 
-        ui.add_space(10.0);
+                                for record in &report.records {
+                                    if !self.inspect_show_all && record.issues.is_empty() {
+                                        continue;
+                                    }
+                                    ui.label(&references.names[record.index]);
+                                    ui.label(record.length.to_string());
+                                    ui.label(format!("{:.2}", record.ambiguity_percent()));
+                                    ui.label(record.gap_count.to_string());
+                                    ui.label(
+                                        record
+                                            .template_kmer_share
+                                            .map_or("-".to_string(), |s| {
+                                                format!("{:.0}%", s * 100.0)
+                                            }),
+                                    );
+                                    let issues: Vec<&str> =
+                                        record.issues.iter().map(|i| i.label()).collect();
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(220, 140, 0),
+                                        issues.join(", "),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
 
-        // === Queued Jobs Table ===
-        ui.heading("Queued Jobs");
-        if self.worklist.is_empty() {
-            ui.colored_label(
-                egui::Color32::GRAY,
-                "No jobs queued. Use the Input Data tab to add jobs.",
-            );
-        } else {
-            let mut pending_remove: Option<usize> = None;
+        self.show_inspect_window = open;
+        if rerun {
+            self.panel_report = None;
+            self.reference_dedup = None;
+        }
+    }
 
-            egui::ScrollArea::vertical()
-                .id_salt("worklist_scroll")
-                .max_height(300.0)
-                .show(ui, |ui| {
-                    egui::Grid::new("worklist_grid")
-                        .striped(true)
-                        .min_col_width(40.0)
-                        .show(ui, |ui| {
-                            // Header
-                            ui.strong("");
-                            ui.strong("#");
-                            ui.strong("Template");
-                            ui.strong("References");
-                            ui.strong("Exclusivity");
-                            ui.strong("Oligo Range");
-                            ui.strong("Method");
-                            ui.strong("Output");
-                            ui.end_row();
+    /// Consensus of the references on the template, built on a background
+    /// thread, with the option to make it the template.
+    fn show_consensus_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_consensus_window;
+        let mut build = false;
+        let mut use_as_template = false;
+        egui::Window::new("Reference Consensus")
+            .open(&mut open)
+            .default_width(600.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                let (Some(template), Some(references)) =
+                    (&self.template_data, &self.reference_data)
+                else {
+                    ui.colored_label(egui::Color32::GRAY, "Load a template and references");
+                    return;
+                };
+                ui.label(format!(
+                    "{} references anchored on {} ({} bp); bases a reference inserts \
+                     relative to the template are left out.",
+                    references.len(),
+                    template.name,
+                    template.sequence.len()
+                ));
 
-                            for (i, job) in self.worklist.iter().enumerate() {
-                                let is_current =
-                                    self.worklist_state == WorklistState::Processing
-                                        && i == self.current_job_index;
-
-                                if is_current {
-                                    ui.spinner();
-                                } else if ui.small_button("X").clicked() {
-                                    pending_remove = Some(i);
-                                }
+                let params = &mut self.consensus_params;
+                ui.horizontal(|ui| {
+                    ui.label("Consensus:");
+                    for mode in ConsensusMode::ALL {
+                        ui.radio_value(&mut params.mode, mode, mode.label());
+                    }
+                    ui.separator();
+                    ui.add_enabled_ui(params.mode == ConsensusMode::Iupac, |ui| {
+                        ui.label("Include bases in at least");
+                        let mut percent = params.min_fraction * 100.0;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut percent)
+                                    .range(1.0..=50.0)
+                                    .speed(0.5)
+                                    .suffix("%"),
+                            )
+                            .changed()
+                        {
+                            params.min_fraction = percent / 100.0;
+                        }
+                        ui.label("of references");
+                    });
+                });
 
-                                ui.label(format!("{}", job.id));
-                                ui.label(&job.template_file_name);
-                                ui.label(format!("{} seqs", job.reference_count));
-                                if job.use_differential {
-                                    ui.label(format!("{} seqs", job.exclusivity_count));
-                                } else {
-                                    ui.label("-");
-                                }
-                                ui.label(format!(
-                                    "{}-{} bp",
-                                    job.params.min_oligo_length,
-                                    job.params.max_oligo_length
-                                ));
-                                ui.label(job.params.method.description());
-                                if job.output_folder.is_some() {
-                                    ui.label("Auto-save");
-                                } else {
-                                    ui.label("-");
-                                }
-                                ui.end_row();
-                            }
-                        });
+                ui.horizontal(|ui| {
+                    let running = self.consensus_rx.is_some();
+                    if ui
+                        .add_enabled(!running, egui::Button::new("Build"))
+                        .clicked()
+                    {
+                        build = true;
+                    }
+                    if running {
+                        ui.spinner();
+                        ui.label("Aligning references...");
+                    }
+                });
+
+                let Some(consensus) = &self.consensus else {
+                    return;
+                };
+                ui.separator();
+                ui.label(format!(
+                    "{} bp from {} aligned references ({} without a template anchor left out)",
+                    consensus.sequence.len(),
+                    consensus.aligned,
+                    consensus.unmapped
+                ));
+                ui.label(format!(
+                    "{} positions differ from the template, {} ambiguity codes, \
+                     {} template bases deleted, {} not covered (kept from the template)",
+                    consensus.changed, consensus.ambiguous, consensus.deleted, consensus.uncovered
+                ));
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !consensus.sequence.is_empty(),
+                            egui::Button::new("Use as Template"),
+                        )
+                        .on_hover_text("Replace the loaded template with the consensus")
+                        .clicked()
+                    {
+                        use_as_template = true;
+                    }
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(consensus.sequence.clone());
+                    }
                 });
+                egui::ScrollArea::vertical()
+                    .id_salt("consensus_sequence_scroll")
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::Label::new(egui::RichText::new(&consensus.sequence).monospace())
+                                .wrap(),
+                        );
+                    });
+            });
+        self.show_consensus_window = open;
 
-            if let Some(idx) = pending_remove {
-                self.remove_worklist_job(idx);
-            }
-        }
-
-        // === Completed Jobs Summary ===
-        if !self.completed_jobs.is_empty() {
-            ui.add_space(10.0);
-            ui.separator();
-            ui.label(format!(
-                "{} completed job(s) available in the Results tab.",
-                self.completed_jobs.len()
-            ));
+        if build
+            && let (Some(template), Some(references)) = (&self.template_data, &self.reference_data)
+        {
+            let template = template.sequence.clone();
+            let references = references.clone();
+            let params = self.consensus_params;
+            let (tx, rx) = channel();
+            self.consensus_rx = Some(rx);
+            self.consensus = None;
+            thread::spawn(move || {
+                let _ = tx.send(reference_consensus(&template, &references, &params));
+            });
         }
 
-        // === Auto-save error ===
-        if let Some(ref err) = self.auto_save_error {
-            ui.colored_label(egui::Color32::RED, err);
+        if use_as_template
+            && let (Some(consensus), Some(template)) = (self.consensus.take(), &self.template_data)
+        {
+            let source = self.reference_file_name.as_deref().unwrap_or("references");
+            self.template_file_name = Some(format!("consensus of {}", source));
+            self.template_data = Some(TemplateData {
+                name: format!("{} consensus", template.name),
+                sequence: consensus.sequence,
+                rna: template.rna,
+            });
+            self.batch_templates.clear();
+            self.template_error = None;
+            self.panel_report = None;
+            self.reference_dedup = None;
+            self.show_consensus_window = false;
         }
     }
 
-    fn show_results_tab(&mut self, ui: &mut egui::Ui) {
-        if self.completed_jobs.is_empty() {
-            ui.heading("Results");
-            ui.separator();
-            ui.label(
-                "No completed jobs yet. Add jobs in the Input tab and process them in the Worklist tab.",
-            );
-            ui.add_space(10.0);
-            if ui.button("Load Results from File").clicked() {
-                self.load_results_into_completed();
-            }
-            if let Some(ref error) = self.load_error {
-                ui.colored_label(egui::Color32::RED, error);
-            }
-            return;
-        }
-
-        // Job selector + header
-        ui.horizontal(|ui| {
-            ui.heading("Results");
-
-            ui.separator();
-            ui.label("Job:");
+    /// Cross-job query: windows matching the criteria in any completed job,
+    /// ranked together with their job identifiers.
+    fn show_query_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_query_window;
+        let mut jump_to: Option<(usize, u32, usize)> = None;
 
-            let selected_label = self
-                .selected_completed_job_index
-                .and_then(|i| self.completed_jobs.get(i))
-                .map(|cj| {
-                    format!("#{} - {}", cj.job.id, cj.job.template_file_name)
-                })
-                .unwrap_or_else(|| "Select a job".to_string());
+        egui::Window::new("Cross-Job Candidate Search")
+            .open(&mut open)
+            .default_width(800.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                egui::CollapsingHeader::new("Search by oligo sequence")
+                    .id_salt("oligo_search")
+                    .show(ui, |ui| self.oligo_search_ui(ui, &mut jump_to));
+                ui.separator();
 
-            let mut new_selection = self.selected_completed_job_index;
-            egui::ComboBox::from_id_salt("completed_job_selector")
-                .selected_text(&selected_label)
-                .show_ui(ui, |ui| {
-                    for (i, cj) in self.completed_jobs.iter().enumerate() {
-                        let label = format!(
-                            "#{} - {} ({} refs, {}-{} bp)",
-                            cj.job.id,
-                            cj.job.template_file_name,
-                            cj.job.reference_count,
-                            cj.job.params.min_oligo_length,
-                            cj.job.params.max_oligo_length,
-                        );
-                        ui.selectable_value(&mut new_selection, Some(i), label);
-                    }
+                let query = &mut self.candidate_query;
+                ui.horizontal(|ui| {
+                    ui.label("Variants needed ≤");
+                    ui.add(egui::DragValue::new(&mut query.max_variants_needed).range(1..=1000));
+                    ui.separator();
+                    let mut use_excl = query.min_exclusivity_mismatches.is_some();
+                    ui.checkbox(&mut use_excl, "Exclusivity mismatches ≥");
+                    let mut min_mm = query.min_exclusivity_mismatches.unwrap_or(4);
+                    ui.add_enabled(use_excl, egui::DragValue::new(&mut min_mm).range(0..=50));
+                    query.min_exclusivity_mismatches = use_excl.then_some(min_mm);
+                    ui.label("ignoring best");
+                    ui.add(egui::DragValue::new(&mut query.ignore_count).range(0..=1000));
                 });
-
-            // Sync results when selection changes
-            if new_selection != self.selected_completed_job_index {
-                self.selected_completed_job_index = new_selection;
-                if let Some(idx) = new_selection {
-                    if let Some(cj) = self.completed_jobs.get(idx) {
-                        self.results = Some(cj.results.clone());
-                        self.view_coverage_threshold = cj.results.params.coverage_threshold;
-                        self.differential_mode = cj.results.differential_enabled;
-                    }
+                ui.horizontal(|ui| {
+                    let mut use_nm = query.max_no_match_percent.is_some();
+                    ui.checkbox(&mut use_nm, "No-match ≤");
+                    let mut max_nm = query.max_no_match_percent.unwrap_or(5.0);
+                    ui.add_enabled(
+                        use_nm,
+                        egui::DragValue::new(&mut max_nm)
+                            .range(0.0..=100.0)
+                            .speed(0.5)
+                            .suffix("%"),
+                    );
+                    query.max_no_match_percent = use_nm.then_some(max_nm);
+                    ui.separator();
+                    ui.label("Max results:");
+                    ui.add(egui::DragValue::new(&mut query.max_results).range(1..=100_000));
+                });
+                if query.min_exclusivity_mismatches.is_some() {
+                    ui.label("Jobs without exclusivity data are skipped while the mismatch criterion is on.");
                 }
-            }
 
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if ui.button("Load Results from File").clicked() {
-                    self.load_results_into_completed();
+                if ui.button("Search All Jobs").clicked() {
+                    let jobs: Vec<&ScreeningResults> =
+                        self.completed_jobs.iter().map(|cj| &cj.results).collect();
+                    self.query_hits = Some(query_candidates(&jobs, &self.candidate_query));
                 }
-                let has_results = self.results.is_some();
-                if ui
-                    .add_enabled(has_results, egui::Button::new("Save Results"))
-                    .clicked()
-                {
-                    self.pending_save = true;
+
+                ui.separator();
+
+                let Some(ref hits) = self.query_hits else {
+                    ui.colored_label(egui::Color32::GRAY, "Press Search to query all completed jobs.");
+                    return;
+                };
+                if hits.is_empty() {
+                    ui.colored_label(egui::Color32::YELLOW, "No windows match the criteria.");
+                    return;
                 }
-            });
-        });
-        ui.separator();
+                ui.label(format!("{} matching windows", hits.len()));
 
-        if self.results.is_none() {
-            ui.label("Select a completed job to view its results.");
-            return;
-        }
+                egui::ScrollArea::both()
+                    .id_salt("query_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("query_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("#");
+                                ui.strong("Job");
+                                ui.strong("Length");
+                                ui.strong("Pos");
+                                ui.strong("Oligo (5'-3')");
+                                ui.strong("Variants");
+                                ui.strong("Coverage");
+                                ui.strong("No match");
+                                ui.strong("Excl. min MM");
+                                ui.end_row();
 
-        // Extract data we need
-        let (lengths, template_seq, total_seqs, has_differential) = {
-            let results = self.results.as_ref().unwrap();
-            let mut lengths: Vec<u32> = results.results_by_length.keys().copied().collect();
-            lengths.sort();
-            (
-                lengths,
-                results.template_sequence.clone(),
-                results.total_sequences,
-                results.differential_enabled,
-            )
-        };
+                                for (i, hit) in hits.iter().enumerate() {
+                                    ui.label(format!("{}", i + 1));
+                                    match self.completed_jobs.get(hit.job_index) {
+                                        Some(cj) => ui.label(format!(
+                                            "#{} - {}",
+                                            cj.job.id, cj.job.template_file_name
+                                        )),
+                                        None => ui.label("?"),
+                                    };
+                                    ui.label(format!("{} bp", hit.oligo_length));
+                                    if ui
+                                        .small_button(format!("{}", hit.position + 1))
+                                        .on_hover_text("Show this job and open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((hit.job_index, hit.oligo_length, hit.position));
+                                    }
+                                    ui.monospace(&hit.oligo);
+                                    ui.label(format!("{}", hit.variants_needed));
+                                    ui.label(format!("{:.1}%", hit.coverage_at_threshold));
+                                    ui.label(format!("{:.1}%", hit.no_match_percent));
+                                    ui.label(match hit.exclusivity_min_mismatches {
+                                        None => "-".to_string(),
+                                        Some(None) => "all no-match".to_string(),
+                                        Some(Some(mm)) => mm.to_string(),
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
 
-        if lengths.is_empty() {
-            ui.label("No length results available.");
-            return;
+        self.show_query_window = open;
+        if let Some((job_index, length, pos)) = jump_to {
+            if self.selected_completed_job_index != Some(job_index) {
+                self.select_completed_job(job_index);
+            }
+            self.current_tab = Tab::Results;
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
         }
+    }
 
-        // Controls row 1: zoom + info + differential toggle
+    /// Search box for an existing oligo: the windows of every completed job
+    /// whose top variants it matches, e.g. to check whether an ordered primer
+    /// already covers a new reference set.
+    fn oligo_search_ui(&mut self, ui: &mut egui::Ui, jump_to: &mut Option<(usize, u32, usize)>) {
+        let mut search = false;
         ui.horizontal(|ui| {
-            ui.label("Zoom:");
-            ui.add(egui::Slider::new(&mut self.zoom_level, 0.5..=3.0));
-            ui.add_space(20.0);
-            ui.label(format!(
-                "{} reference sequences | Template: {} bp",
-                total_seqs,
-                template_seq.len()
-            ));
-            if has_differential {
-                ui.separator();
-                ui.checkbox(&mut self.differential_mode, "Differential mode");
-            }
+            ui.label("Oligo (IUPAC):");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.oligo_search)
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(260.0),
+            );
+            search = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.label("in top");
+            ui.add(egui::DragValue::new(&mut self.oligo_search_top_variants).range(1..=100));
+            ui.label("variants");
+            search |= ui.button("Search").clicked();
         });
+        ui.label("Both strands are searched; the oligo may be shorter or longer than the windows.");
 
-        if !self.differential_mode {
-            // === NORMAL MODE CONTROLS ===
+        if search {
+            // Variant lists of jobs opened from indexed files are read in first
+            let mut loaded = self.load_all_window_variants();
+            for cj in &mut self.completed_jobs {
+                let Some(source) = cj.detail_source.take() else {
+                    continue;
+                };
+                if let Err(e) = source.load_all_variants(&mut cj.results) {
+                    cj.detail_source = Some(source);
+                    self.oligo_hits = Some(Err(format!("Failed to read variants: {}", e)));
+                    loaded = false;
+                    break;
+                }
+            }
+            if loaded {
+                let jobs: Vec<&ScreeningResults> =
+                    self.completed_jobs.iter().map(|cj| &cj.results).collect();
+                self.oligo_hits = Some(search_oligo(
+                    &jobs,
+                    &self.oligo_search,
+                    self.oligo_search_top_variants,
+                    self.candidate_query.max_results,
+                ));
+            }
+        }
 
-            // Controls row 2: coverage threshold + color range
-            ui.horizontal(|ui| {
-                ui.label("Coverage threshold (%):");
-                ui.add(
-                    egui::DragValue::new(&mut self.view_coverage_threshold)
-                        .range(1.0..=100.0)
-                        .speed(0.5),
+        let hits = match &self.oligo_hits {
+            None => return,
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+                return;
+            }
+            Some(Ok(hits)) if hits.is_empty() => {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "The oligo matches no top variant of any window.",
                 );
-                if ui.button("Apply").clicked() {
-                    self.recalculate_coverage_threshold();
-                }
-                ui.separator();
-                ui.label("Color range - Green at:");
-                ui.add(egui::DragValue::new(&mut self.color_green_at).range(1..=1000));
-                ui.label("variants, Red at:");
-                ui.add(egui::DragValue::new(&mut self.color_red_at).range(1..=1000));
-                ui.label("variants");
+                return;
+            }
+            Some(Ok(hits)) => hits,
+        };
+        ui.label(format!("{} windows matched", hits.len()));
+        egui::ScrollArea::both()
+            .id_salt("oligo_search_scroll")
+            .max_height(250.0)
+            .show(ui, |ui| {
+                egui::Grid::new("oligo_search_grid")
+                    .striped(true)
+                    .min_col_width(40.0)
+                    .show(ui, |ui| {
+                        ui.strong("Job");
+                        ui.strong("Length");
+                        ui.strong("Pos");
+                        ui.strong("Strand");
+                        ui.strong("Variants");
+                        ui.strong("Coverage");
+                        ui.end_row();
+
+                        for hit in hits {
+                            match self.completed_jobs.get(hit.job_index) {
+                                Some(cj) => ui.label(format!(
+                                    "#{} - {}",
+                                    cj.job.id, cj.job.template_file_name
+                                )),
+                                None => ui.label("?"),
+                            };
+                            ui.label(format!("{} bp", hit.oligo_length));
+                            if ui
+                                .small_button(format!("{}", hit.position + 1))
+                                .on_hover_text("Show this job and open window details")
+                                .clicked()
+                            {
+                                *jump_to = Some((hit.job_index, hit.oligo_length, hit.position));
+                            }
+                            ui.label(if hit.antisense { "-" } else { "+" });
+                            let ranks: Vec<String> = hit
+                                .variant_ranks
+                                .iter()
+                                .map(|r| format!("#{}", r))
+                                .collect();
+                            ui.label(ranks.join(", "));
+                            ui.label(format!("{:.1}%", hit.coverage));
+                            ui.end_row();
+                        }
+                    });
             });
+    }
 
-            // Ensure green <= red
-            if self.color_green_at > self.color_red_at {
-                self.color_red_at = self.color_green_at;
-            }
+    fn show_sweep_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_sweep_window;
+        let mut generate = false;
+        let base = self.current_params();
 
-            // Controls row 3: no-match darkening thresholds
-            ui.horizontal(|ui| {
-                ui.label("No-match darkening - OK at:");
-                ui.add(
-                    egui::DragValue::new(&mut self.nomatch_ok_percent)
-                        .range(0.0..=100.0)
-                        .speed(0.5)
-                        .suffix("%"),
-                );
-                ui.label(", Dark red at:");
-                ui.add(
-                    egui::DragValue::new(&mut self.nomatch_bad_percent)
-                        .range(0.0..=100.0)
-                        .speed(0.5)
-                        .suffix("%"),
+        egui::Window::new("Parameter Sweep")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Queues one worklist job per combination of the values below, all on the \
+                     current inputs. Lists are comma-separated; a-b covers a range. An empty \
+                     list keeps the Analysis Setup value; other parameters are taken from \
+                     Analysis Setup.",
                 );
-            });
+                ui.add_space(5.0);
+                egui::Grid::new("sweep_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Oligo lengths:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.sweep_spec.lengths).hint_text(
+                                format!("{}-{}", base.min_oligo_length, base.max_oligo_length),
+                            ),
+                        )
+                        .on_hover_text("Length ranges, e.g. 18-22, 25 (25 alone is 25-25)");
+                        ui.end_row();
 
-            if self.nomatch_ok_percent > self.nomatch_bad_percent {
-                self.nomatch_bad_percent = self.nomatch_ok_percent;
-            }
-        } else {
-            // === DIFFERENTIAL MODE CONTROLS ===
+                        ui.checkbox(&mut self.sweep_spec.no_ambiguities, "No Ambiguities");
+                        ui.label("");
+                        ui.end_row();
 
-            // Exclusivity color controls
-            ui.horizontal(|ui| {
-                ui.label("Exclusivity color - Green at:");
-                ui.add(egui::DragValue::new(&mut self.diff_green_at).range(0..=50));
-                ui.label("mismatches, Red at:");
-                ui.add(egui::DragValue::new(&mut self.diff_red_at).range(0..=50));
-                ui.label("mismatches");
-                ui.separator();
-                ui.label("Ignore best:");
-                ui.add(egui::DragValue::new(&mut self.diff_ignore_count).range(0..=1000));
-                ui.label("sequences");
-            });
+                        ui.checkbox(
+                            &mut self.sweep_spec.fixed_ambiguities,
+                            "Fixed Ambiguities, max:",
+                        );
+                        ui.add_enabled(
+                            self.sweep_spec.fixed_ambiguities,
+                            egui::TextEdit::singleline(&mut self.sweep_spec.fixed_values),
+                        );
+                        ui.end_row();
 
-            // Darkening controls (conservation metrics)
-            ui.horizontal(|ui| {
-                ui.label("Darkening - Variant count: Green at:");
-                ui.add(egui::DragValue::new(&mut self.color_green_at).range(1..=1000));
-                ui.label(", Red at:");
-                ui.add(egui::DragValue::new(&mut self.color_red_at).range(1..=1000));
-                ui.separator();
-                ui.label("No-match: OK at:");
-                ui.add(
-                    egui::DragValue::new(&mut self.nomatch_ok_percent)
-                        .range(0.0..=100.0)
-                        .speed(0.5)
-                        .suffix("%"),
-                );
-                ui.label(", Bad at:");
-                ui.add(
-                    egui::DragValue::new(&mut self.nomatch_bad_percent)
-                        .range(0.0..=100.0)
-                        .speed(0.5)
-                        .suffix("%"),
-                );
-            });
+                        ui.checkbox(
+                            &mut self.sweep_spec.incremental,
+                            "Incremental, coverage per step (%):",
+                        );
+                        ui.add_enabled(
+                            self.sweep_spec.incremental,
+                            egui::TextEdit::singleline(&mut self.sweep_spec.incremental_values),
+                        );
+                        ui.end_row();
 
-            if self.color_green_at > self.color_red_at {
-                self.color_red_at = self.color_green_at;
-            }
-            if self.nomatch_ok_percent > self.nomatch_bad_percent {
-                self.nomatch_bad_percent = self.nomatch_ok_percent;
-            }
+                        ui.label("Max mismatches:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.sweep_spec.max_mismatches)
+                                .hint_text(base.pairwise.max_mismatches.to_string()),
+                        );
+                        ui.end_row();
+                    });
+                if !self.sweep_spec.no_ambiguities
+                    && !self.sweep_spec.fixed_ambiguities
+                    && !self.sweep_spec.incremental
+                {
+                    ui.label(format!("Method: {}", base.method.description()));
+                }
 
-            // Coverage threshold (still needed for variant count)
-            ui.horizontal(|ui| {
-                ui.label("Coverage threshold (%):");
-                ui.add(
-                    egui::DragValue::new(&mut self.view_coverage_threshold)
-                        .range(1.0..=100.0)
-                        .speed(0.5),
-                );
-                if ui.button("Apply").clicked() {
-                    self.recalculate_coverage_threshold();
+                ui.add_space(5.0);
+                let sweep = self.sweep_spec.expand(&base);
+                match &sweep {
+                    Ok(sweep) => {
+                        ui.label(format!("{} job(s) will be queued", sweep.len()));
+                    }
+                    Err(e) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                }
+                if let Some(ref e) = self.sweep_error {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                let can_add = self.can_add_to_worklist() && sweep.is_ok();
+                if ui
+                    .add_enabled(can_add, egui::Button::new("Add Jobs to Worklist"))
+                    .clicked()
+                {
+                    generate = true;
                 }
             });
-        }
-
-        ui.add_space(5.0);
-
-        // Heatmap display
-        let coverage_threshold = self.view_coverage_threshold;
-        self.show_heatmap(ui, &lengths, &template_seq, coverage_threshold);
 
-        // Error messages
-        if let Some(ref error) = self.save_error {
-            ui.colored_label(egui::Color32::RED, error);
-        }
-        if let Some(ref error) = self.load_error {
-            ui.colored_label(egui::Color32::RED, error);
+        if generate {
+            self.add_sweep_to_worklist();
+        } else {
+            self.show_sweep_window = open;
         }
     }
 
-    fn show_heatmap(
-        &mut self,
-        ui: &mut egui::Ui,
-        lengths: &[u32],
-        template_seq: &str,
-        coverage_threshold: f64,
-    ) {
-        let results = self.results.as_ref().unwrap();
-
-        // Get positions from the first length result
-        let first_length_result = results.results_by_length.get(&lengths[0]);
-        let positions: Vec<usize> = first_length_result
-            .map(|lr| lr.positions.iter().map(|p| p.position).collect())
-            .unwrap_or_default();
-
-        if positions.is_empty() {
-            ui.label("No positions analyzed.");
-            return;
+    /// Show the results database browser, on the output folder's database
+    /// unless another one was opened.
+    fn open_database_browser(&mut self) {
+        if self.database_path.is_none() {
+            self.database_path = self.output_folder.as_deref().map(database_path);
         }
+        self.refresh_database_jobs();
+        self.show_database_window = true;
+    }
 
-        // Cell dimensions: zoom only affects horizontal width, height is fixed
-        let cell_w = (14.0 * self.zoom_level).max(3.0);
-        let cell_h: f32 = 54.0;
-        let label_width: f32 = 50.0;
-        let header_height: f32 = 20.0;
-        let pos_label_height: f32 = 14.0;
+    fn refresh_database_jobs(&mut self) {
+        // Browsing never creates a database; the first auto-save does
+        self.database_jobs = self.database_path.as_ref().map(|path| {
+            if path.is_file() {
+                ResultsDatabase::open(path).and_then(|db| db.jobs())
+            } else {
+                Err(format!("No results database at {} yet", path.display()))
+            }
+        });
+        self.database_tag_edits.clear();
+        self.database_delete_pending = None;
+    }
 
-        let num_cols = positions.len();
-        let num_rows = lengths.len();
+    fn with_results_database<T>(
+        &self,
+        f: impl FnOnce(&ResultsDatabase) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let path = self
+            .database_path
+            .as_ref()
+            .ok_or_else(|| "No results database selected".to_string())?;
+        ResultsDatabase::open(path).and_then(|db| f(&db))
+    }
 
-        // Summary stats per length
-        ui.group(|ui| {
-            ui.horizontal_wrapped(|ui| {
-                for &length in lengths {
-                    if let Some(lr) = results.results_by_length.get(&length) {
-                        let non_skipped: Vec<_> =
-                            lr.positions.iter().filter(|p| !p.analysis.skipped).collect();
-                        if !non_skipped.is_empty() {
-                            let avg: f64 =
-                                non_skipped.iter().map(|p| p.variants_needed).sum::<usize>()
-                                    as f64
-                                    / non_skipped.len() as f64;
-                            let min = non_skipped
-                                .iter()
-                                .map(|p| p.variants_needed)
-                                .min()
-                                .unwrap_or(0);
-                            let max = non_skipped
-                                .iter()
-                                .map(|p| p.variants_needed)
-                                .max()
-                                .unwrap_or(0);
-                            ui.label(format!(
-                                "{}bp: {}-{} (avg {:.1})",
-                                length, min, max, avg
-                            ));
-                            ui.separator();
-                        }
+    fn show_database_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_database_window;
+        let mut pick_database = false;
+        let mut refresh = false;
+        let mut open_job: Option<(i64, String)> = None;
+        let mut delete_pending: Option<i64> = None;
+        let mut delete_job: Option<i64> = None;
+        let mut tag_update: Option<(i64, Vec<String>)> = None;
+
+        egui::Window::new("Results Database")
+            .open(&mut open)
+            .default_width(850.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    match &self.database_path {
+                        Some(path) => ui.label(path.display().to_string()),
+                        None => ui.label("No database selected"),
+                    };
+                    if ui.button("Open Database...").clicked() {
+                        pick_database = true;
+                    }
+                    if ui
+                        .add_enabled(self.database_path.is_some(), egui::Button::new("Refresh"))
+                        .clicked()
+                    {
+                        refresh = true;
                     }
+                });
+                if let Some(ref e) = self.database_error {
+                    ui.colored_label(egui::Color32::RED, e);
                 }
-            });
-        });
+                ui.separator();
 
-        ui.add_space(5.0);
+                let jobs = match &self.database_jobs {
+                    None => {
+                        ui.label(format!(
+                            "Set an output folder to browse its {}, or open a database",
+                            RESULTS_DATABASE_FILE
+                        ));
+                        return;
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                        return;
+                    }
+                    Some(Ok(jobs)) => jobs,
+                };
+                if jobs.is_empty() {
+                    ui.label("No jobs stored yet");
+                    return;
+                }
+                let total: usize = jobs.iter().map(|job| job.size).sum();
+                ui.label(format!(
+                    "{} stored job(s), {:.1} MB. Results are read only when a job is opened; \
+                     tags are comma-separated.",
+                    jobs.len(),
+                    total as f64 / 1_000_000.0
+                ));
+                ui.add_space(5.0);
 
-        if self.differential_mode {
-            ui.label("Exclusivity: min mismatches (green=specific, red=similar to off-targets). Darkened by conservation metrics.");
-        } else {
-            ui.label(format!(
-                "Variants needed to reach {:.0}% coverage (click cell for details):",
-                coverage_threshold
-            ));
-        }
+                egui::ScrollArea::vertical()
+                    .max_height(450.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("database_grid")
+                            .num_columns(9)
+                            .striped(true)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for header in [
+                                    "Name", "Saved", "Template", "Lengths", "Method", "Refs",
+                                    "Size", "Tags", "",
+                                ] {
+                                    ui.strong(header);
+                                }
+                                ui.end_row();
 
-        // Build heatmap data: lookup by (length, position)
-        let heatmap_data: std::collections::HashMap<
-            (u32, usize),
-            &crate::analysis::PositionResult,
-        > = {
-            let mut map = std::collections::HashMap::new();
-            for &length in lengths {
-                if let Some(lr) = results.results_by_length.get(&length) {
-                    for pr in &lr.positions {
-                        map.insert((length, pr.position), pr);
+                                for job in jobs {
+                                    if job.cancelled {
+                                        ui.label(format!("{} (cancelled)", job.name));
+                                    } else {
+                                        ui.label(&job.name);
+                                    }
+                                    ui.label(format_timestamp(job.saved_at));
+                                    ui.label(format!("{} bp", job.template_length));
+                                    ui.label(format!(
+                                        "{}-{}",
+                                        job.min_oligo_length, job.max_oligo_length
+                                    ));
+                                    if job.differential {
+                                        ui.label(format!("{} (differential)", job.method));
+                                    } else {
+                                        ui.label(&job.method);
+                                    }
+                                    ui.label(job.reference_count.to_string());
+                                    ui.label(format!("{:.1} MB", job.size as f64 / 1_000_000.0));
+
+                                    let text = self
+                                        .database_tag_edits
+                                        .entry(job.id)
+                                        .or_insert_with(|| job.tags.join(", "));
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(text)
+                                            .desired_width(150.0)
+                                            .hint_text("tags"),
+                                    );
+                                    if response.lost_focus() && parse_tags(text) != job.tags {
+                                        tag_update = Some((job.id, parse_tags(text)));
+                                    }
+
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Open").clicked() {
+                                            open_job = Some((job.id, job.name.clone()));
+                                        }
+                                        if self.database_delete_pending == Some(job.id) {
+                                            if ui
+                                                .button(
+                                                    egui::RichText::new("Confirm Delete")
+                                                        .color(egui::Color32::RED),
+                                                )
+                                                .clicked()
+                                            {
+                                                delete_job = Some(job.id);
+                                            }
+                                        } else if ui.button("Delete").clicked() {
+                                            delete_pending = Some(job.id);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if pick_database
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("Results database", &["sqlite"])
+                .pick_file()
+        {
+            self.database_path = Some(path);
+            self.database_error = None;
+            refresh = true;
+        }
+        if let Some((id, tags)) = tag_update {
+            match self.with_results_database(|db| db.set_tags(id, &tags)) {
+                Ok(()) => {
+                    if let Some(Ok(jobs)) = &mut self.database_jobs
+                        && let Some(job) = jobs.iter_mut().find(|job| job.id == id)
+                    {
+                        job.tags = tags;
                     }
+                    self.database_error = None;
                 }
+                Err(e) => self.database_error = Some(format!("Failed to save tags: {}", e)),
             }
-            map
-        };
-
-        // Total width/height for the heatmap area
-        let total_width = label_width + (num_cols as f32 * cell_w);
-        let total_height =
-            pos_label_height + header_height + (num_rows as f32 * cell_h) + 30.0;
+        }
+        if delete_pending.is_some() {
+            self.database_delete_pending = delete_pending;
+        }
+        if let Some(id) = delete_job {
+            match self.with_results_database(|db| db.delete(id)) {
+                Ok(()) => self.database_error = None,
+                Err(e) => self.database_error = Some(format!("Failed to delete: {}", e)),
+            }
+            refresh = true;
+        }
+        if refresh {
+            self.refresh_database_jobs();
+        }
+        if let Some((id, name)) = open_job {
+            match self.with_results_database(|db| db.load(id)) {
+                Ok(results) => {
+                    self.add_loaded_results(results, None, &name);
+                    self.database_error = None;
+                }
+                Err(e) => self.database_error = Some(format!("Failed to open {}: {}", name, e)),
+            }
+        }
 
-        let scroll_output = egui::ScrollArea::horizontal()
-            .id_salt("heatmap_scroll")
-            .show(ui, |ui| {
-                let (response, painter) = ui.allocate_painter(
-                    egui::vec2(total_width, total_height),
-                    egui::Sense::click_and_drag(),
-                );
-                let origin = response.rect.min;
+        self.show_database_window = open;
+    }
 
-                // --- Position numbers row ---
-                let show_every_n = if cell_w < 12.0 {
-                    (12.0 / cell_w).ceil() as usize
-                } else {
-                    1
-                };
+    fn show_amplicon_search_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_amplicon_window;
+        let mut jump_to: Option<(u32, usize)> = None;
 
-                for (col, &pos) in positions.iter().enumerate() {
-                    if col % show_every_n != 0 {
-                        continue;
-                    }
-                    let x = origin.x + label_width + (col as f32 * cell_w) + cell_w / 2.0;
-                    let y = origin.y + pos_label_height / 2.0;
-                    painter.text(
-                        egui::pos2(x, y),
-                        egui::Align2::CENTER_CENTER,
-                        format!("{}", pos + 1),
-                        egui::FontId::proportional(9.0),
-                        egui::Color32::GRAY,
+        egui::Window::new("Amplicon-Constrained Degenerate Primer Search")
+            .open(&mut open)
+            .default_width(800.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Merges each window's top variants into one degenerate oligo within the \
+                     degeneracy budget, then pairs forward/reverse oligos by amplicon length.",
+                );
+                if self.active_exclusivity_filter().is_some() {
+                    ui.label(format!(
+                        "Exclusivity filter active: only windows with > {} mismatches are used.",
+                        self.excl_filter_threshold
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Amplicon length:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.amplicon_params.min_amplicon_length)
+                            .range(10..=10000),
+                    );
+                    ui.label("-");
+                    ui.add(
+                        egui::DragValue::new(&mut self.amplicon_params.max_amplicon_length)
+                            .range(10..=10000),
+                    );
+                    ui.label("bp");
+                    ui.separator();
+                    ui.label("Max degeneracy per primer:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.amplicon_params.max_degeneracy)
+                            .range(1..=4096),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Min coverage per primer (%):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.amplicon_params.min_coverage)
+                            .range(0.0..=100.0)
+                            .speed(0.5),
+                    );
+                    ui.separator();
+                    ui.checkbox(&mut self.amplicon_params.exclude_n, "Exclude N");
+                    ui.separator();
+                    ui.label("Max results:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.amplicon_params.max_results)
+                            .range(1..=10000),
                     );
+                });
+
+                if self.amplicon_params.min_amplicon_length
+                    > self.amplicon_params.max_amplicon_length
+                {
+                    self.amplicon_params.max_amplicon_length =
+                        self.amplicon_params.min_amplicon_length;
                 }
 
-                // --- Template sequence row ---
-                let seq_y_start = origin.y + pos_label_height;
-                if cell_w >= 8.0 {
-                    for (col, &pos) in positions.iter().enumerate() {
-                        if pos < template_seq.len() {
-                            let base = &template_seq[pos..pos + 1];
-                            let x =
-                                origin.x + label_width + (col as f32 * cell_w) + cell_w / 2.0;
-                            let y = seq_y_start + header_height / 2.0;
-
-                            let color = base_color(base.chars().next().unwrap_or('N'));
-                            painter.text(
-                                egui::pos2(x, y),
-                                egui::Align2::CENTER_CENTER,
-                                base,
-                                egui::FontId::monospace(11.0),
-                                color,
-                            );
-                        }
-                    }
-                } else {
-                    for (col, &pos) in positions.iter().enumerate() {
-                        if pos < template_seq.len() {
-                            let base_char = template_seq.as_bytes()[pos] as char;
-                            let color = base_color(base_char);
-                            let x = origin.x + label_width + (col as f32 * cell_w);
-                            let tick_rect = egui::Rect::from_min_size(
-                                egui::pos2(x, seq_y_start + 2.0),
-                                egui::vec2((cell_w - 1.0).max(1.0), header_height - 4.0),
-                            );
-                            painter.rect_filled(tick_rect, 0.0, color);
-                        }
+                if ui.button("Search").clicked()
+                    && self.load_all_window_variants()
+                    && let Some(ref results) = self.results
+                {
+                        self.amplicon_candidates =
+                            Some(find_amplicon_candidates(results, &self.amplicon_params));
                     }
-                }
 
-                // --- Row labels (oligo lengths) ---
-                let grid_y_start = seq_y_start + header_height;
-                for (row, &length) in lengths.iter().enumerate() {
-                    let y = grid_y_start + (row as f32 * cell_h) + cell_h / 2.0;
-                    painter.text(
-                        egui::pos2(origin.x + label_width - 5.0, y),
-                        egui::Align2::RIGHT_CENTER,
-                        format!("{} bp", length),
-                        egui::FontId::proportional(11.0),
-                        egui::Color32::LIGHT_GRAY,
+                ui.separator();
+
+                let Some(ref candidates) = self.amplicon_candidates else {
+                    ui.colored_label(egui::Color32::GRAY, "Press Search to find candidates.");
+                    return;
+                };
+                if candidates.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "No primer pairs satisfy the current constraints.",
                     );
+                    return;
                 }
+                ui.label(format!("{} candidate assays", candidates.len()));
 
-                // --- Heatmap cells ---
-                let mut hovered_cell: Option<(u32, usize)> = None;
-                let mut clicked_cell: Option<(u32, usize)> = None;
+                egui::ScrollArea::both()
+                    .id_salt("amplicon_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("amplicon_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("#");
+                                ui.strong("Forward (5'-3')");
+                                ui.strong("Pos");
+                                ui.strong("Degen.");
+                                ui.strong("Reverse (5'-3')");
+                                ui.strong("Pos");
+                                ui.strong("Degen.");
+                                ui.strong("Amplicon");
+                                ui.strong("Coverage");
+                                ui.end_row();
 
-                let is_differential = self.differential_mode;
+                                for (i, c) in candidates.iter().enumerate() {
+                                    ui.label(format!("{}", i + 1));
+                                    ui.monospace(&c.forward.sequence);
+                                    if ui
+                                        .small_button(format!("{}", c.forward.position + 1))
+                                        .on_hover_text("Open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((c.forward.length, c.forward.position));
+                                    }
+                                    ui.label(format!("{}", c.forward.degeneracy));
+                                    ui.monospace(&c.reverse.sequence);
+                                    if ui
+                                        .small_button(format!("{}", c.reverse.position + 1))
+                                        .on_hover_text("Open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((c.reverse.length, c.reverse.position));
+                                    }
+                                    ui.label(format!("{}", c.reverse.degeneracy));
+                                    ui.label(format!("{} bp", c.amplicon_length));
+                                    ui.label(format!("{:.1}%", c.coverage));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
 
-                for (row, &length) in lengths.iter().enumerate() {
-                    for (col, &pos) in positions.iter().enumerate() {
-                        let cell_x = origin.x + label_width + (col as f32 * cell_w);
-                        let cell_y = grid_y_start + (row as f32 * cell_h);
-                        let cell_rect = egui::Rect::from_min_size(
-                            egui::pos2(cell_x, cell_y),
-                            egui::vec2(cell_w - 1.0, cell_h - 1.0),
-                        );
+        self.show_amplicon_window = open;
+        if let Some((length, pos)) = jump_to {
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
+        }
+    }
 
-                        let color = if let Some(pr) = heatmap_data.get(&(length, pos)) {
-                            if pr.analysis.skipped {
-                                egui::Color32::from_rgb(40, 40, 40)
-                            } else if is_differential {
-                                let eff_min_mm = pr
-                                    .exclusivity
-                                    .as_ref()
-                                    .map(|e| {
-                                        effective_min_mismatches(e, self.diff_ignore_count)
-                                    })
-                                    .flatten();
-                                let no_match_frac = if pr.analysis.total_sequences > 0 {
-                                    pr.analysis.no_match_count as f64
-                                        / pr.analysis.total_sequences as f64
-                                } else {
-                                    0.0
-                                };
-                                differential_position_color(
-                                    eff_min_mm,
-                                    pr.variants_needed,
-                                    no_match_frac,
-                                    self.diff_green_at,
-                                    self.diff_red_at,
-                                    self.color_green_at,
-                                    self.color_red_at,
-                                    self.nomatch_ok_percent / 100.0,
-                                    self.nomatch_bad_percent / 100.0,
-                                )
-                            } else {
-                                let no_match_frac = if pr.analysis.total_sequences > 0 {
-                                    pr.analysis.no_match_count as f64
-                                        / pr.analysis.total_sequences as f64
-                                } else {
-                                    0.0
-                                };
-                                position_color(
-                                    pr.variants_needed,
-                                    no_match_frac,
-                                    self.color_green_at,
-                                    self.color_red_at,
-                                    self.nomatch_ok_percent / 100.0,
-                                    self.nomatch_bad_percent / 100.0,
-                                )
-                            }
-                        } else {
-                            egui::Color32::from_rgb(30, 30, 30)
-                        };
+    fn show_pair_design_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_pair_design_window;
+        let mut jump_to: Option<(u32, usize)> = None;
+        let mut export = false;
 
-                        painter.rect_filled(cell_rect, 1.0, color);
+        egui::Window::new("Forward/Reverse Pair and Triplet Design")
+            .open(&mut open)
+            .default_width(900.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Pairs windows that need few variants within a Tm window and scores each \
+                     pair jointly: references covered by both oligos, Tm difference, and the \
+                     closer exclusivity hit.",
+                );
+                if self.active_exclusivity_filter().is_some() {
+                    ui.label(format!(
+                        "Exclusivity filter active: only windows with > {} mismatches are used.",
+                        self.excl_filter_threshold
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.pair_design_triplets, false, "Primer pairs");
+                    ui.radio_value(
+                        &mut self.pair_design_triplets,
+                        true,
+                        "Triplets (primers + TaqMan probe)",
+                    );
+                });
+                let params = &mut self.pair_design_params;
+                ui.horizontal(|ui| {
+                    ui.label("Amplicon length:");
+                    ui.add(egui::DragValue::new(&mut params.min_amplicon_length).range(10..=10000));
+                    ui.label("-");
+                    ui.add(egui::DragValue::new(&mut params.max_amplicon_length).range(10..=10000));
+                    ui.label("bp");
+                    ui.separator();
+                    ui.label("Max variants per oligo:");
+                    ui.add(egui::DragValue::new(&mut params.max_variants).range(1..=100));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Tm window (°C):");
+                    ui.add(
+                        egui::DragValue::new(&mut params.min_tm)
+                            .range(0.0..=100.0)
+                            .speed(0.5),
+                    );
+                    ui.label("-");
+                    ui.add(
+                        egui::DragValue::new(&mut params.max_tm)
+                            .range(0.0..=100.0)
+                            .speed(0.5),
+                    );
+                    ui.separator();
+                    ui.label("Max Tm difference (°C):");
+                    ui.add(
+                        egui::DragValue::new(&mut params.max_tm_difference)
+                            .range(0.0..=50.0)
+                            .speed(0.1),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Min pair coverage (%):");
+                    ui.add(
+                        egui::DragValue::new(&mut params.min_pair_coverage)
+                            .range(0.0..=100.0)
+                            .speed(0.5),
+                    );
+                    ui.separator();
+                    ui.label("Max results:");
+                    ui.add(egui::DragValue::new(&mut params.max_results).range(1..=10000));
+                });
 
-                        if let Some(pointer_pos) = response.hover_pos() {
-                            if cell_rect.contains(pointer_pos) {
-                                hovered_cell = Some((length, pos));
-                                painter.rect_stroke(
-                                    cell_rect,
-                                    1.0,
-                                    egui::Stroke::new(1.5, egui::Color32::WHITE),
-                                    egui::StrokeKind::Outside,
-                                );
-                            }
-                        }
+                if params.min_amplicon_length > params.max_amplicon_length {
+                    params.max_amplicon_length = params.min_amplicon_length;
+                }
+                if params.min_tm > params.max_tm {
+                    params.max_tm = params.min_tm;
+                }
+                if self.pair_design_triplets {
+                    self.probe_params_ui(ui);
+                }
 
-                        if response.clicked() {
-                            if let Some(pointer_pos) = ui.ctx().pointer_latest_pos() {
-                                if cell_rect.contains(pointer_pos) {
-                                    clicked_cell = Some((length, pos));
-                                }
+                let has_designs = if self.pair_design_triplets {
+                    self.designed_triplets
+                        .as_ref()
+                        .is_some_and(|t| !t.is_empty())
+                } else {
+                    self.designed_pairs.as_ref().is_some_and(|p| !p.is_empty())
+                };
+                ui.horizontal(|ui| {
+                    if ui.button("Design").clicked() && self.load_all_window_variants() {
+                        self.pair_design_params.exclusivity_filter =
+                            self.active_exclusivity_filter();
+                        if let Some(ref results) = self.results {
+                            if self.pair_design_triplets {
+                                self.designed_triplets = Some(design_triplets(
+                                    results,
+                                    self.selected_references(),
+                                    &self.pair_design_params,
+                                    &self.probe_params,
+                                ));
+                            } else {
+                                self.designed_pairs = Some(design_pairs(
+                                    results,
+                                    self.selected_references(),
+                                    &self.pair_design_params,
+                                ));
                             }
                         }
                     }
-                }
-
-                // Handle tooltip
-                if let Some((length, pos)) = hovered_cell {
-                    if let Some(pr) = heatmap_data.get(&(length, pos)) {
-                        let mut tooltip_text = if pr.analysis.skipped {
-                            format!(
-                                "Position: {}, Length: {} bp\nSkipped: {}",
-                                pos + 1,
-                                length,
-                                pr.analysis
-                                    .skip_reason
-                                    .as_deref()
-                                    .unwrap_or("Unknown")
-                            )
-                        } else {
-                            format!(
-                                "Position: {}, Length: {} bp\nVariants needed: {}\nCoverage: {:.1}%\nMatched: {}/{}\nNo match: {}",
-                                pos + 1,
-                                length,
-                                pr.variants_needed,
-                                pr.analysis.coverage_at_threshold,
-                                pr.analysis.sequences_analyzed,
-                                pr.analysis.total_sequences,
-                                pr.analysis.no_match_count,
-                            )
-                        };
+                    let export_label = if self.pair_design_triplets {
+                        "Export Assay Sheet..."
+                    } else {
+                        "Export CSV..."
+                    };
+                    if ui
+                        .add_enabled(has_designs, egui::Button::new(export_label))
+                        .clicked()
+                    {
+                        export = true;
+                    }
+                });
 
-                        // Add exclusivity info to tooltip
-                        if let Some(ref excl) = pr.exclusivity {
-                            let eff = effective_min_mismatches(excl, self.diff_ignore_count);
-                            let mm_str = match eff {
-                                Some(mm) => format!("{}", mm),
-                                None => "all no-match".to_string(),
-                            };
-                            tooltip_text.push_str(&format!(
-                                "\nExclusivity: min mismatches = {} ({} sequences)",
-                                mm_str, excl.total_sequences
-                            ));
-                        }
+                ui.separator();
 
-                        response.clone().on_hover_text(tooltip_text);
-                    }
+                if self.pair_design_triplets {
+                    self.designed_triplets_ui(ui, &mut jump_to);
+                    return;
                 }
 
-                // Handle click
-                if let Some((length, pos)) = clicked_cell {
-                    self.selected_position = Some(pos);
-                    self.selected_length_for_detail = Some(length);
-                    self.show_detail_window = true;
+                let Some(ref pairs) = self.designed_pairs else {
+                    ui.colored_label(egui::Color32::GRAY, "Press Design to rank pairs.");
+                    return;
+                };
+                if pairs.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "No pairs satisfy the current constraints.",
+                    );
+                    return;
                 }
-            });
-
-        // Redirect vertical mouse wheel to horizontal scroll when hovering over heatmap
-        if let Some(hover_pos) = ui.ctx().pointer_hover_pos() {
-            if scroll_output.inner_rect.contains(hover_pos) {
-                let vertical_delta = ui.input(|i| i.smooth_scroll_delta.y);
-                if vertical_delta.abs() > 0.1 {
-                    let mut state = scroll_output.state;
-                    state.offset.x -= vertical_delta;
-                    state.offset.x = state.offset.x.clamp(
-                        0.0,
-                        (total_width - scroll_output.inner_rect.width()).max(0.0),
+                ui.label(format!("{} ranked pairs", pairs.len()));
+                if pairs.iter().any(|p| p.coverage_upper_bound) {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "The reference sequences of these results are not loaded; coverage \
+                         marked * is the lower of the two oligos' coverage.",
                     );
-                    state.store(ui.ctx(), scroll_output.id);
-                    ui.ctx().request_repaint();
                 }
-            }
-        }
 
-        // Legend
-        ui.add_space(5.0);
-        if self.differential_mode {
-            self.show_differential_legend(ui);
-        } else {
-            self.show_normal_legend(ui);
+                egui::ScrollArea::both()
+                    .id_salt("pair_design_scroll")
+                    .show(ui, |ui| {
+                        egui::Grid::new("pair_design_grid")
+                            .striped(true)
+                            .min_col_width(40.0)
+                            .show(ui, |ui| {
+                                ui.strong("#");
+                                ui.strong("Forward (5'-3')");
+                                ui.strong("Pos");
+                                ui.strong("Tm");
+                                ui.strong("Reverse (5'-3')");
+                                ui.strong("Pos");
+                                ui.strong("Tm");
+                                ui.strong("Amplicon");
+                                ui.strong("Coverage");
+                                ui.strong("ΔTm");
+                                ui.strong("Excl. MM");
+                                ui.end_row();
+
+                                for (i, pair) in pairs.iter().enumerate() {
+                                    ui.label(format!("{}", i + 1));
+                                    for oligo in [&pair.forward, &pair.reverse] {
+                                        ui.monospace(oligo.variants.join("\n"));
+                                        if ui
+                                            .small_button(format!("{}", oligo.position + 1))
+                                            .on_hover_text("Open window details")
+                                            .clicked()
+                                        {
+                                            jump_to = Some((oligo.length, oligo.position));
+                                        }
+                                        ui.label(format!("{:.1}", oligo.tm));
+                                    }
+                                    ui.label(format!("{} bp", pair.amplicon_length));
+                                    ui.label(format!(
+                                        "{:.1}%{}",
+                                        pair.coverage,
+                                        if pair.coverage_upper_bound { "*" } else { "" }
+                                    ));
+                                    ui.label(format!("{:.1}", pair.tm_difference));
+                                    ui.label(
+                                        pair.exclusivity_mismatches
+                                            .map_or("-".to_string(), |m| m.to_string()),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        self.show_pair_design_window = open;
+        if export && self.pair_design_triplets {
+            self.export_triplet_assay_sheet();
+        } else if export {
+            self.export_designed_pairs();
+        }
+        if let Some((length, pos)) = jump_to {
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
         }
     }
 
-    fn show_normal_legend(&self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            ui.label("Legend:");
-            ui.add_space(10.0);
-
-            let g = self.color_green_at;
-            let r = self.color_red_at;
-            let sample_points: Vec<(usize, String)> = if r <= g {
-                vec![(g, format!("<={}", g)), (g + 1, format!(">{}", g))]
+    fn export_designed_pairs(&mut self) {
+        let Some(ref pairs) = self.designed_pairs else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("designed_pairs.csv")
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(&path, designed_pairs_csv(pairs)) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
             } else {
-                let mid = (g + r) / 2;
-                let mut pts = vec![(g, format!("<={}", g))];
-                if mid > g && mid < r {
-                    pts.push((mid, format!("{}", mid)));
-                }
-                pts.push((r, format!(">={}", r)));
-                pts
-            };
-
-            let nm_ok = self.nomatch_ok_percent / 100.0;
-            let nm_bad = self.nomatch_bad_percent / 100.0;
-
-            for (count, label) in &sample_points {
-                let color = position_color(*count, 0.0, g, r, nm_ok, nm_bad);
-                let (rect, _) =
-                    ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
-                ui.painter().rect_filled(rect, 2.0, color);
-                ui.label(label);
-                ui.add_space(8.0);
+                self.save_error = None;
             }
+        }
+    }
 
+    fn probe_params_ui(&mut self, ui: &mut egui::Ui) {
+        let probe = &mut self.probe_params;
+        ui.horizontal(|ui| {
+            ui.label("Probe Tm window (°C):");
+            ui.add(
+                egui::DragValue::new(&mut probe.min_tm)
+                    .range(0.0..=100.0)
+                    .speed(0.5),
+            );
+            ui.label("-");
+            ui.add(
+                egui::DragValue::new(&mut probe.max_tm)
+                    .range(0.0..=100.0)
+                    .speed(0.5),
+            );
             ui.separator();
-
-            let mid_count = (g + r) / 2;
-            let mid_count = if mid_count < 1 { 1 } else { mid_count };
-            let nm_samples = [
-                (nm_ok, format!("{}%", self.nomatch_ok_percent as u32)),
-                (nm_bad, format!("{}%", self.nomatch_bad_percent as u32)),
-            ];
-            ui.label("No-match:");
-            for (nm_frac, label) in &nm_samples {
-                let color = position_color(mid_count, *nm_frac, g, r, nm_ok, nm_bad);
-                let (rect, _) =
-                    ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
-                ui.painter().rect_filled(rect, 2.0, color);
-                ui.label(label);
-                ui.add_space(4.0);
-            }
-
+            ui.label("Above primers by ≥");
+            ui.add(
+                egui::DragValue::new(&mut probe.min_tm_above_primers)
+                    .range(0.0..=30.0)
+                    .speed(0.1)
+                    .suffix(" °C"),
+            );
             ui.separator();
-            let (rect, _) =
-                ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
-            ui.painter()
-                .rect_filled(rect, 2.0, egui::Color32::from_rgb(40, 40, 40));
-            ui.label("skipped/no data");
+            ui.label("Max probe variants:");
+            ui.add(egui::DragValue::new(&mut probe.max_variants).range(1..=100));
         });
-    }
-
-    fn show_differential_legend(&self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            ui.label("Legend (Differential):");
-            ui.add_space(10.0);
-
-            // Exclusivity gradient samples (no darkening)
-            let dg = self.diff_green_at;
-            let dr = self.diff_red_at;
-
-            let sample_mms: Vec<(Option<u32>, String)> = if dg > dr {
-                vec![
-                    (Some(dg), format!(">={} mm", dg)),
-                    (Some((dg + dr) / 2), format!("{} mm", (dg + dr) / 2)),
-                    (Some(dr), format!("<={} mm", dr)),
-                ]
-            } else {
-                vec![
-                    (Some(dg), format!("{} mm", dg)),
-                    (Some(dr), format!("{} mm", dr)),
-                ]
-            };
-
-            for (mm_val, label) in &sample_mms {
-                let color = differential_position_color(
-                    *mm_val, 1, 0.0, dg, dr, self.color_green_at, self.color_red_at, 1.0, 1.0,
-                );
-                let (rect, _) =
-                    ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
-                ui.painter().rect_filled(rect, 2.0, color);
-                ui.label(label);
-                ui.add_space(4.0);
-            }
-
-            ui.separator();
-            ui.label("+ darkening from conservation");
-
+            ui.label("Spacing to primers:");
+            ui.add(egui::DragValue::new(&mut probe.min_spacing).range(0..=1000));
+            ui.label("-");
+            ui.add(egui::DragValue::new(&mut probe.max_spacing).range(0..=1000))
+                .on_hover_text("Most bases between the probe and the closer primer");
+            ui.label("bp");
             ui.separator();
-            let (rect, _) =
-                ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
-            ui.painter()
-                .rect_filled(rect, 2.0, egui::Color32::from_rgb(40, 40, 40));
-            ui.label("skipped/no data");
+            ui.checkbox(&mut probe.no_5prime_g, "No 5' G")
+                .on_hover_text(
+                    "Reject probes starting with G, which quenches the reporter dye; \
+                     such probes are tried on the other strand",
+                );
         });
+        if probe.min_tm > probe.max_tm {
+            probe.max_tm = probe.min_tm;
+        }
+        if probe.min_spacing > probe.max_spacing {
+            probe.max_spacing = probe.min_spacing;
+        }
     }
 
-    fn show_variant_detail_window(&mut self, ctx: &egui::Context) {
-        let Some(ref results) = self.results else {
-            self.show_detail_window = false;
+    fn designed_triplets_ui(&self, ui: &mut egui::Ui, jump_to: &mut Option<(u32, usize)>) {
+        let Some(ref triplets) = self.designed_triplets else {
+            ui.colored_label(egui::Color32::GRAY, "Press Design to rank assays.");
             return;
         };
-
-        let Some(length) = self.selected_length_for_detail else {
-            self.show_detail_window = false;
+        if triplets.is_empty() {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "No primer–probe–primer assays satisfy the current constraints.",
+            );
             return;
-        };
+        }
+        ui.label(format!("{} ranked assays", triplets.len()));
+        if triplets.iter().any(|t| t.coverage_upper_bound) {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "The reference sequences of these results are not loaded; coverage marked * \
+                 is the lowest single-oligo coverage.",
+            );
+        }
 
-        let Some(position) = self.selected_position else {
-            self.show_detail_window = false;
+        egui::ScrollArea::both()
+            .id_salt("triplet_design_scroll")
+            .show(ui, |ui| {
+                egui::Grid::new("triplet_design_grid")
+                    .striped(true)
+                    .min_col_width(40.0)
+                    .show(ui, |ui| {
+                        ui.strong("#");
+                        ui.strong("Forward (5'-3')");
+                        ui.strong("Pos");
+                        ui.strong("Tm");
+                        ui.strong("Probe (5'-3')");
+                        ui.strong("Pos");
+                        ui.strong("Tm");
+                        ui.strong("Reverse (5'-3')");
+                        ui.strong("Pos");
+                        ui.strong("Tm");
+                        ui.strong("Amplicon");
+                        ui.strong("Coverage");
+                        ui.strong("Primer ΔTm");
+                        ui.strong("Excl. MM");
+                        ui.end_row();
+
+                        for (i, t) in triplets.iter().enumerate() {
+                            ui.label(format!("{}", i + 1));
+                            let oligos =
+                                [(&t.forward, false), (&t.probe, true), (&t.reverse, false)];
+                            for (oligo, is_probe) in oligos {
+                                let label = ui.monospace(oligo.variants.join("\n"));
+                                if is_probe && t.probe_antisense {
+                                    label.on_hover_text("Antisense probe (reverse complement)");
+                                }
+                                if ui
+                                    .small_button(format!("{}", oligo.position + 1))
+                                    .on_hover_text("Open window details")
+                                    .clicked()
+                                {
+                                    *jump_to = Some((oligo.length, oligo.position));
+                                }
+                                ui.label(format!("{:.1}", oligo.tm));
+                            }
+                            ui.label(format!("{} bp", t.amplicon_length));
+                            ui.label(format!(
+                                "{:.1}%{}",
+                                t.coverage,
+                                if t.coverage_upper_bound { "*" } else { "" }
+                            ));
+                            ui.label(format!("{:.1}", t.primer_tm_difference));
+                            ui.label(
+                                t.exclusivity_mismatches
+                                    .map_or("-".to_string(), |m| m.to_string()),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn export_triplet_assay_sheet(&mut self) {
+        let Some(ref triplets) = self.designed_triplets else {
             return;
         };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("assay_sheet.csv")
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(&path, triplet_assay_sheet(triplets)) {
+                self.save_error = Some(format!("Failed to write file: {}", e));
+            } else {
+                self.save_error = None;
+            }
+        }
+    }
+}
 
-        let Some(length_result) = results.results_by_length.get(&length) else {
-            self.show_detail_window = false;
-            return;
+impl OligoscreenApp {
+    /// Inclusivity panel (references) followed by one panel per exclusivity file
+    /// for the selected completed job.
+    fn selected_job_panels(&self) -> Result<Vec<SequencePanel>, String> {
+        let cj = self
+            .selected_completed_job_index
+            .and_then(|i| self.completed_jobs.get(i))
+            .ok_or_else(|| "No job selected".to_string())?;
+        if cj.job.reference_data.is_empty() {
+            return Err(
+                "Panel sequences are not available for results loaded from file.".to_string(),
+            );
+        }
+
+        let to_bytes = |seqs: &[String]| -> Vec<Vec<u8>> {
+            seqs.iter().map(|s| s.as_bytes().to_vec()).collect()
         };
 
-        let Some(pos_result) = length_result
-            .positions
-            .iter()
-            .find(|p| p.position == position)
-        else {
-            self.show_detail_window = false;
+        let mut panels = vec![SequencePanel {
+            name: format!("Inclusivity: {}", cj.job.reference_file_name),
+            inclusivity: true,
+            sequences: to_bytes(&cj.job.reference_data.sequences),
+        }];
+
+        for group in cj.job.exclusivity_groups() {
+            panels.push(SequencePanel {
+                name: group.name,
+                inclusivity: false,
+                sequences: to_bytes(&group.data.sequences),
+            });
+        }
+
+        Ok(panels)
+    }
+
+    fn start_panel_matrix(&mut self) {
+        let Some(ref results) = self.results else {
             return;
         };
+        let panels = match self.selected_job_panels() {
+            Ok(p) => p,
+            Err(e) => {
+                self.panel_matrix_error = Some(e);
+                return;
+            }
+        };
 
-        let pos_result = pos_result.clone();
-        let coverage_threshold = results.params.coverage_threshold;
+        let filter = self.active_exclusivity_filter();
+        let candidates = select_top_candidates(
+            results,
+            self.panel_matrix_top_n,
+            self.diff_ignore_count,
+            filter.as_ref(),
+        );
+        let results = results.clone();
+        let (tx, rx) = channel();
+        self.panel_matrix_rx = Some(rx);
+        self.panel_matrix_error = None;
 
-        // Extract template oligo for display
-        let template_oligo = if position + length as usize <= results.template_sequence.len() {
-            &results.template_sequence[position..position + length as usize]
-        } else {
-            ""
-        };
-        let template_oligo = template_oligo.to_string();
+        thread::spawn(move || {
+            let rows = build_panel_matrix(&results, &candidates, &panels, &results.params.pairwise);
+            let _ = tx.send(PanelMatrix {
+                panel_is_inclusivity: panels.iter().map(|p| p.inclusivity).collect(),
+                panel_names: panels.into_iter().map(|p| p.name).collect(),
+                rows,
+            });
+        });
+    }
 
-        let show_reverse_complement = self.detail_show_reverse_complement;
-        let show_codon_spacing = self.detail_show_codon_spacing;
+    fn show_panel_matrix_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_panel_matrix_window;
+        let mut start = false;
+        let mut jump_to: Option<(u32, usize)> = None;
 
-        egui::Window::new(format!("Position {} Details", position + 1))
-            .open(&mut self.show_detail_window)
-            .default_width(650.0)
-            .default_height(500.0)
+        egui::Window::new("Inclusivity / Exclusivity Panel Matrix")
+            .open(&mut open)
+            .default_width(800.0)
+            .default_height(450.0)
             .show(ctx, |ui| {
+                ui.label(
+                    "Top candidate windows re-aligned against the inclusivity panel and each \
+                     exclusivity file separately.",
+                );
                 ui.horizontal(|ui| {
-                    ui.label(format!("Position: {}", position + 1));
+                    ui.label("Top candidates:");
+                    ui.add(egui::DragValue::new(&mut self.panel_matrix_top_n).range(1..=500));
                     ui.separator();
-                    ui.label(format!("Oligo length: {} bp", length));
+                    ui.radio_value(&mut self.panel_matrix_show_coverage, false, "Min mismatches");
+                    ui.radio_value(&mut self.panel_matrix_show_coverage, true, "Coverage (% hit)");
+                    ui.separator();
+                    let busy = self.panel_matrix_rx.is_some();
+                    if ui.add_enabled(!busy, egui::Button::new("Compute")).clicked() {
+                        start = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
                 });
 
-                // Template oligo display
-                if !template_oligo.is_empty() {
-                    let display_template = format_sequence_for_display(
-                        &template_oligo,
-                        show_reverse_complement,
-                        show_codon_spacing,
-                    );
-                    ui.horizontal(|ui| {
-                        ui.label("Template oligo:");
-                        ui.add(
-                            egui::Label::new(
-                                egui::RichText::new(&display_template)
-                                    .monospace()
-                                    .size(11.0)
-                                    .color(egui::Color32::from_rgb(100, 180, 255)),
-                            )
-                            .wrap_mode(egui::TextWrapMode::Extend),
-                        );
-                    });
-                }
-
-                ui.separator();
-
-                if pos_result.analysis.skipped {
-                    ui.colored_label(
-                        egui::Color32::YELLOW,
-                        format!(
-                            "This window was skipped: {}",
-                            pos_result
-                                .analysis
-                                .skip_reason
-                                .as_deref()
-                                .unwrap_or("Unknown reason")
-                        ),
-                    );
-                    return;
-                }
-
-                ui.label(format!(
-                    "Total references: {}",
-                    pos_result.analysis.total_sequences
-                ));
-                ui.label(format!(
-                    "Matched: {}",
-                    pos_result.analysis.sequences_analyzed
-                ));
-                if pos_result.analysis.no_match_count > 0 {
-                    ui.colored_label(
-                        egui::Color32::from_rgb(255, 180, 100),
-                        format!(
-                            "No match: {}/{} ({:.1}%)",
-                            pos_result.analysis.no_match_count,
-                            pos_result.analysis.total_sequences,
-                            (pos_result.analysis.no_match_count as f64
-                                / pos_result.analysis.total_sequences as f64)
-                                * 100.0
-                        ),
-                    );
-                }
-                ui.label(format!(
-                    "Variants needed for {:.0}% coverage: {}",
-                    coverage_threshold, pos_result.variants_needed
-                ));
-                ui.label(format!(
-                    "Coverage at threshold: {:.1}%",
-                    pos_result.analysis.coverage_at_threshold
-                ));
+                if let Some(ref err) = self.panel_matrix_error {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
 
                 ui.separator();
 
-                // Display options
-                ui.horizontal(|ui| {
-                    ui.heading("Variants");
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.checkbox(&mut self.detail_show_codon_spacing, "Codon spacing");
-                        ui.checkbox(
-                            &mut self.detail_show_reverse_complement,
-                            "Reverse complement",
-                        );
-                    });
-                });
+                let Some(ref matrix) = self.panel_matrix else {
+                    ui.colored_label(egui::Color32::GRAY, "Press Compute to build the matrix.");
+                    return;
+                };
+                if matrix.rows.is_empty() {
+                    ui.colored_label(egui::Color32::YELLOW, "No candidate windows available.");
+                    return;
+                }
 
-                egui::ScrollArea::vertical()
-                    .id_salt("detail_scroll")
-                    .max_height(250.0)
+                let show_coverage = self.panel_matrix_show_coverage;
+                let diff_green_at = self.diff_green_at;
+                let diff_red_at = self.diff_red_at;
+
+                egui::ScrollArea::both()
+                    .id_salt("panel_matrix_scroll")
                     .show(ui, |ui| {
-                        egui::Grid::new("variants_grid")
+                        egui::Grid::new("panel_matrix_grid")
                             .striped(true)
                             .min_col_width(50.0)
                             .show(ui, |ui| {
-                                ui.strong("#");
-                                ui.strong("Sequence");
-                                ui.strong("Count");
-                                ui.strong("Percentage");
-                                ui.strong("Cumulative");
+                                ui.strong("Pos");
+                                ui.strong("Len");
+                                ui.strong("Oligo");
+                                ui.strong("Variants");
+                                for name in &matrix.panel_names {
+                                    ui.strong(name);
+                                }
                                 ui.end_row();
 
-                                let mut cumulative = 0.0;
-                                for (i, variant) in
-                                    pos_result.analysis.variants.iter().enumerate()
-                                {
-                                    cumulative += variant.percentage;
-
-                                    let is_threshold = i + 1 == pos_result.variants_needed;
-
-                                    if is_threshold {
-                                        ui.colored_label(
-                                            egui::Color32::GREEN,
-                                            format!("{}", i + 1),
-                                        );
-                                    } else {
-                                        ui.label(format!("{}", i + 1));
+                                for row in &matrix.rows {
+                                    if ui
+                                        .small_button(format!("{}", row.position + 1))
+                                        .on_hover_text("Open window details")
+                                        .clicked()
+                                    {
+                                        jump_to = Some((row.length, row.position));
                                     }
-
-                                    let display_seq = format_sequence_for_display(
-                                        &variant.sequence,
-                                        show_reverse_complement,
-                                        show_codon_spacing,
-                                    );
-
-                                    ui.add(
-                                        egui::Label::new(
-                                            egui::RichText::new(&display_seq)
-                                                .monospace()
-                                                .size(11.0),
-                                        )
-                                        .wrap_mode(egui::TextWrapMode::Extend),
-                                    );
-
-                                    ui.label(format!("{}", variant.count));
-                                    ui.label(format!("{:.1}%", variant.percentage));
-
-                                    if is_threshold {
-                                        ui.colored_label(
-                                            egui::Color32::GREEN,
-                                            format!("{:.1}%", cumulative),
+                                    ui.label(format!("{}", row.length));
+                                    ui.monospace(&row.oligo);
+                                    ui.label(format!(
+                                        "{} ({:.1}%)",
+                                        row.variants_needed, row.coverage_at_threshold
+                                    ));
+
+                                    for (score, &is_inclusivity) in
+                                        row.scores.iter().zip(&matrix.panel_is_inclusivity)
+                                    {
+                                        let text = if show_coverage {
+                                            format!("{:.1}%", score.coverage())
+                                        } else {
+                                            match score.min_mismatches {
+                                                Some(mm) => format!("{} mm", mm),
+                                                None => "no hit".to_string(),
+                                            }
+                                        };
+                                        let color = panel_cell_color(
+                                            score,
+                                            is_inclusivity,
+                                            diff_green_at,
+                                            diff_red_at,
                                         );
-                                    } else {
-                                        ui.label(format!("{:.1}%", cumulative));
+                                        ui.colored_label(color, text).on_hover_text(format!(
+                                            "{}/{} sequences hit, min mismatches: {}",
+                                            score.hits,
+                                            score.total,
+                                            score
+                                                .min_mismatches
+                                                .map(|m| m.to_string())
+                                                .unwrap_or_else(|| "-".to_string())
+                                        ));
                                     }
-
-                                    ui.end_row();
-                                }
-
-                                // No match row
-                                if pos_result.analysis.no_match_count > 0 {
-                                    ui.label("");
-                                    ui.colored_label(
-                                        egui::Color32::from_rgb(255, 180, 100),
-                                        "No match",
-                                    );
-                                    ui.colored_label(
-                                        egui::Color32::from_rgb(255, 180, 100),
-                                        format!("{}", pos_result.analysis.no_match_count),
-                                    );
-                                    let no_match_pct = (pos_result.analysis.no_match_count
-                                        as f64
-                                        / pos_result.analysis.total_sequences as f64)
-                                        * 100.0;
-                                    ui.colored_label(
-                                        egui::Color32::from_rgb(255, 180, 100),
-                                        format!("{:.1}%", no_match_pct),
-                                    );
-                                    ui.label("");
                                     ui.end_row();
                                 }
                             });
+                    });
+            });
 
-                        // === Exclusivity Analysis Section ===
-                        if let Some(ref excl) = pos_result.exclusivity {
-                            ui.add_space(10.0);
-                            ui.separator();
-                            ui.heading("Exclusivity Analysis");
+        self.show_panel_matrix_window = open;
+        if start {
+            self.start_panel_matrix();
+        }
+        if let Some((length, pos)) = jump_to {
+            self.selected_length_for_detail = Some(length);
+            self.selected_position = Some(pos);
+            self.show_detail_window = true;
+        }
+    }
+}
 
-                            ui.label(format!(
-                                "Total exclusivity sequences: {}",
-                                excl.total_sequences
-                            ));
-                            if let Some(min_mm) = excl.min_mismatches {
-                                ui.label(format!("Minimum mismatches: {}", min_mm));
-                            } else {
-                                ui.colored_label(
-                                    egui::Color32::from_rgb(100, 200, 100),
-                                    "All exclusivity sequences: no match (fully specific)",
-                                );
-                            }
+/// Text color for a panel matrix cell: inclusivity is good when fully hit,
+/// exclusivity is good when mismatches are high (or nothing hits).
+fn panel_cell_color(
+    score: &PanelScore,
+    is_inclusivity: bool,
+    diff_green_at: u32,
+    diff_red_at: u32,
+) -> egui::Color32 {
+    let t = if is_inclusivity {
+        1.0 - score.coverage() / 100.0
+    } else {
+        exclusivity_gradient_t(score.min_mismatches, diff_green_at, diff_red_at)
+    };
+    let (r, g, b) = green_yellow_red_from_t(t);
+    egui::Color32::from_rgb(r as u8, g as u8, b as u8)
+}
 
-                            ui.add_space(5.0);
+/// Heatmap color for windows removed by the exclusivity candidate filter
+const FILTERED_CELL_COLOR: egui::Color32 = egui::Color32::from_rgb(70, 70, 85);
+
+/// Every window of the results by (length, position)
+fn window_lookup(
+    results: &ScreeningResults,
+) -> std::collections::HashMap<(u32, usize), &PositionResult> {
+    let mut map = std::collections::HashMap::new();
+    for (&length, lr) in &results.results_by_length {
+        for pr in &lr.positions {
+            map.insert((length, pr.position), pr);
+        }
+    }
+    map
+}
 
-                            egui::Grid::new("exclusivity_grid")
-                                .striped(true)
-                                .min_col_width(60.0)
-                                .show(ui, |ui| {
-                                    ui.strong("Mismatches");
-                                    ui.strong("Count");
-                                    ui.strong("Example");
-                                    ui.end_row();
+/// Draw legend items in one row
+fn show_legend(ui: &mut egui::Ui, items: Vec<LegendItem>) {
+    ui.horizontal(|ui| {
+        for item in items {
+            match item {
+                LegendItem::Swatch(color, label) => {
+                    let (rect, _) =
+                        ui.allocate_exact_size(egui::vec2(15.0, 15.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, color);
+                    ui.label(label);
+                    ui.add_space(6.0);
+                }
+                LegendItem::Text(text) => {
+                    ui.label(text);
+                    ui.add_space(4.0);
+                }
+                LegendItem::Separator => {
+                    ui.separator();
+                }
+            }
+        }
+    });
+}
 
-                                    for bucket in &excl.mismatch_histogram {
-                                        if bucket.mismatches == u32::MAX {
-                                            ui.colored_label(
-                                                egui::Color32::from_rgb(100, 200, 100),
-                                                "No match",
-                                            );
-                                        } else {
-                                            let color = if bucket.mismatches == 0 {
-                                                egui::Color32::from_rgb(255, 80, 80)
-                                            } else if bucket.mismatches <= 2 {
-                                                egui::Color32::from_rgb(255, 180, 100)
-                                            } else {
-                                                egui::Color32::LIGHT_GRAY
-                                            };
-                                            ui.colored_label(
-                                                color,
-                                                format!("{}", bucket.mismatches),
-                                            );
-                                        }
-                                        ui.label(format!("{}", bucket.count));
-                                        ui.label(&bucket.example_name);
-                                        ui.end_row();
-                                    }
-                                });
-                        }
-                    });
-            });
+/// Color of a difference in variants needed between two jobs: green where
+/// the first job needs fewer, yellow where equal, red where it needs more,
+/// saturating at `saturation` variants.
+fn delta_color(delta: f64, saturation: usize) -> egui::Color32 {
+    let t = 0.5 + 0.5 * (delta / saturation.max(1) as f64).clamp(-1.0, 1.0);
+    blend_toward_dark_red(green_yellow_red_from_t(t), 0.0)
+}
+
+/// Narrowest heatmap cell (in pixels) drawn per window; when zoomed out
+/// further, adjacent positions are drawn as one aggregated bin
+const MIN_CELL_WIDTH: f32 = 2.0;
+
+/// Oligo lengths shown in the heatmap before it scrolls vertically
+const HEATMAP_MAX_ROWS: usize = 10;
+
+/// Height of the heatmap minimap strip
+const MINIMAP_HEIGHT: f32 = 24.0;
+
+/// Height of one lane of the annotation track
+const ANNOTATION_LANE_HEIGHT: f32 = 14.0;
+
+/// Height of the per-base conservation track
+const CONSERVATION_TRACK_HEIGHT: f32 = 28.0;
+
+/// Line color of masked regions on the Analysis tab's template strip
+const MASK_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 120, 80);
+
+/// Distance between hatch lines (px)
+const HATCH_SPACING: f32 = 6.0;
+
+/// Diagonal hatching over `rect`. Lines sit on a grid fixed to the screen, so
+/// adjacent hatched rects of the same height continue each other's pattern.
+fn paint_hatched(painter: &egui::Painter, rect: egui::Rect, color: egui::Color32) {
+    let painter = painter.with_clip_rect(rect.intersect(painter.clip_rect()));
+    let stroke = egui::Stroke::new(1.0, color);
+    let mut x = ((rect.left() - rect.height()) / HATCH_SPACING).floor() * HATCH_SPACING;
+    while x < rect.right() {
+        painter.line_segment(
+            [
+                egui::pos2(x, rect.bottom()),
+                egui::pos2(x + rect.height(), rect.top()),
+            ],
+            stroke,
+        );
+        x += HATCH_SPACING;
     }
 }
 
-/// Calculate effective minimum mismatches after ignoring the best N sequences.
-fn effective_min_mismatches(
-    excl: &crate::analysis::ExclusivityResult,
+/// Annotation box color by feature type
+fn annotation_color(kind: &str) -> egui::Color32 {
+    match kind {
+        "gene" => egui::Color32::from_rgb(110, 160, 230),
+        "CDS" => egui::Color32::from_rgb(230, 180, 90),
+        "mRNA" | "transcript" | "exon" => egui::Color32::from_rgb(150, 200, 150),
+        _ => egui::Color32::from_rgb(170, 170, 170),
+    }
+}
+
+/// Widest image (in pixels) produced by "Copy whole heatmap"
+const MAX_HEATMAP_IMAGE_WIDTH: usize = 16_384;
+
+/// Labelled summary values of a window, as (label, value) rows
+type SummaryRows = Vec<(&'static str, String)>;
+
+/// Labelled summary values for one window, used by the compare views.
+/// The first row is the template oligo.
+fn window_summary_rows(
+    pr: &PositionResult,
+    length: u32,
+    template: &str,
     ignore_count: usize,
-) -> Option<u32> {
-    if ignore_count == 0 {
-        return excl.min_mismatches;
+) -> SummaryRows {
+    let oligo = template
+        .get(pr.position..pr.position + length as usize)
+        .unwrap_or("")
+        .to_string();
+    let score = match pr.analysis.score_stats {
+        Some(st) => format!("{} / {:.1} / {}", st.min, st.mean, st.max),
+        None => "-".to_string(),
+    };
+    let identity = match pr.analysis.identity_stats {
+        Some(st) => format!("{:.1}% / {:.1}%", st.mean, st.min),
+        None => "-".to_string(),
+    };
+    let mismatches = match pr.analysis.mismatch_stats {
+        Some(st) => format!("{:.2} / {}", st.mean, st.max),
+        None => "-".to_string(),
+    };
+    let exclusivity = match &pr.exclusivity {
+        Some(e) => match effective_min_mismatches(e, ignore_count) {
+            Some(mm) => mm.to_string(),
+            None => "all no-match".to_string(),
+        },
+        None => "-".to_string(),
+    };
+
+    vec![
+        ("Template oligo", oligo),
+        ("Variants needed", pr.variants_needed.to_string()),
+        (
+            "Coverage at threshold",
+            format!("{:.1}%", pr.analysis.coverage_at_threshold),
+        ),
+        (
+            "Matched / total",
+            format!(
+                "{}/{}",
+                pr.analysis.sequences_analyzed, pr.analysis.total_sequences
+            ),
+        ),
+        ("No match", pr.analysis.no_match_count.to_string()),
+        ("Alignment score (min/mean/max)", score),
+        ("Identity (mean/min)", identity),
+        ("Mismatches (mean/max)", mismatches),
+        ("Concrete sequences needed", pr.concrete_sequences_needed().to_string()),
+        ("Exclusivity min mismatches", exclusivity),
+    ]
+}
+
+/// Plot cumulative coverage (%) against variant rank, marking the coverage
+/// threshold and the number of variants needed to reach it.
+fn draw_coverage_curve(
+    ui: &mut egui::Ui,
+    variants: &[Variant],
+    variants_needed: usize,
+    threshold: f64,
+) {
+    if variants.is_empty() {
+        ui.label("No variants.");
+        return;
+    }
+
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width().min(500.0), 140.0),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    let plot = egui::Rect::from_min_max(
+        rect.min + egui::vec2(35.0, 5.0),
+        rect.max - egui::vec2(5.0, 18.0),
+    );
+    let axis_color = egui::Color32::GRAY;
+    let font = egui::FontId::proportional(10.0);
+
+    let n = variants.len();
+    let to_screen = |rank: f64, pct: f64| {
+        egui::pos2(
+            plot.left() + (rank / n as f64) as f32 * plot.width(),
+            plot.bottom() - (pct / 100.0).clamp(0.0, 1.0) as f32 * plot.height(),
+        )
+    };
+
+    painter.rect_stroke(plot, 0.0, egui::Stroke::new(1.0, axis_color), egui::StrokeKind::Inside);
+    for pct in [0.0, 50.0, 100.0] {
+        painter.text(
+            to_screen(0.0, pct) - egui::vec2(4.0, 0.0),
+            egui::Align2::RIGHT_CENTER,
+            format!("{:.0}%", pct),
+            font.clone(),
+            axis_color,
+        );
+    }
+    for rank in [1, variants_needed.max(1), n] {
+        painter.text(
+            to_screen(rank as f64, 0.0) + egui::vec2(0.0, 3.0),
+            egui::Align2::CENTER_TOP,
+            format!("{}", rank),
+            font.clone(),
+            axis_color,
+        );
     }
 
-    let mut remaining_ignore = ignore_count;
-    for bucket in &excl.mismatch_histogram {
-        if bucket.mismatches == u32::MAX {
-            // No-match bucket — these are already "infinite", skip them
-            continue;
+    // Threshold line and variants-needed marker
+    let threshold_y = to_screen(0.0, threshold).y;
+    painter.hline(
+        plot.x_range(),
+        threshold_y,
+        egui::Stroke::new(1.0, egui::Color32::from_rgb(220, 200, 0)),
+    );
+    if variants_needed > 0 {
+        painter.vline(
+            to_screen(variants_needed as f64, 0.0).x,
+            plot.y_range(),
+            egui::Stroke::new(1.0, egui::Color32::GREEN),
+        );
+    }
+
+    let mut cumulative = 0.0;
+    let mut points = vec![to_screen(0.0, 0.0)];
+    let mut cumulative_by_rank = Vec::with_capacity(n);
+    for (i, variant) in variants.iter().enumerate() {
+        cumulative += variant.percentage;
+        cumulative_by_rank.push(cumulative);
+        points.push(to_screen((i + 1) as f64, cumulative));
+    }
+    painter.add(egui::Shape::line(
+        points.clone(),
+        egui::Stroke::new(1.5, egui::Color32::from_rgb(100, 180, 255)),
+    ));
+    for point in &points[1..] {
+        painter.circle_filled(*point, 2.0, egui::Color32::from_rgb(100, 180, 255));
+    }
+
+    if let Some(pointer) = response.hover_pos() {
+        let rank = (((pointer.x - plot.left()) / plot.width()) * n as f32).round() as usize;
+        let rank = rank.clamp(1, n);
+        let gain = cumulative_by_rank[rank - 1]
+            - if rank > 1 { cumulative_by_rank[rank - 2] } else { 0.0 };
+        response.on_hover_text(format!(
+            "{} variant(s): {:.1}% coverage (+{:.1}%)",
+            rank,
+            cumulative_by_rank[rank - 1],
+            gain
+        ));
+    }
+}
+
+/// Subsequence fuzzy match of `query` against `label` (case-insensitive).
+/// Higher scores for consecutive matches and matches at word starts; None if
+/// the query characters don't all appear in order.
+fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut prev_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = next + label[next..].iter().position(|&c| c == q)?;
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == idx) {
+            score += 5;
         }
-        if bucket.count <= remaining_ignore {
-            remaining_ignore -= bucket.count;
-        } else {
-            // This bucket has sequences remaining after ignoring
-            return Some(bucket.mismatches);
+        if idx == 0 || !label[idx - 1].is_alphanumeric() {
+            score += 3;
         }
+        prev_match = Some(idx);
+        next = idx + 1;
+    }
+    // Prefer shorter labels among equal matches
+    Some(score * 100 - label.len() as i32)
+}
+
+/// Audit trail entries, newest first; submissions expand to their parameters.
+fn show_audit_trail(ui: &mut egui::Ui, entries: &[AuditEntry], id_salt: &str) {
+    if entries.is_empty() {
+        ui.colored_label(egui::Color32::GRAY, "No changes recorded yet.");
+        return;
     }
+    egui::ScrollArea::vertical()
+        .id_salt(id_salt)
+        .max_height(200.0)
+        .show(ui, |ui| {
+            for (i, entry) in entries.iter().enumerate().rev() {
+                match &entry.event {
+                    AuditEvent::JobSubmitted { params, .. } => {
+                        egui::CollapsingHeader::new(entry.summary())
+                            .id_salt((id_salt, i))
+                            .show(ui, |ui| {
+                                ui.monospace(
+                                    serde_json::to_string_pretty(params).unwrap_or_default(),
+                                );
+                            });
+                    }
+                    AuditEvent::ParamsChanged { .. } => {
+                        ui.label(entry.summary());
+                    }
+                }
+            }
+        });
+}
+
+/// Estimate a job's runtime from its window count and a quick alignment
+/// calibration on its own sequences, summed over the templates of a
+/// multi-template job.
+fn estimate_job_runtime(
+    template_data: &TemplateData,
+    batch_templates: &[TemplateData],
+    reference_data: &ReferenceData,
+    exclusivity_data: Option<&ReferenceData>,
+    params: &AnalysisParams,
+) -> RuntimeEstimate {
+    let calibration_seq = reference_data
+        .sequences
+        .first()
+        .or_else(|| exclusivity_data.and_then(|d| d.sequences.first()));
+    let ns_per_cell = calibrate_ns_per_cell(
+        template_data.sequence.as_bytes(),
+        calibration_seq.map_or(&[][..], |s| s.as_bytes()),
+        params.max_oligo_length as usize,
+        &params.pairwise,
+    );
+    let estimate = |template: &TemplateData| {
+        let template_length = template.sequence.len();
+        let sequence_lengths: Vec<usize> = reference_data
+            .sequences
+            .iter()
+            .map(|s| match params.reference_trim_flank {
+                Some(flank) => trimmed_length_estimate(template_length, s.len(), flank),
+                None => s.len(),
+            })
+            .chain(
+                exclusivity_data
+                    .iter()
+                    .flat_map(|d| d.sequences.iter().map(|s| s.len())),
+            )
+            .collect();
+        estimate_runtime(template_length, &sequence_lengths, params, ns_per_cell)
+    };
+    batch_templates
+        .iter()
+        .map(&estimate)
+        .fold(estimate(template_data), |total, next| RuntimeEstimate {
+            windows: total.windows + next.windows,
+            alignments: total.alignments + next.alignments,
+            cpu_seconds: total.cpu_seconds + next.cpu_seconds,
+        })
+}
 
-    // All matched sequences were ignored — effectively all are no-match
-    None
+/// One row of an alignment colored by column kind, with the columns outside
+/// the aligned region underlined.
+fn alignment_row(text: &str, view: &AlignmentView) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    for (i, (c, kind)) in text.chars().zip(&view.columns).enumerate() {
+        let color = match kind {
+            ColumnKind::Match => egui::Color32::from_rgb(100, 200, 100),
+            ColumnKind::Mismatch => egui::Color32::from_rgb(255, 80, 80),
+            ColumnKind::Gap => egui::Color32::from_rgb(255, 180, 100),
+        };
+        let mut format = egui::TextFormat {
+            font_id: egui::FontId::monospace(13.0),
+            color,
+            ..Default::default()
+        };
+        if !view.aligned_columns.contains(&i) {
+            format.underline = egui::Stroke::new(1.0, color);
+        }
+        job.append(&c.to_string(), 0.0, format);
+    }
+    job
 }
 
 /// Format a sequence for display with optional transformations
-fn format_sequence_for_display(seq: &str, reverse_comp: bool, codon_spacing: bool) -> String {
+fn format_sequence_for_display(
+    seq: &str,
+    reverse_comp: bool,
+    codon_spacing: bool,
+    rna: bool,
+) -> String {
     let mut result = if reverse_comp {
         reverse_complement(seq)
     } else {
         seq.to_string()
     };
 
+    if rna {
+        result = result.replace('T', "U").replace('t', "u");
+    }
+
     if codon_spacing {
         result = add_codon_spacing(&result);
     }
@@ -2485,16 +12201,273 @@ fn position_color(
         return egui::Color32::from_rgb(40, 40, 40);
     }
 
-    let (base_r, base_g, base_b) =
-        green_yellow_red_gradient(variant_count, green_at, red_at);
+    let base = green_yellow_red_gradient(variant_count, green_at, red_at);
+    darken_for_no_match(base, no_match_fraction, nomatch_ok, nomatch_bad)
+}
 
-    // No-match darkening
-    let dark_red = (100.0f64, 20.0f64, 20.0f64);
+/// Get color for a position colored by percent identity (normal mode).
+/// Green at or above `green_at`% identity, red at or below `red_at`%.
+fn identity_position_color(
+    identity: Option<f64>,
+    no_match_fraction: f64,
+    green_at: f64,
+    red_at: f64,
+    nomatch_ok: f64,
+    nomatch_bad: f64,
+) -> egui::Color32 {
+    let Some(identity) = identity else {
+        return egui::Color32::from_rgb(40, 40, 40);
+    };
+
+    let base = green_yellow_red_from_t(identity_t(identity, green_at, red_at));
+    darken_for_no_match(base, no_match_fraction, nomatch_ok, nomatch_bad)
+}
+
+/// Gradient position for percent identity: 0 at or above `green_at`, 1 at
+/// or below `red_at`.
+fn identity_t(identity: f64, green_at: f64, red_at: f64) -> f64 {
+    if green_at <= red_at {
+        if identity >= green_at { 0.0 } else { 1.0 }
+    } else {
+        ((green_at - identity) / (green_at - red_at)).clamp(0.0, 1.0)
+    }
+}
+
+/// Get color for a position colored by mean mismatches to the template oligo
+/// (normal mode). Green at or below `green_at`, red at or above `red_at`.
+fn mismatch_position_color(
+    mean_mismatches: Option<f64>,
+    no_match_fraction: f64,
+    green_at: f64,
+    red_at: f64,
+    nomatch_ok: f64,
+    nomatch_bad: f64,
+) -> egui::Color32 {
+    let Some(mean) = mean_mismatches else {
+        return egui::Color32::from_rgb(40, 40, 40);
+    };
+
+    let base = green_yellow_red_from_t(mismatch_t(mean, green_at, red_at));
+    darken_for_no_match(base, no_match_fraction, nomatch_ok, nomatch_bad)
+}
+
+/// Gradient position for mean mismatches: 0 at or below `green_at`, 1 at or
+/// above `red_at`.
+fn mismatch_t(mean: f64, green_at: f64, red_at: f64) -> f64 {
+    if red_at <= green_at {
+        if mean <= green_at { 0.0 } else { 1.0 }
+    } else {
+        ((mean - green_at) / (red_at - green_at)).clamp(0.0, 1.0)
+    }
+}
+
+/// Reproducibility record for a completed job. The parameters come from the
+/// results, so they include the thread count actually used. Jobs loaded from
+/// a results file no longer have their input sequences, so only names and
+/// counts are recorded for them.
+fn reproducibility_record(cj: &CompletedJob) -> ReproducibilityRecord {
+    let job = &cj.job;
+    let params = cj.results.params.clone();
+    if job.reference_data.is_empty() && job.exclusivity_data.is_none() {
+        return ReproducibilityRecord::new(
+            params,
+            InputFileRecord::without_sequences(&job.template_file_name, 1),
+            InputFileRecord::without_sequences(&job.reference_file_name, job.reference_count),
+            Vec::new(),
+        );
+    }
+
+    let template = InputFileRecord::new(
+        &job.template_file_name,
+        std::slice::from_ref(&job.template_data.name),
+        std::slice::from_ref(&job.template_data.sequence),
+    );
+    let references = InputFileRecord::new(
+        &job.reference_file_name,
+        &job.reference_data.names,
+        &job.reference_data.sequences,
+    );
+    let mut exclusivity = Vec::new();
+    if let Some(ref excl) = job.exclusivity_data {
+        let mut offset = 0;
+        for (name, &size) in job.exclusivity_file_names.iter().zip(&job.exclusivity_panel_sizes) {
+            let end = (offset + size).min(excl.sequences.len());
+            exclusivity.push(InputFileRecord::new(
+                name,
+                &excl.names[offset..end],
+                &excl.sequences[offset..end],
+            ));
+            offset = end;
+        }
+    }
+    ReproducibilityRecord::new(params, template, references, exclusivity)
+}
+
+/// Write a job's results as `<template file>_<job id>.json` (or `.osrz`) in
+/// `folder`, or into the folder's results database.
+fn write_job_results(
+    results: &ScreeningResults,
+    folder: &str,
+    job: &WorklistJob,
+) -> Result<(), String> {
+    if job.store_in_database {
+        store_results(results, &database_path(folder), &job_database_name(job))
+    } else {
+        write_results_file(results, &job_results_path(folder, job), |_| {})
+    }
+}
+
+/// The results database of an output folder.
+fn database_path(folder: &str) -> std::path::PathBuf {
+    std::path::Path::new(folder).join(RESULTS_DATABASE_FILE)
+}
+
+/// Name a job's results are stored under in the results database
+fn job_database_name(job: &WorklistJob) -> String {
+    format!("{} #{}", job.template_file_name, job.id)
+}
+
+/// Add results to the results database at `path`.
+fn store_results(
+    results: &ScreeningResults,
+    path: &std::path::Path,
+    name: &str,
+) -> Result<(), String> {
+    ResultsDatabase::open(path)
+        .and_then(|db| db.insert(name, results))
+        .map(|_| ())
+        .map_err(|e| format!("failed: {}", e))
+}
+
+/// Auto-save path of a job's results in `folder`.
+fn job_results_path(folder: &str, job: &WorklistJob) -> std::path::PathBuf {
+    let sanitized_name: String = job
+        .template_file_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let extension = if job.binary_results {
+        BINARY_RESULTS_EXTENSION
+    } else {
+        "json"
+    };
+    let file_name = format!("{}_{}.{}", sanitized_name, job.id, extension);
+    std::path::Path::new(folder).join(file_name)
+}
+
+/// Write results as compact JSON, or compressed binary for an `.osrz` path,
+/// reporting bytes written to `progress`.
+fn write_results_file(
+    results: &ScreeningResults,
+    path: &std::path::Path,
+    progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("failed: {}", e))?;
+    if is_binary_results_path(path) {
+        write_results_binary(results, file, progress)
+    } else {
+        write_results_json(results, file, progress)
+    }
+    .map_err(|e| format!("failed: {}", e))
+}
+
+/// Get color for a position from a metric's badness in 0..=1 (normal mode),
+/// as computed by its color scale. Green at 0, red at 1.
+fn scaled_position_color(
+    badness: Option<f64>,
+    no_match_fraction: f64,
+    nomatch_ok: f64,
+    nomatch_bad: f64,
+) -> egui::Color32 {
+    let Some(t) = badness else {
+        return egui::Color32::from_rgb(40, 40, 40);
+    };
+    let base = green_yellow_red_from_t(t);
+    darken_for_no_match(base, no_match_fraction, nomatch_ok, nomatch_bad)
+}
+
+/// Controls for a green/red color range.
+fn metric_range_controls(ui: &mut egui::Ui, range: &mut MetricRange, unit: &str, speed: f64) {
+    ui.label("Color range - Green at:");
+    ui.add(egui::DragValue::new(&mut range.green_at).speed(speed));
+    ui.label(format!("{}, Red at:", unit.trim()));
+    ui.add(egui::DragValue::new(&mut range.red_at).speed(speed));
+    ui.label(unit.trim());
+}
+
+/// Controls for a target band: green inside, red `tolerance` outside it.
+fn target_band_controls(ui: &mut egui::Ui, band: &mut TargetBand, unit: &str) {
+    ui.label("Target:");
+    ui.add(egui::DragValue::new(&mut band.min).speed(0.5));
+    ui.label("to");
+    ui.add(egui::DragValue::new(&mut band.max).speed(0.5));
+    ui.label(format!("{}, Red at ±", unit.trim()));
+    ui.add(
+        egui::DragValue::new(&mut band.tolerance)
+            .range(0.0..=100.0)
+            .speed(0.5),
+    );
+    ui.label(format!("{} outside", unit.trim()));
+    if band.max < band.min {
+        band.max = band.min;
+    }
+}
+
+/// Controls for the ΔG thresholds structures are flagged at.
+fn structure_threshold_controls(ui: &mut egui::Ui, thresholds: &mut StructureThresholds) {
+    ui.label("Flag hairpin ΔG ≤");
+    ui.add(
+        egui::DragValue::new(&mut thresholds.hairpin_dg)
+            .range(-30.0..=0.0)
+            .speed(0.1),
+    );
+    ui.label("dimer ΔG ≤");
+    ui.add(
+        egui::DragValue::new(&mut thresholds.dimer_dg)
+            .range(-30.0..=0.0)
+            .speed(0.1),
+    );
+    ui.label("kcal/mol (37 °C)");
+}
+
+/// A structure check as a warning sign or "ok", with the ΔGs on hover.
+fn structure_label(ui: &mut egui::Ui, check: &StructureCheck, thresholds: &StructureThresholds) {
+    let details = format!(
+        "Hairpin ΔG {:.1}, self-dimer ΔG {:.1} kcal/mol",
+        check.hairpin_dg, check.self_dimer_dg
+    );
+    let warnings = check.warnings(thresholds);
+    if warnings.is_empty() {
+        ui.label("ok").on_hover_text(details);
+    } else {
+        ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warnings.join(", ")))
+            .on_hover_text(details);
+    }
+}
+
+/// Blend a base color toward dark red as the no-match fraction rises.
+fn darken_for_no_match(
+    (base_r, base_g, base_b): (f64, f64, f64),
+    no_match_fraction: f64,
+    nomatch_ok: f64,
+    nomatch_bad: f64,
+) -> egui::Color32 {
     let nm_t = ramp(no_match_fraction, nomatch_ok, nomatch_bad);
+    blend_toward_dark_red((base_r, base_g, base_b), nm_t)
+}
 
-    let r = (base_r * (1.0 - nm_t) + dark_red.0 * nm_t).clamp(0.0, 255.0) as u8;
-    let g = (base_g * (1.0 - nm_t) + dark_red.1 * nm_t).clamp(0.0, 255.0) as u8;
-    let b = (base_b * (1.0 - nm_t) + dark_red.2 * nm_t).clamp(0.0, 255.0) as u8;
+/// Blend a base color toward dark red by `amount` (0..=1).
+fn blend_toward_dark_red((base_r, base_g, base_b): (f64, f64, f64), amount: f64) -> egui::Color32 {
+    let dark_red = (100.0f64, 20.0f64, 20.0f64);
+    let r = (base_r * (1.0 - amount) + dark_red.0 * amount).clamp(0.0, 255.0) as u8;
+    let g = (base_g * (1.0 - amount) + dark_red.1 * amount).clamp(0.0, 255.0) as u8;
+    let b = (base_b * (1.0 - amount) + dark_red.2 * amount).clamp(0.0, 255.0) as u8;
 
     egui::Color32::from_rgb(r, g, b)
 }
@@ -2503,6 +12476,7 @@ fn position_color(
 ///
 /// Base color: exclusivity min mismatches gradient (green=high=specific, red=low=similar).
 /// Darkening: conservation metrics (variant count + no-match %) blend toward dark red.
+#[allow(clippy::too_many_arguments)]
 fn differential_position_color(
     min_mismatches: Option<u32>,
     variant_count: usize,
@@ -2528,7 +12502,20 @@ fn differential_position_color(
 
     // Base color from exclusivity: green→yellow→red gradient
     // None = all no-match = fully specific = best = green (t=0)
-    let t = match min_mismatches {
+    let t = exclusivity_gradient_t(min_mismatches, diff_green_at, diff_red_at);
+
+    // Blend base color toward dark red by the darkening factor
+    blend_toward_dark_red(green_yellow_red_from_t(t), darkening)
+}
+
+/// Gradient position (0 = green, 1 = red) for an exclusivity min-mismatch value.
+/// None = all no-match = fully specific = best = green (t=0).
+fn exclusivity_gradient_t(
+    min_mismatches: Option<u32>,
+    diff_green_at: u32,
+    diff_red_at: u32,
+) -> f64 {
+    match min_mismatches {
         None => 0.0,
         Some(mm) => {
             if diff_green_at <= diff_red_at {
@@ -2541,17 +12528,7 @@ fn differential_position_color(
                 (diff_green_at - mm) as f64 / (diff_green_at - diff_red_at) as f64
             }
         }
-    };
-
-    let (base_r, base_g, base_b) = green_yellow_red_from_t(t);
-
-    // Blend base color toward dark red by the darkening factor
-    let dark_red = (100.0f64, 20.0f64, 20.0f64);
-    let r = (base_r * (1.0 - darkening) + dark_red.0 * darkening).clamp(0.0, 255.0) as u8;
-    let g = (base_g * (1.0 - darkening) + dark_red.1 * darkening).clamp(0.0, 255.0) as u8;
-    let b = (base_b * (1.0 - darkening) + dark_red.2 * darkening).clamp(0.0, 255.0) as u8;
-
-    egui::Color32::from_rgb(r, g, b)
+    }
 }
 
 /// 3-stop gradient: green → yellow → red. Returns (r, g, b) as f64.
@@ -2596,12 +12573,6 @@ fn green_yellow_red_from_t(t: f64) -> (f64, f64, f64) {
     }
 }
 
-/// Convert t=0 to green color (for "all no-match" case in differential mode).
-fn green_yellow_red_to_color(t: f64) -> egui::Color32 {
-    let (r, g, b) = green_yellow_red_from_t(t);
-    egui::Color32::from_rgb(r as u8, g as u8, b as u8)
-}
-
 /// Linear ramp: 0 at low, 1 at high, clamped.
 fn ramp(value: f64, low: f64, high: f64) -> f64 {
     let v = value.clamp(0.0, 1.0);
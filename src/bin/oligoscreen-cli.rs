@@ -0,0 +1,257 @@
+//! Headless screening for machines without a display
+//!
+//! Runs one screening job from FASTA files and writes the results file that
+//! the GUI loads with *Load Results from File*. Parameters start from the
+//! site/user defaults files, then a partial `AnalysisParams` JSON file
+//! (`--params`), then individual flags. Progress goes to stderr.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Instant;
+
+use mimalloc::MiMalloc;
+
+#[global_allocator]
+static GLOBAL: MiMalloc = MiMalloc;
+
+use oligoscreen_differential::analysis::{
+    AnalysisParams, ExclusivityGroup, ProgressInterval, ReferenceData, TemplateAmbiguityPolicy,
+    ThreadCount, default_config_paths, is_binary_results_path, load_defaults,
+    params_with_overrides, parse_template_fasta, read_fasta_file, read_reference_fasta,
+    run_screening, write_results_binary, write_results_json,
+};
+
+const USAGE: &str = "\
+Usage: oligoscreen-cli --template FILE --references FILE --output FILE [options]
+
+Inputs and output:
+  --template FILE        Template FASTA (one sequence)
+  --references FILE      Reference FASTA (not needed with exclusivity_only)
+  --exclusivity FILE     Exclusivity FASTA; repeat for several groups, one per file
+  --output FILE          Results file to write: JSON, or compressed binary
+                         when it ends in .osrz
+  FASTA files may be gzip- or bgzip-compressed.
+
+Parameters (applied in this order, later wins):
+  --params FILE          Partial AnalysisParams JSON, e.g.
+                         {\"min_oligo_length\": 20, \"pairwise\": {\"max_mismatches\": 4}}
+  --min-length N         Minimum oligo length
+  --max-length N         Maximum oligo length
+  --resolution N         Step between analyzed positions
+  --refine N             Re-screen at step 1 around windows of the coarse scan
+                         needing N or fewer variants
+  --keep-variants N      Store only the N most frequent variants per window
+                         (0 = metrics only)
+  --region-start POS     Screen only windows starting at or after this
+                         template position (1-based)
+  --region-end POS       Screen only windows ending at or before this
+                         template position (1-based, inclusive)
+  --coverage PCT         Coverage threshold (%)
+  --max-mismatches N     Maximum mismatches for a match
+  --expand-template-codes
+                         Screen windows over IUPAC codes in a consensus
+                         template as their concrete oligos (default: skip)
+  --threads N            Worker threads (default: all cores)
+  --progress-ms N        Milliseconds between progress lines (default 2000)
+
+  -h, --help             Show this help";
+
+struct CliArgs {
+    template: PathBuf,
+    references: Option<PathBuf>,
+    exclusivity: Vec<PathBuf>,
+    output: PathBuf,
+    params: AnalysisParams,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        eprintln!("{}", USAGE);
+        return if args.is_empty() {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+    match parse_args(&args).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let loaded = load_defaults(&default_config_paths());
+    for error in &loaded.errors {
+        eprintln!("warning: ignored defaults file {}", error);
+    }
+    let mut params = loaded.config.analysis;
+    params.progress_interval = ProgressInterval::Millis(2000);
+
+    let mut template = None;
+    let mut references = None;
+    let mut exclusivity = Vec::new();
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("missing value for {}", flag))
+        };
+        match flag.as_str() {
+            "--template" => template = Some(PathBuf::from(value()?)),
+            "--references" => references = Some(PathBuf::from(value()?)),
+            "--exclusivity" => exclusivity.push(PathBuf::from(value()?)),
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--params" => {
+                let path = value()?;
+                let json = std::fs::read_to_string(path)
+                    .map_err(|e| format!("failed to read {}: {}", path, e))?;
+                params = params_with_overrides(&params, &json)
+                    .map_err(|e| format!("invalid parameters in {}: {}", path, e))?;
+            }
+            "--min-length" => params.min_oligo_length = parse_number(flag, value()?)?,
+            "--max-length" => params.max_oligo_length = parse_number(flag, value()?)?,
+            "--resolution" => params.resolution = parse_number(flag, value()?)?,
+            "--refine" => params.refine_max_variants = Some(parse_number(flag, value()?)?),
+            "--keep-variants" => params.keep_variants = Some(parse_number(flag, value()?)?),
+            "--region-start" => {
+                let pos: usize = parse_number(flag, value()?)?;
+                if pos == 0 {
+                    return Err("--region-start is 1-based".to_string());
+                }
+                params.region_start = Some(pos - 1);
+            }
+            "--region-end" => params.region_end = Some(parse_number(flag, value()?)?),
+            "--coverage" => params.coverage_threshold = parse_number(flag, value()?)?,
+            "--max-mismatches" => params.pairwise.max_mismatches = parse_number(flag, value()?)?,
+            "--expand-template-codes" => {
+                params.template_ambiguity = TemplateAmbiguityPolicy::Expand
+            }
+            "--threads" => params.thread_count = ThreadCount::Fixed(parse_number(flag, value()?)?),
+            "--progress-ms" => {
+                params.progress_interval = ProgressInterval::Millis(parse_number(flag, value()?)?)
+            }
+            other => return Err(format!("unknown argument {} (see --help)", other)),
+        }
+    }
+
+    if params.min_oligo_length == 0 || params.min_oligo_length > params.max_oligo_length {
+        return Err(format!(
+            "invalid oligo length range {}-{}",
+            params.min_oligo_length, params.max_oligo_length
+        ));
+    }
+    if params.resolution == 0 {
+        return Err("resolution must be at least 1".to_string());
+    }
+    if let (Some(start), Some(end)) = (params.region_start, params.region_end)
+        && start >= end
+    {
+        return Err(format!("invalid template region {}-{}", start + 1, end));
+    }
+    if params.exclusivity_only && exclusivity.is_empty() {
+        return Err("exclusivity_only needs at least one --exclusivity file".to_string());
+    }
+    Ok(CliArgs {
+        template: template.ok_or("--template is required")?,
+        // Exclusivity-only screens ignore references, as in the GUI
+        references: match references {
+            _ if params.exclusivity_only => None,
+            Some(path) => Some(path),
+            None => return Err("--references is required".to_string()),
+        },
+        exclusivity,
+        output: output.ok_or("--output is required")?,
+        params,
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn read_fasta(path: &Path) -> Result<String, String> {
+    read_fasta_file(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))
+}
+
+/// Streamed, so multi-GB panels are not held in memory as text
+fn read_references(path: &Path) -> Result<ReferenceData, String> {
+    read_reference_fasta(path, |_, _| {}).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn run(args: CliArgs) -> Result<(), String> {
+    // Created up front so a bad output path fails before the screen, not after
+    let file = std::fs::File::create(&args.output)
+        .map_err(|e| format!("failed to create {}: {}", args.output.display(), e))?;
+    let template = parse_template_fasta(&read_fasta(&args.template)?)
+        .map_err(|e| format!("{}: {}", args.template.display(), e))?;
+    let references = match &args.references {
+        Some(path) => read_references(path)?,
+        None => ReferenceData::new(),
+    };
+    // Each exclusivity file is scored as its own group, named by the file
+    let mut exclusivity = Vec::new();
+    for path in &args.exclusivity {
+        let data = read_references(path)?;
+        if !data.is_empty() {
+            let name = path.file_stem().unwrap_or(path.as_os_str());
+            exclusivity.push(ExclusivityGroup {
+                name: name.to_string_lossy().to_string(),
+                data,
+            });
+        }
+    }
+
+    let mut params = args.params;
+    params.resolve_auto_resolution(template.sequence.len());
+    eprintln!(
+        "Template {} bp, {} references, {} exclusivity sequences; lengths {}-{}, resolution {}",
+        template.sequence.len(),
+        references.len(),
+        exclusivity.iter().map(|g| g.data.len()).sum::<usize>(),
+        params.min_oligo_length,
+        params.max_oligo_length,
+        params.resolution
+    );
+
+    let started = Instant::now();
+    let (progress_tx, progress_rx) = channel();
+    let worker = thread::spawn(move || {
+        run_screening(
+            &template,
+            &references,
+            &params,
+            &exclusivity,
+            Some(progress_tx),
+        )
+    });
+    // Ends when the worker drops its sender
+    for update in progress_rx {
+        eprintln!("{}", update.message);
+    }
+    let results = worker
+        .join()
+        .map_err(|_| "screening failed unexpectedly".to_string())?;
+    eprintln!(
+        "Screening finished in {:.1} s",
+        started.elapsed().as_secs_f64()
+    );
+
+    if is_binary_results_path(&args.output) {
+        write_results_binary(&results, file, |_| {})
+    } else {
+        write_results_json(&results, file, |_| {})
+    }
+    .map_err(|e| format!("failed to write {}: {}", args.output.display(), e))?;
+    eprintln!("Results written to {}", args.output.display());
+    Ok(())
+}
@@ -2,6 +2,46 @@
 //!
 //! A Rust application for screening DNA sequences using pairwise alignment
 //! to find suitable primer sites with low variability.
+//!
+//! The screening engine is usable without the GUI. Build with
+//! `default-features = false` to leave out egui, eframe and rfd:
+//!
+//! ```toml
+//! oligoscreen_differential = { path = "...", default-features = false }
+//! ```
+//!
+//! The entry points are [`parse_template_fasta`] and [`parse_reference_fasta`]
+//! for the inputs, [`AnalysisParams`] for the run settings, and
+//! [`run_screening`], which returns [`ScreeningResults`] holding every
+//! window's variants and metrics per oligo length. [`results_to_json`] and
+//! [`results_from_json`] read and write the results files the GUI uses.
+//!
+//! ```
+//! use oligoscreen_differential::{
+//!     AnalysisParams, parse_reference_fasta, parse_template_fasta, run_screening,
+//! };
+//!
+//! let template = parse_template_fasta(">target\nTATGGTACGTCATGTTCTAG\n").unwrap();
+//! let references = parse_reference_fasta(
+//!     ">r1\nTATGGTACGTCATGTTCTAG\n>r2\nTATGGTCCGTCATGTTCTAG\n",
+//! )
+//! .unwrap();
+//! let params = AnalysisParams {
+//!     min_oligo_length: 10,
+//!     max_oligo_length: 10,
+//!     resolution: 5,
+//!     ..Default::default()
+//! };
+//!
+//! // No exclusivity panel, no progress channel
+//! let results = run_screening(&template, &references, &params, &[], None);
+//! for window in &results.results_by_length[&10].positions {
+//!     println!("{}: {} variants", window.position + 1, window.variants_needed);
+//! }
+//! ```
+//!
+//! Progress is reported through an optional `Sender<ProgressUpdate>`; see
+//! `src/bin/oligoscreen-cli.rs` for a complete command-line front end.
 
 pub mod analysis;
 
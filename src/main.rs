@@ -8,7 +8,7 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-mod analysis;
+use oligoscreen_differential::analysis;
 mod app;
 
 use app::OligoscreenApp;